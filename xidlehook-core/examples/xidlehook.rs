@@ -81,7 +81,7 @@ fn main() -> xidlehook_core::Result<()> {
         timers.push(CmdTimer {
             time: Duration::from_secs(duration),
             activation: Some(command(iter.next().unwrap())),
-            abortion: iter.next().filter(|s| !s.is_empty()).map(|s| command(&s)),
+            abortion: iter.next().filter(|s| !s.is_empty()).map(|s| command(s)),
             ..CmdTimer::default()
         });
     }
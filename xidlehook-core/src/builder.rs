@@ -0,0 +1,122 @@
+//! A fluent way to wire timers, modules and an idle source together,
+//! checking them before anything runs.
+//!
+//! ```rust,no_run
+//! use std::{process::Command, time::Duration};
+//! use xidlehook_core::{builder::XidlehookBuilder, modules::Xcb};
+//!
+//! let runner = XidlehookBuilder::new()
+//!     .add_timer(Duration::from_secs(300), Command::new("i3lock"))
+//!     .idle_source(Xcb::new()?)
+//!     .build()?;
+//! runner.main_sync(|| false)?;
+//! # Ok::<(), xidlehook_core::Error>(())
+//! ```
+
+use crate::{timers::CmdTimer, Action, IdleSource, Module, Result, Xidlehook};
+
+use std::{fmt, process::Command, time::Duration};
+
+/// See the module-level documentation
+#[derive(Default)]
+pub struct XidlehookBuilder {
+    timers: Vec<CmdTimer>,
+    modules: Vec<Box<dyn Module>>,
+    source: Option<Box<dyn IdleSource>>,
+    detect_sleep: bool,
+}
+impl XidlehookBuilder {
+    /// Returns a builder without any timers, modules or idle source
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Run this command after being idle for this long since the
+    /// previous timer, or since the user was last active for the
+    /// first one
+    pub fn add_timer(self, time: Duration, command: Command) -> Self {
+        self.add_cmd_timer(CmdTimer {
+            time,
+            activation: Some(command),
+            ..CmdTimer::default()
+        })
+    }
+    /// Add a timer with more options than `add_timer`, such as a
+    /// canceller
+    pub fn add_cmd_timer(mut self, timer: CmdTimer) -> Self {
+        self.timers.push(timer);
+        self
+    }
+    /// Add a module. Modules are asked in the order they're added in.
+    pub fn add_module<M: Module + 'static>(mut self, module: M) -> Self {
+        self.modules.push(Box::new(module));
+        self
+    }
+    /// Measure the idle time with this source
+    pub fn idle_source<S: IdleSource + 'static>(mut self, source: S) -> Self {
+        self.source = Some(Box::new(source));
+        self
+    }
+    /// See `Xidlehook::set_detect_sleep`
+    pub fn detect_sleep(mut self, value: bool) -> Self {
+        self.detect_sleep = value;
+        self
+    }
+    /// Check the timers and build the instance. This fails if there
+    /// are no timers, a timer has a time of zero, or there's no idle
+    /// source.
+    pub fn build(self) -> Result<Runner> {
+        if self.timers.is_empty() {
+            return Err("xidlehook needs at least one timer".into());
+        }
+        if let Some(i) = self
+            .timers
+            .iter()
+            .position(|timer| timer.time == Duration::ZERO)
+        {
+            return Err(format!("timer {} has a time of zero", i).into());
+        }
+        let source = self.source.ok_or("xidlehook needs an idle source")?;
+        Ok(Runner {
+            xidlehook: Xidlehook::new(self.timers)
+                .with_module(self.modules)
+                .with_detect_sleep(self.detect_sleep),
+            source,
+        })
+    }
+}
+impl fmt::Debug for XidlehookBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "XidlehookBuilder({} timers, {} modules)",
+            self.timers.len(),
+            self.modules.len()
+        )
+    }
+}
+
+/// An xidlehook instance along with the idle source it runs from, as
+/// returned by `XidlehookBuilder::build`
+pub struct Runner {
+    /// The instance, which can still be configured further
+    pub xidlehook: Xidlehook<CmdTimer, Vec<Box<dyn Module>>>,
+    /// The idle source
+    pub source: Box<dyn IdleSource>,
+}
+impl Runner {
+    /// Poll the timers once with the current idle time, see
+    /// `Xidlehook::poll`
+    pub fn poll(&mut self) -> Result<Action> {
+        let idle = self.source.get_idle()?;
+        self.xidlehook.poll(idle)
+    }
+    /// Run until `callback` returns true, see `Xidlehook::main_sync`
+    pub fn main_sync<F: FnMut() -> bool>(self, callback: F) -> Result<()> {
+        self.xidlehook.main_sync(&*self.source, callback)
+    }
+}
+impl fmt::Debug for Runner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Runner({} timers)", self.xidlehook.timers().len())
+    }
+}
@@ -0,0 +1,560 @@
+//! A structured configuration file, as an alternative to assembling
+//! timers and modules by hand. Both TOML and JSON are supported, with
+//! the same schema:
+//!
+//! ```toml
+//! # Reset the idle timer once a suspend was detected
+//! detect_sleep = true
+//!
+//! # Timers run in the order they're listed. Each `time` is the
+//! # amount of seconds of inactivity after the previous timer, or a
+//! # duration like "1h30m".
+//! [[timer]]
+//! name = "dim"
+//! time = 60
+//! command = "xrandr --output eDP-1 --brightness .1"
+//! canceller = "xrandr --output eDP-1 --brightness 1"
+//! tags = ["screen"]
+//!
+//! [[timer]]
+//! name = "lock"
+//! time = "5m"
+//! command = "i3lock"
+//! # Run i3lock again if it crashes while the user is away
+//! relock_on_crash = true
+//!
+//! [[timer]]
+//! name = "backup"
+//! time = "1h"
+//! # A list is run as it is, without a shell
+//! command = ["restic", "backup", "."]
+//! working_dir = "/home/user"
+//! env = { RESTIC_REPOSITORY = "/mnt/backup" }
+//!
+//! [modules]
+//! # Stop after the timer with this name, like StopAt::index. Use
+//! # `once = true` to stop after the last timer.
+//! stop_at = "lock"
+//! not_when_audio = true
+//!
+//! [modules.not_when_fullscreen]
+//! exceptions_wm_class1 = ["mpv"]
+//! # `*` matches any text and `?` any single character, like in the shell
+//! exceptions_wm_class2 = ["Gimp*"]
+//! # See modules::xcb::Rule
+//! exceptions = [
+//!     { and = [{ class = "firefox" }, { not = { name_contains = "Private Browsing" } }] },
+//!     { class_matches = "^steam_app_" },
+//! ]
+//! ```
+//!
+//! Commands written as a string are passed through `/bin/sh -c`, and
+//! those written as a list are run without a shell, with only `$VAR`
+//! and `${VAR}` expanded in their arguments, see `TimerCommand`.
+//! Unknown keys are rejected, so that typos don't go unnoticed.
+
+use crate::{
+    modules::{
+        xcb::{FullscreenOptions, OnMalformed, Rule, ScanMode, DEFAULT_MAX_DEPTH},
+        StopAt, Xcb,
+    },
+    timers::{CmdTimer, SpawnOptions, TimerCommand},
+    Module, Progress, Result, Xidlehook,
+};
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+    str::FromStr,
+    time::Duration,
+};
+
+use nix::sys::signal::Signal;
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+/// The top-level configuration
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Whether or not to reset the idle timer once a suspend was
+    /// detected
+    #[serde(default)]
+    pub detect_sleep: bool,
+    /// How many seconds timers are held off for after the user returns,
+    /// see `Xidlehook::set_resume_grace`. Like a timer's `time`, this
+    /// can be written like `"30s"`.
+    #[serde(default, deserialize_with = "seconds")]
+    pub resume_grace: u64,
+    /// The chain of timers, in order
+    #[serde(default, rename = "timer")]
+    pub timers: Vec<TimerConfig>,
+    /// Which modules to enable
+    #[serde(default)]
+    pub modules: ModulesConfig,
+}
+
+/// The configuration of a single timer
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TimerConfig {
+    /// A unique name other parts of the config can refer to this
+    /// timer by
+    #[serde(default)]
+    pub name: Option<String>,
+    /// The seconds of inactivity, after the previous timer, which
+    /// should trigger this timer. In a config file, this can also be
+    /// written like `"5m"`, see `parse_seconds`.
+    #[serde(deserialize_with = "seconds")]
+    pub time: u64,
+    /// The command to run upon activation
+    pub command: TimerCommand,
+    /// The command to run when the user becomes active after this
+    /// timer has gone off, but before the next timer (if any)
+    #[serde(default)]
+    pub canceller: Option<TimerCommand>,
+    /// The command to run when the next timer is activated
+    #[serde(default)]
+    pub deactivation: Option<TimerCommand>,
+    /// Arbitrary labels for grouping timers
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Whether or not this timer starts out disabled
+    #[serde(default)]
+    pub disabled: bool,
+    /// How many milliseconds the idle time may jitter around the
+    /// threshold without the timer firing or being cancelled again,
+    /// see `Timer::dead_zone`
+    #[serde(default)]
+    pub dead_zone_ms: u64,
+    /// Whether to run the command again if it exits unsuccessfully
+    /// before the user is active, see `CmdTimer::relock_on_crash`
+    #[serde(default)]
+    pub relock_on_crash: bool,
+    /// Run the command again every this many seconds while the user
+    /// stays idle after it, see `Timer::recurring`. Like `time`, this
+    /// can be written like `"30m"`.
+    #[serde(default, deserialize_with = "optional_seconds")]
+    pub recurring: Option<u64>,
+    /// Whether to only run the command once until the user is active
+    /// again, even if the chain starts over without them, see
+    /// `Timer::once`. This can't be used along with `recurring`.
+    #[serde(default)]
+    pub once: bool,
+    /// The command to run `warning_lead` seconds before this timer
+    /// goes off, see `Timer::warning_lead`. It's killed if the user
+    /// becomes active before then.
+    #[serde(default)]
+    pub warning: Option<TimerCommand>,
+    /// How many seconds before this timer the warning is run. It must
+    /// be shorter than the timer's time. Like `time`, this can be
+    /// written like `"30s"`.
+    #[serde(default, deserialize_with = "seconds")]
+    pub warning_lead: u64,
+    /// Whether to signal the command if it's still running when the
+    /// user is active again, see `CmdTimer::kill_on_resume`
+    #[serde(default)]
+    pub kill_on_resume: bool,
+    /// The signal to send with `kill_on_resume`, like `SIGINT`. This
+    /// defaults to `SIGTERM`.
+    #[serde(default)]
+    pub kill_signal: Option<String>,
+    /// Whether this timer's commands run in a process group of their
+    /// own, so they outlive xidlehook, see `CmdTimer::detached`
+    #[serde(default)]
+    pub detached: bool,
+    /// The directory this timer's commands run in, if not xidlehook's
+    #[serde(default)]
+    pub working_dir: Option<PathBuf>,
+    /// Variables to set for this timer's commands
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Whether this timer's commands only see the variables in `env`,
+    /// see `SpawnOptions::clear_env`
+    #[serde(default)]
+    pub clear_env: bool,
+}
+impl TimerConfig {
+    /// How this timer's commands are run
+    pub fn spawn_options(&self) -> SpawnOptions {
+        SpawnOptions {
+            current_dir: self.working_dir.clone(),
+            env: self
+                .env
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            clear_env: self.clear_env,
+        }
+    }
+}
+
+/// Which modules to enable, and their settings
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModulesConfig {
+    /// Stop after the whole chain has been run once
+    #[serde(default)]
+    pub once: bool,
+    /// Stop after the timer with this name
+    #[serde(default)]
+    pub stop_at: Option<String>,
+    /// Don't run timers while a window is fullscreen
+    #[serde(default)]
+    pub not_when_fullscreen: Option<FullscreenConfig>,
+    /// Don't run timers while any audio is playing
+    #[serde(default)]
+    pub not_when_audio: bool,
+}
+
+/// The settings of the `NotWhenFullscreen` module. See
+/// `FullscreenOptions`.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct FullscreenConfig {
+    /// See `FullscreenOptions::exceptions_wm_class1`
+    #[serde(default)]
+    pub exceptions_wm_class1: Vec<String>,
+    /// See `FullscreenOptions::exceptions_wm_class2`
+    #[serde(default)]
+    pub exceptions_wm_class2: Vec<String>,
+    /// See `FullscreenOptions::exceptions_wm_name`
+    #[serde(default)]
+    pub exceptions_wm_name: Vec<String>,
+    /// See `FullscreenOptions::exceptions_wm_role`
+    #[serde(default)]
+    pub exceptions_wm_role: Vec<String>,
+    /// See `FullscreenOptions::exceptions_pid`
+    #[serde(default)]
+    pub exceptions_pid: Vec<u32>,
+    /// See `FullscreenOptions::exceptions_process`
+    #[serde(default)]
+    pub exceptions_process: Vec<String>,
+    /// See `FullscreenOptions::case_insensitive`
+    #[serde(default)]
+    pub case_insensitive: bool,
+    /// See `FullscreenOptions::force_fullscreen_classes`
+    #[serde(default)]
+    pub force_fullscreen_classes: Vec<String>,
+    /// See `FullscreenOptions::exceptions`
+    #[serde(default)]
+    pub exceptions: Vec<Rule>,
+    /// See `FullscreenOptions::ignore_sticky_windows`
+    #[serde(default)]
+    pub ignore_sticky_windows: bool,
+    /// See `FullscreenOptions::ignore_desktop_match`
+    #[serde(default)]
+    pub ignore_desktop_match: bool,
+    /// See `FullscreenOptions::strict_desktop_match`
+    #[serde(default)]
+    pub strict_desktop_match: bool,
+    /// See `FullscreenOptions::count_iconic`
+    #[serde(default)]
+    pub count_iconic: bool,
+    /// See `FullscreenOptions::scan_mode`, one of `active_only`,
+    /// `full_tree` or `top_level`
+    #[serde(default)]
+    pub scan_mode: ScanMode,
+    /// See `FullscreenOptions::on_malformed`
+    #[serde(default)]
+    pub on_malformed: OnMalformed,
+    /// See `FullscreenOptions::max_windows`
+    #[serde(default)]
+    pub max_windows: Option<usize>,
+    /// See `FullscreenOptions::max_depth`. This defaults to
+    /// `DEFAULT_MAX_DEPTH`.
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+    /// See `FullscreenOptions::monitors`
+    #[serde(default)]
+    pub monitors: Option<Vec<String>>,
+    /// See `FullscreenOptions::override_redirect`
+    #[serde(default)]
+    pub override_redirect: bool,
+    /// Start the timers over while a window is fullscreen, rather than
+    /// giving up on them until the user is active, see
+    /// `NotWhenFullscreen::with_progress`
+    #[serde(default)]
+    pub reset: bool,
+}
+impl FullscreenConfig {
+    /// Read and parse a file with only these settings, like the
+    /// `[modules.not_when_fullscreen]` table of a config file. Files
+    /// ending in `.json` are parsed as JSON, everything else as TOML.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let input = fs::read_to_string(path)?;
+        if path.extension().is_some_and(|ext| ext == "json") {
+            Ok(serde_json::from_str(&input)?)
+        } else {
+            Ok(toml::from_str(&input)?)
+        }
+    }
+    /// Convert this to the options used by `NotWhenFullscreen`
+    pub fn options(&self) -> FullscreenOptions {
+        FullscreenOptions {
+            exceptions_wm_class1: self.exceptions_wm_class1.clone(),
+            exceptions_wm_class2: self.exceptions_wm_class2.clone(),
+            exceptions_wm_name: self.exceptions_wm_name.clone(),
+            exceptions_wm_role: self.exceptions_wm_role.clone(),
+            exceptions_pid: self.exceptions_pid.clone(),
+            exceptions_process: self.exceptions_process.clone(),
+            case_insensitive: self.case_insensitive,
+            force_fullscreen_classes: self.force_fullscreen_classes.clone(),
+            exceptions: self.exceptions.clone(),
+            ignore_sticky_windows: self.ignore_sticky_windows,
+            ignore_desktop_match: self.ignore_desktop_match,
+            strict_desktop_match: self.strict_desktop_match,
+            count_iconic: self.count_iconic,
+            scan_mode: self.scan_mode,
+            on_malformed: self.on_malformed,
+            max_windows: self.max_windows,
+            max_depth: self.max_depth.or(Some(DEFAULT_MAX_DEPTH)),
+            monitors: self.monitors.clone(),
+            override_redirect: self.override_redirect,
+            ..FullscreenOptions::default()
+        }
+    }
+}
+
+/// Parse a number of seconds written like `90`, `90s`, `5m` or
+/// `1h30m`. The units are `s`, `m`, `h` and `d`.
+pub fn parse_seconds(input: &str) -> Result<u64> {
+    let invalid = || format!("invalid duration {:?}, expected something like 5m", input);
+    let input = input.trim();
+    if let Ok(seconds) = input.parse() {
+        return Ok(seconds);
+    }
+    if input.is_empty() {
+        return Err(invalid().into());
+    }
+    let mut total: u64 = 0;
+    let mut rest = input;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(invalid)?;
+        let (number, unit) = rest.split_at(digits);
+        let number: u64 = number.parse().map_err(|_| invalid())?;
+        let mut chars = unit.chars();
+        let scale = match chars.next() {
+            Some('s') => 1,
+            Some('m') => 60,
+            Some('h') => 60 * 60,
+            Some('d') => 24 * 60 * 60,
+            _ => return Err(invalid().into()),
+        };
+        total = number
+            .checked_mul(scale)
+            .and_then(|seconds| total.checked_add(seconds))
+            .ok_or_else(|| format!("duration {:?} is too long", input))?;
+        rest = chars.as_str();
+    }
+    Ok(total)
+}
+
+/// Seconds as written in a config file, see `parse_seconds`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Seconds {
+    Number(u64),
+    Text(String),
+}
+impl Seconds {
+    fn parse<E: de::Error>(self) -> Result<u64, E> {
+        match self {
+            Self::Number(seconds) => Ok(seconds),
+            Self::Text(text) => parse_seconds(&text).map_err(E::custom),
+        }
+    }
+}
+fn seconds<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    Seconds::deserialize(deserializer)?.parse()
+}
+fn optional_seconds<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+    Option::<Seconds>::deserialize(deserializer)?
+        .map(Seconds::parse)
+        .transpose()
+}
+
+impl Config {
+    /// Parse and validate a TOML config
+    pub fn from_toml(input: &str) -> Result<Self> {
+        let config: Self = toml::from_str(input)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Parse and validate a JSON config
+    pub fn from_json(input: &str) -> Result<Self> {
+        let config: Self = serde_json::from_str(input)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Read, parse and validate a config file. Files ending in
+    /// `.json` are parsed as JSON, everything else as TOML.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let input = fs::read_to_string(path)?;
+        if path.extension().is_some_and(|ext| ext == "json") {
+            Self::from_json(&input)
+        } else {
+            Self::from_toml(&input)
+        }
+    }
+
+    /// Returns the index of the timer with the specified name
+    pub fn timer_index(&self, name: &str) -> Option<usize> {
+        self.timers
+            .iter()
+            .position(|timer| timer.name.as_deref() == Some(name))
+    }
+
+    /// Check that the config makes sense: there must be at least one
+    /// timer, no timer may be instant or have a command that's an empty
+    /// list, names must be unique, and every reference to a timer name
+    /// must exist.
+    pub fn validate(&self) -> Result<()> {
+        if self.timers.is_empty() {
+            return Err("config has no timers".into());
+        }
+
+        let mut names = HashSet::new();
+        for (i, timer) in self.timers.iter().enumerate() {
+            if timer.time == 0 {
+                return Err(format!("timer {} has a time of zero", i).into());
+            }
+            if let Some(ref name) = timer.name {
+                if !names.insert(name) {
+                    return Err(format!("timer name {:?} is used more than once", name).into());
+                }
+            }
+            let commands = [
+                Some(&timer.command),
+                timer.canceller.as_ref(),
+                timer.deactivation.as_ref(),
+                timer.warning.as_ref(),
+            ];
+            if commands
+                .iter()
+                .flatten()
+                .any(|cmd| *cmd == &TimerCommand::Argv(Vec::new()))
+            {
+                return Err(format!("timer {} has an empty command list", i).into());
+            }
+            if timer.tags.iter().any(String::is_empty) {
+                return Err(format!("timer {} has an empty tag", i).into());
+            }
+            if timer.warning.is_some() && !(1..timer.time).contains(&timer.warning_lead) {
+                return Err(format!(
+                    "timer {} needs a warning_lead between 1 and {} seconds",
+                    i,
+                    timer.time - 1
+                )
+                .into());
+            }
+            if timer.once && timer.recurring.is_some() {
+                return Err(format!("timer {} can't be both once and recurring", i).into());
+            }
+            if timer.recurring == Some(0) {
+                return Err(format!("timer {} can't recur every 0 seconds", i).into());
+            }
+            if let Some(ref name) = timer.kill_signal {
+                Signal::from_str(name)
+                    .map_err(|_| format!("timer {} has an unknown kill_signal {:?}", i, name))?;
+            }
+        }
+
+        if let Some(ref name) = self.modules.stop_at {
+            if self.modules.once {
+                return Err("modules.once and modules.stop_at are mutually exclusive".into());
+            }
+            if self.timer_index(name).is_none() {
+                return Err(format!("modules.stop_at refers to unknown timer {:?}", name).into());
+            }
+        }
+
+        if self.modules.not_when_audio && cfg!(not(feature = "pulse")) {
+            return Err("modules.not_when_audio requires the pulse feature".into());
+        }
+
+        Ok(())
+    }
+
+    /// Build the timers described by this config
+    pub fn timers(&self) -> Vec<CmdTimer> {
+        self.timers
+            .iter()
+            .map(|timer| {
+                let options = timer.spawn_options();
+                let command = |cmd: &TimerCommand| cmd.to_command(&options);
+                CmdTimer {
+                    time: Duration::from_secs(timer.time),
+                    activation: Some(command(&timer.command)),
+                    abortion: timer.canceller.as_ref().map(command),
+                    deactivation: timer.deactivation.as_ref().map(command),
+                    disabled: timer.disabled,
+                    dead_zone: Duration::from_millis(timer.dead_zone_ms),
+                    relock_on_crash: timer.relock_on_crash,
+                    recurring: timer.recurring.map(Duration::from_secs),
+                    once: timer.once,
+                    warning: timer.warning.as_ref().map(command),
+                    warning_lead: Duration::from_secs(timer.warning_lead),
+                    kill_on_resume: timer.kill_on_resume,
+                    kill_signal: timer
+                        .kill_signal
+                        .as_deref()
+                        .and_then(|name| Signal::from_str(name).ok()),
+                    detached: timer.detached,
+                    expand_args: Some(options.clone()),
+                    ..CmdTimer::default()
+                }
+            })
+            .collect()
+    }
+
+    /// Build the modules described by this config. An X connection is
+    /// only required when `not_when_fullscreen` is used.
+    pub fn modules(&self, xcb: Option<&Rc<Xcb>>) -> Result<Vec<Box<dyn Module>>> {
+        let mut modules: Vec<Box<dyn Module>> = Vec::new();
+
+        if self.modules.once {
+            modules.push(Box::new(StopAt::completion()));
+        }
+        if let Some(ref name) = self.modules.stop_at {
+            let index = self
+                .timer_index(name)
+                .ok_or_else(|| format!("modules.stop_at refers to unknown timer {:?}", name))?;
+            modules.push(Box::new(StopAt::index(index)));
+        }
+        if let Some(ref fullscreen) = self.modules.not_when_fullscreen {
+            let xcb = xcb.ok_or("modules.not_when_fullscreen requires an X connection")?;
+            let mut module = Rc::clone(xcb).not_when_fullscreen_with(fullscreen.options());
+            if fullscreen.reset {
+                module = module.with_progress(Progress::Reset);
+            }
+            modules.push(Box::new(module));
+        }
+        #[cfg(feature = "pulse")]
+        {
+            if self.modules.not_when_audio {
+                modules.push(Box::new(crate::modules::NotWhenAudio::new()?));
+            }
+        }
+
+        Ok(modules)
+    }
+
+    /// Build a ready-to-run xidlehook instance from this config
+    pub fn build(
+        &self,
+        xcb: Option<&Rc<Xcb>>,
+    ) -> Result<Xidlehook<CmdTimer, Vec<Box<dyn Module>>>> {
+        Ok(Xidlehook::new(self.timers())
+            .with_module(self.modules(xcb)?)
+            .with_detect_sleep(self.detect_sleep)
+            .with_resume_grace(Duration::from_secs(self.resume_grace)))
+    }
+}
@@ -0,0 +1,153 @@
+//! Timers that go off at a time of day rather than after some idle
+//! time, such as to run a cleanup at 02:00 if the user is idle then.
+//! They're checked on every poll, apart from the chain of timers, and
+//! `Xidlehook::poll` wakes up in time for them. Modules aren't asked
+//! about them.
+
+use crate::{
+    modules::time_window::{minute_of_day, parse_time},
+    Result, Timer,
+};
+
+use std::{convert::TryFrom, fmt, mem, ptr, time::Duration};
+
+use nix::libc;
+
+/// How many seconds there are in a day
+const SECONDS_PER_DAY: u32 = 24 * 60 * 60;
+/// How long after its time of day a daily timer may still go off, for
+/// polls that come a little late
+const WINDOW: u32 = 60;
+
+/// A point in local time, precise to the second
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LocalTime {
+    /// The day, counted from 1970-01-01 in local time
+    pub day: i64,
+    /// The second of the day, counted from midnight
+    pub second: u32,
+}
+impl LocalTime {
+    /// Returns the time at these hours and minutes of this day
+    pub fn at(day: i64, hours: u8, minutes: u8) -> Self {
+        Self {
+            day,
+            second: u32::from(hours) * 3600 + u32::from(minutes) * 60,
+        }
+    }
+    /// Returns the current time in the system's local time zone
+    pub fn now() -> Result<Self> {
+        // The time is only read, and localtime_r doesn't keep the pointers
+        let (now, tm) = unsafe {
+            let now = libc::time(ptr::null_mut());
+            let mut tm: libc::tm = mem::zeroed();
+            if libc::localtime_r(&now, &mut tm).is_null() {
+                return Err("can't tell the local time".into());
+            }
+            (now, tm)
+        };
+        // time_t and long are narrower on some platforms
+        #[allow(clippy::useless_conversion)]
+        let local = i64::from(now) + i64::from(tm.tm_gmtoff);
+        Ok(Self {
+            day: local.div_euclid(i64::from(SECONDS_PER_DAY)),
+            second: u32::try_from(tm.tm_hour * 3600 + tm.tm_min * 60 + tm.tm_sec)?,
+        })
+    }
+}
+
+/// Something that tells the local time, which is `LocalTime::now` but
+/// for tests
+pub type ClockFn = dyn Fn() -> Result<LocalTime>;
+
+/// A timer that goes off once a day, at a time of day, if the user has
+/// been idle for at least `min_idle` then. If the user isn't idle at
+/// the first poll in the minute after that time, or there isn't one,
+/// it waits for the next day. Only `Timer::activate` of the inner
+/// timer is called, so its threshold doesn't matter.
+#[derive(Debug)]
+pub struct DailyTimer<T: Timer> {
+    timer: T,
+    /// The second of the day to go off at
+    at: u32,
+    min_idle: Duration,
+    /// The last day it was due on, whether it went off or not
+    checked_on: Option<i64>,
+}
+impl<T: Timer> DailyTimer<T> {
+    /// How long the user has to be idle for by default
+    pub const DEFAULT_MIN_IDLE: Duration = Duration::from_secs(60);
+
+    /// Returns a timer activating `timer` at these hours and minutes
+    /// every day. This fails if they aren't in the day.
+    pub fn new(at: (u8, u8), timer: T) -> Result<Self> {
+        Ok(Self {
+            timer,
+            at: u32::from(minute_of_day(at)?) * 60,
+            min_idle: Self::DEFAULT_MIN_IDLE,
+            checked_on: None,
+        })
+    }
+    /// Like `new`, with the time of day given like `02:00`
+    pub fn parse(at: &str, timer: T) -> Result<Self> {
+        Self::new(parse_time(at)?, timer)
+    }
+    /// Set how long the user has to have been idle for at the time of
+    /// day, which is `DEFAULT_MIN_IDLE` by default
+    pub fn with_min_idle(mut self, min_idle: Duration) -> Self {
+        self.min_idle = min_idle;
+        self
+    }
+    /// Returns the time of day as hours and minutes
+    pub fn at(&self) -> (u8, u8) {
+        let minute = self.at.div_euclid(60);
+        // Both fit, the time is in the day
+        (minute.div_euclid(60) as u8, minute.rem_euclid(60) as u8)
+    }
+    /// Returns how long the user has to have been idle for
+    pub fn min_idle(&self) -> Duration {
+        self.min_idle
+    }
+    /// Returns the inner timer
+    pub fn timer(&self) -> &T {
+        &self.timer
+    }
+    /// Returns the inner timer, mutably
+    pub fn timer_mut(&mut self) -> &mut T {
+        &mut self.timer
+    }
+
+    /// Activate the timer if it's due at this time and the user has
+    /// been idle for long enough, returning whether it went off. It's
+    /// only due once per day, so this only returns true once a day.
+    pub fn check(&mut self, now: LocalTime, idle: Duration) -> Result<bool> {
+        let due = now.second >= self.at && now.second - self.at < WINDOW;
+        if !due || self.checked_on == Some(now.day) {
+            return Ok(false);
+        }
+        self.checked_on = Some(now.day);
+        if idle < self.min_idle || self.timer.disabled() {
+            return Ok(false);
+        }
+        self.timer.activate()?;
+        Ok(true)
+    }
+
+    /// Returns how long from this time it's due next
+    pub fn until_due(&self, now: LocalTime) -> Duration {
+        let seconds = if now.second < self.at {
+            self.at - now.second
+        } else if now.second - self.at < WINDOW && self.checked_on != Some(now.day) {
+            0
+        } else {
+            SECONDS_PER_DAY - now.second + self.at
+        };
+        Duration::from_secs(seconds.into())
+    }
+}
+impl<T: Timer> fmt::Display for DailyTimer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (hours, minutes) = self.at();
+        write!(f, "{:02}:{:02}", hours, minutes)
+    }
+}
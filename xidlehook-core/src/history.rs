@@ -0,0 +1,102 @@
+//! A record of every timer activation and abortion, written to a file
+//! in the JSON Lines format for later analysis with tools like `jq`:
+//!
+//! ```json
+//! {"timestamp":1602681600,"event":"activated","timer":0,"idle":60.000,"inhibited":false}
+//! {"timestamp":1602681612,"event":"aborted","timer":0,"idle":72.124,"inhibited":false}
+//! ```
+//!
+//! `inhibited` is true for activations that a module prevented.
+
+use crate::Result;
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// What happened to a timer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// The timer went off, or would have if a module hadn't inhibited
+    /// it
+    Activated,
+    /// The user became active after the timer went off
+    Aborted,
+}
+impl Event {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Activated => "activated",
+            Self::Aborted => "aborted",
+        }
+    }
+}
+
+/// A history file. Once it would grow past the maximum size, it's
+/// renamed with a `.1` suffix (replacing any previous one) and a new
+/// file is started.
+#[derive(Debug)]
+pub struct History {
+    path: PathBuf,
+    max_size: Option<u64>,
+}
+impl History {
+    /// Append records to the file at this path
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            max_size: None,
+        }
+    }
+
+    /// Rotate the file once it would exceed this many bytes
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// The path of the file before it's rotated
+    pub fn rotated_path(&self) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".1");
+        path.into()
+    }
+
+    /// Append a record to the file
+    pub fn record(
+        &mut self,
+        event: Event,
+        timer: usize,
+        idle: Duration,
+        inhibited: bool,
+    ) -> Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let line = format!(
+            "{{\"timestamp\":{},\"event\":\"{}\",\"timer\":{},\"idle\":{:.3},\"inhibited\":{}}}\n",
+            timestamp,
+            event.name(),
+            timer,
+            idle.as_secs_f64(),
+            inhibited,
+        );
+
+        if let Some(max_size) = self.max_size {
+            let size = fs::metadata(&self.path).map_or(0, |meta| meta.len());
+            if size > 0 && size + line.len() as u64 > max_size {
+                fs::rename(&self.path, self.rotated_path())?;
+            }
+        }
+
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(line.as_bytes())?;
+        Ok(())
+    }
+}
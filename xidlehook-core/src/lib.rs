@@ -12,7 +12,7 @@
     clippy::decimal_literal_representation,
     clippy::float_cmp_const,
     clippy::get_unwrap,
-    clippy::integer_arithmetic,
+    clippy::arithmetic_side_effects,
     clippy::integer_division,
     clippy::print_stdout,
 )]
@@ -22,7 +22,7 @@
 
     // The integer arithmetic here is mostly regarding indexes into Vecs, indexes where memory
     // allocation will fail far, far earlier than the arithmetic will fail.
-    clippy::integer_arithmetic,
+    clippy::arithmetic_side_effects,
 )]
 
 //! Instead of implementing your extension as something that
@@ -35,11 +35,11 @@
 use std::{
     cmp,
     convert::TryInto,
-    fmt, ptr,
-    time::{Duration, Instant},
+    fmt, iter, mem, ptr,
+    time::{Duration, Instant, SystemTime},
 };
 
-use log::{info, trace, warn};
+use log::{debug, info, trace, warn};
 use nix::libc;
 
 /// The default error type for xidlehook. Unfortunately, it's a
@@ -48,12 +48,35 @@ pub type Error = Box<dyn std::error::Error>;
 /// An alias to Result which overrides the default Error type.
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
+pub mod builder;
+#[cfg(feature = "config")]
+pub mod config;
+pub mod daily;
+pub mod history;
+pub mod logging;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 pub mod modules;
+pub mod simulation;
+pub mod sources;
+#[cfg(feature = "state")]
+pub mod state;
+pub mod status;
 pub mod timers;
+pub mod watchdog;
+
+use self::{
+    daily::{ClockFn, DailyTimer, LocalTime},
+    history::{Event, History},
+    modules::ModuleList,
+    simulation::Simulation,
+};
 
 pub use self::{
-    modules::{Module, Progress},
+    modules::{Module, Observer, Progress},
+    sources::IdleSource,
     timers::Timer,
+    watchdog::Watchdog,
 };
 
 /// An identifier for a timer, based on the index in the timer list
@@ -66,6 +89,10 @@ pub struct TimerInfo {
     pub length: usize,
 }
 
+/// How many times the idle source's resolution a timer needs to be
+/// for it to fire reliably
+const RESOLUTION_MARGIN: u32 = 2;
+
 /// Return value of `poll`, which specifies what one should do next: sleep,
 /// wait forever (until client modifies the xidlehook instance),
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -78,6 +105,56 @@ pub enum Action {
     Quit,
 }
 
+/// How soon `poll` asks to be called again while the idle time fell
+/// for fewer polls than `Xidlehook::set_activity_debounce` asks for
+pub const DIP_RECHECK: Duration = Duration::from_millis(500);
+
+/// What happened during one poll, for the `tick` event
+#[derive(Debug, Default)]
+struct TickTrace {
+    /// The timers whose time came, in order
+    eligible: Vec<usize>,
+    /// What the modules said about each of them
+    verdicts: Vec<(usize, Progress)>,
+    /// The timers that went off or recurred
+    fired: Vec<usize>,
+}
+impl TickTrace {
+    /// Log this as the `tick` event, at trace level
+    fn log(&self, absolute_time: Duration, action: Action) {
+        let list = |indices: &[usize]| {
+            indices
+                .iter()
+                .map(usize::to_string)
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        let verdicts = self
+            .verdicts
+            .iter()
+            .map(|(index, verdict)| format!("{}:{}", index, verdict.name()))
+            .collect::<Vec<_>>()
+            .join(",");
+        let action = match action {
+            Action::Sleep(delay) => format!("sleep:{}", delay.as_millis()),
+            Action::Forever => "forever".into(),
+            Action::Quit => "quit".into(),
+        };
+        let idle_ms: u64 = absolute_time.as_millis().try_into().unwrap_or(u64::MAX);
+        logging::log_event(
+            log::Level::Trace,
+            "tick",
+            &[
+                ("idle_ms", idle_ms.into()),
+                ("eligible", list(&self.eligible).as_str().into()),
+                ("verdicts", verdicts.as_str().into()),
+                ("fired", list(&self.fired).as_str().into()),
+                ("action", action.as_str().into()),
+            ],
+        );
+    }
+}
+
 /// The main xidlehook instance that allows you to schedule things
 pub struct Xidlehook<T: Timer, M: Module>
 where
@@ -85,12 +162,52 @@ where
     M: Module,
 {
     module: M,
+    /// The modules of single timers, by the index of the timer, asked
+    /// after `module`
+    timer_modules: Vec<ModuleList>,
 
     /// Whether to reset on sleep
     detect_sleep: bool,
+    /// The watchdog to report each poll to, if any
+    watchdog: Option<Watchdog>,
+    /// If set, abort all fired timers with this gap between each
+    canceller_gap: Option<Duration>,
+    /// The file to record activations and abortions to, if any
+    history: Option<History>,
+    /// Whether to log every time the user becomes active
+    log_resets: bool,
+    /// The shortest time to sleep between polls, unless a timer is due sooner
+    poll_interval_min: Option<Duration>,
+    /// The longest time to sleep between polls
+    poll_interval_max: Option<Duration>,
+    /// When this instance was created, for the uptime
+    started: Instant,
+    /// The wall clock time this instance was created at
+    started_at: SystemTime,
+    /// Told about every decision of the modules
+    observers: Vec<Box<dyn Observer>>,
+    /// How long after the user returns timers can't fire
+    resume_grace: Duration,
+    /// How long after a timer fires no other timer can
+    cooldown: Duration,
+    /// How many polls in a row the idle time has to stay fallen for to
+    /// count as activity
+    activity_debounce: usize,
+    /// The timers going off at a time of day
+    daily: Vec<DailyTimer<T>>,
+    /// Tells the time of day for the daily timers
+    clock: Box<ClockFn>,
 
     timers: Vec<T>,
     next_index: usize,
+    /// The indexes of the timers activated in this chain, in order
+    fired: Vec<usize>,
+    /// The indexes of the timers still to be aborted since the user
+    /// returned, and when, see `set_canceller_gap`
+    cancellers: Vec<(Instant, usize)>,
+    /// The indexes of the timers that went off since the user was last active and may only do so
+    /// once, see `Timer::once`
+    spent: Vec<usize>,
     /// The base idle time: the absolute idle time when the last timer
     /// was called, used to retrieve the relative idle time since it.
     base_idle_time: Duration,
@@ -100,20 +217,68 @@ where
     /// If a chain is aborted during the process, store this here as
     /// to not make any more attempts to continue it.
     aborted: bool,
+    /// The timer that was last cancelled by the idle time falling out
+    /// of its dead zone, and that dead zone, which it needs to exceed
+    /// its threshold by to fire again
+    rearm: Option<(usize, Duration)>,
+    /// The absolute idle time the last timer went off again at, if it
+    /// did since its first activation, see `Timer::recurring`
+    recurred_at: Option<Duration>,
+    /// The timer whose warning time has come (see `Timer::warning_lead`), and whether it was
+    /// warned, which it isn't if the module objected
+    warning: Option<(usize, bool)>,
+    /// The idle time from before it fell, and for how many polls it
+    /// stayed fallen, while that isn't enough to count as activity yet
+    dip: Option<(Duration, usize)>,
+    /// What happened during this poll, if the `tick` event is logged
+    tick: Option<TickTrace>,
+    /// Whether the modules were told xidlehook is exiting
+    shut_down: bool,
+    /// When the user last returned after a timer went off, for the
+    /// resume grace period
+    resumed_at: Option<Instant>,
+    /// When a timer last fired or recurred, for the cooldown
+    fired_at: Option<Instant>,
 }
 impl<T: Timer> Xidlehook<T, ()> {
     /// An empty instance without any modules
     pub fn new(timers: Vec<T>) -> Self {
         Self {
             module: (),
+            timer_modules: Vec::new(),
 
             detect_sleep: false,
+            watchdog: None,
+            canceller_gap: None,
+            history: None,
+            log_resets: false,
+            poll_interval_min: None,
+            poll_interval_max: None,
+            started: Instant::now(),
+            started_at: SystemTime::now(),
+            observers: Vec::new(),
+            resume_grace: Duration::default(),
+            cooldown: Duration::default(),
+            activity_debounce: 1,
+            daily: Vec::new(),
+            clock: Box::new(LocalTime::now),
 
             timers,
             next_index: 0,
+            fired: Vec::new(),
+            cancellers: Vec::new(),
+            spent: Vec::new(),
             base_idle_time: Duration::default(),
             previous_idle_time: Duration::default(),
             aborted: false,
+            rearm: None,
+            recurred_at: None,
+            warning: None,
+            dip: None,
+            tick: None,
+            shut_down: false,
+            resumed_at: None,
+            fired_at: None,
         }
     }
 }
@@ -122,14 +287,40 @@ macro_rules! with_module {
     ($self:expr, $module:expr) => {
         Xidlehook {
             module: $module,
+            timer_modules: $self.timer_modules,
 
             detect_sleep: $self.detect_sleep,
+            watchdog: $self.watchdog,
+            canceller_gap: $self.canceller_gap,
+            history: $self.history,
+            log_resets: $self.log_resets,
+            poll_interval_min: $self.poll_interval_min,
+            poll_interval_max: $self.poll_interval_max,
+            started: $self.started,
+            started_at: $self.started_at,
+            observers: $self.observers,
+            resume_grace: $self.resume_grace,
+            cooldown: $self.cooldown,
+            activity_debounce: $self.activity_debounce,
+            daily: $self.daily,
+            clock: $self.clock,
 
             timers: $self.timers,
             next_index: $self.next_index,
+            fired: $self.fired,
+            cancellers: $self.cancellers,
+            spent: $self.spent,
             base_idle_time: $self.base_idle_time,
             previous_idle_time: $self.previous_idle_time,
             aborted: $self.aborted,
+            rearm: $self.rearm,
+            recurred_at: $self.recurred_at,
+            warning: $self.warning,
+            dip: $self.dip,
+            tick: $self.tick,
+            shut_down: $self.shut_down,
+            resumed_at: $self.resumed_at,
+            fired_at: $self.fired_at,
         }
     };
 }
@@ -171,6 +362,396 @@ where
         self
     }
 
+    /// Set the watchdog which is informed about every poll
+    pub fn set_watchdog(&mut self, watchdog: Option<Watchdog>) {
+        self.watchdog = watchdog;
+    }
+    /// Get the watchdog which is informed about every poll
+    pub fn watchdog_mut(&mut self) -> Option<&mut Watchdog> {
+        self.watchdog.as_mut()
+    }
+    /// Set the watchdog which is informed about every poll. This is the chainable version of
+    /// `set_watchdog`.
+    pub fn with_watchdog(mut self, watchdog: Watchdog) -> Self {
+        self.watchdog = Some(watchdog);
+        self
+    }
+
+    /// Set whether or not to coalesce cancellers. Normally, when the user returns only the most
+    /// recently activated timer is aborted. With a gap set, every timer activated in the chain is
+    /// aborted instead, in reverse order of activation, with the gap in between. Only the first
+    /// canceller runs right away, the others are run by `poll`, which wakes up in time for them.
+    /// They all run right away before the next timer is activated, or before the timers are
+    /// changed through `timers_mut`.
+    pub fn set_canceller_gap(&mut self, gap: Option<Duration>) {
+        self.canceller_gap = gap;
+    }
+    /// Get the gap between coalesced cancellers, if enabled
+    pub fn canceller_gap(&self) -> Option<Duration> {
+        self.canceller_gap
+    }
+    /// Set whether or not to coalesce cancellers. This is the chainable version of
+    /// `set_canceller_gap`.
+    pub fn with_canceller_gap(mut self, gap: Duration) -> Self {
+        self.canceller_gap = Some(gap);
+        self
+    }
+
+    /// Set the history file which every activation and abortion is recorded to
+    pub fn set_history(&mut self, history: Option<History>) {
+        self.history = history;
+    }
+    /// Set the history file which every activation and abortion is recorded to. This is the
+    /// chainable version of `set_history`.
+    pub fn with_history(mut self, history: History) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Set whether or not to log every time the idle time falls, which
+    /// resets the chain, along with the idle time just before. This
+    /// helps telling if tiny movements keep resetting the timers. The
+    /// messages are logged at info level with the target
+    /// `xidlehook_core::resets`.
+    pub fn set_log_resets(&mut self, value: bool) {
+        self.log_resets = value;
+    }
+    /// Get whether or not resets are logged
+    pub fn log_resets(&self) -> bool {
+        self.log_resets
+    }
+    /// Set whether or not resets are logged. This is the chainable
+    /// version of `set_log_resets`.
+    pub fn with_log_resets(mut self, value: bool) -> Self {
+        self.log_resets = value;
+        self
+    }
+
+    /// Set the shortest time `poll` asks to sleep for. This saves
+    /// power when abort urgencies would otherwise wake it up often.
+    /// Timers that are about to activate still cut the sleep short, so
+    /// they aren't delayed by it.
+    pub fn set_poll_interval_min(&mut self, min: Option<Duration>) {
+        self.poll_interval_min = min;
+    }
+    /// Get the shortest time `poll` asks to sleep for, if any
+    pub fn poll_interval_min(&self) -> Option<Duration> {
+        self.poll_interval_min
+    }
+    /// Set the shortest time `poll` asks to sleep for. This is the
+    /// chainable version of `set_poll_interval_min`.
+    pub fn with_poll_interval_min(mut self, min: Duration) -> Self {
+        self.poll_interval_min = Some(min);
+        self
+    }
+    /// Set the longest time `poll` asks to sleep for, so that activity
+    /// is noticed (and cancellers run) sooner. This takes precedence
+    /// over the minimum if it's smaller.
+    pub fn set_poll_interval_max(&mut self, max: Option<Duration>) {
+        self.poll_interval_max = max;
+    }
+    /// Get the longest time `poll` asks to sleep for, if any
+    pub fn poll_interval_max(&self) -> Option<Duration> {
+        self.poll_interval_max
+    }
+    /// Set the longest time `poll` asks to sleep for. This is the
+    /// chainable version of `set_poll_interval_max`.
+    pub fn with_poll_interval_max(mut self, max: Duration) -> Self {
+        self.poll_interval_max = Some(max);
+        self
+    }
+
+    /// Set how long timers are held off for after the user returns from
+    /// a timer going off, such as by moving the mouse to undo a dim, or
+    /// after a detected suspend, so that going idle again right away
+    /// doesn't fire them again soon. This is zero by default.
+    pub fn set_resume_grace(&mut self, grace: Duration) {
+        self.resume_grace = grace;
+    }
+    /// Get how long timers are held off for after the user returns
+    pub fn resume_grace(&self) -> Duration {
+        self.resume_grace
+    }
+    /// Set how long timers are held off for after the user returns.
+    /// This is the chainable version of `set_resume_grace`.
+    pub fn with_resume_grace(mut self, grace: Duration) -> Self {
+        self.resume_grace = grace;
+        self
+    }
+    /// Returns how much is left of the resume grace period, if the
+    /// user returned recently enough
+    fn resume_grace_left(&self) -> Option<Duration> {
+        self.resumed_at
+            .map(|at| self.resume_grace.saturating_sub(at.elapsed()))
+            .filter(|&left| left > Duration::default())
+    }
+
+    /// Set the shortest time between two timers firing, including a
+    /// timer recurring, so that their commands don't race. A timer
+    /// that would fire sooner waits for it to pass. This is zero by
+    /// default.
+    pub fn set_cooldown(&mut self, cooldown: Duration) {
+        self.cooldown = cooldown;
+    }
+    /// Get the shortest time between two timers firing
+    pub fn cooldown(&self) -> Duration {
+        self.cooldown
+    }
+    /// Set the shortest time between two timers firing. This is the
+    /// chainable version of `set_cooldown`.
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+    /// Set for how many polls in a row the idle time has to stay below
+    /// what it was before it fell for that to count as activity. A dip
+    /// that lasts shorter, such as a single sample of a jittery idle
+    /// counter, is ignored, and timers go on as if it didn't happen.
+    /// While a dip is pending, `poll` asks to be called again within
+    /// `DIP_RECHECK`. This is 1 by default, so every fall counts.
+    pub fn set_activity_debounce(&mut self, polls: usize) {
+        self.activity_debounce = polls;
+    }
+    /// Get for how many polls in a row the idle time has to stay fallen
+    /// for to count as activity
+    pub fn activity_debounce(&self) -> usize {
+        self.activity_debounce
+    }
+    /// Set for how many polls in a row the idle time has to stay fallen
+    /// for to count as activity. This is the chainable version of
+    /// `set_activity_debounce`.
+    pub fn with_activity_debounce(mut self, polls: usize) -> Self {
+        self.activity_debounce = polls;
+        self
+    }
+    /// Returns the idle time to go by for this poll: the one from
+    /// before it fell, while that fall hasn't lasted long enough to
+    /// count as activity, or else this one
+    fn debounce(&mut self, absolute_time: Duration) -> Duration {
+        let (prior, low) = match self.dip {
+            Some((prior, low)) => (prior, low),
+            None => (self.previous_idle_time, 0),
+        };
+        if absolute_time >= prior {
+            if self.dip.take().is_some() {
+                trace!(
+                    "Idle time went back from a dip to {:?}, ignoring it",
+                    absolute_time,
+                );
+            }
+            return absolute_time;
+        }
+        let low = low + 1;
+        if low >= self.activity_debounce {
+            self.dip = None;
+            return absolute_time;
+        }
+        trace!(
+            "Idle time fell from {:?} to {:?}, waiting for {} more polls",
+            prior,
+            absolute_time,
+            self.activity_debounce - low,
+        );
+        self.dip = Some((prior, low));
+        prior
+    }
+
+    /// Add a timer going off at a time of day, if the user is idle then.
+    /// See the `daily` module.
+    pub fn add_daily(&mut self, timer: DailyTimer<T>) {
+        self.daily.push(timer);
+    }
+    /// Get the timers going off at a time of day
+    pub fn daily(&self) -> &[DailyTimer<T>] {
+        &self.daily
+    }
+    /// Add a timer going off at a time of day. This is the chainable
+    /// version of `add_daily`.
+    pub fn with_daily(mut self, timer: DailyTimer<T>) -> Self {
+        self.daily.push(timer);
+        self
+    }
+    /// Set what tells the time of day for the daily timers, which is
+    /// `LocalTime::now` by default. This is meant for tests.
+    pub fn set_clock<F>(&mut self, clock: F)
+    where
+        F: Fn() -> Result<LocalTime> + 'static,
+    {
+        self.clock = Box::new(clock);
+    }
+    /// Go off with the daily timers that are due, returning how soon
+    /// the next one is, if there are any
+    fn poll_daily(&mut self, absolute_time: Duration) -> Result<Option<Duration>> {
+        if self.daily.is_empty() {
+            return Ok(None);
+        }
+        let now = (self.clock)()?;
+        let mut next: Option<Duration> = None;
+        for timer in &mut self.daily {
+            if timer.check(now, absolute_time)? {
+                info!("Daily timer at {} went off", timer);
+            }
+            let due = timer.until_due(now);
+            next = Some(next.map_or(due, |next| cmp::min(next, due)));
+        }
+        Ok(next)
+    }
+
+    /// Lets every timer clean up after itself, see `Timer::reap`
+    fn reap(&mut self) -> Result<()> {
+        let timers = self
+            .timers
+            .iter_mut()
+            .chain(self.daily.iter_mut().map(DailyTimer::timer_mut));
+        for timer in timers {
+            if let Err(err) = timer.reap() {
+                self.module.warning(&err)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns how much is left of the cooldown, if a timer fired
+    /// recently enough
+    fn cooldown_left(&self) -> Option<Duration> {
+        self.fired_at
+            .map(|at| self.cooldown.saturating_sub(at.elapsed()))
+            .filter(|&left| left > Duration::default())
+    }
+
+    /// Bound a sleep into the configured poll interval, where
+    /// `activation` is the time until the next timer activates
+    fn bound_sleep(&self, sleep: Duration, activation: Duration) -> Duration {
+        let mut sleep = sleep;
+        if let Some(min) = self.poll_interval_min {
+            sleep = cmp::max(sleep, min);
+        }
+        if let Some(max) = self.poll_interval_max {
+            sleep = cmp::min(sleep, max);
+        }
+        cmp::min(sleep, activation)
+    }
+
+    /// Record an event to the history file, if any
+    fn record(
+        &mut self,
+        event: Event,
+        index: usize,
+        idle: Duration,
+        inhibited: bool,
+    ) -> Result<()> {
+        if let Some(ref mut history) = self.history {
+            if let Err(err) = history.record(event, index, idle, inhibited) {
+                self.module.warning(&err)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the wall clock time this instance was created at
+    pub fn started_at(&self) -> SystemTime {
+        self.started_at
+    }
+    /// Returns how long ago this instance was created. This uses a
+    /// monotonic clock, so it isn't affected by changes to the system
+    /// time.
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Add an observer, which is told whether the modules let each
+    /// timer run
+    pub fn add_observer<O: Observer + 'static>(&mut self, observer: O) {
+        self.observers.push(Box::new(observer));
+    }
+    /// Add an observer. This is the chainable version of
+    /// `add_observer`.
+    pub fn with_observer<O: Observer + 'static>(mut self, observer: O) -> Self {
+        self.add_observer(observer);
+        self
+    }
+
+    /// Returns the module
+    pub fn module(&self) -> &M {
+        &self.module
+    }
+    /// Returns the module mutably, such as to reorder a `ModuleList`
+    pub fn module_mut(&mut self) -> &mut M {
+        &mut self.module
+    }
+
+    /// Add a module only asked about the timer at this index, after the
+    /// module of all timers, such as to keep only the lock timer from
+    /// running while a window is fullscreen. The modules move along
+    /// with their timer through `insert_timer` and `remove_timer`, but
+    /// stay with the index if the timers are changed with `timers_mut`.
+    pub fn add_timer_module<N: Module + 'static>(
+        &mut self,
+        index: usize,
+        name: impl Into<String>,
+        module: N,
+    ) {
+        self.timer_modules_mut(index).push(name, module);
+    }
+    /// Add a module only asked about the timer at this index. This is
+    /// the chainable version of `add_timer_module`.
+    pub fn with_timer_module<N: Module + 'static>(
+        mut self,
+        index: usize,
+        name: impl Into<String>,
+        module: N,
+    ) -> Self {
+        self.add_timer_module(index, name, module);
+        self
+    }
+    /// Returns the modules only asked about the timer at this index, if
+    /// it has any
+    pub fn timer_modules(&self, index: usize) -> Option<&ModuleList> {
+        self.timer_modules
+            .get(index)
+            .filter(|list| !list.is_empty())
+    }
+    /// Returns the modules only asked about the timer at this index
+    /// mutably, such as to reorder them
+    pub fn timer_modules_mut(&mut self, index: usize) -> &mut ModuleList {
+        if self.timer_modules.len() <= index {
+            self.timer_modules.resize_with(index + 1, ModuleList::new);
+        }
+        &mut self.timer_modules[index]
+    }
+
+    /// Inserts a timer at this index, like inserting it through `timers_mut`, except that the
+    /// modules of the timers after it move along with them (see `add_timer_module`).
+    ///
+    /// # Panics
+    ///
+    /// - If the index is greater than the number of timers
+    pub fn insert_timer(&mut self, index: usize, timer: T) -> Result<()> {
+        self.timers_mut()?.insert(index, timer);
+        if index < self.timer_modules.len() {
+            self.timer_modules.insert(index, ModuleList::new());
+        }
+        Ok(())
+    }
+
+    /// Removes the timer at this index along with its own modules (see `add_timer_module`), like
+    /// removing it through `timers_mut`, except that the modules of the timers after it move
+    /// along with them.
+    ///
+    /// # Panics
+    ///
+    /// - If the index is out of bounds
+    pub fn remove_timer(&mut self, index: usize) -> Result<T> {
+        let timer = self.timers_mut()?.remove(index);
+        if index < self.timer_modules.len() {
+            let mut modules = self.timer_modules.remove(index);
+            if let Err(err) = modules.on_shutdown() {
+                self.module.warning(&err)?;
+            }
+        }
+        Ok(timer)
+    }
+
     /// Returns an immutable list of all timers
     pub fn timers(&self) -> &Vec<T> {
         &self.timers
@@ -180,14 +761,71 @@ where
     /// will abort the idle chain as that may otherwise panic.
     pub fn timers_mut(&mut self) -> Result<&mut Vec<T>> {
         self.abort()?;
+        // Don't keep cancellers waiting for timers that may move
+        self.run_cancellers(true)?;
+        // The indexes may not point to the same timers anymore
+        self.spent.clear();
         Ok(&mut self.timers)
     }
 
-    /// Returns the previous timer that was activated (but not deactivated)
+    /// Warn about timers that are too short for the idle source to
+    /// notice reliably, shorter than twice its resolution. Returns the
+    /// indexes of those timers.
+    pub fn check_resolution<S>(&mut self, source: &S) -> Result<Vec<usize>>
+    where
+        S: IdleSource + ?Sized,
+    {
+        let safe = source.resolution() * RESOLUTION_MARGIN;
+        let mut short = Vec::new();
+        for (index, timer) in self.timers.iter_mut().enumerate() {
+            let threshold = timer.time_left(Duration::default())?.unwrap_or_default();
+            if threshold < safe {
+                warn!(
+                    "Timer {} goes off after {:?}, but the idle source only updates every {:?}, \
+                     so it may not fire reliably",
+                    index,
+                    threshold,
+                    source.resolution(),
+                );
+                short.push(index);
+            }
+        }
+        Ok(short)
+    }
+
+    /// Returns the previous timer that was activated (but not deactivated), unless it was removed
+    /// since
     fn previous(&mut self) -> Option<&mut T> {
+        let index = self.next_index.checked_sub(1)?;
+        self.timers.get_mut(index)
+    }
+
+    /// Returns the index of the next timer in the chain. The timers before it went off (or were
+    /// skipped, if disabled) since the user was last active.
+    pub fn next_index(&self) -> usize {
         self.next_index
-            .checked_sub(1)
-            .map(move |i| &mut self.timers[i])
+    }
+
+    /// Tell the modules xidlehook is exiting, see `Module::on_shutdown`. Only the first call does
+    /// anything. `main_sync` and `main_async` call this when they return, but not when
+    /// `main_async` is cancelled, such as by a signal handler in a `select!`.
+    pub fn shutdown(&mut self) -> Result<()> {
+        if self.shut_down {
+            return Ok(());
+        }
+        self.shut_down = true;
+        self.run_cancellers(true)?;
+        trace!("Shutting down the modules");
+        modules::first_error(
+            iter::once(self.module.on_shutdown())
+                .chain(self.timer_modules.iter_mut().map(Module::on_shutdown)),
+        )
+    }
+
+    /// Returns true if the chain was aborted, such as by a module, so no more timers run until
+    /// the user is active again or it's reset
+    pub fn is_aborted(&self) -> bool {
+        self.aborted
     }
 
     /// Calls the abortion function on the current timer and stops pursuing the chain
@@ -197,8 +835,45 @@ where
         }
 
         self.aborted = true;
-        if let Some(prev) = self.previous() {
-            prev.abort()?;
+
+        let idle = self.previous_idle_time;
+        let gap = match self.canceller_gap {
+            Some(gap) => gap,
+            None => {
+                if let Some(prev) = self.previous() {
+                    prev.abort()?;
+                    self.record(Event::Aborted, self.next_index - 1, idle, false)?;
+                }
+                return Ok(());
+            },
+        };
+
+        // The first one runs right away, and the others from `poll`
+        let mut due = Instant::now();
+        for index in mem::take(&mut self.fired).into_iter().rev() {
+            self.cancellers.push((due, index));
+            due += gap;
+        }
+        self.run_cancellers(false)
+    }
+
+    /// Runs the cancellers queued by `abort` which are due, or all of them if `all` is set
+    fn run_cancellers(&mut self, all: bool) -> Result<()> {
+        let idle = self.previous_idle_time;
+        while let Some(&(due, index)) = self.cancellers.first() {
+            if !all && due > Instant::now() {
+                break;
+            }
+            self.cancellers.remove(0);
+            trace!("Aborting timer {}", index);
+            self.timers[index].abort()?;
+            self.record(Event::Aborted, index, idle, false)?;
+
+            // Keep the gap to the others when this one ran late
+            let late = Instant::now().saturating_duration_since(due);
+            for (due, _) in &mut self.cancellers {
+                *due += late;
+            }
         }
         Ok(())
     }
@@ -206,21 +881,57 @@ where
     /// Calls the abortion functions on the current timer and restarts from index zero. Just like
     /// with the `poll` function, continued usage after an error discouraged.
     pub fn reset(&mut self, absolute_time: Duration) -> Result<()> {
+        let prior_idle = self.previous_idle_time;
         self.abort()?;
 
         trace!("Resetting");
 
         if self.next_index > 0 {
-            if let Err(err) = self.module.reset() {
+            if let Err(err) = self.module.reset(prior_idle) {
                 self.module.warning(&err)?;
             }
+            for modules in &mut self.timer_modules {
+                if let Err(err) = modules.reset(prior_idle) {
+                    modules.warning(&err)?;
+                }
+            }
             self.next_index = 0;
         }
+        self.fired.clear();
+        self.recurred_at = None;
+        if let Some((index, true)) = self.warning.take() {
+            if let Some(timer) = self.timers.get_mut(index) {
+                trace!("Cancelling the warning of timer {}", index);
+                timer.cancel_warning()?;
+            }
+        }
+
+        for timer in &mut self.timers {
+            timer.reset()?;
+        }
 
         self.base_idle_time = absolute_time;
         self.previous_idle_time = absolute_time;
         self.aborted = false;
+        self.dip = None;
+
+        Ok(())
+    }
 
+    /// Tell the modules the user is active again, after having been
+    /// idle for `prior_idle`
+    fn on_active(&mut self, prior_idle: Duration) -> Result<()> {
+        trace!("User is active again after {:?}", prior_idle);
+        self.spent.clear();
+        self.resumed_at = Some(Instant::now());
+        if let Err(err) = self.module.on_active(prior_idle) {
+            self.module.warning(&err)?;
+        }
+        for modules in &mut self.timer_modules {
+            if let Err(err) = modules.on_active(prior_idle) {
+                modules.warning(&err)?;
+            }
+        }
         Ok(())
     }
 
@@ -245,7 +956,7 @@ where
         macro_rules! handle {
             ($progress:expr) => {
                 match $progress {
-                    Progress::Continue => (),
+                    Progress::Continue | Progress::Trigger => (),
                     Progress::Abort => {
                         trace!("Module requested abort of chain.");
                         self.abort()?;
@@ -266,20 +977,55 @@ where
             index,
             length: self.timers.len(),
         };
-
-        let next = &mut self.timers[index];
+        if let Some(ref mut tick) = self.tick {
+            tick.eligible.push(index);
+        }
 
         // Trigger module pre-timer
-        match self.module.pre_timer(timer_info) {
-            Ok(_) if force => (),
-            Ok(progress) => handle!(progress),
-            Err(err) => {
-                self.module.warning(&err)?;
-            },
+        let verdict = self.pre_timer(timer_info)?;
+        let verdict = if force { Progress::Continue } else { verdict };
+        for observer in &mut self.observers {
+            observer.observe(&timer_info, verdict);
+        }
+        if let Some(ref mut tick) = self.tick {
+            tick.verdicts.push((index, verdict));
+        }
+        let idle_ms: u64 = absolute_time.as_millis().try_into().unwrap_or(u64::MAX);
+        if verdict != Progress::Continue {
+            logging::log_event(
+                log::Level::Info,
+                "timer_inhibited",
+                &[
+                    ("timer", index.into()),
+                    ("idle_ms", idle_ms.into()),
+                    ("verdict", verdict.name().into()),
+                ],
+            );
+            self.record(Event::Activated, index, absolute_time, true)?;
         }
+        handle!(verdict);
 
         // Send activation signal to current timer
-        next.activate()?;
+        if self.rearm.is_some_and(|(rearm, _)| rearm == index) {
+            self.rearm = None;
+        }
+        if self.warning.is_some_and(|(warned, _)| warned == index) {
+            self.warning = None;
+        }
+        // Timers are only activated after the cancellers from last time
+        self.run_cancellers(true)?;
+        self.timers[index].set_idle_time(absolute_time);
+        self.timers[index].activate()?;
+        self.fired_at = Some(Instant::now());
+        if let Some(ref mut tick) = self.tick {
+            tick.fired.push(index);
+        }
+        logging::log_event(
+            log::Level::Info,
+            "timer_fired",
+            &[("timer", index.into()), ("idle_ms", idle_ms.into())],
+        );
+        self.record(Event::Activated, index, absolute_time, false)?;
 
         // Send deactivation signal to previous timer
         if let Some(previous) = self.previous() {
@@ -288,6 +1034,11 @@ where
 
         // Reset the idle time to zero
         self.base_idle_time = absolute_time;
+        self.recurred_at = None;
+        self.fired.push(index);
+        if self.timers[index].once() && !self.spent.contains(&index) {
+            self.spent.push(index);
+        }
 
         // Send module post-timer
         match self.module.post_timer(timer_info) {
@@ -296,6 +1047,14 @@ where
                 self.module.warning(&err)?;
             },
         }
+        if let Some(modules) = self.timer_modules.get_mut(index) {
+            match modules.post_timer(timer_info) {
+                Ok(progress) => handle!(progress),
+                Err(err) => {
+                    modules.warning(&err)?;
+                },
+            }
+        }
 
         // Next time, continue from next index
         self.next_index = index + 1;
@@ -303,15 +1062,342 @@ where
         Ok(Progress::Continue)
     }
 
+    /// Runs the activation of the last timer that went off again, see `Timer::recurring`. The
+    /// module is asked first, like before its first activation, and its verdict is handled the
+    /// same way.
+    fn recur(&mut self, index: usize, absolute_time: Duration) -> Result<Progress> {
+        if self.timers[index].disabled() {
+            // Such as while its command from last time is still running
+            trace!("Not repeating disabled timer {}", index);
+            return Ok(Progress::Continue);
+        }
+
+        let timer_info = TimerInfo {
+            index,
+            length: self.timers.len(),
+        };
+        let verdict = self.pre_timer(timer_info)?;
+        for observer in &mut self.observers {
+            observer.observe(&timer_info, verdict);
+        }
+        if let Some(ref mut tick) = self.tick {
+            tick.eligible.push(index);
+            tick.verdicts.push((index, verdict));
+        }
+        match verdict {
+            Progress::Continue | Progress::Trigger => (),
+            Progress::Abort => {
+                trace!("Module requested abort of chain.");
+                self.abort()?;
+                return Ok(verdict);
+            },
+            Progress::Reset => {
+                trace!("Module requested reset of chain.");
+                self.reset(absolute_time)?;
+                return Ok(verdict);
+            },
+            Progress::Stop => return Ok(verdict),
+        }
+
+        trace!("Repeating timer {}", index);
+        self.timers[index].set_idle_time(absolute_time);
+        self.timers[index].activate()?;
+        self.fired_at = Some(Instant::now());
+        if let Some(ref mut tick) = self.tick {
+            tick.fired.push(index);
+        }
+        let idle_ms: u64 = absolute_time.as_millis().try_into().unwrap_or(u64::MAX);
+        logging::log_event(
+            log::Level::Info,
+            "timer_recurred",
+            &[("timer", index.into()), ("idle_ms", idle_ms.into())],
+        );
+        self.record(Event::Activated, index, absolute_time, false)?;
+        Ok(Progress::Continue)
+    }
+
+    /// Runs the warning of a timer that's about to go off, see `Timer::warning_lead`. The module
+    /// is asked first, but only to decide whether to warn: its verdict is handled once the timer
+    /// is due.
+    fn warn(&mut self, index: usize, absolute_time: Duration) -> Result<()> {
+        let timer_info = TimerInfo {
+            index,
+            length: self.timers.len(),
+        };
+        let verdict = self.pre_timer(timer_info)?;
+        let warned = verdict == Progress::Continue;
+        self.warning = Some((index, warned));
+        if !warned {
+            trace!("Not warning about timer {}, the module objected", index);
+            return Ok(());
+        }
+
+        trace!("Warning about timer {}", index);
+        self.timers[index].set_idle_time(absolute_time);
+        self.timers[index].warn()?;
+        let idle_ms: u64 = absolute_time.as_millis().try_into().unwrap_or(u64::MAX);
+        logging::log_event(
+            log::Level::Info,
+            "timer_warned",
+            &[("timer", index.into()), ("idle_ms", idle_ms.into())],
+        );
+        Ok(())
+    }
+
+    /// Asks the module, and then the timer's own modules unless the module already objected,
+    /// whether the timer may run. An error is passed to the `Module::warning` of whichever failed
+    /// and counts as `Progress::Continue`, like `Progress::Trigger` does, which only
+    /// `Module::poll_timer` may say.
+    fn pre_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        let verdict = match self.module.pre_timer(timer) {
+            Ok(Progress::Trigger) => Progress::Continue,
+            Ok(progress) => progress,
+            Err(err) => {
+                self.module.warning(&err)?;
+                Progress::Continue
+            },
+        };
+        let modules = match self.timer_modules.get_mut(timer.index) {
+            Some(modules) if verdict == Progress::Continue => modules,
+            _ => return Ok(verdict),
+        };
+        match modules.pre_timer(timer) {
+            Ok(Progress::Trigger) => Ok(Progress::Continue),
+            Ok(progress) => Ok(progress),
+            Err(err) => {
+                modules.warning(&err)?;
+                Ok(Progress::Continue)
+            },
+        }
+    }
+
+    /// Like `pre_timer`, but with `Module::peek_timer`, for dry runs. Errors aren't passed to
+    /// `Module::warning`, which may have side effects too, and only count as `Progress::Continue`.
+    fn peek_timer(&mut self, timer: TimerInfo) -> Progress {
+        let verdict = match self.module.peek_timer(timer) {
+            Ok(Progress::Trigger) => Progress::Continue,
+            Ok(progress) => progress,
+            Err(err) => {
+                debug!("Module couldn't tell about timer {}: {}", timer.index, err);
+                Progress::Continue
+            },
+        };
+        let modules = match self.timer_modules.get_mut(timer.index) {
+            Some(modules) if verdict == Progress::Continue => modules,
+            _ => return verdict,
+        };
+        match modules.peek_timer(timer) {
+            Ok(Progress::Trigger) => Progress::Continue,
+            Ok(progress) => progress,
+            Err(err) => {
+                debug!("Module couldn't tell about timer {}: {}", timer.index, err);
+                Progress::Continue
+            },
+        }
+    }
+
+    /// Returns an error if the selected timer is the one the idle chain is currently at, as
+    /// testing it would interfere with its real activation/abortion.
+    fn ensure_testable(&self, index: usize) -> Result<()> {
+        if !self.aborted && self.next_index.checked_sub(1) == Some(index) {
+            return Err("refusing to test the currently active timer".into());
+        }
+        Ok(())
+    }
+
+    /// Runs the activation of the selected timer without affecting the idle chain, as a way to
+    /// verify a timer works without waiting for the user to go idle. Pair this with
+    /// `test_abortion` to also verify the canceller.
+    ///
+    /// # Panics
+    ///
+    /// - If the index is out of bounds
+    pub fn test_activation(&mut self, index: usize) -> Result<()> {
+        self.ensure_testable(index)?;
+        info!("TEST: running activation of timer {}", index);
+        self.timers[index].test_activation()
+    }
+
+    /// Runs the abortion of the selected timer without affecting the idle chain. See
+    /// `test_activation`.
+    ///
+    /// # Panics
+    ///
+    /// - If the index is out of bounds
+    pub fn test_abortion(&mut self, index: usize) -> Result<()> {
+        self.ensure_testable(index)?;
+        info!("TEST: running canceller of timer {}", index);
+        self.timers[index].test_abortion()
+    }
+
+    /// Works out which timers would have fired if the user had been idle for `idle`, asking the
+    /// modules about each one with `Module::peek_timer`, and how long until the next one. No
+    /// timers are run, and neither the idle chain nor the modules are affected.
+    pub fn simulate(&mut self, idle: Duration) -> Result<Simulation> {
+        let mut simulation = Simulation::new(idle);
+        let mut relative_time = idle;
+
+        for index in 0..self.timers.len() {
+            if self.timers[index].disabled() {
+                continue;
+            }
+            let threshold = self.timers[index]
+                .time_left(Duration::default())?
+                .unwrap_or_default();
+            // How long the user was idle when the last timer went off, to compare against the
+            // timer's band of idle times
+            let start = idle.saturating_sub(relative_time);
+            let threshold = match self.timers[index].min_idle() {
+                Some(min) => cmp::max(threshold, min.saturating_sub(start)),
+                None => threshold,
+            };
+            if self.timers[index]
+                .max_idle()
+                .is_some_and(|max| start.saturating_add(threshold) > max)
+            {
+                continue;
+            }
+            if relative_time < threshold {
+                return Ok(simulation.with_next(index, threshold - relative_time));
+            }
+
+            let timer_info = TimerInfo {
+                index,
+                length: self.timers.len(),
+            };
+            if self.peek_timer(timer_info) != Progress::Continue {
+                simulation.inhibited = Some(index);
+                break;
+            }
+            simulation.fired.push(index);
+            relative_time -= threshold;
+        }
+        Ok(simulation)
+    }
+
     /// Polls the scheduler for any activated timers. On success, returns the max amount of time a
     /// program can sleep for. Only fatal errors cause this function to return, and at that point,
     /// the state of xidlehook is undefined so it should not be used.
     pub fn poll(&mut self, absolute_time: Duration) -> Result<Action> {
-        if absolute_time < self.previous_idle_time {
+        let start = Instant::now();
+        let absolute_time = self.debounce(absolute_time);
+        self.tick = if log::log_enabled!(target: logging::EVENTS_TARGET, log::Level::Trace) {
+            Some(TickTrace::default())
+        } else {
+            None
+        };
+        self.reap()?;
+        let action = match (self.poll_chain(absolute_time)?, self.dip) {
+            // Check soon whether the dip lasts
+            (Action::Sleep(delay), Some(_)) => Action::Sleep(cmp::min(delay, DIP_RECHECK)),
+            (Action::Forever, Some(_)) => Action::Sleep(DIP_RECHECK),
+            (action, _) => action,
+        };
+        let action = match (action, self.poll_daily(absolute_time)?) {
+            // Wake up in time for the next daily timer
+            (Action::Sleep(delay), Some(due)) => Action::Sleep(cmp::min(delay, due)),
+            (Action::Forever, Some(due)) => Action::Sleep(due),
+            (action, _) => action,
+        };
+        self.run_cancellers(false)?;
+        let next_canceller = self
+            .cancellers
+            .first()
+            .map(|&(due, _)| due.saturating_duration_since(Instant::now()));
+        let action = match (action, next_canceller) {
+            // Wake up in time for the next canceller
+            (Action::Sleep(delay), Some(due)) => Action::Sleep(cmp::min(delay, due)),
+            (Action::Forever, Some(due)) => Action::Sleep(due),
+            (action, _) => action,
+        };
+        if let Some(tick) = self.tick.take() {
+            tick.log(absolute_time, action);
+        }
+
+        let threshold = match self.watchdog {
+            Some(ref watchdog) if watchdog.watches_idle() => self.shortest_threshold()?,
+            _ => None,
+        };
+        let watchdog = match self.watchdog {
+            Some(ref mut watchdog) => watchdog,
+            None => return Ok(action),
+        };
+        // The watchdog failing shouldn't stop the timers
+        if let Err(err) = watchdog.check(start.elapsed()) {
+            self.module.warning(&err)?;
+        }
+        if let Some(threshold) = threshold {
+            if let Err(err) = watchdog.check_idle(absolute_time, threshold) {
+                self.module.warning(&err)?;
+            }
+        }
+
+        // Wake up often enough to keep the heartbeat fresh
+        Ok(match (action, watchdog.interval()) {
+            (Action::Sleep(delay), Some(interval)) => Action::Sleep(cmp::min(delay, interval)),
+            (Action::Forever, Some(interval)) => Action::Sleep(interval),
+            (action, _) => action,
+        })
+    }
+
+    /// Returns the shortest idle time any enabled timer goes off after, apart from timers going
+    /// off right away, if there are any
+    fn shortest_threshold(&mut self) -> Result<Option<Duration>> {
+        let mut shortest: Option<Duration> = None;
+        for timer in &mut self.timers {
+            if timer.disabled() {
+                continue;
+            }
+            if let Some(threshold) = timer.time_left(Duration::default())? {
+                shortest = Some(shortest.map_or(threshold, |d| cmp::min(d, threshold)));
+            }
+        }
+        Ok(shortest)
+    }
+
+    /// Returns the last timer that fired and its dead zone, if it has one
+    fn last_dead_zone(&self) -> Option<(usize, Duration)> {
+        let index = self.next_index.checked_sub(1)?;
+        Some((index, self.timers.get(index)?.dead_zone()))
+            .filter(|&(_, dead_zone)| dead_zone > Duration::default())
+    }
+
+    /// The actual implementation of `poll`, without the watchdog
+    fn poll_chain(&mut self, absolute_time: Duration) -> Result<Action> {
+        let dead_zone = self.last_dead_zone();
+        let jitter = dead_zone.is_some_and(|(_, dead_zone)| {
+            absolute_time.saturating_add(dead_zone) >= self.base_idle_time
+        });
+        if absolute_time < self.previous_idle_time && jitter {
+            // The idle time is still within the dead zone of the timer that fired last, so this
+            // isn't taken as activity
+            trace!(
+                "Idle time fell from {:?} to {:?}, within the dead zone",
+                self.previous_idle_time,
+                absolute_time,
+            );
+        } else if absolute_time < self.previous_idle_time {
             // If the idle time has decreased, the only reasonable explanation is that the user
             // briefly wasn't idle. We reset the base idle time to zero so the entire idle duration
             // is counted.
+            if self.log_resets {
+                info!(
+                    target: "xidlehook_core::resets",
+                    "Idle time fell from {:?} to {:?}, resetting",
+                    self.previous_idle_time,
+                    absolute_time,
+                );
+            }
+            let started = self.next_index > 0 || self.aborted;
+            let prior_idle = self.previous_idle_time;
             self.reset(Duration::from_millis(0))?;
+            self.spent.clear();
+            if dead_zone.is_some() {
+                self.rearm = dead_zone;
+            }
+            if started {
+                self.on_active(prior_idle)?;
+            }
         }
 
         self.previous_idle_time = absolute_time;
@@ -352,15 +1438,31 @@ where
 
         if self.aborted {
             trace!("This chain was aborted, I won't pursue it");
-            return Ok(Action::Sleep(max_sleep));
+            return Ok(self.next_wakeup(max_sleep, Duration::from_nanos(u64::MAX)));
         }
 
-        let relative_time = absolute_time - self.base_idle_time;
+        let relative_time = absolute_time.saturating_sub(self.base_idle_time);
         trace!("Relative time: {:?}", relative_time);
 
         let mut next_index = self.next_index;
 
         while let Some(timer) = self.timers.get_mut(next_index) {
+            // Timers which only go off once, and did, are skipped until the user is active again
+            // (see `Timer::once()`)
+            if self.spent.contains(&next_index) {
+                trace!("Skipping timer {}, which already went off", next_index);
+                next_index += 1;
+                continue;
+            }
+            // Timers past their band of idle times are skipped too (see `Timer::max_idle()`)
+            if timer.max_idle().is_some_and(|max| absolute_time > max) {
+                trace!(
+                    "Skipping timer {}, the user is idle for too long",
+                    next_index
+                );
+                next_index += 1;
+                continue;
+            }
             if !timer.disabled() {
                 break;
             }
@@ -378,47 +1480,191 @@ where
             next_index += 1;
         }
 
+        // The module may want the next timer to run right away (see `Module::poll_timer()`)
+        if next_index < self.timers.len() {
+            let timer_info = TimerInfo {
+                index: next_index,
+                length: self.timers.len(),
+            };
+            let verdict = match self.module.poll_timer(timer_info) {
+                Ok(progress) => progress,
+                Err(err) => {
+                    self.module.warning(&err)?;
+                    Progress::Continue
+                },
+            };
+            match verdict {
+                Progress::Trigger => {
+                    trace!("Module requested timer #{} to run now", next_index);
+                    if self.trigger(next_index, absolute_time, true)? == Progress::Stop {
+                        return Ok(Action::Quit);
+                    }
+                    return self.poll_chain(absolute_time);
+                },
+                Progress::Stop => return Ok(Action::Quit),
+                Progress::Continue | Progress::Abort | Progress::Reset => (),
+            }
+        }
+
         // When there's a next timer available, get the time until that activates
+        // A timer that was cancelled needs to get past its dead zone to fire again
+        let hysteresis = match self.rearm {
+            Some((index, dead_zone)) if index == next_index => dead_zone,
+            _ => Duration::default(),
+        };
         if let Some(next) = self.timers.get_mut(next_index) {
-            if let Some(remaining) = next.time_left(relative_time)? {
+            // The timer may not be in its band of idle times yet (see `Timer::min_idle()`)
+            let below_min = next
+                .min_idle()
+                .and_then(|min| min.checked_sub(absolute_time))
+                .filter(|&d| d > Duration::default());
+            if let Some(remaining) = next.time_left(relative_time.saturating_sub(hysteresis))? {
                 trace!(
                     "Taking next enabled timer into account. Remaining: {:?}",
                     remaining
                 );
                 max_sleep = cmp::min(max_sleep, remaining);
+
+                // The timer may want a warning to go out before it does (see
+                // `Timer::warning_lead()`)
+                let lead = next.warning_lead();
+                if let Some(lead) =
+                    lead.filter(|_| self.warning.is_none_or(|(i, _)| i != next_index))
+                {
+                    match remaining
+                        .checked_sub(lead)
+                        .filter(|&d| d > Duration::default())
+                    {
+                        Some(until) => max_sleep = cmp::min(max_sleep, until),
+                        None => self.warn(next_index, absolute_time)?,
+                    }
+                }
+            } else if let Some(left) = below_min {
+                trace!(
+                    "Not triggering timer #{} yet, the user isn't idle for long enough. Remaining: {:?}",
+                    next_index,
+                    left
+                );
+                max_sleep = cmp::min(max_sleep, left);
+            } else if let Some(left) = self.resume_grace_left() {
+                trace!(
+                    "Not triggering timer #{} yet, the user returned recently. Remaining: {:?}",
+                    next_index,
+                    left
+                );
+                max_sleep = cmp::min(max_sleep, left);
+            } else if let Some(left) = self.cooldown_left() {
+                trace!(
+                    "Not triggering timer #{} yet, another just fired. Remaining: {:?}",
+                    next_index,
+                    left
+                );
+                max_sleep = cmp::min(max_sleep, left);
             } else {
                 trace!("Triggering timer #{}", next_index);
                 // Oh! It has already been passed - let's trigger it.
-                match self.trigger(next_index, absolute_time, false)? {
-                    Progress::Stop => return Ok(Action::Quit),
-                    _ => (),
+                if self.trigger(next_index, absolute_time, false)? == Progress::Stop {
+                    return Ok(Action::Quit);
                 }
 
                 // Recurse to find return value
-                return self.poll(absolute_time);
+                return self.poll_chain(absolute_time);
+            }
+        }
+
+        // The last timer that went off may want to go off again while the user stays idle (see
+        // `Timer::recurring()`)
+        if let Some(index) = self.next_index.checked_sub(1) {
+            if let Some(interval) = self
+                .timers
+                .get(index)
+                .filter(|timer| !timer.once())
+                .filter(|timer| timer.max_idle().is_none_or(|max| absolute_time <= max))
+                .and_then(Timer::recurring)
+                // It would go off on every poll otherwise
+                .filter(|&interval| interval != Duration::default())
+            {
+                let due = self
+                    .recurred_at
+                    .unwrap_or(self.base_idle_time)
+                    .saturating_add(interval);
+                if let Some(remaining) = due
+                    .checked_sub(absolute_time)
+                    .filter(|&d| d > Duration::default())
+                {
+                    trace!(
+                        "Taking recurring timer into account. Remaining: {:?}",
+                        remaining
+                    );
+                    max_sleep = cmp::min(max_sleep, remaining);
+                } else if let Some(left) = self.cooldown_left() {
+                    trace!(
+                        "Not repeating timer {} yet, a timer just fired. Remaining: {:?}",
+                        index,
+                        left
+                    );
+                    max_sleep = cmp::min(max_sleep, left);
+                } else {
+                    self.recurred_at = Some(absolute_time);
+                    if self.recur(index, absolute_time)? == Progress::Stop {
+                        return Ok(Action::Quit);
+                    }
+                    return self.poll_chain(absolute_time);
+                }
             }
         }
 
         // When there's a previous timer, respect that timer's abort urgency (see
         // `Timer::abort_urgency()`)
+        let mut urgency = Duration::from_nanos(u64::MAX);
         if let Some(abort) = self.previous() {
-            if let Some(urgency) = abort.abort_urgency() {
+            if let Some(abort_urgency) = abort.abort_urgency() {
                 trace!(
                     "Taking abort urgency into account. Remaining: {:?}",
-                    urgency
+                    abort_urgency
                 );
-                max_sleep = cmp::min(max_sleep, urgency);
+                urgency = abort_urgency;
             }
         }
+        // Timers that went off may want to keep an eye on their commands while the user is idle
+        // (see `Timer::supervise()`)
+        for index in self.fired.clone() {
+            if let Some(again) = self.timers[index].supervise()? {
+                urgency = cmp::min(urgency, again);
+            }
+        }
+
+        Ok(self.next_wakeup(max_sleep, urgency))
+    }
+
+    /// Works out how long to sleep for: until the next timer is due at `due`, or sooner if a
+    /// timer wants to be checked on before then (`urgency`) or the module or a timer wants to be
+    /// polled more often (see `Module::poll_interval()` and `Timer::poll_interval()`), within the
+    /// configured poll intervals
+    fn next_wakeup(&self, due: Duration, urgency: Duration) -> Action {
+        let mut urgency = urgency;
+        let intervals = iter::once(self.module.poll_interval())
+            .chain(self.timer_modules.iter().map(Module::poll_interval))
+            .chain(self.timers.iter().map(Timer::poll_interval));
+        if let Some(interval) = modules::shortest(intervals) {
+            trace!(
+                "Taking poll interval into account. Remaining: {:?}",
+                interval
+            );
+            urgency = cmp::min(urgency, interval);
+        }
 
-        if max_sleep == Duration::from_nanos(u64::MAX) {
-            Ok(Action::Forever)
+        let sleep = cmp::min(due, urgency);
+        if sleep == Duration::from_nanos(u64::MAX) {
+            self.poll_interval_max
+                .map_or(Action::Forever, Action::Sleep)
         } else {
-            Ok(Action::Sleep(max_sleep))
+            Action::Sleep(self.bound_sleep(sleep, due))
         }
     }
 
-    /// Runs a standard poll-sleep-repeat loop.
+    /// Runs a standard poll-sleep-repeat loop, getting the idle time
+    /// from any idle source, such as `modules::Xcb`.
     /// ```rust
     /// # if std::env::var("DISPLAY").is_err() {
     /// #     // Don't fail on CI.
@@ -464,12 +1710,25 @@ where
     /// xidlehook.main_sync(&xcb, || EXITED.load(Ordering::SeqCst));
     /// # Ok::<(), xidlehook_core::Error>(())
     /// ```
-    pub fn main_sync<F>(mut self, xcb: &self::modules::Xcb, mut callback: F) -> Result<()>
+    pub fn main_sync<S, F>(mut self, source: &S, callback: F) -> Result<()>
     where
+        S: IdleSource + ?Sized,
+        F: FnMut() -> bool,
+    {
+        let result = self.run_sync(source, callback);
+        let shutdown = self.shutdown();
+        result.and(shutdown)
+    }
+
+    /// The loop of `main_sync`
+    fn run_sync<S, F>(&mut self, source: &S, mut callback: F) -> Result<()>
+    where
+        S: IdleSource + ?Sized,
         F: FnMut() -> bool,
     {
         loop {
-            let idle = xcb.get_idle()?;
+            source.refresh();
+            let idle = source.get_idle()?;
             match self.poll(idle)? {
                 Action::Sleep(delay) => {
                     trace!("Sleeping for {:?}", delay);
@@ -477,6 +1736,8 @@ where
                     let sleep_start = Instant::now();
 
                     // This sleep, unlike `thread::sleep`, will stop for signals.
+                    // `c_long` is only 32 bits wide on some targets, hence the fallible conversions.
+                    #[allow(clippy::unnecessary_fallible_conversions)]
                     unsafe {
                         libc::nanosleep(
                             &libc::timespec {
@@ -499,7 +1760,10 @@ where
                                 "We slept {:?} longer than expected - has the computer been suspended?",
                                 time_difference,
                             );
-                            self.reset(xcb.get_idle()?)?;
+                            let prior_idle = self.previous_idle_time;
+                            source.refresh();
+                            self.reset(source.get_idle()?)?;
+                            self.on_active(prior_idle)?;
                         }
                     }
                 },
@@ -518,45 +1782,166 @@ where
         Ok(())
     }
 
+    /// Runs the loop of `main_sync` for this many ticks without
+    /// sleeping, calling `step` with the number of each tick before it
+    /// polls, so that it can change what the source says. This is
+    /// meant for tests, with a `sources::ManualIdleSource`. Returns the
+    /// last action, stopping early at `Action::Forever` or
+    /// `Action::Quit`. Unlike `main_sync`, it neither detects
+    /// suspension nor shuts down afterwards.
+    pub fn run_ticks<S, F>(&mut self, source: &S, ticks: usize, mut step: F) -> Result<Action>
+    where
+        S: IdleSource + ?Sized,
+        F: FnMut(usize),
+    {
+        let mut action = Action::Forever;
+        for tick in 0..ticks {
+            step(tick);
+            source.refresh();
+            action = self.poll(source.get_idle()?)?;
+            if let Action::Forever | Action::Quit = action {
+                break;
+            }
+        }
+        Ok(action)
+    }
+
     /// Runs a standard poll-sleep-repeat loop... asynchronously.
     #[cfg(any(feature = "async-std", feature = "tokio"))]
-    pub async fn main_async(&mut self, xcb: &self::modules::Xcb) -> Result<()> {
+    pub async fn main_async<S>(&mut self, source: &S) -> Result<()>
+    where
+        S: IdleSource + ?Sized,
+    {
+        let result = self.run_async(source).await;
+        let shutdown = self.shutdown();
+        result.and(shutdown)
+    }
+
+    /// The loop of `main_async`
+    #[cfg(any(feature = "async-std", feature = "tokio"))]
+    async fn run_async<S>(&mut self, source: &S) -> Result<()>
+    where
+        S: IdleSource + ?Sized,
+    {
         loop {
-            let idle = xcb.get_idle()?;
-            match self.poll(idle)? {
-                Action::Sleep(delay) => {
-                    trace!("Sleeping for {:?}", delay);
+            source.refresh();
+            let idle = source.get_idle()?;
+            let action = self.poll(idle)?;
+            if !self.wait_async(action, source).await? {
+                break;
+            }
+        }
+        Ok(())
+    }
 
-                    let sleep_start = Instant::now();
+    /// Does what `poll` said to, returning false if it's time to quit
+    #[cfg(any(feature = "async-std", feature = "tokio"))]
+    async fn wait_async<S>(&mut self, action: Action, source: &S) -> Result<bool>
+    where
+        S: IdleSource + ?Sized,
+    {
+        match action {
+            Action::Sleep(delay) => {
+                trace!("Sleeping for {:?}", delay);
 
-                    #[cfg(feature = "async-std")]
-                    async_std::task::sleep(delay).await;
-                    #[cfg(feature = "tokio")]
-                    if cfg!(not(feature = "async-std")) {
-                        tokio::time::sleep(delay).await;
-                    }
+                let sleep_start = Instant::now();
 
-                    if let Some(time_difference) = sleep_start.elapsed().checked_sub(delay) {
-                        if time_difference >= Duration::from_secs(3) && self.detect_sleep {
-                            info!(
-                                "We slept {:?} longer than expected - has the computer been suspended?",
-                                time_difference,
-                            );
-                            self.reset(xcb.get_idle()?)?;
-                        }
-                    }
-                },
-                Action::Forever => {
-                    trace!("Nothing to do");
+                #[cfg(feature = "async-std")]
+                async_std::task::sleep(delay).await;
+                #[cfg(feature = "tokio")]
+                if cfg!(not(feature = "async-std")) {
+                    tokio::time::sleep(delay).await;
+                }
 
-                    #[cfg(feature = "async-std")]
-                    async_std::future::pending::<()>().await;
-                    #[cfg(feature = "tokio")]
-                    if cfg!(not(feature = "async-std")) {
-                        std::future::pending::<()>().await;
+                if let Some(time_difference) = sleep_start.elapsed().checked_sub(delay) {
+                    if time_difference >= Duration::from_secs(3) && self.detect_sleep {
+                        info!(
+                            "We slept {:?} longer than expected - has the computer been suspended?",
+                            time_difference,
+                        );
+                        let prior_idle = self.previous_idle_time;
+                        source.refresh();
+                        self.reset(source.get_idle()?)?;
+                        self.on_active(prior_idle)?;
                     }
-                },
-                Action::Quit => break,
+                }
+            },
+            Action::Forever => {
+                trace!("Nothing to do");
+
+                #[cfg(feature = "async-std")]
+                async_std::future::pending::<()>().await;
+                #[cfg(feature = "tokio")]
+                if cfg!(not(feature = "async-std")) {
+                    std::future::pending::<()>().await;
+                }
+            },
+            Action::Quit => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T, A> Xidlehook<T, modules::Awaited<A>>
+where
+    T: Timer,
+    A: modules::AsyncModule,
+{
+    /// The timers the next poll may ask the module about: the last one
+    /// that fired, which may recur, and the ones after it. If the
+    /// chain was aborted, a reset may start it over from the first.
+    fn upcoming(&self) -> Vec<TimerInfo> {
+        let first = if self.aborted {
+            0
+        } else {
+            self.next_index.saturating_sub(1)
+        };
+        let length = self.timers.len();
+        (first..length)
+            .map(|index| TimerInfo { index, length })
+            .collect()
+    }
+
+    /// Awaits the module's decisions about the timers that may be due
+    /// at the next poll, see `modules::Awaited`
+    pub async fn prefetch(&mut self) {
+        let upcoming = self.upcoming();
+        self.module.prefetch(upcoming).await;
+    }
+
+    /// Like `poll`, but awaits the module's decisions first
+    pub async fn poll_async(&mut self, absolute_time: Duration) -> Result<Action> {
+        self.prefetch().await;
+        self.poll(absolute_time)
+    }
+
+    /// Like `main_async`, but awaits the module's decisions before each
+    /// poll, so they don't stall the executor. The idle time is taken
+    /// once they're in.
+    #[cfg(any(feature = "async-std", feature = "tokio"))]
+    pub async fn main_awaited<S>(&mut self, source: &S) -> Result<()>
+    where
+        S: IdleSource + ?Sized,
+    {
+        let result = self.run_awaited(source).await;
+        let shutdown = self.shutdown();
+        result.and(shutdown)
+    }
+
+    /// The loop of `main_awaited`
+    #[cfg(any(feature = "async-std", feature = "tokio"))]
+    async fn run_awaited<S>(&mut self, source: &S) -> Result<()>
+    where
+        S: IdleSource + ?Sized,
+    {
+        loop {
+            self.prefetch().await;
+            source.refresh();
+            let idle = source.get_idle()?;
+            let action = self.poll(idle)?;
+            if !self.wait_async(action, source).await? {
+                break;
             }
         }
         Ok(())
@@ -0,0 +1,127 @@
+//! Structured log events, for shipping logs to a central system.
+//! Events are ordinary log records, whose message reads like
+//! `timer_fired timer=0 idle_ms=60000` and which carry the same
+//! fields as key-value pairs. `json_line` turns any record into a
+//! line of JSON, using those fields when there are any:
+//!
+//! ```json
+//! {"timestamp":1602681600.000,"level":"INFO","target":"xidlehook_core::events","event":"timer_fired","message":"timer_fired timer=0 idle_ms=60000","timer":0,"idle_ms":60000}
+//! ```
+//!
+//! For telling why a timer didn't fire, every poll also logs a `tick`
+//! event at trace level, with the idle time, the timers whose time came
+//! (`eligible`), what the modules said about them (`verdicts`, like
+//! `0:continue,1:abort`), the timers that went off (`fired`) and what
+//! the loop does next (`action`, like `sleep:5000`). It's only
+//! gathered if the logger wants trace records of `EVENTS_TARGET`.
+
+use std::{
+    fmt::Write,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use log::{
+    kv::{self, Key, Value, Visitor},
+    Level, Record,
+};
+
+/// The target of all events
+pub const EVENTS_TARGET: &str = "xidlehook_core::events";
+
+/// Log an event with these fields, if the logger wants events of this
+/// level
+pub fn log_event(level: Level, event: &str, fields: &[(&str, Value)]) {
+    if !log::log_enabled!(target: EVENTS_TARGET, level) {
+        return;
+    }
+    let mut message = String::from(event);
+    for (key, value) in fields {
+        let _ = write!(message, " {}={}", key, value);
+    }
+    let name = ("event", event);
+    let kvs: &[&dyn kv::Source] = &[&name, &fields];
+    log::logger().log(
+        &Record::builder()
+            .level(level)
+            .target(EVENTS_TARGET)
+            .args(format_args!("{}", message))
+            .key_values(&kvs)
+            .build(),
+    );
+}
+
+/// Format a record as a line of JSON, without the trailing newline.
+/// The timestamp is in seconds since the Unix epoch.
+pub fn json_line(record: &Record, now: SystemTime) -> String {
+    let timestamp = now.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let mut line = format!(
+        "{{\"timestamp\":{}.{:03},\"level\":\"{}\",\"target\":",
+        timestamp.as_secs(),
+        timestamp.subsec_millis(),
+        record.level(),
+    );
+    push_string(&mut line, record.target());
+
+    let mut fields = JsonFields {
+        event: None,
+        fields: String::new(),
+    };
+    let _ = record.key_values().visit(&mut fields);
+    if let Some(event) = fields.event {
+        line.push_str(",\"event\":");
+        push_string(&mut line, &event);
+    }
+    line.push_str(",\"message\":");
+    push_string(&mut line, &record.args().to_string());
+    line.push_str(&fields.fields);
+    line.push('}');
+    line
+}
+
+/// Collects the fields of a record as JSON members, except for the
+/// event name which goes first
+#[derive(Debug)]
+struct JsonFields {
+    event: Option<String>,
+    fields: String,
+}
+impl<'kvs> Visitor<'kvs> for JsonFields {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        if key.as_str() == "event" {
+            self.event = Some(value.to_string());
+            return Ok(());
+        }
+        self.fields.push(',');
+        push_string(&mut self.fields, key.as_str());
+        self.fields.push(':');
+        if let Some(value) = value.to_u64() {
+            self.fields.push_str(&value.to_string());
+        } else if let Some(value) = value.to_i64() {
+            self.fields.push_str(&value.to_string());
+        } else if let Some(value) = value.to_bool() {
+            self.fields.push_str(&value.to_string());
+        } else {
+            push_string(&mut self.fields, &value.to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Append a JSON string literal
+fn push_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            },
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
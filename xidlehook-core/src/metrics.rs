@@ -0,0 +1,192 @@
+//! Counters for monitoring xidlehook, rendered in the Prometheus text
+//! format:
+//!
+//! ```text
+//! # HELP xidlehook_timer_fires_total How many times each timer went off
+//! # TYPE xidlehook_timer_fires_total counter
+//! xidlehook_timer_fires_total{timer="0"} 3
+//! ```
+//!
+//! Register `Metrics` as an observer to count the timers going off,
+//! and wrap the fullscreen module with `Metrics::count_suppressions`
+//! to count the timers it keeps from running. The idle time and the
+//! number of X reconnects are whatever was last set.
+
+use crate::{modules::ModuleDescription, Error, Module, Observer, Progress, Result, TimerInfo};
+
+use std::{cell::RefCell, fmt, rc::Rc, time::Duration};
+
+#[derive(Debug, Default)]
+struct Counters {
+    fires: Vec<u64>,
+    idle: Duration,
+    fullscreen_suppressions: u64,
+    x_reconnects: u64,
+}
+
+/// See the module-level documentation. Clones share the same
+/// counters, so one clone can be registered while another is kept
+/// around to render them.
+#[derive(Clone, Debug, Default)]
+pub struct Metrics(Rc<RefCell<Counters>>);
+impl Metrics {
+    /// Returns metrics with every counter at zero
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Set how many timers there are, so the ones that never went off
+    /// are listed too
+    pub fn set_timers(&self, timers: usize) {
+        self.0.borrow_mut().fires.resize(timers, 0);
+    }
+    /// Set how long the user has been idle
+    pub fn set_idle(&self, idle: Duration) {
+        self.0.borrow_mut().idle = idle;
+    }
+    /// Set how many times the X connection was reconnected, such as
+    /// from `Xcb::reconnects`
+    pub fn set_x_reconnects(&self, x_reconnects: u64) {
+        self.0.borrow_mut().x_reconnects = x_reconnects;
+    }
+    /// Returns how many times this timer went off
+    pub fn fires(&self, index: usize) -> u64 {
+        self.0.borrow().fires.get(index).copied().unwrap_or(0)
+    }
+    /// Returns how many times a fullscreen window kept a timer from
+    /// running
+    pub fn fullscreen_suppressions(&self) -> u64 {
+        self.0.borrow().fullscreen_suppressions
+    }
+    /// Count every time this module, which should be the fullscreen
+    /// one, keeps a timer from running
+    pub fn count_suppressions<M: Module>(&self, module: M) -> Suppressions<M> {
+        Suppressions {
+            module,
+            metrics: self.clone(),
+        }
+    }
+    /// Render the metrics in the Prometheus text format
+    pub fn render(&self) -> String {
+        self.to_string()
+    }
+}
+impl Observer for Metrics {
+    fn observe(&mut self, info: &TimerInfo, verdict: Progress) {
+        if verdict != Progress::Continue {
+            return;
+        }
+        let mut counters = self.0.borrow_mut();
+        if counters.fires.len() <= info.index {
+            counters.fires.resize(info.index.saturating_add(1), 0);
+        }
+        counters.fires[info.index] = counters.fires[info.index].saturating_add(1);
+    }
+}
+impl fmt::Display for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let counters = self.0.borrow();
+
+        header(
+            f,
+            "xidlehook_timer_fires_total",
+            "counter",
+            "How many times each timer went off",
+        )?;
+        for (index, count) in counters.fires.iter().enumerate() {
+            writeln!(
+                f,
+                "xidlehook_timer_fires_total{{timer=\"{}\"}} {}",
+                index, count
+            )?;
+        }
+        header(
+            f,
+            "xidlehook_idle_seconds",
+            "gauge",
+            "How long the user has been idle",
+        )?;
+        writeln!(
+            f,
+            "xidlehook_idle_seconds {:.3}",
+            counters.idle.as_secs_f64()
+        )?;
+        header(
+            f,
+            "xidlehook_fullscreen_suppressions_total",
+            "counter",
+            "How many times a fullscreen window kept a timer from running",
+        )?;
+        writeln!(
+            f,
+            "xidlehook_fullscreen_suppressions_total {}",
+            counters.fullscreen_suppressions
+        )?;
+        header(
+            f,
+            "xidlehook_x_reconnects_total",
+            "counter",
+            "How many times the X connection was reconnected",
+        )?;
+        writeln!(f, "xidlehook_x_reconnects_total {}", counters.x_reconnects)
+    }
+}
+
+fn header(f: &mut fmt::Formatter, name: &str, kind: &str, help: &str) -> fmt::Result {
+    writeln!(f, "# HELP {} {}", name, help)?;
+    writeln!(f, "# TYPE {} {}", name, kind)
+}
+
+/// A module counting the timers it keeps from running, see
+/// `Metrics::count_suppressions`
+pub struct Suppressions<M: Module> {
+    module: M,
+    metrics: Metrics,
+}
+impl<M: Module> Suppressions<M> {
+    /// Returns the module inside
+    pub fn inner(&self) -> &M {
+        &self.module
+    }
+}
+impl<M: Module> Module for Suppressions<M> {
+    fn pre_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        let progress = self.module.pre_timer(timer)?;
+        if progress != Progress::Continue {
+            let mut counters = self.metrics.0.borrow_mut();
+            counters.fullscreen_suppressions = counters.fullscreen_suppressions.saturating_add(1);
+        }
+        Ok(progress)
+    }
+    fn peek_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.module.peek_timer(timer)
+    }
+    fn post_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.module.post_timer(timer)
+    }
+    fn poll_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.module.poll_timer(timer)
+    }
+    fn warning(&mut self, error: &Error) -> Result<()> {
+        self.module.warning(error)
+    }
+    fn reset(&mut self, prior_idle: Duration) -> Result<()> {
+        self.module.reset(prior_idle)
+    }
+    fn on_active(&mut self, prior_idle: Duration) -> Result<()> {
+        self.module.on_active(prior_idle)
+    }
+    fn on_shutdown(&mut self) -> Result<()> {
+        self.module.on_shutdown()
+    }
+    fn poll_interval(&self) -> Option<Duration> {
+        self.module.poll_interval()
+    }
+    fn describe(&self) -> ModuleDescription {
+        self.module.describe()
+    }
+}
+impl<M: Module + fmt::Debug> fmt::Debug for Suppressions<M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Suppressions").field(&self.module).finish()
+    }
+}
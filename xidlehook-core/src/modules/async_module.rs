@@ -0,0 +1,240 @@
+//! Modules whose checks before a timer are asynchronous, such as ones
+//! asking something over the network or D-Bus, so that they don't
+//! stall the main loop while they wait.
+//!
+//! The futures aren't `Send`, like the rest of xidlehook, so they
+//! need to run on a single thread: a current-thread tokio runtime (or
+//! a `LocalSet`), or `block_on` of async-std or `futures`. The main
+//! loop is `Xidlehook::main_awaited`.
+
+use crate::{modules::ModuleDescription, Error, Module, Progress, Result, TimerInfo};
+
+use std::{collections::HashMap, fmt, time::Duration};
+
+use async_trait::async_trait;
+use futures::future;
+
+/// A module which decides if a timer should be allowed to execute
+/// asynchronously. Everything else is up to its `Module`
+/// implementation, of which `pre_timer` isn't used.
+#[async_trait(?Send)]
+pub trait AsyncModule: Module {
+    /// Decides if a timer should be allowed to execute. It may be
+    /// asked about timers that end up not being due yet, so it
+    /// shouldn't count on the timer going off.
+    async fn pre_timer(&mut self, _timer: TimerInfo) -> Result<Progress> {
+        Ok(Progress::Continue)
+    }
+}
+
+impl Module for Box<dyn AsyncModule> {
+    fn pre_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        Module::pre_timer(&mut **self, timer)
+    }
+    fn peek_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        (**self).peek_timer(timer)
+    }
+    fn post_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        (**self).post_timer(timer)
+    }
+    fn poll_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        (**self).poll_timer(timer)
+    }
+    fn warning(&mut self, error: &Error) -> Result<()> {
+        (**self).warning(error)
+    }
+    fn reset(&mut self, prior_idle: Duration) -> Result<()> {
+        (**self).reset(prior_idle)
+    }
+    fn on_active(&mut self, prior_idle: Duration) -> Result<()> {
+        (**self).on_active(prior_idle)
+    }
+    fn on_shutdown(&mut self) -> Result<()> {
+        (**self).on_shutdown()
+    }
+    fn poll_interval(&self) -> Option<Duration> {
+        (**self).poll_interval()
+    }
+    fn describe(&self) -> ModuleDescription {
+        (**self).describe()
+    }
+}
+#[async_trait(?Send)]
+impl AsyncModule for Box<dyn AsyncModule> {
+    async fn pre_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        AsyncModule::pre_timer(&mut **self, timer).await
+    }
+}
+
+/// Both modules are asked at the same time, but the first one's
+/// decision still comes first, like with the synchronous version
+#[async_trait(?Send)]
+impl<A, B> AsyncModule for (A, B)
+where
+    A: AsyncModule,
+    B: AsyncModule,
+{
+    async fn pre_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        let (first, second) = future::join(
+            AsyncModule::pre_timer(&mut self.0, timer),
+            AsyncModule::pre_timer(&mut self.1, timer),
+        )
+        .await;
+        first_verdict(vec![first, second])
+    }
+}
+
+/// All modules are asked at the same time, but the decisions are
+/// taken in order, like with the synchronous version
+#[async_trait(?Send)]
+impl<M: AsyncModule> AsyncModule for Vec<M> {
+    async fn pre_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        let verdicts = future::join_all(
+            self.iter_mut()
+                .map(|module| AsyncModule::pre_timer(module, timer)),
+        )
+        .await;
+        first_verdict(verdicts)
+    }
+}
+
+/// Returns the first decision other than `Progress::Continue`, or the
+/// first error before that
+fn first_verdict(verdicts: Vec<Result<Progress>>) -> Result<Progress> {
+    for verdict in verdicts {
+        if verdict.as_ref().map_or(true, |&v| v != Progress::Continue) {
+            return verdict;
+        }
+    }
+    Ok(Progress::Continue)
+}
+
+/// Lets a synchronous module, like `NotWhenFullscreen`, be used
+/// where an asynchronous one is expected. Its checks block the
+/// thread while they run, like they do in the synchronous main loop.
+#[derive(Clone, Debug)]
+pub struct Blocking<M: Module>(pub M);
+
+impl<M: Module> Module for Blocking<M> {
+    fn pre_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.0.pre_timer(timer)
+    }
+    fn peek_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.0.peek_timer(timer)
+    }
+    fn post_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.0.post_timer(timer)
+    }
+    fn poll_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.0.poll_timer(timer)
+    }
+    fn warning(&mut self, error: &Error) -> Result<()> {
+        self.0.warning(error)
+    }
+    fn reset(&mut self, prior_idle: Duration) -> Result<()> {
+        self.0.reset(prior_idle)
+    }
+    fn on_active(&mut self, prior_idle: Duration) -> Result<()> {
+        self.0.on_active(prior_idle)
+    }
+    fn on_shutdown(&mut self) -> Result<()> {
+        self.0.on_shutdown()
+    }
+    fn poll_interval(&self) -> Option<Duration> {
+        self.0.poll_interval()
+    }
+    fn describe(&self) -> ModuleDescription {
+        self.0.describe()
+    }
+}
+#[async_trait(?Send)]
+impl<M: Module> AsyncModule for Blocking<M> {
+    async fn pre_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.0.pre_timer(timer)
+    }
+}
+
+/// Turns an asynchronous module into a synchronous one for
+/// `Xidlehook`, by awaiting its decisions about the timers that may be
+/// due before each poll, see `Xidlehook::poll_async`. If it's asked
+/// about a timer it had no decision for, that's an error, which is
+/// handled like any other error of a module.
+pub struct Awaited<A: AsyncModule> {
+    module: A,
+    verdicts: HashMap<usize, Result<Progress>>,
+}
+impl<A: AsyncModule> Awaited<A> {
+    /// Awaits the decisions of this module
+    pub fn new(module: A) -> Self {
+        Self {
+            module,
+            verdicts: HashMap::new(),
+        }
+    }
+    /// Returns the module inside
+    pub fn inner(&self) -> &A {
+        &self.module
+    }
+    /// Returns the module inside, mutably
+    pub fn inner_mut(&mut self) -> &mut A {
+        &mut self.module
+    }
+    /// Asks the module about these timers, forgetting any earlier
+    /// decisions
+    pub async fn prefetch<I>(&mut self, timers: I)
+    where
+        I: IntoIterator<Item = TimerInfo>,
+    {
+        self.verdicts.clear();
+        for timer in timers {
+            let verdict = AsyncModule::pre_timer(&mut self.module, timer).await;
+            self.verdicts.insert(timer.index, verdict);
+        }
+    }
+}
+impl<A: AsyncModule> Module for Awaited<A> {
+    fn pre_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.verdicts.remove(&timer.index).unwrap_or_else(|| {
+            Err(format!("no decision was awaited for timer {}", timer.index).into())
+        })
+    }
+    fn peek_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        // Only the decisions already awaited are known, without taking them
+        match self.verdicts.get(&timer.index) {
+            Some(Ok(progress)) => Ok(*progress),
+            _ => Ok(Progress::Continue),
+        }
+    }
+    fn post_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.module.post_timer(timer)
+    }
+    fn poll_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.module.poll_timer(timer)
+    }
+    fn warning(&mut self, error: &Error) -> Result<()> {
+        self.module.warning(error)
+    }
+    fn reset(&mut self, prior_idle: Duration) -> Result<()> {
+        self.module.reset(prior_idle)
+    }
+    fn on_active(&mut self, prior_idle: Duration) -> Result<()> {
+        self.module.on_active(prior_idle)
+    }
+    fn on_shutdown(&mut self) -> Result<()> {
+        self.module.on_shutdown()
+    }
+    fn poll_interval(&self) -> Option<Duration> {
+        self.module.poll_interval()
+    }
+    fn describe(&self) -> ModuleDescription {
+        self.module.describe()
+    }
+}
+impl<A: AsyncModule + fmt::Debug> fmt::Debug for Awaited<A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Awaited")
+            .field("module", &self.module)
+            .field("verdicts", &self.verdicts.len())
+            .finish()
+    }
+}
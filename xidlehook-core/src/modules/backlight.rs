@@ -0,0 +1,222 @@
+//! Dims the screen by lowering the brightness of its backlight when a
+//! timer goes off, rather than through a command, and brings it back
+//! when the user is active again. The backlights are found in
+//! `/sys/class/backlight`.
+//!
+//! Changing the brightness takes write access to its `brightness`
+//! file, which only root has by default. See `UDEV_RULE` for a rule
+//! giving it to the `video` group.
+
+use crate::{modules::ModuleDescription, Module, Progress, Result, TimerInfo};
+
+use std::{
+    convert::TryFrom,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use log::{debug, info};
+
+/// A udev rule letting members of the `video` group change the
+/// brightness of all backlights, for `/etc/udev/rules.d`
+pub const UDEV_RULE: &str = "ACTION==\"add\", SUBSYSTEM==\"backlight\", \
+     RUN+=\"/bin/chgrp video /sys/class/backlight/%k/brightness\", \
+     RUN+=\"/bin/chmod g+w /sys/class/backlight/%k/brightness\"";
+
+/// The kinds of backlights in the order they're preferred in, like
+/// the kernel's documentation recommends: the firmware ones know best
+/// how to drive the hardware.
+const TYPES: [&str; 3] = ["firmware", "platform", "raw"];
+
+/// See the module-level documentation. It dims the screen when the
+/// first timer goes off, unless told otherwise with `with_timer`. A
+/// screen that's already darker than the dim level is left alone.
+pub struct Backlight {
+    sysfs_dir: PathBuf,
+    device: Option<String>,
+    timer: usize,
+    level: u32,
+    /// The brightness before dimming, while dimmed
+    saved: Option<(PathBuf, u32)>,
+}
+impl Backlight {
+    /// Dim the screen to 10% of its maximum brightness
+    pub fn new() -> Self {
+        Self {
+            sysfs_dir: PathBuf::from("/sys/class/backlight"),
+            device: None,
+            timer: 0,
+            level: 10,
+            saved: None,
+        }
+    }
+    /// Look for backlights in this directory instead of
+    /// `/sys/class/backlight`
+    pub fn with_sysfs_dir(mut self, sysfs_dir: impl Into<PathBuf>) -> Self {
+        self.sysfs_dir = sysfs_dir.into();
+        self
+    }
+    /// Use the backlight with this name, like `intel_backlight`, rather
+    /// than picking one
+    pub fn with_device(mut self, device: impl Into<String>) -> Self {
+        self.device = Some(device.into());
+        self
+    }
+    /// Dim the screen when the timer at this index goes off
+    pub fn with_timer(mut self, timer: usize) -> Self {
+        self.timer = timer;
+        self
+    }
+    /// Dim the screen to this percentage of its maximum brightness, up
+    /// to 100
+    pub fn with_level(mut self, percent: u32) -> Self {
+        self.level = percent.min(100);
+        self
+    }
+    /// Returns true while the screen is dimmed
+    pub fn is_dimmed(&self) -> bool {
+        self.saved.is_some()
+    }
+    /// Returns the directory of the backlight used, such as
+    /// `/sys/class/backlight/intel_backlight`
+    pub fn device_dir(&self) -> Result<PathBuf> {
+        if let Some(ref device) = self.device {
+            let dir = self.sysfs_dir.join(device);
+            if !dir.join("brightness").is_file() {
+                return Err(format!(
+                    "there's no backlight {:?} in {}",
+                    device,
+                    self.sysfs_dir.display()
+                )
+                .into());
+            }
+            return Ok(dir);
+        }
+        find_backlight(&self.sysfs_dir)
+            .ok_or_else(|| format!("there's no backlight in {}", self.sysfs_dir.display()).into())
+    }
+    /// Lower the brightness to the dim level, remembering what it was
+    pub fn dim(&mut self) -> Result<()> {
+        if self.saved.is_some() {
+            return Ok(());
+        }
+        let dir = self.device_dir()?;
+        let brightness = read_value(&dir.join("brightness"))?;
+        let max = read_value(&dir.join("max_brightness"))?;
+        let dimmed = u64::from(max)
+            .saturating_mul(self.level.into())
+            .checked_div(100)
+            .and_then(|dimmed| u32::try_from(dimmed).ok())
+            .unwrap_or(max);
+        if brightness <= dimmed {
+            debug!(
+                "Not dimming {}, it's already at {}",
+                dir.display(),
+                brightness
+            );
+            return Ok(());
+        }
+        info!(
+            "Dimming {} from {} to {}",
+            dir.display(),
+            brightness,
+            dimmed
+        );
+        write_value(&dir.join("brightness"), dimmed)?;
+        self.saved = Some((dir, brightness));
+        Ok(())
+    }
+    /// Bring the brightness back to what it was before dimming
+    pub fn restore(&mut self) -> Result<()> {
+        if let Some((dir, brightness)) = self.saved.take() {
+            info!("Restoring {} to {}", dir.display(), brightness);
+            write_value(&dir.join("brightness"), brightness)?;
+        }
+        Ok(())
+    }
+}
+impl Default for Backlight {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Module for Backlight {
+    fn post_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        if timer.index == self.timer {
+            self.dim()?;
+        }
+        Ok(Progress::Continue)
+    }
+    fn reset(&mut self, _prior_idle: Duration) -> Result<()> {
+        self.restore()
+    }
+    fn on_shutdown(&mut self) -> Result<()> {
+        self.restore()
+    }
+    fn describe(&self) -> ModuleDescription {
+        let description = ModuleDescription::new("dim-backlight")
+            .with("timer", self.timer)
+            .with("level", u64::from(self.level));
+        match self.device {
+            Some(ref device) => description.with("device", &**device),
+            None => description,
+        }
+    }
+}
+impl fmt::Debug for Backlight {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Backlight({:?}, {}%)", self.device, self.level)
+    }
+}
+
+/// Returns the preferred backlight in this directory, see `TYPES`
+fn find_backlight(sysfs_dir: &Path) -> Option<PathBuf> {
+    let entries = match fs::read_dir(sysfs_dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            debug!("Couldn't list {}: {}", sysfs_dir.display(), err);
+            return None;
+        },
+    };
+    let mut backlights: Vec<(usize, PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|dir| dir.join("brightness").is_file())
+        .map(|dir| {
+            let kind = fs::read_to_string(dir.join("type")).unwrap_or_default();
+            let rank = TYPES
+                .iter()
+                .position(|&name| name == kind.trim())
+                .unwrap_or(TYPES.len());
+            (rank, dir)
+        })
+        .collect();
+    backlights.sort();
+    debug!("Found backlights: {:?}", backlights);
+    backlights.into_iter().next().map(|(_, dir)| dir)
+}
+
+fn read_value(path: &Path) -> Result<u32> {
+    let value = fs::read_to_string(path)
+        .map_err(|err| format!("couldn't read {}: {}", path.display(), err))?;
+    Ok(value
+        .trim()
+        .parse()
+        .map_err(|err| format!("invalid value in {}: {}", path.display(), err))?)
+}
+
+fn write_value(path: &Path, value: u32) -> Result<()> {
+    fs::write(path, value.to_string()).map_err(|err| {
+        if err.kind() == io::ErrorKind::PermissionDenied {
+            format!(
+                "not allowed to change {}. A udev rule like this lets the video group change it: {}",
+                path.display(),
+                UDEV_RULE
+            )
+        } else {
+            format!("couldn't change {}: {}", path.display(), err)
+        }
+    })?;
+    Ok(())
+}
@@ -0,0 +1,152 @@
+//! A module combining the decisions of several others, so that they
+//! can keep timers from running together rather than each on their
+//! own. `Combine`s can be nested for more involved logic.
+
+use crate::{modules::ModuleDescription, Error, Module, Progress, Result, TimerInfo};
+
+use std::{fmt, time::Duration};
+
+/// How the decisions of the modules are combined
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Combinator {
+    /// Keep timers from running if any of the modules does, like
+    /// `Vec<M>`. The first decision other than `Progress::Continue`
+    /// is used, and the modules after it aren't asked.
+    Any,
+    /// Keep timers from running only if all of the modules do. The
+    /// first module's decision is used, and the modules after one
+    /// saying `Progress::Continue` aren't asked.
+    All,
+}
+impl Combinator {
+    /// The name of this combinator in lower case
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Any => "any",
+            Self::All => "all",
+        }
+    }
+}
+
+/// See the module-level documentation. Without any modules, it
+/// always says `Progress::Continue`. Errors are returned right away.
+pub struct Combine {
+    combinator: Combinator,
+    modules: Vec<Box<dyn Module>>,
+}
+impl Combine {
+    /// Combine these modules
+    pub fn new(combinator: Combinator, modules: Vec<Box<dyn Module>>) -> Self {
+        Self {
+            combinator,
+            modules,
+        }
+    }
+    /// Returns an empty combination of `Combinator::Any`
+    pub fn any() -> Self {
+        Self::new(Combinator::Any, Vec::new())
+    }
+    /// Returns an empty combination of `Combinator::All`
+    pub fn all() -> Self {
+        Self::new(Combinator::All, Vec::new())
+    }
+    /// Add a module to the end
+    pub fn push<M: Module + 'static>(&mut self, module: M) {
+        self.modules.push(Box::new(module));
+    }
+    /// Add a module to the end. This is the chainable version of
+    /// `push`.
+    pub fn with<M: Module + 'static>(mut self, module: M) -> Self {
+        self.push(module);
+        self
+    }
+    /// How the decisions are combined
+    pub fn combinator(&self) -> Combinator {
+        self.combinator
+    }
+    /// The number of modules
+    pub fn len(&self) -> usize {
+        self.modules.len()
+    }
+    /// Returns true if there are no modules
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
+
+    fn decide<F>(&mut self, mut ask: F) -> Result<Progress>
+    where
+        F: FnMut(&mut Box<dyn Module>) -> Result<Progress>,
+    {
+        match self.combinator {
+            Combinator::Any => {
+                for module in &mut self.modules {
+                    let status = ask(module)?;
+                    if status != Progress::Continue {
+                        return Ok(status);
+                    }
+                }
+                Ok(Progress::Continue)
+            },
+            Combinator::All => {
+                let mut first = None;
+                for module in &mut self.modules {
+                    let status = ask(module)?;
+                    if status == Progress::Continue {
+                        return Ok(Progress::Continue);
+                    }
+                    first.get_or_insert(status);
+                }
+                Ok(first.unwrap_or(Progress::Continue))
+            },
+        }
+    }
+}
+impl Module for Combine {
+    fn pre_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.decide(|module| module.pre_timer(timer))
+    }
+    fn peek_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.decide(|module| module.peek_timer(timer))
+    }
+    fn post_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.decide(|module| module.post_timer(timer))
+    }
+    fn poll_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.decide(|module| module.poll_timer(timer))
+    }
+    fn warning(&mut self, error: &Error) -> Result<()> {
+        self.modules.warning(error)
+    }
+    fn reset(&mut self, prior_idle: Duration) -> Result<()> {
+        self.modules.reset(prior_idle)
+    }
+    fn on_active(&mut self, prior_idle: Duration) -> Result<()> {
+        self.modules.on_active(prior_idle)
+    }
+    fn on_shutdown(&mut self) -> Result<()> {
+        self.modules.on_shutdown()
+    }
+    fn poll_interval(&self) -> Option<Duration> {
+        self.modules.poll_interval()
+    }
+    fn describe(&self) -> ModuleDescription {
+        let kinds: Vec<String> = self
+            .modules
+            .iter()
+            .map(|module| module.describe().kind)
+            .collect();
+        ModuleDescription::new("combine")
+            .with("combinator", self.combinator.name())
+            .with("modules", kinds)
+    }
+}
+impl fmt::Debug for Combine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Combine({:?}, {} modules)",
+            self.combinator,
+            self.modules.len()
+        )
+    }
+}
@@ -0,0 +1,125 @@
+//! Keeps timers from running while a boolean D-Bus property is true.
+//! Desktop environments expose things like presentation mode or
+//! inhibits this way, so one module covers many of them, as well as
+//! custom services.
+
+use crate::{modules::ModuleDescription, Module, Progress, Result, TimerInfo};
+
+use std::{
+    convert::TryFrom,
+    fmt,
+    time::{Duration, Instant},
+};
+
+use log::{debug, warn};
+use zbus::{blocking::Connection, zvariant::OwnedValue};
+
+/// How long a value is reused unless configured otherwise
+pub const DEFAULT_CACHE_TIME: Duration = Duration::from_secs(1);
+
+/// Where to find a property on the bus
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DbusProperty {
+    /// The well-known name of the service, like `org.gnome.Shell`
+    pub bus_name: String,
+    /// The object path of the object bearing the property
+    pub path: String,
+    /// The interface of the property
+    pub interface: String,
+    /// The name of the property
+    pub property: String,
+}
+
+/// See the module-level documentation. A service or property which
+/// isn't there, or isn't a boolean, counts as false.
+pub struct NotWhenDbusProperty {
+    conn: Connection,
+    property: DbusProperty,
+    cache_time: Duration,
+    cached: Option<(Instant, bool)>,
+}
+impl NotWhenDbusProperty {
+    /// Read the property off the session bus
+    pub fn new(property: DbusProperty) -> Result<Self> {
+        Ok(Self::with_connection(Connection::session()?, property))
+    }
+    /// Read the property using an existing bus connection
+    pub fn with_connection(conn: Connection, property: DbusProperty) -> Self {
+        Self {
+            conn,
+            property,
+            cache_time: DEFAULT_CACHE_TIME,
+            cached: None,
+        }
+    }
+    /// Reuse the value of the property for this long, rather than
+    /// asking the service before every timer
+    pub fn with_cache_time(mut self, cache_time: Duration) -> Self {
+        self.cache_time = cache_time;
+        self
+    }
+    /// Returns the current value of the property
+    pub fn get(&mut self) -> bool {
+        if let Some((fetched, value)) = self.cached {
+            if fetched.elapsed() < self.cache_time {
+                return value;
+            }
+        }
+        let value = self.fetch();
+        self.cached = Some((Instant::now(), value));
+        value
+    }
+
+    fn fetch(&self) -> bool {
+        let reply = self.conn.call_method(
+            Some(&*self.property.bus_name),
+            &*self.property.path,
+            Some("org.freedesktop.DBus.Properties"),
+            "Get",
+            &(&*self.property.interface, &*self.property.property),
+        );
+        let value = reply.and_then(|reply| reply.body().deserialize::<OwnedValue>());
+        match value {
+            Ok(value) => match bool::try_from(value) {
+                Ok(value) => value,
+                Err(err) => {
+                    warn!(
+                        "D-Bus property {:?} isn't a boolean: {}",
+                        self.property, err
+                    );
+                    false
+                },
+            },
+            Err(err) => {
+                debug!("Couldn't read D-Bus property {:?}: {}", self.property, err);
+                false
+            },
+        }
+    }
+}
+impl Module for NotWhenDbusProperty {
+    fn pre_timer(&mut self, _timer: TimerInfo) -> Result<Progress> {
+        if self.get() {
+            Ok(Progress::Abort)
+        } else {
+            Ok(Progress::Continue)
+        }
+    }
+    fn peek_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.pre_timer(timer)
+    }
+    fn describe(&self) -> ModuleDescription {
+        let cache_ms = u64::try_from(self.cache_time.as_millis()).unwrap_or(u64::MAX);
+        ModuleDescription::new("not-when-dbus-property")
+            .with("bus_name", &*self.property.bus_name)
+            .with("path", &*self.property.path)
+            .with("interface", &*self.property.interface)
+            .with("property", &*self.property.property)
+            .with("cache_ms", cache_ms)
+    }
+}
+impl fmt::Debug for NotWhenDbusProperty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "NotWhenDbusProperty({:?})", self.property)
+    }
+}
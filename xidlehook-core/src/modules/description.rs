@@ -0,0 +1,103 @@
+//! A view of a module and its settings, such as for a config UI to
+//! show the live configuration. Settings which may hold secrets, like
+//! commands with passwords in them, are redacted.
+
+use std::{collections::BTreeMap, convert::TryFrom};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// What redacted settings show instead of their value
+pub const REDACTED: &str = "<redacted>";
+
+/// The value of a single setting
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum Setting {
+    /// A switch
+    Bool(bool),
+    /// A number, such as a limit
+    Number(u64),
+    /// A single piece of text
+    Text(String),
+    /// A list, such as of exceptions
+    List(Vec<String>),
+}
+impl From<bool> for Setting {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+impl From<u64> for Setting {
+    fn from(value: u64) -> Self {
+        Self::Number(value)
+    }
+}
+impl From<usize> for Setting {
+    fn from(value: usize) -> Self {
+        Self::Number(u64::try_from(value).unwrap_or(u64::MAX))
+    }
+}
+impl From<&str> for Setting {
+    fn from(value: &str) -> Self {
+        Self::Text(value.into())
+    }
+}
+impl From<String> for Setting {
+    fn from(value: String) -> Self {
+        Self::Text(value)
+    }
+}
+impl From<Vec<String>> for Setting {
+    fn from(value: Vec<String>) -> Self {
+        Self::List(value)
+    }
+}
+impl From<&[String]> for Setting {
+    fn from(value: &[String]) -> Self {
+        Self::List(value.to_vec())
+    }
+}
+
+/// See the module-level documentation
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct ModuleDescription {
+    /// The name the module is registered under. This is filled in by
+    /// `ModuleList::describe_all`, and empty otherwise.
+    pub name: String,
+    /// What kind of module this is, such as `not-when-fullscreen`
+    pub kind: String,
+    /// Whether the module can currently keep timers from running. A
+    /// `Toggle` that's off can't.
+    pub enabled: bool,
+    /// The settings, by name
+    pub settings: BTreeMap<String, Setting>,
+}
+impl ModuleDescription {
+    /// Returns an enabled module of this kind, without any settings
+    pub fn new(kind: impl Into<String>) -> Self {
+        Self {
+            name: String::new(),
+            kind: kind.into(),
+            enabled: true,
+            settings: BTreeMap::new(),
+        }
+    }
+    /// Set whether the module is enabled
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+    /// Add a setting
+    pub fn with(mut self, key: impl Into<String>, value: impl Into<Setting>) -> Self {
+        self.settings.insert(key.into(), value.into());
+        self
+    }
+    /// Add a setting whose value is a secret, which shows as
+    /// `REDACTED`
+    pub fn with_secret(self, key: impl Into<String>) -> Self {
+        self.with(key, REDACTED)
+    }
+}
@@ -0,0 +1,217 @@
+//! Provides the `org.freedesktop.ScreenSaver` D-Bus service, which
+//! media players and browsers use to keep the screen from locking
+//! while something plays, and keeps timers from running while any of
+//! them does.
+//!
+//! An inhibitor is held until the program that took it releases it
+//! with `UnInhibit`, or leaves the bus, such as by crashing.
+
+use crate::{modules::ModuleDescription, Module, Progress, Result, TimerInfo};
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, Mutex, MutexGuard},
+    thread,
+};
+
+use log::{debug, info, warn};
+use zbus::{
+    blocking::{fdo::DBusProxy, Connection},
+    message::Header,
+};
+
+/// The well-known name of the service
+pub const BUS_NAME: &str = "org.freedesktop.ScreenSaver";
+/// The object paths the service is at. Some programs use the shorter
+/// one.
+pub const OBJECT_PATHS: &[&str] = &["/org/freedesktop/ScreenSaver", "/ScreenSaver"];
+
+/// A program keeping timers from running
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Inhibitor {
+    /// What the program was given to release it with
+    pub cookie: u32,
+    /// The unique bus name of the program
+    pub sender: String,
+    /// The name the program gave itself, like `firefox`
+    pub application: String,
+    /// Why the program keeps timers from running, like `video-playing`
+    pub reason: String,
+}
+
+#[derive(Default)]
+struct Inhibitors {
+    next_cookie: u32,
+    held: BTreeMap<u32, Inhibitor>,
+}
+
+type Shared = Arc<Mutex<Inhibitors>>;
+
+fn lock(inhibitors: &Shared) -> MutexGuard<'_, Inhibitors> {
+    // Nothing can be left half-updated, so a poisoned lock is fine
+    inhibitors
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// The object served on the bus
+struct ScreenSaver(Shared);
+#[zbus::interface(name = "org.freedesktop.ScreenSaver")]
+impl ScreenSaver {
+    fn inhibit(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        application_name: String,
+        reason_for_inhibit: String,
+    ) -> u32 {
+        let sender = header
+            .sender()
+            .map(|name| name.to_string())
+            .unwrap_or_default();
+        let mut inhibitors = lock(&self.0);
+        let cookie = loop {
+            inhibitors.next_cookie = inhibitors.next_cookie.wrapping_add(1);
+            let cookie = inhibitors.next_cookie;
+            if cookie != 0 && !inhibitors.held.contains_key(&cookie) {
+                break cookie;
+            }
+        };
+        info!(
+            "{} ({}) keeps timers from running: {}",
+            application_name, sender, reason_for_inhibit
+        );
+        inhibitors.held.insert(
+            cookie,
+            Inhibitor {
+                cookie,
+                sender,
+                application: application_name,
+                reason: reason_for_inhibit,
+            },
+        );
+        cookie
+    }
+    #[zbus(name = "UnInhibit")]
+    fn un_inhibit(&self, #[zbus(header)] header: Header<'_>, cookie: u32) {
+        let sender = header
+            .sender()
+            .map(|name| name.to_string())
+            .unwrap_or_default();
+        let mut inhibitors = lock(&self.0);
+        // Programs may only release their own inhibitors
+        if inhibitors
+            .held
+            .get(&cookie)
+            .is_some_and(|inhibitor| inhibitor.sender == sender)
+        {
+            if let Some(inhibitor) = inhibitors.held.remove(&cookie) {
+                info!(
+                    "{} ({}) released its inhibitor",
+                    inhibitor.application, sender
+                );
+            }
+        } else {
+            debug!("{} tried to release unknown inhibitor {}", sender, cookie);
+        }
+    }
+}
+
+/// See the module-level documentation
+pub struct NotWhenInhibited {
+    conn: Connection,
+    inhibitors: Shared,
+}
+impl NotWhenInhibited {
+    /// Provide the service on the session bus. This fails if another
+    /// program, such as the desktop environment, already does.
+    pub fn new() -> Result<Self> {
+        Self::with_connection(Connection::session()?)
+    }
+    /// Provide the service using an existing bus connection. See
+    /// `new`.
+    pub fn with_connection(conn: Connection) -> Result<Self> {
+        let inhibitors = Shared::default();
+
+        // Release the inhibitors of programs leaving the bus. This is
+        // subscribed to before anyone can take one.
+        let signals = DBusProxy::new(&conn)?.receive_name_owner_changed()?;
+        {
+            let inhibitors = Arc::clone(&inhibitors);
+            thread::spawn(move || {
+                for signal in signals {
+                    let args = match signal.args() {
+                        Ok(args) => args,
+                        Err(err) => {
+                            warn!("Malformed NameOwnerChanged signal: {}", err);
+                            continue;
+                        },
+                    };
+                    if args.new_owner().is_some() {
+                        continue;
+                    }
+                    let name = args.name().as_str();
+                    lock(&inhibitors).held.retain(|_, inhibitor| {
+                        let keep = inhibitor.sender != name;
+                        if !keep {
+                            info!("{} ({}) left the bus", inhibitor.application, name);
+                        }
+                        keep
+                    });
+                }
+            });
+        }
+
+        for path in OBJECT_PATHS {
+            conn.object_server()
+                .at(*path, ScreenSaver(Arc::clone(&inhibitors)))?;
+        }
+        conn.request_name(BUS_NAME)
+            .map_err(|err| format!("can't provide {}: {}", BUS_NAME, err))?;
+        Ok(Self { conn, inhibitors })
+    }
+    /// Returns the inhibitors held right now, by cookie
+    pub fn inhibitors(&self) -> Vec<Inhibitor> {
+        lock(&self.inhibitors).held.values().cloned().collect()
+    }
+}
+impl Module for NotWhenInhibited {
+    fn pre_timer(&mut self, _timer: TimerInfo) -> Result<Progress> {
+        let inhibitors = lock(&self.inhibitors);
+        if inhibitors.held.is_empty() {
+            Ok(Progress::Continue)
+        } else {
+            debug!(
+                "Not running timers, {} inhibitors held",
+                inhibitors.held.len()
+            );
+            Ok(Progress::Abort)
+        }
+    }
+    fn peek_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.pre_timer(timer)
+    }
+    fn on_shutdown(&mut self) -> Result<()> {
+        // Let the desktop environment or another instance take over
+        self.conn.release_name(BUS_NAME)?;
+        Ok(())
+    }
+    fn describe(&self) -> ModuleDescription {
+        let held: Vec<String> = lock(&self.inhibitors)
+            .held
+            .values()
+            .map(|inhibitor| inhibitor.application.clone())
+            .collect();
+        ModuleDescription::new("not-when-inhibited").with("held_by", held)
+    }
+}
+impl fmt::Debug for NotWhenInhibited {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "NotWhenInhibited({:?}, {} inhibitors)",
+            self.conn.unique_name(),
+            lock(&self.inhibitors).held.len()
+        )
+    }
+}
@@ -0,0 +1,171 @@
+//! Runs a command when the laptop lid closes, such as to lock or
+//! suspend, and optionally another when it opens again, regardless of
+//! the idle time. The lid is found in `/proc/acpi/button/lid`.
+//!
+//! Timers only ask modules whether to run once they're due, so this
+//! isn't polled by `Xidlehook`: call `LidSwitch::check` every so often,
+//! like the daemon does every `LidSwitch::INTERVAL`.
+
+use crate::Result;
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    process::{Child, Command},
+    time::Duration,
+};
+
+use log::{debug, info, trace};
+
+/// Whether the lid is open or closed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LidState {
+    /// The lid is open
+    Open,
+    /// The lid is closed
+    Closed,
+}
+impl LidState {
+    /// Parse the contents of a lid's `state` file, like
+    /// `state:      closed`
+    pub fn parse(contents: &str) -> Option<Self> {
+        match contents.split_whitespace().last()? {
+            "open" => Some(Self::Open),
+            "closed" => Some(Self::Closed),
+            _ => None,
+        }
+    }
+}
+
+/// See the module-level documentation. Machines without a lid, like
+/// most desktops, never see it close.
+pub struct LidSwitch {
+    proc_dir: PathBuf,
+    on_close: Option<Command>,
+    on_open: Option<Command>,
+    /// The `state` file of the lid, once it's found
+    lid: Option<Option<PathBuf>>,
+    last: Option<LidState>,
+    child: Option<Child>,
+}
+impl LidSwitch {
+    /// How often the daemon looks at the lid
+    pub const INTERVAL: Duration = Duration::from_secs(1);
+
+    /// Watch the lid without running anything yet
+    pub fn new() -> Self {
+        Self {
+            proc_dir: PathBuf::from("/proc/acpi/button/lid"),
+            on_close: None,
+            on_open: None,
+            lid: None,
+            last: None,
+            child: None,
+        }
+    }
+    /// Look for the lid in this directory instead of
+    /// `/proc/acpi/button/lid`
+    pub fn with_proc_dir(mut self, proc_dir: impl Into<PathBuf>) -> Self {
+        self.proc_dir = proc_dir.into();
+        self.lid = None;
+        self
+    }
+    /// Run this command when the lid closes
+    pub fn with_on_close(self, cmd: Command) -> Self {
+        Self {
+            on_close: Some(cmd),
+            ..self
+        }
+    }
+    /// Run this command when the lid opens again
+    pub fn with_on_open(self, cmd: Command) -> Self {
+        Self {
+            on_open: Some(cmd),
+            ..self
+        }
+    }
+    /// Returns the state of the lid right now, or `None` if there's no
+    /// lid to be found
+    pub fn state(&mut self) -> Option<LidState> {
+        let proc_dir = &self.proc_dir;
+        let lid = self
+            .lid
+            .get_or_insert_with(|| find_lid(proc_dir))
+            .as_ref()?;
+        match fs::read_to_string(lid) {
+            Ok(contents) => LidState::parse(&contents),
+            Err(err) => {
+                trace!("Couldn't read {}: {}", lid.display(), err);
+                // The lid may have been replaced, look again next time
+                self.lid = None;
+                None
+            },
+        }
+    }
+    /// Look at the lid, and run the matching command if it closed or
+    /// opened since last time. The first look only learns the state.
+    /// Returns the new state after a change. The last command is reaped
+    /// here once it exits, rather than staying a zombie until the next
+    /// change.
+    pub fn check(&mut self) -> Result<Option<LidState>> {
+        // An error means someone else reaped it
+        if let Some(ref mut child) = self.child {
+            if !matches!(child.try_wait(), Ok(None)) {
+                self.child = None;
+            }
+        }
+        let state = match self.state() {
+            Some(state) => state,
+            None => return Ok(None),
+        };
+        let last = self.last.replace(state);
+        if last.is_none_or(|last| last == state) {
+            return Ok(None);
+        }
+
+        info!("The lid is now {:?}", state);
+        let cmd = match state {
+            LidState::Closed => self.on_close.as_mut(),
+            LidState::Open => self.on_open.as_mut(),
+        };
+        if let Some(cmd) = cmd {
+            // Like `OnResume`, a command still running from the last
+            // change isn't piled up on
+            if self.child.is_some() {
+                debug!("The lid command is still running, not running another");
+                return Ok(Some(state));
+            }
+            self.child = Some(cmd.spawn()?);
+        }
+        Ok(Some(state))
+    }
+}
+impl Default for LidSwitch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl fmt::Debug for LidSwitch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "LidSwitch({})", self.proc_dir.display())
+    }
+}
+
+/// Find the `state` file of the first lid in this directory
+fn find_lid(proc_dir: &Path) -> Option<PathBuf> {
+    let entries = match fs::read_dir(proc_dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            debug!("Couldn't list {}: {}", proc_dir.display(), err);
+            return None;
+        },
+    };
+    let mut lids: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path().join("state"))
+        .filter(|state| state.is_file())
+        .collect();
+    lids.sort();
+    debug!("Found lid switches: {:?}", lids);
+    lids.into_iter().next()
+}
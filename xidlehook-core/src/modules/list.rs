@@ -0,0 +1,217 @@
+//! A list of named modules, whose evaluation order can be changed at
+//! runtime. Like `Vec<M>`, it stops at the first module that doesn't
+//! say `Progress::Continue`, so cheap checks can be put first to skip
+//! expensive ones.
+
+use crate::{modules::ModuleDescription, Error, Module, Progress, Result, TimerInfo};
+
+use std::{fmt, time::Duration};
+
+/// See the module-level documentation
+pub struct ModuleList {
+    modules: Vec<(String, Box<dyn Module>)>,
+    short_circuit: bool,
+}
+impl Default for ModuleList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl ModuleList {
+    /// An empty list
+    pub fn new() -> Self {
+        Self {
+            modules: Vec::new(),
+            short_circuit: true,
+        }
+    }
+    /// Add a module to the end of the list
+    pub fn push<M: Module + 'static>(&mut self, name: impl Into<String>, module: M) {
+        self.modules.push((name.into(), Box::new(module)));
+    }
+    /// Add a module to the end of the list. This is the chainable
+    /// version of `push`.
+    pub fn with<M: Module + 'static>(mut self, name: impl Into<String>, module: M) -> Self {
+        self.push(name, module);
+        self
+    }
+    /// Returns the names of the modules, in the order they're
+    /// evaluated in
+    pub fn names(&self) -> Vec<&str> {
+        self.modules.iter().map(|(name, _)| &**name).collect()
+    }
+    /// Returns the number of modules
+    pub fn len(&self) -> usize {
+        self.modules.len()
+    }
+    /// Returns true if there are no modules
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
+
+    /// Set whether to stop at the first module that doesn't say
+    /// `Progress::Continue` (the default). Otherwise, all modules are
+    /// evaluated, such as to let each one log its reason, and the
+    /// first answer other than `Progress::Continue` still wins.
+    pub fn set_short_circuit(&mut self, value: bool) {
+        self.short_circuit = value;
+    }
+    /// Get whether evaluation stops at the first module that doesn't
+    /// say `Progress::Continue`
+    pub fn short_circuit(&self) -> bool {
+        self.short_circuit
+    }
+
+    /// Rearrange the modules, such that the module at index `order[i]`
+    /// is evaluated as the i:th. The order must mention every index
+    /// exactly once.
+    pub fn reorder_modules(&mut self, order: Vec<usize>) -> Result<()> {
+        let mut seen = vec![false; self.modules.len()];
+        for &index in &order {
+            match seen.get_mut(index) {
+                Some(seen) if !*seen => *seen = true,
+                _ => return Err(format!("invalid module order {:?}", order).into()),
+            }
+        }
+        if order.len() != self.modules.len() {
+            return Err(format!("invalid module order {:?}", order).into());
+        }
+
+        let mut modules: Vec<_> = self.modules.drain(..).map(Some).collect();
+        self.modules = order
+            .into_iter()
+            .map(|index| modules[index].take().expect("indexes are unique"))
+            .collect();
+        Ok(())
+    }
+    /// Move the modules with these names to the front, in this order.
+    /// The rest keep their order after them.
+    pub fn reorder_by_name<S: AsRef<str>>(&mut self, names: &[S]) -> Result<()> {
+        let mut order = Vec::with_capacity(self.modules.len());
+        for name in names {
+            let name = name.as_ref();
+            match self.modules.iter().position(|(other, _)| other == name) {
+                Some(index) if !order.contains(&index) => order.push(index),
+                Some(_) => return Err(format!("module {} is listed twice", name).into()),
+                None => return Err(format!("no module named {}", name).into()),
+            }
+        }
+        for index in 0..self.modules.len() {
+            if !order.contains(&index) {
+                order.push(index);
+            }
+        }
+        self.reorder_modules(order)
+    }
+
+    /// Ask every module what it would say before this timer, in order,
+    /// regardless of `short_circuit`. Errors are passed to `warning`
+    /// and count as `Progress::Continue`, like they do for a real timer.
+    pub fn verdicts(&mut self, timer: TimerInfo) -> Result<Vec<(&str, Progress)>> {
+        let mut verdicts = Vec::with_capacity(self.modules.len());
+        for (name, module) in &mut self.modules {
+            let verdict = match module.pre_timer(timer) {
+                Ok(verdict) => verdict,
+                Err(err) => {
+                    module.warning(&err)?;
+                    Progress::Continue
+                },
+            };
+            verdicts.push((&**name, verdict));
+        }
+        Ok(verdicts)
+    }
+
+    /// Describe every module, in order, with the names they're
+    /// registered under
+    pub fn describe_all(&self) -> Vec<ModuleDescription> {
+        self.modules
+            .iter()
+            .map(|(name, module)| ModuleDescription {
+                name: name.clone(),
+                ..module.describe()
+            })
+            .collect()
+    }
+
+    /// Call `f` on every module, in order, returning the first answer
+    /// other than `Progress::Continue`
+    fn evaluate<F>(&mut self, mut f: F) -> Result<Progress>
+    where
+        F: FnMut(&mut dyn Module) -> Result<Progress>,
+    {
+        let mut result = Progress::Continue;
+        for (_, module) in &mut self.modules {
+            let progress = f(&mut **module)?;
+            if result == Progress::Continue {
+                result = progress;
+            }
+            if result != Progress::Continue && self.short_circuit {
+                break;
+            }
+        }
+        Ok(result)
+    }
+}
+impl Module for ModuleList {
+    fn pre_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.evaluate(|module| module.pre_timer(timer))
+    }
+    fn peek_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.evaluate(|module| module.peek_timer(timer))
+    }
+    fn post_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.evaluate(|module| module.post_timer(timer))
+    }
+    fn poll_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.evaluate(|module| module.poll_timer(timer))
+    }
+    fn warning(&mut self, error: &Error) -> Result<()> {
+        for (_, module) in &mut self.modules {
+            module.warning(error)?;
+        }
+        Ok(())
+    }
+    fn reset(&mut self, prior_idle: Duration) -> Result<()> {
+        for (_, module) in &mut self.modules {
+            module.reset(prior_idle)?;
+        }
+        Ok(())
+    }
+    fn on_active(&mut self, prior_idle: Duration) -> Result<()> {
+        for (_, module) in &mut self.modules {
+            module.on_active(prior_idle)?;
+        }
+        Ok(())
+    }
+    fn on_shutdown(&mut self) -> Result<()> {
+        super::first_error(
+            self.modules
+                .iter_mut()
+                .map(|(_, module)| module.on_shutdown()),
+        )
+    }
+    fn poll_interval(&self) -> Option<Duration> {
+        super::shortest(
+            self.modules
+                .iter()
+                .map(|(_, module)| module.poll_interval()),
+        )
+    }
+    fn describe(&self) -> ModuleDescription {
+        ModuleDescription::new("list")
+            .with(
+                "order",
+                self.names()
+                    .into_iter()
+                    .map(String::from)
+                    .collect::<Vec<_>>(),
+            )
+            .with("short_circuit", self.short_circuit)
+    }
+}
+impl fmt::Debug for ModuleList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ModuleList({:?})", self.names())
+    }
+}
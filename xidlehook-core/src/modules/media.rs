@@ -0,0 +1,123 @@
+//! Keeps timers from running while a media player is playing, as told
+//! by the MPRIS D-Bus interface most players implement. This is
+//! lighter than asking PulseAudio, and ignores sounds like
+//! notifications.
+
+use crate::{modules::ModuleDescription, Module, Progress, Result, TimerInfo};
+
+use std::{convert::TryFrom, fmt};
+
+use log::{debug, trace, warn};
+use zbus::{
+    blocking::{fdo::DBusProxy, Connection},
+    zvariant::OwnedValue,
+};
+
+/// What the bus names of MPRIS players start with
+pub const BUS_NAME_PREFIX: &str = "org.mpris.MediaPlayer2.";
+/// The object path players are at
+pub const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+/// The interface bearing the `PlaybackStatus` property
+pub const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+
+/// See the module-level documentation. Players which go away or don't
+/// answer while being asked count as not playing.
+pub struct NotWhenMediaPlaying {
+    conn: Connection,
+    players: Vec<String>,
+}
+impl NotWhenMediaPlaying {
+    /// Look for players on the session bus
+    pub fn new() -> Result<Self> {
+        Ok(Self::with_connection(Connection::session()?))
+    }
+    /// Look for players using an existing bus connection
+    pub fn with_connection(conn: Connection) -> Self {
+        Self {
+            conn,
+            players: Vec::new(),
+        }
+    }
+    /// Only look at players with these names, like `spotify` for
+    /// `org.mpris.MediaPlayer2.spotify`. A name also covers the
+    /// instances some players add to it, like
+    /// `org.mpris.MediaPlayer2.firefox.instance_1_42`. By default,
+    /// all players are.
+    pub fn with_players(mut self, players: Vec<String>) -> Self {
+        self.players = players;
+        self
+    }
+    /// Returns the names of the players that are playing right now
+    pub fn playing(&self) -> Result<Vec<String>> {
+        let names = DBusProxy::new(&self.conn)?.list_names()?;
+        let mut playing = Vec::new();
+        for name in names {
+            let player = match name.strip_prefix(BUS_NAME_PREFIX) {
+                Some(player) if self.is_selected(player) => player,
+                _ => continue,
+            };
+            if self.is_playing(&name) {
+                playing.push(player.to_string());
+            }
+        }
+        Ok(playing)
+    }
+
+    fn is_selected(&self, player: &str) -> bool {
+        self.players.is_empty()
+            || self.players.iter().any(|name| {
+                player
+                    .strip_prefix(&**name)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+            })
+    }
+    fn is_playing(&self, bus_name: &str) -> bool {
+        let reply = self.conn.call_method(
+            Some(bus_name),
+            OBJECT_PATH,
+            Some("org.freedesktop.DBus.Properties"),
+            "Get",
+            &(PLAYER_INTERFACE, "PlaybackStatus"),
+        );
+        let status = reply
+            .and_then(|reply| reply.body().deserialize::<OwnedValue>())
+            .map_err(crate::Error::from)
+            .and_then(|value| Ok(String::try_from(value)?));
+        match status {
+            Ok(status) => {
+                trace!("{} is {}", bus_name, status);
+                status == "Playing"
+            },
+            Err(err) => {
+                debug!("Couldn't read the status of {}: {}", bus_name, err);
+                false
+            },
+        }
+    }
+}
+impl Module for NotWhenMediaPlaying {
+    fn pre_timer(&mut self, _timer: TimerInfo) -> Result<Progress> {
+        match self.playing() {
+            Ok(playing) if playing.is_empty() => Ok(Progress::Continue),
+            Ok(playing) => {
+                debug!("Not running timers, {} is playing", playing.join(", "));
+                Ok(Progress::Abort)
+            },
+            Err(err) => {
+                warn!("Couldn't list the media players: {}", err);
+                Ok(Progress::Continue)
+            },
+        }
+    }
+    fn peek_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.pre_timer(timer)
+    }
+    fn describe(&self) -> ModuleDescription {
+        ModuleDescription::new("not-when-media-playing").with("players", &*self.players)
+    }
+}
+impl fmt::Debug for NotWhenMediaPlaying {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "NotWhenMediaPlaying({:?})", self.players)
+    }
+}
@@ -3,6 +3,8 @@
 
 use crate::{Error, Result, TimerInfo};
 
+use std::{any, time::Duration};
+
 use log::warn;
 
 /// A decision each module has to take before a timer is executed:
@@ -22,6 +24,23 @@ pub enum Progress {
     Reset,
     /// Stop the program completely. Use this sparingly.
     Stop,
+    /// Run the timer right away, however long the user has been idle,
+    /// such as to lock when the lid closes. This is only meaningful
+    /// from `Module::poll_timer`: elsewhere, it's the same as
+    /// `Continue`.
+    Trigger,
+}
+impl Progress {
+    /// The name of this decision in lower case, such as for logs
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Continue => "continue",
+            Self::Abort => "abort",
+            Self::Reset => "reset",
+            Self::Stop => "stop",
+            Self::Trigger => "trigger",
+        }
+    }
 }
 
 /// A generic module that controls whether timers should execute or
@@ -32,11 +51,37 @@ pub trait Module {
         Ok(Progress::Continue)
     }
 
-    /// Decides what happens after a timer has executed
+    /// Tells what `pre_timer` would decide right now, without changing
+    /// anything, for dry runs like `Xidlehook::simulate`. Modules only
+    /// querying something in `pre_timer` can just call it, while the
+    /// default of `Progress::Continue` leaves stateful ones out.
+    fn peek_timer(&mut self, _timer: TimerInfo) -> Result<Progress> {
+        Ok(Progress::Continue)
+    }
+
+    /// Decides what happens after a timer has executed. This is
+    /// called once its action has run, not for timers kept from
+    /// running, so it can also be used to log or report them.
     fn post_timer(&mut self, _timer: TimerInfo) -> Result<Progress> {
         Ok(Progress::Continue)
     }
 
+    /// Is asked about the next timer every time the timers are
+    /// checked, whether it's due or not, unless the chain was aborted.
+    /// Return `Progress::Trigger` to run it right away, without asking
+    /// `pre_timer`, or `Progress::Stop` to stop. Anything else waits
+    /// for the timer to be due. Since the next timer is asked about
+    /// right after, only say `Trigger` once for each time it should
+    /// run, and see `poll_interval` for how soon it's noticed.
+    ///
+    /// When combined, such as in a `Vec`, the first module saying
+    /// something other than `Progress::Continue` wins, like for
+    /// `pre_timer`: a module forcing a timer goes ahead of one
+    /// stopping after it, and the other way around.
+    fn poll_timer(&mut self, _timer: TimerInfo) -> Result<Progress> {
+        Ok(Progress::Continue)
+    }
+
     /// Is called when there's a potentially recoverable error. Can
     /// re-throw an unrecoverable error.
     fn warning(&mut self, _error: &Error) -> Result<()> {
@@ -44,10 +89,45 @@ pub trait Module {
     }
 
     /// If this is called, the counting was reset - clear any cache
-    /// here
-    fn reset(&mut self) -> Result<()> {
+    /// here. That's when the user is active again, or the chain is
+    /// reset by hand, after at least one timer went off; see
+    /// `on_active` for an edge that also covers chains a module
+    /// aborted. `prior_idle` is how long the user had been idle before.
+    fn reset(&mut self, _prior_idle: Duration) -> Result<()> {
+        Ok(())
+    }
+
+    /// Is called once when the user becomes active again after the
+    /// idle chain has started (that is, after the first timer was
+    /// reached), or when returning from a detected suspend.
+    /// `prior_idle` is how long the user had been idle before
+    /// returning.
+    fn on_active(&mut self, _prior_idle: Duration) -> Result<()> {
         Ok(())
     }
+
+    /// Is called once when xidlehook exits, such as to release what
+    /// the module holds on to. Every module gets called, even if an
+    /// earlier one fails.
+    fn on_shutdown(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// How often this module wants timers to be checked, at most,
+    /// such as when it keeps them from running because of something
+    /// that can change without the user being active. The main loop
+    /// never sleeps longer than this, but may sleep less.
+    fn poll_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Describes this module and its settings. By default, that's
+    /// just the name of its type.
+    fn describe(&self) -> ModuleDescription {
+        let name = any::type_name::<Self>();
+        let name = name.split('<').next().unwrap_or(name);
+        ModuleDescription::new(name.rsplit("::").next().unwrap_or(name))
+    }
 }
 
 /// The default module is also the unit type because why not
@@ -60,16 +140,34 @@ impl Module for () {
 
 impl Module for Box<dyn Module> {
     fn pre_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
-        (&mut **self).pre_timer(timer)
+        (**self).pre_timer(timer)
+    }
+    fn peek_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        (**self).peek_timer(timer)
     }
     fn post_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
-        (&mut **self).post_timer(timer)
+        (**self).post_timer(timer)
+    }
+    fn poll_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        (**self).poll_timer(timer)
     }
     fn warning(&mut self, error: &Error) -> Result<()> {
-        (&mut **self).warning(error)
+        (**self).warning(error)
+    }
+    fn reset(&mut self, prior_idle: Duration) -> Result<()> {
+        (**self).reset(prior_idle)
+    }
+    fn on_active(&mut self, prior_idle: Duration) -> Result<()> {
+        (**self).on_active(prior_idle)
+    }
+    fn on_shutdown(&mut self) -> Result<()> {
+        (**self).on_shutdown()
     }
-    fn reset(&mut self) -> Result<()> {
-        (&mut **self).reset()
+    fn poll_interval(&self) -> Option<Duration> {
+        (**self).poll_interval()
+    }
+    fn describe(&self) -> ModuleDescription {
+        (**self).describe()
     }
 }
 
@@ -87,6 +185,13 @@ where
         }
         self.1.pre_timer(timer)
     }
+    fn peek_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        let status = self.0.peek_timer(timer)?;
+        if status != Progress::Continue {
+            return Ok(status);
+        }
+        self.1.peek_timer(timer)
+    }
     fn post_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
         let status = self.0.post_timer(timer)?;
         if status != Progress::Continue {
@@ -94,13 +199,32 @@ where
         }
         self.1.post_timer(timer)
     }
+    fn poll_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        let status = self.0.poll_timer(timer)?;
+        if status != Progress::Continue {
+            return Ok(status);
+        }
+        self.1.poll_timer(timer)
+    }
     fn warning(&mut self, error: &Error) -> Result<()> {
         self.0.warning(error)?;
         self.1.warning(error)
     }
-    fn reset(&mut self) -> Result<()> {
-        self.0.reset()?;
-        self.1.reset()
+    fn reset(&mut self, prior_idle: Duration) -> Result<()> {
+        self.0.reset(prior_idle)?;
+        self.1.reset(prior_idle)
+    }
+    fn on_active(&mut self, prior_idle: Duration) -> Result<()> {
+        self.0.on_active(prior_idle)?;
+        self.1.on_active(prior_idle)
+    }
+    fn on_shutdown(&mut self) -> Result<()> {
+        let first = self.0.on_shutdown();
+        let second = self.1.on_shutdown();
+        first.and(second)
+    }
+    fn poll_interval(&self) -> Option<Duration> {
+        shortest([self.0.poll_interval(), self.1.poll_interval()])
     }
 }
 
@@ -115,6 +239,15 @@ impl<M: Module> Module for Vec<M> {
         }
         Ok(Progress::Continue)
     }
+    fn peek_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        for module in self {
+            let status = module.peek_timer(timer)?;
+            if status != Progress::Continue {
+                return Ok(status);
+            }
+        }
+        Ok(Progress::Continue)
+    }
     fn post_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
         for module in self {
             let status = module.post_timer(timer)?;
@@ -124,25 +257,107 @@ impl<M: Module> Module for Vec<M> {
         }
         Ok(Progress::Continue)
     }
+    fn poll_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        for module in self {
+            let status = module.poll_timer(timer)?;
+            if status != Progress::Continue {
+                return Ok(status);
+            }
+        }
+        Ok(Progress::Continue)
+    }
     fn warning(&mut self, error: &Error) -> Result<()> {
         for module in self {
             module.warning(error)?;
         }
         Ok(())
     }
-    fn reset(&mut self) -> Result<()> {
+    fn reset(&mut self, prior_idle: Duration) -> Result<()> {
+        for module in self {
+            module.reset(prior_idle)?;
+        }
+        Ok(())
+    }
+    fn on_active(&mut self, prior_idle: Duration) -> Result<()> {
         for module in self {
-            module.reset()?;
+            module.on_active(prior_idle)?;
         }
         Ok(())
     }
+    fn on_shutdown(&mut self) -> Result<()> {
+        first_error(self.iter_mut().map(Module::on_shutdown))
+    }
+    fn poll_interval(&self) -> Option<Duration> {
+        shortest(self.iter().map(Module::poll_interval))
+    }
+}
+
+/// Returns the first error, after going through all of them, so that
+/// every module gets to shut down
+pub(crate) fn first_error<I>(results: I) -> Result<()>
+where
+    I: IntoIterator<Item = Result<()>>,
+{
+    results.into_iter().fold(Ok(()), Result::and)
+}
+
+/// The shortest of some poll intervals, see `Module::poll_interval`
+/// and `Timer::poll_interval`
+pub(crate) fn shortest<I>(intervals: I) -> Option<Duration>
+where
+    I: IntoIterator<Item = Option<Duration>>,
+{
+    intervals.into_iter().flatten().min()
 }
 
+#[cfg(feature = "async")]
+pub mod async_module;
+pub mod backlight;
+pub mod combine;
+#[cfg(feature = "dbus")]
+pub mod dbus_property;
+pub mod description;
+#[cfg(feature = "dbus")]
+pub mod inhibit;
+pub mod lid;
+pub mod list;
+#[cfg(feature = "dbus")]
+pub mod media;
+pub mod observer;
+pub mod on_resume;
+pub mod power;
+pub mod process;
 #[cfg(feature = "pulse")]
 pub mod pulse;
+pub mod reset_on_release;
 pub mod stop_at;
+pub mod time_window;
+pub mod toggle;
 pub mod xcb;
 
+#[cfg(feature = "async")]
+pub use self::async_module::{AsyncModule, Awaited, Blocking};
+#[cfg(feature = "dbus")]
+pub use self::dbus_property::{DbusProperty, NotWhenDbusProperty};
+#[cfg(feature = "dbus")]
+pub use self::inhibit::NotWhenInhibited;
+#[cfg(feature = "dbus")]
+pub use self::media::NotWhenMediaPlaying;
 #[cfg(feature = "pulse")]
 pub use self::pulse::NotWhenAudio;
-pub use self::{stop_at::StopAt, xcb::Xcb};
+pub use self::{
+    backlight::Backlight,
+    combine::{Combinator, Combine},
+    description::{ModuleDescription, Setting},
+    lid::{LidState, LidSwitch},
+    list::ModuleList,
+    observer::Observer,
+    on_resume::OnResume,
+    power::{PowerSource, PowerState},
+    process::NotWhenProcess,
+    reset_on_release::ResetOnRelease,
+    stop_at::StopAt,
+    time_window::{TimeRange, TimeWindow},
+    toggle::Toggle,
+    xcb::Xcb,
+};
@@ -0,0 +1,23 @@
+//! Observers see what the modules decided before each timer, without
+//! being able to change it. This suits logging and metrics, which
+//! shouldn't ever keep a timer from running by accident.
+
+use crate::{Progress, TimerInfo};
+
+/// See the module-level documentation. Register observers with
+/// `Xidlehook::add_observer`.
+pub trait Observer {
+    /// Called before each timer, once the modules have decided whether
+    /// it may run. The verdict is `Progress::Continue` if it runs,
+    /// including when it's triggered by force.
+    fn observe(&mut self, info: &TimerInfo, verdict: Progress);
+}
+
+impl<F> Observer for F
+where
+    F: FnMut(&TimerInfo, Progress),
+{
+    fn observe(&mut self, info: &TimerInfo, verdict: Progress) {
+        self(info, verdict)
+    }
+}
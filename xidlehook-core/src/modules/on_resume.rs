@@ -0,0 +1,59 @@
+//! Runs a command whenever the user becomes active again after the
+//! idle chain started, such as to resume music. It's not run on
+//! startup, since the user wasn't idle before.
+
+use crate::{modules::ModuleDescription, Module, Result};
+
+use std::{
+    fmt,
+    process::{Child, Command},
+    time::Duration,
+};
+
+use log::debug;
+
+/// See the module-level documentation. The command gets
+/// `$XIDLEHOOK_PRIOR_IDLE` set to how many seconds the user had been
+/// idle for.
+pub struct OnResume {
+    cmd: Command,
+    child: Option<Child>,
+}
+impl OnResume {
+    /// Run this command when the user returns
+    pub fn new(cmd: Command) -> Self {
+        Self { cmd, child: None }
+    }
+    /// Run this through `/bin/sh -c` when the user returns
+    pub fn shell(script: &str) -> Self {
+        let mut cmd = Command::new("/bin/sh");
+        cmd.arg("-c").arg(script);
+        Self::new(cmd)
+    }
+}
+impl Module for OnResume {
+    fn on_active(&mut self, prior_idle: Duration) -> Result<()> {
+        // Returning again while the last run is still going doesn't
+        // pile up commands. An error means someone else reaped it.
+        if let Some(ref mut child) = self.child {
+            if let Ok(None) = child.try_wait() {
+                debug!("The on-resume command is still running, not running it again");
+                return Ok(());
+            }
+        }
+        self.child = Some(
+            self.cmd
+                .env("XIDLEHOOK_PRIOR_IDLE", prior_idle.as_secs().to_string())
+                .spawn()?,
+        );
+        Ok(())
+    }
+    fn describe(&self) -> ModuleDescription {
+        ModuleDescription::new("on-resume").with_secret("command")
+    }
+}
+impl fmt::Debug for OnResume {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "OnResume({:?})", self.cmd.get_program())
+    }
+}
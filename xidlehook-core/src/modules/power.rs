@@ -0,0 +1,128 @@
+//! Keeps timers from running depending on whether the computer runs
+//! off AC power or its battery, such as to only dim the screen on
+//! battery. The power supplies are found in
+//! `/sys/class/power_supply`.
+
+use crate::{modules::ModuleDescription, Module, Progress, Result, TimerInfo};
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use log::{debug, trace};
+
+/// What the computer runs off
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerSource {
+    /// An AC adapter
+    Ac,
+    /// Its battery
+    Battery,
+}
+
+/// See the module-level documentation. The computer counts as running
+/// off AC power if any AC adapter is online, or if it has none at all,
+/// like most desktops.
+pub struct PowerState {
+    abort_on: PowerSource,
+    sysfs_dir: PathBuf,
+    /// The `online` files of the AC adapters, once they're found
+    adapters: Option<Vec<PathBuf>>,
+}
+impl PowerState {
+    /// Keep timers from running while the computer runs off this
+    pub fn new(abort_on: PowerSource) -> Self {
+        Self {
+            abort_on,
+            sysfs_dir: PathBuf::from("/sys/class/power_supply"),
+            adapters: None,
+        }
+    }
+    /// Look for power supplies in this directory instead of
+    /// `/sys/class/power_supply`
+    pub fn with_sysfs_dir(mut self, sysfs_dir: impl Into<PathBuf>) -> Self {
+        self.sysfs_dir = sysfs_dir.into();
+        self.adapters = None;
+        self
+    }
+    /// Returns what the computer runs off right now
+    pub fn source(&mut self) -> PowerSource {
+        let sysfs_dir = &self.sysfs_dir;
+        let adapters = self
+            .adapters
+            .get_or_insert_with(|| find_adapters(sysfs_dir));
+        if adapters.is_empty() {
+            return PowerSource::Ac;
+        }
+
+        let mut online = false;
+        let mut readable = false;
+        for adapter in adapters.iter() {
+            match fs::read_to_string(adapter) {
+                Ok(value) => {
+                    readable = true;
+                    online |= value.trim() == "1";
+                },
+                Err(err) => trace!("Couldn't read {}: {}", adapter.display(), err),
+            }
+        }
+        if !readable {
+            // The adapters may have been replaced, look again next time
+            self.adapters = None;
+            return PowerSource::Ac;
+        }
+        if online {
+            PowerSource::Ac
+        } else {
+            PowerSource::Battery
+        }
+    }
+}
+impl Module for PowerState {
+    fn pre_timer(&mut self, _timer: TimerInfo) -> Result<Progress> {
+        if self.source() == self.abort_on {
+            debug!("Not running timers on {:?} power", self.abort_on);
+            Ok(Progress::Abort)
+        } else {
+            Ok(Progress::Continue)
+        }
+    }
+    fn peek_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.pre_timer(timer)
+    }
+    fn describe(&self) -> ModuleDescription {
+        let abort_on = match self.abort_on {
+            PowerSource::Ac => "ac",
+            PowerSource::Battery => "battery",
+        };
+        ModuleDescription::new("not-on-power").with("abort_on", abort_on)
+    }
+}
+impl fmt::Debug for PowerState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PowerState({:?})", self.abort_on)
+    }
+}
+
+/// Returns the `online` files of all AC adapters
+fn find_adapters(sysfs_dir: &Path) -> Vec<PathBuf> {
+    let entries = match fs::read_dir(sysfs_dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            debug!("Couldn't list {}: {}", sysfs_dir.display(), err);
+            return Vec::new();
+        },
+    };
+    let mut adapters: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|supply| {
+            fs::read_to_string(supply.join("type")).is_ok_and(|kind| kind.trim() == "Mains")
+        })
+        .map(|supply| supply.join("online"))
+        .collect();
+    adapters.sort();
+    debug!("Found AC adapters: {:?}", adapters);
+    adapters
+}
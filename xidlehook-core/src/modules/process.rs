@@ -0,0 +1,153 @@
+//! Keeps timers from running while certain processes run, such as a
+//! backup job or a game launcher that doesn't go fullscreen. Processes
+//! are found through `/proc`.
+
+use crate::{modules::ModuleDescription, Module, Progress, Result, TimerInfo};
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use log::{debug, trace, warn};
+
+/// How many bytes of a process name the kernel keeps in `comm`
+const COMM_LEN: usize = 15;
+
+/// See the module-level documentation. Processes are matched by plain
+/// names, not patterns. A process that exits or can't be read while
+/// scanning is skipped.
+pub struct NotWhenProcess {
+    names: Vec<String>,
+    substring: bool,
+    cmdline: bool,
+    proc_dir: PathBuf,
+}
+impl NotWhenProcess {
+    /// Keep timers from running while any process is named exactly
+    /// like one of these
+    pub fn new(names: Vec<String>) -> Self {
+        Self {
+            names,
+            substring: false,
+            cmdline: false,
+            proc_dir: PathBuf::from("/proc"),
+        }
+    }
+    /// Match processes whose name contains one of the names, rather
+    /// than exactly matches it
+    pub fn with_substring(mut self, substring: bool) -> Self {
+        self.substring = substring;
+        self
+    }
+    /// Match the full command line, with the arguments separated by
+    /// spaces, as well as the process name
+    pub fn with_cmdline(mut self, cmdline: bool) -> Self {
+        self.cmdline = cmdline;
+        self
+    }
+    /// Look for processes in this directory instead of `/proc`
+    pub fn with_proc_dir(mut self, proc_dir: impl Into<PathBuf>) -> Self {
+        self.proc_dir = proc_dir.into();
+        self
+    }
+    /// Returns the names of the processes that match, which are empty
+    /// unless timers are kept from running
+    pub fn matching(&self) -> Result<Vec<String>> {
+        let mut found = Vec::new();
+        for entry in fs::read_dir(&self.proc_dir)? {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    trace!("Skipping a process: {}", err);
+                    continue;
+                },
+            };
+            let is_pid = entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| name.bytes().all(|b| b.is_ascii_digit()));
+            if !is_pid {
+                continue;
+            }
+            match self.process_matches(&entry.path()) {
+                Ok(Some(name)) => found.push(name),
+                Ok(None) => (),
+                Err(err) => trace!("Skipping process {:?}: {}", entry.file_name(), err),
+            }
+        }
+        Ok(found)
+    }
+
+    /// Returns the name of the process, if it matches
+    fn process_matches(&self, dir: &Path) -> Result<Option<String>> {
+        let comm = fs::read_to_string(dir.join("comm"))?;
+        let comm = comm.trim_end_matches('\n');
+        if self.names.iter().any(|name| self.comm_matches(comm, name)) {
+            return Ok(Some(comm.to_owned()));
+        }
+        if self.cmdline {
+            let cmdline = fs::read(dir.join("cmdline"))?;
+            let cmdline = String::from_utf8_lossy(&cmdline);
+            let cmdline = cmdline.trim_end_matches('\0').replace('\0', " ");
+            if self
+                .names
+                .iter()
+                .any(|name| self.text_matches(&cmdline, name))
+            {
+                return Ok(Some(comm.to_owned()));
+            }
+        }
+        Ok(None)
+    }
+
+    fn comm_matches(&self, comm: &str, name: &str) -> bool {
+        if self.text_matches(comm, name) {
+            return true;
+        }
+        // The kernel cuts longer names off, so match what's left of
+        // the name
+        !self.substring && comm.len() == COMM_LEN && name.get(..COMM_LEN) == Some(comm)
+    }
+
+    fn text_matches(&self, text: &str, name: &str) -> bool {
+        if self.substring {
+            text.contains(name)
+        } else {
+            text == name
+        }
+    }
+}
+impl Module for NotWhenProcess {
+    fn pre_timer(&mut self, _timer: TimerInfo) -> Result<Progress> {
+        match self.matching() {
+            Ok(found) if !found.is_empty() => {
+                debug!("Not running timers because of {:?}", found);
+                Ok(Progress::Abort)
+            },
+            Ok(_) => Ok(Progress::Continue),
+            Err(err) => {
+                warn!(
+                    "Couldn't look for processes in {}: {}",
+                    self.proc_dir.display(),
+                    err
+                );
+                Ok(Progress::Continue)
+            },
+        }
+    }
+    fn peek_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.pre_timer(timer)
+    }
+    fn describe(&self) -> ModuleDescription {
+        ModuleDescription::new("not-when-process")
+            .with("names", &*self.names)
+            .with("substring", self.substring)
+            .with("cmdline", self.cmdline)
+    }
+}
+impl fmt::Debug for NotWhenProcess {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "NotWhenProcess({:?})", self.names)
+    }
+}
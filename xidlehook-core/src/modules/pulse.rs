@@ -2,37 +2,67 @@
 //! if so it refuses to let xidlehook run the next timer command. This
 //! is used to implement `--not-when-audio` in the xidlehook example
 //! application.
+//!
+//! Audio counts as playing while a stream that isn't paused (corked)
+//! goes to a sink that is running. If `PulseAudio` can't be reached,
+//! or goes away, audio counts as not playing, and connecting is retried
+//! every `RECONNECT_INTERVAL`.
 
-use crate::{Error, Module, Progress, Result, TimerInfo};
+use crate::{modules::ModuleDescription, Error, Module, Progress, Result, TimerInfo};
 
 use libpulse_binding::{
     callbacks::ListResult,
     context::{self, subscribe::Facility, Context, State},
+    def::SinkState,
     mainloop::threaded::Mainloop,
 };
-use log::debug;
+use log::{debug, warn};
 use std::{
-    cell::{Cell, RefCell},
+    cell::RefCell,
+    collections::HashSet,
     fmt,
     rc::Rc,
+    time::{Duration, Instant},
 };
 
 const PA_NAME: &str = "xidlehook";
 
+/// How often connecting to `PulseAudio` is retried while it can't be
+/// reached
+pub const RECONNECT_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Default)]
 struct Counter {
-    in_progress: Cell<usize>,
-    last_total: Cell<usize>,
+    /// The sinks of the unpaused streams, as they're being listed
+    streams_in_progress: RefCell<Vec<u32>>,
+    /// The sinks of the unpaused streams
+    streams: RefCell<Vec<u32>>,
+    /// The running sinks, as they're being listed
+    sinks_in_progress: RefCell<HashSet<u32>>,
+    /// The running sinks
+    sinks: RefCell<HashSet<u32>>,
+}
+impl Counter {
+    /// How many streams are playing
+    fn playing(&self) -> usize {
+        let sinks = self.sinks.borrow();
+        self.streams
+            .borrow()
+            .iter()
+            .filter(|sink| sinks.contains(sink))
+            .count()
+    }
 }
 
-/// See module-level docs
-pub struct NotWhenAudio {
+/// A connection to `PulseAudio`, keeping count of what's playing
+struct Connection {
     counter: Rc<Counter>,
     ctx: Rc<RefCell<Context>>,
     mainloop: Rc<RefCell<Mainloop>>,
 }
-impl NotWhenAudio {
+impl Connection {
     /// Connect to `PulseAudio` and subscribe to notification of changes
-    pub fn new() -> Result<Self> {
+    fn new() -> Result<Self> {
         let mainloop = Rc::new(RefCell::new(
             Mainloop::new().ok_or("pulseaudio: failed to create main loop")?,
         ));
@@ -96,13 +126,12 @@ impl NotWhenAudio {
         }
         ctx.borrow_mut().set_state_callback(None);
 
-        let counter = Rc::new(Counter {
-            in_progress: Cell::new(0),
-            last_total: Cell::new(0),
-        });
+        let counter = Rc::new(Counter::default());
 
-        // Closure for setting up async count of input sinks
+        // Closure for setting up async listing of the streams and the
+        // sinks they go to
         let get_sinks = |ctx: &mut Context, counter: Rc<Counter>| {
+            let streams_counter = Rc::clone(&counter);
             ctx.introspect()
                 .get_sink_input_info_list(move |res| match res {
                     ListResult::Item(item) => {
@@ -110,24 +139,40 @@ impl NotWhenAudio {
                             // Workaround: Check for AudioCallbackDriver of Firefox and ignore it
                             // FIXME: Is there a better way?
                             match &item.name {
-                                Some(name) => if name != "AudioCallbackDriver" {
-                                let count = counter.in_progress.get().saturating_add(1);
-                                counter.in_progress.set(count);
-                                debug!("Partial count: {}", count);
-                                }
-                                else {
-                                    debug!("Not considered {}", name);
+                                Some(name) => {
+                                    if name != "AudioCallbackDriver" {
+                                        streams_counter
+                                            .streams_in_progress
+                                            .borrow_mut()
+                                            .push(item.sink);
+                                        debug!("Unpaused stream {} to sink {}", name, item.sink);
+                                    } else {
+                                        debug!("Not considered {}", name);
+                                    }
                                 },
                                 None => debug!(""),
                             }
                         }
                     },
                     ListResult::End | ListResult::Error => {
-                        let count = counter.in_progress.replace(0);
-                        counter.last_total.set(count);
-                        debug!("Total sum: {}", count);
+                        let streams = streams_counter.streams_in_progress.replace(Vec::new());
+                        debug!("Unpaused streams: {}", streams.len());
+                        streams_counter.streams.replace(streams);
                     },
                 });
+            ctx.introspect().get_sink_info_list(move |res| match res {
+                ListResult::Item(item) => {
+                    // Servers that can't tell the state say it's invalid
+                    if let SinkState::Running | SinkState::Invalid = item.state {
+                        counter.sinks_in_progress.borrow_mut().insert(item.index);
+                    }
+                },
+                ListResult::End | ListResult::Error => {
+                    let sinks = counter.sinks_in_progress.replace(HashSet::new());
+                    debug!("Running sinks: {}", sinks.len());
+                    counter.sinks.replace(sinks);
+                },
+            });
         };
 
         // Setup notification callback
@@ -145,9 +190,11 @@ impl NotWhenAudio {
                 })));
         }
 
-        // Subscribe to sink input events
-        ctx.borrow_mut()
-            .subscribe(Facility::SinkInput.to_interest_mask(), |_| ());
+        // Subscribe to sink input and sink events
+        ctx.borrow_mut().subscribe(
+            Facility::SinkInput.to_interest_mask() | Facility::Sink.to_interest_mask(),
+            |_| (),
+        );
 
         // Check if audio is already playing
         get_sinks(&mut ctx.borrow_mut(), Rc::clone(&counter));
@@ -160,13 +207,22 @@ impl NotWhenAudio {
             mainloop,
         })
     }
-}
-impl fmt::Debug for NotWhenAudio {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "NotWhenAudio")
+    /// Whether the connection is still up
+    fn is_up(&self) -> bool {
+        self.mainloop.borrow_mut().lock();
+        let state = self.ctx.borrow().get_state();
+        self.mainloop.borrow_mut().unlock();
+        !matches!(state, State::Failed | State::Terminated)
+    }
+    /// How many streams are playing
+    fn playing(&self) -> usize {
+        self.mainloop.borrow_mut().lock();
+        let players = self.counter.playing();
+        self.mainloop.borrow_mut().unlock();
+        players
     }
 }
-impl Drop for NotWhenAudio {
+impl Drop for Connection {
     fn drop(&mut self) {
         debug!("Stopping PulseAudio main loop");
         self.mainloop.borrow_mut().stop();
@@ -176,15 +232,86 @@ impl Drop for NotWhenAudio {
         debug!("Stopped");
     }
 }
+
+/// See module-level docs
+pub struct NotWhenAudio {
+    conn: Option<Connection>,
+    /// When connecting was last tried, if the connection isn't up
+    last_attempt: Option<Instant>,
+}
+impl NotWhenAudio {
+    /// Connect to `PulseAudio` and subscribe to notification of
+    /// changes. This fails if it can't be reached, see `lenient` for a
+    /// version which doesn't.
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            conn: Some(Connection::new()?),
+            last_attempt: None,
+        })
+    }
+    /// Like `new`, but if `PulseAudio` can't be reached, audio counts
+    /// as not playing until it can be
+    pub fn lenient() -> Self {
+        let mut module = Self {
+            conn: None,
+            last_attempt: None,
+        };
+        module.reconnect();
+        module
+    }
+    /// Whether `PulseAudio` is connected to
+    pub fn is_connected(&self) -> bool {
+        self.conn.is_some()
+    }
+
+    fn reconnect(&mut self) {
+        self.last_attempt = Some(Instant::now());
+        match Connection::new() {
+            Ok(conn) => self.conn = Some(conn),
+            Err(err) => warn!("Treating audio as not playing: {}", err),
+        }
+    }
+}
+impl fmt::Debug for NotWhenAudio {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "NotWhenAudio")
+    }
+}
 impl Module for NotWhenAudio {
     fn pre_timer(&mut self, _timer: TimerInfo) -> Result<Progress> {
-        self.mainloop.borrow_mut().lock();
-        let players = self.counter.last_total.get();
-        self.mainloop.borrow_mut().unlock();
+        if self.conn.as_ref().is_some_and(|conn| !conn.is_up()) {
+            warn!("Lost the connection to PulseAudio, treating audio as not playing");
+            self.conn = None;
+            self.last_attempt = Some(Instant::now());
+        }
+        if self.conn.is_none()
+            && self
+                .last_attempt
+                .is_none_or(|at| at.elapsed() >= RECONNECT_INTERVAL)
+        {
+            self.reconnect();
+        }
+        let players = self.conn.as_ref().map_or(0, Connection::playing);
         if players == 0 {
             Ok(Progress::Continue)
         } else {
             Ok(Progress::Reset)
         }
     }
+    fn peek_timer(&mut self, _timer: TimerInfo) -> Result<Progress> {
+        // Asks the connection there is, without reconnecting
+        let players = self
+            .conn
+            .as_ref()
+            .filter(|conn| conn.is_up())
+            .map_or(0, Connection::playing);
+        if players == 0 {
+            Ok(Progress::Continue)
+        } else {
+            Ok(Progress::Reset)
+        }
+    }
+    fn describe(&self) -> ModuleDescription {
+        ModuleDescription::new("not-when-audio").with("connected", self.is_connected())
+    }
 }
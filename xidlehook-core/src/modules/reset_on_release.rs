@@ -0,0 +1,101 @@
+//! Starts the chain over once a module stops keeping timers from
+//! running, such as `NotWhenFullscreen` once a video ends, so that all
+//! timers wait their whole time again as if the user was just active.
+//! Otherwise, once the idle time is high after a long video, the next
+//! timer can go off right away.
+
+use crate::{modules::ModuleDescription, Error, Module, Progress, Result, TimerInfo};
+
+use std::{fmt, time::Duration};
+
+use log::debug;
+
+/// See the module-level documentation. The first time the inner
+/// module lets a timer run after keeping one from running, this says
+/// `Progress::Reset` instead, which starts the chain over from the
+/// current idle time. An aborted chain already starts over once it's
+/// picked up again, such as when the user is active.
+pub struct ResetOnRelease<M: Module> {
+    inner: M,
+    /// What the inner module said last when it kept a timer from
+    /// running, until it lets one run again
+    held: Option<Progress>,
+}
+impl<M: Module> ResetOnRelease<M> {
+    /// Wrap this module
+    pub fn new(inner: M) -> Self {
+        Self { inner, held: None }
+    }
+    /// The module this wraps
+    pub fn inner(&self) -> &M {
+        &self.inner
+    }
+    /// The module this wraps, mutably
+    pub fn inner_mut(&mut self) -> &mut M {
+        &mut self.inner
+    }
+    /// Returns true if the inner module kept the last timer it was
+    /// asked about from running
+    pub fn is_held(&self) -> bool {
+        self.held.is_some()
+    }
+}
+impl<M: Module> Module for ResetOnRelease<M> {
+    fn pre_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        match self.inner.pre_timer(timer)? {
+            Progress::Continue => match self.held.take() {
+                Some(_) => {
+                    debug!("Timers may run again, starting them over");
+                    Ok(Progress::Reset)
+                },
+                None => Ok(Progress::Continue),
+            },
+            progress @ Progress::Abort | progress @ Progress::Reset => {
+                self.held = Some(progress);
+                Ok(progress)
+            },
+            progress => Ok(progress),
+        }
+    }
+    fn peek_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        match self.inner.peek_timer(timer)? {
+            Progress::Continue if self.held.is_some() => Ok(Progress::Reset),
+            progress => Ok(progress),
+        }
+    }
+    fn post_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.inner.post_timer(timer)
+    }
+    fn poll_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.inner.poll_timer(timer)
+    }
+    fn warning(&mut self, error: &Error) -> Result<()> {
+        self.inner.warning(error)
+    }
+    fn reset(&mut self, prior_idle: Duration) -> Result<()> {
+        // An aborted chain starting over needs no other reset, while one
+        // reset by the inner module is still held
+        if self.held == Some(Progress::Abort) {
+            self.held = None;
+        }
+        self.inner.reset(prior_idle)
+    }
+    fn on_active(&mut self, prior_idle: Duration) -> Result<()> {
+        self.held = None;
+        self.inner.on_active(prior_idle)
+    }
+    fn on_shutdown(&mut self) -> Result<()> {
+        self.inner.on_shutdown()
+    }
+    fn poll_interval(&self) -> Option<Duration> {
+        self.inner.poll_interval()
+    }
+    fn describe(&self) -> ModuleDescription {
+        self.inner.describe().with("reset_on_release", true)
+    }
+}
+impl<M: Module + fmt::Debug> fmt::Debug for ResetOnRelease<M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ResetOnRelease({:?})", self.inner)
+    }
+}
@@ -2,7 +2,7 @@
 //! the end. This is used to implement `--once` in the xidlehook
 //! example application.
 
-use crate::{Module, Progress, Result, TimerInfo};
+use crate::{modules::ModuleDescription, Module, Progress, Result, TimerInfo};
 
 use std::fmt;
 
@@ -29,7 +29,7 @@ impl StopAt {
 }
 impl Module for StopAt {
     fn post_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
-        #[allow(clippy::integer_arithmetic)] // timer list is never empty
+        #[allow(clippy::arithmetic_side_effects)] // timer list is never empty
         let stop_after = self.stop_after.unwrap_or(timer.length - 1);
 
         trace!("{}/{}", timer.index, stop_after);
@@ -39,6 +39,13 @@ impl Module for StopAt {
             Ok(Progress::Continue)
         }
     }
+    fn describe(&self) -> ModuleDescription {
+        let description = ModuleDescription::new("stop-at");
+        match self.stop_after {
+            Some(index) => description.with("stop_after", index),
+            None => description.with("stop_after", "completion"),
+        }
+    }
 }
 impl fmt::Debug for StopAt {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -0,0 +1,154 @@
+//! Only lets timers run at certain times of day, in local time, such
+//! as to only dim the screen aggressively at night.
+
+use crate::{modules::ModuleDescription, Module, Progress, Result, TimerInfo};
+
+use std::{convert::TryFrom, fmt, mem, ptr, str::FromStr};
+
+use log::debug;
+use nix::libc;
+
+/// A range of the day, from the minute it starts at up to the minute
+/// it ends at. A range ending before it starts wraps past midnight,
+/// and one ending when it starts covers the whole day.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeRange {
+    start: u16,
+    end: u16,
+}
+impl TimeRange {
+    /// Returns the range between these times, given as hours and
+    /// minutes. This fails if a time isn't in the day.
+    pub fn new(start: (u8, u8), end: (u8, u8)) -> Result<Self> {
+        Ok(Self {
+            start: minute_of_day(start)?,
+            end: minute_of_day(end)?,
+        })
+    }
+    /// Returns true if this minute of the day, counted from midnight,
+    /// is in the range
+    pub fn contains(self, minute: u16) -> bool {
+        if self.start < self.end {
+            self.start <= minute && minute < self.end
+        } else if self.start > self.end {
+            minute >= self.start || minute < self.end
+        } else {
+            true
+        }
+    }
+}
+impl FromStr for TimeRange {
+    type Err = crate::Error;
+
+    /// Parses a range like `22:00-06:00`
+    fn from_str(s: &str) -> Result<Self> {
+        let (start, end) = s
+            .split_once('-')
+            .ok_or_else(|| format!("{} isn't a range like 22:00-06:00", s))?;
+        Self::new(parse_time(start)?, parse_time(end)?)
+    }
+}
+impl fmt::Display for TimeRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let time = |minute: u16| (minute.div_euclid(60), minute.rem_euclid(60));
+        let (start, end) = (time(self.start), time(self.end));
+        write!(f, "{:02}:{:02}-{:02}:{:02}", start.0, start.1, end.0, end.1)
+    }
+}
+
+/// Parses a time of day like `22:00` into hours and minutes, without
+/// checking that it's in the day
+pub fn parse_time(time: &str) -> Result<(u8, u8)> {
+    let (hours, minutes) = time
+        .trim()
+        .split_once(':')
+        .ok_or_else(|| format!("{} isn't a time like 22:00", time))?;
+    Ok((hours.parse()?, minutes.parse()?))
+}
+
+/// Returns the minute of the day these hours and minutes are at,
+/// counted from midnight. This fails if they aren't in the day.
+pub fn minute_of_day((hours, minutes): (u8, u8)) -> Result<u16> {
+    if hours >= 24 || minutes >= 60 {
+        return Err(format!("{:02}:{:02} isn't a time of day", hours, minutes).into());
+    }
+    Ok(u16::from(hours) * 60 + u16::from(minutes))
+}
+
+/// Returns the current minute of the day in the system's local time
+/// zone
+pub fn local_minute() -> Result<u16> {
+    // The time is only read, and localtime_r doesn't keep the pointers
+    unsafe {
+        let now = libc::time(ptr::null_mut());
+        let mut tm: libc::tm = mem::zeroed();
+        if libc::localtime_r(&now, &mut tm).is_null() {
+            return Err("can't tell the local time".into());
+        }
+        Ok(u16::try_from(tm.tm_hour * 60 + tm.tm_min)?)
+    }
+}
+
+/// See the module-level documentation. Outside of all ranges, timers
+/// are kept from running with `Progress::Abort`.
+pub struct TimeWindow {
+    ranges: Vec<TimeRange>,
+    timers: Option<Vec<usize>>,
+}
+impl TimeWindow {
+    /// Only let timers run during any of these ranges
+    pub fn new(ranges: Vec<TimeRange>) -> Self {
+        Self {
+            ranges,
+            timers: None,
+        }
+    }
+    /// Only keep the timers at these indices from running, such as
+    /// just the one dimming the screen. By default, all are.
+    pub fn with_timers(mut self, timers: Vec<usize>) -> Self {
+        self.timers = Some(timers);
+        self
+    }
+    /// The ranges timers may run during
+    pub fn ranges(&self) -> &[TimeRange] {
+        &self.ranges
+    }
+    /// Returns true if this timer may run at this minute of the day
+    pub fn allows(&self, timer: TimerInfo, minute: u16) -> bool {
+        let applies = self
+            .timers
+            .as_ref()
+            .is_none_or(|timers| timers.contains(&timer.index));
+        !applies || self.ranges.iter().any(|range| range.contains(minute))
+    }
+}
+impl Module for TimeWindow {
+    fn pre_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        let minute = local_minute()?;
+        if self.allows(timer, minute) {
+            Ok(Progress::Continue)
+        } else {
+            debug!("Not running timer {} at this time of day", timer.index);
+            Ok(Progress::Abort)
+        }
+    }
+    fn peek_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.pre_timer(timer)
+    }
+    fn describe(&self) -> ModuleDescription {
+        let ranges: Vec<String> = self.ranges.iter().map(TimeRange::to_string).collect();
+        let description = ModuleDescription::new("time-window").with("ranges", ranges);
+        match self.timers {
+            Some(ref timers) => {
+                let timers: Vec<String> = timers.iter().map(usize::to_string).collect();
+                description.with("timers", timers)
+            },
+            None => description,
+        }
+    }
+}
+impl fmt::Debug for TimeWindow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "TimeWindow({} ranges)", self.ranges.len())
+    }
+}
@@ -0,0 +1,65 @@
+//! A module which can be switched on and off at runtime, such as from
+//! a signal handler. While it's on, it keeps timers from running.
+//! This is used to implement the signal controls of the example
+//! application.
+
+use crate::{modules::ModuleDescription, Module, Progress, Result, TimerInfo};
+
+use std::{cell::Cell, fmt, rc::Rc};
+
+/// See the module-level documentation. Clones share the same state,
+/// so one clone can be registered while another is kept around to
+/// switch it.
+#[derive(Clone)]
+pub struct Toggle {
+    on: Rc<Cell<bool>>,
+    progress: Progress,
+}
+impl Toggle {
+    /// Returns a module, which is initially off, that answers with
+    /// `progress` before each timer while it's on. Use
+    /// `Progress::Abort` to simply not run any timers, or
+    /// `Progress::Reset` to treat the user as active.
+    pub fn new(progress: Progress) -> Self {
+        Self {
+            on: Rc::new(Cell::new(false)),
+            progress,
+        }
+    }
+    /// Returns true if it's currently on
+    pub fn is_on(&self) -> bool {
+        self.on.get()
+    }
+    /// Switch it on or off
+    pub fn set(&self, on: bool) {
+        self.on.set(on);
+    }
+    /// Switch it to the opposite state, returning the new state
+    pub fn toggle(&self) -> bool {
+        let on = !self.on.get();
+        self.on.set(on);
+        on
+    }
+}
+impl Module for Toggle {
+    fn pre_timer(&mut self, _timer: TimerInfo) -> Result<Progress> {
+        if self.is_on() {
+            Ok(self.progress)
+        } else {
+            Ok(Progress::Continue)
+        }
+    }
+    fn peek_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.pre_timer(timer)
+    }
+    fn describe(&self) -> ModuleDescription {
+        ModuleDescription::new("toggle")
+            .with_enabled(self.is_on())
+            .with("progress", self.progress.name())
+    }
+}
+impl fmt::Debug for Toggle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Toggle({})", if self.is_on() { "on" } else { "off" })
+    }
+}
@@ -0,0 +1,226 @@
+//! A minimal built-in blanker, for users who don't run a screen
+//! locker: a black (or, with a compositor, dimmed) window covering
+//! every screen, which is removed as soon as the user is active.
+//!
+//! **This is not a lock.** Any input removes the window, and nothing
+//! stops other clients from drawing over it or from reading input.
+
+use super::Xcb;
+use crate::{timers::Timer, Result};
+
+use std::{fmt, rc::Rc, time::Duration};
+
+const NET_WM_WINDOW_OPACITY: &str = "_NET_WM_WINDOW_OPACITY";
+
+/// Something that can cover the screen
+pub trait Blanker {
+    /// Cover the screen and grab the input
+    fn show(&mut self) -> Result<()>;
+    /// Let go of the input, but keep the screen covered, so that a
+    /// locker started afterwards can grab it
+    fn release_input(&mut self) -> Result<()>;
+    /// Stop covering the screen
+    fn hide(&mut self) -> Result<()>;
+}
+
+/// Covers the screens using override-redirect windows, over the
+/// existing X connection. **This is not a lock**: any input removes
+/// the windows, and nothing stops other clients from drawing over
+/// them or from reading input.
+pub struct XcbBlanker {
+    xcb: Rc<Xcb>,
+    opacity: Option<f64>,
+    windows: Vec<xcb::Window>,
+}
+impl XcbBlanker {
+    /// Returns a blanker using this connection
+    pub fn new(xcb: Rc<Xcb>) -> Self {
+        Self {
+            xcb,
+            opacity: None,
+            windows: Vec::new(),
+        }
+    }
+    /// Dim the screen rather than blacking it out, such that
+    /// `opacity` is between 0 (transparent) and 1 (black). This needs
+    /// a compositor which honors `_NET_WM_WINDOW_OPACITY`.
+    pub fn with_opacity(mut self, opacity: f64) -> Self {
+        self.opacity = Some(opacity.clamp(0.0, 1.0));
+        self
+    }
+    fn ungrab(&self) {
+        let session = self.xcb.session();
+        let conn = &session.conn;
+        xcb::xproto::ungrab_keyboard(conn, xcb::CURRENT_TIME);
+        xcb::xproto::ungrab_pointer(conn, xcb::CURRENT_TIME);
+    }
+}
+impl Blanker for XcbBlanker {
+    fn show(&mut self) -> Result<()> {
+        let session = self.xcb.session();
+        let conn = &session.conn;
+        let opacity_atom = match self.opacity {
+            Some(_) => Some(
+                xcb::xproto::intern_atom(conn, false, NET_WM_WINDOW_OPACITY)
+                    .get_reply()?
+                    .atom(),
+            ),
+            None => None,
+        };
+
+        for screen in conn.get_setup().roots() {
+            let window = conn.generate_id();
+            xcb::xproto::create_window(
+                conn,
+                xcb::COPY_FROM_PARENT as u8,
+                window,
+                screen.root(),
+                0,
+                0,
+                screen.width_in_pixels(),
+                screen.height_in_pixels(),
+                0,
+                xcb::xproto::WINDOW_CLASS_INPUT_OUTPUT as u16,
+                screen.root_visual(),
+                &[
+                    (xcb::xproto::CW_BACK_PIXEL, screen.black_pixel()),
+                    (xcb::xproto::CW_OVERRIDE_REDIRECT, 1),
+                    (
+                        xcb::xproto::CW_EVENT_MASK,
+                        xcb::xproto::EVENT_MASK_KEY_PRESS | xcb::xproto::EVENT_MASK_BUTTON_PRESS,
+                    ),
+                ],
+            );
+            if let (Some(atom), Some(opacity)) = (opacity_atom, self.opacity) {
+                // Fully opaque is u32::MAX
+                let opacity = (opacity * f64::from(u32::MAX)) as u32;
+                xcb::xproto::change_property(
+                    conn,
+                    xcb::xproto::PROP_MODE_REPLACE as u8,
+                    window,
+                    atom,
+                    xcb::xproto::ATOM_CARDINAL,
+                    32,
+                    &[opacity],
+                );
+            }
+            xcb::xproto::map_window(conn, window);
+            self.windows.push(window);
+        }
+
+        // Grab the input of the first screen. Other clients can't get
+        // key presses meant to wake the screen, but this can fail if a
+        // client already has a grab, which is fine for a blanker.
+        if let Some(&window) = self.windows.first() {
+            let mode = xcb::xproto::GRAB_MODE_ASYNC as u8;
+            xcb::xproto::grab_keyboard(conn, true, window, xcb::CURRENT_TIME, mode, mode);
+            xcb::xproto::grab_pointer(
+                conn,
+                true,
+                window,
+                xcb::xproto::EVENT_MASK_BUTTON_PRESS as u16,
+                mode,
+                mode,
+                xcb::NONE,
+                xcb::NONE,
+                xcb::CURRENT_TIME,
+            );
+        }
+        conn.flush();
+        Ok(())
+    }
+    fn release_input(&mut self) -> Result<()> {
+        self.ungrab();
+        self.xcb.session().conn.flush();
+        Ok(())
+    }
+    fn hide(&mut self) -> Result<()> {
+        self.ungrab();
+        let session = self.xcb.session();
+        for window in self.windows.drain(..) {
+            xcb::xproto::destroy_window(&session.conn, window);
+        }
+        session.conn.flush();
+        Ok(())
+    }
+}
+impl fmt::Debug for XcbBlanker {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "XcbBlanker({} windows)", self.windows.len())
+    }
+}
+
+/// A timer which covers the screen with a blanker when activated,
+/// and removes it when the user is active again. If another timer
+/// goes off afterwards, the input is released so that it can start a
+/// locker, while the screen stays covered.
+#[derive(Debug)]
+pub struct BlankTimer<B: Blanker> {
+    /// The idle time required for this timer to activate
+    pub time: Duration,
+    /// Whether or not to disable this timer
+    pub disabled: bool,
+    blanker: B,
+    shown: bool,
+}
+impl<B: Blanker> BlankTimer<B> {
+    /// Cover the screen with this blanker after some idle time
+    pub fn new(time: Duration, blanker: B) -> Self {
+        Self {
+            time,
+            disabled: false,
+            blanker,
+            shown: false,
+        }
+    }
+    /// Returns true if the screen is currently covered
+    pub fn is_shown(&self) -> bool {
+        self.shown
+    }
+    /// Returns a reference to the blanker
+    pub fn blanker(&self) -> &B {
+        &self.blanker
+    }
+    fn hide(&mut self) -> Result<()> {
+        if self.shown {
+            self.shown = false;
+            self.blanker.hide()?;
+        }
+        Ok(())
+    }
+}
+impl<B: Blanker> Timer for BlankTimer<B> {
+    fn time_left(&mut self, idle_time: Duration) -> Result<Option<Duration>> {
+        Ok(self
+            .time
+            .checked_sub(idle_time)
+            .filter(|&dur| dur != Duration::default()))
+    }
+    fn activate(&mut self) -> Result<()> {
+        if !self.shown {
+            self.shown = true;
+            self.blanker.show()?;
+        }
+        Ok(())
+    }
+    fn abort(&mut self) -> Result<()> {
+        self.hide()
+    }
+    fn deactivate(&mut self) -> Result<()> {
+        if self.shown {
+            self.blanker.release_input()?;
+        }
+        Ok(())
+    }
+    fn reset(&mut self) -> Result<()> {
+        self.hide()
+    }
+    fn disabled(&mut self) -> bool {
+        self.disabled
+    }
+}
+impl<B: Blanker> Drop for BlankTimer<B> {
+    fn drop(&mut self) {
+        let _ = self.hide();
+    }
+}
@@ -0,0 +1,92 @@
+//! A bounded cache of per-window properties which don't change over
+//! the lifetime of a window, so that they don't have to be fetched on
+//! every pass over the window tree.
+
+use std::collections::{HashMap, HashSet};
+
+/// The number of windows cached unless configured otherwise
+pub const DEFAULT_MAX_ENTRIES: usize = 1024;
+
+/// See the module-level documentation. Once it's full, the least
+/// recently used entry is evicted. Entries for destroyed windows are
+/// evicted when a walk over the whole window tree no longer sees them.
+#[derive(Clone, Debug)]
+pub struct WindowCache<V> {
+    max_entries: usize,
+    /// The value and the tick it was last used at
+    entries: HashMap<xcb::Window, (V, u64)>,
+    tick: u64,
+}
+impl<V> WindowCache<V> {
+    /// Returns an empty cache which holds at most `max_entries`
+    /// windows. A maximum of zero disables the cache.
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            max_entries,
+            entries: HashMap::new(),
+            tick: 0,
+        }
+    }
+    /// The maximum number of cached windows
+    pub fn max_entries(&self) -> usize {
+        self.max_entries
+    }
+    /// Change the maximum number of cached windows, evicting entries
+    /// if there are more than that
+    pub fn set_max_entries(&mut self, max_entries: usize) {
+        self.max_entries = max_entries;
+        while self.entries.len() > max_entries {
+            self.evict_oldest();
+        }
+    }
+    /// The number of cached windows
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    /// Returns true if no windows are cached
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    /// Look up a window, marking it as recently used
+    pub fn get(&mut self, window: xcb::Window) -> Option<&V> {
+        self.tick += 1;
+        let tick = self.tick;
+        self.entries.get_mut(&window).map(|entry| {
+            entry.1 = tick;
+            &entry.0
+        })
+    }
+    /// Cache a value for a window, evicting the least recently used
+    /// window if the cache is full
+    pub fn insert(&mut self, window: xcb::Window, value: V) {
+        if self.max_entries == 0 {
+            return;
+        }
+        if !self.entries.contains_key(&window) && self.entries.len() >= self.max_entries {
+            self.evict_oldest();
+        }
+        self.tick += 1;
+        self.entries.insert(window, (value, self.tick));
+    }
+    /// Evict all windows which aren't in this set, such as the windows
+    /// seen by a `query_tree` walk
+    pub fn retain_present(&mut self, present: &HashSet<xcb::Window>) {
+        self.entries.retain(|window, _| present.contains(window));
+    }
+
+    fn evict_oldest(&mut self) {
+        let oldest = self
+            .entries
+            .iter()
+            .min_by_key(|(_, &(_, tick))| tick)
+            .map(|(&window, _)| window);
+        if let Some(oldest) = oldest {
+            self.entries.remove(&oldest);
+        }
+    }
+}
+impl<V> Default for WindowCache<V> {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_ENTRIES)
+    }
+}
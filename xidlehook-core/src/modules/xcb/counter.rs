@@ -0,0 +1,72 @@
+//! Keeps idle times going up past the point where the X server's
+//! 32-bit millisecond counter wraps around, after 49.7 days.
+
+use super::idle_from_ms_since_input;
+
+use std::{
+    convert::TryFrom,
+    time::{Duration, Instant},
+};
+
+use log::debug;
+
+/// How many milliseconds fit before the counter wraps around
+const WRAP: u64 = 1 << 32;
+
+/// How far off a reading may be from the one expected after wrapping
+/// around, for the time that passed between them
+pub const WRAP_TOLERANCE: Duration = Duration::from_secs(60);
+
+/// Follows the `ms_since_user_input` readings of one screen. When a
+/// reading is smaller than the last, the user was usually active, but
+/// if the last one was close enough to the limit that the time since
+/// explains the drop, the counter wrapped around instead, and the idle
+/// time keeps counting from there.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IdleCounter {
+    last: Option<(u32, Instant)>,
+    wraps: u64,
+}
+impl IdleCounter {
+    /// Returns a counter that hasn't seen any readings
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Returns the idle time for a reading taken now
+    pub fn observe(&mut self, ms: u32) -> Duration {
+        self.observe_at(ms, Instant::now())
+    }
+    /// Returns the idle time for a reading taken at this point in time,
+    /// which must not be before the last one
+    pub fn observe_at(&mut self, ms: u32, now: Instant) -> Duration {
+        if let Some((last, at)) = self.last {
+            if ms < last {
+                if Self::wrapped(last, ms, now.saturating_duration_since(at)) {
+                    self.wraps = self.wraps.saturating_add(1);
+                    debug!("The XScreenSaver idle counter wrapped around");
+                } else {
+                    self.wraps = 0;
+                }
+            }
+        }
+        self.last = Some((ms, now));
+        idle_from_ms_since_input(ms)
+            .saturating_add(Duration::from_millis(WRAP.saturating_mul(self.wraps)))
+    }
+    /// How many times the counter wrapped around since the user was
+    /// last active
+    pub fn wraps(&self) -> u64 {
+        self.wraps
+    }
+
+    fn wrapped(last: u32, ms: u32, elapsed: Duration) -> bool {
+        let elapsed = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX);
+        let tolerance = u64::try_from(WRAP_TOLERANCE.as_millis()).unwrap_or(u64::MAX);
+        let expected = u64::from(last).saturating_add(elapsed);
+        if expected.saturating_add(tolerance) < WRAP {
+            return false;
+        }
+        let expected = expected.saturating_sub(WRAP);
+        u64::from(ms).abs_diff(expected) <= tolerance
+    }
+}
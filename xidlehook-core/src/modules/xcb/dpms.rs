@@ -0,0 +1,225 @@
+//! Skips timers once DPMS has already put the monitors to sleep, so
+//! that a dim or a lock doesn't wake them up or flicker. The
+//! `NotWhenScreenOff` module can be limited to some timers, such as
+//! only the lock.
+//!
+//! The one request needed is sent by hand, since linking
+//! `libxcb-dpms` just for it isn't worth the extra dependency.
+
+use super::XidlehookError;
+use crate::{modules::ModuleDescription, Module, Progress, Result, TimerInfo};
+
+use std::{fmt, mem, ptr};
+
+use nix::libc;
+
+/// The name the X server knows the extension by
+pub(super) const EXTENSION: &str = "DPMS";
+
+/// The minor opcode of `DPMSInfo`
+const INFO: u8 = 4;
+
+#[allow(non_camel_case_types)]
+mod ffi {
+    use nix::libc::{c_int, c_uint, c_void, iovec};
+    use xcb::ffi::{xcb_connection_t, xcb_generic_error_t};
+
+    pub(super) const XCB_REQUEST_CHECKED: c_int = 0x01;
+    pub(super) const XCB_REQUEST_RAW: c_int = 0x02;
+
+    #[repr(C)]
+    pub(super) struct xcb_protocol_request_t {
+        pub(super) count: usize,
+        pub(super) ext: *mut c_void,
+        pub(super) opcode: u8,
+        pub(super) isvoid: u8,
+    }
+
+    #[repr(C)]
+    pub(super) struct xcb_dpms_info_reply_t {
+        pub(super) response_type: u8,
+        pub(super) pad0: u8,
+        pub(super) sequence: u16,
+        pub(super) length: u32,
+        pub(super) power_level: u16,
+        pub(super) state: u8,
+        pub(super) pad1: [u8; 21],
+    }
+
+    #[link(name = "xcb")]
+    extern "C" {
+        pub(super) fn xcb_send_request(
+            c: *mut xcb_connection_t,
+            flags: c_int,
+            vector: *mut iovec,
+            request: *const xcb_protocol_request_t,
+        ) -> c_uint;
+        pub(super) fn xcb_wait_for_reply(
+            c: *mut xcb_connection_t,
+            request: c_uint,
+            e: *mut *mut xcb_generic_error_t,
+        ) -> *mut c_void;
+    }
+}
+
+/// The power state of the monitors, as reported by the DPMS extension
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DpmsState {
+    /// The monitors are on
+    On,
+    /// The monitors are in standby
+    Standby,
+    /// The monitors are suspended
+    Suspend,
+    /// The monitors are off
+    Off,
+    /// DPMS is switched off, such as with `xset -dpms`, so the
+    /// monitors stay on
+    Disabled,
+    /// The X server doesn't support DPMS
+    Unsupported,
+}
+impl DpmsState {
+    /// Decode the power level from the extension's representation,
+    /// returning `None` if it's unknown
+    pub fn from_power_level(level: u16) -> Option<Self> {
+        match level {
+            0 => Some(Self::On),
+            1 => Some(Self::Standby),
+            2 => Some(Self::Suspend),
+            3 => Some(Self::Off),
+            _ => None,
+        }
+    }
+    /// Returns true if the monitors are asleep, in standby, suspended
+    /// or off
+    pub fn is_asleep(self) -> bool {
+        match self {
+            Self::Standby | Self::Suspend | Self::Off => true,
+            Self::On | Self::Disabled | Self::Unsupported => false,
+        }
+    }
+}
+
+/// Ask the extension, whose major opcode this is, for the power state
+pub(super) fn query(conn: &xcb::Connection, major_opcode: u8) -> Result<DpmsState> {
+    // A request without arguments is just its header: the major and
+    // minor opcode, and the length in 4-byte units
+    let mut request: [u8; 4] = [major_opcode, INFO, 0, 0];
+    request[2..].copy_from_slice(&1u16.to_ne_bytes());
+    // libxcb wants two vectors in front for its own use
+    let mut vectors = [libc::iovec {
+        iov_base: ptr::null_mut(),
+        iov_len: 0,
+    }; 3];
+    vectors[2] = libc::iovec {
+        iov_base: request.as_mut_ptr().cast(),
+        iov_len: mem::size_of_val(&request),
+    };
+    let protocol = ffi::xcb_protocol_request_t {
+        count: 1,
+        ext: ptr::null_mut(),
+        opcode: INFO,
+        isvoid: 0,
+    };
+
+    let (level, enabled) = unsafe {
+        let sequence = ffi::xcb_send_request(
+            conn.get_raw_conn(),
+            ffi::XCB_REQUEST_CHECKED | ffi::XCB_REQUEST_RAW,
+            vectors[2..].as_mut_ptr(),
+            &protocol,
+        );
+        let mut err = ptr::null_mut();
+        let reply: *mut ffi::xcb_dpms_info_reply_t =
+            ffi::xcb_wait_for_reply(conn.get_raw_conn(), sequence, &mut err).cast();
+        if reply.is_null() {
+            if err.is_null() {
+                conn.has_error().map_err(XidlehookError::from)?;
+                return Err("xcb: DPMS didn't answer".into());
+            }
+            return Err(XidlehookError::from(xcb::GenericError { ptr: err }).into());
+        }
+        let info = ((*reply).power_level, (*reply).state != 0);
+        libc::free(reply.cast());
+        info
+    };
+    if !enabled {
+        return Ok(DpmsState::Disabled);
+    }
+    Ok(DpmsState::from_power_level(level).ok_or("unknown DPMS power level")?)
+}
+
+/// Something that can tell the power state of the monitors
+pub trait DpmsSource {
+    /// Get the current power state
+    fn dpms_state(&self) -> Result<DpmsState>;
+}
+impl<S: DpmsSource + ?Sized> DpmsSource for std::rc::Rc<S> {
+    fn dpms_state(&self) -> Result<DpmsState> {
+        (**self).dpms_state()
+    }
+}
+
+/// See the module-level documentation
+pub struct NotWhenScreenOff<S: DpmsSource> {
+    source: S,
+    timers: Option<Vec<usize>>,
+    progress: Progress,
+}
+impl<S: DpmsSource> NotWhenScreenOff<S> {
+    /// Returns a module reading the power state off this source,
+    /// which is usually an `Rc<Xcb>`
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            timers: None,
+            progress: Progress::Abort,
+        }
+    }
+    /// Only skip these timers, by their index, rather than all of them
+    pub fn with_timers(self, timers: Vec<usize>) -> Self {
+        Self {
+            timers: Some(timers),
+            ..self
+        }
+    }
+    /// Decide what timers do while the monitors are asleep, which is
+    /// `Progress::Abort` by default, like `NotWhenFullscreen`
+    pub fn with_progress(self, progress: Progress) -> Self {
+        Self { progress, ..self }
+    }
+}
+impl<S: DpmsSource> Module for NotWhenScreenOff<S> {
+    fn pre_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        if let Some(ref timers) = self.timers {
+            if !timers.contains(&timer.index) {
+                return Ok(Progress::Continue);
+            }
+        }
+        if self.source.dpms_state()?.is_asleep() {
+            Ok(self.progress)
+        } else {
+            Ok(Progress::Continue)
+        }
+    }
+    fn peek_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.pre_timer(timer)
+    }
+    fn describe(&self) -> ModuleDescription {
+        let description =
+            ModuleDescription::new("not-when-screen-off").with("progress", self.progress.name());
+        match self.timers {
+            Some(ref timers) => {
+                let timers: Vec<String> = timers.iter().map(usize::to_string).collect();
+                description.with("timers", timers)
+            },
+            None => description,
+        }
+    }
+}
+impl<S: DpmsSource> fmt::Debug for NotWhenScreenOff<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "NotWhenScreenOff")
+    }
+}
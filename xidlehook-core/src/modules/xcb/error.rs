@@ -0,0 +1,90 @@
+//! The errors talking to the X server can fail with, so that callers
+//! can tell them apart, such as a missing extension from a broken
+//! connection.
+
+use super::property::MalformedProperty;
+
+use std::{error::Error, fmt};
+
+/// An error from the X server or the connection to it. Functions
+/// returning the crate's `Result` can be downcast to this.
+#[derive(Debug)]
+pub enum XidlehookError {
+    /// The X server on this display couldn't be connected to. The
+    /// display is empty if `$DISPLAY` isn't set.
+    Connect {
+        /// The display, like `:0`
+        display: String,
+        /// Why it failed
+        error: xcb::ConnError,
+    },
+    /// The connection to the X server broke
+    Connection(xcb::ConnError),
+    /// The X server has no screens
+    NoRoot,
+    /// The X server wouldn't intern an atom
+    MissingAtom(&'static str),
+    /// The X server doesn't support an extension that's needed
+    MissingExtension(&'static str),
+    /// The X server answered a request with an error
+    Reply(xcb::GenericError),
+    /// A window property was in the wrong format. Strings are decoded
+    /// lossily, so invalid UTF-8 never is one.
+    Malformed(MalformedProperty),
+}
+impl XidlehookError {
+    /// Returns true if the error is about a window that doesn't exist
+    /// (anymore)
+    pub fn is_bad_window(&self) -> bool {
+        match self {
+            Self::Reply(err) => super::is_bad_window(err),
+            _ => false,
+        }
+    }
+}
+impl From<xcb::ConnError> for XidlehookError {
+    fn from(err: xcb::ConnError) -> Self {
+        Self::Connection(err)
+    }
+}
+impl From<xcb::GenericError> for XidlehookError {
+    fn from(err: xcb::GenericError) -> Self {
+        Self::Reply(err)
+    }
+}
+impl From<MalformedProperty> for XidlehookError {
+    fn from(err: MalformedProperty) -> Self {
+        Self::Malformed(err)
+    }
+}
+impl fmt::Display for XidlehookError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Connect { display, error } if display.is_empty() => {
+                write!(f, "xcb: can't connect, DISPLAY is not set: {}", error)
+            },
+            Self::Connect { display, error } => {
+                write!(f, "xcb: can't connect to display {}: {}", display, error)
+            },
+            Self::Connection(err) => write!(f, "xcb: {}", err),
+            Self::NoRoot => write!(f, "no xcb root"),
+            Self::MissingAtom(name) => write!(f, "xcb: couldn't intern atom {}", name),
+            Self::MissingExtension(name) => {
+                write!(f, "xcb: the X server doesn't support {}", name)
+            },
+            Self::Reply(err) => write!(f, "{}", err),
+            Self::Malformed(err) => write!(f, "{}", err),
+        }
+    }
+}
+impl Error for XidlehookError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Connect { error, .. } => Some(error),
+            Self::Connection(err) => Some(err),
+            Self::Reply(err) => Some(err),
+            Self::Malformed(err) => Some(err),
+            _ => None,
+        }
+    }
+}
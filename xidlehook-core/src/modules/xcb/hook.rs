@@ -0,0 +1,165 @@
+//! Runs commands when a window enters or leaves fullscreen, such as to
+//! turn off the compositor or notifications while watching something.
+//! Windows are checked whenever a timer is about to run, so changes
+//! are noticed then rather than right away.
+
+use super::FullscreenOptions;
+use crate::{modules::ModuleDescription, Module, Progress, Result, TimerInfo};
+
+use std::{
+    convert::TryFrom,
+    fmt,
+    process::{Child, Command},
+    time::{Duration, Instant},
+};
+
+use log::{debug, info};
+
+/// How long a change has to last unless configured otherwise
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// Something that can tell whether a window is fullscreen
+pub trait FullscreenSource {
+    /// Returns true if any window is fullscreen with these options
+    fn is_fullscreen(&self, options: &FullscreenOptions) -> Result<bool>;
+}
+impl<S: FullscreenSource + ?Sized> FullscreenSource for std::rc::Rc<S> {
+    fn is_fullscreen(&self, options: &FullscreenOptions) -> Result<bool> {
+        (**self).is_fullscreen(options)
+    }
+}
+
+/// A command and the last process it was run as
+struct Hook {
+    cmd: Command,
+    child: Option<Child>,
+}
+impl Hook {
+    fn run(&mut self, name: &str) -> Result<()> {
+        // An error means someone else reaped it
+        if let Some(ref mut child) = self.child {
+            if let Ok(None) = child.try_wait() {
+                debug!(
+                    "The {} command is still running, not running it again",
+                    name
+                );
+                return Ok(());
+            }
+        }
+        info!("Running the {} command", name);
+        self.child = Some(self.cmd.spawn()?);
+        Ok(())
+    }
+}
+
+/// See the module-level documentation. It never keeps timers from
+/// running. The commands only run on a change, not for whether a
+/// window is fullscreen when it's first checked.
+pub struct FullscreenHook<S: FullscreenSource> {
+    source: S,
+    options: FullscreenOptions,
+    on_enter: Option<Hook>,
+    on_exit: Option<Hook>,
+    debounce: Duration,
+    fullscreen: Option<bool>,
+    /// A change that hasn't lasted long enough yet, and when it was
+    /// first seen
+    pending: Option<(bool, Instant)>,
+}
+impl<S: FullscreenSource> FullscreenHook<S> {
+    /// Returns a module checking windows with these options on this
+    /// source, which is usually an `Rc<Xcb>`, without any commands
+    pub fn new(source: S, options: FullscreenOptions) -> Self {
+        Self {
+            source,
+            options,
+            on_enter: None,
+            on_exit: None,
+            debounce: DEFAULT_DEBOUNCE,
+            fullscreen: None,
+            pending: None,
+        }
+    }
+    /// Run this command when a window enters fullscreen
+    pub fn with_on_enter(mut self, cmd: Command) -> Self {
+        self.on_enter = Some(Hook { cmd, child: None });
+        self
+    }
+    /// Run this command when no window is fullscreen anymore
+    pub fn with_on_exit(mut self, cmd: Command) -> Self {
+        self.on_exit = Some(Hook { cmd, child: None });
+        self
+    }
+    /// Only run a command once a change has been seen for this long,
+    /// so that flapping back and forth doesn't run them over and over.
+    /// Zero runs them on the first check seeing a change.
+    pub fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+    /// Returns whether a window was fullscreen the last time a change
+    /// was accepted, or `None` before the first check
+    pub fn is_fullscreen(&self) -> Option<bool> {
+        self.fullscreen
+    }
+    /// Check for a change now, running a command if one lasted long
+    /// enough
+    pub fn check(&mut self) -> Result<()> {
+        let fullscreen = self.source.is_fullscreen(&self.options)?;
+        let previous = match self.fullscreen {
+            Some(previous) => previous,
+            None => {
+                self.fullscreen = Some(fullscreen);
+                return Ok(());
+            },
+        };
+        if fullscreen == previous {
+            self.pending = None;
+            return Ok(());
+        }
+        let since = match self.pending {
+            Some((pending, since)) if pending == fullscreen => since,
+            _ => {
+                let now = Instant::now();
+                self.pending = Some((fullscreen, now));
+                now
+            },
+        };
+        if since.elapsed() < self.debounce {
+            return Ok(());
+        }
+
+        self.fullscreen = Some(fullscreen);
+        self.pending = None;
+        if fullscreen {
+            if let Some(ref mut hook) = self.on_enter {
+                hook.run("fullscreen enter")?;
+            }
+        } else if let Some(ref mut hook) = self.on_exit {
+            hook.run("fullscreen exit")?;
+        }
+        Ok(())
+    }
+}
+impl<S: FullscreenSource> Module for FullscreenHook<S> {
+    fn pre_timer(&mut self, _timer: TimerInfo) -> Result<Progress> {
+        self.check()?;
+        Ok(Progress::Continue)
+    }
+    fn describe(&self) -> ModuleDescription {
+        let debounce_ms = u64::try_from(self.debounce.as_millis()).unwrap_or(u64::MAX);
+        let mut description = ModuleDescription::new("fullscreen-hook");
+        if self.on_enter.is_some() {
+            description = description.with_secret("on_enter");
+        }
+        if self.on_exit.is_some() {
+            description = description.with_secret("on_exit");
+        }
+        description.with("debounce_ms", debounce_ms)
+    }
+}
+impl<S: FullscreenSource> fmt::Debug for FullscreenHook<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FullscreenHook({:?})", self.fullscreen)
+    }
+}
@@ -0,0 +1,1886 @@
+//! Various X-related utilities. The `Xcb` object must be used
+//! regardless of whether or not you want to use `NotWhenAudio` - it's
+//! xidlehook's simple way to obtain the idle time. The
+//! `NotWhenFullscreen` module is used to implement
+//! `--not-when-fullscreen` in the example client, and
+//! `NotWhenScreensaverDisabled` implements
+//! `--not-when-screensaver-disabled`, and `NotWhenRootProperty`
+//! implements `--not-when-root-property`.
+
+use crate::{
+    logging, modules::ModuleDescription, sources::IdleUpdates, IdleSource, Module, Progress,
+    Result, TimerInfo,
+};
+
+use std::{
+    cell::{Cell, Ref, RefCell},
+    collections::{HashMap, HashSet},
+    env, fmt,
+    os::unix::io::{AsRawFd, RawFd},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use log::{debug, info, trace, warn};
+use xcb::xproto::{GetPropertyCookie, GetPropertyReply};
+
+mod blank;
+mod cache;
+mod counter;
+mod dpms;
+mod error;
+mod hook;
+mod property;
+mod root_property;
+mod rule;
+mod screensaver;
+mod watch;
+mod window;
+
+pub use self::{
+    blank::{BlankTimer, Blanker, XcbBlanker},
+    cache::{WindowCache, DEFAULT_MAX_ENTRIES},
+    counter::{IdleCounter, WRAP_TOLERANCE},
+    dpms::{DpmsSource, DpmsState, NotWhenScreenOff},
+    error::XidlehookError,
+    hook::{FullscreenHook, FullscreenSource, DEFAULT_DEBOUNCE},
+    property::{decode_string, decode_u32s, MalformedProperty, OnMalformed},
+    root_property::{
+        is_inhibiting, NotWhenRootProperty, RootPropertySource, DEFAULT_INHIBIT_PROPERTY,
+    },
+    rule::{glob_matches, Pattern, Rule},
+    screensaver::{NotWhenScreensaverDisabled, ScreensaverSource, ScreensaverState},
+    watch::FullscreenWatch,
+    window::{
+        fullscreen_hits, is_fullscreen_through_modals, is_on_monitors, parse_wm_class,
+        resolve_client_window,
+        FullscreenHit, FullscreenOptions, Monitor, Rect, ScanMode, WindowInfo, WindowProperties,
+        WindowSearch, WindowTree, DEFAULT_MAX_DEPTH, STICKY_DESKTOP,
+    },
+};
+
+const WM_STATE: &str = "WM_STATE";
+const NET_WM_STATE: &str = "_NET_WM_STATE";
+const NET_WM_STATE_FULLSCREEN: &str = "_NET_WM_STATE_FULLSCREEN";
+const NET_WM_STATE_MODAL: &str = "_NET_WM_STATE_MODAL";
+const NET_WM_DESKTOP: &str = "_NET_WM_DESKTOP";
+const NET_CURRENT_DESKTOP: &str = "_NET_CURRENT_DESKTOP";
+const NET_ACTIVE_WINDOW: &str = "_NET_ACTIVE_WINDOW";
+const NET_WM_PID: &str = "_NET_WM_PID";
+const WM_WINDOW_ROLE: &str = "WM_WINDOW_ROLE";
+const NET_WM_NAME: &str = "_NET_WM_NAME";
+const UTF8_STRING: &str = "UTF8_STRING";
+/// Everything interned when connecting, in the order of `Session`'s
+/// fields
+const ATOMS: [&str; 11] = [
+    WM_STATE,
+    NET_WM_STATE,
+    NET_WM_STATE_FULLSCREEN,
+    NET_WM_STATE_MODAL,
+    NET_WM_DESKTOP,
+    NET_CURRENT_DESKTOP,
+    NET_ACTIVE_WINDOW,
+    NET_WM_PID,
+    WM_WINDOW_ROLE,
+    NET_WM_NAME,
+    UTF8_STRING,
+];
+
+/// How long to wait before trying to reconnect to the X server again,
+/// at first. This doubles after each failed attempt, up to the maximum.
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+/// Idle times within this many milliseconds of `u32::MAX` are
+/// suspicious: the X server's counter wraps around after 49.7 days.
+const IDLE_CEILING_MARGIN: u32 = 60 * 60 * 1000;
+
+/// Convert the `ms_since_user_input` of a `XScreenSaver` reply, which
+/// is an unsigned 32-bit number of milliseconds (a `CARD32`), to a
+/// duration. Values near the ceiling are logged, as they're more
+/// likely a server quirk than a user who's been away for weeks. See
+/// `IdleCounter` for when they have been.
+pub fn idle_from_ms_since_input(ms: u32) -> Duration {
+    if ms >= u32::MAX - IDLE_CEILING_MARGIN {
+        debug!(
+            "XScreenSaver reported an idle time of {} ms, close to the 32-bit limit",
+            ms
+        );
+    }
+    Duration::from_millis(u64::from(ms))
+}
+
+/// Log that a window counts as fullscreen, along with the properties
+/// an exception could match it by
+fn log_fullscreen(window: xcb::Window, props: &WindowProperties) {
+    let desktop = props
+        .desktop
+        .map_or_else(|| String::from("none"), |desktop| desktop.to_string());
+    logging::log_event(
+        log::Level::Debug,
+        "fullscreen_window",
+        &[
+            ("window", window.into()),
+            ("instance", props.instance().into()),
+            ("class", props.class().into()),
+            ("name", props.wm_name.as_str().into()),
+            ("role", props.wm_role.as_deref().unwrap_or_default().into()),
+            ("desktop", desktop.as_str().into()),
+        ],
+    );
+}
+
+/// Returns true if the error means the window doesn't exist (any
+/// more)
+fn is_bad_window(err: &xcb::GenericError) -> bool {
+    err.error_code() == xcb::xproto::WINDOW
+}
+
+/// Decode a property as a list of 32-bit values, which are empty if
+/// the property has a different format. This is for properties which
+/// don't belong to a single window, and thus have no window to skip.
+fn values_u32(reply: &GetPropertyReply) -> Vec<u32> {
+    decode_u32s(reply.format(), reply.value(), OnMalformed::Skip).unwrap_or_default()
+}
+
+/// Decode a window property as a list of 32-bit values
+fn window_values_u32(
+    reply: &GetPropertyReply,
+    policy: OnMalformed,
+) -> Result<Vec<u32>, XidlehookError> {
+    Ok(decode_u32s(reply.format(), reply.value(), policy)?)
+}
+
+/// Decode a window property as a string
+fn window_value_string(
+    reply: &GetPropertyReply,
+    policy: OnMalformed,
+) -> Result<String, XidlehookError> {
+    Ok(decode_string(reply.format(), reply.value(), policy)?)
+}
+
+/// Apply the `OnMalformed::Skip` policy: a window with a malformed
+/// property is logged and treated like it has no properties to check,
+/// which is returned as `None`
+fn skip_malformed<T>(
+    window: xcb::Window,
+    result: Result<T, XidlehookError>,
+    policy: OnMalformed,
+) -> Result<Option<T>, XidlehookError> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(XidlehookError::Malformed(err)) if policy == OnMalformed::Skip => {
+            warn!("Ignoring window {}: {}", window, err);
+            Ok(None)
+        },
+        Err(err) => Err(err),
+    }
+}
+
+/// A window checked for fullscreen doesn't exist, which is an error
+/// with `error_on_stale_window`
+fn stale_window(window: xcb::Window, options: &FullscreenOptions) -> Result<bool> {
+    if options.error_on_stale_window {
+        Err(format!("window {} does not exist (BadWindow)", window).into())
+    } else {
+        Ok(false)
+    }
+}
+
+/// The requests for the properties of a window, which were sent but
+/// not answered yet, see `Xcb::request_properties`
+struct PropertyCookies<'a> {
+    window: xcb::Window,
+    net_wm_state: GetPropertyCookie<'a>,
+    wm_state: GetPropertyCookie<'a>,
+    desktop: GetPropertyCookie<'a>,
+    net_wm_name: GetPropertyCookie<'a>,
+    wm_name: GetPropertyCookie<'a>,
+    transient_for: GetPropertyCookie<'a>,
+    wm_role: GetPropertyCookie<'a>,
+    pid: GetPropertyCookie<'a>,
+    wm_class: WmClass<'a>,
+}
+
+/// What checking windows for fullscreen needs to know about their root
+/// window, read once per screen and search rather than for every
+/// window checked
+struct RootProperties {
+    /// The active window, if it was asked for and the window manager
+    /// tells
+    active: Option<xcb::Window>,
+    /// See `Xcb::root_properties`
+    current_desktops: Vec<u32>,
+    /// See `Xcb::selected_monitors`
+    monitors: Option<Vec<Monitor>>,
+}
+
+/// A `WM_CLASS` that's either cached or requested
+enum WmClass<'a> {
+    Cached(Vec<String>),
+    Pending(GetPropertyCookie<'a>),
+}
+
+/// What happened to the connection to the X server, as told to the
+/// function given to `Xcb::set_connection_observer`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionEvent {
+    /// The connection broke. This is told once, before the first
+    /// attempt to reconnect.
+    Lost,
+    /// The connection was re-established
+    Regained {
+        /// How many attempts it took, including the one that worked
+        attempts: u64,
+    },
+}
+
+type ConnectionFn = dyn FnMut(ConnectionEvent);
+
+/// Which window the idle time is measured against
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IdleTarget {
+    /// The root window of the first screen
+    #[default]
+    FirstScreen,
+    /// The root window of the screen with this index
+    Screen(usize),
+    /// A specific drawable
+    Window(xcb::Window),
+}
+impl IdleTarget {
+    /// Returns the window to query, given the root windows of all
+    /// screens
+    pub fn resolve(self, roots: &[xcb::Window]) -> Result<xcb::Window> {
+        match self {
+            Self::FirstScreen => Ok(*roots.first().ok_or(XidlehookError::NoRoot)?),
+            Self::Screen(index) => roots.get(index).copied().ok_or_else(|| {
+                format!("there is no screen {}, only {}", index, roots.len()).into()
+            }),
+            Self::Window(window) => Ok(window),
+        }
+    }
+}
+
+/// How the idle times of several screens are combined into one. This
+/// only matters with classic multi-screen X, where each screen has a
+/// root window of its own; multiple monitors usually make up a single
+/// screen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IdleAggregate {
+    /// The shortest idle time, so that activity on any screen counts
+    #[default]
+    Min,
+    /// The longest idle time, so that the user counts as active only
+    /// while there's activity on every screen
+    Max,
+    /// The idle time of the screen with this index
+    Screen(usize),
+}
+impl IdleAggregate {
+    /// Combine the idle times of all screens, in order
+    pub fn aggregate(self, idle: &[Duration]) -> Result<Duration> {
+        self.combine(idle)
+    }
+    /// Like `aggregate`, but for the server's raw millisecond counters
+    pub fn aggregate_ms(self, idle: &[u32]) -> Result<u32> {
+        self.combine(idle)
+    }
+    fn combine<T: Ord + Copy>(self, idle: &[T]) -> Result<T> {
+        let combined = match self {
+            Self::Min => idle.iter().min(),
+            Self::Max => idle.iter().max(),
+            Self::Screen(index) => idle.get(index),
+        };
+        combined.copied().ok_or_else(|| match self {
+            Self::Screen(index) => {
+                format!("there is no screen {}, only {}", index, idle.len()).into()
+            },
+            _ => XidlehookError::NoRoot.into(),
+        })
+    }
+}
+
+/// Intern all these atoms in one round-trip, by sending every request
+/// before waiting for any of the replies. Counts the flushes in
+/// `flushes`.
+fn intern_atoms<const N: usize>(
+    conn: &xcb::Connection,
+    names: [&'static str; N],
+    flushes: &mut u32,
+) -> Result<[xcb::Atom; N], XidlehookError> {
+    let cookies: Vec<_> = names
+        .iter()
+        .map(|name| xcb::xproto::intern_atom(conn, false, name))
+        .collect();
+    conn.flush();
+    *flushes = flushes.saturating_add(1);
+
+    let mut atoms = [xcb::NONE; N];
+    for ((atom, cookie), name) in atoms.iter_mut().zip(cookies).zip(names) {
+        *atom = cookie.get_reply()?.atom();
+        if *atom == xcb::NONE {
+            return Err(XidlehookError::MissingAtom(name));
+        }
+    }
+    Ok(atoms)
+}
+
+/// The X connection, along with what was learned from the server when
+/// connecting. It's replaced as a whole when reconnecting.
+struct Session {
+    conn: xcb::Connection,
+    root_window: xcb::Window,
+    // Aside from being a property, WM_STATE is also a
+    // type that is not present in the xcb bindings.
+    type_wm_state: u32,
+    atom_net_wm_state: xcb::Atom,
+    atom_net_wm_state_fullscreen: xcb::Atom,
+    atom_net_wm_state_modal: xcb::Atom,
+    atom_net_wm_desktop: xcb::Atom,
+    atom_net_current_desktop: xcb::Atom,
+    atom_net_active_window: xcb::Atom,
+    atom_net_wm_pid: xcb::Atom,
+    atom_wm_window_role: xcb::Atom,
+    atom_net_wm_name: xcb::Atom,
+    atom_utf8_string: xcb::Atom,
+    /// Whether the server supports MIT-SCREEN-SAVER
+    screensaver: bool,
+    /// Whether the server supports RandR 1.3, for listing monitors
+    randr: bool,
+    /// The major opcode of DPMS, if the server supports it
+    dpms: Option<u8>,
+    /// How often the connection was flushed while interning the atoms
+    flushes: u32,
+}
+impl Session {
+    fn connect(display: Option<&str>) -> Result<Self, XidlehookError> {
+        let connect_error = |error| XidlehookError::Connect {
+            display: display
+                .map(String::from)
+                .or_else(|| env::var("DISPLAY").ok())
+                .unwrap_or_default(),
+            error,
+        };
+        if display.is_some_and(|display| display.contains('\0')) {
+            return Err(connect_error(xcb::ConnError::ClosedParseErr));
+        }
+        let (conn, _) = xcb::Connection::connect(display).map_err(connect_error)?;
+
+        let setup = conn.get_setup();
+        let screen = setup.roots().next().ok_or(XidlehookError::NoRoot)?;
+        let root_window = screen.root();
+
+        let mut flushes = 0;
+        let atoms = intern_atoms(&conn, ATOMS, &mut flushes)?;
+        let screensaver = conn
+            .get_extension_data(xcb::screensaver::id())
+            .is_some_and(|ext| ext.present());
+        // The server wants to know which version is spoken before
+        // answering newer requests
+        let randr = conn
+            .get_extension_data(xcb::randr::id())
+            .is_some_and(|ext| ext.present())
+            && xcb::randr::query_version(&conn, 1, 3)
+                .get_reply()
+                .is_ok_and(|version| (version.major_version(), version.minor_version()) >= (1, 3));
+
+        let dpms = xcb::xproto::query_extension(&conn, dpms::EXTENSION)
+            .get_reply()
+            .ok()
+            .filter(|ext| ext.present())
+            .map(|ext| ext.major_opcode());
+
+        Ok(Self {
+            type_wm_state: atoms[0],
+            atom_net_wm_state: atoms[1],
+            atom_net_wm_state_fullscreen: atoms[2],
+            atom_net_wm_state_modal: atoms[3],
+            atom_net_wm_desktop: atoms[4],
+            atom_net_current_desktop: atoms[5],
+            atom_net_active_window: atoms[6],
+            atom_net_wm_pid: atoms[7],
+            atom_wm_window_role: atoms[8],
+            atom_net_wm_name: atoms[9],
+            atom_utf8_string: atoms[10],
+            flushes,
+            screensaver,
+            randr,
+            dpms,
+            root_window,
+            conn,
+        })
+    }
+
+    /// Ask MIT-SCREEN-SAVER about this drawable
+    fn query_screensaver(
+        &self,
+        drawable: xcb::Drawable,
+    ) -> Result<xcb::screensaver::QueryInfoReply, XidlehookError> {
+        if !self.screensaver {
+            return Err(XidlehookError::MissingExtension("MIT-SCREEN-SAVER"));
+        }
+        Ok(xcb::screensaver::query_info(&self.conn, drawable).get_reply()?)
+    }
+
+    fn roots(&self) -> Vec<xcb::Window> {
+        self.conn.get_setup().roots().map(|s| s.root()).collect()
+    }
+
+    fn get_property(
+        &self,
+        window: xcb::Window,
+        property: xcb::Atom,
+        type_: xcb::Atom,
+    ) -> Result<GetPropertyReply, xcb::GenericError> {
+        self.request_property(window, property, type_).get_reply()
+    }
+
+    /// Send a request for a property without waiting for the reply, so
+    /// several requests can be answered in one round-trip
+    fn request_property(
+        &self,
+        window: xcb::Window,
+        property: xcb::Atom,
+        type_: xcb::Atom,
+    ) -> GetPropertyCookie<'_> {
+        xcb::xproto::get_property(
+            &self.conn, // c
+            false,      // delete
+            window,     // window
+            property,   // property
+            type_,      // type_
+            0,          // long_offset
+            u32::MAX,   // long_length
+        )
+    }
+}
+
+/// See the crate-level documentation. If the connection to the X
+/// server breaks, such as when it restarts, `Xcb` reconnects the next
+/// time the idle time or fullscreen windows are asked for, waiting
+/// longer between each failed attempt.
+pub struct Xcb {
+    session: RefCell<Rc<Session>>,
+    /// What idle time is queried for, if set. Otherwise, the idle
+    /// times of all screens are combined.
+    idle_target: Option<IdleTarget>,
+    /// The window `idle_target` resolved to
+    idle_window: Cell<Option<xcb::Window>>,
+    idle_aggregate: IdleAggregate,
+    wm_class_cache: RefCell<WindowCache<Vec<String>>>,
+    /// Follows each drawable's idle counter to see it wrap around
+    idle_counters: RefCell<HashMap<xcb::Drawable, IdleCounter>>,
+    reconnects: Cell<u64>,
+    /// How many times reconnecting failed since the connection broke
+    failed_attempts: Cell<u64>,
+    /// Told when the connection breaks and is re-established
+    connection_observer: RefCell<Option<Box<ConnectionFn>>>,
+    /// When to try reconnecting again, after failing to
+    retry_at: Cell<Option<Instant>>,
+    retry_delay: Cell<Duration>,
+    /// The display to connect to, instead of `$DISPLAY`
+    display: Option<String>,
+    /// Counts the ticks started with `refresh`, see
+    /// `find_fullscreen_cached`
+    tick: Cell<u64>,
+    /// What `find_fullscreen_cached` found during the tick
+    /// `fullscreen_cache_tick`, by the options
+    fullscreen_cache: RefCell<Vec<(FullscreenOptions, Option<xcb::Window>)>>,
+    fullscreen_cache_tick: Cell<u64>,
+    fullscreen_scans: Cell<u64>,
+}
+impl Xcb {
+    /// Initialize all the things, like setting up an X connection.
+    ///
+    /// The idle time comes from the MIT-SCREEN-SAVER extension, so this
+    /// fails with `XidlehookError::MissingExtension` if the X server
+    /// doesn't support it, rather than on every poll. That's the place
+    /// to fall back to another idle source, like XInput 2 or Wayland,
+    /// using `without_screensaver` for the rest.
+    pub fn new() -> Result<Self> {
+        Self::with_display(None)
+    }
+    /// Like `new`, but connect to this display, like `:1`, instead of
+    /// the one in `$DISPLAY`. This is also the display reconnected to.
+    pub fn with_display(display: Option<&str>) -> Result<Self> {
+        let xcb = Self::without_screensaver(display)?;
+        if !xcb.session().screensaver {
+            return Err(XidlehookError::MissingExtension("MIT-SCREEN-SAVER").into());
+        }
+        Ok(xcb)
+    }
+    /// Like `with_display`, but don't require MIT-SCREEN-SAVER, for when
+    /// the idle time comes from somewhere else. Asking for it, or the
+    /// screensaver state, then fails.
+    pub fn without_screensaver(display: Option<&str>) -> Result<Self> {
+        Ok(Self {
+            session: RefCell::new(Rc::new(Session::connect(display)?)),
+            idle_target: None,
+            idle_window: Cell::new(None),
+            idle_aggregate: IdleAggregate::default(),
+            wm_class_cache: RefCell::new(WindowCache::default()),
+            idle_counters: RefCell::new(HashMap::new()),
+            reconnects: Cell::new(0),
+            failed_attempts: Cell::new(0),
+            connection_observer: RefCell::new(None),
+            retry_at: Cell::new(None),
+            retry_delay: Cell::new(MIN_RECONNECT_DELAY),
+            display: display.map(String::from),
+            tick: Cell::new(0),
+            fullscreen_cache: RefCell::new(Vec::new()),
+            fullscreen_cache_tick: Cell::new(0),
+            fullscreen_scans: Cell::new(0),
+        })
+    }
+    /// The atoms interned when connecting, by name
+    pub fn atoms(&self) -> Vec<(&'static str, xcb::Atom)> {
+        let session = self.session();
+        vec![
+            (WM_STATE, session.type_wm_state),
+            (NET_WM_STATE, session.atom_net_wm_state),
+            (
+                NET_WM_STATE_FULLSCREEN,
+                session.atom_net_wm_state_fullscreen,
+            ),
+            (NET_WM_STATE_MODAL, session.atom_net_wm_state_modal),
+            (NET_WM_DESKTOP, session.atom_net_wm_desktop),
+            (NET_CURRENT_DESKTOP, session.atom_net_current_desktop),
+            (NET_ACTIVE_WINDOW, session.atom_net_active_window),
+            (NET_WM_PID, session.atom_net_wm_pid),
+            (WM_WINDOW_ROLE, session.atom_wm_window_role),
+            (NET_WM_NAME, session.atom_net_wm_name),
+            (UTF8_STRING, session.atom_utf8_string),
+        ]
+    }
+    /// How often the connection was flushed to intern the atoms when
+    /// connecting. They're all requested before waiting for any reply,
+    /// so this is 1.
+    pub fn atom_flushes(&self) -> u32 {
+        self.session().flushes
+    }
+    /// The display connected to, unless it's the one in `$DISPLAY`
+    pub fn display(&self) -> Option<&str> {
+        self.display.as_deref()
+    }
+    fn session(&self) -> Rc<Session> {
+        Rc::clone(&self.session.borrow())
+    }
+    /// Returns false if the connection to the X server broke
+    pub fn is_connected(&self) -> bool {
+        self.session().conn.has_error().is_ok()
+    }
+    /// How many times the connection was re-established. The file
+    /// descriptor, see `as_raw_fd`, changes each time.
+    pub fn reconnects(&self) -> u64 {
+        self.reconnects.get()
+    }
+    /// Call this function when the connection to the X server breaks,
+    /// and again when it's re-established, such as to show it in a
+    /// status bar. It's called from whichever call noticed, and must
+    /// not call this `Xcb` back. Replaces any function set before.
+    pub fn set_connection_observer<F>(&self, observer: F)
+    where
+        F: FnMut(ConnectionEvent) + 'static,
+    {
+        *self.connection_observer.borrow_mut() = Some(Box::new(observer));
+    }
+    /// Stop telling anyone about the connection, see
+    /// `set_connection_observer`
+    pub fn clear_connection_observer(&self) {
+        *self.connection_observer.borrow_mut() = None;
+    }
+    fn notify_connection(&self, event: ConnectionEvent) {
+        if let Some(ref mut observer) = *self.connection_observer.borrow_mut() {
+            observer(event);
+        }
+    }
+    /// Connect to the X server again, unless the last attempt failed
+    /// too recently
+    fn reconnect(&self) -> Result<()> {
+        let now = Instant::now();
+        if self.retry_at.get().is_some_and(|at| now < at) {
+            return Err("xcb: not reconnecting to the X server yet".into());
+        }
+        let session = match Session::connect(self.display()) {
+            Ok(session) => session,
+            Err(err) => {
+                self.failed_attempts
+                    .set(self.failed_attempts.get().saturating_add(1));
+                let delay = self.retry_delay.get();
+                self.retry_at.set(now.checked_add(delay));
+                self.retry_delay
+                    .set(delay.saturating_mul(2).min(MAX_RECONNECT_DELAY));
+                warn!(
+                    "Couldn't reconnect to the X server, trying again in {:?}: {}",
+                    delay, err
+                );
+                return Err(err.into());
+            },
+        };
+        info!("Reconnected to the X server");
+        if let Some(target) = self.idle_target {
+            match target.resolve(&session.roots()) {
+                Ok(window) => self.idle_window.set(Some(window)),
+                Err(err) => warn!("Couldn't find the idle target again: {}", err),
+            }
+        }
+        *self.session.borrow_mut() = Rc::new(session);
+        // Window IDs may be reused by the new server
+        self.wm_class_cache
+            .borrow_mut()
+            .retain_present(&HashSet::new());
+        self.idle_counters.borrow_mut().clear();
+        self.reconnects.set(self.reconnects.get().saturating_add(1));
+        self.retry_at.set(None);
+        self.retry_delay.set(MIN_RECONNECT_DELAY);
+        let attempts = self.failed_attempts.replace(0).saturating_add(1);
+        self.notify_connection(ConnectionEvent::Regained { attempts });
+        Ok(())
+    }
+    /// Run `f`, and if it failed because the connection broke, run it
+    /// once more after reconnecting
+    fn reconnecting<T>(&self, f: impl Fn() -> Result<T>) -> Result<T> {
+        match f() {
+            Err(err) if !self.is_connected() => {
+                if self.retry_at.get().is_none() {
+                    warn!("Lost the connection to the X server: {}", err);
+                    self.notify_connection(ConnectionEvent::Lost);
+                }
+                self.reconnect()
+                    .map_err(|_| format!("xcb: the connection broke: {}", err))?;
+                f()
+            },
+            result => result,
+        }
+    }
+    /// Send any requests that are still buffered, such as to blank
+    /// the screen, and disconnect. Dropping the connection does the
+    /// same, but can't report if the X server went away before the
+    /// requests reached it.
+    pub fn close(self) -> Result<()> {
+        let session = self.session();
+        if session.conn.flush() {
+            Ok(())
+        } else {
+            session.conn.has_error().map_err(XidlehookError::from)?;
+            Err("xcb: the connection broke before it was closed".into())
+        }
+    }
+    /// Get the user's idle time using the `XScreenSaver` plugin. With
+    /// several screens, their idle times are combined as configured by
+    /// `set_idle_aggregate`, unless `set_idle_target` picked a window.
+    pub fn get_idle(&self) -> Result<Duration> {
+        self.reconnecting(|| match self.idle_window.get() {
+            Some(window) => self.get_idle_of(window),
+            None => self.idle_aggregate.aggregate(&self.get_idle_of_screens()?),
+        })
+    }
+    /// Measure the idle time every `interval`, as an iterator that
+    /// never ends, for programs that don't need timers. Like with
+    /// `get_idle`, a lost connection is re-established while iterating,
+    /// and yields errors until then. See `sources::IdleUpdates`.
+    pub fn idle_updates(&self, interval: Duration) -> IdleUpdates<&Self> {
+        IdleUpdates::new(self, interval)
+    }
+    /// Like `get_idle`, but the server's counter as is: an unsigned
+    /// 32-bit number of milliseconds, which unlike `get_idle` starts
+    /// over at zero when it wraps around.
+    pub fn get_idle_ms(&self) -> Result<u32> {
+        self.reconnecting(|| match self.idle_window.get() {
+            Some(window) => self.get_idle_ms_of(window),
+            None => {
+                let idle = self
+                    .session()
+                    .roots()
+                    .into_iter()
+                    .map(|root| self.get_idle_ms_of(root))
+                    .collect::<Result<Vec<u32>>>()?;
+                self.idle_aggregate.aggregate_ms(&idle)
+            },
+        })
+    }
+    /// Get the idle time of the screen with this index
+    pub fn get_idle_for_screen(&self, index: usize) -> Result<Duration> {
+        self.get_idle_of(IdleTarget::Screen(index).resolve(&self.session().roots())?)
+    }
+    /// Get the idle times of all screens, in order
+    pub fn get_idle_of_screens(&self) -> Result<Vec<Duration>> {
+        self.session()
+            .roots()
+            .into_iter()
+            .map(|root| self.get_idle_of(root))
+            .collect()
+    }
+    /// Get the idle time of the screen with this root window, such as
+    /// for one seat of a multi-seat machine with a screen per seat.
+    /// Fails if it isn't the root of any screen.
+    pub fn get_idle_for_root(&self, root: xcb::Window) -> Result<Duration> {
+        if !self.session().roots().contains(&root) {
+            return Err(format!("window {} isn't the root of any screen", root).into());
+        }
+        self.reconnecting(|| self.get_idle_of(root))
+    }
+    /// Get the idle time of every screen, by its root window, so that
+    /// each seat can be handled on its own. MIT-SCREEN-SAVER answers
+    /// for each screen separately, and screens it has no answer for
+    /// are left out, so this is empty where it isn't supported. Only
+    /// a broken connection is an error.
+    pub fn get_idle_all(&self) -> Result<HashMap<xcb::Window, Duration>> {
+        self.reconnecting(|| {
+            let mut idle = HashMap::new();
+            for root in self.session().roots() {
+                match self.get_idle_of(root) {
+                    Ok(duration) => {
+                        idle.insert(root, duration);
+                    },
+                    Err(err) if self.is_connected() => {
+                        debug!("Leaving out the idle time of root {}: {}", root, err);
+                    },
+                    Err(err) => return Err(err),
+                }
+            }
+            Ok(idle)
+        })
+    }
+    /// Like `get_idle`, but for a specific drawable instead of the
+    /// configured one. Idle times keep going up when the server's
+    /// counter wraps around, see `IdleCounter`.
+    pub fn get_idle_of(&self, drawable: xcb::Drawable) -> Result<Duration> {
+        let ms = self.get_idle_ms_of(drawable)?;
+        Ok(self
+            .idle_counters
+            .borrow_mut()
+            .entry(drawable)
+            .or_default()
+            .observe(ms))
+    }
+    /// Like `get_idle_ms`, but for a specific drawable
+    pub fn get_idle_ms_of(&self, drawable: xcb::Drawable) -> Result<u32> {
+        let info = self.session().query_screensaver(drawable)?;
+        Ok(info.ms_since_user_input())
+    }
+    /// Measure idle time against this target from now on. A specific
+    /// window must exist.
+    pub fn set_idle_target(&mut self, target: IdleTarget) -> Result<()> {
+        let session = self.session();
+        let window = target.resolve(&session.roots())?;
+        if let IdleTarget::Window(window) = target {
+            xcb::xproto::get_geometry(&session.conn, window)
+                .get_reply()
+                .map_err(|_| format!("window {} is not a valid drawable", window))?;
+        }
+        self.idle_target = Some(target);
+        self.idle_window.set(Some(window));
+        Ok(())
+    }
+    /// The window idle time is queried for, if any. See `get_idle`.
+    pub fn idle_window(&self) -> Option<xcb::Window> {
+        self.idle_window.get()
+    }
+    /// Measure idle time by combining all screens from now on, which
+    /// undoes `set_idle_target`
+    pub fn set_idle_aggregate(&mut self, aggregate: IdleAggregate) {
+        self.idle_target = None;
+        self.idle_window.set(None);
+        self.idle_aggregate = aggregate;
+    }
+    /// How the idle times of all screens are combined
+    pub fn idle_aggregate(&self) -> IdleAggregate {
+        self.idle_aggregate
+    }
+
+    /// Read the index of the desktop shown on the first screen off
+    /// its root window's `_NET_CURRENT_DESKTOP`, or `None` if the
+    /// window manager doesn't say. Window managers with a workspace
+    /// per monitor may list several, of which this is the first.
+    pub fn current_desktop(&self) -> Result<Option<u32>> {
+        self.reconnecting(|| {
+            let session = self.session();
+            let root = match session.roots().first() {
+                Some(&root) => root,
+                None => return Err(XidlehookError::NoRoot.into()),
+            };
+            let reply = session.get_property(
+                root,
+                session.atom_net_current_desktop,
+                xcb::xproto::ATOM_CARDINAL,
+            )?;
+            Ok(values_u32(&reply).first().copied())
+        })
+    }
+
+    /// Read what checking windows for fullscreen needs off a root
+    /// window: the current desktops, of which there's usually one but
+    /// window managers with a workspace per monitor may list several,
+    /// the selected monitors, and the active window if `active` is
+    /// set. The properties are read in one round-trip.
+    fn root_properties(
+        &self,
+        root: xcb::Window,
+        options: &FullscreenOptions,
+        active: bool,
+    ) -> Result<RootProperties> {
+        let session = self.session();
+        let active = if active {
+            Some(session.request_property(
+                root,
+                session.atom_net_active_window,
+                xcb::xproto::ATOM_WINDOW,
+            ))
+        } else {
+            None
+        };
+        let current_desktops = session.request_property(
+            root,
+            session.atom_net_current_desktop,
+            xcb::xproto::ATOM_CARDINAL,
+        );
+        let active = match active {
+            Some(cookie) => values_u32(&cookie.get_reply()?)
+                .first()
+                .copied()
+                .filter(|&window| window != xcb::NONE),
+            None => None,
+        };
+        Ok(RootProperties {
+            active,
+            current_desktops: values_u32(&current_desktops.get_reply()?),
+            monitors: self.selected_monitors(root, options),
+        })
+    }
+
+    /// Read the active window off a root window
+    fn active_window(&self, root: xcb::Window) -> Result<Option<xcb::Window>> {
+        let session = self.session();
+        let reply = session.get_property(
+            root,
+            session.atom_net_active_window,
+            xcb::xproto::ATOM_WINDOW,
+        )?;
+        Ok(values_u32(&reply)
+            .first()
+            .copied()
+            .filter(|&window| window != xcb::NONE))
+    }
+
+    /// List the RandR outputs showing part of a root window, if
+    /// `options.monitors` is set. Returns `None` if they can't be
+    /// listed, such as without RandR, which means all of them count.
+    fn selected_monitors(
+        &self,
+        root: xcb::Window,
+        options: &FullscreenOptions,
+    ) -> Option<Vec<Monitor>> {
+        let session = self.session();
+        if options.monitors.is_none() || !session.randr {
+            return None;
+        }
+        let list = || -> Result<Vec<Monitor>, xcb::GenericError> {
+            let resources =
+                xcb::randr::get_screen_resources_current(&session.conn, root).get_reply()?;
+            let timestamp = resources.config_timestamp();
+            let cookies: Vec<_> = resources
+                .outputs()
+                .iter()
+                .map(|&output| xcb::randr::get_output_info(&session.conn, output, timestamp))
+                .collect();
+            let mut monitors = Vec::new();
+            for cookie in cookies {
+                let output = cookie.get_reply()?;
+                // Outputs which are off aren't showing anything
+                if output.crtc() == xcb::NONE {
+                    continue;
+                }
+                let crtc = xcb::randr::get_crtc_info(&session.conn, output.crtc(), timestamp)
+                    .get_reply()?;
+                monitors.push(Monitor {
+                    name: String::from_utf8_lossy(output.name()).into_owned(),
+                    rect: Rect {
+                        x: crtc.x().into(),
+                        y: crtc.y().into(),
+                        width: crtc.width().into(),
+                        height: crtc.height().into(),
+                    },
+                });
+            }
+            Ok(monitors)
+        };
+        match list() {
+            Ok(monitors) => Some(monitors),
+            Err(err) => {
+                debug!("Can't list RandR outputs, counting all of them: {}", err);
+                None
+            },
+        }
+    }
+
+    /// Find out which part of the screen a window covers
+    fn window_rect(&self, window: xcb::Window) -> Result<Rect, XidlehookError> {
+        let session = self.session();
+        let geometry = xcb::xproto::get_geometry(&session.conn, window).get_reply()?;
+        let origin =
+            xcb::xproto::translate_coordinates(&session.conn, window, geometry.root(), 0, 0)
+                .get_reply()?;
+        Ok(Rect {
+            x: origin.dst_x().into(),
+            y: origin.dst_y().into(),
+            width: geometry.width().into(),
+            height: geometry.height().into(),
+        })
+    }
+
+    /// Returns true if a window is on one of the outputs in
+    /// `options.monitors`, given those of its screen. A window which
+    /// no longer exists isn't.
+    fn on_selected_monitor(
+        &self,
+        window: xcb::Window,
+        monitors: Option<&[Monitor]>,
+        options: &FullscreenOptions,
+    ) -> Result<bool> {
+        let selected = match (&options.monitors, monitors) {
+            (Some(selected), Some(_)) => selected,
+            _ => return Ok(true),
+        };
+        match self.window_rect(window) {
+            Ok(rect) => Ok(is_on_monitors(rect, monitors, selected)),
+            Err(ref err) if err.is_bad_window() => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Follow the parents of a window until one bearing `WM_STATE` or
+    /// `_NET_WM_STATE` is found. See `resolve_client_window`.
+    pub fn resolve_client_window(&self, window: xcb::Window) -> Result<xcb::Window> {
+        resolve_client_window(self, window)
+    }
+
+    /// Read the class, title, desktop and fullscreen state of a
+    /// window, decoded the same way as for `get_fullscreen`.
+    /// Properties in the wrong format are decoded as well as possible.
+    pub fn window_info(&self, window: xcb::Window) -> Result<WindowInfo> {
+        self.reconnecting(|| {
+            let props = self.window_properties(window, OnMalformed::BestEffort)?;
+            Ok(props.into())
+        })
+    }
+
+    /// Like `window_info`, for the client window of the first screen's
+    /// `_NET_ACTIVE_WINDOW`. Returns `None` if the window manager
+    /// doesn't set it.
+    pub fn active_window_info(&self) -> Result<Option<WindowInfo>> {
+        self.reconnecting(|| {
+            let root = match self.session().roots().first() {
+                Some(&root) => root,
+                None => return Err(XidlehookError::NoRoot.into()),
+            };
+            let active = match self.active_window(root)? {
+                Some(active) => self.resolve_client_window(active)?,
+                None => return Ok(None),
+            };
+            let props = self.window_properties(active, OnMalformed::BestEffort)?;
+            Ok(Some(props.into()))
+        })
+    }
+
+    /// Fetch the properties needed to determine whether a window is
+    /// fullscreen
+    fn window_properties(
+        &self,
+        window: xcb::Window,
+        policy: OnMalformed,
+    ) -> Result<WindowProperties, XidlehookError> {
+        let session = self.session();
+        self.receive_properties(&session, self.request_properties(&session, window), policy)
+    }
+
+    /// Fetch the properties of several windows, sending all requests
+    /// before waiting for any reply
+    fn windows_properties(
+        &self,
+        windows: &[xcb::Window],
+        policy: OnMalformed,
+    ) -> Vec<Result<Option<WindowProperties>, XidlehookError>> {
+        let session = self.session();
+        let cookies: Vec<_> = windows
+            .iter()
+            .map(|&window| self.request_properties(&session, window))
+            .collect();
+        cookies
+            .into_iter()
+            .map(|cookies| {
+                let window = cookies.window;
+                skip_malformed(
+                    window,
+                    self.receive_properties(&session, cookies, policy),
+                    policy,
+                )
+            })
+            .collect()
+    }
+
+    /// Send the requests for the properties of a window, see
+    /// `receive_properties`. `WM_CLASS` is only requested if it's not
+    /// cached, since applications set it before mapping a window and
+    /// don't change it afterwards.
+    fn request_properties<'a>(
+        &self,
+        session: &'a Session,
+        window: xcb::Window,
+    ) -> PropertyCookies<'a> {
+        let wm_class = match self.wm_class_cache.borrow_mut().get(window) {
+            Some(wm_class) => WmClass::Cached(wm_class.clone()),
+            None => WmClass::Pending(session.request_property(
+                window,
+                xcb::xproto::ATOM_WM_CLASS,
+                xcb::xproto::ATOM_STRING,
+            )),
+        };
+        PropertyCookies {
+            window,
+            net_wm_state: session.request_property(
+                window,
+                session.atom_net_wm_state,
+                xcb::xproto::ATOM_ATOM,
+            ),
+            wm_state: session.request_property(
+                window,
+                session.type_wm_state,
+                xcb::xproto::ATOM_ANY,
+            ),
+            desktop: session.request_property(
+                window,
+                session.atom_net_wm_desktop,
+                xcb::xproto::ATOM_CARDINAL,
+            ),
+            net_wm_name: session.request_property(
+                window,
+                session.atom_net_wm_name,
+                session.atom_utf8_string,
+            ),
+            wm_name: session.request_property(
+                window,
+                xcb::xproto::ATOM_WM_NAME,
+                xcb::xproto::ATOM_ANY,
+            ),
+            transient_for: session.request_property(
+                window,
+                xcb::xproto::ATOM_WM_TRANSIENT_FOR,
+                xcb::xproto::ATOM_WINDOW,
+            ),
+            wm_role: session.request_property(
+                window,
+                session.atom_wm_window_role,
+                xcb::xproto::ATOM_STRING,
+            ),
+            pid: session.request_property(
+                window,
+                session.atom_net_wm_pid,
+                xcb::xproto::ATOM_CARDINAL,
+            ),
+            wm_class,
+        }
+    }
+
+    /// Wait for the replies to `request_properties` and decode them
+    fn receive_properties(
+        &self,
+        session: &Session,
+        cookies: PropertyCookies<'_>,
+        policy: OnMalformed,
+    ) -> Result<WindowProperties, XidlehookError> {
+        let prop_net_wm_state = cookies.net_wm_state.get_reply()?;
+        let prop_wm_state = cookies.wm_state.get_reply()?;
+        let prop_desktop = cookies.desktop.get_reply()?;
+        let prop_net_wm_name = cookies.net_wm_name.get_reply()?;
+        let prop_wm_name = cookies.wm_name.get_reply()?;
+        let prop_transient_for = cookies.transient_for.get_reply()?;
+        let prop_wm_role = cookies.wm_role.get_reply()?;
+        let prop_pid = cookies.pid.get_reply()?;
+        let wm_class = match cookies.wm_class {
+            WmClass::Cached(wm_class) => wm_class,
+            WmClass::Pending(cookie) => {
+                self.decode_wm_class(cookies.window, &cookie.get_reply()?, policy)?
+            },
+        };
+
+        let net_wm_state = window_values_u32(&prop_net_wm_state, policy)?;
+        // _NET_WM_NAME is always UTF-8, unlike the legacy WM_NAME
+        let mut wm_name = window_value_string(&prop_net_wm_name, policy)?;
+        if wm_name.is_empty() {
+            wm_name = window_value_string(&prop_wm_name, policy)?;
+        }
+        Ok(WindowProperties {
+            wm_class,
+            wm_name,
+            wm_role: Some(window_value_string(&prop_wm_role, policy)?)
+                .filter(|role| !role.is_empty()),
+            fullscreen: net_wm_state.contains(&session.atom_net_wm_state_fullscreen),
+            wm_state: window_values_u32(&prop_wm_state, policy)?.first().copied(),
+            desktop: window_values_u32(&prop_desktop, policy)?.first().copied(),
+            modal: net_wm_state.contains(&session.atom_net_wm_state_modal),
+            transient_for: window_values_u32(&prop_transient_for, policy)?
+                .first()
+                .copied()
+                .filter(|&window| window != xcb::NONE),
+            pid: window_values_u32(&prop_pid, policy)?.first().copied(),
+        })
+    }
+
+    /// Decode `WM_CLASS`, and cache it
+    fn decode_wm_class(
+        &self,
+        window: xcb::Window,
+        prop_wm_class: &GetPropertyReply,
+        policy: OnMalformed,
+    ) -> Result<Vec<String>, XidlehookError> {
+        let value = window_value_string(prop_wm_class, policy)?;
+        let wm_class = parse_wm_class(&value);
+
+        // A window which doesn't have it yet might get it later
+        if !value.is_empty() {
+            self.wm_class_cache
+                .borrow_mut()
+                .insert(window, wm_class.clone());
+        }
+        Ok(wm_class)
+    }
+
+    fn query_fullscreen(
+        &self,
+        root: xcb::Window,
+        root_props: &RootProperties,
+        options: &FullscreenOptions,
+        search: &mut WindowSearch,
+        seen: &mut HashSet<xcb::Window>,
+    ) -> Result<Option<xcb::Window>> {
+        let session = self.session();
+        // Windows can be destroyed while the tree is walked, which
+        // skips them rather than failing the whole search
+        let children = |parent| match xcb::xproto::query_tree(&session.conn, parent).get_reply() {
+            Ok(windows) => Ok(windows.children().to_vec()),
+            Err(ref err) if is_bad_window(err) => {
+                debug!("Not walking below window {}, it's gone", parent);
+                Ok(Vec::new())
+            },
+            Err(err) => Err(err.into()),
+        };
+        let policy = options.on_malformed;
+        let properties = |windows: &[xcb::Window]| Ok(self.windows_properties(windows, policy));
+        let matches = |window, props: Result<Option<WindowProperties>, XidlehookError>| {
+            let props = match props {
+                Err(ref err) if err.is_bad_window() => {
+                    debug!("Skipping window {}, it's gone", window);
+                    return Ok(false);
+                },
+                props => props?,
+            };
+            seen.insert(window);
+            if let Some(ref props) = props {
+                trace!(
+                    "Window {}: class {:?}, name {:?}, desktop {:?}, state {:?}, fullscreen {}",
+                    window,
+                    props.wm_class,
+                    props.wm_name,
+                    props.desktop,
+                    props.wm_state,
+                    props.fullscreen
+                );
+            }
+            match props {
+                Some(ref props)
+                    if props.is_fullscreen_on(&root_props.current_desktops, options)
+                        && self.on_selected_monitor(
+                            window,
+                            root_props.monitors.as_deref(),
+                            options,
+                        )? =>
+                {
+                    log_fullscreen(window, props);
+                    Ok(true)
+                },
+                _ => Ok(false),
+            }
+        };
+        if options.scan_mode == ScanMode::TopLevel {
+            // Client windows bear WM_STATE, frames around them don't
+            let is_client = |props: &Result<Option<WindowProperties>, XidlehookError>| matches!(props, Ok(Some(props)) if props.wm_state.is_some());
+            search.find_top_level(root, children, properties, is_client, matches)
+        } else {
+            search.find_batched(root, children, properties, matches)
+        }
+    }
+
+    /// List the mapped override-redirect windows among the children of
+    /// this root which cover the whole screen and aren't exceptions,
+    /// see `FullscreenOptions::override_redirect`. The window manager
+    /// doesn't reparent them, so they're never further down the tree.
+    /// This takes a round-trip for the tree, one for the attributes of
+    /// all children, one for the geometry of the override-redirect ones
+    /// and one for the properties of those covering the screen.
+    fn override_redirect_windows(
+        &self,
+        root: xcb::Window,
+        root_props: &RootProperties,
+        options: &FullscreenOptions,
+    ) -> Result<Vec<(xcb::Window, WindowProperties)>> {
+        let session = self.session();
+        let tree = xcb::xproto::query_tree(&session.conn, root).get_reply()?;
+        let children = tree.children();
+        let screen = self.window_rect(root)?;
+
+        let cookies: Vec<_> = children
+            .iter()
+            .map(|&window| xcb::xproto::get_window_attributes(&session.conn, window))
+            .collect();
+        let mut unmanaged = Vec::new();
+        for (&window, cookie) in children.iter().zip(cookies) {
+            match cookie.get_reply() {
+                Ok(attributes) => {
+                    let viewable = attributes.map_state() == xcb::xproto::MAP_STATE_VIEWABLE as u8;
+                    if attributes.override_redirect() && viewable {
+                        unmanaged.push(window);
+                    }
+                },
+                Err(ref err) if is_bad_window(err) => (),
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        let cookies: Vec<_> = unmanaged
+            .iter()
+            .map(|&window| xcb::xproto::get_geometry(&session.conn, window))
+            .collect();
+        let mut covering = Vec::new();
+        for (&window, cookie) in unmanaged.iter().zip(cookies) {
+            match cookie.get_reply() {
+                // Children of the root are positioned relative to it
+                Ok(geometry) => {
+                    let rect = Rect {
+                        x: geometry.x().into(),
+                        y: geometry.y().into(),
+                        width: geometry.width().into(),
+                        height: geometry.height().into(),
+                    };
+                    if rect.covers(&screen) {
+                        covering.push(window);
+                    }
+                },
+                Err(ref err) if is_bad_window(err) => (),
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        let policy = options.on_malformed;
+        let mut windows = Vec::new();
+        for (&window, props) in covering
+            .iter()
+            .zip(self.windows_properties(&covering, policy))
+        {
+            let props = match props {
+                Ok(Some(props)) => props,
+                Ok(None) => continue,
+                Err(ref err) if err.is_bad_window() => continue,
+                Err(err) => return Err(err.into()),
+            };
+            trace!(
+                "Override-redirect window {} covers the screen: class {:?}, name {:?}",
+                window,
+                props.wm_class,
+                props.wm_name
+            );
+            if !props.is_exception(options)
+                && self.on_selected_monitor(window, root_props.monitors.as_deref(), options)?
+            {
+                windows.push((window, props));
+            }
+        }
+        Ok(windows)
+    }
+
+    /// Collect every window below this one along with its properties,
+    /// skipping windows which disappear while doing so
+    fn collect_windows(
+        &self,
+        parent: xcb::Window,
+        policy: OnMalformed,
+        out: &mut Vec<(xcb::Window, WindowProperties)>,
+    ) -> Result<()> {
+        let session = self.session();
+        let windows = match xcb::xproto::query_tree(&session.conn, parent).get_reply() {
+            Ok(windows) => windows,
+            Err(ref err) if is_bad_window(err) => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+        let children = windows.children();
+        let properties = self.windows_properties(children, policy);
+        for (&window, props) in children.iter().zip(properties) {
+            match props {
+                Ok(Some(props)) => out.push((window, props)),
+                Ok(None) => (),
+                Err(ref err) if err.is_bad_window() => continue,
+                Err(err) => return Err(err.into()),
+            }
+            self.collect_windows(window, policy, out)?;
+        }
+        Ok(())
+    }
+
+    /// List all fullscreen windows on all screens, rather than
+    /// stopping at the first like `get_fullscreen_with`. The
+    /// scan mode is ignored, the whole tree is always walked.
+    pub fn fullscreen_windows(&self, options: &FullscreenOptions) -> Result<Vec<FullscreenHit>> {
+        self.reconnecting(|| self.list_fullscreen(options))
+    }
+
+    /// See `fullscreen_windows`
+    fn list_fullscreen(&self, options: &FullscreenOptions) -> Result<Vec<FullscreenHit>> {
+        let session = self.session();
+        let mut hits = Vec::new();
+        for (index, screen) in session.conn.get_setup().roots().enumerate() {
+            let root = screen.root();
+            let mut windows = Vec::new();
+            self.collect_windows(root, options.on_malformed, &mut windows)?;
+            let root_props = self.root_properties(root, options, false)?;
+            for hit in fullscreen_hits(index, windows, &root_props.current_desktops, options) {
+                if self.on_selected_monitor(hit.window, root_props.monitors.as_deref(), options)? {
+                    hits.push(hit);
+                }
+            }
+            if options.override_redirect {
+                for (window, props) in self.override_redirect_windows(root, &root_props, options)? {
+                    if hits.iter().all(|hit| hit.window != window) {
+                        hits.push(FullscreenHit {
+                            window,
+                            screen: index,
+                            wm_class: props.wm_class,
+                            wm_name: props.wm_name,
+                            desktop: props.desktop,
+                            pid: props.pid,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(hits)
+    }
+
+    /// Get whether or not the user's currently active window is
+    /// fullscreen. Only windows on the current desktop count, see
+    /// `FullscreenOptions::ignore_desktop_match` for counting those on
+    /// any desktop like earlier versions did.
+    pub fn get_fullscreen(&self) -> Result<bool> {
+        self.get_fullscreen_with(&FullscreenOptions::default())
+    }
+
+    /// Like `get_fullscreen`, but with the specified options
+    pub fn get_fullscreen_with(&self, options: &FullscreenOptions) -> Result<bool> {
+        Ok(self.find_fullscreen_with(options)?.is_some())
+    }
+
+    /// Like `get_fullscreen_with`, but returns the first fullscreen
+    /// window found
+    pub fn find_fullscreen_with(&self, options: &FullscreenOptions) -> Result<Option<xcb::Window>> {
+        self.fullscreen_scans
+            .set(self.fullscreen_scans.get().saturating_add(1));
+        self.reconnecting(|| self.search_fullscreen(options))
+    }
+
+    /// Like `find_fullscreen_with`, but only search once per tick for
+    /// the same options, such as for several `NotWhenFullscreen`
+    /// modules. A tick starts every time the main loop refreshes this
+    /// as its idle source, see `IdleSource::refresh`. Until the first
+    /// one, every call searches.
+    pub fn find_fullscreen_cached(
+        &self,
+        options: &FullscreenOptions,
+    ) -> Result<Option<xcb::Window>> {
+        let tick = self.tick.get();
+        if tick == 0 {
+            return self.find_fullscreen_with(options);
+        }
+        if self.fullscreen_cache_tick.replace(tick) != tick {
+            self.fullscreen_cache.borrow_mut().clear();
+        }
+        let cached = self
+            .fullscreen_cache
+            .borrow()
+            .iter()
+            .find(|(cached, _)| cached == options)
+            .map(|&(_, found)| found);
+        if let Some(found) = cached {
+            return Ok(found);
+        }
+        let found = self.find_fullscreen_with(options)?;
+        self.fullscreen_cache
+            .borrow_mut()
+            .push((options.clone(), found));
+        Ok(found)
+    }
+
+    /// How many times windows were searched for a fullscreen one, by
+    /// `find_fullscreen_with` and everything built on it
+    pub fn fullscreen_scans(&self) -> u64 {
+        self.fullscreen_scans.get()
+    }
+
+    /// See `find_fullscreen_with`. Each screen's root properties are
+    /// read once, in one round-trip. If the window manager tells the
+    /// active window, checking it takes that round-trip, the one or two
+    /// `resolve_client_window` usually needs, and one for the client's
+    /// properties. That's two fewer than reading the root properties
+    /// one by one and looking the client's root up again. Walking a
+    /// tree of K windows takes that round-trip, one `QueryTree` per
+    /// window and one for the properties of each window's children,
+    /// which are 9 replies per window, or 8 once its `WM_CLASS` is
+    /// cached. Checking only the top level takes that round-trip, one
+    /// `QueryTree` and one for the properties of the root's children,
+    /// and the same again per frame among them. Looking for
+    /// override-redirect windows takes up to four more round-trips per
+    /// screen.
+    fn search_fullscreen(&self, options: &FullscreenOptions) -> Result<Option<xcb::Window>> {
+        let session = self.session();
+        let mut search = WindowSearch::new(options.max_windows).with_max_depth(options.max_depth);
+        let mut seen = HashSet::new();
+        let mut walked_all = true;
+        for screen in session.conn.get_setup().roots() {
+            let root = screen.root();
+            let root_props =
+                self.root_properties(root, options, options.scan_mode == ScanMode::ActiveOnly)?;
+            let fullscreen = match root_props.active {
+                Some(active) => {
+                    walked_all = false;
+                    let client = self.resolve_client_window(active)?;
+                    if self.is_client_fullscreen(client, &root_props, options)? {
+                        Some(client)
+                    } else {
+                        None
+                    }
+                },
+                // Window managers which don't set _NET_ACTIVE_WINDOW get the whole tree walked,
+                // unless only the top level is asked for
+                None => {
+                    walked_all &= options.scan_mode != ScanMode::TopLevel;
+                    self.query_fullscreen(root, &root_props, options, &mut search, &mut seen)?
+                },
+            };
+            if fullscreen.is_some() {
+                return Ok(fullscreen);
+            }
+            if options.override_redirect {
+                if let Some((window, props)) = self
+                    .override_redirect_windows(root, &root_props, options)?
+                    .first()
+                {
+                    log_fullscreen(*window, props);
+                    return Ok(Some(*window));
+                }
+            }
+            if search.is_limited() {
+                warn!(
+                    "Stopped looking for fullscreen windows after {} windows",
+                    search.examined()
+                );
+                return Ok(None);
+            }
+        }
+        // After a complete walk, windows that weren't seen are gone
+        if walked_all {
+            self.wm_class_cache.borrow_mut().retain_present(&seen);
+        }
+        Ok(None)
+    }
+
+    /// Get whether or not a specific window is fullscreen, using the
+    /// same criteria as `get_fullscreen` but without walking the
+    /// window tree. A window which doesn't exist is not fullscreen. A
+    /// modal dialog is fullscreen if the window it's transient for is,
+    /// see `is_fullscreen_through_modals`.
+    pub fn is_window_fullscreen(&self, window: xcb::Window) -> Result<bool> {
+        self.is_window_fullscreen_with(window, &FullscreenOptions::default())
+    }
+
+    /// Like `is_window_fullscreen`, but with the specified options
+    pub fn is_window_fullscreen_with(
+        &self,
+        window: xcb::Window,
+        options: &FullscreenOptions,
+    ) -> Result<bool> {
+        let session = self.session();
+        match xcb::xproto::query_tree(&session.conn, window).get_reply() {
+            Ok(tree) => {
+                let root_props = self.root_properties(tree.root(), options, false)?;
+                self.is_client_fullscreen(window, &root_props, options)
+            },
+            Err(ref err) if is_bad_window(err) => stale_window(window, options),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// See `is_window_fullscreen_with`, given the properties of the
+    /// window's root
+    fn is_client_fullscreen(
+        &self,
+        window: xcb::Window,
+        root_props: &RootProperties,
+        options: &FullscreenOptions,
+    ) -> Result<bool> {
+        let policy = options.on_malformed;
+        let props = match skip_malformed(window, self.window_properties(window, policy), policy) {
+            Ok(Some(props)) => props,
+            Ok(None) => return Ok(false),
+            Err(ref err) if err.is_bad_window() => return stale_window(window, options),
+            Err(err) => return Err(err.into()),
+        };
+        let fullscreen =
+            is_fullscreen_through_modals(&props, &root_props.current_desktops, options, |owner| {
+                let props = self.window_properties(owner, policy);
+                match skip_malformed(owner, props, policy) {
+                    Ok(props) => Ok(props),
+                    Err(ref err) if err.is_bad_window() => Ok(None),
+                    Err(err) => Err(err.into()),
+                }
+            })?;
+        if !fullscreen
+            || !self.on_selected_monitor(window, root_props.monitors.as_deref(), options)?
+        {
+            return Ok(false);
+        }
+        log_fullscreen(window, &props);
+        Ok(true)
+    }
+
+    /// Returns an empty `FullscreenWatch` using this server's atoms
+    pub fn fullscreen_watch(&self) -> FullscreenWatch {
+        let session = self.session();
+        FullscreenWatch::new(
+            session.atom_net_wm_state,
+            session.atom_net_wm_state_fullscreen,
+        )
+    }
+
+    /// Ask the X server for the events `FullscreenWatch` needs about
+    /// this window: property changes, and when it's destroyed
+    pub fn watch_window(&self, window: xcb::Window) {
+        let session = self.session();
+        xcb::xproto::change_window_attributes(
+            &session.conn,
+            window,
+            &[(
+                xcb::xproto::CW_EVENT_MASK,
+                xcb::xproto::EVENT_MASK_PROPERTY_CHANGE | xcb::xproto::EVENT_MASK_STRUCTURE_NOTIFY,
+            )],
+        );
+        session.conn.flush();
+    }
+
+    /// Handle all pending X events, without blocking. Returns true if
+    /// one of them took the last watched window out of fullscreen.
+    /// Wait for the connection to become readable, see `as_raw_fd`,
+    /// to call this as soon as events arrive.
+    pub fn handle_fullscreen_events(&self, watch: &FullscreenWatch) -> Result<bool> {
+        let session = self.session();
+        let mut exited = false;
+        while let Some(event) = session.conn.poll_for_event() {
+            match event.response_type() & !0x80 {
+                xcb::xproto::PROPERTY_NOTIFY => {
+                    let event: &xcb::PropertyNotifyEvent = unsafe { xcb::cast_event(&event) };
+                    exited |= watch.handle_property_notify(event, |window| {
+                        match session.get_property(
+                            window,
+                            session.atom_net_wm_state,
+                            xcb::xproto::ATOM_ATOM,
+                        ) {
+                            Ok(reply) => Ok(Some(values_u32(&reply))),
+                            Err(ref err) if is_bad_window(err) => Ok(None),
+                            Err(err) => Err(err.into()),
+                        }
+                    })?;
+                },
+                xcb::xproto::DESTROY_NOTIFY => {
+                    let event: &xcb::DestroyNotifyEvent = unsafe { xcb::cast_event(&event) };
+                    exited |= watch.forget(event.window());
+                },
+                _ => (),
+            }
+        }
+        if exited {
+            debug!("No watched window is fullscreen anymore");
+        }
+        Ok(exited)
+    }
+
+    /// Return a `NotWhenFullscreen` instance for a reference-counted
+    /// self
+    pub fn not_when_fullscreen(self: Rc<Self>) -> NotWhenFullscreen {
+        self.not_when_fullscreen_with(FullscreenOptions::default())
+    }
+
+    /// Like `not_when_fullscreen`, but with the specified options
+    pub fn not_when_fullscreen_with(
+        self: Rc<Self>,
+        options: FullscreenOptions,
+    ) -> NotWhenFullscreen {
+        NotWhenFullscreen {
+            xcb: self,
+            options: Rc::new(RefCell::new(options)),
+            watch: None,
+            progress: Progress::Abort,
+        }
+    }
+
+    /// Return a `FullscreenHook` instance for a reference-counted
+    /// self, checking windows with these options
+    pub fn fullscreen_hook(self: Rc<Self>, options: FullscreenOptions) -> FullscreenHook<Rc<Self>> {
+        FullscreenHook::new(self, options)
+    }
+
+    /// The number of windows whose `WM_CLASS` is cached
+    pub fn window_cache_len(&self) -> usize {
+        self.wm_class_cache.borrow().len()
+    }
+    /// Cache the `WM_CLASS` of at most this many windows, see
+    /// `WindowCache`
+    pub fn set_window_cache_size(&self, max_entries: usize) {
+        self.wm_class_cache
+            .borrow_mut()
+            .set_max_entries(max_entries);
+    }
+
+    /// Get the state of the X screensaver using the `XScreenSaver`
+    /// plugin
+    pub fn get_screensaver_state(&self) -> Result<ScreensaverState> {
+        self.reconnecting(|| {
+            let session = self.session();
+            let info = session.query_screensaver(session.root_window)?;
+            Ok(ScreensaverState::from_raw(info.state().into())
+                .ok_or("unknown screensaver state")?)
+        })
+    }
+
+    /// Get the power state of the monitors from DPMS. This is
+    /// `DpmsState::Unsupported` if the X server doesn't support it.
+    pub fn dpms_state(&self) -> Result<DpmsState> {
+        self.reconnecting(|| {
+            let session = self.session();
+            match session.dpms {
+                Some(opcode) => dpms::query(&session.conn, opcode),
+                None => Ok(DpmsState::Unsupported),
+            }
+        })
+    }
+
+    /// Return a `BlankTimer` covering the screens after this idle
+    /// time. This is not a lock, see `XcbBlanker`.
+    pub fn blank_timer(self: Rc<Self>, time: Duration) -> BlankTimer<XcbBlanker> {
+        BlankTimer::new(time, XcbBlanker::new(self))
+    }
+
+    /// Return a `NotWhenScreensaverDisabled` instance for a
+    /// reference-counted self
+    pub fn not_when_screensaver_disabled(self: Rc<Self>) -> NotWhenScreensaverDisabled<Rc<Self>> {
+        NotWhenScreensaverDisabled::new(self)
+    }
+
+    /// Return a `NotWhenScreenOff` instance for a reference-counted
+    /// self
+    pub fn not_when_screen_off(self: Rc<Self>) -> NotWhenScreenOff<Rc<Self>> {
+        NotWhenScreenOff::new(self)
+    }
+
+    /// Return a `NotWhenRootProperty` instance for a reference-counted
+    /// self, watching `DEFAULT_INHIBIT_PROPERTY`
+    pub fn not_when_root_property(self: Rc<Self>) -> NotWhenRootProperty<Rc<Self>> {
+        NotWhenRootProperty::new(self)
+    }
+
+    /// Intern an atom by name, creating it if the server doesn't know
+    /// it yet. Atoms are only valid for the connection they were
+    /// interned on, see `reconnects`.
+    pub fn intern_atom(&self, name: &str) -> Result<xcb::Atom> {
+        self.reconnecting(|| {
+            let session = self.session();
+            let reply = xcb::xproto::intern_atom(&session.conn, false, name).get_reply()?;
+            Ok(reply.atom())
+        })
+    }
+
+    /// Returns true if this property is set to something other than
+    /// zero on the root window of any screen, see `is_inhibiting`
+    pub fn is_root_property_set(&self, property: xcb::Atom) -> Result<bool> {
+        self.reconnecting(|| {
+            let session = self.session();
+            let cookies: Vec<_> = session
+                .roots()
+                .into_iter()
+                .map(|root| session.request_property(root, property, xcb::xproto::ATOM_ANY))
+                .collect();
+            let mut set = false;
+            for cookie in cookies {
+                let reply = cookie.get_reply()?;
+                set |= is_inhibiting(reply.format(), reply.value());
+            }
+            Ok(set)
+        })
+    }
+}
+impl FullscreenSource for Xcb {
+    fn is_fullscreen(&self, options: &FullscreenOptions) -> Result<bool> {
+        self.get_fullscreen_with(options)
+    }
+}
+impl DpmsSource for Xcb {
+    fn dpms_state(&self) -> Result<DpmsState> {
+        Xcb::dpms_state(self)
+    }
+}
+impl ScreensaverSource for Xcb {
+    fn screensaver_state(&self) -> Result<ScreensaverState> {
+        self.get_screensaver_state()
+    }
+}
+impl RootPropertySource for Xcb {
+    fn intern_atom(&self, name: &str) -> Result<xcb::Atom> {
+        Xcb::intern_atom(self, name)
+    }
+    fn is_root_property_set(&self, property: xcb::Atom) -> Result<bool> {
+        Xcb::is_root_property_set(self, property)
+    }
+    fn reconnects(&self) -> u64 {
+        Xcb::reconnects(self)
+    }
+}
+impl WindowTree for Xcb {
+    fn is_client(&self, window: xcb::Window) -> Result<bool> {
+        let session = self.session();
+        for &property in &[session.type_wm_state, session.atom_net_wm_state] {
+            match session.get_property(window, property, xcb::xproto::ATOM_ANY) {
+                Ok(reply) if reply.type_() != xcb::NONE => return Ok(true),
+                Ok(_) => (),
+                // A window which is gone can't be a client window
+                Err(ref err) if is_bad_window(err) => return Ok(false),
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(false)
+    }
+    fn parent(&self, window: xcb::Window) -> Result<Option<xcb::Window>> {
+        let session = self.session();
+        match xcb::xproto::query_tree(&session.conn, window).get_reply() {
+            Ok(tree) if tree.parent() != xcb::NONE && window != tree.root() => {
+                Ok(Some(tree.parent()))
+            },
+            Ok(_) => Ok(None),
+            Err(ref err) if is_bad_window(err) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+impl AsRawFd for Xcb {
+    fn as_raw_fd(&self) -> RawFd {
+        let session = self.session();
+        session.conn.as_raw_fd()
+    }
+}
+impl IdleSource for Xcb {
+    fn get_idle(&self) -> Result<Duration> {
+        Xcb::get_idle(self)
+    }
+    fn resolution(&self) -> Duration {
+        // The X server counts idle time in milliseconds
+        Duration::from_millis(1)
+    }
+    fn refresh(&self) {
+        self.tick.set(self.tick.get().wrapping_add(1).max(1));
+    }
+}
+impl Drop for Xcb {
+    fn drop(&mut self) {
+        // Replies that weren't waited for are discarded when the
+        // connection is closed, after this
+        self.session().conn.flush();
+    }
+}
+impl fmt::Debug for Xcb {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Xcb")
+    }
+}
+
+/// See the module-level documentation
+pub struct NotWhenFullscreen {
+    xcb: Rc<Xcb>,
+    options: Rc<RefCell<FullscreenOptions>>,
+    watch: Option<FullscreenWatch>,
+    progress: Progress,
+}
+impl NotWhenFullscreen {
+    /// Returns the options windows are checked with
+    pub fn options(&self) -> Ref<'_, FullscreenOptions> {
+        self.options.borrow()
+    }
+    /// Returns the options windows are checked with, shared with this
+    /// module so they can be changed while it's running, such as to
+    /// reload the exceptions. Changes take effect at the next check.
+    pub fn shared_options(&self) -> Rc<RefCell<FullscreenOptions>> {
+        Rc::clone(&self.options)
+    }
+    /// Decide what timers do while a window is fullscreen, which is
+    /// `Progress::Abort` by default. Aborting gives up on the chain
+    /// until the user is active again, or a watched window leaves
+    /// fullscreen (see `with_watch`), so nothing goes off after a movie
+    /// until then. `Progress::Reset` starts the chain over each time a
+    /// timer finds a window fullscreen, so after a movie, the timers
+    /// go off as if the user was last active at most one timer's time
+    /// before it ended.
+    pub fn with_progress(self, progress: Progress) -> Self {
+        Self { progress, ..self }
+    }
+    /// Returns what timers do while a window is fullscreen
+    pub fn progress(&self) -> Progress {
+        self.progress
+    }
+    /// Add the fullscreen windows which keep timers from running to
+    /// this watch, so it can tell when they leave fullscreen
+    pub fn with_watch(self, watch: FullscreenWatch) -> Self {
+        Self {
+            watch: Some(watch),
+            ..self
+        }
+    }
+}
+impl Module for NotWhenFullscreen {
+    fn pre_timer(&mut self, _timer: TimerInfo) -> Result<Progress> {
+        let found = self.xcb.find_fullscreen_cached(&self.options.borrow())?;
+        match found {
+            Some(window) => {
+                if let Some(ref watch) = self.watch {
+                    self.xcb.watch_window(window);
+                    watch.insert(window);
+                }
+                Ok(self.progress)
+            },
+            None => Ok(Progress::Continue),
+        }
+    }
+    fn peek_timer(&mut self, _timer: TimerInfo) -> Result<Progress> {
+        // Like `pre_timer`, without watching the window
+        match self.xcb.find_fullscreen_cached(&self.options.borrow())? {
+            Some(_) => Ok(self.progress),
+            None => Ok(Progress::Continue),
+        }
+    }
+    fn describe(&self) -> ModuleDescription {
+        self.options
+            .borrow()
+            .describe()
+            .with("watch_fullscreen", self.watch.is_some())
+            .with("progress", self.progress.name())
+    }
+}
+impl fmt::Debug for NotWhenFullscreen {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "NotWhenFullscreen")
+    }
+}
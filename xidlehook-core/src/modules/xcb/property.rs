@@ -0,0 +1,84 @@
+//! Decoding of window properties, and what to do with one in an
+//! unexpected format, such as a list of numbers that a buggy
+//! application stored as bytes.
+
+use std::{error::Error, fmt};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// What to do with a window property whose format doesn't match what
+/// it should be
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum OnMalformed {
+    /// Log it and ignore the window it belongs to
+    #[default]
+    Skip,
+    /// Return an error
+    Error,
+    /// Decode the raw bytes as if they were in the right format,
+    /// dropping any that are left over
+    BestEffort,
+}
+
+/// A property was in the wrong format
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MalformedProperty {
+    /// The format it should be in: 8, 16 or 32 bits per item
+    pub expected: u8,
+    /// The format it was in
+    pub found: u8,
+}
+impl fmt::Display for MalformedProperty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "property has format {} instead of {}",
+            self.found, self.expected
+        )
+    }
+}
+impl Error for MalformedProperty {}
+
+/// Check a property's format. A format of zero means the property
+/// isn't set, which is never malformed. Returns true if the data can be
+/// decoded.
+fn check(expected: u8, found: u8, policy: OnMalformed) -> Result<bool, MalformedProperty> {
+    match (found, policy) {
+        (0, _) => Ok(false),
+        (_, OnMalformed::BestEffort) => Ok(true),
+        _ if found == expected => Ok(true),
+        _ => Err(MalformedProperty { expected, found }),
+    }
+}
+
+/// Decode the raw bytes of a 32-bit property, such as a list of atoms
+/// or a cardinal
+pub fn decode_u32s(
+    format: u8,
+    data: &[u8],
+    policy: OnMalformed,
+) -> Result<Vec<u32>, MalformedProperty> {
+    if !check(32, format, policy)? {
+        return Ok(Vec::new());
+    }
+    Ok(data
+        .chunks_exact(4)
+        .map(|chunk| u32::from_ne_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect())
+}
+
+/// Decode the raw bytes of an 8-bit property as a string, replacing
+/// invalid UTF-8
+pub fn decode_string(
+    format: u8,
+    data: &[u8],
+    policy: OnMalformed,
+) -> Result<String, MalformedProperty> {
+    if !check(8, format, policy)? {
+        return Ok(String::new());
+    }
+    Ok(String::from_utf8_lossy(data).into_owned())
+}
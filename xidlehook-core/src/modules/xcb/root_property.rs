@@ -0,0 +1,146 @@
+//! Inhibits through a property on the root window, so that scripts can
+//! keep timers from running with `xprop` rather than D-Bus:
+//!
+//! ```sh
+//! xprop -root -f _XIDLEHOOK_INHIBIT 32c -set _XIDLEHOOK_INHIBIT 1
+//! xprop -root -remove _XIDLEHOOK_INHIBIT
+//! ```
+//!
+//! The `NotWhenRootProperty` module keeps timers from running while any
+//! of its properties is set to something other than zero on the root
+//! window of any screen. A property that's absent, empty or zero
+//! doesn't inhibit. Tools which mark inhibition on the root window in
+//! their own way can be followed by adding their properties with
+//! `with_property`.
+
+use crate::{modules::ModuleDescription, Module, Progress, Result, TimerInfo};
+
+use std::fmt;
+
+/// The property watched by default
+pub const DEFAULT_INHIBIT_PROPERTY: &str = "_XIDLEHOOK_INHIBIT";
+
+/// Returns true if a property with this format and value inhibits:
+/// it's set, and isn't only zeros. Text, which has a format of 8,
+/// doesn't inhibit if it's `0` either, so that `xprop -set` works with
+/// strings too.
+pub fn is_inhibiting(format: u8, value: &[u8]) -> bool {
+    match format {
+        0 => false,
+        8 => {
+            let text = String::from_utf8_lossy(value);
+            let text = text.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+            !text.is_empty() && text != "0"
+        },
+        _ => value.iter().any(|&byte| byte != 0),
+    }
+}
+
+/// Something that can read properties off the root windows
+pub trait RootPropertySource {
+    /// Intern an atom by name, creating it if it doesn't exist yet
+    fn intern_atom(&self, name: &str) -> Result<xcb::Atom>;
+    /// Returns true if this property inhibits, see `is_inhibiting`,
+    /// on the root window of any screen
+    fn is_root_property_set(&self, property: xcb::Atom) -> Result<bool>;
+    /// How many times the connection was re-established, after which
+    /// the atoms have to be interned again
+    fn reconnects(&self) -> u64 {
+        0
+    }
+}
+impl<S: RootPropertySource + ?Sized> RootPropertySource for std::rc::Rc<S> {
+    fn intern_atom(&self, name: &str) -> Result<xcb::Atom> {
+        (**self).intern_atom(name)
+    }
+    fn is_root_property_set(&self, property: xcb::Atom) -> Result<bool> {
+        (**self).is_root_property_set(property)
+    }
+    fn reconnects(&self) -> u64 {
+        (**self).reconnects()
+    }
+}
+
+/// See the module-level documentation
+pub struct NotWhenRootProperty<S: RootPropertySource> {
+    source: S,
+    properties: Vec<String>,
+    /// The interned atoms of `properties`, along with the number of
+    /// reconnects they were interned after
+    atoms: Option<(u64, Vec<xcb::Atom>)>,
+    progress: Progress,
+}
+impl<S: RootPropertySource> NotWhenRootProperty<S> {
+    /// Returns a module watching `DEFAULT_INHIBIT_PROPERTY` on this
+    /// source, which is usually an `Rc<Xcb>`
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            properties: vec![DEFAULT_INHIBIT_PROPERTY.into()],
+            atoms: None,
+            progress: Progress::Abort,
+        }
+    }
+    /// Watch these properties instead
+    pub fn with_properties(self, properties: Vec<String>) -> Self {
+        Self {
+            properties,
+            atoms: None,
+            ..self
+        }
+    }
+    /// Watch this property too
+    pub fn with_property(mut self, property: impl Into<String>) -> Self {
+        self.properties.push(property.into());
+        self.atoms = None;
+        self
+    }
+    /// Decide what timers do while inhibited, which is
+    /// `Progress::Abort` by default
+    pub fn with_progress(self, progress: Progress) -> Self {
+        Self { progress, ..self }
+    }
+    /// Returns the names of the properties watched
+    pub fn properties(&self) -> &[String] {
+        &self.properties
+    }
+    /// The atoms of the properties, interned once per connection
+    fn atoms(&mut self) -> Result<Vec<xcb::Atom>> {
+        let reconnects = self.source.reconnects();
+        if let Some((interned, ref atoms)) = self.atoms {
+            if interned == reconnects {
+                return Ok(atoms.clone());
+            }
+        }
+        let atoms = self
+            .properties
+            .iter()
+            .map(|name| self.source.intern_atom(name))
+            .collect::<Result<Vec<_>>>()?;
+        self.atoms = Some((reconnects, atoms.clone()));
+        Ok(atoms)
+    }
+}
+impl<S: RootPropertySource> Module for NotWhenRootProperty<S> {
+    fn pre_timer(&mut self, _timer: TimerInfo) -> Result<Progress> {
+        for atom in self.atoms()? {
+            if self.source.is_root_property_set(atom)? {
+                return Ok(self.progress);
+            }
+        }
+        Ok(Progress::Continue)
+    }
+    fn peek_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.pre_timer(timer)
+    }
+    fn describe(&self) -> ModuleDescription {
+        ModuleDescription::new("not-when-root-property")
+            .with("properties", self.properties.as_slice())
+            .with("progress", self.progress.name())
+    }
+}
+impl<S: RootPropertySource> fmt::Debug for NotWhenRootProperty<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "NotWhenRootProperty")
+    }
+}
@@ -0,0 +1,173 @@
+//! Combinable rules for matching windows, used for fullscreen
+//! exceptions that the flat lists can't express, such as "Firefox,
+//! except private windows".
+
+use super::WindowProperties;
+use crate::Result;
+
+use std::fmt;
+
+use regex::Regex;
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A regular expression, compiled once. It isn't anchored, so
+/// `steam_app_` matches anywhere and `^steam_app_` only at the start.
+#[derive(Clone)]
+pub struct Pattern(Regex);
+impl Pattern {
+    /// Compile the pattern, failing if it's invalid
+    pub fn new(pattern: &str) -> Result<Self> {
+        Ok(Self(Regex::new(pattern)?))
+    }
+    /// A pattern matching exactly this text, ignoring case
+    pub fn ignoring_case(text: &str) -> Self {
+        let pattern = format!("(?i)^{}$", regex::escape(text));
+        Self(Regex::new(&pattern).expect("escaped text is a valid pattern"))
+    }
+    /// A pattern matching the same text as this glob, see
+    /// `glob_matches`
+    pub fn glob(glob: &str, ignore_case: bool) -> Self {
+        let mut pattern = String::from(if ignore_case { "(?i)^" } else { "^" });
+        for c in glob.chars() {
+            match c {
+                '*' => pattern.push_str(".*"),
+                '?' => pattern.push('.'),
+                c => pattern.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+            }
+        }
+        pattern.push('$');
+        Self(Regex::new(&pattern).expect("converted glob is a valid pattern"))
+    }
+    /// Returns true if the pattern matches anywhere in the text
+    pub fn is_match(&self, text: &str) -> bool {
+        self.0.is_match(text)
+    }
+    /// The pattern as it was written
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+impl Eq for Pattern {}
+impl fmt::Debug for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Pattern({:?})", self.as_str())
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        Regex::new(&pattern).map(Self).map_err(de::Error::custom)
+    }
+}
+#[cfg(feature = "serde")]
+impl Serialize for Pattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_str().serialize(serializer)
+    }
+}
+
+/// Returns true if the text matches a shell-style glob, where `*`
+/// matches any text and `?` any single character. The whole text has
+/// to match, so a glob without either is compared literally.
+pub fn glob_matches(glob: &str, text: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut g, mut t) = (0, 0);
+    // Where to resume after the last `*` if the rest doesn't match,
+    // letting it match one more character
+    let mut star = None;
+    while t < text.len() {
+        match glob.get(g) {
+            Some('*') => {
+                star = Some((g + 1, t));
+                g += 1;
+            },
+            Some(&c) if c == '?' || c == text[t] => {
+                g += 1;
+                t += 1;
+            },
+            _ => match star {
+                Some((after, matched)) => {
+                    star = Some((after, matched + 1));
+                    g = after;
+                    t = matched + 1;
+                },
+                None => return false,
+            },
+        }
+    }
+    glob[g..].iter().all(|&c| c == '*')
+}
+
+/// A condition on a window's properties. In a config file, rules are
+/// written like `{ and = [{ class = "Firefox" }, { not = {
+/// name_contains = "Private Browsing" } }] }`, and patterns like
+/// `{ class_matches = "^steam_app_" }`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Rule {
+    /// The `WM_CLASS` class name is exactly this
+    Class(String),
+    /// The `WM_CLASS` instance name is exactly this
+    Instance(String),
+    /// The `WM_NAME` is exactly this
+    Name(String),
+    /// The `WM_NAME` contains this
+    NameContains(String),
+    /// The `WM_WINDOW_ROLE` is exactly this. Windows without a role
+    /// don't match.
+    Role(String),
+    /// The `WM_CLASS` class name matches this pattern
+    ClassMatches(Pattern),
+    /// The `WM_CLASS` instance name matches this pattern
+    InstanceMatches(Pattern),
+    /// The `WM_NAME` matches this pattern
+    NameMatches(Pattern),
+    /// The `WM_WINDOW_ROLE` matches this pattern. Windows without a
+    /// role don't match.
+    RoleMatches(Pattern),
+    /// The window is owned by the process with this id, according to
+    /// `_NET_WM_PID`
+    Pid(u32),
+    /// The window is owned by a process with this name, see
+    /// `WindowProperties::process_name`
+    Process(String),
+    /// All of the rules match. This is true if there are none.
+    And(Vec<Rule>),
+    /// Any of the rules match. This is false if there are none.
+    Or(Vec<Rule>),
+    /// The rule doesn't match
+    Not(Box<Rule>),
+}
+impl Rule {
+    /// Returns true if the window matches this rule
+    pub fn matches(&self, window: &WindowProperties) -> bool {
+        match self {
+            Self::Class(class) => window.class() == class,
+            Self::Instance(instance) => window.instance() == instance,
+            Self::Name(name) => window.wm_name == *name,
+            Self::NameContains(part) => window.wm_name.contains(part.as_str()),
+            Self::Role(role) => window.wm_role.as_ref() == Some(role),
+            Self::ClassMatches(pattern) => pattern.is_match(window.class()),
+            Self::InstanceMatches(pattern) => pattern.is_match(window.instance()),
+            Self::NameMatches(pattern) => pattern.is_match(&window.wm_name),
+            Self::RoleMatches(pattern) => window
+                .wm_role
+                .as_ref()
+                .is_some_and(|role| pattern.is_match(role)),
+            Self::Pid(pid) => window.pid == Some(*pid),
+            Self::Process(name) => window.process_name().as_ref() == Some(name),
+            Self::And(rules) => rules.iter().all(|rule| rule.matches(window)),
+            Self::Or(rules) => rules.iter().any(|rule| rule.matches(window)),
+            Self::Not(rule) => !rule.matches(window),
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! Honors `xdg-screensaver suspend`, heuristically. On a plain X
+//! session, `xdg-screensaver` inhibits the screensaver by turning the
+//! X screensaver off (like `xset s off`), which the `XScreenSaver`
+//! extension reports as the `Disabled` state. The
+//! `NotWhenScreensaverDisabled` module treats that state as an inhibit.
+//!
+//! Limits of this heuristic:
+//!
+//! - If you've disabled the X screensaver yourself (for example in
+//!   your xinitrc, since xidlehook is your screensaver), timers will
+//!   never run while this module is active.
+//! - Inhibits which don't touch the X screensaver, such as
+//!   `xdg-screensaver` repeatedly calling `xset s reset`, or D-Bus
+//!   inhibits, aren't detected.
+
+use crate::{modules::ModuleDescription, Module, Progress, Result, TimerInfo};
+
+use std::fmt;
+
+/// The state of the X screensaver as reported by the `XScreenSaver`
+/// extension
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScreensaverState {
+    /// The screensaver isn't active
+    Off,
+    /// The screensaver is active
+    On,
+    /// The screensaver is active and cycling
+    Cycle,
+    /// The screensaver has been turned off, such as by `xset s off`
+    Disabled,
+}
+impl ScreensaverState {
+    /// Decode the state from the extension's representation, returning
+    /// `None` if it's unknown
+    pub fn from_raw(state: u32) -> Option<Self> {
+        match state {
+            xcb::screensaver::STATE_OFF => Some(Self::Off),
+            xcb::screensaver::STATE_ON => Some(Self::On),
+            xcb::screensaver::STATE_CYCLE => Some(Self::Cycle),
+            xcb::screensaver::STATE_DISABLED => Some(Self::Disabled),
+            _ => None,
+        }
+    }
+}
+
+/// Something that can tell the state of the X screensaver
+pub trait ScreensaverSource {
+    /// Get the current screensaver state
+    fn screensaver_state(&self) -> Result<ScreensaverState>;
+}
+impl<S: ScreensaverSource + ?Sized> ScreensaverSource for std::rc::Rc<S> {
+    fn screensaver_state(&self) -> Result<ScreensaverState> {
+        (**self).screensaver_state()
+    }
+}
+
+/// See the module-level documentation
+pub struct NotWhenScreensaverDisabled<S: ScreensaverSource> {
+    source: S,
+}
+impl<S: ScreensaverSource> NotWhenScreensaverDisabled<S> {
+    /// Returns a module reading the screensaver state off this source,
+    /// which is usually an `Rc<Xcb>`
+    pub fn new(source: S) -> Self {
+        Self { source }
+    }
+}
+impl<S: ScreensaverSource> Module for NotWhenScreensaverDisabled<S> {
+    fn pre_timer(&mut self, _timer: TimerInfo) -> Result<Progress> {
+        if self.source.screensaver_state()? == ScreensaverState::Disabled {
+            Ok(Progress::Abort)
+        } else {
+            Ok(Progress::Continue)
+        }
+    }
+    fn peek_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.pre_timer(timer)
+    }
+    fn describe(&self) -> ModuleDescription {
+        ModuleDescription::new("not-when-screensaver-disabled")
+    }
+}
+impl<S: ScreensaverSource> fmt::Debug for NotWhenScreensaverDisabled<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "NotWhenScreensaverDisabled")
+    }
+}
@@ -0,0 +1,81 @@
+//! Noticing right away when a window leaves fullscreen, instead of at
+//! the next poll. Windows found to be fullscreen are watched for
+//! `PropertyNotify` events on their `_NET_WM_STATE`, and once none of
+//! them is fullscreen anymore, the timers which were kept from
+//! running can start counting again.
+
+use crate::Result;
+
+use std::{cell::RefCell, collections::HashSet, fmt, rc::Rc};
+
+/// See the module-level documentation. Clones share the same state,
+/// so one clone can be given to `NotWhenFullscreen::with_watch` while
+/// another handles the events.
+#[derive(Clone)]
+pub struct FullscreenWatch {
+    atom_net_wm_state: xcb::Atom,
+    atom_net_wm_state_fullscreen: xcb::Atom,
+    windows: Rc<RefCell<HashSet<xcb::Window>>>,
+}
+impl FullscreenWatch {
+    /// Returns an empty watch, recognizing the state property and its
+    /// fullscreen value by these atoms. Use `Xcb::fullscreen_watch` to
+    /// get one with the atoms of the X server.
+    pub fn new(atom_net_wm_state: xcb::Atom, atom_net_wm_state_fullscreen: xcb::Atom) -> Self {
+        Self {
+            atom_net_wm_state,
+            atom_net_wm_state_fullscreen,
+            windows: Rc::new(RefCell::new(HashSet::new())),
+        }
+    }
+    /// Start tracking a window which is fullscreen. The X server must
+    /// also be told to send its events, see `Xcb::watch_window`.
+    pub fn insert(&self, window: xcb::Window) {
+        self.windows.borrow_mut().insert(window);
+    }
+    /// Returns true if a watched window is still fullscreen
+    pub fn is_fullscreen(&self) -> bool {
+        !self.windows.borrow().is_empty()
+    }
+    /// The number of watched windows
+    pub fn len(&self) -> usize {
+        self.windows.borrow().len()
+    }
+    /// Returns true if no windows are watched
+    pub fn is_empty(&self) -> bool {
+        self.windows.borrow().is_empty()
+    }
+    /// Stop tracking a window, such as one which was destroyed.
+    /// Returns true if this was the last fullscreen window.
+    pub fn forget(&self, window: xcb::Window) -> bool {
+        let mut windows = self.windows.borrow_mut();
+        windows.remove(&window) && windows.is_empty()
+    }
+    /// Update the state after a `PropertyNotify` event. Events about
+    /// other properties or windows are ignored. If the state changed,
+    /// `get_states` is asked for the window's new `_NET_WM_STATE`
+    /// atoms, or `None` if the window is gone. Returns true if this
+    /// took the last watched window out of fullscreen.
+    pub fn handle_property_notify<F>(
+        &self,
+        event: &xcb::PropertyNotifyEvent,
+        get_states: F,
+    ) -> Result<bool>
+    where
+        F: FnOnce(xcb::Window) -> Result<Option<Vec<xcb::Atom>>>,
+    {
+        let window = event.window();
+        if event.atom() != self.atom_net_wm_state || !self.windows.borrow().contains(&window) {
+            return Ok(false);
+        }
+        let fullscreen = event.state() != xcb::xproto::PROPERTY_DELETE as u8
+            && get_states(window)?
+                .is_some_and(|states| states.contains(&self.atom_net_wm_state_fullscreen));
+        Ok(!fullscreen && self.forget(window))
+    }
+}
+impl fmt::Debug for FullscreenWatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FullscreenWatch({} windows)", self.len())
+    }
+}
@@ -0,0 +1,815 @@
+//! The per-window part of fullscreen detection. This is kept apart
+//! from the X requests themselves so that a single window can be
+//! checked, and so the decision can be tested without an X server.
+
+use super::{glob_matches, OnMalformed, Pattern, Rule};
+use crate::{modules::ModuleDescription, Result};
+
+use std::fs;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The `WM_STATE` of a window which is shown
+const NORMAL_STATE: u32 = 1;
+/// The `WM_STATE` of a window which is minimized
+const ICONIC_STATE: u32 = 3;
+
+/// The `_NET_WM_DESKTOP` of a sticky window, which is shown on all
+/// desktops
+pub const STICKY_DESKTOP: u32 = 0xFFFF_FFFF;
+
+/// How many `WM_TRANSIENT_FOR` links are followed from a modal
+/// window, in case they form a cycle
+const MAX_MODAL_DEPTH: usize = 8;
+
+/// How deep into the window tree fullscreen windows are looked for,
+/// unless configured otherwise. Client windows are normally children
+/// of the root window, or of a frame the window manager put them in.
+pub const DEFAULT_MAX_DEPTH: usize = 3;
+
+/// Which windows are checked for being fullscreen
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ScanMode {
+    /// Only check the active window (`_NET_ACTIVE_WINDOW`), or rather
+    /// the client window it belongs to. If the window manager doesn't
+    /// say which window is active, the whole tree is walked instead.
+    /// This suits window managers following EWMH, which most do, and
+    /// is the cheapest.
+    #[default]
+    ActiveOnly,
+    /// Walk the whole window tree, looking for any fullscreen window,
+    /// down to `FullscreenOptions::max_depth`. This works with any
+    /// window manager, or none, but costs the most on big trees.
+    FullTree,
+    /// Only check the direct children of the root window, and for
+    /// those which aren't client windows themselves, such as frames,
+    /// the client windows among their children. This ignores
+    /// `FullscreenOptions::max_depth`. It suits window managers which
+    /// don't reparent, like dwm or xmonad, or which put each client in
+    /// a single frame, like Openbox or Fluxbox, when `ActiveOnly`
+    /// doesn't do because the fullscreen window isn't always the
+    /// active one, such as a video on another monitor. Window managers
+    /// nesting clients deeper, like KWin does, or adding a virtual
+    /// root, need `FullTree`.
+    TopLevel,
+}
+
+/// Options affecting which windows count as fullscreen. Entries of
+/// `exceptions_wm_class1`, `exceptions_wm_class2`, `exceptions_wm_name`
+/// and `exceptions_wm_role` may be globs like `Gimp*`, see
+/// `glob_matches`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FullscreenOptions {
+    /// Windows whose `WM_CLASS` instance name is in this list are
+    /// never considered fullscreen
+    pub exceptions_wm_class1: Vec<String>,
+    /// Windows whose `WM_CLASS` class name is in this list are never
+    /// considered fullscreen
+    pub exceptions_wm_class2: Vec<String>,
+    /// Windows whose `WM_NAME` is in this list are never considered
+    /// fullscreen
+    pub exceptions_wm_name: Vec<String>,
+    /// Windows whose `WM_WINDOW_ROLE` is in this list are never
+    /// considered fullscreen. Windows without a role never match.
+    pub exceptions_wm_role: Vec<String>,
+    /// Windows owned by a process with one of these ids, according to
+    /// `_NET_WM_PID`, are never considered fullscreen. Windows without
+    /// it never match.
+    pub exceptions_pid: Vec<u32>,
+    /// Windows owned by a process with one of these names, like `mpv`,
+    /// are never considered fullscreen. The names are looked up
+    /// through `/proc` by `_NET_WM_PID`, and the kernel cuts them off
+    /// after 15 bytes.
+    pub exceptions_process: Vec<String>,
+    /// Windows matching any of these rules are never considered
+    /// fullscreen
+    pub exceptions: Vec<Rule>,
+    /// Compare the flat exception lists (`exceptions_wm_class1`,
+    /// `exceptions_wm_class2`, `exceptions_wm_name` and
+    /// `exceptions_wm_role`) ignoring case, so `firefox` matches
+    /// `Firefox`
+    pub case_insensitive: bool,
+    /// Mapped windows whose `WM_CLASS` instance or class name is in
+    /// this list are considered fullscreen even if they don't say so,
+    /// for applications that don't set `_NET_WM_STATE_FULLSCREEN`.
+    /// Exceptions still apply to them.
+    pub force_fullscreen_classes: Vec<String>,
+    /// When checking a single window which no longer exists, return
+    /// an error rather than saying it's not fullscreen
+    pub error_on_stale_window: bool,
+    /// Don't treat sticky windows (those on all desktops) as being on
+    /// the current desktop, so that only windows on exactly the
+    /// current desktop count
+    pub ignore_sticky_windows: bool,
+    /// Count fullscreen windows on any desktop, not only the current
+    /// one, such as a video playing on another desktop. This is best
+    /// combined with `ScanMode::FullTree`, since the active window is
+    /// always on the current desktop. Earlier versions counted windows
+    /// on any desktop when walking the tree, which this brings back.
+    pub ignore_desktop_match: bool,
+    /// Window managers which don't set `_NET_CURRENT_DESKTOP`, like
+    /// many tiling ones, don't say which desktop is current, so every
+    /// window counts as on it. With this set, windows on any desktop
+    /// don't count then, other than sticky ones. Windows without
+    /// `_NET_WM_DESKTOP` always count.
+    pub strict_desktop_match: bool,
+    /// Also count minimized windows, whose `WM_STATE` is
+    /// `IconicState`. Otherwise, only shown windows count.
+    pub count_iconic: bool,
+    /// Which windows are checked
+    pub scan_mode: ScanMode,
+    /// What to do with windows that have a property in the wrong
+    /// format
+    pub on_malformed: OnMalformed,
+    /// Stop looking for a fullscreen window after examining this many
+    /// windows, to bound the cost of a check on huge window trees
+    pub max_windows: Option<usize>,
+    /// Don't look deeper into the window tree than this, if set. A
+    /// depth of 1 means only the direct children of the root window
+    /// are examined. This defaults to `DEFAULT_MAX_DEPTH`.
+    pub max_depth: Option<usize>,
+    /// Only windows on one of these RandR outputs, like `HDMI-1`,
+    /// count, if set. A window is on the output showing its center.
+    /// If the server doesn't support RandR, windows on any monitor
+    /// count.
+    pub monitors: Option<Vec<String>>,
+    /// Also count mapped override-redirect windows that cover the
+    /// whole screen, which the window manager doesn't manage and thus
+    /// never marks fullscreen, like some games and video players. This
+    /// is a guess, so it's off by default. Exceptions still apply to
+    /// them.
+    pub override_redirect: bool,
+}
+impl Default for FullscreenOptions {
+    fn default() -> Self {
+        Self {
+            exceptions_wm_class1: Vec::new(),
+            exceptions_wm_class2: Vec::new(),
+            exceptions_wm_name: Vec::new(),
+            exceptions_wm_role: Vec::new(),
+            exceptions_pid: Vec::new(),
+            exceptions_process: Vec::new(),
+            exceptions: Vec::new(),
+            case_insensitive: false,
+            force_fullscreen_classes: Vec::new(),
+            error_on_stale_window: false,
+            ignore_sticky_windows: false,
+            ignore_desktop_match: false,
+            strict_desktop_match: false,
+            count_iconic: false,
+            scan_mode: ScanMode::default(),
+            on_malformed: OnMalformed::default(),
+            max_windows: None,
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+            monitors: None,
+            override_redirect: false,
+        }
+    }
+}
+
+impl FullscreenOptions {
+    /// Replace the exceptions with those of `other`, keeping all other
+    /// settings
+    pub fn set_exceptions(&mut self, other: &Self) {
+        self.exceptions_wm_class1 = other.exceptions_wm_class1.clone();
+        self.exceptions_wm_class2 = other.exceptions_wm_class2.clone();
+        self.exceptions_wm_name = other.exceptions_wm_name.clone();
+        self.exceptions_wm_role = other.exceptions_wm_role.clone();
+        self.exceptions_pid = other.exceptions_pid.clone();
+        self.exceptions_process = other.exceptions_process.clone();
+        self.exceptions = other.exceptions.clone();
+    }
+    /// Describe a `NotWhenFullscreen` module checking windows with
+    /// these options. Rules are shown the way they're debug printed.
+    pub fn describe(&self) -> ModuleDescription {
+        let description = ModuleDescription::new("not-when-fullscreen")
+            .with("exceptions_wm_class1", &*self.exceptions_wm_class1)
+            .with("exceptions_wm_class2", &*self.exceptions_wm_class2)
+            .with("exceptions_wm_name", &*self.exceptions_wm_name)
+            .with("exceptions_wm_role", &*self.exceptions_wm_role)
+            .with(
+                "exceptions_pid",
+                self.exceptions_pid
+                    .iter()
+                    .map(u32::to_string)
+                    .collect::<Vec<_>>(),
+            )
+            .with("exceptions_process", &*self.exceptions_process)
+            .with(
+                "exceptions",
+                self.exceptions
+                    .iter()
+                    .map(|rule| format!("{:?}", rule))
+                    .collect::<Vec<_>>(),
+            )
+            .with("case_insensitive", self.case_insensitive)
+            .with("force_fullscreen_classes", &*self.force_fullscreen_classes)
+            .with("error_on_stale_window", self.error_on_stale_window)
+            .with("ignore_sticky_windows", self.ignore_sticky_windows)
+            .with("ignore_desktop_match", self.ignore_desktop_match)
+            .with("strict_desktop_match", self.strict_desktop_match)
+            .with("count_iconic", self.count_iconic)
+            .with("override_redirect", self.override_redirect)
+            .with("scan_mode", format!("{:?}", self.scan_mode))
+            .with("on_malformed", format!("{:?}", self.on_malformed));
+        let description = match self.max_windows {
+            Some(max) => description.with("max_windows", max),
+            None => description,
+        };
+        let description = match self.max_depth {
+            Some(max) => description.with("max_depth", max),
+            None => description,
+        };
+        match self.monitors {
+            Some(ref monitors) => description.with("monitors", &**monitors),
+            None => description,
+        }
+    }
+    /// Combine all exceptions, including the flat lists, into a
+    /// single rule
+    pub fn exception_rule(&self) -> Rule {
+        let case_insensitive = self.case_insensitive;
+        let rules = |list: &[String], exact: fn(String) -> Rule, matches: fn(Pattern) -> Rule| {
+            list.iter()
+                .map(|entry| {
+                    if entry.contains(['*', '?']) {
+                        matches(Pattern::glob(entry, case_insensitive))
+                    } else if case_insensitive {
+                        matches(Pattern::ignoring_case(entry))
+                    } else {
+                        exact(entry.clone())
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+        let instances = rules(
+            &self.exceptions_wm_class1,
+            Rule::Instance,
+            Rule::InstanceMatches,
+        );
+        let classes = rules(&self.exceptions_wm_class2, Rule::Class, Rule::ClassMatches);
+        let names = rules(&self.exceptions_wm_name, Rule::Name, Rule::NameMatches);
+        let roles = rules(&self.exceptions_wm_role, Rule::Role, Rule::RoleMatches);
+        let pids = self.exceptions_pid.iter().copied().map(Rule::Pid);
+        let processes = self.exceptions_process.iter().cloned().map(Rule::Process);
+        Rule::Or(
+            instances
+                .into_iter()
+                .chain(classes)
+                .chain(names)
+                .chain(roles)
+                .chain(pids)
+                .chain(processes)
+                .chain(self.exceptions.iter().cloned())
+                .collect(),
+        )
+    }
+}
+
+/// Splits the value of `WM_CLASS` into its parts. These are normally
+/// the instance and class name, each followed by a NUL, but a missing
+/// final NUL, more parts than two and empty parts are kept as they
+/// are. Only the NUL ending the last part doesn't make another one, so
+/// an empty value has no parts at all.
+pub fn parse_wm_class(value: &str) -> Vec<String> {
+    let value = value.strip_suffix('\0').unwrap_or(value);
+    if value.is_empty() {
+        return Vec::new();
+    }
+    value.split('\0').map(String::from).collect()
+}
+
+/// Returns this part of `WM_CLASS`, or an empty string if it doesn't
+/// have that many
+fn wm_class_part(wm_class: &[String], index: usize) -> &str {
+    wm_class.get(index).map_or("", String::as_str)
+}
+
+/// The properties of a window that matter for whether or not it's
+/// fullscreen, decoded from the X server's replies
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WindowProperties {
+    /// The parts of `WM_CLASS`, normally the instance and class name,
+    /// see `parse_wm_class`. This is empty if not set.
+    pub wm_class: Vec<String>,
+    /// The contents of `_NET_WM_NAME`, or `WM_NAME` if that isn't set.
+    /// This is empty if neither is set.
+    pub wm_name: String,
+    /// The contents of `WM_WINDOW_ROLE`, if set
+    pub wm_role: Option<String>,
+    /// Whether `_NET_WM_STATE` contains `_NET_WM_STATE_FULLSCREEN`
+    pub fullscreen: bool,
+    /// The first value of `WM_STATE`, if any
+    pub wm_state: Option<u32>,
+    /// The value of `_NET_WM_DESKTOP`, if any
+    pub desktop: Option<u32>,
+    /// Whether `_NET_WM_STATE` contains `_NET_WM_STATE_MODAL`
+    pub modal: bool,
+    /// The value of `WM_TRANSIENT_FOR`, if any
+    pub transient_for: Option<xcb::Window>,
+    /// The process owning the window according to `_NET_WM_PID`, if
+    /// it says
+    pub pid: Option<u32>,
+}
+impl WindowProperties {
+    /// Returns the instance name, the first part of `WM_CLASS`, or an
+    /// empty string if there is none
+    pub fn instance(&self) -> &str {
+        wm_class_part(&self.wm_class, 0)
+    }
+    /// Returns the class name, the second part of `WM_CLASS`, or an
+    /// empty string if there is none
+    pub fn class(&self) -> &str {
+        wm_class_part(&self.wm_class, 1)
+    }
+
+    /// Returns the name of the process owning the window, if it says
+    /// and the process is still running
+    pub fn process_name(&self) -> Option<String> {
+        let comm = fs::read_to_string(format!("/proc/{}/comm", self.pid?)).ok()?;
+        Some(comm.trim_end_matches('\n').to_owned())
+    }
+
+    /// Returns true if this window should be considered fullscreen,
+    /// given the current desktop (if known). The window must have
+    /// the fullscreen state, must be shown rather than withdrawn or
+    /// minimized, unless `options.count_iconic` is set, and must be on
+    /// the current desktop, unless `options.ignore_desktop_match` is
+    /// set. Sticky windows are on every desktop unless
+    /// `options.ignore_sticky_windows` is set. Windows matching
+    /// any of the exceptions are never fullscreen.
+    pub fn is_fullscreen(&self, current_desktop: Option<u32>, options: &FullscreenOptions) -> bool {
+        self.is_fullscreen_on(current_desktop.as_slice(), options)
+    }
+
+    /// Like `is_fullscreen`, but with any number of current desktops,
+    /// for window managers with a workspace per monitor which list
+    /// them all in `_NET_CURRENT_DESKTOP`. A window on any of them
+    /// counts. If there are none, the desktop is unknown, and windows
+    /// on any desktop count unless `options.strict_desktop_match` is
+    /// set.
+    pub fn is_fullscreen_on(&self, current_desktops: &[u32], options: &FullscreenOptions) -> bool {
+        let mapped = self.wm_state.is_some_and(|state| {
+            state == NORMAL_STATE || (options.count_iconic && state == ICONIC_STATE)
+        });
+        let on_current_desktop = match self.desktop {
+            _ if options.ignore_desktop_match => true,
+            // The window manager doesn't say which desktop is current
+            _ if current_desktops.is_empty() && !options.strict_desktop_match => true,
+            Some(STICKY_DESKTOP) => !options.ignore_sticky_windows,
+            Some(desktop) => current_desktops.contains(&desktop),
+            None => true,
+        };
+        let fullscreen = self.fullscreen || self.is_forced_fullscreen(options);
+        fullscreen && mapped && on_current_desktop && !self.is_exception(options)
+    }
+
+    /// Returns true if this window's `WM_CLASS` is in
+    /// `force_fullscreen_classes`
+    pub fn is_forced_fullscreen(&self, options: &FullscreenOptions) -> bool {
+        self.wm_class
+            .iter()
+            .any(|name| !name.is_empty() && options.force_fullscreen_classes.contains(name))
+    }
+
+    /// Returns true if this window matches any of the exceptions.
+    /// This is equivalent to `options.exception_rule().matches(self)`,
+    /// without building the rule.
+    pub fn is_exception(&self, options: &FullscreenOptions) -> bool {
+        let listed = |list: &[String], value: &str| {
+            if options.case_insensitive {
+                let value = value.to_lowercase();
+                list.iter()
+                    .any(|entry| glob_matches(&entry.to_lowercase(), &value))
+            } else {
+                list.iter().any(|entry| glob_matches(entry, value))
+            }
+        };
+        listed(&options.exceptions_wm_class1, self.instance())
+            || listed(&options.exceptions_wm_class2, self.class())
+            || listed(&options.exceptions_wm_name, &self.wm_name)
+            || self
+                .wm_role
+                .as_ref()
+                .is_some_and(|role| listed(&options.exceptions_wm_role, role))
+            || self
+                .pid
+                .is_some_and(|pid| options.exceptions_pid.contains(&pid))
+            || (!options.exceptions_process.is_empty()
+                && self
+                    .process_name()
+                    .is_some_and(|name| options.exceptions_process.contains(&name)))
+            || options.exceptions.iter().any(|rule| rule.matches(self))
+    }
+
+    /// Returns the window this is a modal dialog for, if any
+    pub fn modal_for(&self) -> Option<xcb::Window> {
+        if self.modal {
+            self.transient_for
+        } else {
+            None
+        }
+    }
+}
+
+/// The class, title, desktop and fullscreen state of a window, as
+/// reported by `Xcb::window_info`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct WindowInfo {
+    /// The parts of `WM_CLASS`, normally the instance and class name,
+    /// which are empty if not set
+    pub wm_class: Vec<String>,
+    /// The contents of `_NET_WM_NAME`, or else `WM_NAME`
+    pub wm_name: String,
+    /// The value of `_NET_WM_DESKTOP`, if any
+    pub desktop: Option<u32>,
+    /// Whether `_NET_WM_STATE` contains `_NET_WM_STATE_FULLSCREEN`.
+    /// Unlike `WindowProperties::is_fullscreen`, this doesn't take
+    /// the desktop or any exceptions into account.
+    pub fullscreen: bool,
+}
+impl From<WindowProperties> for WindowInfo {
+    fn from(props: WindowProperties) -> Self {
+        Self {
+            wm_class: props.wm_class,
+            wm_name: props.wm_name,
+            desktop: props.desktop,
+            fullscreen: props.fullscreen,
+        }
+    }
+}
+
+/// An area of the screen, in pixels from the top left corner of the
+/// root window
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rect {
+    /// The left edge
+    pub x: i32,
+    /// The top edge
+    pub y: i32,
+    /// The width
+    pub width: u32,
+    /// The height
+    pub height: u32,
+}
+impl Rect {
+    /// Returns true if the middle of `other` is inside this area
+    pub fn contains_center_of(&self, other: &Self) -> bool {
+        // Doubled, so that the middle of odd sizes is exact
+        let double = |start: i32, length: u32| {
+            (
+                2 * i64::from(start),
+                2 * i64::from(start) + 2 * i64::from(length),
+            )
+        };
+        let (left, right) = double(self.x, self.width);
+        let (top, bottom) = double(self.y, self.height);
+        let x = 2 * i64::from(other.x) + i64::from(other.width);
+        let y = 2 * i64::from(other.y) + i64::from(other.height);
+        (left..right).contains(&x) && (top..bottom).contains(&y)
+    }
+    /// Returns true if this area covers all of `other`
+    pub fn covers(&self, other: &Self) -> bool {
+        let edges = |rect: &Self| {
+            (
+                i64::from(rect.x),
+                i64::from(rect.y),
+                i64::from(rect.x) + i64::from(rect.width),
+                i64::from(rect.y) + i64::from(rect.height),
+            )
+        };
+        let (left, top, right, bottom) = edges(self);
+        let (other_left, other_top, other_right, other_bottom) = edges(other);
+        left <= other_left && top <= other_top && right >= other_right && bottom >= other_bottom
+    }
+}
+
+/// A RandR output which is showing part of the screen
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Monitor {
+    /// The name of the output, like `HDMI-1`
+    pub name: String,
+    /// The part of the screen the output shows
+    pub rect: Rect,
+}
+
+/// Returns true if a window covering `window` is on one of the
+/// `selected` outputs, meaning one of them shows its center. If the
+/// outputs are unknown, such as without RandR, it's on all of them.
+pub fn is_on_monitors(window: Rect, monitors: Option<&[Monitor]>, selected: &[String]) -> bool {
+    let monitors = match monitors {
+        Some(monitors) => monitors,
+        None => return true,
+    };
+    monitors
+        .iter()
+        .any(|monitor| selected.contains(&monitor.name) && monitor.rect.contains_center_of(&window))
+}
+
+/// A window which counts as fullscreen, as reported by
+/// `Xcb::fullscreen_windows`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct FullscreenHit {
+    /// The window's id
+    pub window: xcb::Window,
+    /// The index of the screen the window is on
+    pub screen: usize,
+    /// The parts of `WM_CLASS`, normally the instance and class name
+    pub wm_class: Vec<String>,
+    /// The contents of `_NET_WM_NAME`, or else `WM_NAME`
+    pub wm_name: String,
+    /// The desktop the window is on according to `_NET_WM_DESKTOP`,
+    /// which is `STICKY_DESKTOP` for all of them, if it says
+    pub desktop: Option<u32>,
+    /// The process owning the window according to `_NET_WM_PID`, if
+    /// it says
+    pub pid: Option<u32>,
+}
+
+/// Pick out all the fullscreen windows of a screen, rather than just
+/// the first
+pub fn fullscreen_hits<I>(
+    screen: usize,
+    windows: I,
+    current_desktops: &[u32],
+    options: &FullscreenOptions,
+) -> Vec<FullscreenHit>
+where
+    I: IntoIterator<Item = (xcb::Window, WindowProperties)>,
+{
+    windows
+        .into_iter()
+        .filter(|(_, props)| props.is_fullscreen_on(current_desktops, options))
+        .map(|(window, props)| FullscreenHit {
+            window,
+            screen,
+            wm_class: props.wm_class,
+            wm_name: props.wm_name,
+            desktop: props.desktop,
+            pid: props.pid,
+        })
+        .collect()
+}
+
+/// Like `WindowProperties::is_fullscreen`, but a modal dialog counts
+/// as fullscreen if the window it's transient for does, so the screen
+/// isn't blanked behind a file dialog of a fullscreen application.
+/// `properties` fetches the properties of the windows that are
+/// followed, returning `None` for windows that don't exist.
+pub fn is_fullscreen_through_modals<F>(
+    window: &WindowProperties,
+    current_desktops: &[u32],
+    options: &FullscreenOptions,
+    mut properties: F,
+) -> Result<bool>
+where
+    F: FnMut(xcb::Window) -> Result<Option<WindowProperties>>,
+{
+    if window.is_fullscreen_on(current_desktops, options) {
+        return Ok(true);
+    }
+    let mut owner = window.modal_for();
+    for _ in 0..MAX_MODAL_DEPTH {
+        let props = match owner {
+            Some(owner) => properties(owner)?,
+            None => return Ok(false),
+        };
+        match props {
+            Some(props) if props.is_fullscreen_on(current_desktops, options) => return Ok(true),
+            Some(props) => owner = props.modal_for(),
+            None => return Ok(false),
+        }
+    }
+    Ok(false)
+}
+
+/// A depth-first search over window trees, in the order `query_tree`
+/// lists them, which gives up after examining a number of windows.
+/// The limit spans all searches with the same instance, such as one
+/// per screen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WindowSearch {
+    max_windows: Option<usize>,
+    max_depth: Option<usize>,
+    examined: usize,
+    limited: bool,
+}
+impl WindowSearch {
+    /// Returns a search examining at most `max_windows` windows, if
+    /// set
+    pub fn new(max_windows: Option<usize>) -> Self {
+        Self {
+            max_windows,
+            ..Self::default()
+        }
+    }
+    /// Don't look deeper than this below the root, if set. A depth of
+    /// 1 means only its direct children are examined. Windows below
+    /// that aren't counted as missed by `is_limited`.
+    pub fn with_max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+    /// The number of windows examined so far
+    pub fn examined(&self) -> usize {
+        self.examined
+    }
+    /// Returns true if a search stopped because of the limit, meaning
+    /// not all windows were examined
+    pub fn is_limited(&self) -> bool {
+        self.limited
+    }
+    /// Find the first window below `root` for which `matches` returns
+    /// true, listing the children of each window with `children`.
+    /// Returns `None` if there's no such window, or if the limit was
+    /// reached first.
+    pub fn find<C, M>(
+        &mut self,
+        root: xcb::Window,
+        children: C,
+        mut matches: M,
+    ) -> Result<Option<xcb::Window>>
+    where
+        C: FnMut(xcb::Window) -> Result<Vec<xcb::Window>>,
+        M: FnMut(xcb::Window) -> Result<bool>,
+    {
+        self.find_batched(
+            root,
+            children,
+            |windows| Ok(windows.to_vec()),
+            |window, _| matches(window),
+        )
+    }
+    /// Like `find`, but `fetch` is called once for all children of a
+    /// window that will be examined, before any of them are, such as
+    /// to request all their properties in one round-trip. `matches`
+    /// gets what it returned for each window.
+    pub fn find_batched<C, F, P, M>(
+        &mut self,
+        root: xcb::Window,
+        mut children: C,
+        mut fetch: F,
+        mut matches: M,
+    ) -> Result<Option<xcb::Window>>
+    where
+        C: FnMut(xcb::Window) -> Result<Vec<xcb::Window>>,
+        F: FnMut(&[xcb::Window]) -> Result<Vec<P>>,
+        M: FnMut(xcb::Window, P) -> Result<bool>,
+    {
+        self.find_below(root, 1, &mut children, &mut fetch, &mut matches)
+    }
+
+    /// Like `find_batched`, but only looking at the direct children of
+    /// `root` and, for those which `is_client` says aren't client
+    /// windows, such as frames, at their children which are. This is
+    /// `ScanMode::TopLevel`, and ignores the maximum depth.
+    pub fn find_top_level<C, F, P, K, M>(
+        &mut self,
+        root: xcb::Window,
+        mut children: C,
+        mut fetch: F,
+        mut is_client: K,
+        mut matches: M,
+    ) -> Result<Option<xcb::Window>>
+    where
+        C: FnMut(xcb::Window) -> Result<Vec<xcb::Window>>,
+        F: FnMut(&[xcb::Window]) -> Result<Vec<P>>,
+        K: FnMut(&P) -> bool,
+        M: FnMut(xcb::Window, P) -> Result<bool>,
+    {
+        let mut frames = Vec::new();
+        let found = self.examine(&children(root)?, &mut fetch, |window, fetched| {
+            let client = is_client(&fetched);
+            if matches(window, fetched)? {
+                return Ok(true);
+            }
+            if !client {
+                frames.push(window);
+            }
+            Ok(false)
+        })?;
+        if found.is_some() || self.limited {
+            return Ok(found);
+        }
+        for frame in frames {
+            let found = self.examine(&children(frame)?, &mut fetch, |window, fetched| {
+                Ok(is_client(&fetched) && matches(window, fetched)?)
+            })?;
+            if found.is_some() || self.limited {
+                return Ok(found);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Examine these siblings, without looking below them, until one
+    /// matches or the limit is reached
+    fn examine<F, P, M>(
+        &mut self,
+        windows: &[xcb::Window],
+        fetch: &mut F,
+        mut matches: M,
+    ) -> Result<Option<xcb::Window>>
+    where
+        F: FnMut(&[xcb::Window]) -> Result<Vec<P>>,
+        M: FnMut(xcb::Window, P) -> Result<bool>,
+    {
+        let left = self
+            .max_windows
+            .map_or(windows.len(), |max| max.saturating_sub(self.examined));
+        let batch = &windows[..windows.len().min(left)];
+        if !batch.is_empty() {
+            for (&window, fetched) in batch.iter().zip(fetch(batch)?) {
+                self.examined += 1;
+                if matches(window, fetched)? {
+                    return Ok(Some(window));
+                }
+            }
+        }
+        if batch.len() < windows.len() {
+            self.limited = true;
+        }
+        Ok(None)
+    }
+
+    /// Search the children of `parent`, which are at this depth
+    fn find_below<C, F, P, M>(
+        &mut self,
+        parent: xcb::Window,
+        depth: usize,
+        children: &mut C,
+        fetch: &mut F,
+        matches: &mut M,
+    ) -> Result<Option<xcb::Window>>
+    where
+        C: FnMut(xcb::Window) -> Result<Vec<xcb::Window>>,
+        F: FnMut(&[xcb::Window]) -> Result<Vec<P>>,
+        M: FnMut(xcb::Window, P) -> Result<bool>,
+    {
+        let windows = children(parent)?;
+        // Don't fetch more than the limit leaves to examine
+        let left = self
+            .max_windows
+            .map_or(windows.len(), |max| max.saturating_sub(self.examined));
+        let batch = &windows[..windows.len().min(left)];
+
+        if !batch.is_empty() {
+            for (&window, fetched) in batch.iter().zip(fetch(batch)?) {
+                if self.max_windows.is_some_and(|max| self.examined >= max) {
+                    self.limited = true;
+                    return Ok(None);
+                }
+                self.examined += 1;
+                if matches(window, fetched)? {
+                    return Ok(Some(window));
+                }
+                if self.max_depth.is_some_and(|max| depth >= max) {
+                    continue;
+                }
+                let below = depth.saturating_add(1);
+                if let Some(found) = self.find_below(window, below, children, fetch, matches)? {
+                    return Ok(Some(found));
+                }
+            }
+        }
+        if batch.len() < windows.len() {
+            self.limited = true;
+        }
+        Ok(None)
+    }
+}
+
+/// The parts of the window tree needed to find the client window of a
+/// window, so that it can be walked without an X server
+pub trait WindowTree {
+    /// Returns true if this window bears `WM_STATE` or
+    /// `_NET_WM_STATE`, meaning it's a client window
+    fn is_client(&self, window: xcb::Window) -> Result<bool>;
+    /// Returns the parent of the window, or `None` for a root window
+    fn parent(&self, window: xcb::Window) -> Result<Option<xcb::Window>>;
+}
+
+/// Follow the parents of a window until a client window is found.
+/// Reparenting window managers put the EWMH state on the client
+/// window, which may not be the window that reports as focused. If no
+/// client window is found, the window itself is returned.
+pub fn resolve_client_window<T>(tree: &T, window: xcb::Window) -> Result<xcb::Window>
+where
+    T: WindowTree + ?Sized,
+{
+    let mut current = window;
+    loop {
+        if tree.is_client(current)? {
+            return Ok(current);
+        }
+        match tree.parent(current)? {
+            Some(parent) => current = parent,
+            None => return Ok(window),
+        }
+    }
+}
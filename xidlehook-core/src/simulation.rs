@@ -0,0 +1,47 @@
+//! A dry run of the timer chain, answering "what would have happened
+//! by now if the user had been idle this long?" without running
+//! anything.
+
+use std::{convert::TryInto, time::Duration};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The outcome of `Xidlehook::simulate`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Simulation {
+    /// The idle time that was simulated, in milliseconds
+    pub idle_ms: u64,
+    /// The indexes of the timers that would have fired, in order
+    pub fired: Vec<usize>,
+    /// The timer a module would have kept from firing, which ends the
+    /// chain there
+    pub inhibited: Option<usize>,
+    /// The next timer to fire, if any
+    pub next_timer: Option<usize>,
+    /// How much longer the user would need to stay idle for the next
+    /// timer to fire, in milliseconds
+    pub next_in_ms: Option<u64>,
+}
+impl Simulation {
+    /// Returns an empty simulation of this idle time
+    pub fn new(idle: Duration) -> Self {
+        Self {
+            idle_ms: duration_ms(idle),
+            ..Self::default()
+        }
+    }
+    /// Record the next timer to fire, and how long until it does
+    pub fn with_next(self, index: usize, left: Duration) -> Self {
+        Self {
+            next_timer: Some(index),
+            next_in_ms: Some(duration_ms(left)),
+            ..self
+        }
+    }
+}
+
+fn duration_ms(duration: Duration) -> u64 {
+    duration.as_millis().try_into().unwrap_or(u64::MAX)
+}
@@ -0,0 +1,66 @@
+//! Wraps another idle source so that asking for the idle time several
+//! times in quick succession, such as by the main loop and the socket
+//! in the same poll tick, only asks the inner source once.
+
+use crate::{sources::IdleSource, Result};
+
+use std::{
+    cell::Cell,
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// How long an idle time is reused, unless configured otherwise
+pub const DEFAULT_TTL: Duration = Duration::from_millis(50);
+
+/// See the module-level documentation. An idle time is reused until
+/// it's older than the TTL, or `refresh` is called, which the main
+/// loops do once per tick. Errors aren't cached.
+pub struct CachedIdleSource<S> {
+    inner: S,
+    ttl: Duration,
+    cached: Cell<Option<(Instant, Duration)>>,
+}
+impl<S: IdleSource> CachedIdleSource<S> {
+    /// Wrap the source, reusing idle times for the default TTL
+    pub fn new(inner: S) -> Self {
+        Self::with_ttl(inner, DEFAULT_TTL)
+    }
+    /// Wrap the source, reusing idle times for this long
+    pub fn with_ttl(inner: S, ttl: Duration) -> Self {
+        Self {
+            inner,
+            ttl,
+            cached: Cell::new(None),
+        }
+    }
+    /// The source this wraps
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+impl<S: IdleSource> IdleSource for CachedIdleSource<S> {
+    fn get_idle(&self) -> Result<Duration> {
+        if let Some((at, idle)) = self.cached.get() {
+            if at.elapsed() < self.ttl {
+                return Ok(idle);
+            }
+        }
+        let idle = self.inner.get_idle();
+        self.cached
+            .set(idle.as_ref().ok().map(|&idle| (Instant::now(), idle)));
+        idle
+    }
+    fn resolution(&self) -> Duration {
+        self.inner.resolution()
+    }
+    fn refresh(&self) {
+        self.cached.set(None);
+        self.inner.refresh();
+    }
+}
+impl<S: fmt::Debug> fmt::Debug for CachedIdleSource<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CachedIdleSource({:?}, {:?})", self.inner, self.ttl)
+    }
+}
@@ -0,0 +1,93 @@
+//! An idle source which runs a command each time, for integrating
+//! idle logic that xidlehook doesn't know about, such as a script
+//! querying a Wayland compositor.
+
+use crate::{sources::IdleSource, Result};
+
+use std::{
+    cell::RefCell,
+    fmt,
+    io::Read,
+    process::{Command, Stdio},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+/// How long the command may run unless configured otherwise
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Runs a command whose standard output is the idle time in
+/// milliseconds. A command which doesn't exit successfully in time,
+/// or prints anything but a number, is an error. The output is read on
+/// a thread of its own while the command runs, and the command counts
+/// as done once it closes its output.
+pub struct CommandIdleSource {
+    command: RefCell<Command>,
+    timeout: Duration,
+}
+impl CommandIdleSource {
+    /// Use this command, with the default timeout
+    pub fn new(command: Command) -> Self {
+        Self {
+            command: RefCell::new(command),
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+    /// Kill the command if it hasn't exited after this long
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+    /// How long the command may run
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+}
+impl IdleSource for CommandIdleSource {
+    fn get_idle(&self) -> Result<Duration> {
+        let mut child = self
+            .command
+            .borrow_mut()
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        // Read the output while the command runs, so that it can't fill
+        // the pipe and get stuck before exiting
+        let mut stdout = child.stdout.take().expect("stdout is piped");
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut output = String::new();
+            let read = stdout.read_to_string(&mut output).map(|_| output);
+            let _ = sender.send(read);
+        });
+
+        let output = match receiver.recv_timeout(self.timeout) {
+            Ok(output) => output,
+            Err(_) => {
+                // It may have exited in the meantime, which is fine
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("idle command didn't exit within {:?}", self.timeout).into());
+            },
+        };
+        // Its output is closed once it's done
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(format!("idle command failed: {}", status).into());
+        }
+
+        let output = output?;
+        let ms: u64 = output
+            .trim()
+            .parse()
+            .map_err(|err| format!("idle command printed {:?}: {}", output.trim(), err))?;
+        Ok(Duration::from_millis(ms))
+    }
+}
+impl fmt::Debug for CommandIdleSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CommandIdleSource({:?})", self.command.borrow())
+    }
+}
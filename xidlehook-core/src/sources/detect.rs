@@ -0,0 +1,98 @@
+//! Picking the idle source for the kind of session xidlehook runs in,
+//! from `$WAYLAND_DISPLAY` and `$DISPLAY`, so that one binary works in
+//! both X and Wayland sessions.
+
+use crate::{
+    modules::Xcb,
+    sources::{IdleSource, WaylandIdle},
+    Result,
+};
+
+use std::{env, ffi::OsString};
+
+use log::{info, warn};
+
+/// A kind of idle source that can be detected
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// `sources::WaylandIdle`
+    Wayland,
+    /// `modules::Xcb`
+    X,
+}
+impl Backend {
+    /// Returns the name of the backend, as logged
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Wayland => "wayland",
+            Self::X => "x",
+        }
+    }
+    /// Returns the backends the environment points to, in the order
+    /// they should be tried, looking variables up with `var`. Wayland
+    /// comes first, since X clients under Wayland only see the input
+    /// that goes to other X clients. Empty variables count as unset.
+    pub fn candidates<F>(var: F) -> Vec<Self>
+    where
+        F: Fn(&str) -> Option<OsString>,
+    {
+        let is_set = |name| var(name).is_some_and(|value| !value.is_empty());
+        let mut candidates = Vec::new();
+        if is_set("WAYLAND_DISPLAY") {
+            candidates.push(Self::Wayland);
+        }
+        if is_set("DISPLAY") {
+            candidates.push(Self::X);
+        }
+        candidates
+    }
+}
+
+/// Connect to the first backend in `Backend::candidates` that works,
+/// using the environment of this process
+pub fn detect_idle_source() -> Result<Box<dyn IdleSource>> {
+    detect_idle_source_with(
+        |name| env::var_os(name),
+        || Ok(Box::new(WaylandIdle::new()?)),
+        || Ok(Box::new(Xcb::new()?)),
+    )
+}
+
+/// Connect to the first backend in `Backend::candidates` that works,
+/// looking variables up with `var` and connecting with `wayland` and
+/// `x`. Which one was picked is logged. If none of them works, the
+/// error says why each of them didn't.
+pub fn detect_idle_source_with<F, W, X>(var: F, wayland: W, x: X) -> Result<Box<dyn IdleSource>>
+where
+    F: Fn(&str) -> Option<OsString>,
+    W: FnOnce() -> Result<Box<dyn IdleSource>>,
+    X: FnOnce() -> Result<Box<dyn IdleSource>>,
+{
+    let candidates = Backend::candidates(var);
+    let mut errors = Vec::new();
+    let mut connect = |backend: Backend, connected: Result<Box<dyn IdleSource>>| match connected {
+        Ok(source) => {
+            info!("Getting the idle time from {}", backend.name());
+            Some(source)
+        },
+        Err(err) => {
+            warn!("Can't get the idle time from {}: {}", backend.name(), err);
+            errors.push(format!("{}: {}", backend.name(), err));
+            None
+        },
+    };
+    if candidates.contains(&Backend::Wayland) {
+        if let Some(source) = connect(Backend::Wayland, wayland()) {
+            return Ok(source);
+        }
+    }
+    if candidates.contains(&Backend::X) {
+        if let Some(source) = connect(Backend::X, x()) {
+            return Ok(source);
+        }
+    }
+    if errors.is_empty() {
+        return Err("no idle source: neither WAYLAND_DISPLAY nor DISPLAY is set".into());
+    }
+    Err(format!("no idle source is usable ({})", errors.join(", ")).into())
+}
@@ -0,0 +1,189 @@
+//! Several idle sources tried in order, such as the X screensaver
+//! with a fallback to Mutter. Once the active one fails, the next
+//! one that works takes over, which is logged and can be hooked
+//! into with `on_backend_switch`. With `retry_after`, failed backends
+//! are skipped for a while, and taken back once they work again.
+
+use crate::{logging, sources::IdleSource, Result};
+
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// Called with the names of the old and new backend
+type SwitchHook = Box<dyn FnMut(&str, &str)>;
+
+/// See the module-level documentation. The first backend starts out
+/// active. Without `retry_after`, backends before the active one are
+/// only tried again after every later one failed, so it doesn't flap
+/// back and forth.
+#[derive(Default)]
+pub struct FallbackIdleSource {
+    backends: Vec<(String, Box<dyn IdleSource>)>,
+    active: Cell<usize>,
+    on_switch: RefCell<Option<SwitchHook>>,
+    retry_after: Option<Duration>,
+    /// When each backend last failed, by index
+    failed_at: RefCell<Vec<Option<Instant>>>,
+}
+impl FallbackIdleSource {
+    /// Returns a source without any backends, which always fails
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Add a backend to try after the others
+    pub fn push<S>(&mut self, name: impl Into<String>, source: S)
+    where
+        S: IdleSource + 'static,
+    {
+        self.backends.push((name.into(), Box::new(source)));
+        self.failed_at.get_mut().push(None);
+    }
+    /// Add a backend to try after the others. This is the chainable
+    /// version of `push`.
+    pub fn with<S>(mut self, name: impl Into<String>, source: S) -> Self
+    where
+        S: IdleSource + 'static,
+    {
+        self.push(name, source);
+        self
+    }
+    /// Call this whenever the active backend changes, with the names
+    /// of the old and new backend
+    pub fn set_on_backend_switch<F>(&mut self, hook: F)
+    where
+        F: FnMut(&str, &str) + 'static,
+    {
+        *self.on_switch.get_mut() = Some(Box::new(hook));
+    }
+    /// Call this whenever the active backend changes. This is the
+    /// chainable version of `set_on_backend_switch`.
+    pub fn with_on_backend_switch<F>(mut self, hook: F) -> Self
+    where
+        F: FnMut(&str, &str) + 'static,
+    {
+        self.set_on_backend_switch(hook);
+        self
+    }
+    /// Skip a backend for this long after it failed. After that,
+    /// backends before the active one are tried first again, and
+    /// switched back to if they work.
+    pub fn set_retry_after(&mut self, retry_after: Option<Duration>) {
+        self.retry_after = retry_after;
+    }
+    /// How long a failed backend is skipped for, if at all
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.retry_after
+    }
+    /// Skip a backend for this long after it failed. This is the
+    /// chainable version of `set_retry_after`.
+    pub fn with_retry_after(mut self, retry_after: Duration) -> Self {
+        self.set_retry_after(Some(retry_after));
+        self
+    }
+    /// The name of the backend in use, if there are any
+    pub fn active_name(&self) -> Option<&str> {
+        self.backends
+            .get(self.active.get())
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Returns true if this backend failed too recently to try it
+    fn is_demoted(&self, index: usize, now: Instant) -> bool {
+        match (self.retry_after, self.failed_at.borrow()[index]) {
+            (Some(retry_after), Some(failed_at)) => now.duration_since(failed_at) < retry_after,
+            _ => false,
+        }
+    }
+    /// Ask a backend for the idle time, noting when it fails
+    fn try_backend(&self, index: usize, now: Instant) -> Result<Duration> {
+        let result = self.backends[index].1.get_idle();
+        self.failed_at.borrow_mut()[index] = match result {
+            Ok(_) => None,
+            Err(_) => Some(now),
+        };
+        result
+    }
+
+    /// Switch to another backend, because of this error with the active
+    /// one, or because it's preferred and works again
+    fn switch(&self, to: usize, err: Option<&crate::Error>) {
+        let from = &self.backends[self.active.get()].0;
+        let to_name = &self.backends[to].0;
+        let error = err.map(ToString::to_string);
+        let mut fields = vec![
+            ("from", from.as_str().into()),
+            ("to", to_name.as_str().into()),
+        ];
+        let level = match error {
+            Some(ref error) => {
+                fields.push(("error", error.as_str().into()));
+                log::Level::Warn
+            },
+            None => log::Level::Info,
+        };
+        logging::log_event(level, "idle_backend_switch", &fields);
+        if let Some(ref mut hook) = *self.on_switch.borrow_mut() {
+            hook(from, to_name);
+        }
+        self.active.set(to);
+    }
+}
+impl IdleSource for FallbackIdleSource {
+    fn get_idle(&self) -> Result<Duration> {
+        let active = self.active.get();
+        if active >= self.backends.len() {
+            return Err("no idle source to fall back to".into());
+        }
+        let now = Instant::now();
+
+        // The preferred backends get another chance once they've been
+        // skipped for long enough
+        if self.retry_after.is_some() {
+            for index in 0..active {
+                if self.is_demoted(index, now) {
+                    continue;
+                }
+                if let Ok(idle) = self.try_backend(index, now) {
+                    self.switch(index, None);
+                    return Ok(idle);
+                }
+            }
+        }
+
+        let err = match self.try_backend(active, now) {
+            Ok(idle) => return Ok(idle),
+            Err(err) => err,
+        };
+
+        let len = self.backends.len();
+        for offset in 1..len {
+            let index = (active + offset) % len;
+            if self.is_demoted(index, now) {
+                continue;
+            }
+            if let Ok(idle) = self.try_backend(index, now) {
+                self.switch(index, Some(&err));
+                return Ok(idle);
+            }
+        }
+        Err(err)
+    }
+    fn resolution(&self) -> Duration {
+        self.backends
+            .get(self.active.get())
+            .map_or(Duration::from_secs(1), |(_, source)| source.resolution())
+    }
+    fn refresh(&self) {
+        for (_, source) in &self.backends {
+            source.refresh();
+        }
+    }
+}
+impl fmt::Debug for FallbackIdleSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FallbackIdleSource({:?})", self.active_name())
+    }
+}
@@ -0,0 +1,58 @@
+//! Deciding what to do when the idle source can't be set up, such as
+//! when xidlehook is autostarted before the X server is up.
+
+use crate::{logging, Result};
+
+use std::{convert::TryInto, thread, time::Duration};
+
+use log::info;
+
+/// What to do if the idle source can't be initialized
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MissingIdleSource {
+    /// Return the error
+    #[default]
+    Error,
+    /// Keep trying, waiting this long between attempts
+    RetryForever {
+        /// How long to wait between attempts
+        interval: Duration,
+    },
+    /// Give up without an error, for environments where xidlehook
+    /// doesn't apply
+    ExitZero,
+}
+
+/// Initialize an idle source using `init`, handling failure according
+/// to the policy. Returns `None` if it gave up without an error.
+pub fn init_idle_source<S, F>(policy: MissingIdleSource, mut init: F) -> Result<Option<S>>
+where
+    F: FnMut() -> Result<S>,
+{
+    loop {
+        let err = match init() {
+            Ok(source) => return Ok(Some(source)),
+            Err(err) => err,
+        };
+        match policy {
+            MissingIdleSource::Error => return Err(err),
+            MissingIdleSource::RetryForever { interval } => {
+                let retry_ms: u64 = interval.as_millis().try_into().unwrap_or(u64::MAX);
+                let error = err.to_string();
+                logging::log_event(
+                    log::Level::Warn,
+                    "idle_source_retry",
+                    &[
+                        ("retry_ms", retry_ms.into()),
+                        ("error", error.as_str().into()),
+                    ],
+                );
+                thread::sleep(interval);
+            },
+            MissingIdleSource::ExitZero => {
+                info!("No idle source, so there's nothing to do: {}", err);
+                return Ok(None);
+            },
+        }
+    }
+}
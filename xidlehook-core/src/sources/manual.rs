@@ -0,0 +1,53 @@
+//! An idle source whose idle time is set by hand, such as by a test
+//! or by a backend that's told about activity instead of asking.
+
+use crate::{sources::IdleSource, Result};
+
+use std::{cell::Cell, time::Duration};
+
+/// See the module-level documentation. It can be changed through a
+/// shared reference, so that it can be updated while the main loop
+/// holds on to it.
+#[derive(Debug, Default)]
+pub struct ManualIdleSource {
+    idle: Cell<Duration>,
+    failing: Cell<bool>,
+    resolution: Option<Duration>,
+}
+impl ManualIdleSource {
+    /// Returns a source reporting the given idle time
+    pub fn new(idle: Duration) -> Self {
+        Self {
+            idle: Cell::new(idle),
+            ..Self::default()
+        }
+    }
+    /// Report a different resolution than the default
+    pub fn with_resolution(mut self, resolution: Duration) -> Self {
+        self.resolution = Some(resolution);
+        self
+    }
+    /// Set the reported idle time
+    pub fn set(&self, idle: Duration) {
+        self.idle.set(idle);
+    }
+    /// Increase the reported idle time, as if the user stayed idle
+    pub fn advance(&self, by: Duration) {
+        self.idle.set(self.idle.get().saturating_add(by));
+    }
+    /// Start or stop failing, as if the backend went missing
+    pub fn set_failing(&self, failing: bool) {
+        self.failing.set(failing);
+    }
+}
+impl IdleSource for ManualIdleSource {
+    fn get_idle(&self) -> Result<Duration> {
+        if self.failing.get() {
+            return Err("the manual idle source is set to fail".into());
+        }
+        Ok(self.idle.get())
+    }
+    fn resolution(&self) -> Duration {
+        self.resolution.unwrap_or_else(|| Duration::from_secs(1))
+    }
+}
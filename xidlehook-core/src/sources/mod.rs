@@ -0,0 +1,84 @@
+//! The idle source trait and some alternative implementations. The
+//! default source, which uses the `XScreenSaver` extension, is
+//! `modules::Xcb`.
+
+use crate::Result;
+
+use std::{rc::Rc, time::Duration};
+
+/// Something that can tell how long the user has been idle for
+pub trait IdleSource {
+    /// Get the user's idle time
+    fn get_idle(&self) -> Result<Duration>;
+    /// How often the idle time changes, at best. Timers shorter than a
+    /// couple of these may not fire reliably.
+    fn resolution(&self) -> Duration {
+        Duration::from_secs(1)
+    }
+    /// Forget any idle time that's cached, so the next `get_idle`
+    /// measures it again. The main loops call this once per tick.
+    fn refresh(&self) {}
+}
+impl<S: IdleSource + ?Sized> IdleSource for Box<S> {
+    fn get_idle(&self) -> Result<Duration> {
+        (**self).get_idle()
+    }
+    fn resolution(&self) -> Duration {
+        (**self).resolution()
+    }
+    fn refresh(&self) {
+        (**self).refresh()
+    }
+}
+impl<S: IdleSource + ?Sized> IdleSource for &S {
+    fn get_idle(&self) -> Result<Duration> {
+        (**self).get_idle()
+    }
+    fn resolution(&self) -> Duration {
+        (**self).resolution()
+    }
+    fn refresh(&self) {
+        (**self).refresh()
+    }
+}
+impl<S: IdleSource + ?Sized> IdleSource for Rc<S> {
+    fn get_idle(&self) -> Result<Duration> {
+        (**self).get_idle()
+    }
+    fn resolution(&self) -> Duration {
+        (**self).resolution()
+    }
+    fn refresh(&self) {
+        (**self).refresh()
+    }
+}
+
+pub mod cached;
+pub mod command;
+pub mod detect;
+pub mod fallback;
+pub mod init;
+pub mod manual;
+#[cfg(feature = "dbus")]
+pub mod mutter;
+pub mod timeout;
+pub mod updates;
+pub mod wayland;
+#[cfg(feature = "xinput")]
+pub mod xinput;
+
+#[cfg(feature = "dbus")]
+pub use self::mutter::MutterIdleSource;
+#[cfg(feature = "xinput")]
+pub use self::xinput::{InputClass, XInput2Idle};
+pub use self::{
+    cached::CachedIdleSource,
+    command::CommandIdleSource,
+    detect::{detect_idle_source, Backend},
+    fallback::FallbackIdleSource,
+    init::{init_idle_source, MissingIdleSource},
+    manual::ManualIdleSource,
+    timeout::TimeoutIdleSource,
+    updates::IdleUpdates,
+    wayland::WaylandIdle,
+};
@@ -0,0 +1,52 @@
+//! Uses GNOME's `org.gnome.Mutter.IdleMonitor` D-Bus interface to
+//! obtain the idle time. This is accurate under GNOME, even in cases
+//! where the `XScreenSaver` extension isn't, such as with XWayland.
+
+use crate::{sources::IdleSource, Result};
+
+use std::{convert::TryInto, fmt, time::Duration};
+
+use zbus::blocking::{fdo::DBusProxy, Connection};
+
+const BUS_NAME: &str = "org.gnome.Mutter.IdleMonitor";
+const OBJECT_PATH: &str = "/org/gnome/Mutter/IdleMonitor/Core";
+const INTERFACE: &str = "org.gnome.Mutter.IdleMonitor";
+
+/// See module-level docs
+pub struct MutterIdleSource {
+    conn: Connection,
+}
+impl MutterIdleSource {
+    /// Connect to the session bus. This fails if Mutter's idle
+    /// monitor isn't present, so the caller can fall back to another
+    /// idle source.
+    pub fn new() -> Result<Self> {
+        Self::with_connection(Connection::session()?)
+    }
+    /// Use an existing bus connection. See `new`.
+    pub fn with_connection(conn: Connection) -> Result<Self> {
+        let present = DBusProxy::new(&conn)?.name_has_owner(BUS_NAME.try_into()?)?;
+        if !present {
+            return Err(format!("mutter: {} is not present on the bus", BUS_NAME).into());
+        }
+        Ok(Self { conn })
+    }
+}
+impl IdleSource for MutterIdleSource {
+    fn get_idle(&self) -> Result<Duration> {
+        let reply = self.conn.call_method(
+            Some(BUS_NAME),
+            OBJECT_PATH,
+            Some(INTERFACE),
+            "GetIdletime",
+            &(),
+        )?;
+        let millis: u64 = reply.body().deserialize()?;
+        Ok(Duration::from_millis(millis))
+    }
+}
+impl fmt::Debug for MutterIdleSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MutterIdleSource")
+    }
+}
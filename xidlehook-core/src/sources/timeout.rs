@@ -0,0 +1,113 @@
+//! Wraps another idle source with a deadline, so a backend that stops
+//! responding can't hang the main loop.
+
+use crate::{sources::IdleSource, Result};
+
+use std::{
+    cell::Cell,
+    fmt,
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::Duration,
+};
+
+use log::warn;
+
+/// Runs the inner source on a separate thread. If it doesn't respond
+/// within the timeout, the last known idle time is returned instead
+/// (or an error, if there is none yet). A late response isn't lost:
+/// it becomes the last known idle time.
+pub struct TimeoutIdleSource {
+    timeout: Duration,
+    /// The resolution of the inner source
+    resolution: Duration,
+    requests: Sender<()>,
+    responses: Receiver<Result<Duration, String>>,
+    /// Whether a request hasn't been responded to yet
+    pending: Cell<bool>,
+    last: Cell<Option<Duration>>,
+}
+impl TimeoutIdleSource {
+    /// Wrap the source. It's moved to a new thread, which stops once
+    /// this is dropped (and the inner source returns).
+    pub fn new<S>(inner: S, timeout: Duration) -> Self
+    where
+        S: IdleSource + Send + 'static,
+    {
+        let resolution = inner.resolution();
+        let (requests, worker_requests) = mpsc::channel::<()>();
+        let (worker_responses, responses) = mpsc::channel();
+
+        thread::spawn(move || {
+            for () in worker_requests {
+                let idle = inner.get_idle().map_err(|err| err.to_string());
+                if worker_responses.send(idle).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            timeout,
+            resolution,
+            requests,
+            responses,
+            pending: Cell::new(false),
+            last: Cell::new(None),
+        }
+    }
+
+    /// The timeout the inner source has to respond within
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Handle a response from the worker
+    fn handle(&self, response: Result<Duration, String>) -> Result<Duration> {
+        self.pending.set(false);
+        let idle = response?;
+        self.last.set(Some(idle));
+        Ok(idle)
+    }
+}
+impl IdleSource for TimeoutIdleSource {
+    fn get_idle(&self) -> Result<Duration> {
+        // Catch up on responses that came in too late
+        while let Ok(response) = self.responses.try_recv() {
+            // Errors from old requests don't matter anymore
+            let _ = self.handle(response);
+        }
+
+        // Don't queue up requests behind one that's still stuck
+        if !self.pending.get() {
+            self.requests
+                .send(())
+                .map_err(|_| "idle source thread has stopped")?;
+            self.pending.set(true);
+        }
+
+        match self.responses.recv_timeout(self.timeout) {
+            Ok(response) => self.handle(response),
+            Err(RecvTimeoutError::Timeout) => {
+                let last = self
+                    .last
+                    .get()
+                    .ok_or("idle source timed out before responding even once")?;
+                warn!(
+                    "Idle source didn't respond within {:?}, using the last known idle time {:?}",
+                    self.timeout, last,
+                );
+                Ok(last)
+            },
+            Err(RecvTimeoutError::Disconnected) => Err("idle source thread has stopped".into()),
+        }
+    }
+    fn resolution(&self) -> Duration {
+        self.resolution
+    }
+}
+impl fmt::Debug for TimeoutIdleSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "TimeoutIdleSource")
+    }
+}
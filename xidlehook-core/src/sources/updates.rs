@@ -0,0 +1,61 @@
+//! Polls an idle source at a fixed interval as an iterator, for
+//! programs that only want the idle time, without timers or modules:
+//!
+//! ```no_run
+//! # use std::time::Duration;
+//! # use xidlehook_core::modules::Xcb;
+//! let xcb = Xcb::new()?;
+//! for idle in xcb.idle_updates(Duration::from_secs(1)) {
+//!     println!("Idle for {:?}", idle?);
+//! }
+//! # Ok::<(), xidlehook_core::Error>(())
+//! ```
+
+use crate::{sources::IdleSource, Result};
+
+use std::{fmt, thread, time::Duration};
+
+/// See the module-level documentation. The first idle time is measured
+/// right away, and each one after sleeping for the interval. An error
+/// is yielded like any other item, so iterating can go on after it,
+/// such as once the X server is back.
+pub struct IdleUpdates<S> {
+    source: S,
+    interval: Duration,
+    started: bool,
+}
+impl<S: IdleSource> IdleUpdates<S> {
+    /// Measure the idle time of this source every `interval`
+    pub fn new(source: S, interval: Duration) -> Self {
+        Self {
+            source,
+            interval,
+            started: false,
+        }
+    }
+    /// The source this measures
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+    /// How long it sleeps between measurements
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+}
+impl<S: IdleSource> Iterator for IdleUpdates<S> {
+    type Item = Result<Duration>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.started && self.interval > Duration::default() {
+            thread::sleep(self.interval);
+        }
+        self.started = true;
+        self.source.refresh();
+        Some(self.source.get_idle())
+    }
+}
+impl<S> fmt::Debug for IdleUpdates<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "IdleUpdates(every {:?})", self.interval)
+    }
+}
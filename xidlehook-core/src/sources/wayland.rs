@@ -0,0 +1,337 @@
+//! Uses the `ext-idle-notify-v1` Wayland protocol, which wlroots
+//! compositors such as sway support, to obtain the idle time.
+//!
+//! The protocol only tells when the user went idle for a minimum time
+//! and when they came back, so the idle time is counted from the last
+//! of those events. It's only as precise as that minimum time, which
+//! is the resolution of this source.
+//!
+//! This speaks the small part of the Wayland wire protocol it needs
+//! itself, so it doesn't need any native libraries.
+
+use crate::{sources::IdleSource, Result};
+
+use std::{
+    convert::TryFrom,
+    env, fmt,
+    io::{self, Read, Write},
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How long the user has to be idle before it's noticed, unless
+/// configured otherwise
+pub const DEFAULT_THRESHOLD: Duration = Duration::from_secs(1);
+
+/// How long the compositor may take to list its globals
+const SETUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+const NOTIFIER_INTERFACE: &str = "ext_idle_notifier_v1";
+const SEAT_INTERFACE: &str = "wl_seat";
+
+// The object IDs this client allocates, in order
+const DISPLAY: u32 = 1;
+const REGISTRY: u32 = 2;
+const SYNC_CALLBACK: u32 = 3;
+const SEAT: u32 = 4;
+const NOTIFIER: u32 = 5;
+const NOTIFICATION: u32 = 6;
+
+// Request opcodes
+const DISPLAY_SYNC: u16 = 0;
+const DISPLAY_GET_REGISTRY: u16 = 1;
+const REGISTRY_BIND: u16 = 0;
+const NOTIFIER_GET_IDLE_NOTIFICATION: u16 = 1;
+
+// Event opcodes
+const DISPLAY_ERROR: u16 = 0;
+const REGISTRY_GLOBAL: u16 = 0;
+const CALLBACK_DONE: u16 = 0;
+const NOTIFICATION_IDLED: u16 = 0;
+const NOTIFICATION_RESUMED: u16 = 1;
+
+/// A single message, either way
+struct Message {
+    object: u32,
+    opcode: u16,
+    args: Vec<u8>,
+}
+impl Message {
+    fn new(object: u32, opcode: u16) -> Self {
+        Self {
+            object,
+            opcode,
+            args: Vec::new(),
+        }
+    }
+    fn uint(mut self, value: u32) -> Self {
+        self.args.extend_from_slice(&value.to_ne_bytes());
+        self
+    }
+    fn string(mut self, value: &str) -> Self {
+        // The length includes the terminating NUL, and the contents are
+        // padded to 32 bits
+        let len = u32::try_from(value.len().saturating_add(1)).unwrap_or(u32::MAX);
+        self = self.uint(len);
+        self.args.extend_from_slice(value.as_bytes());
+        self.args.push(0);
+        while self.args.len() & 3 != 0 {
+            self.args.push(0);
+        }
+        self
+    }
+    fn encode(&self) -> Vec<u8> {
+        let size = self.args.len().saturating_add(8);
+        let header = u32::try_from(size).unwrap_or(u32::MAX) << 16 | u32::from(self.opcode);
+        let mut out = Vec::with_capacity(size);
+        out.extend_from_slice(&self.object.to_ne_bytes());
+        out.extend_from_slice(&header.to_ne_bytes());
+        out.extend_from_slice(&self.args);
+        out
+    }
+    /// Read the next message, or `None` once the connection is closed
+    fn read(stream: &mut impl Read) -> Result<Option<Self>> {
+        let mut header = [0; 8];
+        match stream.read_exact(&mut header) {
+            Ok(()) => (),
+            Err(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err.into()),
+        }
+        let object = u32::from_ne_bytes([header[0], header[1], header[2], header[3]]);
+        let word = u32::from_ne_bytes([header[4], header[5], header[6], header[7]]);
+        let size = usize::try_from(word >> 16)?;
+        let opcode = u16::try_from(word & 0xFFFF)?;
+        let mut args = vec![0; size.checked_sub(8).ok_or("wayland: message is too short")?];
+        stream.read_exact(&mut args)?;
+        Ok(Some(Self {
+            object,
+            opcode,
+            args,
+        }))
+    }
+}
+
+/// Reads the arguments of an event
+struct Args<'a>(&'a [u8]);
+impl<'a> Args<'a> {
+    fn uint(&mut self) -> Result<u32> {
+        if self.0.len() < 4 {
+            return Err("wayland: event is missing arguments".into());
+        }
+        let (value, rest) = self.0.split_at(4);
+        self.0 = rest;
+        Ok(u32::from_ne_bytes([value[0], value[1], value[2], value[3]]))
+    }
+    fn string(&mut self) -> Result<String> {
+        let len = usize::try_from(self.uint()?)?;
+        let padded = len.saturating_add(3) & !3;
+        if self.0.len() < padded || len == 0 {
+            return Err("wayland: event has a malformed string".into());
+        }
+        let (value, rest) = self.0.split_at(padded);
+        self.0 = rest;
+        Ok(String::from_utf8_lossy(&value[..len - 1]).into_owned())
+    }
+}
+
+/// What the event thread learned
+#[derive(Debug)]
+struct Shared {
+    /// When the user was last active, as far as is known
+    active_at: Instant,
+    /// Whether the user is idle, in which case `active_at` is the time
+    /// the idle notification was sent minus the threshold
+    idle: bool,
+    /// Why the connection broke, if it did
+    error: Option<String>,
+}
+
+/// See the module-level documentation. Events are read on a separate
+/// thread, so that they're timestamped as soon as they arrive.
+pub struct WaylandIdle {
+    stream: UnixStream,
+    threshold: Duration,
+    shared: Arc<Mutex<Shared>>,
+}
+impl WaylandIdle {
+    /// Connect to the compositor in `WAYLAND_DISPLAY`, with the
+    /// default threshold
+    pub fn new() -> Result<Self> {
+        Self::with_threshold(DEFAULT_THRESHOLD)
+    }
+    /// Connect to the compositor in `WAYLAND_DISPLAY`. The user counts
+    /// as idle once they've been idle for the threshold.
+    pub fn with_threshold(threshold: Duration) -> Result<Self> {
+        let display =
+            env::var_os("WAYLAND_DISPLAY").ok_or("wayland: WAYLAND_DISPLAY is not set")?;
+        let mut path = PathBuf::from(display);
+        if path.is_relative() {
+            let runtime_dir =
+                env::var_os("XDG_RUNTIME_DIR").ok_or("wayland: XDG_RUNTIME_DIR is not set")?;
+            path = Path::new(&runtime_dir).join(path);
+        }
+        Self::connect(path, threshold)
+    }
+    /// Connect to the compositor listening on this socket. This fails
+    /// if it doesn't support `ext-idle-notify-v1`, so the caller can
+    /// fall back to another idle source.
+    pub fn connect(path: impl AsRef<Path>, threshold: Duration) -> Result<Self> {
+        let path = path.as_ref();
+        let mut stream = UnixStream::connect(path)
+            .map_err(|err| format!("wayland: can't connect to {}: {}", path.display(), err))?;
+        stream.set_read_timeout(Some(SETUP_TIMEOUT))?;
+
+        let mut request = Message::new(DISPLAY, DISPLAY_GET_REGISTRY)
+            .uint(REGISTRY)
+            .encode();
+        request.extend(
+            Message::new(DISPLAY, DISPLAY_SYNC)
+                .uint(SYNC_CALLBACK)
+                .encode(),
+        );
+        stream.write_all(&request)?;
+
+        // The globals are all listed before the sync is done
+        let mut seat = None;
+        let mut notifier = None;
+        loop {
+            let message = Message::read(&mut stream)?.ok_or("wayland: compositor hung up")?;
+            let mut args = Args(&message.args);
+            match (message.object, message.opcode) {
+                (DISPLAY, DISPLAY_ERROR) => return Err(display_error(args)?.into()),
+                (REGISTRY, REGISTRY_GLOBAL) => {
+                    let name = args.uint()?;
+                    let interface = args.string()?;
+                    if interface == SEAT_INTERFACE && seat.is_none() {
+                        seat = Some(name);
+                    } else if interface == NOTIFIER_INTERFACE {
+                        notifier = Some(name);
+                    }
+                },
+                (SYNC_CALLBACK, CALLBACK_DONE) => break,
+                _ => (),
+            }
+        }
+        let notifier = notifier.ok_or_else(|| {
+            format!(
+                "wayland: the compositor doesn't support {}",
+                NOTIFIER_INTERFACE
+            )
+        })?;
+        let seat = seat.ok_or("wayland: the compositor has no seat")?;
+
+        let timeout = u32::try_from(threshold.as_millis()).unwrap_or(u32::MAX);
+        let mut request = bind(seat, SEAT_INTERFACE, SEAT).encode();
+        request.extend(bind(notifier, NOTIFIER_INTERFACE, NOTIFIER).encode());
+        request.extend(
+            Message::new(NOTIFIER, NOTIFIER_GET_IDLE_NOTIFICATION)
+                .uint(NOTIFICATION)
+                .uint(timeout)
+                .uint(SEAT)
+                .encode(),
+        );
+        stream.write_all(&request)?;
+        stream.set_read_timeout(None)?;
+
+        let shared = Arc::new(Mutex::new(Shared {
+            active_at: Instant::now(),
+            idle: false,
+            error: None,
+        }));
+        let mut reader = stream.try_clone()?;
+        {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || {
+                let error = match read_events(&mut reader, &shared, threshold) {
+                    Ok(()) => String::from("wayland: compositor hung up"),
+                    Err(err) => err.to_string(),
+                };
+                if let Ok(mut shared) = shared.lock() {
+                    shared.error = Some(error);
+                }
+            });
+        }
+
+        Ok(Self {
+            stream,
+            threshold,
+            shared,
+        })
+    }
+    /// How long the user has to be idle before it's noticed
+    pub fn threshold(&self) -> Duration {
+        self.threshold
+    }
+}
+impl IdleSource for WaylandIdle {
+    fn get_idle(&self) -> Result<Duration> {
+        let shared = self
+            .shared
+            .lock()
+            .map_err(|_| "wayland: event thread panicked")?;
+        if let Some(ref error) = shared.error {
+            return Err(error.clone().into());
+        }
+        if shared.idle {
+            Ok(shared.active_at.elapsed())
+        } else {
+            Ok(Duration::default())
+        }
+    }
+    fn resolution(&self) -> Duration {
+        self.threshold
+    }
+}
+impl Drop for WaylandIdle {
+    fn drop(&mut self) {
+        // This stops the event thread
+        let _ = self.stream.shutdown(std::net::Shutdown::Both);
+    }
+}
+impl fmt::Debug for WaylandIdle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "WaylandIdle({:?})", self.threshold)
+    }
+}
+
+fn bind(name: u32, interface: &str, id: u32) -> Message {
+    Message::new(REGISTRY, REGISTRY_BIND)
+        .uint(name)
+        .string(interface)
+        .uint(1)
+        .uint(id)
+}
+
+fn display_error(mut args: Args) -> Result<String> {
+    let object = args.uint()?;
+    let code = args.uint()?;
+    let message = args.string()?;
+    Ok(format!(
+        "wayland: error {} on object {}: {}",
+        code, object, message
+    ))
+}
+
+fn read_events(stream: &mut UnixStream, shared: &Mutex<Shared>, threshold: Duration) -> Result<()> {
+    while let Some(message) = Message::read(stream)? {
+        match (message.object, message.opcode) {
+            (DISPLAY, DISPLAY_ERROR) => return Err(display_error(Args(&message.args))?.into()),
+            (NOTIFICATION, NOTIFICATION_IDLED) => {
+                let mut shared = shared.lock().map_err(|_| "wayland: poisoned lock")?;
+                let now = Instant::now();
+                shared.active_at = now.checked_sub(threshold).unwrap_or(now);
+                shared.idle = true;
+            },
+            (NOTIFICATION, NOTIFICATION_RESUMED) => {
+                let mut shared = shared.lock().map_err(|_| "wayland: poisoned lock")?;
+                shared.active_at = Instant::now();
+                shared.idle = false;
+            },
+            _ => (),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,318 @@
+//! Uses XInput2 raw events to obtain the idle time. Unlike the
+//! `XScreenSaver` idle counter, these are sent for every input device,
+//! including gamepads and tablets that aren't attached to the pointer
+//! or keyboard.
+//!
+//! The idle time is counted from the last event, which is noticed as
+//! soon as it arrives. Before the first event, it's counted from when
+//! the source was created.
+//!
+//! This needs `libxcb-xinput`. The few requests it makes are declared
+//! here, since the `xcb` crate's bindings for it don't build.
+
+use crate::{modules::xcb::XidlehookError, sources::IdleSource, Result};
+
+use std::{
+    fmt, ptr,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::debug;
+use nix::libc;
+
+const EXTENSION: &str = "XInputExtension";
+
+// From XI2.h
+const ALL_DEVICES: u16 = 0;
+const RAW_KEY_PRESS: u16 = 13;
+const RAW_KEY_RELEASE: u16 = 14;
+const RAW_BUTTON_PRESS: u16 = 15;
+const RAW_BUTTON_RELEASE: u16 = 16;
+const RAW_MOTION: u16 = 17;
+const RAW_TOUCH_BEGIN: u16 = 22;
+const RAW_TOUCH_UPDATE: u16 = 23;
+const RAW_TOUCH_END: u16 = 24;
+
+#[allow(non_camel_case_types)]
+mod ffi {
+    use nix::libc::c_uint;
+    use xcb::ffi::{xcb_connection_t, xcb_generic_error_t, xcb_void_cookie_t, xcb_window_t};
+
+    /// The mask argument of `XISelectEvents`, with a single word of
+    /// event bits
+    #[repr(C)]
+    pub(super) struct xcb_input_event_mask_t {
+        pub(super) deviceid: u16,
+        pub(super) mask_len: u16,
+        pub(super) mask: u32,
+    }
+
+    #[derive(Clone, Copy)]
+    #[repr(C)]
+    pub(super) struct xcb_input_xi_query_version_cookie_t {
+        pub(super) sequence: c_uint,
+    }
+
+    #[repr(C)]
+    pub(super) struct xcb_input_xi_query_version_reply_t {
+        pub(super) response_type: u8,
+        pub(super) pad0: u8,
+        pub(super) sequence: u16,
+        pub(super) length: u32,
+        pub(super) major_version: u16,
+        pub(super) minor_version: u16,
+        pub(super) pad1: [u8; 20],
+    }
+
+    #[link(name = "xcb-xinput")]
+    extern "C" {
+        pub(super) fn xcb_input_xi_query_version(
+            c: *mut xcb_connection_t,
+            major_version: u16,
+            minor_version: u16,
+        ) -> xcb_input_xi_query_version_cookie_t;
+        pub(super) fn xcb_input_xi_query_version_reply(
+            c: *mut xcb_connection_t,
+            cookie: xcb_input_xi_query_version_cookie_t,
+            error: *mut *mut xcb_generic_error_t,
+        ) -> *mut xcb_input_xi_query_version_reply_t;
+        pub(super) fn xcb_input_xi_select_events_checked(
+            c: *mut xcb_connection_t,
+            window: xcb_window_t,
+            num_mask: u16,
+            masks: *const xcb_input_event_mask_t,
+        ) -> xcb_void_cookie_t;
+    }
+}
+
+/// A kind of input that resets the idle time
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputClass {
+    /// Key presses and releases
+    Keys,
+    /// Button presses and releases, such as of a mouse or gamepad
+    Buttons,
+    /// Moving an axis, such as of a mouse, stylus or gamepad stick
+    Motion,
+    /// Touches on a touchscreen or touchpad
+    Touch,
+}
+impl InputClass {
+    /// Every kind of input
+    pub const ALL: &'static [Self] = &[Self::Keys, Self::Buttons, Self::Motion, Self::Touch];
+
+    fn event_types(self) -> &'static [u16] {
+        match self {
+            Self::Keys => &[RAW_KEY_PRESS, RAW_KEY_RELEASE],
+            Self::Buttons => &[RAW_BUTTON_PRESS, RAW_BUTTON_RELEASE],
+            Self::Motion => &[RAW_MOTION],
+            Self::Touch => &[RAW_TOUCH_BEGIN, RAW_TOUCH_UPDATE, RAW_TOUCH_END],
+        }
+    }
+}
+
+/// What the event thread learned
+#[derive(Debug)]
+struct Shared {
+    active_at: Instant,
+    /// Why the connection broke, if it did
+    error: Option<String>,
+}
+
+/// See the module-level documentation. Events are read on a separate
+/// thread, from a connection of its own.
+pub struct XInput2Idle {
+    conn: Arc<xcb::Connection>,
+    /// Destroying this window stops the event thread
+    window: xcb::Window,
+    classes: Vec<InputClass>,
+    shared: Arc<Mutex<Shared>>,
+}
+impl XInput2Idle {
+    /// Connect to the X server, counting every kind of input
+    pub fn new() -> Result<Self> {
+        Self::with_classes(InputClass::ALL)
+    }
+    /// Connect to the X server, counting only these kinds of input.
+    /// This fails if the server doesn't support XInput 2, so the
+    /// caller can fall back to another idle source.
+    pub fn with_classes(classes: &[InputClass]) -> Result<Self> {
+        let (conn, _) = xcb::Connection::connect(None)?;
+
+        let extension = xcb::xproto::query_extension(&conn, EXTENSION).get_reply()?;
+        if !extension.present() {
+            return Err(XidlehookError::MissingExtension(EXTENSION).into());
+        }
+        let opcode = extension.major_opcode();
+
+        // The server only sends XInput 2 events to clients that asked
+        // for its version
+        let (major, minor) = unsafe {
+            let cookie = ffi::xcb_input_xi_query_version(conn.get_raw_conn(), 2, 0);
+            let mut err = ptr::null_mut();
+            let reply =
+                ffi::xcb_input_xi_query_version_reply(conn.get_raw_conn(), cookie, &mut err);
+            if reply.is_null() {
+                return Err(xcb::GenericError { ptr: err }.into());
+            }
+            let version = ((*reply).major_version, (*reply).minor_version);
+            libc::free(reply.cast());
+            version
+        };
+        if major < 2 {
+            return Err(format!(
+                "xinput: the X server only supports XInput {}.{}",
+                major, minor
+            )
+            .into());
+        }
+
+        let setup = conn.get_setup();
+        let screen = setup.roots().next().ok_or(XidlehookError::NoRoot)?;
+        let events = ffi::xcb_input_event_mask_t {
+            deviceid: ALL_DEVICES,
+            mask_len: 1,
+            mask: classes
+                .iter()
+                .flat_map(|class| class.event_types())
+                .fold(0, |mask, &event_type| mask | 1 << event_type),
+        };
+        // Raw events are only ever sent to the root window
+        xcb::VoidCookie {
+            cookie: unsafe {
+                ffi::xcb_input_xi_select_events_checked(
+                    conn.get_raw_conn(),
+                    screen.root(),
+                    1,
+                    &events,
+                )
+            },
+            conn: &conn,
+            checked: true,
+        }
+        .request_check()?;
+
+        let window = conn.generate_id();
+        xcb::xproto::create_window_checked(
+            &conn,
+            xcb::COPY_FROM_PARENT as u8,
+            window,
+            screen.root(),
+            0,
+            0,
+            1,
+            1,
+            0,
+            xcb::xproto::WINDOW_CLASS_INPUT_ONLY as u16,
+            screen.root_visual(),
+            &[(
+                xcb::xproto::CW_EVENT_MASK,
+                xcb::xproto::EVENT_MASK_STRUCTURE_NOTIFY,
+            )],
+        )
+        .request_check()?;
+
+        let conn = Arc::new(conn);
+        let shared = Arc::new(Mutex::new(Shared {
+            active_at: Instant::now(),
+            error: None,
+        }));
+        {
+            let conn = Arc::clone(&conn);
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || {
+                let error = read_events(&conn, &shared, opcode, window);
+                if let Ok(mut shared) = shared.lock() {
+                    shared.error = error;
+                }
+            });
+        }
+
+        Ok(Self {
+            conn,
+            window,
+            classes: classes.to_vec(),
+            shared,
+        })
+    }
+    /// The kinds of input that are counted
+    pub fn classes(&self) -> &[InputClass] {
+        &self.classes
+    }
+}
+impl IdleSource for XInput2Idle {
+    fn get_idle(&self) -> Result<Duration> {
+        let shared = self
+            .shared
+            .lock()
+            .map_err(|_| "xinput: event thread panicked")?;
+        if let Some(ref error) = shared.error {
+            return Err(error.clone().into());
+        }
+        Ok(shared.active_at.elapsed())
+    }
+    fn resolution(&self) -> Duration {
+        // Events are noticed as soon as they arrive
+        Duration::from_millis(1)
+    }
+}
+impl Drop for XInput2Idle {
+    fn drop(&mut self) {
+        xcb::xproto::destroy_window(&self.conn, self.window);
+        self.conn.flush();
+    }
+}
+impl fmt::Debug for XInput2Idle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "XInput2Idle({:?})", self.classes)
+    }
+}
+
+/// Read events until the window is destroyed, returning why the
+/// connection broke if it did
+fn read_events(
+    conn: &xcb::Connection,
+    shared: &Mutex<Shared>,
+    opcode: u8,
+    window: xcb::Window,
+) -> Option<String> {
+    loop {
+        let event = match conn.wait_for_event() {
+            Some(event) => event,
+            None => {
+                return Some(match conn.has_error() {
+                    Err(err) => format!("xinput: the connection broke: {}", err),
+                    Ok(()) => String::from("xinput: the connection broke"),
+                })
+            },
+        };
+        match event.response_type() & !0x80 {
+            xcb::xproto::GE_GENERIC => {
+                let event: &xcb::GeGenericEvent = unsafe { xcb::cast_event(&event) };
+                let (extension, event_type) =
+                    unsafe { ((*event.ptr).extension, (*event.ptr).event_type) };
+                // Only the selected kinds of raw events are sent
+                if extension == opcode && is_raw_event(event_type) {
+                    let mut shared = shared.lock().ok()?;
+                    shared.active_at = Instant::now();
+                }
+            },
+            xcb::xproto::DESTROY_NOTIFY => {
+                let event: &xcb::DestroyNotifyEvent = unsafe { xcb::cast_event(&event) };
+                if event.window() == window {
+                    debug!("Stopping the XInput event thread");
+                    return None;
+                }
+            },
+            _ => (),
+        }
+    }
+}
+
+fn is_raw_event(event_type: u16) -> bool {
+    InputClass::ALL
+        .iter()
+        .any(|class| class.event_types().contains(&event_type))
+}
@@ -0,0 +1,102 @@
+//! Runtime state worth keeping across restarts, such as an upgrade,
+//! written to a JSON file on shutdown and read back on startup:
+//!
+//! ```json
+//! {"version":1,"presentation":true,"disabled":false,"once_per_day":{"backup":1602681600},"counters":{}}
+//! ```
+//!
+//! The idle progress isn't kept, a restarted daemon starts counting
+//! from zero. The file carries a schema version, so that files written
+//! by older versions can still be read, and newer ones are refused
+//! rather than misread.
+
+use crate::Result;
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// The schema version written by this version of xidlehook
+pub const STATE_VERSION: u32 = 1;
+
+/// See the module-level documentation. Fields missing from the file
+/// keep their default.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct State {
+    /// The schema version of the file
+    pub version: u32,
+    /// Whether presentation mode was on
+    pub presentation: bool,
+    /// Whether timers were disabled
+    pub disabled: bool,
+    /// When each once-per-day timer, by name, last ran, in seconds
+    /// since the Unix epoch
+    pub once_per_day: BTreeMap<String, u64>,
+    /// Statistics counters, by name
+    pub counters: BTreeMap<String, u64>,
+}
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            version: STATE_VERSION,
+            presentation: false,
+            disabled: false,
+            once_per_day: BTreeMap::new(),
+            counters: BTreeMap::new(),
+        }
+    }
+}
+impl State {
+    /// Parse a state file's contents, refusing schema versions newer
+    /// than `STATE_VERSION`
+    pub fn from_json(input: &str) -> Result<Self> {
+        let mut state: Self = serde_json::from_str(input)?;
+        if state.version > STATE_VERSION {
+            return Err(format!(
+                "state file has version {}, but only up to {} is supported",
+                state.version, STATE_VERSION
+            )
+            .into());
+        }
+        // Version 1 is the first, so there's nothing to migrate yet
+        state.version = STATE_VERSION;
+        Ok(state)
+    }
+
+    /// Serialize the state, as the current schema version
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&Self {
+            version: STATE_VERSION,
+            ..self.clone()
+        })?)
+    }
+
+    /// Read the state file, returning `None` if there is none yet
+    pub fn load(path: impl AsRef<Path>) -> Result<Option<Self>> {
+        match fs::read_to_string(path) {
+            Ok(input) => Ok(Some(Self::from_json(&input)?)),
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Write the state file. It's written next to the file first and
+    /// then renamed over it, so a crash never leaves half a file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+
+        let mut file = fs::File::create(&temporary)?;
+        file.write_all(self.to_json()?.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&temporary, path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,205 @@
+//! A snapshot of xidlehook's state, for status bars and other
+//! consumers. The reported idle time can be rounded, so a display
+//! like "idle: 4m" doesn't flicker. It's purely for display and has
+//! no effect on when timers go off.
+
+use crate::Progress;
+
+use std::{
+    convert::TryInto,
+    fmt,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// See the module-level documentation
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Status {
+    /// The idle time in milliseconds, rounded to the configured unit
+    pub idle_ms: u64,
+    /// The idle time in milliseconds, as it was measured
+    pub raw_idle_ms: u64,
+    /// The number of windows in the X property cache, see
+    /// `modules::xcb::WindowCache`
+    pub window_cache_entries: usize,
+    /// When the daemon was started, in seconds since the Unix epoch
+    pub started_at: u64,
+    /// How long the daemon has been running, in seconds
+    pub uptime_seconds: u64,
+    /// The names of the modules, in the order they're evaluated in,
+    /// see `modules::ModuleList`
+    pub module_order: Vec<String>,
+    /// Whether all timers are kept from running, such as with SIGUSR1
+    pub paused: bool,
+}
+impl Status {
+    /// Returns the status for this idle time, rounded to the nearest
+    /// multiple of `rounding`. A zero `rounding` means no rounding.
+    /// The other fields are left for the caller to fill in.
+    pub fn new(idle: Duration, rounding: Duration) -> Self {
+        let raw_idle_ms = duration_ms(idle);
+        Self {
+            idle_ms: round_ms(raw_idle_ms, duration_ms(rounding)),
+            raw_idle_ms,
+            window_cache_entries: 0,
+            started_at: 0,
+            uptime_seconds: 0,
+            module_order: Vec::new(),
+            paused: false,
+        }
+    }
+    /// Fill in when the daemon was started, and how long ago that was,
+    /// such as from `Xidlehook::started_at` and `Xidlehook::uptime`
+    pub fn with_uptime(self, started_at: SystemTime, uptime: Duration) -> Self {
+        Self {
+            started_at: started_at
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs()),
+            uptime_seconds: uptime.as_secs(),
+            ..self
+        }
+    }
+}
+
+/// The exit code for scripts asking if timers are inhibited, when
+/// the daemon can't be reached
+pub const EXIT_NOT_RUNNING: i32 = 2;
+
+/// A module which would keep timers from running right now
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Inhibitor {
+    /// The name of the module, see `modules::ModuleList`
+    pub module: String,
+    /// What it would do instead, see `Progress::name`
+    pub verdict: String,
+}
+
+/// Whether timers are being kept from running right now, and by
+/// which modules
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Inhibition {
+    /// Every module keeping timers from running, in order
+    pub inhibitors: Vec<Inhibitor>,
+}
+impl Inhibition {
+    /// Collect the modules that don't say `Progress::Continue`, such
+    /// as from `ModuleList::verdicts`
+    pub fn new<I, S>(verdicts: I) -> Self
+    where
+        I: IntoIterator<Item = (S, Progress)>,
+        S: Into<String>,
+    {
+        Self {
+            inhibitors: verdicts
+                .into_iter()
+                .filter(|(_, verdict)| *verdict != Progress::Continue)
+                .map(|(module, verdict)| Inhibitor {
+                    module: module.into(),
+                    verdict: verdict.name().into(),
+                })
+                .collect(),
+        }
+    }
+    /// Returns true if any module keeps timers from running
+    pub fn is_inhibited(&self) -> bool {
+        !self.inhibitors.is_empty()
+    }
+    /// The exit code for scripts asking if timers are inhibited: 0 if
+    /// they are, so it can be used in `if`, and 1 if not. See also
+    /// `EXIT_NOT_RUNNING`.
+    pub fn exit_code(&self) -> i32 {
+        if self.is_inhibited() {
+            0
+        } else {
+            1
+        }
+    }
+}
+
+/// A timer as shown in an `Overview`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct TimerStatus {
+    /// The position of the timer, starting at 0
+    pub index: usize,
+    /// How long the user has to be idle for the timer to go off
+    pub duration_ms: u64,
+    /// Whether the timer went off since the user was last active
+    pub fired: bool,
+    /// What the timer runs when it goes off
+    pub command: String,
+}
+
+/// The idle time, whether a window is fullscreen, what keeps timers
+/// from running and how far along the timers are, all at once. The
+/// `Display` implementation is the plain text form meant for humans.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Overview {
+    /// The idle time in milliseconds, unrounded
+    pub idle_ms: u64,
+    /// Whether any window is fullscreen right now
+    pub fullscreen: bool,
+    /// What keeps timers from running
+    pub inhibition: Inhibition,
+    /// Every timer, in order
+    pub timers: Vec<TimerStatus>,
+}
+impl fmt::Display for Overview {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "idle: {:?}", Duration::from_millis(self.idle_ms))?;
+        writeln!(
+            f,
+            "fullscreen: {}",
+            if self.fullscreen { "yes" } else { "no" }
+        )?;
+        if self.inhibition.is_inhibited() {
+            let inhibitors: Vec<String> = self
+                .inhibition
+                .inhibitors
+                .iter()
+                .map(|inhibitor| format!("{} ({})", inhibitor.module, inhibitor.verdict))
+                .collect();
+            writeln!(f, "inhibited by: {}", inhibitors.join(", "))?;
+        } else {
+            writeln!(f, "inhibited by: nothing")?;
+        }
+        for timer in &self.timers {
+            write!(
+                f,
+                "timer {}: {:?}, {}",
+                timer.index,
+                Duration::from_millis(timer.duration_ms),
+                if timer.fired { "fired" } else { "pending" }
+            )?;
+            if !timer.command.is_empty() {
+                write!(f, ", {}", timer.command)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn duration_ms(duration: Duration) -> u64 {
+    duration.as_millis().try_into().unwrap_or(u64::MAX)
+}
+
+/// Round to the nearest multiple of `unit`, with halves rounding up
+fn round_ms(ms: u64, unit: u64) -> u64 {
+    if unit == 0 {
+        return ms;
+    }
+    let remainder = ms % unit;
+    let down = ms - remainder;
+    if remainder >= unit - remainder {
+        down.saturating_add(unit)
+    } else {
+        down
+    }
+}
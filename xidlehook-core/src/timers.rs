@@ -1,11 +1,38 @@
 //! The timer trait and some useful implementations
 
-use crate::Result;
+use crate::{logging, Result};
 use std::{
-    process::{Child, Command},
-    time::Duration,
+    cell::{Cell, RefCell},
+    cmp,
+    collections::VecDeque,
+    convert::TryFrom,
+    env,
+    ffi::{OsStr, OsString},
+    fmt, fs,
+    io::{self, Write},
+    mem,
+    os::unix::{
+        ffi::{OsStrExt, OsStringExt},
+        process::CommandExt,
+    },
+    path::PathBuf,
+    process::{Child, ChildStdin, Command, Stdio},
+    rc::Rc,
+    time::{Duration, Instant},
 };
 
+use log::warn;
+use nix::{
+    libc,
+    sys::{
+        signal::{self, Signal},
+        wait::{self, WaitPidFlag, WaitStatus},
+    },
+    unistd::Pid,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 /// The timer trait is used to tell xidlehook after how much idle time
 /// your timer should activate (relatively), and what activation
 /// actually means. It also provides you with the ability to implement
@@ -20,6 +47,17 @@ pub trait Timer {
     fn abort_urgency(&self) -> Option<Duration> {
         None
     }
+    /// How often this timer wants to be polled at most, whether or not it's close to going off,
+    /// such as to notice activity in between. The main loop never sleeps longer than this, but
+    /// may sleep less.
+    fn poll_interval(&self) -> Option<Duration> {
+        None
+    }
+    /// Called on every poll, for every timer, whether or not it's next in the chain, such as to
+    /// clean up after commands that exited
+    fn reap(&mut self) -> Result<()> {
+        Ok(())
+    }
 
     /// Called when the timer was activated
     fn activate(&mut self) -> Result<()> {
@@ -34,16 +72,158 @@ pub trait Timer {
     fn deactivate(&mut self) -> Result<()> {
         Ok(())
     }
+    /// Called when the idle chain starts over, such as when the user becomes active again
+    fn reset(&mut self) -> Result<()> {
+        Ok(())
+    }
     /// Return true if the timer is disabled and should be skipped. Changes to this value are
     /// reflected - you may enable a timer that was previously disabled, and xidlehook will call it
     /// as soon as the timer is passed - or immediately if the timer has already passed.
     fn disabled(&mut self) -> bool {
         false
     }
+    /// A band around the threshold in which the idle time may jitter: once the timer fired, the
+    /// idle time falling by less than this isn't taken as activity, and once that cancelled
+    /// it, it only fires again past its threshold plus this.
+    fn dead_zone(&self) -> Duration {
+        Duration::default()
+    }
+    /// If set, the timer goes off again this long after each time it went off, for as long as the
+    /// user stays idle and no later timer went off. A zero interval counts as not recurring.
+    fn recurring(&self) -> Option<Duration> {
+        None
+    }
+    /// Return true if the timer may only go off once until the user is active again. Otherwise,
+    /// it goes off again when the chain starts over without the user, such as when a module
+    /// returns `Progress::Reset`. A timer that only goes off once doesn't recur either.
+    fn once(&self) -> bool {
+        false
+    }
+    /// If set, the timer waits to go off until the user has been idle this long in total, even if
+    /// its own time passed, such as after the chain started over with `Progress::Reset`.
+    fn min_idle(&self) -> Option<Duration> {
+        None
+    }
+    /// If set, the timer is skipped once the user has been idle longer than this in total, as if
+    /// it was disabled, and stops recurring. Other timers go off as usual.
+    fn max_idle(&self) -> Option<Duration> {
+        None
+    }
+    /// Called on every poll after the timer was activated, until the idle chain starts over,
+    /// such as to keep an eye on its command. Return how soon it should be called again, if ever.
+    fn supervise(&mut self) -> Result<Option<Duration>> {
+        Ok(None)
+    }
+    /// If set, `warn` is called this long before the timer goes off, such as to tell the user
+    /// the screen is about to be locked.
+    fn warning_lead(&self) -> Option<Duration> {
+        None
+    }
+    /// Called `warning_lead` before the timer goes off, if the module doesn't object
+    fn warn(&mut self) -> Result<()> {
+        Ok(())
+    }
+    /// Called instead of `abort` when the user becomes active after `warn`, but before the timer
+    /// went off
+    fn cancel_warning(&mut self) -> Result<()> {
+        Ok(())
+    }
+    /// Called with how long the user has been idle right before `activate` or `warn`, such as to
+    /// pass it on to a command
+    fn set_idle_time(&mut self, _idle_time: Duration) {}
+    /// Called to run the activation as a test, see `Xidlehook::test_activation`. It shouldn't
+    /// change the state of the timer, such as whether it's disabled while its command runs. This
+    /// is `activate` by default.
+    fn test_activation(&mut self) -> Result<()> {
+        self.activate()
+    }
+    /// Called to run the abortion as a test, after `test_activation`. This is `abort` by default.
+    fn test_abortion(&mut self) -> Result<()> {
+        self.abort()
+    }
 }
 
+impl<'a> Timer for Box<dyn Timer + 'a> {
+    fn time_left(&mut self, idle_time: Duration) -> Result<Option<Duration>> {
+        (**self).time_left(idle_time)
+    }
+    fn abort_urgency(&self) -> Option<Duration> {
+        (**self).abort_urgency()
+    }
+    fn poll_interval(&self) -> Option<Duration> {
+        (**self).poll_interval()
+    }
+    fn reap(&mut self) -> Result<()> {
+        (**self).reap()
+    }
+    fn activate(&mut self) -> Result<()> {
+        (**self).activate()
+    }
+    fn abort(&mut self) -> Result<()> {
+        (**self).abort()
+    }
+    fn deactivate(&mut self) -> Result<()> {
+        (**self).deactivate()
+    }
+    fn reset(&mut self) -> Result<()> {
+        (**self).reset()
+    }
+    fn disabled(&mut self) -> bool {
+        (**self).disabled()
+    }
+    fn dead_zone(&self) -> Duration {
+        (**self).dead_zone()
+    }
+    fn recurring(&self) -> Option<Duration> {
+        (**self).recurring()
+    }
+    fn once(&self) -> bool {
+        (**self).once()
+    }
+    fn min_idle(&self) -> Option<Duration> {
+        (**self).min_idle()
+    }
+    fn max_idle(&self) -> Option<Duration> {
+        (**self).max_idle()
+    }
+    fn supervise(&mut self) -> Result<Option<Duration>> {
+        (**self).supervise()
+    }
+    fn warning_lead(&self) -> Option<Duration> {
+        (**self).warning_lead()
+    }
+    fn warn(&mut self) -> Result<()> {
+        (**self).warn()
+    }
+    fn cancel_warning(&mut self) -> Result<()> {
+        (**self).cancel_warning()
+    }
+    fn set_idle_time(&mut self, idle_time: Duration) {
+        (**self).set_idle_time(idle_time)
+    }
+    fn test_activation(&mut self) -> Result<()> {
+        (**self).test_activation()
+    }
+    fn test_abortion(&mut self) -> Result<()> {
+        (**self).test_abortion()
+    }
+}
+
+/// How often `CmdTimer` checks whether its activation command is
+/// still running, with `relock_on_crash`
+const RELOCK_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
 /// A simple timer that runs a binary executable after a certain
-/// amount of time
+/// amount of time. Commands are spawned as they are, not through a
+/// shell, unless they were built from a `TimerCommand::Shell`. The activation and warning commands get
+/// `$XIDLEHOOK_IDLE_MS` set to how many milliseconds the user has been
+/// idle, and the abortion and deactivation commands get
+/// `$XIDLEHOOK_PID` set to the process id of the activation command.
+///
+/// Commands are reaped once they exit, so that they don't linger as
+/// zombies: the activation and warning commands while they're waited
+/// for, and all others on every poll (see `Timer::reap`). They're in
+/// xidlehook's process group unless `detached` is set.
 #[derive(Debug, Default)]
 pub struct CmdTimer {
     /// The idle time required for this timer to activate
@@ -56,9 +236,134 @@ pub struct CmdTimer {
     pub deactivation: Option<Command>,
     /// Whether or not to disable this timer
     pub disabled: bool,
+    /// How much the idle time may jitter around the threshold, see
+    /// `Timer::dead_zone`
+    pub dead_zone: Duration,
+    /// How often to run the activation command again while the user
+    /// stays idle after it, if at all, see `Timer::recurring`
+    pub recurring: Option<Duration>,
+    /// Whether to only go off once until the user is active again,
+    /// see `Timer::once`
+    pub once: bool,
+    /// How long the user must have been idle in total for this timer
+    /// to go off, if set, see `Timer::min_idle`
+    pub min_idle: Option<Duration>,
+    /// How long the user may have been idle in total for this timer to
+    /// still go off, if set, see `Timer::max_idle`
+    pub max_idle: Option<Duration>,
+    /// The command, if any, to run `warning_lead` before activation.
+    /// It's killed if the user becomes active before activation.
+    pub warning: Option<Command>,
+    /// How long before activation to run the warning command
+    pub warning_lead: Duration,
+    /// The warning command, while it may still be running
+    pub warning_child: Option<Child>,
 
     /// The child process that is currently running
     pub activation_child: Option<Child>,
+    /// Whether to run the activation command again if it exits
+    /// unsuccessfully before the user is active, such as when a
+    /// screen locker crashes or is killed
+    pub relock_on_crash: bool,
+    /// Whether to send the activation command a signal if it's still
+    /// running when the idle chain starts over, such as when the user
+    /// is active again during a fade-out
+    pub kill_on_resume: bool,
+    /// The signal `kill_on_resume` sends, `SIGTERM` if not set
+    pub kill_signal: Option<Signal>,
+    /// Whether to start the commands in a process group of their own,
+    /// so that they aren't killed along with xidlehook, such as by
+    /// Ctrl+C in its terminal, and keep running after it exits. A
+    /// spawn hook which builds its own command decides for itself.
+    pub detached: bool,
+    /// The options the commands were built with, if `$VAR` and
+    /// `${VAR}` in their arguments are to be expanded every time
+    /// they're spawned, see `expand_vars`. That's how a
+    /// `TimerCommand::Argv` gets `$XIDLEHOOK_IDLE_MS`, while commands
+    /// which are `sh -c` scripts are left for the shell to expand.
+    pub expand_args: Option<SpawnOptions>,
+    /// The other commands that were started and may still be running,
+    /// until they're reaped
+    pub children: Vec<Child>,
+    /// The process id of the activation command last run as a test,
+    /// for the canceller run as a test
+    pub test_pid: Option<u32>,
+
+    /// The limit on concurrently running commands to spawn through,
+    /// if any. Share it between timers with `Rc::clone`.
+    pub limit: Option<Rc<CommandLimit>>,
+    /// Launches the commands instead of `Command::spawn`, if set
+    pub spawn_hook: Option<SpawnHook>,
+    /// Runs the abortion and deactivation commands instead of
+    /// spawning them, if set. These bypass the limit and spawn hook.
+    pub worker: Option<Rc<ShellWorker>>,
+}
+impl CmdTimer {
+    /// Spawn a command, through the limit if there is one
+    fn spawn(
+        limit: Option<&CommandLimit>,
+        hook: Option<&SpawnHook>,
+        cmd: &mut Command,
+        detached: bool,
+        expand: Option<&SpawnOptions>,
+    ) -> Result<Option<Child>> {
+        let mut expanded = expand.and_then(|options| expand_command(cmd, options));
+        let cmd = expanded.as_mut().unwrap_or(cmd);
+        if detached {
+            cmd.process_group(0);
+        }
+        match limit {
+            Some(limit) => limit.spawn_or_queue(cmd, hook, detached),
+            None => SpawnHook::spawn_or_default(hook, cmd).map(Some),
+        }
+    }
+    /// Spawn a copy of a command as a test, with the `$XIDLEHOOK_PID`
+    /// of the test activation
+    fn spawn_test(&mut self, mut copy: Command, pid: Option<u32>) -> Result<Option<u32>> {
+        match pid {
+            Some(pid) => copy.env("XIDLEHOOK_PID", pid.to_string()),
+            None => copy.env_remove("XIDLEHOOK_PID"),
+        };
+        let child = Self::spawn(
+            self.limit.as_deref(),
+            self.spawn_hook.as_ref(),
+            &mut copy,
+            self.detached,
+            self.expand_args.as_ref(),
+        )?;
+        let pid = child.as_ref().map(Child::id);
+        // It's reaped along with the others
+        self.children.extend(child);
+        Ok(pid)
+    }
+    /// Run a command whose child isn't needed, through the worker if
+    /// there is one
+    fn run(&mut self, which: fn(&mut Self) -> Option<&mut Command>) -> Result<()> {
+        let worker = self.worker.clone();
+        let limit = self.limit.clone();
+        let hook = self.spawn_hook.clone();
+        let detached = self.detached;
+        let expand = self.expand_args.clone();
+        if let Some(cmd) = which(self) {
+            match worker {
+                Some(worker) => match expand.and_then(|options| expand_command(cmd, &options)) {
+                    Some(expanded) => worker.run(&expanded)?,
+                    None => worker.run(cmd)?,
+                },
+                None => {
+                    let child = Self::spawn(
+                        limit.as_deref(),
+                        hook.as_ref(),
+                        cmd,
+                        detached,
+                        expand.as_ref(),
+                    )?;
+                    self.children.extend(child);
+                },
+            }
+        }
+        Ok(())
+    }
 }
 impl Timer for CmdTimer {
     fn time_left(&mut self, idle_time: Duration) -> Result<Option<Duration>> {
@@ -71,10 +376,28 @@ impl Timer for CmdTimer {
     fn abort_urgency(&self) -> Option<Duration> {
         self.abortion.as_ref().map(|_| Duration::from_secs(1))
     }
+    fn reap(&mut self) -> Result<()> {
+        if let Some(ref limit) = self.limit {
+            limit.reap()?;
+        }
+        // An error means someone else, such as the limit, reaped it
+        self.children
+            .retain_mut(|child| matches!(child.try_wait(), Ok(None)));
+        Ok(())
+    }
 
     fn activate(&mut self) -> Result<()> {
         if let Some(ref mut activation) = self.activation {
-            let child = activation.spawn()?;
+            let child = match Self::spawn(
+                self.limit.as_deref(),
+                self.spawn_hook.as_ref(),
+                activation,
+                self.detached,
+                self.expand_args.as_ref(),
+            )? {
+                Some(child) => child,
+                None => return Ok(()),
+            };
             let pid = child.id().to_string();
 
             if let Some(ref mut abortion) = self.abortion {
@@ -84,20 +407,36 @@ impl Timer for CmdTimer {
                 deactivation.env("XIDLEHOOK_PID", &pid);
             }
 
-            self.activation_child = Some(child);
+            // The one from last time, such as when recurring, is reaped later
+            self.children.extend(self.activation_child.replace(child));
         }
         Ok(())
     }
     fn abort(&mut self) -> Result<()> {
-        if let Some(ref mut abortion) = self.abortion {
-            abortion.spawn()?;
-        }
-        Ok(())
+        self.run(|me| me.abortion.as_mut())
     }
     fn deactivate(&mut self) -> Result<()> {
-        if let Some(ref mut deactivation) = self.deactivation {
-            deactivation.spawn()?;
+        self.run(|me| me.deactivation.as_mut())
+    }
+    fn reset(&mut self) -> Result<()> {
+        if !self.kill_on_resume {
+            return Ok(());
+        }
+        let child = match self.activation_child {
+            Some(ref mut child) => child,
+            None => return Ok(()),
+        };
+        if !still_running(child)? {
+            return Ok(());
         }
+        let sig = self.kill_signal.unwrap_or(Signal::SIGTERM);
+        logging::log_event(
+            log::Level::Info,
+            "kill_on_resume",
+            &[("pid", child.id().into()), ("signal", sig.as_ref().into())],
+        );
+        // It's reaped once it exits, and keeps the timer disabled until then
+        signal::kill(Pid::from_raw(i32::try_from(child.id())?), sig)?;
         Ok(())
     }
     fn disabled(&mut self) -> bool {
@@ -109,6 +448,622 @@ impl Timer for CmdTimer {
             self.disabled
         }
     }
+    fn dead_zone(&self) -> Duration {
+        self.dead_zone
+    }
+    fn recurring(&self) -> Option<Duration> {
+        self.recurring
+    }
+    fn once(&self) -> bool {
+        self.once
+    }
+    fn min_idle(&self) -> Option<Duration> {
+        self.min_idle
+    }
+    fn max_idle(&self) -> Option<Duration> {
+        self.max_idle
+    }
+    fn warning_lead(&self) -> Option<Duration> {
+        self.warning
+            .as_ref()
+            .map(|_| self.warning_lead)
+            .filter(|&lead| lead > Duration::default())
+    }
+    fn warn(&mut self) -> Result<()> {
+        if let Some(ref mut warning) = self.warning {
+            let child = Self::spawn(
+                self.limit.as_deref(),
+                self.spawn_hook.as_ref(),
+                warning,
+                self.detached,
+                self.expand_args.as_ref(),
+            )?;
+            // The one from last time is reaped later
+            self.children
+                .extend(mem::replace(&mut self.warning_child, child));
+        }
+        Ok(())
+    }
+    fn cancel_warning(&mut self) -> Result<()> {
+        if let Some(mut child) = self.warning_child.take() {
+            if still_running(&mut child)? {
+                child.kill()?;
+                child.wait()?;
+            }
+        }
+        Ok(())
+    }
+    fn set_idle_time(&mut self, idle_time: Duration) {
+        let ms = idle_time.as_millis().to_string();
+        for cmd in self.activation.iter_mut().chain(self.warning.iter_mut()) {
+            cmd.env("XIDLEHOOK_IDLE_MS", &ms);
+        }
+    }
+    fn supervise(&mut self) -> Result<Option<Duration>> {
+        if !self.relock_on_crash {
+            return Ok(None);
+        }
+        let status = match self.activation_child {
+            Some(ref mut child) => match child.try_wait() {
+                Ok(Some(status)) => status,
+                Ok(None) => return Ok(Some(RELOCK_CHECK_INTERVAL)),
+                Err(ref err) if reaped_elsewhere(err) => {
+                    // Whether it crashed is unknown, so it's taken as unlocked
+                    warn!("The activation command was reaped elsewhere, not watching it anymore");
+                    self.activation_child = None;
+                    return Ok(None);
+                },
+                Err(err) => return Err(err.into()),
+            },
+            None => return Ok(None),
+        };
+        self.activation_child = None;
+        if status.success() {
+            return Ok(None);
+        }
+
+        logging::log_event(
+            log::Level::Warn,
+            "relock",
+            &[("status", status.to_string().as_str().into())],
+        );
+        self.activate()?;
+        Ok(self
+            .activation_child
+            .as_ref()
+            .map(|_| RELOCK_CHECK_INTERVAL))
+    }
+    fn test_activation(&mut self) -> Result<()> {
+        if let Some(activation) = self.activation.as_ref().map(copy_command) {
+            self.test_pid = self.spawn_test(activation, None)?;
+        }
+        Ok(())
+    }
+    fn test_abortion(&mut self) -> Result<()> {
+        let pid = self.test_pid.take();
+        if let Some(abortion) = self.abortion.as_ref().map(copy_command) {
+            self.spawn_test(abortion, pid)?;
+        }
+        Ok(())
+    }
+}
+
+/// A command for a timer to run: either a program and its arguments,
+/// which is run directly, or a script for `/bin/sh -c`, which expands
+/// variables and globs. In a config file, the first is written as a
+/// list and the second as a string. The timers a config builds expand
+/// `$VAR` and `${VAR}` in the arguments of a program themselves, such
+/// as `$XIDLEHOOK_IDLE_MS`, see `CmdTimer::expand_args`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum TimerCommand {
+    /// A program, looked up in `$PATH`, followed by its arguments
+    Argv(Vec<String>),
+    /// A script run by `/bin/sh -c`
+    Shell(String),
+}
+impl TimerCommand {
+    /// Returns true if there's nothing to run: no program, or an
+    /// empty script
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Self::Argv(argv) => argv.is_empty(),
+            Self::Shell(script) => script.is_empty(),
+        }
+    }
+    /// Build the command, run as told by the options. An empty argument
+    /// list fails to spawn.
+    pub fn to_command(&self, options: &SpawnOptions) -> Command {
+        let mut cmd = match self {
+            Self::Argv(argv) => {
+                let mut cmd = Command::new(argv.first().map_or("", String::as_str));
+                cmd.args(argv.iter().skip(1));
+                cmd
+            },
+            Self::Shell(script) => {
+                let mut cmd = Command::new("/bin/sh");
+                cmd.arg("-c").arg(script);
+                cmd
+            },
+        };
+        options.apply(&mut cmd);
+        cmd
+    }
+}
+impl Default for TimerCommand {
+    /// An empty script
+    fn default() -> Self {
+        Self::Shell(String::new())
+    }
+}
+impl From<&str> for TimerCommand {
+    fn from(script: &str) -> Self {
+        Self::Shell(script.into())
+    }
+}
+impl From<String> for TimerCommand {
+    fn from(script: String) -> Self {
+        Self::Shell(script)
+    }
+}
+impl From<Vec<String>> for TimerCommand {
+    fn from(argv: Vec<String>) -> Self {
+        Self::Argv(argv)
+    }
+}
+
+/// Where timer commands run, and with which environment. By default,
+/// they inherit xidlehook's working directory and environment.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SpawnOptions {
+    /// The directory to run in, if not xidlehook's
+    pub current_dir: Option<PathBuf>,
+    /// Variables to set, in this order
+    pub env: Vec<(String, String)>,
+    /// Whether to start from an empty environment rather than
+    /// xidlehook's, so commands only see `env` and the variables timers
+    /// set, like `$XIDLEHOOK_IDLE_MS`. Without a `PATH` in `env`,
+    /// programs are looked up in the system's default path. Commands
+    /// queued by a `CommandLimit`, or passed to a `SpawnHook` which
+    /// copies them, inherit the environment regardless.
+    pub clear_env: bool,
+}
+impl SpawnOptions {
+    /// Set up a command according to these options
+    pub fn apply(&self, cmd: &mut Command) {
+        if self.clear_env {
+            cmd.env_clear();
+        }
+        cmd.envs(self.env.iter().map(|(key, value)| (key, value)));
+        if let Some(ref dir) = self.current_dir {
+            cmd.current_dir(dir);
+        }
+    }
+}
+
+/// A function that launches timer commands, to wrap them all
+/// uniformly, such as in `systemd-run` for resource limits or in a
+/// sandbox. It gets the command that would have been spawned, and
+/// returns the child it started instead. Clones share the same
+/// function.
+#[derive(Clone)]
+pub struct SpawnHook(Rc<RefCell<SpawnFn>>);
+
+type SpawnFn = dyn FnMut(&Command) -> Result<Child>;
+impl SpawnHook {
+    /// Use this function to launch commands
+    pub fn new<F>(f: F) -> Self
+    where
+        F: FnMut(&Command) -> Result<Child> + 'static,
+    {
+        Self(Rc::new(RefCell::new(f)))
+    }
+    /// Launch a command with this hook
+    pub fn spawn(&self, cmd: &Command) -> Result<Child> {
+        (self.0.borrow_mut())(cmd)
+    }
+    /// Launch a command with the hook, or with `Command::spawn` if
+    /// there is none
+    fn spawn_or_default(hook: Option<&Self>, cmd: &mut Command) -> Result<Child> {
+        match hook {
+            Some(hook) => hook.spawn(cmd),
+            None => Ok(cmd.spawn()?),
+        }
+    }
+}
+impl fmt::Debug for SpawnHook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SpawnHook")
+    }
+}
+
+/// A long-lived shell that runs commands fed to it on a pipe, which
+/// saves starting a new shell for every command. It's started on first
+/// use, and started again if it has died, such as from a command
+/// calling `exit`. Commands sent while it's exiting are lost.
+///
+/// Commands run one after another in the same shell context, so
+/// variables, functions and directory changes made by one are visible
+/// to the next, and a command that doesn't return holds up the rest.
+/// Commands that are `sh -c <script>` have their script run directly.
+/// Nothing is waited for: `run` returns once the command is written.
+/// Dropping the worker waits for the shell to finish the commands it
+/// was sent and exit, so it doesn't linger as a zombie.
+#[derive(Debug)]
+pub struct ShellWorker {
+    shell: PathBuf,
+    child: RefCell<Option<(Child, ChildStdin)>>,
+}
+impl Default for ShellWorker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl ShellWorker {
+    /// A worker using `/bin/sh`
+    pub fn new() -> Self {
+        Self::with_shell("/bin/sh")
+    }
+    /// A worker using this POSIX shell
+    pub fn with_shell<P: Into<PathBuf>>(shell: P) -> Self {
+        Self {
+            shell: shell.into(),
+            child: RefCell::new(None),
+        }
+    }
+    /// Returns the pid of the shell, if it's running
+    pub fn pid(&self) -> Option<u32> {
+        let mut child = self.child.borrow_mut();
+        if let Some((ref mut shell, _)) = *child {
+            if let Ok(None) = shell.try_wait() {
+                return Some(shell.id());
+            }
+        }
+        None
+    }
+    /// Send a command to the shell, starting it if needed
+    pub fn run(&self, cmd: &Command) -> Result<()> {
+        let line = shell_line(cmd);
+        let mut child = self.child.borrow_mut();
+
+        // Try the existing shell first, and a new one if that has died
+        if let Some((ref mut shell, ref mut stdin)) = *child {
+            if let Ok(None) = shell.try_wait() {
+                match stdin.write_all(&line) {
+                    Ok(()) => return Ok(()),
+                    Err(err) if err.kind() == io::ErrorKind::BrokenPipe => (),
+                    Err(err) => return Err(err.into()),
+                }
+            }
+            warn!("Shell worker {} died, starting a new one", shell.id());
+            let _ = shell.wait();
+        }
+
+        let mut shell = Command::new(&self.shell).stdin(Stdio::piped()).spawn()?;
+        let mut stdin = shell.stdin.take().expect("stdin is piped");
+        stdin.write_all(&line)?;
+        *child = Some((shell, stdin));
+        Ok(())
+    }
+}
+impl Drop for ShellWorker {
+    fn drop(&mut self) {
+        // Closing stdin lets the shell exit once it's done with the
+        // commands already sent, which it's reaped after
+        if let Some((mut shell, stdin)) = self.child.get_mut().take() {
+            drop(stdin);
+            if let Err(err) = shell.wait() {
+                warn!("Couldn't wait for shell worker {}: {}", shell.id(), err);
+            }
+        }
+    }
+}
+
+/// Turn a command into a line of shell, run with `eval` so a syntax
+/// error can't swallow the commands after it
+fn shell_line(cmd: &Command) -> Vec<u8> {
+    let mut line = Vec::new();
+    for (key, value) in cmd.get_envs() {
+        match value {
+            Some(value) => {
+                line.extend_from_slice(b"export ");
+                line.extend_from_slice(key.as_bytes());
+                line.push(b'=');
+                shell_quote(&mut line, value);
+            },
+            None => {
+                line.extend_from_slice(b"unset ");
+                line.extend_from_slice(key.as_bytes());
+            },
+        }
+        line.extend_from_slice(b"; ");
+    }
+    if let Some(dir) = cmd.get_current_dir() {
+        line.extend_from_slice(b"cd ");
+        shell_quote(&mut line, dir.as_os_str());
+        line.extend_from_slice(b" && ");
+    }
+
+    let script = match shell_script(cmd) {
+        Some(script) => script.as_bytes().to_vec(),
+        None => {
+            let mut script = Vec::new();
+            shell_quote(&mut script, cmd.get_program());
+            for arg in cmd.get_args() {
+                script.push(b' ');
+                shell_quote(&mut script, arg);
+            }
+            script
+        },
+    };
+    line.extend_from_slice(b"eval ");
+    shell_quote(&mut line, OsStr::from_bytes(&script));
+    line.push(b'\n');
+    line
+}
+
+/// Returns the script of a command that's `sh -c <script>`
+fn shell_script(cmd: &Command) -> Option<&OsStr> {
+    let program = cmd.get_program().as_bytes();
+    let args: Vec<&OsStr> = cmd.get_args().collect();
+    match *args {
+        [flag, script] if flag == "-c" && (program == b"sh" || program.ends_with(b"/sh")) => {
+            Some(script)
+        },
+        _ => None,
+    }
+}
+
+/// Copy a command with the variables in its arguments expanded, unless
+/// it's a `sh -c` script. Variables are looked up in what's set on
+/// the command, and then in xidlehook's environment unless the options
+/// clear it.
+fn expand_command(cmd: &Command, options: &SpawnOptions) -> Option<Command> {
+    if shell_script(cmd).is_some() {
+        return None;
+    }
+    let lookup = |name: &OsStr| match cmd.get_envs().find(|&(key, _)| key == name) {
+        Some((_, value)) => value.map(OsStr::to_os_string),
+        None if options.clear_env => None,
+        None => env::var_os(name),
+    };
+    let mut copy = Command::new(cmd.get_program());
+    copy.args(cmd.get_args().map(|arg| expand_vars(arg, lookup)));
+    if options.clear_env {
+        copy.env_clear();
+    }
+    for (key, value) in cmd.get_envs() {
+        match value {
+            Some(value) => copy.env(key, value),
+            None => copy.env_remove(key),
+        };
+    }
+    if let Some(dir) = cmd.get_current_dir() {
+        copy.current_dir(dir);
+    }
+    Some(copy)
+}
+
+/// Expand `$VAR` and `${VAR}` in an argument, without a shell: nothing
+/// else is special, such as quotes or globs. A name is made of ASCII
+/// letters, digits and underscores, not starting with a digit. A
+/// variable `lookup` doesn't know expands to nothing, like in the
+/// shell. `$$` is a literal `$`, and a `$` that isn't followed by a
+/// name, or a `${` without a valid name and a `}`, is kept as it is.
+pub fn expand_vars<F>(arg: &OsStr, mut lookup: F) -> OsString
+where
+    F: FnMut(&OsStr) -> Option<OsString>,
+{
+    let is_name = |name: &[u8]| {
+        name.first()
+            .is_some_and(|&first| first == b'_' || first.is_ascii_alphabetic())
+            && name
+                .iter()
+                .all(|&byte| byte == b'_' || byte.is_ascii_alphanumeric())
+    };
+    let arg = arg.as_bytes();
+    let mut out = Vec::with_capacity(arg.len());
+    let mut rest = arg;
+    while let Some(start) = rest.iter().position(|&byte| byte == b'$') {
+        out.extend_from_slice(&rest[..start]);
+        let after = &rest[start + 1..];
+        let name = match after.first() {
+            Some(b'$') => {
+                out.push(b'$');
+                rest = &after[1..];
+                continue;
+            },
+            Some(b'{') => after
+                .iter()
+                .position(|&byte| byte == b'}')
+                .map(|end| (&after[1..end], end + 1)),
+            _ => {
+                let len = after
+                    .iter()
+                    .position(|&byte| byte != b'_' && !byte.is_ascii_alphanumeric())
+                    .unwrap_or(after.len());
+                Some((&after[..len], len))
+            },
+        };
+        match name.filter(|&(name, _)| is_name(name)) {
+            Some((name, len)) => {
+                if let Some(value) = lookup(OsStr::from_bytes(name)) {
+                    out.extend_from_slice(value.as_bytes());
+                }
+                rest = &after[len..];
+            },
+            None => {
+                out.push(b'$');
+                rest = after;
+            },
+        }
+    }
+    out.extend_from_slice(rest);
+    OsString::from_vec(out)
+}
+
+/// Append a single-quoted version of this string
+fn shell_quote(out: &mut Vec<u8>, value: &OsStr) {
+    out.push(b'\'');
+    for &byte in value.as_bytes() {
+        if byte == b'\'' {
+            out.extend_from_slice(b"'\\''");
+        } else {
+            out.push(byte);
+        }
+    }
+    out.push(b'\'');
+}
+
+/// What to do with a command when the limit of concurrently running
+/// commands has been reached
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueueFull {
+    /// Don't run it at all
+    Drop,
+    /// Run it once another command has exited
+    Queue,
+}
+
+/// A limit on how many spawned commands may run at the same time,
+/// to stop a misconfiguration from piling up processes. Exited
+/// children are reaped whenever a timer using the limit is polled,
+/// which is also when queued commands are started. Note that a
+/// queued activation can't be waited for by its timer, and its pid
+/// isn't passed to the canceller.
+pub struct CommandLimit {
+    max: usize,
+    policy: QueueFull,
+    running: RefCell<Vec<Pid>>,
+    queue: RefCell<VecDeque<(Command, Option<SpawnHook>)>>,
+}
+impl CommandLimit {
+    /// Allow at most `max` commands to run at once
+    pub fn new(max: usize, policy: QueueFull) -> Self {
+        Self {
+            max,
+            policy,
+            running: RefCell::new(Vec::new()),
+            queue: RefCell::new(VecDeque::new()),
+        }
+    }
+    /// Returns the amount of commands that are still running
+    pub fn running(&self) -> Result<usize> {
+        self.reap()?;
+        Ok(self.running.borrow().len())
+    }
+    /// Returns the amount of commands waiting for a free slot
+    pub fn queued(&self) -> usize {
+        self.queue.borrow().len()
+    }
+    /// Forget about children that have exited, and start queued
+    /// commands in the slots that frees up
+    pub fn reap(&self) -> Result<()> {
+        self.running.borrow_mut().retain(|&pid| {
+            match wait::waitpid(pid, Some(WaitPidFlag::WNOHANG)) {
+                Ok(WaitStatus::StillAlive) => true,
+                // Either it exited, or someone else already reaped it
+                _ => false,
+            }
+        });
+        while self.running.borrow().len() < self.max {
+            let (mut cmd, hook) = match self.queue.borrow_mut().pop_front() {
+                Some(queued) => queued,
+                None => break,
+            };
+            self.start(&mut cmd, hook.as_ref())?;
+        }
+        Ok(())
+    }
+    /// Spawn the command if there's a free slot. Otherwise, it's
+    /// dropped or queued depending on the policy, and `None` is
+    /// returned.
+    pub fn spawn(&self, cmd: &mut Command) -> Result<Option<Child>> {
+        self.spawn_with(cmd, None)
+    }
+    /// Like `spawn`, but launch the command with a hook. If it's
+    /// queued, the hook is used once it's started.
+    pub fn spawn_with(&self, cmd: &mut Command, hook: Option<&SpawnHook>) -> Result<Option<Child>> {
+        self.spawn_or_queue(cmd, hook, false)
+    }
+    /// Like `spawn_with`, keeping a queued command in a process group
+    /// of its own if it's `detached`
+    fn spawn_or_queue(
+        &self,
+        cmd: &mut Command,
+        hook: Option<&SpawnHook>,
+        detached: bool,
+    ) -> Result<Option<Child>> {
+        self.reap()?;
+        if self.running.borrow().len() < self.max {
+            return self.start(cmd, hook).map(Some);
+        }
+        match self.policy {
+            QueueFull::Drop => {
+                warn!(
+                    "Not running {:?}: {} commands are already running",
+                    cmd, self.max
+                );
+            },
+            QueueFull::Queue => {
+                warn!(
+                    "Queueing {:?}: {} commands are already running",
+                    cmd, self.max
+                );
+                let mut copy = copy_command(cmd);
+                if detached {
+                    copy.process_group(0);
+                }
+                self.queue.borrow_mut().push_back((copy, hook.cloned()));
+            },
+        }
+        Ok(None)
+    }
+    fn start(&self, cmd: &mut Command, hook: Option<&SpawnHook>) -> Result<Child> {
+        let child = SpawnHook::spawn_or_default(hook, cmd)?;
+        let pid = Pid::from_raw(i32::try_from(child.id())?);
+        self.running.borrow_mut().push(pid);
+        Ok(child)
+    }
+}
+impl fmt::Debug for CommandLimit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CommandLimit({})", self.max)
+    }
+}
+
+/// Returns true if the child is still running. One that someone else
+/// already reaped, such as the `CommandLimit` it was started through,
+/// has exited too, even though its exit status is lost.
+pub(crate) fn still_running(child: &mut Child) -> Result<bool> {
+    match child.try_wait() {
+        Ok(status) => Ok(status.is_none()),
+        Err(ref err) if reaped_elsewhere(err) => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Whether waiting on a child failed because someone else already
+/// reaped it
+fn reaped_elsewhere(err: &io::Error) -> bool {
+    err.raw_os_error() == Some(libc::ECHILD)
+}
+
+/// Make an owned copy of a command, so that it can be queued
+fn copy_command(cmd: &Command) -> Command {
+    let mut copy = Command::new(cmd.get_program());
+    copy.args(cmd.get_args());
+    for (key, value) in cmd.get_envs() {
+        match value {
+            Some(value) => copy.env(key, value),
+            None => copy.env_remove(key),
+        };
+    }
+    if let Some(dir) = cmd.get_current_dir() {
+        copy.current_dir(dir);
+    }
+    copy
 }
 
 /// A timer that lets you easily execute a rust callback on
@@ -174,3 +1129,432 @@ where
         self.disabled
     }
 }
+
+/// Implements the listed `Timer` methods by forwarding them to `self.inner`, so that timer
+/// wrappers only write out the ones they change
+macro_rules! forward_to_inner {
+    ($($method:ident),* $(,)?) => {
+        $(forward_to_inner!(@ $method);)*
+    };
+    (@ time_left) => {
+        fn time_left(&mut self, idle_time: Duration) -> Result<Option<Duration>> {
+            self.inner.time_left(idle_time)
+        }
+    };
+    (@ abort_urgency) => {
+        fn abort_urgency(&self) -> Option<Duration> {
+            self.inner.abort_urgency()
+        }
+    };
+    (@ poll_interval) => {
+        fn poll_interval(&self) -> Option<Duration> {
+            self.inner.poll_interval()
+        }
+    };
+    (@ reap) => {
+        fn reap(&mut self) -> Result<()> {
+            self.inner.reap()
+        }
+    };
+    (@ activate) => {
+        fn activate(&mut self) -> Result<()> {
+            self.inner.activate()
+        }
+    };
+    (@ abort) => {
+        fn abort(&mut self) -> Result<()> {
+            self.inner.abort()
+        }
+    };
+    (@ deactivate) => {
+        fn deactivate(&mut self) -> Result<()> {
+            self.inner.deactivate()
+        }
+    };
+    (@ reset) => {
+        fn reset(&mut self) -> Result<()> {
+            self.inner.reset()
+        }
+    };
+    (@ disabled) => {
+        fn disabled(&mut self) -> bool {
+            self.inner.disabled()
+        }
+    };
+    (@ dead_zone) => {
+        fn dead_zone(&self) -> Duration {
+            self.inner.dead_zone()
+        }
+    };
+    (@ recurring) => {
+        fn recurring(&self) -> Option<Duration> {
+            self.inner.recurring()
+        }
+    };
+    (@ once) => {
+        fn once(&self) -> bool {
+            self.inner.once()
+        }
+    };
+    (@ min_idle) => {
+        fn min_idle(&self) -> Option<Duration> {
+            self.inner.min_idle()
+        }
+    };
+    (@ max_idle) => {
+        fn max_idle(&self) -> Option<Duration> {
+            self.inner.max_idle()
+        }
+    };
+    (@ supervise) => {
+        fn supervise(&mut self) -> Result<Option<Duration>> {
+            self.inner.supervise()
+        }
+    };
+    (@ warning_lead) => {
+        fn warning_lead(&self) -> Option<Duration> {
+            self.inner.warning_lead()
+        }
+    };
+    (@ warn) => {
+        fn warn(&mut self) -> Result<()> {
+            self.inner.warn()
+        }
+    };
+    (@ cancel_warning) => {
+        fn cancel_warning(&mut self) -> Result<()> {
+            self.inner.cancel_warning()
+        }
+    };
+    (@ set_idle_time) => {
+        fn set_idle_time(&mut self, idle_time: Duration) {
+            self.inner.set_idle_time(idle_time)
+        }
+    };
+    (@ test_activation) => {
+        fn test_activation(&mut self) -> Result<()> {
+            self.inner.test_activation()
+        }
+    };
+    (@ test_abortion) => {
+        fn test_abortion(&mut self) -> Result<()> {
+            self.inner.test_abortion()
+        }
+    };
+}
+
+/// A timer wrapper which delays the activation of the inner timer by a pseudo-random offset
+/// between zero and `jitter`, so that many machines going idle at the same time don't all fire
+/// at once. A new offset is picked for every idle cycle (each time the chain is reset).
+///
+/// The offsets are deterministic: the n:th offset is derived from the seed and n using the
+/// splitmix64 generator. `new` seeds from the FNV-1a hash of `/etc/machine-id`, which means each
+/// machine gets its own reproducible sequence of offsets.
+#[derive(Debug)]
+pub struct Jittered<T: Timer> {
+    inner: T,
+    jitter: Duration,
+    seed: u64,
+    cycle: u64,
+    offset: Duration,
+}
+impl<T: Timer> Jittered<T> {
+    /// Wrap a timer, seeding from the machine id. If the machine id can't be read, the seed is
+    /// zero.
+    pub fn new(inner: T, jitter: Duration) -> Self {
+        let seed = fs::read("/etc/machine-id")
+            .map(|id| fnv1a(id.trim_ascii()))
+            .unwrap_or(0);
+        Self::with_seed(inner, jitter, seed)
+    }
+    /// Wrap a timer, using the specified seed
+    pub fn with_seed(inner: T, jitter: Duration, seed: u64) -> Self {
+        let mut me = Self {
+            inner,
+            jitter,
+            seed,
+            cycle: 0,
+            offset: Duration::default(),
+        };
+        me.roll();
+        me
+    }
+    /// Returns the offset added to the inner timer's threshold this idle cycle
+    pub fn offset(&self) -> Duration {
+        self.offset
+    }
+    /// Returns a reference to the inner timer
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+    /// Returns a mutable reference to the inner timer
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    fn roll(&mut self) {
+        let range = u64::try_from(self.jitter.as_millis())
+            .unwrap_or(u64::MAX)
+            .saturating_add(1);
+        let random = splitmix64(self.seed.wrapping_add(self.cycle));
+        self.offset = Duration::from_millis(random % range);
+    }
+}
+impl<T: Timer> Timer for Jittered<T> {
+    fn time_left(&mut self, idle_time: Duration) -> Result<Option<Duration>> {
+        match idle_time.checked_sub(self.offset) {
+            Some(shifted) => self.inner.time_left(shifted),
+            None => Ok(self
+                .inner
+                .time_left(Duration::default())?
+                .map(|left| left + (self.offset - idle_time))),
+        }
+    }
+    fn reset(&mut self) -> Result<()> {
+        self.cycle = self.cycle.wrapping_add(1);
+        self.roll();
+        self.inner.reset()
+    }
+    forward_to_inner! {
+        abort_urgency, poll_interval, reap, activate, abort, deactivate, disabled, dead_zone,
+        recurring, once, min_idle, max_idle, supervise, warning_lead, warn, cancel_warning,
+        set_idle_time, test_activation, test_abortion,
+    }
+}
+
+/// A timer wrapper whose threshold is reached by idle time accumulated across short bursts of
+/// activity. A burst of at most `max_burst` only pauses the accumulator, while longer activity
+/// resets it. It's reset after every idle cycle in which the inner timer was activated. This is
+/// meant to be the first timer, since later ones see the idle time since the previous timer.
+///
+/// Activity is only noticed when polling, so this asks to be polled at least every half
+/// `max_burst` (see `Timer::poll_interval`). A burst is measured from the last poll before it,
+/// which means it may be taken to be up to that much longer than it was, and the idle time right
+/// before it isn't counted.
+#[derive(Debug)]
+pub struct AccumulatedIdleTimer<T: Timer> {
+    inner: T,
+    max_burst: Duration,
+    /// The idle time of the stretches before the current one
+    accumulated: Duration,
+    /// The idle time at the last poll, and when that was
+    last: Option<(Duration, Instant)>,
+    /// When the current burst of activity started, if any
+    burst_start: Option<Instant>,
+    /// Whether the inner timer was activated this idle cycle
+    activated: bool,
+}
+impl<T: Timer> AccumulatedIdleTimer<T> {
+    /// Wrap a timer, which gets the accumulated idle time instead of the actual one
+    pub fn new(inner: T, max_burst: Duration) -> Self {
+        Self {
+            inner,
+            max_burst,
+            accumulated: Duration::default(),
+            last: None,
+            burst_start: None,
+            activated: false,
+        }
+    }
+    /// Returns the idle time accumulated as of the last poll
+    pub fn accumulated(&self) -> Duration {
+        self.accumulated + self.last.map_or_else(Duration::default, |(idle, _)| idle)
+    }
+    /// Returns a reference to the inner timer
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+    /// Returns a mutable reference to the inner timer
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+    /// Like `time_left`, but as if it was polled at `now`
+    pub fn time_left_at(&mut self, idle_time: Duration, now: Instant) -> Result<Option<Duration>> {
+        // Xidlehook asks about zero idle time to see when the timer would go off if the user was
+        // active right now, which isn't a measurement
+        if idle_time == Duration::default() {
+            return self.inner.time_left(self.accumulated);
+        }
+
+        if let Some((last_idle, last_poll)) = self.last {
+            if idle_time < last_idle {
+                // The user was active since the last poll
+                let burst_start = match self.burst_start {
+                    Some(start) => start,
+                    None => {
+                        self.accumulated += last_idle;
+                        last_poll
+                    },
+                };
+                self.burst_start = Some(burst_start);
+
+                let idle_start = now.checked_sub(idle_time).unwrap_or(now);
+                if idle_start.saturating_duration_since(burst_start) > self.max_burst {
+                    self.accumulated = Duration::default();
+                }
+            } else {
+                self.burst_start = None;
+            }
+        }
+        self.last = Some((idle_time, now));
+
+        self.inner.time_left(self.accumulated + idle_time)
+    }
+}
+impl<T: Timer> Timer for AccumulatedIdleTimer<T> {
+    fn time_left(&mut self, idle_time: Duration) -> Result<Option<Duration>> {
+        self.time_left_at(idle_time, Instant::now())
+    }
+    fn poll_interval(&self) -> Option<Duration> {
+        // Activity is only noticed when polling
+        let own = Some(self.max_burst / 2).filter(|&poll| poll != Duration::default());
+        crate::modules::shortest([own, self.inner.poll_interval()])
+    }
+    fn activate(&mut self) -> Result<()> {
+        self.activated = true;
+        self.inner.activate()
+    }
+    fn reset(&mut self) -> Result<()> {
+        // Brief activity resets the chain too, which only counts once the inner timer went off
+        if self.activated {
+            self.activated = false;
+            self.accumulated = Duration::default();
+            self.last = None;
+            self.burst_start = None;
+        }
+        self.inner.reset()
+    }
+    forward_to_inner! {
+        abort_urgency, reap, abort, deactivate, disabled, dead_zone, recurring, once, min_idle,
+        max_idle, supervise, warning_lead, warn, cancel_warning, set_idle_time, test_activation,
+        test_abortion,
+    }
+}
+
+/// A minimum time between two timers going off, measured from when the first one actually went
+/// off rather than in idle time. For example, a suspend timer can wait until the locker had time
+/// to grab input, even if the user has been idle for longer than the suspend timer's threshold
+/// already. Wrap the first timer with `lock`, and the one which waits with `after_lock`. If the
+/// first one didn't go off in this idle cycle, such as because it's disabled, the other one
+/// doesn't wait.
+#[derive(Clone, Debug)]
+pub struct LockGrace {
+    grace: Duration,
+    locked_at: Rc<Cell<Option<Instant>>>,
+}
+impl LockGrace {
+    /// Keep this much time between the timers
+    pub fn new(grace: Duration) -> Self {
+        Self {
+            grace,
+            locked_at: Rc::new(Cell::new(None)),
+        }
+    }
+    /// Returns the time kept between the timers
+    pub fn grace(&self) -> Duration {
+        self.grace
+    }
+    /// Returns how much is left of the grace time, if the first timer
+    /// went off
+    pub fn remaining(&self) -> Option<Duration> {
+        self.locked_at
+            .get()
+            .map(|at| self.grace.saturating_sub(at.elapsed()))
+    }
+    /// Wrap the timer the grace time starts at, such as a lock timer
+    pub fn lock<T: Timer>(&self, inner: T) -> GraceStart<T> {
+        GraceStart {
+            inner,
+            grace: self.clone(),
+        }
+    }
+    /// Wrap the timer that waits for the grace time, such as a
+    /// suspend timer
+    pub fn after_lock<T: Timer>(&self, inner: T) -> GraceWait<T> {
+        GraceWait {
+            inner,
+            grace: self.clone(),
+        }
+    }
+}
+
+/// A timer that starts a `LockGrace` when it goes off
+#[derive(Debug)]
+pub struct GraceStart<T: Timer> {
+    inner: T,
+    grace: LockGrace,
+}
+impl<T: Timer> GraceStart<T> {
+    /// Returns a reference to the inner timer
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+    /// Returns a mutable reference to the inner timer
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+impl<T: Timer> Timer for GraceStart<T> {
+    fn activate(&mut self) -> Result<()> {
+        let result = self.inner.activate();
+        self.grace.locked_at.set(Some(Instant::now()));
+        result
+    }
+    fn reset(&mut self) -> Result<()> {
+        self.grace.locked_at.set(None);
+        self.inner.reset()
+    }
+    forward_to_inner! {
+        time_left, abort_urgency, poll_interval, reap, abort, deactivate, disabled, dead_zone,
+        recurring, once, min_idle, max_idle, supervise, warning_lead, warn, cancel_warning,
+        set_idle_time, test_activation, test_abortion,
+    }
+}
+
+/// A timer that doesn't go off until a `LockGrace` has passed
+#[derive(Debug)]
+pub struct GraceWait<T: Timer> {
+    inner: T,
+    grace: LockGrace,
+}
+impl<T: Timer> GraceWait<T> {
+    /// Returns a reference to the inner timer
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+    /// Returns a mutable reference to the inner timer
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+impl<T: Timer> Timer for GraceWait<T> {
+    fn time_left(&mut self, idle_time: Duration) -> Result<Option<Duration>> {
+        let left = self.inner.time_left(idle_time)?;
+        let grace = self
+            .grace
+            .remaining()
+            .filter(|&remaining| remaining != Duration::default());
+        Ok(match (left, grace) {
+            (Some(left), Some(grace)) => Some(cmp::max(left, grace)),
+            (left, grace) => left.or(grace),
+        })
+    }
+    forward_to_inner! {
+        abort_urgency, poll_interval, reap, activate, abort, deactivate, reset, disabled, dead_zone,
+        recurring, once, min_idle, max_idle, supervise, warning_lead, warn, cancel_warning,
+        set_idle_time, test_activation, test_abortion,
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn splitmix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,207 @@
+//! A watchdog that lets external programs know xidlehook is alive,
+//! and that notices when xidlehook itself hangs, or when the idle time
+//! seems stuck
+
+use crate::{timers::still_running, Result};
+use std::{
+    fs,
+    path::PathBuf,
+    process::{Child, Command},
+    time::{Duration, Instant},
+};
+
+use log::{error, warn};
+
+/// Keeps track of every poll. If configured, a heartbeat file is
+/// rewritten with an increasing counter after each poll, so an
+/// external check can look at its modification time. If a single
+/// poll takes longer than the configured maximum, an error is logged
+/// and the recovery command (if any) is run.
+///
+/// It can also watch the idle time for a broken idle source, such as
+/// one kept at zero by a misbehaving device, which otherwise just
+/// keeps any timer from going off. See `with_stuck_idle`.
+#[derive(Debug, Default)]
+pub struct Watchdog {
+    heartbeat: Option<(PathBuf, Duration)>,
+    max_iteration: Option<Duration>,
+    recovery: Option<Command>,
+    recovery_child: Option<Child>,
+    beats: u64,
+    stuck_idle: Option<StuckIdle>,
+}
+
+/// The state of the check for a stuck idle time
+#[derive(Debug)]
+struct StuckIdle {
+    multiple: u32,
+    command: Option<Command>,
+    child: Option<Child>,
+    /// The idle time at the last poll
+    last_idle: Duration,
+    /// When the user was last seen active, or the idle time last
+    /// reached the threshold
+    since: Option<Instant>,
+    /// Whether it was reported since then
+    tripped: bool,
+}
+impl Watchdog {
+    /// A watchdog which doesn't do anything yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write a heartbeat to this file after every poll. Polls happen
+    /// at least once every `interval`, so an external check can
+    /// consider xidlehook stuck when the file is older than that.
+    pub fn with_heartbeat(mut self, path: impl Into<PathBuf>, interval: Duration) -> Self {
+        self.heartbeat = Some((path.into(), interval));
+        self
+    }
+
+    /// Consider xidlehook hung if a single poll takes longer than
+    /// `max`, and in that case run `recovery`, if any.
+    pub fn with_max_iteration(mut self, max: Duration, recovery: Option<Command>) -> Self {
+        self.max_iteration = Some(max);
+        self.recovery = recovery;
+        self
+    }
+
+    /// Suspect the idle source is broken if the idle time stays below
+    /// the shortest threshold of the timers for `multiple` times that
+    /// threshold, without ever falling like it does when the user is
+    /// active. In that case, a warning is logged and `command` is run,
+    /// if any, once until the idle time moves again.
+    pub fn with_stuck_idle(mut self, multiple: u32, command: Option<Command>) -> Self {
+        self.stuck_idle = Some(StuckIdle {
+            multiple,
+            command,
+            child: None,
+            last_idle: Duration::default(),
+            since: None,
+            tripped: false,
+        });
+        self
+    }
+
+    /// Returns true if the idle time is watched, see `with_stuck_idle`
+    pub fn watches_idle(&self) -> bool {
+        self.stuck_idle.is_some()
+    }
+
+    /// The amount of polls seen so far
+    pub fn beats(&self) -> u64 {
+        self.beats
+    }
+
+    /// The maximum time xidlehook may sleep between polls, so that the
+    /// heartbeat stays fresh
+    pub fn interval(&self) -> Option<Duration> {
+        self.heartbeat.as_ref().map(|&(_, interval)| interval)
+    }
+
+    /// The recovery process that was last started, if any
+    pub fn recovery_child(&mut self) -> Option<&mut Child> {
+        self.recovery_child.as_mut()
+    }
+
+    /// The command that was last started because the idle time was
+    /// stuck, if any
+    pub fn stuck_idle_child(&mut self) -> Option<&mut Child> {
+        self.stuck_idle.as_mut()?.child.as_mut()
+    }
+
+    /// Record a poll which took the specified amount of time. Returns
+    /// true if it overran the maximum. The overrun is still detected
+    /// if writing the heartbeat fails, but the error is returned.
+    pub fn check(&mut self, took: Duration) -> Result<bool> {
+        self.beats += 1;
+
+        let written = match self.heartbeat {
+            Some((ref path, _)) => fs::write(path, format!("{}\n", self.beats)),
+            None => Ok(()),
+        };
+        let overran = self.check_iteration(took)?;
+        written?;
+        Ok(overran)
+    }
+
+    /// The part of `check` looking at how long the poll took
+    fn check_iteration(&mut self, took: Duration) -> Result<bool> {
+        let max = match self.max_iteration {
+            Some(max) if took > max => max,
+            _ => {
+                reap(&mut self.recovery_child)?;
+                return Ok(false);
+            },
+        };
+        error!(
+            "A poll took {:?}, which is longer than the maximum of {:?} - is xidlehook hanging?",
+            took, max,
+        );
+        if let Some(ref mut recovery) = self.recovery {
+            spawn(recovery, &mut self.recovery_child)?;
+        }
+        Ok(true)
+    }
+
+    /// Record the idle time of a poll, and the shortest threshold of
+    /// the timers it should reach. Returns true if it's considered stuck,
+    /// only once until it moves again.
+    pub fn check_idle(&mut self, idle: Duration, threshold: Duration) -> Result<bool> {
+        let stuck = match self.stuck_idle {
+            Some(ref mut stuck) => stuck,
+            None => return Ok(false),
+        };
+        reap(&mut stuck.child)?;
+        let now = Instant::now();
+        let fell = idle < stuck.last_idle;
+        stuck.last_idle = idle;
+        let since = match stuck.since {
+            Some(since) if !fell && idle < threshold => since,
+            _ => {
+                stuck.since = Some(now);
+                stuck.tripped = false;
+                return Ok(false);
+            },
+        };
+        let limit = threshold * stuck.multiple;
+        if stuck.tripped || now.duration_since(since) < limit {
+            return Ok(false);
+        }
+        stuck.tripped = true;
+        warn!(
+            "The idle time stayed below {:?} for over {:?} without the user being active - is the \
+             idle source broken?",
+            threshold, limit,
+        );
+        if let Some(ref mut command) = stuck.command {
+            spawn(command, &mut stuck.child)?;
+        }
+        Ok(true)
+    }
+}
+
+/// Reap a command started earlier, if it exited. Returns true if it's
+/// still running.
+fn reap(child: &mut Option<Child>) -> Result<bool> {
+    match *child {
+        // The exit status is kept, so waiting on it again still works
+        Some(ref mut child) => still_running(child),
+        None => Ok(false),
+    }
+}
+
+/// Start a command in place of the one started earlier, unless that
+/// one is still running
+fn spawn(command: &mut Command, child: &mut Option<Child>) -> Result<()> {
+    if reap(child)? {
+        warn!(
+            "Not running {:?} again, it's still running from last time",
+            command
+        );
+        return Ok(());
+    }
+    *child = Some(command.spawn()?);
+    Ok(())
+}
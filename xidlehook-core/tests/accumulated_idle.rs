@@ -0,0 +1,110 @@
+use std::time::{Duration, Instant};
+use xidlehook_core::{
+    timers::{AccumulatedIdleTimer, CallbackTimer},
+    Action::*,
+    Timer, Xidlehook,
+};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+#[test]
+fn accumulated_idle() {
+    let start = Instant::now();
+    let at = |units: u32| start + TEST_UNIT * units;
+
+    let mut timer =
+        AccumulatedIdleTimer::new(CallbackTimer::new(TEST_UNIT * 30, || ()), TEST_UNIT * 10);
+
+    // It asks to be polled often enough to notice bursts, but tells the
+    // actual time left
+    assert_eq!(timer.poll_interval(), Some(TEST_UNIT * 5));
+    assert_eq!(
+        timer.time_left_at(TEST_UNIT, at(0)).unwrap(),
+        Some(TEST_UNIT * 29)
+    );
+    assert_eq!(
+        timer.time_left_at(TEST_UNIT * 11, at(10)).unwrap(),
+        Some(TEST_UNIT * 19)
+    );
+    assert_eq!(timer.accumulated(), TEST_UNIT * 11);
+
+    // A short burst pauses the accumulator, but doesn't reset it
+    timer.time_left_at(TEST_UNIT, at(12)).unwrap();
+    assert_eq!(timer.accumulated(), TEST_UNIT * 12);
+    timer.time_left_at(TEST_UNIT * 9, at(20)).unwrap();
+    assert_eq!(timer.accumulated(), TEST_UNIT * 20);
+
+    // Asking about zero idle time doesn't count as activity
+    assert_eq!(
+        timer.time_left_at(Duration::default(), at(20)).unwrap(),
+        Some(TEST_UNIT * 19)
+    );
+    assert_eq!(timer.accumulated(), TEST_UNIT * 20);
+
+    // Another one, and the threshold is reached
+    timer.time_left_at(TEST_UNIT * 2, at(25)).unwrap();
+    assert_eq!(timer.accumulated(), TEST_UNIT * 22);
+    assert_eq!(
+        timer.time_left_at(TEST_UNIT * 7, at(30)).unwrap(),
+        Some(TEST_UNIT * 3)
+    );
+    assert_eq!(timer.time_left_at(TEST_UNIT * 10, at(33)).unwrap(), None);
+
+    // It starts over after going off
+    timer.activate().unwrap();
+    timer.reset().unwrap();
+    assert_eq!(timer.accumulated(), Duration::default());
+    assert_eq!(
+        timer.time_left_at(TEST_UNIT * 2, at(40)).unwrap(),
+        Some(TEST_UNIT * 28)
+    );
+    assert_eq!(timer.accumulated(), TEST_UNIT * 2);
+}
+
+#[test]
+fn sustained_activity() {
+    let start = Instant::now();
+    let at = |units: u32| start + TEST_UNIT * units;
+
+    let mut timer =
+        AccumulatedIdleTimer::new(CallbackTimer::new(TEST_UNIT * 30, || ()), TEST_UNIT * 10);
+    timer.time_left_at(TEST_UNIT, at(0)).unwrap();
+    timer.time_left_at(TEST_UNIT * 11, at(10)).unwrap();
+
+    // Activity that keeps going is a single burst, even if every poll sees a little idle time
+    timer.time_left_at(TEST_UNIT, at(15)).unwrap();
+    timer.time_left_at(TEST_UNIT / 2, at(20)).unwrap();
+    assert_eq!(timer.accumulated(), TEST_UNIT * 11 + TEST_UNIT / 2);
+
+    // Once it's longer than the maximum burst, the accumulator is reset
+    timer.time_left_at(TEST_UNIT / 4, at(25)).unwrap();
+    assert_eq!(timer.accumulated(), TEST_UNIT / 4);
+    assert_eq!(
+        timer.time_left_at(TEST_UNIT * 5, at(30)).unwrap(),
+        Some(TEST_UNIT * 25)
+    );
+    assert_eq!(timer.accumulated(), TEST_UNIT * 5);
+}
+
+#[test]
+fn polled_often() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let mut xidlehook = Xidlehook::new(vec![
+        AccumulatedIdleTimer::new(CallbackTimer::new(TEST_UNIT * 30, || ()), TEST_UNIT * 10),
+        AccumulatedIdleTimer::new(CallbackTimer::new(TEST_UNIT * 3, || ()), TEST_UNIT * 10),
+    ]);
+
+    // Long before the timer goes off, it's polled often enough to notice bursts
+    assert_eq!(xidlehook.poll(TEST_UNIT).unwrap(), Sleep(TEST_UNIT * 5));
+    // ... while close to it, it's woken up right on time
+    assert_eq!(
+        xidlehook.poll(TEST_UNIT * 28).unwrap(),
+        Sleep(TEST_UNIT * 2)
+    );
+    // The same goes for the next timer, which isn't cut short either
+    assert_eq!(
+        xidlehook.poll(TEST_UNIT * 30).unwrap(),
+        Sleep(TEST_UNIT * 3)
+    );
+}
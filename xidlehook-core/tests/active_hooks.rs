@@ -0,0 +1,48 @@
+use std::{cell::Cell, rc::Rc, time::Duration};
+use xidlehook_core::{timers::CallbackTimer, Module, Result, Xidlehook};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+struct CountActive(Rc<Cell<usize>>);
+impl Module for CountActive {
+    fn on_active(&mut self, _prior_idle: Duration) -> Result<()> {
+        self.0.set(self.0.get() + 1);
+        Ok(())
+    }
+}
+
+#[test]
+fn active_hooks() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let count = Rc::new(Cell::new(0));
+
+    let mut timer = Xidlehook::new(vec![
+        CallbackTimer::new(TEST_UNIT * 10, || ()),
+        CallbackTimer::new(TEST_UNIT * 10, || ()),
+    ])
+    .register(CountActive(Rc::clone(&count)));
+
+    // Activity before the first timer is reached isn't a resume
+    timer.poll(TEST_UNIT * 5).unwrap();
+    timer.poll(TEST_UNIT * 1).unwrap();
+    assert_eq!(count.get(), 0);
+
+    // Go idle for long enough for a timer to fire, then return
+    timer.poll(TEST_UNIT * 10).unwrap();
+    timer.poll(TEST_UNIT * 15).unwrap();
+    assert_eq!(count.get(), 0);
+    timer.poll(TEST_UNIT * 1).unwrap();
+    assert_eq!(count.get(), 1);
+
+    // Subsequent polls with the user active don't fire it again
+    timer.poll(TEST_UNIT * 2).unwrap();
+    timer.poll(Duration::default()).unwrap();
+    timer.poll(TEST_UNIT * 3).unwrap();
+    assert_eq!(count.get(), 1);
+
+    // The next wake does
+    timer.poll(TEST_UNIT * 20).unwrap();
+    timer.poll(Duration::default()).unwrap();
+    assert_eq!(count.get(), 2);
+}
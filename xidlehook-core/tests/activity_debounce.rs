@@ -0,0 +1,67 @@
+mod common;
+
+use common::RecordingTimer;
+use std::{cell::RefCell, time::Duration};
+use xidlehook_core::{Action, Xidlehook, DIP_RECHECK};
+
+const THRESHOLD: Duration = Duration::from_millis(1000);
+
+/// A timer logging when it fires and when it's cancelled
+fn recording<'a>(log: &'a RefCell<Vec<&'static str>>) -> RecordingTimer<'a> {
+    RecordingTimer {
+        abortion: Some("cancel"),
+        ..RecordingTimer::new("fire", THRESHOLD, log)
+    }
+}
+
+/// Poll with these idle times in milliseconds, returning what the timer did
+fn feed(debounce: usize, idle_ms: &[u64]) -> Vec<&'static str> {
+    let log = RefCell::new(Vec::new());
+    let mut timer = Xidlehook::new(vec![recording(&log)]).with_activity_debounce(debounce);
+    for &idle in idle_ms {
+        timer.poll(Duration::from_millis(idle)).unwrap();
+    }
+    log.into_inner()
+}
+
+#[test]
+fn activity_debounce() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // Single samples reading low in between high ones
+    let jittery = [500, 1100, 20, 1300, 1400, 0, 1600];
+
+    // By default, every dip counts as activity
+    assert_eq!(
+        feed(1, &jittery),
+        ["fire", "cancel", "fire", "cancel", "fire"]
+    );
+
+    // Debounced, the timer fires once and stays fired
+    assert_eq!(feed(2, &jittery), ["fire"]);
+
+    // Activity that lasts is still noticed, just a poll later
+    assert_eq!(feed(2, &[1100, 20, 60, 1100]), ["fire", "cancel", "fire"]);
+    assert_eq!(feed(3, &[1100, 20, 60, 1200]), ["fire"]);
+    assert_eq!(
+        feed(3, &[1100, 20, 60, 100, 1100]),
+        ["fire", "cancel", "fire"]
+    );
+}
+
+#[test]
+fn activity_debounce_recheck() {
+    let log = RefCell::new(Vec::new());
+    let mut timer = Xidlehook::new(vec![recording(&log)]).with_activity_debounce(2);
+
+    timer.poll(Duration::from_millis(100)).unwrap();
+    // While a dip is pending, it's checked again soon
+    assert_eq!(
+        timer.poll(Duration::from_millis(10)).unwrap(),
+        Action::Sleep(DIP_RECHECK)
+    );
+    assert_eq!(
+        timer.poll(Duration::from_millis(110)).unwrap(),
+        Action::Sleep(THRESHOLD - Duration::from_millis(110))
+    );
+}
@@ -0,0 +1,113 @@
+#![cfg(feature = "async")]
+
+use async_trait::async_trait;
+use std::{
+    cell::Cell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+use xidlehook_core::{
+    modules::{AsyncModule, Awaited, Blocking, Toggle},
+    timers::CallbackTimer,
+    Module, Progress, Result, TimerInfo, Xidlehook,
+};
+
+const SECOND: Duration = Duration::from_secs(1);
+
+/// Takes its time to answer, like something asked over the network
+struct SlowModule {
+    delay: Duration,
+    verdict: Rc<Cell<Progress>>,
+}
+impl Module for SlowModule {}
+#[async_trait(?Send)]
+impl AsyncModule for SlowModule {
+    async fn pre_timer(&mut self, _timer: TimerInfo) -> Result<Progress> {
+        tokio::time::sleep(self.delay).await;
+        Ok(self.verdict.get())
+    }
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
+
+#[test]
+fn async_module() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let verdict = Rc::new(Cell::new(Progress::Abort));
+    let fired = Cell::new(0);
+    let timers = vec![CallbackTimer::new(10 * SECOND, || {
+        fired.set(fired.get() + 1)
+    })];
+    let module = SlowModule {
+        delay: Duration::from_millis(10),
+        verdict: Rc::clone(&verdict),
+    };
+    let mut xidlehook = Xidlehook::new(timers).with_module(Awaited::new(module));
+
+    block_on(async {
+        xidlehook.poll_async(5 * SECOND).await.unwrap();
+        xidlehook.poll_async(10 * SECOND).await.unwrap();
+        assert_eq!(fired.get(), 0);
+
+        // The user comes back, and leaves again
+        xidlehook.poll_async(SECOND).await.unwrap();
+        verdict.set(Progress::Continue);
+        xidlehook.poll_async(10 * SECOND).await.unwrap();
+        assert_eq!(fired.get(), 1);
+    });
+
+    // Without awaiting the module first, it can't decide
+    let info = TimerInfo {
+        index: 0,
+        length: 1,
+    };
+    let mut awaited = Awaited::new(SlowModule {
+        delay: Duration::default(),
+        verdict,
+    });
+    assert!(Module::pre_timer(&mut awaited, info).is_err());
+    block_on(awaited.prefetch(vec![info]));
+    assert_eq!(
+        Module::pre_timer(&mut awaited, info).unwrap(),
+        Progress::Continue
+    );
+}
+
+#[test]
+fn async_modules_together() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let info = TimerInfo {
+        index: 0,
+        length: 1,
+    };
+    let slow = |progress| -> Box<dyn AsyncModule> {
+        Box::new(SlowModule {
+            delay: Duration::from_millis(100),
+            verdict: Rc::new(Cell::new(progress)),
+        })
+    };
+    let toggle = Toggle::new(Progress::Abort);
+    let mut modules = vec![
+        slow(Progress::Continue),
+        Box::new(Blocking(toggle.clone())),
+        slow(Progress::Reset),
+    ];
+
+    // The modules are asked at the same time, but the decisions are
+    // taken in order
+    let start = Instant::now();
+    let progress = block_on(AsyncModule::pre_timer(&mut modules, info)).unwrap();
+    assert!(start.elapsed() < Duration::from_millis(190));
+    assert_eq!(progress, Progress::Reset);
+    toggle.set(true);
+    let progress = block_on(AsyncModule::pre_timer(&mut modules, info)).unwrap();
+    assert_eq!(progress, Progress::Abort);
+}
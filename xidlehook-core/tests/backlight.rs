@@ -0,0 +1,82 @@
+use std::{env, fs, os::unix::fs::PermissionsExt, path::PathBuf, time::Duration};
+use xidlehook_core::{modules::Backlight, Module, TimerInfo};
+
+/// A fake `/sys/class/backlight` with these backlights
+fn sysfs_dir(backlights: &[(&str, &str, u32, u32)]) -> PathBuf {
+    let dir = env::temp_dir().join(format!("xidlehook-backlight-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    for (name, kind, brightness, max) in backlights {
+        let device = dir.join(name);
+        fs::create_dir_all(&device).unwrap();
+        fs::write(device.join("type"), format!("{}\n", kind)).unwrap();
+        fs::write(device.join("brightness"), format!("{}\n", brightness)).unwrap();
+        fs::write(device.join("max_brightness"), format!("{}\n", max)).unwrap();
+    }
+    dir
+}
+
+fn timer(index: usize) -> TimerInfo {
+    TimerInfo { index, length: 2 }
+}
+
+#[test]
+fn backlight() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dir = sysfs_dir(&[
+        ("intel_backlight", "raw", 800, 1000),
+        ("acpi_video0", "firmware", 80, 100),
+    ]);
+    let brightness = |name: &str| {
+        fs::read_to_string(dir.join(name).join("brightness"))
+            .unwrap()
+            .trim()
+            .to_string()
+    };
+
+    // The firmware backlight is preferred
+    let backlight = Backlight::new().with_sysfs_dir(&dir).with_level(20);
+    assert_eq!(backlight.device_dir().unwrap(), dir.join("acpi_video0"));
+
+    // ... unless one is asked for by name
+    let mut backlight = backlight.with_device("intel_backlight").with_timer(1);
+    assert_eq!(backlight.device_dir().unwrap(), dir.join("intel_backlight"));
+
+    // Only the chosen timer dims, and the user returning restores it
+    backlight.post_timer(timer(0)).unwrap();
+    assert_eq!(brightness("intel_backlight"), "800");
+    backlight.post_timer(timer(1)).unwrap();
+    assert!(backlight.is_dimmed());
+    assert_eq!(brightness("intel_backlight"), "200");
+    backlight.reset(Duration::from_secs(60)).unwrap();
+    assert!(!backlight.is_dimmed());
+    assert_eq!(brightness("intel_backlight"), "800");
+
+    // A screen that's already dark enough is left alone
+    fs::write(dir.join("intel_backlight").join("brightness"), "100\n").unwrap();
+    backlight.post_timer(timer(1)).unwrap();
+    assert!(!backlight.is_dimmed());
+    assert_eq!(brightness("intel_backlight"), "100");
+
+    // Unknown backlights are an error
+    assert!(Backlight::new()
+        .with_sysfs_dir(&dir)
+        .with_device("missing")
+        .dim()
+        .is_err());
+    assert!(Backlight::new()
+        .with_sysfs_dir(dir.join("missing"))
+        .dim()
+        .is_err());
+
+    // Not being allowed to change it suggests a udev rule, unless
+    // running as root, whom nothing stops
+    let path = dir.join("acpi_video0").join("brightness");
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o444)).unwrap();
+    if fs::write(&path, "80\n").is_err() {
+        let err = Backlight::new().with_sysfs_dir(&dir).dim().unwrap_err();
+        assert!(err.to_string().contains("udev"), "{}", err);
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
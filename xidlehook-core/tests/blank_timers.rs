@@ -0,0 +1,73 @@
+use std::{cell::RefCell, rc::Rc, time::Duration};
+use xidlehook_core::{
+    modules::xcb::{BlankTimer, Blanker},
+    Result, Timer, Xidlehook,
+};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+/// Records the requests made to it
+#[derive(Clone, Default)]
+struct MockBlanker(Rc<RefCell<Vec<&'static str>>>);
+impl Blanker for MockBlanker {
+    fn show(&mut self) -> Result<()> {
+        self.0.borrow_mut().push("show");
+        Ok(())
+    }
+    fn release_input(&mut self) -> Result<()> {
+        self.0.borrow_mut().push("release");
+        Ok(())
+    }
+    fn hide(&mut self) -> Result<()> {
+        self.0.borrow_mut().push("hide");
+        Ok(())
+    }
+}
+
+#[test]
+fn blank_timers() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let blanker = MockBlanker::default();
+    let mut timer = Xidlehook::new(vec![BlankTimer::new(TEST_UNIT * 10, blanker.clone())]);
+
+    // Shown once the timer is reached, and hidden on activity
+    timer.poll(TEST_UNIT * 5).unwrap();
+    assert!(blanker.0.borrow().is_empty());
+    timer.poll(TEST_UNIT * 10).unwrap();
+    assert!(timer.timers()[0].is_shown());
+    timer.poll(TEST_UNIT * 11).unwrap();
+    timer.poll(Duration::default()).unwrap();
+    assert!(!timer.timers()[0].is_shown());
+    assert_eq!(*blanker.0.borrow(), ["show", "hide"]);
+
+    // Dropping a shown blank timer hides it
+    let mut blank = BlankTimer::new(TEST_UNIT, blanker.clone());
+    blank.activate().unwrap();
+    drop(blank);
+    assert_eq!(*blanker.0.borrow(), ["show", "hide", "show", "hide"]);
+}
+
+#[test]
+fn blank_before_lock() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // The second one stands in for a locker
+    let blanker = MockBlanker::default();
+    let locker = MockBlanker::default();
+    let mut timer = Xidlehook::new(vec![
+        BlankTimer::new(TEST_UNIT * 10, blanker.clone()),
+        BlankTimer::new(TEST_UNIT * 10, locker.clone()),
+    ]);
+
+    // The blanker lets go of the input when the next timer goes off,
+    // so a locker can grab it, but stays until the user is active
+    timer.poll(TEST_UNIT * 10).unwrap();
+    timer.poll(TEST_UNIT * 20).unwrap();
+    assert_eq!(*blanker.0.borrow(), ["show", "release"]);
+    assert_eq!(*locker.0.borrow(), ["show"]);
+
+    timer.poll(Duration::default()).unwrap();
+    assert_eq!(*blanker.0.borrow(), ["show", "release", "hide"]);
+    assert_eq!(*locker.0.borrow(), ["show", "hide"]);
+}
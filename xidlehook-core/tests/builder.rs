@@ -0,0 +1,56 @@
+use std::{process::Command, rc::Rc, time::Duration};
+use xidlehook_core::{
+    builder::XidlehookBuilder, modules::StopAt, sources::ManualIdleSource, timers::CmdTimer,
+};
+
+const TEST_UNIT: Duration = Duration::from_millis(10);
+
+#[test]
+fn builder() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let source = Rc::new(ManualIdleSource::new(Duration::default()));
+    let mut runner = XidlehookBuilder::new()
+        .add_timer(TEST_UNIT, Command::new("true"))
+        .add_cmd_timer(CmdTimer {
+            time: TEST_UNIT,
+            activation: Some(Command::new("true")),
+            abortion: Some(Command::new("true")),
+            ..CmdTimer::default()
+        })
+        .add_module(StopAt::completion())
+        .idle_source(Rc::clone(&source))
+        .detect_sleep(true)
+        .build()
+        .unwrap();
+    assert_eq!(runner.xidlehook.timers().len(), 2);
+    assert!(runner.xidlehook.detect_sleep());
+
+    // The timers run off the idle source
+    runner.poll().unwrap();
+    assert_eq!(runner.xidlehook.next_index(), 0);
+    source.advance(TEST_UNIT);
+    runner.poll().unwrap();
+    assert_eq!(runner.xidlehook.next_index(), 1);
+}
+
+#[test]
+fn invalid_builders() {
+    // No timers
+    let source = ManualIdleSource::new(Duration::default());
+    assert!(XidlehookBuilder::new().idle_source(source).build().is_err());
+
+    // A timer of zero
+    let source = ManualIdleSource::new(Duration::default());
+    assert!(XidlehookBuilder::new()
+        .add_timer(Duration::default(), Command::new("true"))
+        .idle_source(source)
+        .build()
+        .is_err());
+
+    // No idle source
+    assert!(XidlehookBuilder::new()
+        .add_timer(TEST_UNIT, Command::new("true"))
+        .build()
+        .is_err());
+}
@@ -0,0 +1,70 @@
+use std::{cell::Cell, thread, time::Duration};
+use xidlehook_core::{
+    sources::CachedIdleSource, timers::CallbackTimer, IdleSource, Result, Xidlehook,
+};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+/// Counts how often it's asked, and fails if told to
+#[derive(Default)]
+struct CountingSource {
+    queries: Cell<u32>,
+    failing: Cell<bool>,
+}
+impl IdleSource for CountingSource {
+    fn get_idle(&self) -> Result<Duration> {
+        self.queries.set(self.queries.get() + 1);
+        if self.failing.get() {
+            return Err("failing".into());
+        }
+        Ok(Duration::from_secs(self.queries.get().into()))
+    }
+}
+
+#[test]
+fn cached_idle() {
+    let source = CachedIdleSource::with_ttl(CountingSource::default(), TEST_UNIT);
+
+    // Any number of consumers in one tick cause one query
+    for _ in 0..10 {
+        assert_eq!(source.get_idle().unwrap(), Duration::from_secs(1));
+    }
+    assert_eq!(source.inner().queries.get(), 1);
+
+    // The next tick queries again
+    source.refresh();
+    assert_eq!(source.get_idle().unwrap(), Duration::from_secs(2));
+    assert_eq!(source.get_idle().unwrap(), Duration::from_secs(2));
+    assert_eq!(source.inner().queries.get(), 2);
+
+    // So does a consumer after the TTL
+    thread::sleep(TEST_UNIT * 2);
+    assert_eq!(source.get_idle().unwrap(), Duration::from_secs(3));
+
+    // Errors aren't cached
+    source.refresh();
+    source.inner().failing.set(true);
+    assert!(source.get_idle().is_err());
+    source.inner().failing.set(false);
+    assert_eq!(source.get_idle().unwrap(), Duration::from_secs(5));
+}
+
+#[test]
+fn cached_idle_ticks() {
+    // The main loop refreshes the source once per tick, however long
+    // the TTL is
+    let source = CachedIdleSource::with_ttl(CountingSource::default(), Duration::from_secs(3600));
+    let xidlehook = Xidlehook::new(vec![CallbackTimer::new(Duration::from_secs(3600), || ())])
+        .with_poll_interval_max(TEST_UNIT);
+    let mut ticks = 0;
+    xidlehook
+        .main_sync(&source, || {
+            // Other consumers during the tick
+            source.get_idle().unwrap();
+            source.get_idle().unwrap();
+            ticks += 1;
+            ticks == 3
+        })
+        .unwrap();
+    assert_eq!(source.inner().queries.get(), 3);
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use common::RecordingTimer;
+use std::{
+    cell::RefCell,
+    thread,
+    time::{Duration, Instant},
+};
+use xidlehook_core::{Action::*, Xidlehook};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+/// Timers going off one after another, which log their index when aborted
+fn timers<'a>(log: &'a RefCell<Vec<&'static str>>) -> Vec<RecordingTimer<'a>> {
+    ["0", "1", "2"]
+        .iter()
+        .map(|&id| RecordingTimer {
+            activation: None,
+            abortion: Some(id),
+            ..RecordingTimer::new(id, TEST_UNIT * 10, log)
+        })
+        .collect()
+}
+
+#[test]
+fn coalesced_cancellers() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let log = RefCell::new(Vec::new());
+
+    let mut timer = Xidlehook::new(timers(&log)).with_canceller_gap(TEST_UNIT * 2);
+
+    // Fire all timers
+    for i in 1..=3 {
+        timer.poll(TEST_UNIT * 10 * i).unwrap();
+    }
+    assert!(log.borrow().is_empty());
+
+    // The user returns: only the most recent canceller runs right away, and
+    // the poll wakes up in time for the next one
+    let start = Instant::now();
+    let mut action = timer.poll(Duration::default()).unwrap();
+    assert_eq!(*log.borrow(), ["2"]);
+    assert!(start.elapsed() < TEST_UNIT);
+
+    // The others run from later polls, in reverse order, spaced by the gap
+    while let Sleep(delay) = action {
+        if log.borrow().len() == 3 {
+            break;
+        }
+        assert!(delay <= TEST_UNIT * 2);
+        thread::sleep(delay);
+        action = timer.poll(Duration::default()).unwrap();
+    }
+    assert_eq!(*log.borrow(), ["2", "1", "0"]);
+    let aborted_at = |index: usize| timer.timers()[index].aborted_at[0];
+    assert!(aborted_at(1) - aborted_at(2) >= TEST_UNIT * 2);
+    assert!(aborted_at(0) - aborted_at(1) >= TEST_UNIT * 2);
+}
+
+#[test]
+fn uncoalesced_cancellers() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let log = RefCell::new(Vec::new());
+
+    let mut timer = Xidlehook::new(timers(&log));
+
+    for i in 1..=3 {
+        timer.poll(TEST_UNIT * 10 * i).unwrap();
+    }
+
+    // Without a gap, only the most recent timer is aborted
+    timer.poll(Duration::default()).unwrap();
+    assert_eq!(*log.borrow(), ["2"]);
+}
+
+#[test]
+fn flushed_cancellers() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let log = RefCell::new(Vec::new());
+
+    let mut timer = Xidlehook::new(timers(&log)).with_canceller_gap(TEST_UNIT * 20);
+
+    for i in 1..=3 {
+        timer.poll(TEST_UNIT * 10 * i).unwrap();
+    }
+    timer.poll(Duration::default()).unwrap();
+
+    // A timer going off again first runs the cancellers still waiting
+    timer.poll(TEST_UNIT * 10).unwrap();
+    assert_eq!(*log.borrow(), ["2", "1", "0"]);
+}
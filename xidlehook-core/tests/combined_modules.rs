@@ -0,0 +1,86 @@
+use std::{cell::Cell, rc::Rc};
+use xidlehook_core::{
+    modules::{Combinator, Combine},
+    Module, Progress, Result, TimerInfo,
+};
+
+const INFO: TimerInfo = TimerInfo {
+    index: 1,
+    length: 2,
+};
+
+/// Counts how often it's checked, and answers with a fixed progress
+struct Check(Rc<Cell<usize>>, Progress);
+impl Module for Check {
+    fn pre_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        assert_eq!((timer.index, timer.length), (INFO.index, INFO.length));
+        self.0.set(self.0.get() + 1);
+        Ok(self.1)
+    }
+}
+
+struct Failing;
+impl Module for Failing {
+    fn pre_timer(&mut self, _timer: TimerInfo) -> Result<Progress> {
+        Err("broken".into())
+    }
+}
+
+fn combine(combinator: Combinator, progress: &[Progress]) -> (Combine, Rc<Cell<usize>>) {
+    let checks = Rc::new(Cell::new(0));
+    let modules = progress
+        .iter()
+        .map(|&progress| Box::new(Check(Rc::clone(&checks), progress)) as Box<dyn Module>)
+        .collect();
+    (Combine::new(combinator, modules), checks)
+}
+
+#[test]
+fn any_module() {
+    use Progress::{Abort, Continue};
+
+    let (mut any, checks) = combine(Combinator::Any, &[Continue, Continue]);
+    assert_eq!(any.pre_timer(INFO).unwrap(), Continue);
+    assert_eq!(checks.get(), 2);
+
+    // The modules after the first one aborting aren't asked
+    let (mut any, checks) = combine(Combinator::Any, &[Continue, Abort, Continue]);
+    assert_eq!(any.pre_timer(INFO).unwrap(), Abort);
+    assert_eq!(checks.get(), 2);
+}
+
+#[test]
+fn all_modules() {
+    use Progress::{Abort, Continue, Reset};
+
+    let (mut all, checks) = combine(Combinator::All, &[Abort, Continue, Abort]);
+    assert_eq!(all.pre_timer(INFO).unwrap(), Continue);
+    assert_eq!(checks.get(), 2);
+
+    // The first decision is used
+    let (mut all, checks) = combine(Combinator::All, &[Reset, Abort]);
+    assert_eq!(all.pre_timer(INFO).unwrap(), Reset);
+    assert_eq!(checks.get(), 2);
+}
+
+#[test]
+fn nested_combinations() {
+    use Progress::{Abort, Continue};
+
+    // Fullscreen or audio, but only if not on AC
+    let fullscreen = Check(Rc::default(), Continue);
+    let audio = Check(Rc::default(), Abort);
+    let on_battery = Check(Rc::default(), Abort);
+    let mut combined = Combine::all()
+        .with(Combine::any().with(fullscreen).with(audio))
+        .with(on_battery);
+    assert_eq!(combined.pre_timer(INFO).unwrap(), Abort);
+    assert_eq!(combined.describe().kind, "combine");
+
+    // Empty combinations don't keep timers from running
+    assert_eq!(Combine::all().pre_timer(INFO).unwrap(), Continue);
+    assert_eq!(Combine::any().pre_timer(INFO).unwrap(), Continue);
+
+    // Errors are passed on
+    assert!(Combine::all().with(Failing).pre_timer(INFO).is_err());
+}
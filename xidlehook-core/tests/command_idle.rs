@@ -0,0 +1,41 @@
+use std::{
+    process::Command,
+    time::{Duration, Instant},
+};
+use xidlehook_core::{sources::CommandIdleSource, IdleSource};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+fn script(script: &str) -> CommandIdleSource {
+    let mut cmd = Command::new("/bin/sh");
+    cmd.arg("-c").arg(script);
+    CommandIdleSource::new(cmd).with_timeout(TEST_UNIT * 4)
+}
+
+#[test]
+fn command_idle() {
+    assert_eq!(
+        script("echo 1234").get_idle().unwrap(),
+        Duration::from_millis(1234)
+    );
+
+    // Anything but a number is an error, as is failing
+    assert!(script("echo soon").get_idle().is_err());
+    assert!(script("echo").get_idle().is_err());
+    assert!(script("echo 1234; exit 1").get_idle().is_err());
+
+    // More output than a pipe holds is read while the command runs
+    assert_eq!(
+        script("head -c 100000 /dev/zero | tr '\\0' ' '; echo 1234")
+            .get_idle()
+            .unwrap(),
+        Duration::from_millis(1234)
+    );
+}
+
+#[test]
+fn command_idle_timeout() {
+    let start = Instant::now();
+    assert!(script("sleep 5; echo 1234").get_idle().is_err());
+    assert!(start.elapsed() < TEST_UNIT * 20);
+}
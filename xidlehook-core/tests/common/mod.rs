@@ -0,0 +1,90 @@
+//! Helpers shared by the tests. Every test file is its own crate, so
+//! each one only uses some of these.
+#![allow(dead_code)]
+
+use std::{
+    cell::RefCell,
+    io::{BufRead, BufReader},
+    process::{Child, Command, Stdio},
+    time::{Duration, Instant},
+};
+use xidlehook_core::{Result, Timer};
+
+/// A timer which writes what happens to it to a log
+pub struct RecordingTimer<'a> {
+    pub time: Duration,
+    /// Logged when the timer is activated, if set
+    pub activation: Option<&'static str>,
+    /// Logged when the timer is aborted, if set
+    pub abortion: Option<&'static str>,
+    pub once: bool,
+    pub recurring: Option<Duration>,
+    pub dead_zone: Duration,
+    /// When the timer was aborted, every time
+    pub aborted_at: Vec<Instant>,
+    pub log: &'a RefCell<Vec<&'static str>>,
+}
+impl<'a> RecordingTimer<'a> {
+    /// A timer going off after `time`, which logs `name` when it does
+    pub fn new(name: &'static str, time: Duration, log: &'a RefCell<Vec<&'static str>>) -> Self {
+        Self {
+            time,
+            activation: Some(name),
+            abortion: None,
+            once: false,
+            recurring: None,
+            dead_zone: Duration::default(),
+            aborted_at: Vec::new(),
+            log,
+        }
+    }
+}
+impl<'a> Timer for RecordingTimer<'a> {
+    fn time_left(&mut self, idle_time: Duration) -> Result<Option<Duration>> {
+        Ok(self
+            .time
+            .checked_sub(idle_time)
+            .filter(|&d| d != Duration::default()))
+    }
+    fn activate(&mut self) -> Result<()> {
+        self.log.borrow_mut().extend(self.activation);
+        Ok(())
+    }
+    fn abort(&mut self) -> Result<()> {
+        self.aborted_at.push(Instant::now());
+        self.log.borrow_mut().extend(self.abortion);
+        Ok(())
+    }
+    fn once(&self) -> bool {
+        self.once
+    }
+    fn recurring(&self) -> Option<Duration> {
+        self.recurring
+    }
+    fn dead_zone(&self) -> Duration {
+        self.dead_zone
+    }
+}
+
+/// A private D-Bus daemon, which is stopped when dropped
+pub struct Bus(Child);
+impl Drop for Bus {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// Start a private bus, returning `None` if dbus-daemon isn't available
+pub fn private_bus() -> Option<(Bus, String)> {
+    let mut child = Command::new("dbus-daemon")
+        .args(["--session", "--nofork", "--print-address"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .ok()?;
+    let mut address = String::new();
+    BufReader::new(child.stdout.take()?)
+        .read_line(&mut address)
+        .ok()?;
+    Some((Bus(child), address.trim().to_string()))
+}
@@ -0,0 +1,371 @@
+#![cfg(feature = "config")]
+
+use std::{collections::BTreeMap, ffi::OsStr, fs, path::Path, time::Duration};
+use xidlehook_core::{
+    config::{parse_seconds, Config, FullscreenConfig, TimerConfig},
+    modules::xcb::{FullscreenOptions, Pattern, Rule, ScanMode},
+    timers::TimerCommand,
+    Action::*,
+    Timer,
+};
+
+const SAMPLE: &str = r#"
+detect_sleep = true
+
+[[timer]]
+name = "dim"
+time = 60
+command = "true dim"
+canceller = "true undim"
+tags = ["screen"]
+
+[[timer]]
+name = "lock"
+time = 10
+command = "true lock"
+relock_on_crash = true
+warning = "true warn"
+warning_lead = 5
+
+[[timer]]
+time = 3600
+command = "true suspend"
+disabled = true
+recurring = 1800
+
+[modules]
+stop_at = "lock"
+
+[modules.not_when_fullscreen]
+exceptions_wm_class1 = ["mpv"]
+exceptions_process = ["vlc"]
+exceptions = [
+    { and = [{ class = "firefox" }, { not = { name_contains = "Private Browsing" } }] },
+    { class_matches = "^steam_app_" },
+]
+ignore_sticky_windows = true
+scan_mode = "full_tree"
+case_insensitive = true
+monitors = ["HDMI-1"]
+override_redirect = true
+"#;
+
+#[test]
+fn config() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let config = Config::from_toml(SAMPLE).unwrap();
+    assert!(config.detect_sleep);
+    assert_eq!(
+        config.timers[0],
+        TimerConfig {
+            name: Some("dim".into()),
+            time: 60,
+            command: "true dim".into(),
+            canceller: Some("true undim".into()),
+            deactivation: None,
+            tags: vec!["screen".into()],
+            disabled: false,
+            dead_zone_ms: 0,
+            relock_on_crash: false,
+            recurring: None,
+            once: false,
+            warning: None,
+            warning_lead: 0,
+            kill_on_resume: false,
+            kill_signal: None,
+            detached: false,
+            working_dir: None,
+            env: BTreeMap::new(),
+            clear_env: false,
+        }
+    );
+    assert_eq!(config.timer_index("lock"), Some(1));
+    assert_eq!(config.modules.stop_at.as_deref(), Some("lock"));
+
+    let fullscreen = config.modules.not_when_fullscreen.as_ref().unwrap();
+    assert_eq!(
+        *fullscreen,
+        FullscreenConfig {
+            exceptions_wm_class1: vec!["mpv".into()],
+            exceptions_process: vec!["vlc".into()],
+            exceptions: vec![
+                Rule::And(vec![
+                    Rule::Class("firefox".into()),
+                    Rule::Not(Box::new(Rule::NameContains("Private Browsing".into()))),
+                ]),
+                Rule::ClassMatches(Pattern::new("^steam_app_").unwrap()),
+            ],
+            ignore_sticky_windows: true,
+            scan_mode: ScanMode::FullTree,
+            case_insensitive: true,
+            monitors: Some(vec!["HDMI-1".into()]),
+            override_redirect: true,
+            ..FullscreenConfig::default()
+        }
+    );
+    let options = fullscreen.options();
+    assert_eq!(options.exceptions_wm_class1, vec![String::from("mpv")]);
+    assert!(options.ignore_sticky_windows);
+    assert_eq!(options.scan_mode, ScanMode::FullTree);
+    assert!(options.case_insensitive);
+    assert_eq!(options.monitors, Some(vec![String::from("HDMI-1")]));
+    assert!(options.override_redirect);
+    // Only the active window is checked unless configured otherwise
+    assert_eq!(FullscreenOptions::default().scan_mode, ScanMode::ActiveOnly);
+
+    let timers = config.timers();
+    assert_eq!(
+        timers.iter().map(|timer| timer.time).collect::<Vec<_>>(),
+        vec![
+            Duration::from_secs(60),
+            Duration::from_secs(10),
+            Duration::from_secs(3600)
+        ]
+    );
+    assert!(format!("{:?}", timers[0].activation.as_ref().unwrap()).contains("true dim"));
+    assert!(format!("{:?}", timers[0].abortion.as_ref().unwrap()).contains("true undim"));
+    assert!(timers[1].abortion.is_none());
+    assert!(timers[1].relock_on_crash);
+    assert_eq!(timers[1].warning_lead(), Some(Duration::from_secs(5)));
+    assert!(timers[2].disabled);
+    assert_eq!(timers[2].recurring, Some(Duration::from_secs(1800)));
+
+    // The fullscreen module can't be built without X
+    assert!(config.build(None).is_err());
+
+    // The same config can be expressed in JSON
+    let json = Config::from_json(
+        r#"{
+            "detect_sleep": true,
+            "timer": [
+                { "name": "dim", "time": 60, "command": "true dim", "canceller": "true undim", "tags": ["screen"] },
+                { "name": "lock", "time": 10, "command": "true lock", "relock_on_crash": true, "warning": "true warn", "warning_lead": 5 },
+                { "time": 3600, "command": "true suspend", "disabled": true, "recurring": 1800 }
+            ],
+            "modules": {
+                "stop_at": "lock",
+                "not_when_fullscreen": {
+                    "exceptions_wm_class1": ["mpv"],
+                    "exceptions_process": ["vlc"],
+                    "exceptions": [
+                        { "and": [{ "class": "firefox" }, { "not": { "name_contains": "Private Browsing" } }] },
+                        { "class_matches": "^steam_app_" }
+                    ],
+                    "ignore_sticky_windows": true,
+                    "scan_mode": "full_tree",
+                    "case_insensitive": true,
+                    "monitors": ["HDMI-1"],
+                    "override_redirect": true
+                }
+            }
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(json, config);
+}
+
+#[test]
+fn config_modules() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let config = Config::from_toml(
+        r#"
+        [[timer]]
+        time = 1
+        command = "true"
+
+        [[timer]]
+        time = 1
+        command = "true"
+
+        [modules]
+        once = true
+        "#,
+    )
+    .unwrap();
+    let mut xidlehook = config.build(None).unwrap();
+    assert_eq!(xidlehook.timers().len(), 2);
+    assert!(!xidlehook.detect_sleep());
+    assert_eq!(xidlehook.resume_grace(), Duration::default());
+
+    // The chain stops once it's complete
+    assert_eq!(
+        xidlehook.poll(Duration::from_secs(1)).unwrap(),
+        Sleep(Duration::from_secs(1))
+    );
+    assert_eq!(xidlehook.poll(Duration::from_secs(2)).unwrap(), Quit);
+}
+
+#[test]
+fn config_durations() {
+    assert_eq!(parse_seconds("90").unwrap(), 90);
+    assert_eq!(parse_seconds("90s").unwrap(), 90);
+    assert_eq!(parse_seconds("5m").unwrap(), 300);
+    assert_eq!(parse_seconds("1h30m").unwrap(), 5400);
+    assert_eq!(parse_seconds("1d").unwrap(), 86400);
+    for invalid in &[
+        "",
+        "m",
+        "5x",
+        "1.5h",
+        "-5m",
+        "5m30",
+        "99999999999999999999d",
+    ] {
+        assert!(parse_seconds(invalid).is_err(), "{:?} parsed", invalid);
+    }
+
+    let config = Config::from_toml(
+        r#"
+        resume_grace = "1m"
+
+        [[timer]]
+        time = "5m"
+        command = "true"
+        warning = "true warn"
+        warning_lead = "30s"
+
+        [[timer]]
+        time = 3600
+        command = "true"
+        recurring = "1h"
+        "#,
+    )
+    .unwrap();
+    let timers = config.timers();
+    assert_eq!(timers[0].time, Duration::from_secs(300));
+    assert_eq!(timers[0].warning_lead(), Some(Duration::from_secs(30)));
+    assert_eq!(timers[1].time, Duration::from_secs(3600));
+    assert_eq!(timers[1].recurring, Some(Duration::from_secs(3600)));
+    let xidlehook = config.build(None).unwrap();
+    assert_eq!(xidlehook.resume_grace(), Duration::from_secs(60));
+
+    let err = Config::from_toml("[[timer]]\ntime = '5 minutes'\ncommand = 'true'").unwrap_err();
+    assert!(err.to_string().contains("5 minutes"), "{}", err);
+}
+
+#[test]
+fn config_commands() {
+    let config = Config::from_toml(
+        r#"
+        [[timer]]
+        time = 60
+        command = ["restic", "backup", "."]
+        canceller = "pkill restic"
+        working_dir = "/tmp"
+        env = { RESTIC_REPOSITORY = "/mnt/backup" }
+        "#,
+    )
+    .unwrap();
+    let timer = &config.timers[0];
+    assert_eq!(
+        timer.command,
+        TimerCommand::Argv(vec!["restic".into(), "backup".into(), ".".into()])
+    );
+    assert_eq!(
+        timer.canceller,
+        Some(TimerCommand::Shell("pkill restic".into()))
+    );
+
+    let timers = config.timers();
+    let activation = timers[0].activation.as_ref().unwrap();
+    assert_eq!(activation.get_program(), "restic");
+    assert_eq!(activation.get_args().collect::<Vec<_>>(), ["backup", "."]);
+    assert_eq!(activation.get_current_dir(), Some(Path::new("/tmp")));
+    let env: Vec<_> = activation.get_envs().collect();
+    assert_eq!(
+        env,
+        [(
+            OsStr::new("RESTIC_REPOSITORY"),
+            Some(OsStr::new("/mnt/backup"))
+        )]
+    );
+    let abortion = timers[0].abortion.as_ref().unwrap();
+    assert_eq!(abortion.get_program(), "/bin/sh");
+    assert_eq!(abortion.get_current_dir(), Some(Path::new("/tmp")));
+}
+
+#[test]
+fn config_round_trip() {
+    // Configs can be written back out, with durations in seconds
+    let config = Config::from_toml(SAMPLE).unwrap();
+    let json = serde_json::to_string(&config).unwrap();
+    assert_eq!(Config::from_json(&json).unwrap(), config);
+    let toml = toml::to_string(&config).unwrap();
+    assert_eq!(Config::from_toml(&toml).unwrap(), config);
+}
+
+#[test]
+fn fullscreen_config_files() {
+    let dir = std::env::temp_dir();
+    let toml = dir.join(format!("xidlehook-exceptions-{}.toml", std::process::id()));
+    let json = dir.join(format!("xidlehook-exceptions-{}.json", std::process::id()));
+    fs::write(&toml, "exceptions_wm_class1 = [\"mpv\"]\n").unwrap();
+    fs::write(&json, r#"{"exceptions_wm_name": ["Café"]}"#).unwrap();
+
+    let config = FullscreenConfig::load(&toml).unwrap();
+    assert_eq!(config.exceptions_wm_class1, vec![String::from("mpv")]);
+    let config = FullscreenConfig::load(&json).unwrap();
+    assert_eq!(config.exceptions_wm_name, vec![String::from("Café")]);
+
+    // Typos aren't ignored
+    fs::write(&toml, "exception_wm_class1 = [\"mpv\"]\n").unwrap();
+    assert!(FullscreenConfig::load(&toml).is_err());
+    let _ = fs::remove_file(&toml);
+    let _ = fs::remove_file(&json);
+    assert!(FullscreenConfig::load(&toml).is_err());
+}
+
+#[test]
+fn config_validation() {
+    let invalid = |input| Config::from_toml(input).is_err();
+
+    // No timers
+    assert!(invalid(""));
+    // Zero time
+    assert!(invalid("[[timer]]\ntime = 0\ncommand = 'true'"));
+    // Typo in a key
+    assert!(invalid("[[timer]]\ntime = 1\ncomand = 'true'"));
+    // Duplicate names
+    assert!(invalid(
+        "[[timer]]\nname = 'a'\ntime = 1\ncommand = 'true'\n\
+         [[timer]]\nname = 'a'\ntime = 1\ncommand = 'true'"
+    ));
+    // Unknown reference
+    assert!(invalid(
+        "[[timer]]\nname = 'a'\ntime = 1\ncommand = 'true'\n\
+         [modules]\nstop_at = 'b'"
+    ));
+    // Empty tag
+    assert!(invalid(
+        "[[timer]]\ntime = 1\ncommand = 'true'\ntags = ['']"
+    ));
+    // A warning lead as long as the timer itself
+    assert!(invalid(
+        "[[timer]]\ntime = 10\ncommand = 'true'\nwarning = 'true'\nwarning_lead = 10"
+    ));
+    // A warning without a lead
+    assert!(invalid(
+        "[[timer]]\ntime = 10\ncommand = 'true'\nwarning = 'true'"
+    ));
+    // Both once and recurring
+    assert!(invalid(
+        "[[timer]]\ntime = 1\ncommand = 'true'\nonce = true\nrecurring = 5"
+    ));
+    // Recurring all the time
+    assert!(invalid(
+        "[[timer]]\ntime = 1\ncommand = 'true'\nrecurring = 0"
+    ));
+    // A command that's an empty list
+    assert!(invalid("[[timer]]\ntime = 1\ncommand = []"));
+    // An unknown signal
+    assert!(invalid(
+        "[[timer]]\ntime = 1\ncommand = 'true'\nkill_on_resume = true\nkill_signal = 'SIGNOPE'"
+    ));
+    // Invalid pattern
+    assert!(invalid(
+        "[[timer]]\ntime = 1\ncommand = 'true'\n\
+         [modules.not_when_fullscreen]\nexceptions = [{ class_matches = '(' }]"
+    ));
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use common::RecordingTimer;
+use std::{cell::RefCell, thread, time::Duration};
+use xidlehook_core::{Action::*, Xidlehook};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+/// A notification, with the screen locking right after
+fn timers<'a>(log: &'a RefCell<Vec<&'static str>>) -> Vec<RecordingTimer<'a>> {
+    vec![
+        RecordingTimer::new("notify", TEST_UNIT * 10, log),
+        RecordingTimer::new("lock", TEST_UNIT / 10, log),
+    ]
+}
+
+#[test]
+fn cooldown() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let log = RefCell::new(Vec::new());
+    let mut xidlehook = Xidlehook::new(timers(&log)).with_cooldown(TEST_UNIT * 4);
+    assert_eq!(xidlehook.cooldown(), TEST_UNIT * 4);
+
+    // The second timer is due right after the first, but waits
+    match xidlehook.poll(TEST_UNIT * 11).unwrap() {
+        Sleep(delay) => assert!(delay <= TEST_UNIT * 4, "{:?}", delay),
+        action => panic!("expected to sleep, got {:?}", action),
+    }
+    assert_eq!(*log.borrow(), ["notify"]);
+    xidlehook.poll(TEST_UNIT * 12).unwrap();
+    assert_eq!(*log.borrow(), ["notify"]);
+
+    thread::sleep(TEST_UNIT * 4);
+    xidlehook.poll(TEST_UNIT * 16).unwrap();
+    assert_eq!(*log.borrow(), ["notify", "lock"]);
+}
+
+#[test]
+fn cooldown_recurring() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // Repeating a timer waits for the cooldown too
+    let log = RefCell::new(Vec::new());
+    let timers = vec![RecordingTimer {
+        recurring: Some(TEST_UNIT),
+        ..RecordingTimer::new("notify", TEST_UNIT, &log)
+    }];
+    let mut xidlehook = Xidlehook::new(timers).with_cooldown(TEST_UNIT * 4);
+    xidlehook.poll(TEST_UNIT).unwrap();
+    xidlehook.poll(TEST_UNIT * 3).unwrap();
+    assert_eq!(log.borrow().len(), 1);
+    thread::sleep(TEST_UNIT * 4);
+    xidlehook.poll(TEST_UNIT * 7).unwrap();
+    assert_eq!(log.borrow().len(), 2);
+}
+
+#[test]
+fn no_cooldown() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // By default, the second timer fires as soon as it's due
+    let log = RefCell::new(Vec::new());
+    let mut xidlehook = Xidlehook::new(timers(&log));
+    assert_eq!(xidlehook.cooldown(), Duration::default());
+    xidlehook.poll(TEST_UNIT * 11).unwrap();
+    xidlehook.poll(TEST_UNIT * 12).unwrap();
+    assert_eq!(*log.borrow(), ["notify", "lock"]);
+}
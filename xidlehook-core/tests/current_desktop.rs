@@ -0,0 +1,59 @@
+use std::{convert::TryFrom, env};
+use xidlehook_core::modules::Xcb;
+
+#[test]
+fn current_desktop() {
+    // This needs an X server
+    if env::var_os("DISPLAY").is_none() {
+        return;
+    }
+
+    let xcb = Xcb::new().unwrap();
+    // Whatever the window manager says, if there is one, is plausible
+    if let Some(desktop) = xcb.current_desktop().unwrap() {
+        assert!(desktop < 1000, "{} desktops", desktop);
+    }
+
+    // Pretend to be a window manager on a server without one
+    let (conn, screen) = xcb::Connection::connect(None).unwrap();
+    let root = conn
+        .get_setup()
+        .roots()
+        .nth(usize::try_from(screen).unwrap())
+        .unwrap()
+        .root();
+    let atom = xcb::xproto::intern_atom(&conn, false, "_NET_CURRENT_DESKTOP")
+        .get_reply()
+        .unwrap()
+        .atom();
+    let original =
+        xcb::xproto::get_property(&conn, false, root, atom, xcb::xproto::ATOM_CARDINAL, 0, 1)
+            .get_reply()
+            .unwrap();
+    if original.value_len() > 0 {
+        // Don't fight a real window manager
+        return;
+    }
+    let set = |desktop: &[u32]| {
+        xcb::xproto::change_property(
+            &conn,
+            xcb::xproto::PROP_MODE_REPLACE as u8,
+            root,
+            atom,
+            xcb::xproto::ATOM_CARDINAL,
+            32,
+            desktop,
+        )
+        .request_check()
+        .unwrap();
+    };
+    set(&[2]);
+    assert_eq!(xcb.current_desktop().unwrap(), Some(2));
+    set(&[1, 3]);
+    assert_eq!(xcb.current_desktop().unwrap(), Some(1));
+
+    xcb::xproto::delete_property(&conn, root, atom)
+        .request_check()
+        .unwrap();
+    assert_eq!(xcb.current_desktop().unwrap(), None);
+}
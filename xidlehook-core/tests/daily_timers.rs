@@ -0,0 +1,85 @@
+use std::{cell::Cell, rc::Rc, time::Duration};
+use xidlehook_core::{
+    daily::{DailyTimer, LocalTime},
+    sources::ManualIdleSource,
+    timers::CallbackTimer,
+    Action, Xidlehook,
+};
+
+const MINUTE: Duration = Duration::from_secs(60);
+
+#[test]
+fn daily_timers() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let cleanups = Rc::new(Cell::new(0));
+    let cleanup = {
+        let cleanups = Rc::clone(&cleanups);
+        CallbackTimer::new(Duration::default(), move || {
+            cleanups.set(cleanups.get() + 1)
+        })
+    };
+    let daily = DailyTimer::parse("02:00", cleanup)
+        .unwrap()
+        .with_min_idle(MINUTE * 10);
+    assert_eq!(daily.to_string(), "02:00");
+
+    let now = Rc::new(Cell::new(LocalTime::at(0, 1, 30)));
+    let mut xidlehook = Xidlehook::new(Vec::new()).with_daily(daily);
+    xidlehook.set_clock({
+        let now = Rc::clone(&now);
+        move || Ok(now.get())
+    });
+    let source = ManualIdleSource::new(MINUTE * 20);
+
+    // Without other timers, it wakes up at 02:00
+    let action = xidlehook.run_ticks(&source, 1, |_| ()).unwrap();
+    assert_eq!(action, Action::Sleep(MINUTE * 30));
+    assert_eq!(cleanups.get(), 0);
+
+    // Idle then, the timer goes off, and only once that day
+    now.set(LocalTime::at(0, 2, 0));
+    let action = xidlehook.run_ticks(&source, 3, |_| ()).unwrap();
+    assert_eq!(cleanups.get(), 1);
+    assert_eq!(action, Action::Sleep(MINUTE * 60 * 24));
+    now.set(LocalTime {
+        day: 0,
+        second: LocalTime::at(0, 2, 0).second + 30,
+    });
+    xidlehook.run_ticks(&source, 1, |_| ()).unwrap();
+    assert_eq!(cleanups.get(), 1);
+
+    // Active at that time the next day, it waits for the day after,
+    // even if the user is idle a little later
+    now.set(LocalTime::at(1, 2, 0));
+    source.set(MINUTE);
+    xidlehook.run_ticks(&source, 1, |_| ()).unwrap();
+    now.set(LocalTime::at(1, 2, 15));
+    source.set(MINUTE * 15);
+    xidlehook.run_ticks(&source, 1, |_| ()).unwrap();
+    assert_eq!(cleanups.get(), 1);
+
+    // Missing the minute, such as while suspended, skips the day too
+    now.set(LocalTime::at(2, 2, 1));
+    xidlehook.run_ticks(&source, 1, |_| ()).unwrap();
+    assert_eq!(cleanups.get(), 1);
+
+    now.set(LocalTime::at(3, 2, 0));
+    xidlehook.run_ticks(&source, 1, |_| ()).unwrap();
+    assert_eq!(cleanups.get(), 2);
+}
+
+#[test]
+fn daily_times() {
+    let timer = || CallbackTimer::new(Duration::default(), || ());
+    assert_eq!(DailyTimer::parse("2:05", timer()).unwrap().at(), (2, 5));
+    for invalid in &["24:00", "12:60", "noon", "12"] {
+        assert!(DailyTimer::parse(invalid, timer()).is_err(), "{}", invalid);
+    }
+
+    // Later than the time of day, it's due the next day
+    let daily = DailyTimer::new((2, 0), timer()).unwrap();
+    assert_eq!(daily.until_due(LocalTime::at(0, 1, 59)), MINUTE);
+    assert_eq!(daily.until_due(LocalTime::at(0, 3, 0)), MINUTE * (60 * 23));
+    assert!(LocalTime::now().unwrap().second < 24 * 60 * 60);
+}
@@ -0,0 +1,86 @@
+#![cfg(feature = "dbus")]
+
+mod common;
+
+use common::private_bus;
+use std::time::Duration;
+use xidlehook_core::{
+    modules::{DbusProperty, NotWhenDbusProperty},
+    timers::CallbackTimer,
+    Xidlehook,
+};
+use zbus::blocking::connection;
+
+struct StubPresentation {
+    active: bool,
+}
+#[zbus::interface(name = "org.example.Presentation")]
+impl StubPresentation {
+    #[zbus(property)]
+    fn active(&self) -> bool {
+        self.active
+    }
+    #[zbus(property)]
+    fn mode(&self) -> String {
+        String::from("slides")
+    }
+}
+
+fn property(bus_name: &str, property: &str) -> DbusProperty {
+    DbusProperty {
+        bus_name: bus_name.into(),
+        path: "/org/example/Presentation".into(),
+        interface: "org.example.Presentation".into(),
+        property: property.into(),
+    }
+}
+
+#[test]
+fn dbus_property() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let (_bus, address) = match private_bus() {
+        Some(bus) => bus,
+        None => return, // Don't fail where there's no D-Bus
+    };
+    let services: Vec<_> = [("org.example.On", true), ("org.example.Off", false)]
+        .iter()
+        .map(|&(name, active)| {
+            connection::Builder::address(&*address)
+                .unwrap()
+                .name(name)
+                .unwrap()
+                .serve_at("/org/example/Presentation", StubPresentation { active })
+                .unwrap()
+                .build()
+                .unwrap()
+        })
+        .collect();
+
+    let module = |property| {
+        let conn = connection::Builder::address(&*address)
+            .unwrap()
+            .build()
+            .unwrap();
+        NotWhenDbusProperty::with_connection(conn, property)
+    };
+    assert!(module(property("org.example.On", "Active")).get());
+    assert!(!module(property("org.example.Off", "Active")).get());
+
+    // Absent services and properties, and other types, count as false
+    assert!(!module(property("org.example.Absent", "Active")).get());
+    assert!(!module(property("org.example.On", "Absent")).get());
+    assert!(!module(property("org.example.On", "Mode")).get());
+
+    // While it's true, timers don't run
+    let mut triggered = 0;
+    let mut timer = Xidlehook::new(vec![CallbackTimer::new(Duration::from_secs(1), || {
+        triggered += 1
+    })])
+    .register(module(property("org.example.On", "Active")));
+    timer.poll(Duration::from_secs(1)).unwrap();
+    drop(timer);
+    assert_eq!(triggered, 0);
+
+    drop(services);
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use common::RecordingTimer;
+use std::{cell::RefCell, time::Duration};
+use xidlehook_core::Xidlehook;
+
+const THRESHOLD: Duration = Duration::from_millis(1000);
+
+/// Poll with these idle times in milliseconds, returning what the timer did
+fn feed(dead_zone: Duration, idle_ms: &[u64]) -> Vec<&'static str> {
+    let log = RefCell::new(Vec::new());
+    let mut timer = Xidlehook::new(vec![RecordingTimer {
+        abortion: Some("cancel"),
+        dead_zone,
+        ..RecordingTimer::new("fire", THRESHOLD, &log)
+    }]);
+    for &idle in idle_ms {
+        timer.poll(Duration::from_millis(idle)).unwrap();
+    }
+    log.into_inner()
+}
+
+#[test]
+fn dead_zones() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let oscillating = [998, 1001, 999, 1002, 997, 1003];
+
+    // Without a dead zone, every dip counts as activity
+    assert_eq!(
+        feed(Duration::default(), &oscillating),
+        ["fire", "cancel", "fire", "cancel", "fire"]
+    );
+
+    // With one, the timer stays fired
+    let dead_zone = Duration::from_millis(10);
+    assert_eq!(feed(dead_zone, &oscillating), ["fire"]);
+
+    // Falling out of the dead zone cancels it, and it only fires again
+    // past the threshold plus the dead zone
+    assert_eq!(
+        feed(dead_zone, &[1001, 985, 1005, 1009, 1011]),
+        ["fire", "cancel", "fire"]
+    );
+    assert_eq!(
+        feed(dead_zone, &[1001, 985, 1005, 1009]),
+        ["fire", "cancel"]
+    );
+}
@@ -0,0 +1,124 @@
+use nix::unistd::{self, Pid};
+use std::{
+    fs,
+    process::{Child, Command},
+    thread,
+    time::Duration,
+};
+use xidlehook_core::{timers::CmdTimer, Xidlehook};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+/// Returns the state of a process, like `Z` for a zombie, or `None`
+/// once it's gone
+fn state(pid: u32) -> Option<char> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The state follows the command name in parentheses
+    stat.rsplit(')').next()?.trim().chars().next()
+}
+
+#[test]
+fn reaped_commands() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let pid_file = std::env::temp_dir().join(format!("xidlehook-reaped-{}", std::process::id()));
+    let mut canceller = Command::new("sh");
+    canceller
+        .arg("-c")
+        .arg(format!("echo $$ > {}", pid_file.display()));
+
+    let mut xidlehook = Xidlehook::new(vec![CmdTimer {
+        time: TEST_UNIT,
+        activation: Some(Command::new("true")),
+        abortion: Some(canceller),
+        ..CmdTimer::default()
+    }]);
+    xidlehook.poll(TEST_UNIT).unwrap();
+    // Being active runs the canceller
+    xidlehook.poll(Duration::default()).unwrap();
+
+    let mut pid = None;
+    for _ in 0..40 {
+        pid = fs::read_to_string(&pid_file)
+            .ok()
+            .and_then(|pid| pid.trim().parse::<u32>().ok());
+        if pid.is_some() {
+            break;
+        }
+        thread::sleep(TEST_UNIT);
+    }
+    let pid = pid.unwrap();
+    // Give it time to exit, after which it's a zombie until reaped
+    thread::sleep(TEST_UNIT * 2);
+
+    // The next poll reaps it
+    xidlehook.poll(Duration::default()).unwrap();
+    assert_eq!(state(pid), None);
+    assert!(xidlehook.timers()[0].children.is_empty());
+
+    fs::remove_file(&pid_file).unwrap();
+}
+
+#[test]
+fn reaped_unreached_commands() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let timer = |units| CmdTimer {
+        time: TEST_UNIT * units,
+        activation: Some(Command::new("true")),
+        ..CmdTimer::default()
+    };
+    let mut xidlehook = Xidlehook::new(vec![timer(10), timer(10), timer(10)]);
+    xidlehook.poll(Duration::default()).unwrap();
+
+    // The last timer's command runs as a test, while the chain is far
+    // from reaching that timer
+    xidlehook.test_activation(2).unwrap();
+    let pid = xidlehook.timers()[2].test_pid.unwrap();
+    thread::sleep(TEST_UNIT * 2);
+
+    // It's still reaped by the next poll
+    xidlehook.poll(TEST_UNIT).unwrap();
+    assert_eq!(state(pid), None);
+    assert!(xidlehook.timers()[2].children.is_empty());
+}
+
+/// Go idle until the timer fires and return its command, dropping
+/// everything else
+fn run_sleep(detached: bool) -> Child {
+    let mut sleep = Command::new("sleep");
+    sleep.arg("10");
+    let mut xidlehook = Xidlehook::new(vec![CmdTimer {
+        time: TEST_UNIT,
+        activation: Some(sleep),
+        detached,
+        ..CmdTimer::default()
+    }]);
+    xidlehook.poll(TEST_UNIT).unwrap();
+    xidlehook.timers_mut().unwrap()[0]
+        .activation_child
+        .take()
+        .unwrap()
+}
+
+#[test]
+fn detached_commands() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // Commands are in xidlehook's process group by default
+    let mut child = run_sleep(false);
+    let pid = Pid::from_raw(child.id() as i32);
+    assert_eq!(unistd::getpgid(Some(pid)).unwrap(), unistd::getpgrp());
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    // Detached, they're in their own, and keep running after the timer
+    // is gone
+    let mut child = run_sleep(true);
+    let pid = Pid::from_raw(child.id() as i32);
+    assert_eq!(unistd::getpgid(Some(pid)).unwrap(), pid);
+    thread::sleep(TEST_UNIT);
+    assert_eq!(state(child.id()), Some('S'));
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
@@ -0,0 +1,78 @@
+use std::{cell::Cell, collections::HashMap, ffi::OsString, time::Duration};
+use xidlehook_core::{
+    sources::{detect::detect_idle_source_with, Backend, ManualIdleSource},
+    IdleSource, Result,
+};
+
+const WAYLAND_IDLE: Duration = Duration::from_secs(1);
+const X_IDLE: Duration = Duration::from_secs(2);
+
+fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<OsString> {
+    let vars: HashMap<String, OsString> = vars
+        .iter()
+        .map(|&(name, value)| (name.to_string(), value.into()))
+        .collect();
+    move |name| vars.get(name).cloned()
+}
+
+/// Which backend was picked, with connections that fail as asked
+fn detect(vars: &[(&str, &str)], wayland_works: bool, x_works: bool) -> Result<Duration> {
+    let connect = |works: bool, idle| -> Result<Box<dyn IdleSource>> {
+        if works {
+            Ok(Box::new(ManualIdleSource::new(idle)))
+        } else {
+            Err("can't connect".into())
+        }
+    };
+    let source = detect_idle_source_with(
+        env(vars),
+        || connect(wayland_works, WAYLAND_IDLE),
+        || connect(x_works, X_IDLE),
+    )?;
+    source.get_idle()
+}
+
+#[test]
+fn detect_idle() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    assert_eq!(
+        Backend::candidates(env(&[("WAYLAND_DISPLAY", "wayland-0"), ("DISPLAY", ":0")])),
+        vec![Backend::Wayland, Backend::X]
+    );
+    assert_eq!(
+        Backend::candidates(env(&[("WAYLAND_DISPLAY", ""), ("DISPLAY", ":0")])),
+        vec![Backend::X]
+    );
+    assert_eq!(Backend::candidates(env(&[])), vec![]);
+
+    // Wayland is preferred, with X under it as a fallback
+    let both = [("WAYLAND_DISPLAY", "wayland-0"), ("DISPLAY", ":0")];
+    assert_eq!(detect(&both, true, true).unwrap(), WAYLAND_IDLE);
+    assert_eq!(detect(&both, false, true).unwrap(), X_IDLE);
+    assert_eq!(detect(&[("DISPLAY", ":0")], true, true).unwrap(), X_IDLE);
+    assert_eq!(
+        detect(&[("WAYLAND_DISPLAY", "wayland-0")], true, true).unwrap(),
+        WAYLAND_IDLE
+    );
+
+    // Backends that aren't in the environment aren't tried
+    let tried = Cell::new(false);
+    let result = detect_idle_source_with(
+        env(&[("DISPLAY", ":0")]),
+        || {
+            tried.set(true);
+            Err("can't connect".into())
+        },
+        || Ok(Box::new(ManualIdleSource::new(X_IDLE))),
+    );
+    assert!(result.is_ok());
+    assert!(!tried.get());
+
+    // The errors say what went wrong
+    let err = detect(&[], true, true).unwrap_err().to_string();
+    assert!(err.contains("WAYLAND_DISPLAY"), "{}", err);
+    let err = detect(&both, false, false).unwrap_err().to_string();
+    assert!(err.contains("wayland: can't connect"), "{}", err);
+    assert!(err.contains("x: can't connect"), "{}", err);
+}
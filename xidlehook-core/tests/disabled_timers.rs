@@ -1,3 +1,6 @@
+// Zero-padded literals (and multiplying by zero) keep the assertions aligned
+#![allow(clippy::zero_prefixed_literal, clippy::erasing_op)]
+
 use std::{cell::Cell, time::Duration};
 use xidlehook_core::{timers::CallbackTimer, Action::*, Xidlehook};
 
@@ -0,0 +1,64 @@
+use std::{cell::Cell, env, rc::Rc, time::Duration};
+use xidlehook_core::{
+    modules::{
+        xcb::{DpmsSource, DpmsState, NotWhenScreenOff},
+        Xcb,
+    },
+    timers::CallbackTimer,
+    Result, Xidlehook,
+};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+struct MockDpms(Cell<DpmsState>);
+impl DpmsSource for MockDpms {
+    fn dpms_state(&self) -> Result<DpmsState> {
+        Ok(self.0.get())
+    }
+}
+
+#[test]
+fn dpms() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    assert_eq!(DpmsState::from_power_level(0), Some(DpmsState::On));
+    assert_eq!(DpmsState::from_power_level(3), Some(DpmsState::Off));
+    assert_eq!(DpmsState::from_power_level(4), None);
+    assert!(DpmsState::Standby.is_asleep());
+    assert!(!DpmsState::Disabled.is_asleep());
+    assert!(!DpmsState::Unsupported.is_asleep());
+
+    // Only the lock is skipped while the monitors are off
+    let dimmed = Cell::new(0);
+    let locked = Cell::new(0);
+    let dpms = Rc::new(MockDpms(Cell::new(DpmsState::Off)));
+    let mut xidlehook = Xidlehook::new(vec![
+        CallbackTimer::new(TEST_UNIT, || dimmed.set(dimmed.get() + 1)),
+        CallbackTimer::new(TEST_UNIT, || locked.set(locked.get() + 1)),
+    ])
+    .register(NotWhenScreenOff::new(Rc::clone(&dpms)).with_timers(vec![1]));
+
+    xidlehook.poll(TEST_UNIT).unwrap();
+    xidlehook.poll(TEST_UNIT * 2).unwrap();
+    assert_eq!((dimmed.get(), locked.get()), (1, 0));
+
+    // ... and runs once they're back on
+    xidlehook.poll(Duration::default()).unwrap();
+    dpms.0.set(DpmsState::On);
+    xidlehook.poll(TEST_UNIT).unwrap();
+    xidlehook.poll(TEST_UNIT * 2).unwrap();
+    assert_eq!((dimmed.get(), locked.get()), (2, 1));
+}
+
+#[test]
+fn xcb_dpms() {
+    // This needs an X server
+    if env::var_os("DISPLAY").is_none() {
+        return;
+    }
+
+    let xcb = Xcb::new().unwrap();
+    // Whatever the server says is fine, as long as it answers
+    let state = xcb.dpms_state().unwrap();
+    assert_eq!(xcb.dpms_state().unwrap(), state);
+}
@@ -0,0 +1,283 @@
+use xidlehook_core::modules::xcb::{
+    glob_matches, FullscreenOptions, Pattern, Rule::*, WindowProperties,
+};
+
+fn window(class: &str, name: &str) -> WindowProperties {
+    WindowProperties {
+        wm_class: vec![class.to_lowercase(), class.into()],
+        wm_name: name.into(),
+        fullscreen: true,
+        wm_state: Some(1), // NormalState
+        desktop: None,
+        ..WindowProperties::default()
+    }
+}
+
+#[test]
+fn exception_rules() {
+    let firefox = window("Firefox", "YouTube - Mozilla Firefox");
+    let private = window("Firefox", "YouTube - Mozilla Firefox Private Browsing");
+    let mpv = window("mpv", "movie.mkv");
+
+    // Firefox windows, except private ones
+    let rule = And(vec![
+        Class("Firefox".into()),
+        Not(Box::new(NameContains("Private Browsing".into()))),
+    ]);
+    assert!(rule.matches(&firefox));
+    assert!(!rule.matches(&private));
+    assert!(!rule.matches(&mpv));
+
+    // ... or mpv
+    let rule = Or(vec![rule, Instance("mpv".into())]);
+    assert!(rule.matches(&firefox));
+    assert!(!rule.matches(&private));
+    assert!(rule.matches(&mpv));
+
+    // Empty rules
+    assert!(And(vec![]).matches(&mpv));
+    assert!(!Or(vec![]).matches(&mpv));
+
+    // Rules are used as exceptions
+    let options = FullscreenOptions {
+        exceptions: vec![rule],
+        ..FullscreenOptions::default()
+    };
+    assert!(!firefox.is_fullscreen(None, &options));
+    assert!(private.is_fullscreen(None, &options));
+    assert!(!mpv.is_fullscreen(None, &options));
+}
+
+#[test]
+fn flat_exceptions_as_rules() {
+    let options = FullscreenOptions {
+        exceptions_wm_class1: vec!["mpv".into()],
+        exceptions_wm_class2: vec!["Firefox".into()],
+        exceptions_wm_name: vec!["Presentation".into()],
+        exceptions: vec![Name("Slides".into())],
+        ..FullscreenOptions::default()
+    };
+    assert_eq!(
+        options.exception_rule(),
+        Or(vec![
+            Instance("mpv".into()),
+            Class("Firefox".into()),
+            Name("Presentation".into()),
+            Name("Slides".into()),
+        ])
+    );
+
+    // The flat lists behave just like the rule they compile to
+    let rule = options.exception_rule();
+    for window in &[
+        window("mpv", "movie.mkv"),
+        window("Firefox", "YouTube"),
+        window("Okular", "Presentation"),
+        window("Okular", "Slides"),
+        window("Okular", "paper.pdf"),
+    ] {
+        assert_eq!(window.is_exception(&options), rule.matches(window));
+    }
+}
+
+#[test]
+fn role_exceptions() {
+    let browser = WindowProperties {
+        wm_role: Some("browser".into()),
+        ..window("Firefox", "YouTube - Mozilla Firefox")
+    };
+    let popup = WindowProperties {
+        wm_role: Some("Popup".into()),
+        ..window("Firefox", "Picture-in-Picture")
+    };
+    let mpv = window("mpv", "movie.mkv");
+
+    let options = FullscreenOptions {
+        exceptions_wm_role: vec!["browser".into()],
+        ..FullscreenOptions::default()
+    };
+    assert!(!browser.is_fullscreen(None, &options));
+    assert!(popup.is_fullscreen(None, &options));
+    // Windows without a role are unaffected
+    assert!(mpv.is_fullscreen(None, &options));
+
+    let rule = options.exception_rule();
+    assert_eq!(rule, Or(vec![Role("browser".into())]));
+    for window in &[browser, popup, mpv] {
+        assert_eq!(window.is_exception(&options), rule.matches(window));
+    }
+}
+
+#[test]
+fn pattern_exceptions() {
+    let game = window("steam_app_570", "Dota 2");
+    let other = window("steam_app_1091500", "Cyberpunk 2077");
+    let client = window("Steam", "Steam");
+    let pattern = |pattern| Pattern::new(pattern).unwrap();
+
+    let rule = ClassMatches(pattern("^steam_app_"));
+    assert!(rule.matches(&game));
+    assert!(rule.matches(&other));
+    assert!(!rule.matches(&client));
+
+    // Patterns aren't anchored unless asked to
+    assert!(InstanceMatches(pattern("app_")).matches(&game));
+    assert!(!InstanceMatches(pattern("^app_")).matches(&game));
+    assert!(NameMatches(pattern(r"\d{4}$")).matches(&other));
+    assert!(!RoleMatches(pattern(".*")).matches(&game));
+
+    assert!(Pattern::new("steam_app_(").is_err());
+    assert_eq!(pattern("^steam").as_str(), "^steam");
+    assert_eq!(pattern("^steam"), pattern("^steam"));
+
+    let options = FullscreenOptions {
+        exceptions: vec![rule],
+        ..FullscreenOptions::default()
+    };
+    assert!(!game.is_fullscreen(None, &options));
+    assert!(client.is_fullscreen(None, &options));
+}
+
+#[test]
+fn case_insensitive_exceptions() {
+    let firefox = WindowProperties {
+        wm_role: Some("Browser".into()),
+        ..window("Firefox", "YouTube - Mozilla Firefox")
+    };
+    let mpv = window("MPV", "Movie.mkv");
+    let okular = window("Okular", "paper.pdf");
+
+    let mut options = FullscreenOptions {
+        exceptions_wm_class1: vec!["Mpv".into()],
+        exceptions_wm_class2: vec!["firefox".into()],
+        exceptions_wm_name: vec!["PAPER.PDF".into()],
+        ..FullscreenOptions::default()
+    };
+    assert!(!firefox.is_exception(&options));
+    assert!(!mpv.is_exception(&options));
+    assert!(!okular.is_exception(&options));
+
+    options.case_insensitive = true;
+    assert!(firefox.is_exception(&options));
+    assert!(mpv.is_exception(&options));
+    assert!(okular.is_exception(&options));
+
+    // Roles too, but only windows that have one
+    let options = FullscreenOptions {
+        exceptions_wm_role: vec!["browser".into()],
+        case_insensitive: true,
+        ..FullscreenOptions::default()
+    };
+    assert!(firefox.is_exception(&options));
+    assert!(!mpv.is_exception(&options));
+
+    // Still exact other than case, and the same as the rule
+    let options = FullscreenOptions {
+        exceptions_wm_class2: vec!["fire".into(), "OKULAR".into()],
+        exceptions_wm_name: vec!["movie.MKV".into()],
+        case_insensitive: true,
+        ..FullscreenOptions::default()
+    };
+    let rule = options.exception_rule();
+    for (window, exception) in &[(&firefox, false), (&mpv, true), (&okular, true)] {
+        assert_eq!(window.is_exception(&options), *exception);
+        assert_eq!(rule.matches(window), *exception);
+    }
+}
+
+#[test]
+fn pid_exceptions() {
+    // This test's own process stands in for a video player
+    let own = WindowProperties {
+        pid: Some(std::process::id()),
+        ..window("mpv", "movie.mkv")
+    };
+    let other = WindowProperties {
+        pid: Some(1),
+        ..window("mpv", "movie.mkv")
+    };
+    let unknown = window("mpv", "movie.mkv");
+    let name = std::fs::read_to_string("/proc/self/comm").unwrap();
+    let name = name.trim_end().to_owned();
+    assert_eq!(own.process_name(), Some(name.clone()));
+    assert_eq!(unknown.process_name(), None);
+
+    let options = FullscreenOptions {
+        exceptions_pid: vec![std::process::id()],
+        ..FullscreenOptions::default()
+    };
+    assert!(!own.is_fullscreen(None, &options));
+    assert!(other.is_fullscreen(None, &options));
+    assert!(unknown.is_fullscreen(None, &options));
+
+    let options = FullscreenOptions {
+        exceptions_process: vec![name.clone()],
+        ..FullscreenOptions::default()
+    };
+    assert!(own.is_exception(&options));
+    assert!(!unknown.is_exception(&options));
+
+    let options = FullscreenOptions {
+        exceptions_pid: vec![1],
+        exceptions_process: vec![name.clone()],
+        ..FullscreenOptions::default()
+    };
+    let rule = options.exception_rule();
+    assert_eq!(rule, Or(vec![Pid(1), Process(name)]));
+    for window in &[own, other, unknown] {
+        assert_eq!(window.is_exception(&options), rule.matches(window));
+    }
+}
+
+#[test]
+fn glob_exceptions() {
+    assert!(glob_matches("Gimp*", "Gimp-2.10"));
+    assert!(glob_matches("Gimp*", "Gimp"));
+    assert!(glob_matches("*-2.??", "Gimp-2.10"));
+    assert!(glob_matches("G*p*1*", "Gimp-2.10"));
+    assert!(!glob_matches("Gimp?", "Gimp"));
+    assert!(!glob_matches("*Gimp", "Gimp-2.10"));
+    assert!(!glob_matches("Gimp", "Gimp-2.10"));
+    assert!(glob_matches("", ""));
+
+    let gimp = window("Gimp-2.10", "GNU Image Manipulation Program");
+    let gimp_old = window("Gimp-2.8", "GNU Image Manipulation Program");
+    let gimpy = window("Gimpy", "Gimpy");
+    let options = FullscreenOptions {
+        exceptions_wm_class2: vec!["Gimp*".into()],
+        exceptions_wm_name: vec!["Gimp".into()],
+        ..FullscreenOptions::default()
+    };
+    for window in &[&gimp, &gimp_old, &gimpy] {
+        assert!(window.is_exception(&options));
+    }
+    assert_eq!(
+        options.exception_rule(),
+        Or(vec![
+            ClassMatches(Pattern::glob("Gimp*", false)),
+            Name("Gimp".into()),
+        ])
+    );
+
+    // Literal entries are still compared as a whole
+    let options = FullscreenOptions {
+        exceptions_wm_class2: vec!["Gimp".into(), "gimp-2.?".into()],
+        ..FullscreenOptions::default()
+    };
+    assert!(!gimp.is_exception(&options));
+    assert!(!gimp_old.is_exception(&options));
+
+    let options = FullscreenOptions {
+        case_insensitive: true,
+        ..options
+    };
+    assert!(!gimp.is_exception(&options));
+    assert!(gimp_old.is_exception(&options));
+
+    for window in &[gimp, gimp_old, gimpy] {
+        assert_eq!(
+            window.is_exception(&options),
+            options.exception_rule().matches(window)
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    thread,
+    time::Duration,
+};
+use xidlehook_core::{sources::FallbackIdleSource, IdleSource, Result};
+
+/// Returns a fixed idle time, or fails while it's broken
+struct Backend {
+    idle: Duration,
+    broken: Rc<RefCell<bool>>,
+}
+impl IdleSource for Backend {
+    fn get_idle(&self) -> Result<Duration> {
+        if *self.broken.borrow() {
+            return Err("stuck".into());
+        }
+        Ok(self.idle)
+    }
+}
+
+#[test]
+fn fallback_idle() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let switches = Rc::new(RefCell::new(Vec::new()));
+    let xcb_broken = Rc::new(RefCell::new(false));
+    let mutter_broken = Rc::new(RefCell::new(false));
+    let source = FallbackIdleSource::new()
+        .with(
+            "xcb",
+            Backend {
+                idle: Duration::from_secs(1),
+                broken: Rc::clone(&xcb_broken),
+            },
+        )
+        .with(
+            "mutter",
+            Backend {
+                idle: Duration::from_secs(2),
+                broken: Rc::clone(&mutter_broken),
+            },
+        )
+        .with_on_backend_switch({
+            let switches = Rc::clone(&switches);
+            move |from: &str, to: &str| switches.borrow_mut().push((from.to_owned(), to.to_owned()))
+        });
+
+    assert_eq!(source.active_name(), Some("xcb"));
+    assert_eq!(source.get_idle().unwrap(), Duration::from_secs(1));
+    assert!(switches.borrow().is_empty());
+
+    // The first backend gets stuck, so the second takes over, once
+    *xcb_broken.borrow_mut() = true;
+    assert_eq!(source.get_idle().unwrap(), Duration::from_secs(2));
+    assert_eq!(source.get_idle().unwrap(), Duration::from_secs(2));
+    assert_eq!(source.active_name(), Some("mutter"));
+    assert_eq!(
+        *switches.borrow(),
+        vec![(String::from("xcb"), String::from("mutter"))]
+    );
+
+    // It doesn't go back just because the first one works again
+    *xcb_broken.borrow_mut() = false;
+    assert_eq!(source.get_idle().unwrap(), Duration::from_secs(2));
+    assert_eq!(switches.borrow().len(), 1);
+
+    // When every backend fails, the error of the active one is kept
+    *xcb_broken.borrow_mut() = true;
+    *mutter_broken.borrow_mut() = true;
+    assert!(source.get_idle().is_err());
+    assert_eq!(source.active_name(), Some("mutter"));
+    assert_eq!(switches.borrow().len(), 1);
+
+    assert!(FallbackIdleSource::new().get_idle().is_err());
+}
+
+/// Like `Backend`, but counts how often it's asked
+struct Counted {
+    idle: Duration,
+    broken: Rc<Cell<bool>>,
+    calls: Rc<Cell<usize>>,
+}
+impl IdleSource for Counted {
+    fn get_idle(&self) -> Result<Duration> {
+        self.calls.set(self.calls.get() + 1);
+        if self.broken.get() {
+            return Err("stuck".into());
+        }
+        Ok(self.idle)
+    }
+}
+
+#[test]
+fn fallback_idle_retry() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let retry_after = Duration::from_millis(100);
+    let broken = Rc::new(Cell::new(false));
+    let calls = Rc::new(Cell::new(0));
+    let source = FallbackIdleSource::new()
+        .with(
+            "wayland",
+            Counted {
+                idle: Duration::from_secs(1),
+                broken: Rc::clone(&broken),
+                calls: Rc::clone(&calls),
+            },
+        )
+        .with(
+            "xcb",
+            Backend {
+                idle: Duration::from_secs(2),
+                broken: Rc::new(RefCell::new(false)),
+            },
+        )
+        .with_retry_after(retry_after);
+    assert_eq!(source.retry_after(), Some(retry_after));
+
+    // The primary fails, so the secondary takes over
+    broken.set(true);
+    assert_eq!(source.get_idle().unwrap(), Duration::from_secs(2));
+    assert_eq!(source.active_name(), Some("xcb"));
+    assert_eq!(calls.get(), 1);
+
+    // ... and the primary isn't asked again until the cooldown passed
+    assert_eq!(source.get_idle().unwrap(), Duration::from_secs(2));
+    assert_eq!(calls.get(), 1);
+    thread::sleep(retry_after);
+    assert_eq!(source.get_idle().unwrap(), Duration::from_secs(2));
+    assert_eq!(calls.get(), 2);
+
+    // Once it works again, it's switched back to
+    broken.set(false);
+    thread::sleep(retry_after);
+    assert_eq!(source.get_idle().unwrap(), Duration::from_secs(1));
+    assert_eq!(source.active_name(), Some("wayland"));
+}
@@ -0,0 +1,77 @@
+use std::{cell::Cell, rc::Rc, time::Duration};
+use xidlehook_core::{
+    timers::CallbackTimer, Action, Module, Progress, Result, TimerInfo, Xidlehook,
+};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+/// Runs the next timer once asked to, like a "lock now" command, but
+/// otherwise keeps timers from running
+struct LockNow {
+    asked: Rc<Cell<bool>>,
+}
+impl Module for LockNow {
+    fn pre_timer(&mut self, _timer: TimerInfo) -> Result<Progress> {
+        Ok(Progress::Abort)
+    }
+    fn poll_timer(&mut self, _timer: TimerInfo) -> Result<Progress> {
+        if self.asked.replace(false) {
+            Ok(Progress::Trigger)
+        } else {
+            Ok(Progress::Continue)
+        }
+    }
+}
+
+/// Stops as soon as it's asked
+struct Quit;
+impl Module for Quit {
+    fn poll_timer(&mut self, _timer: TimerInfo) -> Result<Progress> {
+        Ok(Progress::Stop)
+    }
+}
+
+#[test]
+fn forced_timers() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let fired = Rc::new(Cell::new(0));
+    let timers = || {
+        let fired = Rc::clone(&fired);
+        vec![
+            CallbackTimer::new(TEST_UNIT * 10, move || fired.set(fired.get() + 1)),
+            CallbackTimer::new(TEST_UNIT * 10, || ()),
+        ]
+    };
+    let asked = Rc::new(Cell::new(false));
+    let lock_now = || LockNow {
+        asked: Rc::clone(&asked),
+    };
+    let mut xidlehook = Xidlehook::new(timers()).register(lock_now());
+
+    // Nothing is forced until asked
+    assert!(matches!(
+        xidlehook.poll(TEST_UNIT).unwrap(),
+        Action::Sleep(_)
+    ));
+    assert_eq!(fired.get(), 0);
+
+    // Then the next timer runs right away, even though `pre_timer` would
+    // keep it from running, and only once
+    asked.set(true);
+    xidlehook.poll(TEST_UNIT * 2).unwrap();
+    assert_eq!(fired.get(), 1);
+    xidlehook.poll(TEST_UNIT * 3).unwrap();
+    assert_eq!(xidlehook.next_index(), 1);
+
+    // Earlier modules win, so stopping ahead of it quits...
+    asked.set(true);
+    let mut xidlehook = Xidlehook::new(timers()).register((Quit, lock_now()));
+    assert!(matches!(xidlehook.poll(TEST_UNIT).unwrap(), Action::Quit));
+    assert_eq!(fired.get(), 1);
+
+    // ... while forcing ahead of it runs the timer
+    let mut xidlehook = Xidlehook::new(timers()).register((lock_now(), Quit));
+    assert!(matches!(xidlehook.poll(TEST_UNIT).unwrap(), Action::Quit));
+    assert_eq!(fired.get(), 2);
+}
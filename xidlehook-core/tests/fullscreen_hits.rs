@@ -0,0 +1,62 @@
+use xidlehook_core::modules::xcb::{
+    fullscreen_hits, FullscreenHit, FullscreenOptions, WindowProperties,
+};
+
+fn window(class: &str, name: &str, fullscreen: bool) -> WindowProperties {
+    WindowProperties {
+        wm_class: vec![class.to_lowercase(), class.into()],
+        wm_name: name.into(),
+        fullscreen,
+        wm_state: Some(1), // NormalState
+        ..WindowProperties::default()
+    }
+}
+
+#[test]
+fn fullscreen_hits_all() {
+    let windows = vec![
+        (0x10, window("mpv", "movie.mkv - mpv", true)),
+        (0x20, window("Alacritty", "~", false)),
+        (
+            0x30,
+            WindowProperties {
+                desktop: Some(2),
+                ..window("Firefox", "YouTube - Mozilla Firefox", true)
+            },
+        ),
+        (
+            0x40,
+            WindowProperties {
+                desktop: Some(1),
+                ..window("Gimp", "GNU Image Manipulation Program", true)
+            },
+        ),
+    ];
+
+    let hits = fullscreen_hits(1, windows, &[2], &FullscreenOptions::default());
+    assert_eq!(
+        hits,
+        [
+            FullscreenHit {
+                window: 0x10,
+                screen: 1,
+                wm_class: vec!["mpv".into(), "mpv".into()],
+                wm_name: "movie.mkv - mpv".into(),
+                desktop: None,
+                pid: None,
+            },
+            FullscreenHit {
+                window: 0x30,
+                screen: 1,
+                wm_class: vec!["firefox".into(), "Firefox".into()],
+                wm_name: "YouTube - Mozilla Firefox".into(),
+                desktop: Some(2),
+                pid: None,
+            },
+        ]
+    );
+
+    // Nothing fullscreen, nothing reported
+    let windows = vec![(0x20, window("Alacritty", "~", false))];
+    assert!(fullscreen_hits(0, windows, &[], &FullscreenOptions::default()).is_empty());
+}
@@ -0,0 +1,130 @@
+use std::{
+    cell::Cell,
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+    rc::Rc,
+    thread,
+    time::Duration,
+};
+use xidlehook_core::{
+    modules::xcb::{FullscreenHook, FullscreenOptions, FullscreenSource},
+    Module, Progress, Result, TimerInfo,
+};
+
+const INFO: TimerInfo = TimerInfo {
+    index: 0,
+    length: 1,
+};
+
+struct MockFullscreen(Cell<bool>);
+impl FullscreenSource for MockFullscreen {
+    fn is_fullscreen(&self, _options: &FullscreenOptions) -> Result<bool> {
+        Ok(self.0.get())
+    }
+}
+
+fn log_file(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("xidlehook-test-{}-{}", name, std::process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+/// A command appending this line to the file
+fn append(path: &Path, line: &str) -> Command {
+    let mut cmd = Command::new("/bin/sh");
+    cmd.arg("-c")
+        .arg(format!("echo {} >> \"$0\"", line))
+        .arg(path);
+    cmd
+}
+
+/// Wait for the commands to have written this many lines
+fn lines(path: &Path, expected: usize) -> Vec<String> {
+    for _ in 0..100 {
+        let lines: Vec<String> = fs::read_to_string(path)
+            .unwrap_or_default()
+            .lines()
+            .map(String::from)
+            .collect();
+        if lines.len() >= expected {
+            return lines;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    panic!("the commands didn't run");
+}
+
+#[test]
+fn fullscreen_hook() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let path = log_file("fullscreen-hook");
+    let source = Rc::new(MockFullscreen(Cell::new(true)));
+    let mut hook = FullscreenHook::new(Rc::clone(&source), FullscreenOptions::default())
+        .with_on_enter(append(&path, "enter"))
+        .with_on_exit(append(&path, "exit"))
+        .with_debounce(Duration::default());
+
+    // Being fullscreen at first isn't a change
+    assert_eq!(hook.is_fullscreen(), None);
+    assert_eq!(hook.pre_timer(INFO).unwrap(), Progress::Continue);
+    assert_eq!(hook.is_fullscreen(), Some(true));
+    hook.check().unwrap();
+
+    source.0.set(false);
+    hook.check().unwrap();
+    assert_eq!(hook.is_fullscreen(), Some(false));
+    assert_eq!(lines(&path, 1), ["exit"]);
+    hook.check().unwrap();
+
+    source.0.set(true);
+    hook.check().unwrap();
+    assert_eq!(lines(&path, 2), ["exit", "enter"]);
+
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(lines(&path, 2).len(), 2);
+
+    assert_eq!(hook.describe().kind, "fullscreen-hook");
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn debounced_fullscreen_hook() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let path = log_file("debounced-fullscreen-hook");
+    let source = Rc::new(MockFullscreen(Cell::new(false)));
+    let mut hook = FullscreenHook::new(Rc::clone(&source), FullscreenOptions::default())
+        .with_on_enter(append(&path, "enter"))
+        .with_debounce(Duration::from_millis(100));
+    hook.check().unwrap();
+
+    // Flapping doesn't run anything
+    for _ in 0..10 {
+        source.0.set(!source.0.get());
+        hook.check().unwrap();
+        thread::sleep(Duration::from_millis(20));
+    }
+    assert_eq!(hook.is_fullscreen(), Some(false));
+
+    // A change which lasts does
+    source.0.set(true);
+    hook.check().unwrap();
+    assert_eq!(hook.is_fullscreen(), Some(false));
+    thread::sleep(Duration::from_millis(100));
+    hook.check().unwrap();
+    assert_eq!(hook.is_fullscreen(), Some(true));
+    assert_eq!(lines(&path, 1), ["enter"]);
+
+    // Leaving has no command, which is fine
+    source.0.set(false);
+    thread::sleep(Duration::from_millis(100));
+    hook.check().unwrap();
+    hook.check().unwrap();
+    thread::sleep(Duration::from_millis(100));
+    hook.check().unwrap();
+    assert_eq!(hook.is_fullscreen(), Some(false));
+    assert_eq!(lines(&path, 1), ["enter"]);
+    let _ = fs::remove_file(&path);
+}
@@ -0,0 +1,54 @@
+use xidlehook_core::modules::xcb::{is_on_monitors, FullscreenOptions, Monitor, Rect};
+
+fn monitor(name: &str, x: i32, width: u32) -> Monitor {
+    Monitor {
+        name: name.into(),
+        rect: Rect {
+            x,
+            y: 0,
+            width,
+            height: 1080,
+        },
+    }
+}
+
+#[test]
+fn fullscreen_monitors() {
+    let monitors = [monitor("eDP-1", 0, 1920), monitor("HDMI-1", 1920, 3840)];
+    let tv = vec![String::from("HDMI-1")];
+    let on_laptop = Rect {
+        x: 0,
+        y: 0,
+        width: 1920,
+        height: 1080,
+    };
+    let on_tv = Rect {
+        x: 1920,
+        ..on_laptop
+    };
+    assert!(!is_on_monitors(on_laptop, Some(&monitors), &tv));
+    assert!(is_on_monitors(on_tv, Some(&monitors), &tv));
+
+    // A window spanning both is on the one showing its center
+    let spanning = Rect {
+        x: 1000,
+        width: 1920,
+        ..on_laptop
+    };
+    assert!(is_on_monitors(spanning, Some(&monitors), &tv));
+
+    // Outputs that aren't showing anything, or unknown names, match
+    // nothing
+    assert!(!is_on_monitors(on_tv, Some(&[]), &tv));
+    assert!(!is_on_monitors(
+        on_tv,
+        Some(&monitors),
+        &[String::from("DP-2")]
+    ));
+
+    // Without RandR, every window is on every monitor
+    assert!(is_on_monitors(on_laptop, None, &tv));
+
+    // All monitors count unless configured otherwise
+    assert_eq!(FullscreenOptions::default().monitors, None);
+}
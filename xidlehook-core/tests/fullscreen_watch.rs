@@ -0,0 +1,78 @@
+use std::cell::Cell;
+use xidlehook_core::modules::xcb::FullscreenWatch;
+
+const NET_WM_STATE: xcb::Atom = 300;
+const FULLSCREEN: xcb::Atom = 301;
+const ABOVE: xcb::Atom = 302;
+
+fn notify(window: xcb::Window, atom: xcb::Atom, state: u32) -> xcb::PropertyNotifyEvent {
+    xcb::PropertyNotifyEvent::new(window, atom, xcb::CURRENT_TIME, state as u8)
+}
+
+#[test]
+fn fullscreen_watch() {
+    let watch = FullscreenWatch::new(NET_WM_STATE, FULLSCREEN);
+    let handler = watch.clone();
+    watch.insert(1);
+    watch.insert(2);
+    assert!(handler.is_fullscreen());
+
+    // Unrelated properties and windows aren't even looked up
+    let asked = Cell::new(0);
+    let states = |states: Vec<xcb::Atom>| {
+        let asked = &asked;
+        move |_| {
+            asked.set(asked.get() + 1);
+            Ok(Some(states))
+        }
+    };
+    let new_value = xcb::xproto::PROPERTY_NEW_VALUE;
+    let event = notify(1, xcb::xproto::ATOM_WM_NAME, new_value);
+    assert!(!handler
+        .handle_property_notify(&event, states(vec![]))
+        .unwrap());
+    let event = notify(3, NET_WM_STATE, new_value);
+    assert!(!handler
+        .handle_property_notify(&event, states(vec![]))
+        .unwrap());
+    assert_eq!(asked.get(), 0);
+    assert_eq!(handler.len(), 2);
+
+    // Still fullscreen, with some other state added
+    let event = notify(1, NET_WM_STATE, new_value);
+    let still = states(vec![ABOVE, FULLSCREEN]);
+    assert!(!handler.handle_property_notify(&event, still).unwrap());
+    assert_eq!(handler.len(), 2);
+
+    // One window leaves fullscreen, but another is still in it
+    let event = notify(1, NET_WM_STATE, new_value);
+    assert!(!handler
+        .handle_property_notify(&event, states(vec![ABOVE]))
+        .unwrap());
+    assert_eq!(handler.len(), 1);
+    assert!(handler.is_fullscreen());
+
+    // The last fullscreen window removes the atom
+    let event = notify(2, NET_WM_STATE, new_value);
+    assert!(handler
+        .handle_property_notify(&event, states(vec![]))
+        .unwrap());
+    assert!(!handler.is_fullscreen());
+    assert_eq!(asked.get(), 3);
+
+    // Deleting the property or destroying the window ends it too
+    watch.insert(4);
+    let event = notify(4, NET_WM_STATE, xcb::xproto::PROPERTY_DELETE);
+    assert!(handler
+        .handle_property_notify(&event, states(vec![FULLSCREEN]))
+        .unwrap());
+    assert_eq!(asked.get(), 3);
+    watch.insert(5);
+    let event = notify(5, NET_WM_STATE, new_value);
+    assert!(handler
+        .handle_property_notify(&event, |_| Ok(None))
+        .unwrap());
+    watch.insert(6);
+    assert!(handler.forget(6));
+    assert!(!handler.forget(6));
+}
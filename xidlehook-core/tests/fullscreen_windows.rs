@@ -0,0 +1,594 @@
+use xidlehook_core::{
+    modules::xcb::{
+        is_fullscreen_through_modals, parse_wm_class, resolve_client_window, FullscreenOptions,
+        WindowProperties, WindowSearch, WindowTree, STICKY_DESKTOP,
+    },
+    Result,
+};
+
+#[test]
+fn fullscreen_windows() {
+    let options = FullscreenOptions::default();
+    let window = WindowProperties {
+        fullscreen: true,
+        wm_state: Some(1), // NormalState
+        desktop: Some(2),
+        ..WindowProperties::default()
+    };
+    assert!(window.is_fullscreen(Some(2), &options));
+    assert!(window.is_fullscreen(None, &options));
+
+    // Only windows on the current desktop count
+    assert!(!window.is_fullscreen(Some(0), &options));
+
+    // Withdrawn windows and windows without WM_STATE don't count
+    for &wm_state in &[Some(0), None] {
+        assert!(!WindowProperties {
+            wm_state,
+            ..window.clone()
+        }
+        .is_fullscreen(Some(2), &options));
+    }
+
+    // Nor do minimized windows, unless asked to
+    let iconic = WindowProperties {
+        wm_state: Some(3), // IconicState
+        ..window.clone()
+    };
+    assert!(!iconic.is_fullscreen(Some(2), &options));
+    assert!(iconic.is_fullscreen(
+        Some(2),
+        &FullscreenOptions {
+            count_iconic: true,
+            ..FullscreenOptions::default()
+        }
+    ));
+
+    // Neither do windows that aren't fullscreen, obviously
+    assert!(!WindowProperties {
+        fullscreen: false,
+        ..window.clone()
+    }
+    .is_fullscreen(Some(2), &options));
+
+    // A window without a desktop is assumed to be on the current one
+    assert!(WindowProperties {
+        desktop: None,
+        ..window.clone()
+    }
+    .is_fullscreen(Some(2), &options));
+}
+
+#[test]
+fn per_monitor_desktops() {
+    let options = FullscreenOptions::default();
+    let window = WindowProperties {
+        fullscreen: true,
+        wm_state: Some(1), // NormalState
+        desktop: Some(4),
+        ..WindowProperties::default()
+    };
+
+    // With a workspace per monitor, a window on any of them counts
+    assert!(window.is_fullscreen_on(&[1, 4], &options));
+    assert!(window.is_fullscreen_on(&[4, 7, 9], &options));
+    assert!(!window.is_fullscreen_on(&[1, 7, 9], &options));
+
+    // No current desktop means it's unknown
+    assert!(window.is_fullscreen_on(&[], &options));
+
+    let sticky = WindowProperties {
+        desktop: Some(STICKY_DESKTOP),
+        ..window.clone()
+    };
+    assert!(sticky.is_fullscreen_on(&[1, 7], &options));
+    let ignore_sticky = FullscreenOptions {
+        ignore_sticky_windows: true,
+        ..FullscreenOptions::default()
+    };
+    assert!(!sticky.is_fullscreen_on(&[1, 7], &ignore_sticky));
+}
+
+#[test]
+fn sticky_fullscreen_windows() {
+    let sticky = WindowProperties {
+        fullscreen: true,
+        wm_state: Some(1), // NormalState
+        desktop: Some(STICKY_DESKTOP),
+        ..WindowProperties::default()
+    };
+
+    // Sticky windows are on every desktop
+    for &current in &[Some(0), Some(3), None] {
+        assert!(sticky.is_fullscreen(current, &FullscreenOptions::default()));
+    }
+
+    // ... unless that's opted out of
+    let options = FullscreenOptions {
+        ignore_sticky_windows: true,
+        ..FullscreenOptions::default()
+    };
+    assert!(!sticky.is_fullscreen(Some(0), &options));
+}
+
+#[test]
+fn fullscreen_on_any_desktop() {
+    let elsewhere = WindowProperties {
+        fullscreen: true,
+        wm_state: Some(1), // NormalState
+        desktop: Some(2),
+        ..WindowProperties::default()
+    };
+    assert!(!elsewhere.is_fullscreen(Some(0), &FullscreenOptions::default()));
+    assert!(!elsewhere.is_fullscreen_on(&[0, 1], &FullscreenOptions::default()));
+
+    // Windows on other desktops count if asked for
+    let options = FullscreenOptions {
+        ignore_desktop_match: true,
+        ..FullscreenOptions::default()
+    };
+    assert!(elsewhere.is_fullscreen(Some(0), &options));
+    assert!(elsewhere.is_fullscreen_on(&[0, 1], &options));
+
+    // It still has to be fullscreen
+    let windowed = WindowProperties {
+        fullscreen: false,
+        ..elsewhere
+    };
+    assert!(!windowed.is_fullscreen(Some(2), &options));
+}
+
+#[test]
+fn fullscreen_exceptions() {
+    let window = WindowProperties {
+        wm_class: vec!["mpv".into(), "mpv".into()],
+        wm_name: "movie.mkv - mpv".into(),
+        fullscreen: true,
+        wm_state: Some(1), // NormalState
+        desktop: None,
+        ..WindowProperties::default()
+    };
+    assert!(window.is_fullscreen(None, &FullscreenOptions::default()));
+
+    let excepted = |options: FullscreenOptions| !window.is_fullscreen(None, &options);
+    assert!(excepted(FullscreenOptions {
+        exceptions_wm_class1: vec!["firefox".into(), "mpv".into()],
+        ..FullscreenOptions::default()
+    }));
+    assert!(excepted(FullscreenOptions {
+        exceptions_wm_class2: vec!["mpv".into()],
+        ..FullscreenOptions::default()
+    }));
+    assert!(excepted(FullscreenOptions {
+        exceptions_wm_name: vec!["movie.mkv - mpv".into()],
+        ..FullscreenOptions::default()
+    }));
+
+    // Exceptions match entire values
+    assert!(!excepted(FullscreenOptions {
+        exceptions_wm_name: vec!["mpv".into()],
+        ..FullscreenOptions::default()
+    }));
+}
+
+#[test]
+fn forced_fullscreen() {
+    // An emulator that covers the screen without saying so
+    let emulator = WindowProperties {
+        wm_class: vec!["retroarch".into(), "RetroArch".into()],
+        wm_name: "RetroArch".into(),
+        fullscreen: false,
+        wm_state: Some(1), // NormalState
+        desktop: Some(0),
+        ..WindowProperties::default()
+    };
+    assert!(!emulator.is_fullscreen(Some(0), &FullscreenOptions::default()));
+
+    // Either part of WM_CLASS can be forced
+    for class in &["retroarch", "RetroArch"] {
+        let options = FullscreenOptions {
+            force_fullscreen_classes: vec![(*class).into()],
+            ..FullscreenOptions::default()
+        };
+        assert!(emulator.is_forced_fullscreen(&options));
+        assert!(emulator.is_fullscreen(Some(0), &options));
+
+        // ... but it must still be mapped and on the current desktop
+        assert!(!emulator.is_fullscreen(Some(1), &options));
+        let unmapped = WindowProperties {
+            wm_state: None,
+            ..emulator.clone()
+        };
+        assert!(!unmapped.is_fullscreen(Some(0), &options));
+    }
+
+    // Exceptions still apply
+    let options = FullscreenOptions {
+        force_fullscreen_classes: vec!["RetroArch".into()],
+        exceptions_wm_name: vec!["RetroArch".into()],
+        ..FullscreenOptions::default()
+    };
+    assert!(!emulator.is_fullscreen(Some(0), &options));
+}
+
+struct MockTree {
+    /// (window, parent, is client)
+    windows: Vec<(u32, Option<u32>, bool)>,
+}
+impl WindowTree for MockTree {
+    fn is_client(&self, window: u32) -> Result<bool> {
+        Ok(self
+            .windows
+            .iter()
+            .any(|&(w, _, client)| w == window && client))
+    }
+    fn parent(&self, window: u32) -> Result<Option<u32>> {
+        Ok(self
+            .windows
+            .iter()
+            .find(|&&(w, _, _)| w == window)
+            .and_then(|&(_, parent, _)| parent))
+    }
+}
+
+#[test]
+fn client_windows() {
+    // root (1) -> frame (2) -> client (3) -> focused child (4)
+    let tree = MockTree {
+        windows: vec![
+            (1, None, false),
+            (2, Some(1), false),
+            (3, Some(2), true),
+            (4, Some(3), false),
+            (5, Some(1), false),
+        ],
+    };
+
+    // The client window is found by walking up the parents
+    assert_eq!(resolve_client_window(&tree, 4).unwrap(), 3);
+    assert_eq!(resolve_client_window(&tree, 3).unwrap(), 3);
+
+    // Without a client window anywhere up the chain, the window itself is used
+    assert_eq!(resolve_client_window(&tree, 5).unwrap(), 5);
+}
+
+#[test]
+fn modal_over_fullscreen() {
+    let options = FullscreenOptions::default();
+    let video = WindowProperties {
+        fullscreen: true,
+        wm_state: Some(1), // NormalState
+        ..WindowProperties::default()
+    };
+    // A file dialog (3) on top of the fullscreen window (2), and a
+    // confirmation dialog (4) on top of that
+    let dialog = WindowProperties {
+        wm_state: Some(1),
+        modal: true,
+        transient_for: Some(2),
+        ..WindowProperties::default()
+    };
+    let confirmation = WindowProperties {
+        transient_for: Some(3),
+        ..dialog.clone()
+    };
+    let properties = |window| {
+        Ok(match window {
+            2 => Some(video.clone()),
+            3 => Some(dialog.clone()),
+            4 => Some(confirmation.clone()),
+            _ => None,
+        })
+    };
+
+    assert!(!dialog.is_fullscreen(None, &options));
+    assert!(is_fullscreen_through_modals(&dialog, &[], &options, properties).unwrap());
+    assert!(is_fullscreen_through_modals(&confirmation, &[], &options, properties).unwrap());
+
+    // Only modal windows are followed
+    let transient = WindowProperties {
+        modal: false,
+        ..dialog.clone()
+    };
+    assert!(!is_fullscreen_through_modals(&transient, &[], &options, properties).unwrap());
+
+    // Neither a missing owner nor a cycle is fullscreen
+    let orphan = WindowProperties {
+        transient_for: Some(9),
+        ..dialog.clone()
+    };
+    assert!(!is_fullscreen_through_modals(&orphan, &[], &options, properties).unwrap());
+    let cycle = |_| Ok(Some(dialog.clone()));
+    assert!(!is_fullscreen_through_modals(&dialog, &[], &options, cycle).unwrap());
+}
+
+#[test]
+fn window_search_limit() {
+    // A root (0) with 100 windows, each with 100 children of its own:
+    // window n has children n * 100 + 1 ..= n * 100 + 100
+    let children = |parent: u32| -> Result<Vec<u32>> {
+        if parent > 100 {
+            return Ok(Vec::new());
+        }
+        Ok((1..=100).map(|i| parent * 100 + i).collect())
+    };
+    let last = 100 * 100 + 100;
+
+    // Without a limit, the whole tree is walked
+    let mut search = WindowSearch::new(None);
+    let found = search
+        .find(0, children, |window| Ok(window == last))
+        .unwrap();
+    assert_eq!(found, Some(last));
+    assert_eq!(search.examined(), 100 + 100 * 100);
+    assert!(!search.is_limited());
+
+    // With one, the walk stops there and nothing is found
+    let mut examined = 0;
+    let mut search = WindowSearch::new(Some(500));
+    let found = search
+        .find(0, children, |window| {
+            examined += 1;
+            Ok(window == last)
+        })
+        .unwrap();
+    assert_eq!(found, None);
+    assert_eq!(examined, 500);
+    assert_eq!(search.examined(), 500);
+    assert!(search.is_limited());
+
+    // The limit spans searches, such as one per screen
+    let found = search.find(0, children, |_| Ok(true)).unwrap();
+    assert_eq!(found, None);
+    assert_eq!(search.examined(), 500);
+
+    // Windows within the limit are still found, depth first
+    let mut search = WindowSearch::new(Some(500));
+    let found = search
+        .find(0, children, |window| Ok(window == 101))
+        .unwrap();
+    assert_eq!(found, Some(101));
+    assert_eq!(search.examined(), 2);
+    assert!(!search.is_limited());
+}
+
+#[test]
+fn batched_window_search() {
+    // The same tree as in window_search_limit
+    let children = |parent: u32| -> Result<Vec<u32>> {
+        if parent > 100 {
+            return Ok(Vec::new());
+        }
+        Ok((1..=100).map(|i| parent * 100 + i).collect())
+    };
+    let last = 100 * 100 + 100;
+
+    // Fetching properties one window at a time costs a round-trip each,
+    // while a batch per list of siblings costs one per parent
+    let mut round_trips = 0;
+    let mut fetched = 0;
+    let mut search = WindowSearch::new(None);
+    let found = search
+        .find_batched(
+            0,
+            children,
+            |windows| {
+                round_trips += 1;
+                fetched += windows.len();
+                Ok(windows.to_vec())
+            },
+            |window, props| {
+                assert_eq!(window, props);
+                Ok(window == last)
+            },
+        )
+        .unwrap();
+    assert_eq!(found, Some(last));
+    assert_eq!(fetched, search.examined());
+    assert_eq!(round_trips, 1 + 100);
+
+    // Windows past the limit aren't fetched
+    let mut fetched = Vec::new();
+    let mut search = WindowSearch::new(Some(150));
+    let found = search
+        .find_batched(
+            0,
+            children,
+            |windows| {
+                fetched.push(windows.len());
+                Ok(windows.to_vec())
+            },
+            |_, _| Ok(false),
+        )
+        .unwrap();
+    assert_eq!(found, None);
+    assert!(search.is_limited());
+    assert_eq!(search.examined(), 150);
+    // The root's children, then those of window 1 and 2 with what's left
+    assert_eq!(fetched, [100, 100, 48]);
+}
+
+#[test]
+fn window_search_depth() {
+    // A chain of windows nested 10 deep, where only the deepest one
+    // would match
+    let children = |parent: u32| -> Result<Vec<u32>> {
+        if parent >= 10 {
+            return Ok(Vec::new());
+        }
+        Ok(vec![parent + 1])
+    };
+
+    let mut search = WindowSearch::new(None);
+    let found = search.find(0, children, |window| Ok(window == 10)).unwrap();
+    assert_eq!(found, Some(10));
+    assert_eq!(search.examined(), 10);
+
+    // Depth 1 is the direct children of the root only
+    for &depth in &[1, 3, 9] {
+        let mut search = WindowSearch::new(None).with_max_depth(Some(depth));
+        let found = search.find(0, children, |window| Ok(window == 10)).unwrap();
+        assert_eq!(found, None);
+        assert_eq!(search.examined(), depth);
+        assert!(!search.is_limited(), "the depth isn't a missed window");
+    }
+    let mut search = WindowSearch::new(None).with_max_depth(Some(10));
+    assert_eq!(
+        search.find(0, children, |window| Ok(window == 10)).unwrap(),
+        Some(10)
+    );
+
+    assert_eq!(
+        FullscreenOptions::default().max_depth,
+        Some(xidlehook_core::modules::xcb::DEFAULT_MAX_DEPTH)
+    );
+}
+
+#[test]
+fn top_level_window_search() {
+    // The root has a frame, an unframed client and another frame. The
+    // clients are those below 100 or ending in 1, which each have a
+    // child of their own.
+    let children = |parent: u32| -> Result<Vec<u32>> {
+        Ok(match parent {
+            0 => vec![1, 2, 3],
+            1 => vec![11, 12],
+            2 => vec![21],
+            3 => vec![31],
+            11 => vec![111],
+            _ => Vec::new(),
+        })
+    };
+    let is_client = |&(window, _): &(u32, bool)| window == 2 || window % 10 == 1 && window > 10;
+    let fetch = |windows: &[u32]| Ok(windows.iter().map(|&w| (w, false)).collect());
+    let find = |wanted: u32| {
+        let mut search = WindowSearch::new(None);
+        let found = search
+            .find_top_level(0, children, fetch, is_client, |window, _| {
+                Ok(window == wanted)
+            })
+            .unwrap();
+        (found, search.examined())
+    };
+
+    // A fullscreen window directly below the root is found first
+    assert_eq!(find(2), (Some(2), 2));
+    // So is a client in a frame
+    assert_eq!(find(31), (Some(31), 6));
+    assert_eq!(find(11), (Some(11), 4));
+    // Neither frames' children that aren't clients, nor anything below
+    // clients, is looked at
+    assert_eq!(find(12), (None, 6));
+    assert_eq!(find(21), (None, 6));
+    assert_eq!(find(111), (None, 6));
+
+    // The limit still applies
+    let mut search = WindowSearch::new(Some(4));
+    let found = search
+        .find_top_level(0, children, fetch, is_client, |_, _| Ok(false))
+        .unwrap();
+    assert_eq!(found, None);
+    assert!(search.is_limited());
+    assert_eq!(search.examined(), 4);
+}
+
+#[test]
+fn unknown_current_desktop() {
+    // Without _NET_CURRENT_DESKTOP, windows on any desktop count
+    let window = WindowProperties {
+        fullscreen: true,
+        wm_state: Some(1), // NormalState
+        desktop: Some(2),
+        ..WindowProperties::default()
+    };
+    assert!(window.is_fullscreen(None, &FullscreenOptions::default()));
+    assert!(window.is_fullscreen_on(&[], &FullscreenOptions::default()));
+
+    // Unless that's too lax
+    let strict = FullscreenOptions {
+        strict_desktop_match: true,
+        ..FullscreenOptions::default()
+    };
+    assert!(!window.is_fullscreen(None, &strict));
+    assert!(window.is_fullscreen(Some(2), &strict));
+    assert!(!window.is_fullscreen(Some(0), &strict));
+    // Sticky windows and those that aren't on a desktop still count
+    for &desktop in &[Some(STICKY_DESKTOP), None] {
+        assert!(WindowProperties {
+            desktop,
+            ..window.clone()
+        }
+        .is_fullscreen(None, &strict));
+    }
+}
+
+#[test]
+fn wm_class_parts() {
+    let parts = |parts: &[&str]| {
+        parts
+            .iter()
+            .map(|&part| String::from(part))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(parse_wm_class("mpv\0mpv\0"), parts(&["mpv", "mpv"]));
+    // A missing final NUL
+    assert_eq!(
+        parse_wm_class("navigator\0Firefox"),
+        parts(&["navigator", "Firefox"])
+    );
+    assert_eq!(parse_wm_class("xterm"), parts(&["xterm"]));
+    // Trailing data, and empty parts
+    assert_eq!(parse_wm_class("a\0b\0c\0"), parts(&["a", "b", "c"]));
+    assert_eq!(parse_wm_class("\0Steam\0"), parts(&["", "Steam"]));
+    assert_eq!(parse_wm_class("\0\0"), parts(&["", ""]));
+    // Empty values
+    assert!(parse_wm_class("").is_empty());
+    assert!(parse_wm_class("\0").is_empty());
+
+    // Exceptions go by the instance and class name, whatever follows
+    let window = |wm_class: &str| WindowProperties {
+        wm_class: parse_wm_class(wm_class),
+        fullscreen: true,
+        wm_state: Some(1), // NormalState
+        ..WindowProperties::default()
+    };
+    let options = FullscreenOptions {
+        exceptions_wm_class2: vec!["Firefox".into()],
+        ..FullscreenOptions::default()
+    };
+    assert!(!window("navigator\0Firefox\0extra\0").is_fullscreen(None, &options));
+    assert!(!window("navigator\0Firefox").is_fullscreen(None, &options));
+    assert!(window("Firefox\0").is_fullscreen(None, &options));
+    assert!(window("").is_fullscreen(None, &options));
+    assert_eq!(window("Firefox\0").instance(), "Firefox");
+    assert_eq!(window("Firefox\0").class(), "");
+}
+
+#[test]
+fn replaced_exceptions() {
+    let mpv = WindowProperties {
+        wm_class: vec!["mpv".into(), "mpv".into()],
+        fullscreen: true,
+        wm_state: Some(1), // NormalState
+        desktop: Some(2),
+        ..WindowProperties::default()
+    };
+    let mut options = FullscreenOptions {
+        exceptions_wm_name: vec!["Private Browsing".into()],
+        ignore_desktop_match: true,
+        ..FullscreenOptions::default()
+    };
+    assert!(mpv.is_fullscreen(Some(0), &options));
+
+    options.set_exceptions(&FullscreenOptions {
+        exceptions_wm_class1: vec!["mpv".into()],
+        ..FullscreenOptions::default()
+    });
+    assert!(!mpv.is_fullscreen(Some(0), &options));
+    // The old exceptions are gone, but nothing else changed
+    assert!(options.exceptions_wm_name.is_empty());
+    assert!(options.ignore_desktop_match);
+
+    options.set_exceptions(&FullscreenOptions::default());
+    assert!(mpv.is_fullscreen(Some(0), &options));
+}
@@ -0,0 +1,91 @@
+use std::{fs, time::Duration};
+use xidlehook_core::{
+    history::History, timers::CallbackTimer, Module, Progress, Result, TimerInfo, Xidlehook,
+};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+/// Inhibits the second timer
+struct Inhibit;
+impl Module for Inhibit {
+    fn pre_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        if timer.index == 1 {
+            Ok(Progress::Abort)
+        } else {
+            Ok(Progress::Continue)
+        }
+    }
+}
+
+/// Returns the records without their timestamps
+fn records(path: &std::path::Path) -> Vec<String> {
+    fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            assert!(line.starts_with("{\"timestamp\":"));
+            line[line.find(',').unwrap() + 1..].to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn history() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dir = std::env::temp_dir().join(format!("xidlehook-history-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("history.jsonl");
+
+    let mut timer = Xidlehook::new(vec![
+        CallbackTimer::new(TEST_UNIT * 10, || ()),
+        CallbackTimer::new(TEST_UNIT * 10, || ()),
+    ])
+    .register(Inhibit)
+    .with_history(History::new(&path));
+
+    timer.poll(TEST_UNIT * 10).unwrap();
+    assert_eq!(
+        records(&path),
+        vec![r#""event":"activated","timer":0,"idle":0.500,"inhibited":false}"#]
+    );
+
+    timer.poll(TEST_UNIT * 25).unwrap();
+    timer.poll(Duration::default()).unwrap();
+    assert_eq!(
+        records(&path)[1..],
+        [
+            r#""event":"activated","timer":1,"idle":1.250,"inhibited":true}"#,
+            r#""event":"aborted","timer":0,"idle":1.250,"inhibited":false}"#,
+        ]
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn history_rotation() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dir = std::env::temp_dir().join(format!("xidlehook-rotation-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("history.jsonl");
+
+    // Room for two records, but not three
+    let mut history = History::new(&path).with_max_size(200);
+    let rotated = history.rotated_path();
+    for _ in 0..3 {
+        history
+            .record(
+                xidlehook_core::history::Event::Activated,
+                0,
+                TEST_UNIT,
+                false,
+            )
+            .unwrap();
+    }
+    assert_eq!(records(&rotated).len(), 2);
+    assert_eq!(records(&path).len(), 1);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,114 @@
+use std::{cell::Cell, rc::Rc, time::Duration};
+use xidlehook_core::{Result, Timer, Xidlehook};
+
+const MINUTE: Duration = Duration::from_secs(60);
+
+/// Counts how often it goes off, only within its band of idle times
+struct Reminder {
+    time: Duration,
+    recurring: Option<Duration>,
+    min_idle: Option<Duration>,
+    max_idle: Option<Duration>,
+    fired: Rc<Cell<usize>>,
+}
+impl Reminder {
+    fn new(time: Duration, fired: &Rc<Cell<usize>>) -> Self {
+        Self {
+            time,
+            recurring: None,
+            min_idle: None,
+            max_idle: None,
+            fired: Rc::clone(fired),
+        }
+    }
+}
+impl Timer for Reminder {
+    fn time_left(&mut self, idle_time: Duration) -> Result<Option<Duration>> {
+        Ok(self
+            .time
+            .checked_sub(idle_time)
+            .filter(|&d| d != Duration::default()))
+    }
+    fn activate(&mut self) -> Result<()> {
+        self.fired.set(self.fired.get() + 1);
+        Ok(())
+    }
+    fn recurring(&self) -> Option<Duration> {
+        self.recurring
+    }
+    fn min_idle(&self) -> Option<Duration> {
+        self.min_idle
+    }
+    fn max_idle(&self) -> Option<Duration> {
+        self.max_idle
+    }
+}
+
+#[test]
+fn idle_band() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // A reminder every minute, from 5 up to 15 minutes of idle time
+    let reminded = Rc::new(Cell::new(0));
+    let locked = Rc::new(Cell::new(0));
+    let mut xidlehook = Xidlehook::new(vec![
+        Reminder {
+            recurring: Some(MINUTE),
+            max_idle: Some(MINUTE * 15),
+            ..Reminder::new(MINUTE * 5, &reminded)
+        },
+        Reminder::new(MINUTE * 20, &locked),
+    ]);
+
+    // Below the band, nothing happens
+    xidlehook.poll(MINUTE * 4).unwrap();
+    assert_eq!(reminded.get(), 0);
+
+    // Within it, the reminder goes off and recurs
+    xidlehook.poll(MINUTE * 5).unwrap();
+    assert_eq!(reminded.get(), 1);
+    for minute in 6..=15 {
+        xidlehook.poll(MINUTE * minute).unwrap();
+    }
+    assert_eq!(reminded.get(), 11);
+
+    // Past it, it stops recurring, without keeping the lock from going
+    // off at 25 minutes
+    xidlehook.poll(MINUTE * 16).unwrap();
+    xidlehook.poll(MINUTE * 20).unwrap();
+    assert_eq!((reminded.get(), locked.get()), (11, 0));
+    xidlehook.poll(MINUTE * 25).unwrap();
+    assert_eq!((reminded.get(), locked.get()), (11, 1));
+
+    // A timer that's due only after the band is skipped, and the next
+    // one still goes off
+    let skipped = Rc::new(Cell::new(0));
+    let next = Rc::new(Cell::new(0));
+    let mut xidlehook = Xidlehook::new(vec![
+        Reminder {
+            max_idle: Some(MINUTE * 3),
+            ..Reminder::new(MINUTE * 5, &skipped)
+        },
+        Reminder::new(MINUTE * 6, &next),
+    ]);
+    xidlehook.poll(MINUTE * 5).unwrap();
+    xidlehook.poll(MINUTE * 6).unwrap();
+    assert_eq!((skipped.get(), next.get()), (0, 1));
+
+    // A timer whose time passed waits for the idle time to reach its
+    // band
+    let dimmed = Rc::new(Cell::new(0));
+    let banded = Rc::new(Cell::new(0));
+    let mut xidlehook = Xidlehook::new(vec![
+        Reminder::new(MINUTE * 5, &dimmed),
+        Reminder {
+            min_idle: Some(MINUTE * 10),
+            ..Reminder::new(MINUTE, &banded)
+        },
+    ]);
+    xidlehook.poll(MINUTE * 5).unwrap();
+    xidlehook.poll(MINUTE * 6).unwrap();
+    assert_eq!((dimmed.get(), banded.get()), (1, 0));
+    xidlehook.poll(MINUTE * 10).unwrap();
+    assert_eq!(banded.get(), 1);
+}
@@ -0,0 +1,140 @@
+use std::{
+    ffi::{OsStr, OsString},
+    fs,
+    path::Path,
+    process::Command,
+    thread,
+    time::{Duration, Instant},
+};
+use xidlehook_core::{
+    timers::{self, CmdTimer, SpawnOptions, TimerCommand},
+    Xidlehook,
+};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+/// Wait for the command to have written its line
+fn read_line(path: &Path) -> String {
+    let start = Instant::now();
+    loop {
+        let contents = fs::read_to_string(path).unwrap_or_default();
+        if contents.ends_with('\n') || start.elapsed() > TEST_UNIT * 100 {
+            return contents.trim_end().to_string();
+        }
+        thread::sleep(TEST_UNIT / 5);
+    }
+}
+
+fn shell(script: String) -> Option<Command> {
+    let mut cmd = Command::new("/bin/sh");
+    cmd.arg("-c").arg(script);
+    Some(cmd)
+}
+
+#[test]
+fn idle_env() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dir = std::env::temp_dir().join(format!("xidlehook-idle-env-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let activated = dir.join("activated");
+    let warned = dir.join("warned");
+    let script = |path: &Path| {
+        // A variable that isn't set expands to nothing
+        shell(format!(
+            "echo \"$XIDLEHOOK_IDLE_MS [$XIDLEHOOK_UNSET_VARIABLE]\" > '{}'",
+            path.display()
+        ))
+    };
+
+    let mut xidlehook = Xidlehook::new(vec![CmdTimer {
+        time: TEST_UNIT * 4,
+        activation: script(&activated),
+        warning: script(&warned),
+        warning_lead: TEST_UNIT * 2,
+        ..CmdTimer::default()
+    }]);
+
+    xidlehook.poll(TEST_UNIT * 3).unwrap();
+    assert_eq!(read_line(&warned), "150 []");
+    assert!(!activated.exists());
+
+    xidlehook.poll(TEST_UNIT * 5).unwrap();
+    assert_eq!(read_line(&activated), "250 []");
+
+    // It's updated every time. The timer stays disabled until its
+    // command exited.
+    fs::remove_file(&activated).unwrap();
+    if let Some(mut child) = xidlehook.timers_mut().unwrap()[0].activation_child.take() {
+        child.wait().unwrap();
+    }
+    xidlehook.poll(TEST_UNIT).unwrap();
+    xidlehook.poll(TEST_UNIT * 6).unwrap();
+    assert_eq!(read_line(&activated), "300 []");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn argv_expansion() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dir = std::env::temp_dir().join(format!("xidlehook-argv-env-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let activated = dir.join("activated");
+    let options = SpawnOptions {
+        env: vec![("XIDLEHOOK_TEST".into(), "a b".into())],
+        ..SpawnOptions::default()
+    };
+    // The script gets the line as an argument, so it's xidlehook that
+    // expands it rather than the shell. `$1` isn't a name, so it stays.
+    let argv = TimerCommand::Argv(vec![
+        "/bin/sh".into(),
+        "-c".into(),
+        format!("echo \"$1\" > '{}'", activated.display()),
+        "sh".into(),
+        "$XIDLEHOOK_IDLE_MS ${XIDLEHOOK_TEST} [$XIDLEHOOK_UNSET_VARIABLE] $$HOME".into(),
+    ]);
+
+    let mut xidlehook = Xidlehook::new(vec![CmdTimer {
+        time: TEST_UNIT * 2,
+        activation: Some(argv.to_command(&options)),
+        expand_args: Some(options.clone()),
+        ..CmdTimer::default()
+    }]);
+    xidlehook.poll(TEST_UNIT * 3).unwrap();
+    assert_eq!(read_line(&activated), "150 a b [] $HOME");
+
+    // The command itself is left as it is, to be expanded again next time
+    let timer = &xidlehook.timers()[0];
+    let args: Vec<&OsStr> = timer.activation.as_ref().unwrap().get_args().collect();
+    assert_eq!(
+        args[3],
+        "$XIDLEHOOK_IDLE_MS ${XIDLEHOOK_TEST} [$XIDLEHOOK_UNSET_VARIABLE] $$HOME"
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn expand_vars() {
+    let expand = |arg: &str| {
+        timers::expand_vars(OsStr::new(arg), |name| match name.to_str() {
+            Some("A") => Some(OsString::from("1")),
+            Some("A_2") => Some(OsString::from("x y")),
+            Some("EMPTY") => Some(OsString::new()),
+            _ => None,
+        })
+    };
+
+    assert_eq!(expand("$A/${A}/$A_2"), "1/1/x y");
+    assert_eq!(expand("${A}_2 $A-2"), "1_2 1-2");
+    assert_eq!(expand("[$EMPTY][$UNSET][${UNSET}]"), "[][][]");
+
+    // Nothing else is special
+    assert_eq!(expand("'$A' \"*\" ~"), "'1' \"*\" ~");
+
+    // Dollars which don't start a name are kept
+    assert_eq!(expand("$$A $1 $ $-A ${1} ${A"), "$A $1 $ $-A ${1} ${A");
+    assert_eq!(expand("a$"), "a$");
+}
@@ -0,0 +1,55 @@
+use std::time::{Duration, Instant};
+use xidlehook_core::modules::xcb::{idle_from_ms_since_input, IdleCounter};
+
+#[test]
+fn idle_overflow() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    for &ms in &[0, 1500, i32::MAX as u32, u32::MAX - 1, u32::MAX] {
+        assert_eq!(
+            idle_from_ms_since_input(ms),
+            Duration::from_millis(u64::from(ms))
+        );
+    }
+    assert_eq!(
+        idle_from_ms_since_input(u32::MAX).as_millis(),
+        4_294_967_295
+    );
+}
+
+#[test]
+fn idle_counter_wraps() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let start = Instant::now();
+    let at = |ms: u64| start + Duration::from_millis(ms);
+    let mut counter = IdleCounter::new();
+
+    // Readings every 30 minutes, starting an hour before the counter
+    // wraps around
+    let before = u32::MAX - 60 * 60 * 1000;
+    let mut last = Duration::default();
+    for step in 0..6u32 {
+        let elapsed = step * 30 * 60 * 1000;
+        let ms = before.wrapping_add(elapsed);
+        let idle = counter.observe_at(ms, at(u64::from(elapsed)));
+        assert!(idle > last, "{:?} after {:?}", idle, last);
+        assert_eq!(idle.as_millis(), u128::from(before) + u128::from(elapsed));
+        last = idle;
+    }
+    assert_eq!(counter.wraps(), 1);
+
+    // A drop the time passed doesn't explain is the user being active
+    let idle = counter.observe_at(500, at(6 * 30 * 60 * 1000));
+    assert_eq!(idle, Duration::from_millis(500));
+    assert_eq!(counter.wraps(), 0);
+
+    // Even right at the limit, if the reading is too far off
+    let mut counter = IdleCounter::new();
+    counter.observe_at(u32::MAX - 1000, at(0));
+    let idle = counter.observe_at(10 * 60 * 1000, at(2000));
+    assert_eq!(idle, Duration::from_millis(10 * 60 * 1000));
+    assert_eq!(counter.wraps(), 0);
+    let idle = counter.observe_at(20 * 60 * 1000, at(10 * 60 * 1000 + 2000));
+    assert_eq!(idle, Duration::from_millis(20 * 60 * 1000));
+}
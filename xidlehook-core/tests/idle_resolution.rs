@@ -0,0 +1,26 @@
+use std::time::Duration;
+use xidlehook_core::{timers::CallbackTimer, IdleSource, Result, Xidlehook};
+
+/// A source which only updates every 5 seconds
+struct CoarseSource;
+impl IdleSource for CoarseSource {
+    fn get_idle(&self) -> Result<Duration> {
+        Ok(Duration::default())
+    }
+    fn resolution(&self) -> Duration {
+        Duration::from_secs(5)
+    }
+}
+
+#[test]
+fn idle_resolution() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let mut timer = Xidlehook::new(vec![
+        CallbackTimer::new(Duration::from_secs(60), || ()),
+        CallbackTimer::new(Duration::from_secs(1), || ()),
+        CallbackTimer::new(Duration::from_secs(10), || ()),
+    ]);
+
+    assert_eq!(timer.check_resolution(&CoarseSource).unwrap(), [1]);
+}
@@ -0,0 +1,60 @@
+use std::time::Duration;
+use xidlehook_core::modules::xcb::{idle_from_ms_since_input, IdleAggregate, IdleTarget};
+
+#[test]
+fn idle_targets() {
+    let roots = [0x100, 0x200, 0x300];
+
+    // By default, the first screen's root is queried
+    assert_eq!(IdleTarget::default().resolve(&roots).unwrap(), 0x100);
+    assert_eq!(IdleTarget::Screen(2).resolve(&roots).unwrap(), 0x300);
+    assert_eq!(IdleTarget::Window(0x1234).resolve(&roots).unwrap(), 0x1234);
+
+    assert!(IdleTarget::Screen(3).resolve(&roots).is_err());
+    assert!(IdleTarget::FirstScreen.resolve(&[]).is_err());
+}
+
+#[test]
+fn idle_aggregates() {
+    let second = Duration::from_secs(1);
+    // Active on the second screen, idle on the others
+    let idle = [second * 300, second * 2, second * 60];
+
+    assert_eq!(IdleAggregate::default(), IdleAggregate::Min);
+    assert_eq!(IdleAggregate::Min.aggregate(&idle).unwrap(), second * 2);
+    assert_eq!(IdleAggregate::Max.aggregate(&idle).unwrap(), second * 300);
+    assert_eq!(
+        IdleAggregate::Screen(0).aggregate(&idle).unwrap(),
+        second * 300
+    );
+    assert_eq!(
+        IdleAggregate::Screen(2).aggregate(&idle).unwrap(),
+        second * 60
+    );
+
+    assert!(IdleAggregate::Screen(3).aggregate(&idle).is_err());
+    assert!(IdleAggregate::Min.aggregate(&[]).is_err());
+
+    // A single screen is reported as is
+    for &aggregate in &[
+        IdleAggregate::Min,
+        IdleAggregate::Max,
+        IdleAggregate::Screen(0),
+    ] {
+        assert_eq!(aggregate.aggregate(&[second]).unwrap(), second);
+    }
+
+    // The raw counters pick the same screen as the durations
+    let ms = [300_000, 2000, 60_000];
+    for &aggregate in &[
+        IdleAggregate::Min,
+        IdleAggregate::Max,
+        IdleAggregate::Screen(2),
+    ] {
+        let raw = aggregate.aggregate_ms(&ms).unwrap();
+        assert_eq!(
+            idle_from_ms_since_input(raw),
+            aggregate.aggregate(&idle).unwrap()
+        );
+    }
+}
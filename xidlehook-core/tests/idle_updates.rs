@@ -0,0 +1,58 @@
+use std::{
+    cell::Cell,
+    time::{Duration, Instant},
+};
+use xidlehook_core::{
+    sources::{IdleSource, IdleUpdates, ManualIdleSource},
+    Result,
+};
+
+/// A source whose idle time grows by a second every time it's
+/// refreshed, and which fails the third time
+#[derive(Default)]
+struct GrowingSource {
+    refreshed: Cell<u64>,
+}
+impl IdleSource for GrowingSource {
+    fn get_idle(&self) -> Result<Duration> {
+        match self.refreshed.get() {
+            3 => Err("the X server went away".into()),
+            refreshed => Ok(Duration::from_secs(refreshed)),
+        }
+    }
+    fn refresh(&self) {
+        self.refreshed.set(self.refreshed.get() + 1);
+    }
+}
+
+#[test]
+fn idle_updates() {
+    let source = GrowingSource::default();
+    let updates: Vec<_> = IdleUpdates::new(&source, Duration::default())
+        .take(5)
+        .map(|idle| idle.map_err(|err| err.to_string()))
+        .collect();
+    // Each idle time is measured anew, and an error doesn't end it
+    assert_eq!(
+        updates,
+        [
+            Ok(Duration::from_secs(1)),
+            Ok(Duration::from_secs(2)),
+            Err("the X server went away".to_string()),
+            Ok(Duration::from_secs(4)),
+            Ok(Duration::from_secs(5)),
+        ]
+    );
+    assert_eq!(source.refreshed.get(), 5);
+
+    // The first one comes right away, and the others an interval apart
+    let interval = Duration::from_millis(20);
+    let source = ManualIdleSource::new(Duration::from_secs(7));
+    let mut updates = IdleUpdates::new(&source, interval);
+    let start = Instant::now();
+    assert_eq!(updates.next().unwrap().unwrap(), Duration::from_secs(7));
+    assert!(start.elapsed() < interval);
+    source.set(Duration::default());
+    assert_eq!(updates.next().unwrap().unwrap(), Duration::default());
+    assert!(start.elapsed() >= interval);
+}
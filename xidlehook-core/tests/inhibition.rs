@@ -0,0 +1,54 @@
+use xidlehook_core::{
+    modules::{ModuleList, Toggle},
+    status::{Inhibition, Inhibitor, EXIT_NOT_RUNNING},
+    Progress, TimerInfo,
+};
+
+const TIMER: TimerInfo = TimerInfo {
+    index: 0,
+    length: 1,
+};
+
+#[test]
+fn inhibition() {
+    let disabled = Toggle::new(Progress::Abort);
+    let presentation = Toggle::new(Progress::Reset);
+    let mut modules = ModuleList::new()
+        .with("disabled", disabled.clone())
+        .with("presentation", presentation.clone());
+
+    // Nothing inhibits, so scripts get a failing exit code
+    let inhibition = Inhibition::new(modules.verdicts(TIMER).unwrap());
+    assert!(!inhibition.is_inhibited());
+    assert_eq!(inhibition.exit_code(), 1);
+
+    // Every inhibiting module is listed, even when short-circuiting
+    disabled.set(true);
+    presentation.set(true);
+    assert!(modules.short_circuit());
+    let inhibition = Inhibition::new(modules.verdicts(TIMER).unwrap());
+    assert!(inhibition.is_inhibited());
+    assert_eq!(inhibition.exit_code(), 0);
+    assert_eq!(
+        inhibition.inhibitors,
+        vec![
+            Inhibitor {
+                module: String::from("disabled"),
+                verdict: String::from("abort"),
+            },
+            Inhibitor {
+                module: String::from("presentation"),
+                verdict: String::from("reset"),
+            },
+        ]
+    );
+
+    disabled.set(false);
+    let inhibition = Inhibition::new(modules.verdicts(TIMER).unwrap());
+    assert_eq!(inhibition.exit_code(), 0);
+    assert_eq!(inhibition.inhibitors.len(), 1);
+
+    // Not being able to tell is distinct from either answer
+    assert_ne!(EXIT_NOT_RUNNING, 0);
+    assert_ne!(EXIT_NOT_RUNNING, 1);
+}
@@ -0,0 +1,97 @@
+#![cfg(feature = "dbus")]
+
+mod common;
+
+use common::private_bus;
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+use xidlehook_core::{modules::NotWhenInhibited, Module, Progress, TimerInfo};
+use zbus::blocking::{connection, Connection};
+
+const INFO: TimerInfo = TimerInfo {
+    index: 0,
+    length: 1,
+};
+
+fn connect(address: &str) -> Connection {
+    connection::Builder::address(address)
+        .unwrap()
+        .build()
+        .unwrap()
+}
+
+fn inhibit(conn: &Connection, path: &str, application: &str) -> u32 {
+    conn.call_method(
+        Some("org.freedesktop.ScreenSaver"),
+        path,
+        Some("org.freedesktop.ScreenSaver"),
+        "Inhibit",
+        &(application, "video-playing"),
+    )
+    .unwrap()
+    .body()
+    .deserialize()
+    .unwrap()
+}
+
+fn uninhibit(conn: &Connection, cookie: u32) {
+    conn.call_method(
+        Some("org.freedesktop.ScreenSaver"),
+        "/org/freedesktop/ScreenSaver",
+        Some("org.freedesktop.ScreenSaver"),
+        "UnInhibit",
+        &(cookie,),
+    )
+    .unwrap();
+}
+
+/// Wait for a signal to be handled
+fn eventually(mut condition: impl FnMut() -> bool) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        if condition() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    false
+}
+
+#[test]
+fn inhibitors() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let (_bus, address) = match private_bus() {
+        Some(bus) => bus,
+        None => return, // Don't fail where there's no D-Bus
+    };
+    let mut module = NotWhenInhibited::with_connection(connect(&address)).unwrap();
+    assert_eq!(module.pre_timer(INFO).unwrap(), Progress::Continue);
+
+    // There can only be one provider
+    assert!(NotWhenInhibited::with_connection(connect(&address)).is_err());
+
+    let firefox = connect(&address);
+    let vlc = connect(&address);
+    let cookie = inhibit(&firefox, "/org/freedesktop/ScreenSaver", "firefox");
+    let other = inhibit(&vlc, "/ScreenSaver", "vlc");
+    assert_ne!(cookie, other);
+    assert_eq!(module.inhibitors().len(), 2);
+    assert_eq!(module.inhibitors()[0].application, "firefox");
+    assert_eq!(module.inhibitors()[0].reason, "video-playing");
+    assert_eq!(module.pre_timer(INFO).unwrap(), Progress::Abort);
+
+    // Only the program that took it can release it
+    uninhibit(&vlc, cookie);
+    assert_eq!(module.inhibitors().len(), 2);
+    uninhibit(&firefox, cookie);
+    assert_eq!(module.inhibitors().len(), 1);
+    assert_eq!(module.pre_timer(INFO).unwrap(), Progress::Abort);
+
+    // Leaving the bus releases it too
+    drop(vlc);
+    assert!(eventually(|| module.inhibitors().is_empty()));
+    assert_eq!(module.pre_timer(INFO).unwrap(), Progress::Continue);
+}
@@ -0,0 +1,63 @@
+use std::{cell::Cell, time::Duration};
+use xidlehook_core::{
+    timers::{CallbackTimer, Jittered},
+    Action::*,
+    Xidlehook,
+};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+#[test]
+fn jittered_timers() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let triggered = Cell::new(0);
+
+    let mut timer = Xidlehook::new(vec![Jittered::with_seed(
+        CallbackTimer::new(TEST_UNIT * 100, || triggered.set(triggered.get() + 1)),
+        TEST_UNIT * 20,
+        42,
+    )]);
+
+    for _ in 0..10 {
+        let offset = timer.timers()[0].offset();
+        assert!(offset <= TEST_UNIT * 20);
+        let threshold = TEST_UNIT * 100 + offset;
+
+        // The offset is stable across polls within one idle cycle
+        assert_eq!(
+            timer.poll(TEST_UNIT * 50).unwrap(),
+            Sleep(threshold - TEST_UNIT * 50)
+        );
+        assert_eq!(
+            timer.poll(TEST_UNIT * 99).unwrap(),
+            Sleep(threshold - TEST_UNIT * 99)
+        );
+        assert_eq!(timer.timers()[0].offset(), offset);
+
+        let before = triggered.get();
+        timer.poll(threshold - Duration::from_millis(1)).unwrap();
+        assert_eq!(triggered.get(), before);
+        timer.poll(threshold).unwrap();
+        assert_eq!(triggered.get(), before + 1);
+        assert_eq!(timer.timers()[0].offset(), offset);
+
+        // User activity starts a new cycle
+        timer.poll(Duration::default()).unwrap();
+    }
+
+    // The same seed always gives the same offsets
+    let offsets = |seed| {
+        let mut jittered =
+            Jittered::with_seed(CallbackTimer::new(TEST_UNIT, || ()), TEST_UNIT, seed);
+        (0..5)
+            .map(|_| {
+                let offset = jittered.offset();
+                xidlehook_core::Timer::reset(&mut jittered).unwrap();
+                offset
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(offsets(1), offsets(1));
+    assert_ne!(offsets(1), offsets(2));
+}
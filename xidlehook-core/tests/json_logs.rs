@@ -0,0 +1,82 @@
+use log::{Log, Metadata, Record};
+use serde_json::{json, Value};
+use std::{
+    sync::Mutex,
+    time::{Duration, UNIX_EPOCH},
+};
+use xidlehook_core::{
+    logging::{self, EVENTS_TARGET},
+    modules::Toggle,
+    timers::CallbackTimer,
+    Progress, Xidlehook,
+};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+/// Keeps every record, formatted as JSON
+struct Capture(Mutex<Vec<String>>);
+impl Log for Capture {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+    fn log(&self, record: &Record) {
+        let now = UNIX_EPOCH + Duration::from_millis(1_602_681_600_250);
+        let line = logging::json_line(record, now);
+        self.0.lock().unwrap().push(line);
+    }
+    fn flush(&self) {}
+}
+
+static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+
+fn events(name: &str) -> Vec<Value> {
+    CAPTURE
+        .0
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|line| serde_json::from_str::<Value>(line).expect("invalid JSON"))
+        .filter(|line| line["event"] == name)
+        .collect()
+}
+
+#[test]
+fn json_logs() {
+    log::set_logger(&CAPTURE).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+
+    let disabled = Toggle::new(Progress::Abort);
+    let mut timer =
+        Xidlehook::new(vec![CallbackTimer::new(TEST_UNIT, || ())]).register(disabled.clone());
+
+    timer.poll(TEST_UNIT).unwrap();
+    let fired = events("timer_fired");
+    assert_eq!(fired.len(), 1);
+    assert_eq!(
+        fired[0],
+        json!({
+            "timestamp": 1_602_681_600.25,
+            "level": "INFO",
+            "target": EVENTS_TARGET,
+            "event": "timer_fired",
+            "message": "timer_fired timer=0 idle_ms=50",
+            "timer": 0,
+            "idle_ms": 50,
+        })
+    );
+
+    disabled.set(true);
+    timer.poll(Duration::default()).unwrap();
+    timer.poll(TEST_UNIT).unwrap();
+    let inhibited = events("timer_inhibited");
+    assert_eq!(inhibited.len(), 1);
+    assert_eq!(inhibited[0]["timer"], 0);
+    assert_eq!(inhibited[0]["verdict"], "abort");
+
+    // Plain records have no event, and their messages are escaped
+    log::info!("a \"quoted\"\nmessage");
+    let lines = CAPTURE.0.lock().unwrap();
+    let plain: Value = serde_json::from_str(lines.last().unwrap()).unwrap();
+    assert_eq!(plain["message"], "a \"quoted\"\nmessage");
+    assert_eq!(plain["event"], Value::Null);
+}
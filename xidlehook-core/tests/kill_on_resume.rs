@@ -0,0 +1,51 @@
+use nix::sys::signal::Signal;
+use std::{
+    os::unix::process::ExitStatusExt,
+    process::{Child, Command},
+    time::Duration,
+};
+use xidlehook_core::{timers::CmdTimer, Xidlehook};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+fn fade_timer(kill_on_resume: bool, kill_signal: Option<Signal>) -> CmdTimer {
+    let mut fade = Command::new("sleep");
+    fade.arg("10");
+    CmdTimer {
+        time: TEST_UNIT,
+        activation: Some(fade),
+        kill_on_resume,
+        kill_signal,
+        ..CmdTimer::default()
+    }
+}
+
+/// Go idle until the timer fires, then be active again, returning
+/// the command it ran
+fn resume(timer: CmdTimer) -> Child {
+    let mut xidlehook = Xidlehook::new(vec![timer]);
+    xidlehook.poll(TEST_UNIT).unwrap();
+    xidlehook.poll(Duration::default()).unwrap();
+    xidlehook.timers_mut().unwrap()[0]
+        .activation_child
+        .take()
+        .unwrap()
+}
+
+#[test]
+fn kill_on_resume() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let status = resume(fade_timer(true, None)).wait().unwrap();
+    assert_eq!(status.signal(), Some(Signal::SIGTERM as i32));
+    let status = resume(fade_timer(true, Some(Signal::SIGINT)))
+        .wait()
+        .unwrap();
+    assert_eq!(status.signal(), Some(Signal::SIGINT as i32));
+
+    // Commands are left running unless asked to
+    let mut child = resume(fade_timer(false, None));
+    assert!(child.try_wait().unwrap().is_none());
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
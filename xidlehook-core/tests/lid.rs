@@ -0,0 +1,71 @@
+use std::{
+    fs,
+    path::Path,
+    process::Command,
+    thread,
+    time::{Duration, Instant},
+};
+use xidlehook_core::modules::{LidState, LidSwitch};
+
+/// Wait for the command to have written its lines
+fn read_lines(path: &Path, count: usize) -> String {
+    let start = Instant::now();
+    loop {
+        let contents = fs::read_to_string(path).unwrap_or_default();
+        if contents.lines().count() >= count || start.elapsed() > Duration::from_secs(5) {
+            return contents;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn lid() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    assert_eq!(LidState::parse("state:      open\n"), Some(LidState::Open));
+    assert_eq!(
+        LidState::parse("state:      closed\n"),
+        Some(LidState::Closed)
+    );
+    assert_eq!(LidState::parse(""), None);
+
+    let dir = std::env::temp_dir().join(format!("xidlehook-lid-{}", std::process::id()));
+    let proc_dir = dir.join("lid");
+    fs::create_dir_all(proc_dir.join("LID0")).unwrap();
+    let state = proc_dir.join("LID0").join("state");
+    let log = dir.join("log");
+    let append = |word: &str| {
+        let mut cmd = Command::new("/bin/sh");
+        cmd.arg("-c")
+            .arg(format!("echo {} >> '{}'", word, log.display()));
+        cmd
+    };
+    let mut lid = LidSwitch::new()
+        .with_proc_dir(&proc_dir)
+        .with_on_close(append("closed"))
+        .with_on_open(append("opened"));
+
+    // Starting out closed isn't a change
+    fs::write(&state, "state:      closed\n").unwrap();
+    assert_eq!(lid.check().unwrap(), None);
+    assert_eq!(lid.check().unwrap(), None);
+
+    fs::write(&state, "state:      open\n").unwrap();
+    assert_eq!(lid.check().unwrap(), Some(LidState::Open));
+    assert_eq!(read_lines(&log, 1), "opened\n");
+
+    fs::write(&state, "state:      closed\n").unwrap();
+    assert_eq!(lid.check().unwrap(), Some(LidState::Closed));
+    assert_eq!(read_lines(&log, 2), "opened\nclosed\n");
+    assert_eq!(lid.check().unwrap(), None);
+
+    // Without a lid, nothing ever happens
+    let mut none = LidSwitch::new()
+        .with_proc_dir(dir.join("missing"))
+        .with_on_close(append("never"));
+    assert_eq!(none.state(), None);
+    assert_eq!(none.check().unwrap(), None);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
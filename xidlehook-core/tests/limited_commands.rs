@@ -0,0 +1,65 @@
+use std::{process::Command, rc::Rc, thread, time::Duration};
+use xidlehook_core::{
+    timers::{CmdTimer, CommandLimit, QueueFull},
+    Timer,
+};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+fn sleeper(limit: &Rc<CommandLimit>) -> CmdTimer {
+    let mut sleep = Command::new("sleep");
+    sleep.arg("0.2");
+    CmdTimer {
+        time: TEST_UNIT,
+        activation: Some(sleep),
+        limit: Some(Rc::clone(limit)),
+        ..CmdTimer::default()
+    }
+}
+
+#[test]
+fn dropped_commands() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let limit = Rc::new(CommandLimit::new(2, QueueFull::Drop));
+    let mut timers: Vec<_> = (0..3).map(|_| sleeper(&limit)).collect();
+
+    for timer in &mut timers {
+        timer.activate().unwrap();
+    }
+
+    // The third activation was dropped
+    assert_eq!(limit.running().unwrap(), 2);
+    assert_eq!(limit.queued(), 0);
+    assert!(timers[1].activation_child.is_some());
+    assert!(timers[2].activation_child.is_none());
+
+    // Once the children exit, they're reaped
+    thread::sleep(TEST_UNIT * 6);
+    assert_eq!(limit.running().unwrap(), 0);
+}
+
+#[test]
+fn queued_commands() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let limit = Rc::new(CommandLimit::new(2, QueueFull::Queue));
+    let mut timers: Vec<_> = (0..3).map(|_| sleeper(&limit)).collect();
+
+    for timer in &mut timers {
+        timer.activate().unwrap();
+    }
+
+    // The third activation is waiting for a slot
+    assert_eq!(limit.running().unwrap(), 2);
+    assert_eq!(limit.queued(), 1);
+
+    // ... which it gets once the others exit
+    thread::sleep(TEST_UNIT * 6);
+    timers[0].time_left(Duration::default()).unwrap();
+    assert_eq!(limit.running().unwrap(), 1);
+    assert_eq!(limit.queued(), 0);
+
+    thread::sleep(TEST_UNIT * 6);
+    assert_eq!(limit.running().unwrap(), 0);
+}
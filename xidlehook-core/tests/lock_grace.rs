@@ -0,0 +1,53 @@
+use std::{
+    cell::Cell,
+    thread,
+    time::{Duration, Instant},
+};
+use xidlehook_core::{
+    timers::{CallbackTimer, LockGrace},
+    Action::*,
+    Timer, Xidlehook,
+};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+#[test]
+fn lock_grace() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let locked = Cell::new(0);
+    let suspended = Cell::new(0);
+
+    let grace = LockGrace::new(TEST_UNIT * 4);
+    let timers: Vec<Box<dyn Timer + '_>> = vec![
+        Box::new(grace.lock(CallbackTimer::new(TEST_UNIT * 2, || {
+            locked.set(locked.get() + 1)
+        }))),
+        Box::new(grace.after_lock(CallbackTimer::new(TEST_UNIT, || {
+            suspended.set(suspended.get() + 1)
+        }))),
+    ];
+    let mut timer = Xidlehook::new(timers);
+
+    // Without having locked, there's no grace time
+    assert_eq!(grace.remaining(), None);
+    let before_lock = Instant::now();
+    timer.poll(TEST_UNIT * 2).unwrap();
+    assert_eq!(locked.get(), 1);
+
+    // Even though the user has been idle for long enough, the suspend
+    // timer waits for the grace time
+    while suspended.get() == 0 {
+        match timer.poll(TEST_UNIT * 100).unwrap() {
+            Sleep(delay) => thread::sleep(delay),
+            action => panic!("expected to sleep, got {:?}", action),
+        }
+    }
+    assert!(before_lock.elapsed() >= TEST_UNIT * 4);
+    assert_eq!(grace.remaining(), Some(Duration::default()));
+    assert_eq!(suspended.get(), 1);
+
+    // Activity starts over
+    timer.poll(Duration::default()).unwrap();
+    assert_eq!(grace.remaining(), None);
+}
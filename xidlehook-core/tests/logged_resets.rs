@@ -0,0 +1,47 @@
+use std::{sync::Mutex, time::Duration};
+use xidlehook_core::{timers::CallbackTimer, Xidlehook};
+
+use log::{Log, Metadata, Record};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+/// Keeps the messages about resets
+struct ResetLogger(Mutex<Vec<String>>);
+impl Log for ResetLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.target() == "xidlehook_core::resets"
+    }
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.0.lock().unwrap().push(record.args().to_string());
+        }
+    }
+    fn flush(&self) {}
+}
+static LOGGER: ResetLogger = ResetLogger(Mutex::new(Vec::new()));
+
+#[test]
+fn logged_resets() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(log::LevelFilter::Info);
+
+    let mut timer = Xidlehook::new(vec![CallbackTimer::new(TEST_UNIT * 100, || ())]);
+
+    // Nothing is logged unless enabled
+    timer.poll(TEST_UNIT * 3).unwrap();
+    timer.poll(TEST_UNIT).unwrap();
+    assert!(LOGGER.0.lock().unwrap().is_empty());
+
+    timer.set_log_resets(true);
+    for &idle in &[5, 7, 0, 2, 1, 9, 3] {
+        timer.poll(TEST_UNIT * idle).unwrap();
+    }
+    assert_eq!(
+        *LOGGER.0.lock().unwrap(),
+        [
+            "Idle time fell from 350ms to 0ns, resetting",
+            "Idle time fell from 100ms to 50ms, resetting",
+            "Idle time fell from 450ms to 150ms, resetting",
+        ]
+    );
+}
@@ -0,0 +1,80 @@
+use xidlehook_core::modules::xcb::{decode_string, decode_u32s, MalformedProperty, OnMalformed};
+
+#[test]
+fn well_formed_properties() {
+    let atoms: Vec<u8> = [7u32, 42].iter().flat_map(|v| v.to_ne_bytes()).collect();
+    for &policy in &[
+        OnMalformed::Skip,
+        OnMalformed::Error,
+        OnMalformed::BestEffort,
+    ] {
+        assert_eq!(decode_u32s(32, &atoms, policy), Ok(vec![7, 42]));
+        assert_eq!(decode_string(8, b"mpv", policy), Ok("mpv".into()));
+
+        // A property that isn't set has format 0, which isn't malformed
+        assert_eq!(decode_u32s(0, &[], policy), Ok(vec![]));
+        assert_eq!(decode_string(0, &[], policy), Ok(String::new()));
+    }
+}
+
+#[test]
+fn malformed_properties() {
+    // A list of atoms some application stored as bytes
+    let atoms: Vec<u8> = [7u32, 42].iter().flat_map(|v| v.to_ne_bytes()).collect();
+    let mut misaligned = atoms.clone();
+    misaligned.push(1);
+
+    let wrong_format = Err(MalformedProperty {
+        expected: 32,
+        found: 8,
+    });
+    assert_eq!(decode_u32s(8, &atoms, OnMalformed::Skip), wrong_format);
+    assert_eq!(decode_u32s(8, &atoms, OnMalformed::Error), wrong_format);
+    assert_eq!(
+        decode_u32s(8, &atoms, OnMalformed::BestEffort),
+        Ok(vec![7, 42])
+    );
+    assert_eq!(
+        decode_u32s(8, &misaligned, OnMalformed::BestEffort),
+        Ok(vec![7, 42]),
+        "leftover bytes are dropped"
+    );
+
+    // ... and a string stored as 16-bit items
+    let wrong_format = Err(MalformedProperty {
+        expected: 8,
+        found: 16,
+    });
+    assert_eq!(decode_string(16, b"mpv\0", OnMalformed::Skip), wrong_format);
+    assert_eq!(
+        decode_string(16, b"mpv\0", OnMalformed::Error),
+        wrong_format
+    );
+    assert_eq!(
+        decode_string(16, b"mpv\0", OnMalformed::BestEffort),
+        Ok("mpv\0".into())
+    );
+
+    assert_eq!(
+        decode_string(16, b"mpv\0", OnMalformed::Error)
+            .unwrap_err()
+            .to_string(),
+        "property has format 16 instead of 8"
+    );
+}
+
+#[test]
+fn invalid_utf8_strings() {
+    // A latin-1 title, as legacy applications set it
+    let title = b"caf\xe9 \xe0 wine";
+    for &policy in &[
+        OnMalformed::Skip,
+        OnMalformed::Error,
+        OnMalformed::BestEffort,
+    ] {
+        assert_eq!(
+            decode_string(8, title, policy),
+            Ok("caf\u{FFFD} \u{FFFD} wine".into())
+        );
+    }
+}
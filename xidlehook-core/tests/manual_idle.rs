@@ -0,0 +1,45 @@
+use std::{cell::Cell, time::Duration};
+use xidlehook_core::{
+    modules::StopAt, sources::ManualIdleSource, timers::CallbackTimer, IdleSource, Xidlehook,
+};
+
+const TEST_UNIT: Duration = Duration::from_millis(10);
+
+#[test]
+fn manual_idle() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let source = ManualIdleSource::new(TEST_UNIT).with_resolution(TEST_UNIT);
+    assert_eq!(source.get_idle().unwrap(), TEST_UNIT);
+    assert_eq!(source.resolution(), TEST_UNIT);
+    source.advance(TEST_UNIT);
+    assert_eq!(source.get_idle().unwrap(), TEST_UNIT * 2);
+    source.set(Duration::default());
+    assert_eq!(source.get_idle().unwrap(), Duration::default());
+
+    // The main loop runs on whatever the source says, until the chain
+    // completes
+    let fired = Cell::new(0);
+    let xidlehook = Xidlehook::new(vec![
+        CallbackTimer::new(TEST_UNIT * 2, || fired.set(fired.get() + 1)),
+        CallbackTimer::new(TEST_UNIT * 2, || fired.set(fired.get() + 1)),
+    ])
+    .register(StopAt::completion());
+    let mut polls = 0;
+    xidlehook
+        .main_sync(&source, || {
+            source.advance(TEST_UNIT);
+            polls += 1;
+            polls > 100
+        })
+        .unwrap();
+    assert_eq!(fired.get(), 2);
+    assert!(polls < 100);
+
+    // Errors from the source end the loop
+    source.set_failing(true);
+    assert!(source.get_idle().is_err());
+    let xidlehook = Xidlehook::new(vec![CallbackTimer::new(TEST_UNIT, || ())]);
+    let boxed: Box<dyn IdleSource> = Box::new(source);
+    assert!(xidlehook.main_sync(&*boxed, || false).is_err());
+}
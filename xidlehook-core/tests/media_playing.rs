@@ -0,0 +1,94 @@
+#![cfg(feature = "dbus")]
+
+mod common;
+
+use common::private_bus;
+use xidlehook_core::{
+    modules::{media::OBJECT_PATH, NotWhenMediaPlaying},
+    Module, Progress, TimerInfo,
+};
+use zbus::blocking::connection;
+
+const INFO: TimerInfo = TimerInfo {
+    index: 0,
+    length: 1,
+};
+
+struct StubPlayer {
+    status: &'static str,
+}
+#[zbus::interface(name = "org.mpris.MediaPlayer2.Player")]
+impl StubPlayer {
+    #[zbus(property)]
+    fn playback_status(&self) -> String {
+        self.status.into()
+    }
+}
+
+#[test]
+fn media_playing() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let (_bus, address) = match private_bus() {
+        Some(bus) => bus,
+        None => return, // Don't fail where there's no D-Bus
+    };
+    let connect = || {
+        connection::Builder::address(&*address)
+            .unwrap()
+            .build()
+            .unwrap()
+    };
+    let player = |name: &str, status| {
+        connection::Builder::address(&*address)
+            .unwrap()
+            .name(format!("org.mpris.MediaPlayer2.{}", name))
+            .unwrap()
+            .serve_at(OBJECT_PATH, StubPlayer { status })
+            .unwrap()
+            .build()
+            .unwrap()
+    };
+    let module = || NotWhenMediaPlaying::with_connection(connect());
+
+    // No players at all
+    assert!(module().playing().unwrap().is_empty());
+    assert_eq!(module().pre_timer(INFO).unwrap(), Progress::Continue);
+
+    let paused = player("spotify", "Paused");
+    let _stopped = player("vlc", "Stopped");
+    assert_eq!(module().pre_timer(INFO).unwrap(), Progress::Continue);
+    drop(paused);
+
+    let _spotify = player("spotify", "Playing");
+    let _firefox = player("firefox.instance_1_42", "Playing");
+    // Not a player, even though it's named like one
+    let _other = connection::Builder::address(&*address)
+        .unwrap()
+        .name("org.mpris.MediaPlayer2")
+        .unwrap()
+        .build()
+        .unwrap();
+
+    let mut playing = module().playing().unwrap();
+    playing.sort();
+    assert_eq!(playing, ["firefox.instance_1_42", "spotify"]);
+    assert_eq!(module().pre_timer(INFO).unwrap(), Progress::Abort);
+
+    let only = |players: &[&str]| {
+        module().with_players(players.iter().map(|&player| player.into()).collect())
+    };
+    assert_eq!(
+        only(&["firefox"]).playing().unwrap(),
+        ["firefox.instance_1_42"]
+    );
+    assert_eq!(only(&["spotify"]).playing().unwrap(), ["spotify"]);
+    assert!(only(&["vlc", "spot"]).playing().unwrap().is_empty());
+    assert_eq!(only(&["vlc"]).pre_timer(INFO).unwrap(), Progress::Continue);
+
+    // The connection is kept
+    let mut module = only(&["spotify"]);
+    assert_eq!(module.pre_timer(INFO).unwrap(), Progress::Abort);
+    assert_eq!(module.pre_timer(INFO).unwrap(), Progress::Abort);
+    assert_eq!(module.describe().kind, "not-when-media-playing");
+}
@@ -0,0 +1,59 @@
+#![cfg(feature = "metrics")]
+
+use std::time::Duration;
+use xidlehook_core::{
+    metrics::Metrics, modules::Toggle, timers::CallbackTimer, Progress, Xidlehook,
+};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+#[test]
+fn metrics() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let metrics = Metrics::new();
+    let fullscreen = Toggle::new(Progress::Abort);
+    let mut xidlehook = Xidlehook::new(vec![
+        CallbackTimer::new(TEST_UNIT, || ()),
+        CallbackTimer::new(TEST_UNIT, || ()),
+        CallbackTimer::new(TEST_UNIT, || ()),
+    ])
+    .register(metrics.count_suppressions(fullscreen.clone()))
+    .with_observer(metrics.clone());
+    metrics.set_timers(xidlehook.timers().len());
+
+    xidlehook.poll(TEST_UNIT).unwrap();
+    xidlehook.poll(TEST_UNIT * 2).unwrap();
+    xidlehook.poll(Duration::default()).unwrap();
+    xidlehook.poll(TEST_UNIT).unwrap();
+
+    // A fullscreen window keeps the first timer from running
+    xidlehook.poll(Duration::default()).unwrap();
+    fullscreen.set(true);
+    xidlehook.poll(TEST_UNIT).unwrap();
+
+    assert_eq!(metrics.fires(0), 2);
+    assert_eq!(metrics.fires(1), 1);
+    assert_eq!(metrics.fires(2), 0);
+    assert_eq!(metrics.fullscreen_suppressions(), 1);
+
+    metrics.set_idle(Duration::from_millis(1500));
+    metrics.set_x_reconnects(2);
+    let page = metrics.render();
+    let lines: Vec<&str> = page.lines().collect();
+    for (name, kind) in &[
+        ("xidlehook_timer_fires_total", "counter"),
+        ("xidlehook_idle_seconds", "gauge"),
+        ("xidlehook_fullscreen_suppressions_total", "counter"),
+        ("xidlehook_x_reconnects_total", "counter"),
+    ] {
+        let declared = format!("# TYPE {} {}", name, kind);
+        assert!(lines.contains(&declared.as_str()), "{}", page);
+    }
+    assert!(lines.contains(&"xidlehook_timer_fires_total{timer=\"0\"} 2"));
+    assert!(lines.contains(&"xidlehook_timer_fires_total{timer=\"1\"} 1"));
+    assert!(lines.contains(&"xidlehook_timer_fires_total{timer=\"2\"} 0"));
+    assert!(lines.contains(&"xidlehook_idle_seconds 1.500"));
+    assert!(lines.contains(&"xidlehook_fullscreen_suppressions_total 1"));
+    assert!(lines.contains(&"xidlehook_x_reconnects_total 2"));
+}
@@ -0,0 +1,40 @@
+use std::{cell::Cell, time::Duration};
+use xidlehook_core::{
+    sources::{init_idle_source, MissingIdleSource},
+    Result,
+};
+
+const TEST_UNIT: Duration = Duration::from_millis(10);
+
+#[test]
+fn missing_idle_source() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let attempts = Cell::new(0);
+    // Becomes available on the third attempt
+    let init = || -> Result<&str> {
+        attempts.set(attempts.get() + 1);
+        if attempts.get() < 3 {
+            Err("no X server yet".into())
+        } else {
+            Ok("source")
+        }
+    };
+
+    let retry = MissingIdleSource::RetryForever {
+        interval: TEST_UNIT,
+    };
+    assert_eq!(init_idle_source(retry, init).unwrap(), Some("source"));
+    assert_eq!(attempts.get(), 3);
+
+    attempts.set(0);
+    assert!(init_idle_source(MissingIdleSource::Error, init).is_err());
+    assert_eq!(attempts.get(), 1);
+
+    attempts.set(0);
+    assert_eq!(
+        init_idle_source(MissingIdleSource::ExitZero, init).unwrap(),
+        None
+    );
+    assert_eq!(attempts.get(), 1);
+}
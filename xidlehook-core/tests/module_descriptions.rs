@@ -0,0 +1,63 @@
+use xidlehook_core::{
+    modules::{
+        description::REDACTED,
+        xcb::{FullscreenOptions, Rule, ScanMode},
+        ModuleDescription, ModuleList, Setting, StopAt, Toggle,
+    },
+    Module, Progress,
+};
+
+#[test]
+fn module_descriptions() {
+    let options = FullscreenOptions {
+        exceptions_wm_class1: vec!["mpv".into()],
+        exceptions: vec![Rule::Class("firefox".into())],
+        scan_mode: ScanMode::FullTree,
+        ..FullscreenOptions::default()
+    };
+    let fullscreen = options.describe();
+    assert_eq!(fullscreen.kind, "not-when-fullscreen");
+    assert!(fullscreen.enabled);
+    assert_eq!(
+        fullscreen.settings["exceptions_wm_class1"],
+        Setting::List(vec!["mpv".into()])
+    );
+    assert_eq!(
+        fullscreen.settings["exceptions_wm_class2"],
+        Setting::List(Vec::new())
+    );
+    assert_eq!(
+        fullscreen.settings["exceptions"],
+        Setting::List(vec![r#"Class("firefox")"#.into()])
+    );
+    assert_eq!(
+        fullscreen.settings["scan_mode"],
+        Setting::Text("FullTree".into())
+    );
+    assert!(!fullscreen.settings.contains_key("max_windows"));
+
+    // A list names its modules, and a toggle is only enabled while on
+    let presentation = Toggle::new(Progress::Reset);
+    let list = ModuleList::new()
+        .with("presentation", presentation.clone())
+        .with("once", StopAt::completion());
+    let descriptions = list.describe_all();
+    assert_eq!(descriptions[0].name, "presentation");
+    assert_eq!(descriptions[0].kind, "toggle");
+    assert!(!descriptions[0].enabled);
+    presentation.set(true);
+    assert!(list.describe_all()[0].enabled);
+    assert_eq!(descriptions[1].name, "once");
+    assert_eq!(
+        descriptions[1].settings["stop_after"],
+        Setting::Text("completion".into())
+    );
+
+    // Modules without a description of their own are named by type
+    struct Custom;
+    impl Module for Custom {}
+    assert_eq!(Custom.describe().kind, "Custom");
+
+    let secret = ModuleDescription::new("custom").with_secret("password");
+    assert_eq!(secret.settings["password"], Setting::Text(REDACTED.into()));
+}
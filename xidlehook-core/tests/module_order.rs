@@ -0,0 +1,81 @@
+use std::{cell::Cell, rc::Rc, time::Duration};
+use xidlehook_core::{
+    modules::ModuleList, timers::CallbackTimer, Module, Progress, Result, TimerInfo, Xidlehook,
+};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+/// Counts how often it's checked, and answers with a fixed progress
+struct Check(Rc<Cell<usize>>, Progress);
+impl Module for Check {
+    fn pre_timer(&mut self, _timer: TimerInfo) -> Result<Progress> {
+        self.0.set(self.0.get() + 1);
+        Ok(self.1)
+    }
+}
+
+#[test]
+fn module_order() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let cheap = Rc::new(Cell::new(0));
+    let expensive = Rc::new(Cell::new(0));
+    let triggered = Cell::new(0);
+
+    let modules = ModuleList::new()
+        .with(
+            "expensive",
+            Check(Rc::clone(&expensive), Progress::Continue),
+        )
+        .with("cheap", Check(Rc::clone(&cheap), Progress::Abort));
+    let mut timer = Xidlehook::new(vec![CallbackTimer::new(TEST_UNIT, || {
+        triggered.set(triggered.get() + 1)
+    })])
+    .register(modules);
+
+    // Both are checked while the expensive one comes first
+    timer.poll(TEST_UNIT).unwrap();
+    assert_eq!((expensive.get(), cheap.get()), (1, 1));
+
+    // Putting the cheap one first skips the expensive one
+    timer.module_mut().1.reorder_modules(vec![1, 0]).unwrap();
+    assert_eq!(timer.module().1.names(), vec!["cheap", "expensive"]);
+    timer.poll(Duration::default()).unwrap();
+    timer.poll(TEST_UNIT).unwrap();
+    assert_eq!((expensive.get(), cheap.get()), (1, 2));
+
+    // ... unless all modules are to be checked
+    timer.module_mut().1.set_short_circuit(false);
+    timer.poll(Duration::default()).unwrap();
+    timer.poll(TEST_UNIT).unwrap();
+    assert_eq!((expensive.get(), cheap.get()), (2, 3));
+
+    // The first answer still wins
+    assert_eq!(triggered.get(), 0);
+}
+
+#[test]
+fn invalid_module_orders() {
+    let counter = Rc::new(Cell::new(0));
+    let check = || Check(Rc::clone(&counter), Progress::Continue);
+    let mut modules = ModuleList::new()
+        .with("a", check())
+        .with("b", check())
+        .with("c", check());
+
+    assert!(modules.reorder_modules(vec![0, 1]).is_err());
+    assert!(modules.reorder_modules(vec![0, 1, 1]).is_err());
+    assert!(modules.reorder_modules(vec![0, 1, 3]).is_err());
+    assert!(modules.reorder_modules(vec![0, 1, 2, 0]).is_err());
+    assert_eq!(modules.names(), vec!["a", "b", "c"]);
+
+    modules.reorder_modules(vec![2, 0, 1]).unwrap();
+    assert_eq!(modules.names(), vec!["c", "a", "b"]);
+
+    // By name, the rest keep their order
+    modules.reorder_by_name(&["b"]).unwrap();
+    assert_eq!(modules.names(), vec!["b", "c", "a"]);
+    assert!(modules.reorder_by_name(&["d"]).is_err());
+    assert!(modules.reorder_by_name(&["a", "a"]).is_err());
+    assert_eq!(modules.names(), vec!["b", "c", "a"]);
+}
@@ -0,0 +1,61 @@
+#![cfg(feature = "dbus")]
+
+mod common;
+
+use common::private_bus;
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+use xidlehook_core::{sources::MutterIdleSource, IdleSource};
+use zbus::blocking::{connection, Connection};
+
+struct StubIdleMonitor {
+    idletime: AtomicU64,
+}
+#[zbus::interface(name = "org.gnome.Mutter.IdleMonitor")]
+impl StubIdleMonitor {
+    fn get_idletime(&self) -> u64 {
+        // Every call the user has been idle a bit longer
+        self.idletime.fetch_add(1000, Ordering::SeqCst)
+    }
+}
+
+#[test]
+fn mutter_idle() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let (_bus, address) = match private_bus() {
+        Some(bus) => bus,
+        None => return, // Don't fail where there's no D-Bus
+    };
+
+    // Without Mutter present on the bus, construction fails so that one can fall back
+    let conn = connection::Builder::address(&*address)
+        .unwrap()
+        .build()
+        .unwrap();
+    assert!(MutterIdleSource::with_connection(conn).is_err());
+
+    let _service = connection::Builder::address(&*address)
+        .unwrap()
+        .name("org.gnome.Mutter.IdleMonitor")
+        .unwrap()
+        .serve_at(
+            "/org/gnome/Mutter/IdleMonitor/Core",
+            StubIdleMonitor {
+                idletime: AtomicU64::new(1500),
+            },
+        )
+        .unwrap()
+        .build()
+        .unwrap();
+
+    let conn: Connection = connection::Builder::address(&*address)
+        .unwrap()
+        .build()
+        .unwrap();
+    let source = MutterIdleSource::with_connection(conn).unwrap();
+    assert_eq!(source.get_idle().unwrap(), Duration::from_millis(1500));
+    assert_eq!(source.get_idle().unwrap(), Duration::from_millis(2500));
+}
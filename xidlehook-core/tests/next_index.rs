@@ -0,0 +1,32 @@
+use std::time::Duration;
+use xidlehook_core::{timers::CallbackTimer, Xidlehook};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+#[test]
+fn next_index() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let mut disabled = CallbackTimer::new(TEST_UNIT * 10, || ());
+    disabled.disabled = true;
+    let mut timer = Xidlehook::new(vec![
+        CallbackTimer::new(TEST_UNIT * 10, || ()),
+        disabled,
+        CallbackTimer::new(TEST_UNIT * 10, || ()),
+    ]);
+
+    timer.poll(Duration::default()).unwrap();
+    assert_eq!(timer.next_index(), 0);
+
+    // It moves on as timers go off
+    timer.poll(TEST_UNIT * 10).unwrap();
+    assert_eq!(timer.next_index(), 1);
+
+    // ... and past disabled ones
+    timer.poll(TEST_UNIT * 20).unwrap();
+    assert_eq!(timer.next_index(), 3);
+
+    // It starts over when the user is active again
+    timer.poll(Duration::default()).unwrap();
+    assert_eq!(timer.next_index(), 0);
+}
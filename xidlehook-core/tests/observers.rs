@@ -0,0 +1,48 @@
+use std::{cell::RefCell, rc::Rc, time::Duration};
+use xidlehook_core::{modules::Toggle, timers::CallbackTimer, Progress, TimerInfo, Xidlehook};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+#[test]
+fn observers() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let verdicts = Rc::new(RefCell::new(Vec::new()));
+    let fired = RefCell::new(Vec::new());
+
+    let disabled = Toggle::new(Progress::Abort);
+    let mut timer = Xidlehook::new(vec![
+        CallbackTimer::new(TEST_UNIT, || fired.borrow_mut().push(0)),
+        CallbackTimer::new(TEST_UNIT, || fired.borrow_mut().push(1)),
+    ])
+    .register(disabled.clone())
+    .with_observer({
+        let verdicts = Rc::clone(&verdicts);
+        move |info: &TimerInfo, verdict| verdicts.borrow_mut().push((info.index, verdict))
+    });
+
+    // Timers fire as usual, and the observer sees them allowed
+    timer.poll(TEST_UNIT).unwrap();
+    timer.poll(TEST_UNIT * 2).unwrap();
+    assert_eq!(*fired.borrow(), vec![0, 1]);
+
+    // ... and sees them aborted
+    timer.poll(Duration::default()).unwrap();
+    disabled.set(true);
+    timer.poll(TEST_UNIT).unwrap();
+    assert_eq!(*fired.borrow(), vec![0, 1]);
+
+    // Forcing a timer runs it regardless
+    timer.trigger(1, TEST_UNIT, true).unwrap();
+    assert_eq!(*fired.borrow(), vec![0, 1, 1]);
+
+    assert_eq!(
+        *verdicts.borrow(),
+        vec![
+            (0, Progress::Continue),
+            (1, Progress::Continue),
+            (0, Progress::Abort),
+            (1, Progress::Continue),
+        ]
+    );
+}
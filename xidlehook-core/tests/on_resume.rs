@@ -0,0 +1,39 @@
+use std::{env, fs, process, thread, time::Duration};
+use xidlehook_core::{modules::OnResume, timers::CallbackTimer, Xidlehook};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+fn runs(path: &std::path::Path) -> Vec<String> {
+    fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .map(String::from)
+        .collect()
+}
+
+#[test]
+fn on_resume() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let path = env::temp_dir().join(format!("xidlehook-on-resume-{}", process::id()));
+    let _ = fs::remove_file(&path);
+    let script = format!("echo $XIDLEHOOK_PRIOR_IDLE >> {}", path.display());
+    let mut timer = Xidlehook::new(vec![CallbackTimer::new(TEST_UNIT * 10, || ())])
+        .register(OnResume::shell(&script));
+
+    // Starting up isn't returning
+    timer.poll(TEST_UNIT).unwrap();
+    timer.poll(TEST_UNIT * 2).unwrap();
+    thread::sleep(TEST_UNIT * 4);
+    assert!(runs(&path).is_empty());
+
+    // Idle past the first timer for 2 seconds, then the user returns
+    timer.poll(TEST_UNIT * 10).unwrap();
+    timer.poll(TEST_UNIT * 40).unwrap();
+    timer.poll(TEST_UNIT).unwrap();
+    timer.poll(TEST_UNIT * 2).unwrap();
+    thread::sleep(TEST_UNIT * 4);
+    assert_eq!(runs(&path), ["2"]);
+
+    let _ = fs::remove_file(&path);
+}
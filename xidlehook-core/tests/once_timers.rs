@@ -0,0 +1,104 @@
+mod common;
+
+use common::RecordingTimer;
+use std::{cell::RefCell, time::Duration};
+use xidlehook_core::Xidlehook;
+
+const SECOND: Duration = Duration::from_secs(1);
+
+enum Step {
+    /// Poll with this idle time in seconds
+    Poll(u64),
+    /// Start the chain over without the user, like `--watch-fullscreen`
+    Reset(u64),
+}
+use Step::*;
+
+fn run(xidlehook: &mut Xidlehook<RecordingTimer<'_>, ()>, steps: &[Step]) {
+    for step in steps {
+        match *step {
+            Poll(idle) => {
+                xidlehook.poll(Duration::from_secs(idle)).unwrap();
+            },
+            Reset(idle) => xidlehook.reset(Duration::from_secs(idle)).unwrap(),
+        }
+    }
+}
+
+#[test]
+fn once_timers() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let log = RefCell::new(Vec::new());
+    let timers = |once| {
+        vec![
+            RecordingTimer {
+                once,
+                ..RecordingTimer::new("dim", 10 * SECOND, &log)
+            },
+            RecordingTimer::new("lock", 20 * SECOND, &log),
+        ]
+    };
+
+    // Staying idle well past the threshold only fires each timer once
+    let mut xidlehook = Xidlehook::new(timers(true));
+    run(
+        &mut xidlehook,
+        &[
+            Poll(9),
+            Poll(10),
+            Poll(11),
+            Poll(29),
+            Poll(30),
+            Poll(500),
+            Poll(50_000),
+        ],
+    );
+    assert_eq!(*log.borrow(), ["dim", "lock"]);
+
+    // When the chain starts over without the user, timers fire again,
+    // unless they only do once
+    log.borrow_mut().clear();
+    let mut xidlehook = Xidlehook::new(timers(false));
+    run(&mut xidlehook, &[Poll(10), Reset(10), Poll(20), Poll(40)]);
+    assert_eq!(*log.borrow(), ["dim", "dim", "lock"]);
+
+    log.borrow_mut().clear();
+    let mut xidlehook = Xidlehook::new(timers(true));
+    run(
+        &mut xidlehook,
+        &[
+            Poll(10),
+            Reset(10),
+            Poll(20),
+            Poll(29),
+            Poll(30),
+            Reset(30),
+            Poll(45),
+            Poll(50),
+        ],
+    );
+    // The chain skips the dim timer, counting the lock timer from the
+    // reset, which can go off again
+    assert_eq!(*log.borrow(), ["dim", "lock", "lock"]);
+
+    // Once the user is active, it fires again
+    log.borrow_mut().clear();
+    run(&mut xidlehook, &[Poll(1), Poll(10), Reset(10), Poll(20)]);
+    assert_eq!(*log.borrow(), ["dim"]);
+}
+
+#[test]
+fn once_recurring_timers() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // A timer that only fires once doesn't recur either
+    let log = RefCell::new(Vec::new());
+    let mut xidlehook = Xidlehook::new(vec![RecordingTimer {
+        once: true,
+        recurring: Some(5 * SECOND),
+        ..RecordingTimer::new("dim", 10 * SECOND, &log)
+    }]);
+    run(&mut xidlehook, &[Poll(10), Poll(15), Poll(20), Poll(100)]);
+    assert_eq!(*log.borrow(), ["dim"]);
+}
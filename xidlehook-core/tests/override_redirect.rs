@@ -0,0 +1,34 @@
+use xidlehook_core::modules::xcb::{FullscreenOptions, Rect};
+
+#[test]
+fn override_redirect() {
+    let screen = Rect {
+        x: 0,
+        y: 0,
+        width: 3840,
+        height: 1080,
+    };
+    assert!(screen.covers(&screen));
+
+    // SDL games often make their windows a bit larger than the screen
+    let game = Rect {
+        x: -1,
+        y: -1,
+        width: 3842,
+        height: 1082,
+    };
+    assert!(game.covers(&screen));
+    assert!(!screen.covers(&game));
+
+    // Covering one monitor isn't covering the screen
+    let one_monitor = Rect {
+        width: 1920,
+        ..screen
+    };
+    assert!(!one_monitor.covers(&screen));
+    let shifted = Rect { y: 1, ..screen };
+    assert!(!shifted.covers(&screen));
+
+    // It's a guess, so it's off unless asked for
+    assert!(!FullscreenOptions::default().override_redirect);
+}
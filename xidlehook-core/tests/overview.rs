@@ -0,0 +1,55 @@
+#![cfg(feature = "serde")]
+
+use xidlehook_core::{
+    status::{Inhibition, Overview, TimerStatus},
+    Progress,
+};
+
+fn overview() -> Overview {
+    Overview {
+        idle_ms: 61_500,
+        fullscreen: true,
+        inhibition: Inhibition::new(vec![("not-when-audio", Progress::Abort)]),
+        timers: vec![
+            TimerStatus {
+                index: 0,
+                duration_ms: 60_000,
+                fired: true,
+                command: String::from("xset dpms force off"),
+            },
+            TimerStatus {
+                index: 1,
+                duration_ms: 120_000,
+                fired: false,
+                command: String::new(),
+            },
+        ],
+    }
+}
+
+#[test]
+fn overview_round_trip() {
+    let overview = overview();
+    let json = serde_json::to_value(&overview).unwrap();
+    assert_eq!(json["idle_ms"], 61_500);
+    assert_eq!(json["fullscreen"], true);
+    assert_eq!(json["timers"][0]["index"], 0);
+    assert_eq!(json["timers"][0]["duration_ms"], 60_000);
+    assert_eq!(json["timers"][0]["fired"], true);
+    assert_eq!(json["timers"][0]["command"], "xset dpms force off");
+
+    let parsed: Overview = serde_json::from_value(json).unwrap();
+    assert_eq!(parsed, overview);
+}
+
+#[test]
+fn overview_text() {
+    assert_eq!(
+        overview().to_string(),
+        "idle: 61.5s\n\
+         fullscreen: yes\n\
+         inhibited by: not-when-audio (abort)\n\
+         timer 0: 60s, fired, xset dpms force off\n\
+         timer 1: 120s, pending\n"
+    );
+}
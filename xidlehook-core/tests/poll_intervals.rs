@@ -0,0 +1,109 @@
+use std::time::Duration;
+use xidlehook_core::{
+    modules::ModuleList, timers::CallbackTimer, Action::*, Module, Result, Timer, Xidlehook,
+};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+/// A timer whose canceller wants to run quickly
+struct Urgent(Duration);
+impl Timer for Urgent {
+    fn time_left(&mut self, idle_time: Duration) -> Result<Option<Duration>> {
+        Ok(self
+            .0
+            .checked_sub(idle_time)
+            .filter(|&left| left > Duration::default()))
+    }
+    fn abort_urgency(&self) -> Option<Duration> {
+        Some(TEST_UNIT)
+    }
+}
+
+#[test]
+fn poll_intervals() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let mut timer = Xidlehook::new(vec![Urgent(TEST_UNIT * 100), Urgent(TEST_UNIT * 100)])
+        .with_poll_interval_min(TEST_UNIT * 10)
+        .with_poll_interval_max(TEST_UNIT * 40);
+
+    // Long sleeps are cut down to the maximum
+    assert_eq!(timer.poll(TEST_UNIT).unwrap(), Sleep(TEST_UNIT * 40));
+    assert_eq!(timer.poll(TEST_UNIT * 30).unwrap(), Sleep(TEST_UNIT * 40));
+    assert_eq!(timer.poll(TEST_UNIT * 70).unwrap(), Sleep(TEST_UNIT * 30));
+
+    // A timer about to activate overrides the minimum
+    assert_eq!(timer.poll(TEST_UNIT * 95).unwrap(), Sleep(TEST_UNIT * 5));
+
+    // The abort urgency of the activated timer is raised to the minimum
+    assert_eq!(timer.poll(TEST_UNIT * 100).unwrap(), Sleep(TEST_UNIT * 10));
+    assert_eq!(timer.poll(TEST_UNIT * 150).unwrap(), Sleep(TEST_UNIT * 10));
+    assert_eq!(timer.poll(TEST_UNIT * 195).unwrap(), Sleep(TEST_UNIT * 5));
+
+    // Without bounds, the abort urgency is used as is
+    timer.set_poll_interval_min(None);
+    timer.set_poll_interval_max(None);
+    assert_eq!(timer.poll(TEST_UNIT * 2).unwrap(), Sleep(TEST_UNIT * 98));
+    assert_eq!(timer.poll(TEST_UNIT * 100).unwrap(), Sleep(TEST_UNIT));
+}
+
+#[test]
+fn poll_interval_max_without_timers() {
+    let mut timer = Xidlehook::<Urgent, ()>::new(vec![]);
+    assert_eq!(timer.poll(TEST_UNIT).unwrap(), Forever);
+
+    timer.set_poll_interval_max(Some(TEST_UNIT * 40));
+    assert_eq!(timer.poll(TEST_UNIT).unwrap(), Sleep(TEST_UNIT * 40));
+}
+
+/// A module which wants timers to be checked this often
+struct Polling(Option<Duration>);
+impl Module for Polling {
+    fn poll_interval(&self) -> Option<Duration> {
+        self.0
+    }
+}
+
+#[test]
+fn module_poll_intervals() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let timers = || {
+        vec![
+            CallbackTimer::new(TEST_UNIT * 10, || ()),
+            CallbackTimer::new(TEST_UNIT * 20, || ()),
+        ]
+    };
+
+    // Without modules asking, it sleeps until the next timer is due
+    let mut timer = Xidlehook::new(timers()).register(Polling(None));
+    assert_eq!(timer.poll(TEST_UNIT * 4).unwrap(), Sleep(TEST_UNIT * 6));
+    // After the first timer, the second one is due after it, but the
+    // user may become active and go idle again meanwhile
+    assert_eq!(timer.poll(TEST_UNIT * 10).unwrap(), Sleep(TEST_UNIT * 10));
+    assert_eq!(timer.poll(TEST_UNIT * 25).unwrap(), Sleep(TEST_UNIT * 5));
+
+    // The shortest interval any module asks for wins if it's sooner
+    let modules = ModuleList::new()
+        .with("slow", Polling(Some(TEST_UNIT * 8)))
+        .with("fast", Polling(Some(TEST_UNIT * 2)))
+        .with("none", Polling(None));
+    let mut timer = Xidlehook::new(timers()).register(modules);
+    assert_eq!(timer.poll(TEST_UNIT * 4).unwrap(), Sleep(TEST_UNIT * 2));
+    assert_eq!(timer.poll(TEST_UNIT * 9).unwrap(), Sleep(TEST_UNIT));
+
+    let mut timer = Xidlehook::new(timers())
+        .register(Polling(Some(TEST_UNIT * 3)))
+        .register(Polling(Some(TEST_UNIT * 5)));
+    assert_eq!(timer.poll(TEST_UNIT * 4).unwrap(), Sleep(TEST_UNIT * 3));
+
+    // Like abort urgencies, it's raised to the minimum poll interval
+    timer.set_poll_interval_min(Some(TEST_UNIT * 4));
+    assert_eq!(timer.poll(TEST_UNIT * 4).unwrap(), Sleep(TEST_UNIT * 4));
+
+    // It's still woken up while the chain is aborted, such as to
+    // check for activity
+    timer.abort().unwrap();
+    timer.set_poll_interval_min(None);
+    assert_eq!(timer.poll(TEST_UNIT * 5).unwrap(), Sleep(TEST_UNIT * 3));
+}
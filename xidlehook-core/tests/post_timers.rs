@@ -0,0 +1,62 @@
+use std::{cell::RefCell, rc::Rc, time::Duration};
+use xidlehook_core::{timers::CallbackTimer, Module, Progress, Result, TimerInfo, Xidlehook};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+/// Records what happened, in order
+type Log = Rc<RefCell<Vec<String>>>;
+
+struct Audit {
+    log: Log,
+    abort: Rc<RefCell<bool>>,
+}
+impl Module for Audit {
+    fn pre_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        if *self.abort.borrow() {
+            return Ok(Progress::Abort);
+        }
+        self.log.borrow_mut().push(format!("pre {}", timer.index));
+        Ok(Progress::Continue)
+    }
+    fn post_timer(&mut self, timer: TimerInfo) -> Result<Progress> {
+        self.log
+            .borrow_mut()
+            .push(format!("post {}/{}", timer.index, timer.length));
+        Ok(Progress::Continue)
+    }
+}
+
+#[test]
+fn post_timers() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let log = Log::default();
+    let abort = Rc::new(RefCell::new(false));
+    let fired = |index: usize| {
+        let log = Rc::clone(&log);
+        move || log.borrow_mut().push(format!("fired {}", index))
+    };
+    let mut timer = Xidlehook::new(vec![
+        CallbackTimer::new(TEST_UNIT * 10, fired(0)),
+        CallbackTimer::new(TEST_UNIT * 10, fired(1)),
+    ])
+    .register(Audit {
+        log: Rc::clone(&log),
+        abort: Rc::clone(&abort),
+    });
+
+    // The hook runs once the action has run
+    timer.poll(TEST_UNIT * 10).unwrap();
+    timer.poll(TEST_UNIT * 20).unwrap();
+    assert_eq!(
+        *log.borrow(),
+        ["pre 0", "fired 0", "post 0/2", "pre 1", "fired 1", "post 1/2"]
+    );
+
+    // ... and not at all for a timer that was kept from running
+    log.borrow_mut().clear();
+    *abort.borrow_mut() = true;
+    timer.poll(TEST_UNIT).unwrap();
+    timer.poll(TEST_UNIT * 10).unwrap();
+    assert!(log.borrow().is_empty());
+}
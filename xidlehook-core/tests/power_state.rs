@@ -0,0 +1,67 @@
+use std::{env, fs, path::PathBuf};
+use xidlehook_core::{
+    modules::{PowerSource, PowerState},
+    Module, Progress, TimerInfo,
+};
+
+const INFO: TimerInfo = TimerInfo {
+    index: 0,
+    length: 1,
+};
+
+/// A fake `/sys/class/power_supply` with these supplies
+fn sysfs_dir(name: &str, supplies: &[(&str, &str, &str)]) -> PathBuf {
+    let dir = env::temp_dir().join(format!("xidlehook-test-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    for (supply, kind, online) in supplies {
+        fs::create_dir(dir.join(supply)).unwrap();
+        fs::write(dir.join(supply).join("type"), format!("{}\n", kind)).unwrap();
+        fs::write(dir.join(supply).join("online"), format!("{}\n", online)).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn power_state() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dir = sysfs_dir(
+        "laptop",
+        &[
+            ("AC", "Mains", "0"),
+            ("ADP1", "Mains", "0"),
+            ("BAT0", "Battery", "1"),
+        ],
+    );
+    let mut on_battery = PowerState::new(PowerSource::Battery).with_sysfs_dir(&dir);
+    let mut on_ac = PowerState::new(PowerSource::Ac).with_sysfs_dir(&dir);
+    assert_eq!(on_battery.source(), PowerSource::Battery);
+    assert_eq!(on_battery.pre_timer(INFO).unwrap(), Progress::Abort);
+    assert_eq!(on_ac.pre_timer(INFO).unwrap(), Progress::Continue);
+
+    // Any of several adapters is enough
+    fs::write(dir.join("ADP1").join("online"), "1\n").unwrap();
+    assert_eq!(on_battery.source(), PowerSource::Ac);
+    assert_eq!(on_battery.pre_timer(INFO).unwrap(), Progress::Continue);
+    assert_eq!(on_ac.pre_timer(INFO).unwrap(), Progress::Abort);
+
+    // Adapters that went away are looked for again
+    fs::remove_dir_all(dir.join("AC")).unwrap();
+    fs::remove_dir_all(dir.join("ADP1")).unwrap();
+    assert_eq!(on_battery.source(), PowerSource::Ac);
+    fs::create_dir(dir.join("AC0")).unwrap();
+    fs::write(dir.join("AC0").join("type"), "Mains\n").unwrap();
+    fs::write(dir.join("AC0").join("online"), "0\n").unwrap();
+    assert_eq!(on_battery.source(), PowerSource::Battery);
+
+    // Without any adapter, like a desktop, it's on AC
+    let desktop = sysfs_dir("desktop", &[]);
+    let mut module = PowerState::new(PowerSource::Battery).with_sysfs_dir(&desktop);
+    assert_eq!(module.source(), PowerSource::Ac);
+    let mut module = PowerState::new(PowerSource::Battery).with_sysfs_dir(desktop.join("missing"));
+    assert_eq!(module.source(), PowerSource::Ac);
+
+    fs::remove_dir_all(&dir).unwrap();
+    fs::remove_dir_all(&desktop).unwrap();
+}
@@ -0,0 +1,47 @@
+use std::{cell::RefCell, rc::Rc, time::Duration};
+use xidlehook_core::{timers::CallbackTimer, Module, Result, Xidlehook};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+#[derive(Default)]
+struct Returns {
+    resets: Vec<Duration>,
+    actives: Vec<Duration>,
+}
+
+struct RecordReturns(Rc<RefCell<Returns>>);
+impl Module for RecordReturns {
+    fn reset(&mut self, prior_idle: Duration) -> Result<()> {
+        self.0.borrow_mut().resets.push(prior_idle);
+        Ok(())
+    }
+    fn on_active(&mut self, prior_idle: Duration) -> Result<()> {
+        self.0.borrow_mut().actives.push(prior_idle);
+        Ok(())
+    }
+}
+
+#[test]
+fn prior_idle() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let returns = Rc::new(RefCell::new(Returns::default()));
+    let mut timer = Xidlehook::new(vec![
+        CallbackTimer::new(TEST_UNIT * 10, || ()),
+        CallbackTimer::new(TEST_UNIT * 10, || ()),
+    ])
+    .register(RecordReturns(Rc::clone(&returns)));
+
+    // Idle past the first timer, then the user returns
+    timer.poll(TEST_UNIT * 10).unwrap();
+    timer.poll(TEST_UNIT * 14).unwrap();
+    timer.poll(TEST_UNIT).unwrap();
+    assert_eq!(returns.borrow().resets, [TEST_UNIT * 14]);
+    assert_eq!(returns.borrow().actives, [TEST_UNIT * 14]);
+
+    // Each return reports its own idle stretch, from the last poll
+    timer.poll(TEST_UNIT * 25).unwrap();
+    timer.poll(TEST_UNIT * 2).unwrap();
+    assert_eq!(returns.borrow().resets, [TEST_UNIT * 14, TEST_UNIT * 25]);
+    assert_eq!(returns.borrow().actives, [TEST_UNIT * 14, TEST_UNIT * 25]);
+}
@@ -0,0 +1,90 @@
+use std::{env, fs, path::PathBuf};
+use xidlehook_core::{modules::NotWhenProcess, Module, Progress, TimerInfo};
+
+const INFO: TimerInfo = TimerInfo {
+    index: 0,
+    length: 1,
+};
+
+/// A fake `/proc` with these processes, by PID
+fn proc_dir(name: &str, processes: &[(&str, &str, &str)]) -> PathBuf {
+    let dir = env::temp_dir().join(format!("xidlehook-test-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("self")).unwrap();
+    for (pid, comm, cmdline) in processes {
+        fs::create_dir(dir.join(pid)).unwrap();
+        fs::write(dir.join(pid).join("comm"), format!("{}\n", comm)).unwrap();
+        fs::write(dir.join(pid).join("cmdline"), cmdline).unwrap();
+    }
+    // A process that exited while being scanned
+    fs::create_dir(dir.join("999")).unwrap();
+    dir
+}
+
+#[test]
+fn process_exceptions() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dir = proc_dir(
+        "processes",
+        &[
+            ("1", "systemd", "/sbin/init\0"),
+            ("20", "bash", "bash\0backup.sh\0--full\0"),
+            ("300", "steam-runtime-l", "steam-runtime-launcher\0"),
+        ],
+    );
+    let module = |names: &[&str]| {
+        NotWhenProcess::new(names.iter().map(|&name| name.to_owned()).collect()).with_proc_dir(&dir)
+    };
+
+    assert_eq!(module(&["systemd"]).matching().unwrap(), ["systemd"]);
+    assert!(module(&["system"]).matching().unwrap().is_empty());
+    assert_eq!(
+        module(&["system"]).with_substring(true).matching().unwrap(),
+        ["systemd"]
+    );
+
+    // Names the kernel cut off still match exactly
+    assert_eq!(
+        module(&["steam-runtime-launcher"]).matching().unwrap(),
+        ["steam-runtime-l"]
+    );
+
+    // The command line is only looked at if asked to
+    assert!(module(&["backup.sh"])
+        .with_substring(true)
+        .matching()
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        module(&["backup.sh"])
+            .with_substring(true)
+            .with_cmdline(true)
+            .matching()
+            .unwrap(),
+        ["bash"]
+    );
+    assert_eq!(
+        module(&["bash backup.sh --full"])
+            .with_cmdline(true)
+            .matching()
+            .unwrap(),
+        ["bash"]
+    );
+
+    assert_eq!(
+        module(&["rsync", "bash"]).pre_timer(INFO).unwrap(),
+        Progress::Abort
+    );
+    assert_eq!(
+        module(&["rsync"]).pre_timer(INFO).unwrap(),
+        Progress::Continue
+    );
+
+    // Without /proc, timers aren't held up
+    let mut missing = NotWhenProcess::new(vec!["bash".into()]).with_proc_dir(dir.join("missing"));
+    assert!(missing.matching().is_err());
+    assert_eq!(missing.pre_timer(INFO).unwrap(), Progress::Continue);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
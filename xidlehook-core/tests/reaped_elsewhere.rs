@@ -0,0 +1,52 @@
+use nix::sys::wait::{self, WaitPidFlag, WaitStatus};
+use std::{process::Command, thread, time::Duration};
+use xidlehook_core::{timers::CmdTimer, Timer};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+/// Reap every exited child of the process, like a `SIGCHLD` handler
+/// which doesn't know whose they are
+fn reap_all() {
+    while let Ok(status) = wait::waitpid(None, Some(WaitPidFlag::WNOHANG)) {
+        if status == WaitStatus::StillAlive {
+            break;
+        }
+    }
+}
+
+// This is the only test in the file, since reaping every child would
+// steal them from other tests running alongside
+#[test]
+fn reaped_elsewhere() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let mut timer = CmdTimer {
+        time: TEST_UNIT,
+        activation: Some(Command::new("true")),
+        warning: Some(Command::new("true")),
+        warning_lead: TEST_UNIT,
+        relock_on_crash: true,
+        kill_on_resume: true,
+        ..CmdTimer::default()
+    };
+
+    // A warning that already exited and was reaped has nothing to cancel
+    timer.warn().unwrap();
+    thread::sleep(TEST_UNIT * 2);
+    reap_all();
+    timer.cancel_warning().unwrap();
+
+    // Nor does a command that was reaped need a signal, and whether it
+    // crashed is unknown, so it isn't run again
+    timer.activate().unwrap();
+    thread::sleep(TEST_UNIT * 2);
+    reap_all();
+    assert_eq!(timer.supervise().unwrap(), None);
+    assert!(timer.activation_child.is_none());
+
+    timer.activate().unwrap();
+    thread::sleep(TEST_UNIT * 2);
+    reap_all();
+    timer.reset().unwrap();
+    assert!(!timer.disabled());
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::RecordingTimer;
+use std::{cell::RefCell, time::Duration};
+use xidlehook_core::{Action, Xidlehook};
+
+const SECOND: Duration = Duration::from_secs(1);
+
+/// Poll with these idle times in seconds, returning how long to sleep after each
+fn feed(timers: Vec<RecordingTimer<'_>>, idle_secs: &[u64]) -> Vec<Action> {
+    let mut xidlehook = Xidlehook::new(timers);
+    idle_secs
+        .iter()
+        .map(|&idle| xidlehook.poll(Duration::from_secs(idle)).unwrap())
+        .collect()
+}
+
+#[test]
+fn recurring_timers() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // Fires at 10s, then every 5s while the user stays idle
+    let log = RefCell::new(Vec::new());
+    let timer = || RecordingTimer {
+        recurring: Some(5 * SECOND),
+        ..RecordingTimer::new("dim", 10 * SECOND, &log)
+    };
+    let actions = feed(vec![timer()], &[9, 10, 12, 15, 19, 20, 26]);
+    assert_eq!(log.borrow().len(), 4);
+    // Sleeps until the next repeat is due
+    assert_eq!(actions[1], Action::Sleep(5 * SECOND));
+    assert_eq!(actions[2], Action::Sleep(3 * SECOND));
+    // Polling late fires once, and counts the interval from then
+    assert_eq!(actions[6], Action::Sleep(5 * SECOND));
+
+    // Activity goes back to the normal schedule
+    log.borrow_mut().clear();
+    feed(vec![timer()], &[10, 15, 2, 9, 10, 14, 15]);
+    assert_eq!(log.borrow().len(), 4);
+
+    // Once a later timer fires, the earlier one stops repeating
+    log.borrow_mut().clear();
+    let lock = RecordingTimer::new("lock", 12 * SECOND, &log);
+    feed(vec![timer(), lock], &[10, 15, 20, 22, 27, 40]);
+    assert_eq!(*log.borrow(), ["dim", "dim", "dim", "lock"]);
+}
+
+#[test]
+fn zero_interval() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // A zero interval means not recurring, rather than going off on every poll
+    let log = RefCell::new(Vec::new());
+    let timer = |recurring| RecordingTimer {
+        recurring,
+        ..RecordingTimer::new("dim", 10 * SECOND, &log)
+    };
+    let actions = feed(vec![timer(Some(Duration::default()))], &[10, 10, 15]);
+    assert_eq!(*log.borrow(), ["dim"]);
+    assert_eq!(actions, feed(vec![timer(None)], &[10, 10, 15]));
+}
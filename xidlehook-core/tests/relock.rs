@@ -0,0 +1,61 @@
+use std::{cell::RefCell, process::Command, rc::Rc, thread, time::Duration};
+use xidlehook_core::{
+    timers::{CmdTimer, SpawnHook},
+    Xidlehook,
+};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+/// Counts the locks, the first of which crashes shortly while the
+/// later ones exit successfully, like when the user unlocks
+fn locker(locks: &Rc<RefCell<usize>>) -> SpawnHook {
+    let locks = Rc::clone(locks);
+    SpawnHook::new(move |_: &Command| {
+        *locks.borrow_mut() += 1;
+        let script = if *locks.borrow() == 1 {
+            "sleep 0.1; kill -9 $$"
+        } else {
+            "true"
+        };
+        Ok(Command::new("sh").arg("-c").arg(script).spawn()?)
+    })
+}
+
+fn lock_timer(hook: SpawnHook, relock_on_crash: bool) -> CmdTimer {
+    CmdTimer {
+        time: TEST_UNIT,
+        activation: Some(Command::new("i3lock")),
+        spawn_hook: Some(hook),
+        relock_on_crash,
+        ..CmdTimer::default()
+    }
+}
+
+#[test]
+fn relock() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let locks = Rc::new(RefCell::new(0));
+    let mut timer = Xidlehook::new(vec![lock_timer(locker(&locks), true)]);
+
+    timer.poll(TEST_UNIT).unwrap();
+    assert_eq!(*locks.borrow(), 1);
+
+    // The locker was killed while the user is still idle
+    thread::sleep(TEST_UNIT * 4);
+    timer.poll(TEST_UNIT * 2).unwrap();
+    assert_eq!(*locks.borrow(), 2);
+
+    // The new one exits successfully, which isn't a crash
+    thread::sleep(TEST_UNIT * 4);
+    timer.poll(TEST_UNIT * 3).unwrap();
+    assert_eq!(*locks.borrow(), 2);
+
+    // Without relock_on_crash, a crashed locker stays dead
+    let locks = Rc::new(RefCell::new(0));
+    let mut timer = Xidlehook::new(vec![lock_timer(locker(&locks), false)]);
+    timer.poll(TEST_UNIT).unwrap();
+    thread::sleep(TEST_UNIT * 4);
+    timer.poll(TEST_UNIT * 2).unwrap();
+    assert_eq!(*locks.borrow(), 1);
+}
@@ -0,0 +1,35 @@
+use std::{cell::Cell, time::Duration};
+use xidlehook_core::{timers::CallbackTimer, Xidlehook};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+#[test]
+fn removed_timers() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let triggered = Cell::new(0);
+    let timer = || {
+        CallbackTimer::new(TEST_UNIT, || {
+            triggered.set(triggered.get() + 1);
+        })
+    };
+    let mut xidlehook = Xidlehook::new(vec![timer(), timer()]);
+
+    xidlehook.poll(TEST_UNIT).unwrap();
+    xidlehook.poll(TEST_UNIT * 2).unwrap();
+    assert_eq!(triggered.get(), 2);
+    assert_eq!(xidlehook.next_index(), 2);
+
+    // Changing the timers stops the chain, even when the ones that went
+    // off are removed, and it picks up again once the user was active
+    xidlehook.timers_mut().unwrap().truncate(1);
+    assert!(xidlehook.is_aborted());
+    xidlehook.poll(TEST_UNIT * 3).unwrap();
+    xidlehook.timers_mut().unwrap().push(timer());
+    xidlehook.poll(Duration::default()).unwrap();
+    assert_eq!(xidlehook.next_index(), 0);
+
+    xidlehook.poll(TEST_UNIT).unwrap();
+    xidlehook.poll(TEST_UNIT * 2).unwrap();
+    assert_eq!(triggered.get(), 4);
+}
@@ -0,0 +1,75 @@
+use std::{cell::RefCell, time::Duration};
+use xidlehook_core::{
+    modules::{ResetOnRelease, Toggle},
+    timers::CallbackTimer,
+    Module, Progress, TimerInfo, Xidlehook,
+};
+
+/// Simulate a video from 300s to 800s of idle time, a window being
+/// fullscreen that resets the chain, and return the idle times at which
+/// the first timer fired
+fn watch_video<M: Module>(wrap: impl FnOnce(Toggle) -> M) -> Vec<u64> {
+    let fired = RefCell::new(Vec::new());
+    let idle = RefCell::new(0);
+    let fullscreen = Toggle::new(Progress::Reset);
+
+    let timers = vec![
+        CallbackTimer::new(Duration::from_secs(300), || {
+            fired.borrow_mut().push(*idle.borrow())
+        }),
+        CallbackTimer::new(Duration::from_secs(60), || ()),
+    ];
+    let mut xidlehook = Xidlehook::new(timers).register(wrap(fullscreen.clone()));
+
+    for secs in (0..=1300).step_by(100) {
+        fullscreen.set((300..800).contains(&secs));
+        *idle.borrow_mut() = secs;
+        xidlehook.poll(Duration::from_secs(secs)).unwrap();
+    }
+    drop(xidlehook);
+    fired.into_inner()
+}
+
+#[test]
+fn reset_on_release() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // The last reset was while the video played, so the timer goes off
+    // only 100s after it ended
+    assert_eq!(watch_video(|module| module), [900]);
+
+    // Once the video is over, the whole timeout starts over
+    assert_eq!(watch_video(ResetOnRelease::new), [1200]);
+}
+
+#[test]
+fn held_verdicts() {
+    let toggle = Toggle::new(Progress::Abort);
+    let mut module = ResetOnRelease::new(toggle.clone());
+    let timer = TimerInfo {
+        index: 0,
+        length: 1,
+    };
+
+    assert_eq!(module.pre_timer(timer).unwrap(), Progress::Continue);
+    toggle.set(true);
+    assert_eq!(module.pre_timer(timer).unwrap(), Progress::Abort);
+    assert!(module.is_held());
+
+    // Released, the chain starts over once
+    toggle.set(false);
+    assert_eq!(module.pre_timer(timer).unwrap(), Progress::Reset);
+    assert_eq!(module.pre_timer(timer).unwrap(), Progress::Continue);
+
+    // The user being active is a fresh start already
+    toggle.set(true);
+    module.pre_timer(timer).unwrap();
+    module.on_active(Duration::from_secs(10)).unwrap();
+    toggle.set(false);
+    assert_eq!(module.pre_timer(timer).unwrap(), Progress::Continue);
+
+    assert_eq!(
+        module.describe(),
+        toggle.describe().with("reset_on_release", true)
+    );
+}
@@ -0,0 +1,57 @@
+use std::{cell::Cell, thread, time::Duration};
+use xidlehook_core::{timers::CallbackTimer, Action::*, Xidlehook};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+#[test]
+fn resume_grace() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dimmed = Cell::new(0);
+    let timers = vec![CallbackTimer::new(TEST_UNIT, || {
+        dimmed.set(dimmed.get() + 1)
+    })];
+    let mut xidlehook = Xidlehook::new(timers).with_resume_grace(TEST_UNIT * 6);
+    assert_eq!(xidlehook.resume_grace(), TEST_UNIT * 6);
+
+    // Before the user ever returned, there's no grace period
+    xidlehook.poll(TEST_UNIT).unwrap();
+    assert_eq!(dimmed.get(), 1);
+
+    // Moving the mouse to undo the dim and going idle again right away
+    // doesn't dim again until the grace period is over
+    xidlehook.poll(Duration::default()).unwrap();
+    match xidlehook.poll(TEST_UNIT * 2).unwrap() {
+        Sleep(delay) => assert!(delay <= TEST_UNIT * 6, "{:?}", delay),
+        action => panic!("expected to sleep, got {:?}", action),
+    }
+    assert_eq!(dimmed.get(), 1);
+    thread::sleep(TEST_UNIT * 6);
+    xidlehook.poll(TEST_UNIT * 8).unwrap();
+    assert_eq!(dimmed.get(), 2);
+
+    // Being active again before any timer went off isn't returning
+    xidlehook.poll(Duration::default()).unwrap();
+    thread::sleep(TEST_UNIT * 6);
+    xidlehook.poll(TEST_UNIT / 2).unwrap();
+    xidlehook.poll(Duration::default()).unwrap();
+    xidlehook.poll(TEST_UNIT).unwrap();
+    assert_eq!(dimmed.get(), 3);
+}
+
+#[test]
+fn no_resume_grace() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // By default, timers fire again as soon as they would
+    let dimmed = Cell::new(0);
+    let timers = vec![CallbackTimer::new(TEST_UNIT, || {
+        dimmed.set(dimmed.get() + 1)
+    })];
+    let mut xidlehook = Xidlehook::new(timers);
+    assert_eq!(xidlehook.resume_grace(), Duration::default());
+    xidlehook.poll(TEST_UNIT).unwrap();
+    xidlehook.poll(Duration::default()).unwrap();
+    xidlehook.poll(TEST_UNIT).unwrap();
+    assert_eq!(dimmed.get(), 2);
+}
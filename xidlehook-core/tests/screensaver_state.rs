@@ -0,0 +1,45 @@
+use std::{cell::Cell, rc::Rc, time::Duration};
+use xidlehook_core::{
+    modules::xcb::{NotWhenScreensaverDisabled, ScreensaverSource, ScreensaverState},
+    timers::CallbackTimer,
+    Result, Xidlehook,
+};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+struct MockScreensaver(Cell<ScreensaverState>);
+impl ScreensaverSource for MockScreensaver {
+    fn screensaver_state(&self) -> Result<ScreensaverState> {
+        Ok(self.0.get())
+    }
+}
+
+#[test]
+fn screensaver_state() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    assert_eq!(ScreensaverState::from_raw(0), Some(ScreensaverState::Off));
+    assert_eq!(
+        ScreensaverState::from_raw(3),
+        Some(ScreensaverState::Disabled)
+    );
+    assert_eq!(ScreensaverState::from_raw(4), None);
+
+    let triggered = Cell::new(0);
+    let screensaver = Rc::new(MockScreensaver(Cell::new(ScreensaverState::Disabled)));
+
+    let mut timer = Xidlehook::new(vec![CallbackTimer::new(TEST_UNIT * 10, || {
+        triggered.set(triggered.get() + 1)
+    })])
+    .register(NotWhenScreensaverDisabled::new(Rc::clone(&screensaver)));
+
+    // Inhibited while the screensaver is disabled
+    timer.poll(TEST_UNIT * 10).unwrap();
+    assert_eq!(triggered.get(), 0);
+
+    // Runs again once it's back on
+    screensaver.0.set(ScreensaverState::Off);
+    timer.poll(Duration::default()).unwrap();
+    timer.poll(TEST_UNIT * 10).unwrap();
+    assert_eq!(triggered.get(), 1);
+}
@@ -0,0 +1,118 @@
+use std::{
+    fs,
+    path::Path,
+    process::Command,
+    rc::Rc,
+    thread,
+    time::{Duration, Instant},
+};
+use xidlehook_core::{
+    timers::{CmdTimer, ShellWorker},
+    Xidlehook,
+};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+/// Wait for the worker to have written this many lines
+fn read_lines(path: &Path, count: usize) -> Vec<String> {
+    let start = Instant::now();
+    loop {
+        let lines: Vec<String> = fs::read_to_string(path)
+            .unwrap_or_default()
+            .lines()
+            .map(String::from)
+            .collect();
+        if lines.len() >= count || start.elapsed() > TEST_UNIT * 100 {
+            return lines;
+        }
+        thread::sleep(TEST_UNIT / 5);
+    }
+}
+
+fn shell(script: String) -> Option<Command> {
+    let mut cmd = Command::new("/bin/sh");
+    cmd.arg("-c").arg(script);
+    Some(cmd)
+}
+
+#[test]
+fn shell_workers() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dir = std::env::temp_dir().join(format!("xidlehook-worker-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let out = dir.join("out");
+    let quoted = format!("'{}'", out.display());
+
+    let worker = Rc::new(ShellWorker::new());
+    assert_eq!(worker.pid(), None, "the worker is started lazily");
+
+    let mut timer = Xidlehook::new(vec![
+        CmdTimer {
+            time: TEST_UNIT,
+            abortion: shell(format!("echo \"abort $$ ${{COUNT:=0}}\" >> {}", quoted)),
+            deactivation: shell(format!(
+                "COUNT=$((COUNT + 1)); echo \"deactivate $$ $COUNT\" >> {}",
+                quoted
+            )),
+            worker: Some(Rc::clone(&worker)),
+            ..CmdTimer::default()
+        },
+        CmdTimer {
+            time: TEST_UNIT,
+            ..CmdTimer::default()
+        },
+    ]);
+
+    // Cancellers are run by the worker, which shares its context
+    // between them
+    timer.poll(TEST_UNIT).unwrap();
+    timer.poll(Duration::default()).unwrap();
+    timer.poll(TEST_UNIT).unwrap();
+    timer.poll(TEST_UNIT * 2).unwrap();
+    timer.poll(Duration::default()).unwrap();
+    timer.poll(TEST_UNIT).unwrap();
+    timer.poll(Duration::default()).unwrap();
+    let pid = worker.pid().expect("the worker is running");
+    assert_eq!(
+        read_lines(&out, 3),
+        vec![
+            format!("abort {} 0", pid),
+            format!("deactivate {} 1", pid),
+            format!("abort {} 1", pid),
+        ],
+    );
+
+    // Plain commands have their arguments and environment passed
+    // along intact
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c")
+        .arg("printf '%s %s\\n' \"$1\" \"$WORD\" >> \"$2\"")
+        .arg("sh")
+        .arg("it's $HOME")
+        .arg(&out)
+        .env("WORD", "`not run`");
+    worker.run(&cmd).unwrap();
+    assert_eq!(read_lines(&out, 4)[3], "it's $HOME `not run`");
+    assert_eq!(worker.pid(), Some(pid));
+
+    // A worker that exited is replaced
+    worker.run(&shell(String::from("exit")).unwrap()).unwrap();
+    let start = Instant::now();
+    while worker.pid().is_some() && start.elapsed() < TEST_UNIT * 100 {
+        thread::sleep(TEST_UNIT / 5);
+    }
+    timer.poll(TEST_UNIT).unwrap();
+    timer.poll(Duration::default()).unwrap();
+    let lines = read_lines(&out, 5);
+    let new_pid = worker.pid().expect("a new worker is running");
+    assert_ne!(new_pid, pid);
+    assert_eq!(lines[4], format!("abort {} 0", new_pid));
+
+    // Dropping the worker waits for the shell, which leaves no zombie
+    drop(timer);
+    drop(worker);
+    assert!(!Path::new(&format!("/proc/{}", new_pid)).exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
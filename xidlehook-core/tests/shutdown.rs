@@ -0,0 +1,67 @@
+use std::{cell::Cell, rc::Rc, time::Duration};
+use xidlehook_core::{
+    modules::{ModuleList, StopAt},
+    sources::ManualIdleSource,
+    timers::CallbackTimer,
+    Module, Result, Xidlehook,
+};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+/// Counts how often it was shut down, and fails if told to
+struct Counting {
+    shutdowns: Rc<Cell<usize>>,
+    fail: bool,
+}
+impl Module for Counting {
+    fn on_shutdown(&mut self) -> Result<()> {
+        self.shutdowns.set(self.shutdowns.get() + 1);
+        if self.fail {
+            return Err("can't shut down".into());
+        }
+        Ok(())
+    }
+}
+
+fn counting(shutdowns: &Rc<Cell<usize>>, fail: bool) -> Counting {
+    Counting {
+        shutdowns: Rc::clone(shutdowns),
+        fail,
+    }
+}
+
+#[test]
+fn shutdown() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // The main loop shuts the modules down once it's done
+    let shutdowns = Rc::new(Cell::new(0));
+    let xidlehook = Xidlehook::new(vec![CallbackTimer::new(TEST_UNIT, || ())])
+        .register(StopAt::completion())
+        .register(counting(&shutdowns, false));
+    xidlehook
+        .main_sync(&ManualIdleSource::new(TEST_UNIT), || false)
+        .unwrap();
+    assert_eq!(shutdowns.get(), 1);
+
+    // Only the first call does anything
+    let mut xidlehook =
+        Xidlehook::new(Vec::<CallbackTimer<fn()>>::new()).register(counting(&shutdowns, false));
+    xidlehook.shutdown().unwrap();
+    xidlehook.shutdown().unwrap();
+    assert_eq!(shutdowns.get(), 2);
+
+    // Every module is shut down, even after one failed
+    shutdowns.set(0);
+    let modules = ModuleList::new()
+        .with("first", counting(&shutdowns, true))
+        .with("second", counting(&shutdowns, false));
+    let mut xidlehook = Xidlehook::new(Vec::<CallbackTimer<fn()>>::new())
+        .register(modules)
+        .register(vec![
+            counting(&shutdowns, true),
+            counting(&shutdowns, false),
+        ]);
+    assert!(xidlehook.shutdown().is_err());
+    assert_eq!(shutdowns.get(), 4);
+}
@@ -0,0 +1,116 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    time::Duration,
+};
+use xidlehook_core::{
+    modules::{ResetOnRelease, Toggle},
+    timers::CallbackTimer,
+    Module, Progress, Result, TimerInfo, Xidlehook,
+};
+
+/// Keeps the first timer from running once, counting how often it was
+/// asked
+#[derive(Clone, Default)]
+struct Once(Rc<Cell<u32>>);
+impl Module for Once {
+    fn pre_timer(&mut self, _timer: TimerInfo) -> Result<Progress> {
+        self.0.set(self.0.get() + 1);
+        if self.0.get() == 1 {
+            Ok(Progress::Abort)
+        } else {
+            Ok(Progress::Continue)
+        }
+    }
+}
+
+#[test]
+fn simulation() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let fired = RefCell::new(Vec::new());
+
+    let inhibit = Toggle::new(Progress::Abort);
+    let mut timer = Xidlehook::new(vec![
+        CallbackTimer::new(Duration::from_secs(300), || fired.borrow_mut().push(0)),
+        CallbackTimer::new(Duration::from_secs(60), || fired.borrow_mut().push(1)),
+        CallbackTimer::new(Duration::from_secs(240), || fired.borrow_mut().push(2)),
+    ])
+    .register(inhibit.clone());
+
+    // Only the first timer is due, and the second one is 10 seconds off
+    let simulation = timer.simulate(Duration::from_secs(350)).unwrap();
+    assert_eq!(simulation.idle_ms, 350_000);
+    assert_eq!(simulation.fired, vec![0]);
+    assert_eq!(simulation.inhibited, None);
+    assert_eq!(simulation.next_timer, Some(1));
+    assert_eq!(simulation.next_in_ms, Some(10_000));
+
+    // Past the end of the chain, there's nothing left
+    let simulation = timer.simulate(Duration::from_secs(700)).unwrap();
+    assert_eq!(simulation.fired, vec![0, 1, 2]);
+    assert_eq!(simulation.next_timer, None);
+    assert_eq!(simulation.next_in_ms, None);
+
+    // A module keeping the timers from running ends the chain
+    inhibit.set(true);
+    let simulation = timer.simulate(Duration::from_secs(350)).unwrap();
+    assert!(simulation.fired.is_empty());
+    assert_eq!(simulation.inhibited, Some(0));
+    assert_eq!(simulation.next_timer, None);
+    inhibit.set(false);
+
+    // Nothing actually ran, and the chain still works
+    assert!(fired.borrow().is_empty());
+    timer.poll(Duration::from_secs(350)).unwrap();
+    assert_eq!(*fired.borrow(), vec![0]);
+
+    // Disabled timers are skipped
+    timer.timers_mut().unwrap()[1].disabled = true;
+    let simulation = timer.simulate(Duration::from_secs(350)).unwrap();
+    assert_eq!(simulation.fired, vec![0]);
+    assert_eq!(simulation.next_timer, Some(2));
+    assert_eq!(simulation.next_in_ms, Some(190_000));
+}
+
+#[test]
+fn simulation_leaves_modules_alone() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let once = Once::default();
+    let inhibit = Toggle::new(Progress::Abort);
+    let mut timer = Xidlehook::new(vec![
+        CallbackTimer::new(Duration::from_secs(60), || ()),
+        CallbackTimer::new(Duration::from_secs(60), || ()),
+    ])
+    .register((once.clone(), ResetOnRelease::new(inhibit.clone())));
+
+    // A module which isn't asked without side effects isn't asked at all
+    let simulation = timer.simulate(Duration::from_secs(150)).unwrap();
+    assert_eq!(simulation.fired, vec![0, 1]);
+    assert_eq!(once.0.get(), 0);
+
+    // Query-only modules still keep timers from running
+    inhibit.set(true);
+    let simulation = timer.simulate(Duration::from_secs(150)).unwrap();
+    assert_eq!(simulation.inhibited, Some(0));
+    inhibit.set(false);
+
+    // The first real check is still the one kept from running
+    timer.poll(Duration::from_secs(70)).unwrap();
+    assert_eq!(once.0.get(), 1);
+
+    // A module waiting for a release tells about it without forgetting
+    let mut release = ResetOnRelease::new(inhibit.clone());
+    let info = TimerInfo {
+        index: 0,
+        length: 1,
+    };
+    inhibit.set(true);
+    assert_eq!(release.pre_timer(info).unwrap(), Progress::Abort);
+    inhibit.set(false);
+    assert_eq!(release.peek_timer(info).unwrap(), Progress::Reset);
+    assert!(release.is_held());
+    assert_eq!(release.pre_timer(info).unwrap(), Progress::Reset);
+    assert!(!release.is_held());
+}
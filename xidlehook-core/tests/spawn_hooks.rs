@@ -0,0 +1,54 @@
+use std::{cell::RefCell, process::Command, rc::Rc, time::Duration};
+use xidlehook_core::{
+    timers::{CmdTimer, CommandLimit, QueueFull, SpawnHook},
+    Xidlehook,
+};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+#[test]
+fn spawn_hooks() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let spawned = Rc::new(RefCell::new(Vec::new()));
+    let hook = {
+        let spawned = Rc::clone(&spawned);
+        SpawnHook::new(move |cmd: &Command| {
+            let args: Vec<_> = cmd.get_args().map(|arg| arg.to_string_lossy()).collect();
+            spawned.borrow_mut().push(args.join(" "));
+            // Run something harmless instead
+            Ok(Command::new("true").spawn()?)
+        })
+    };
+
+    let command = |name: &str| {
+        let mut cmd = Command::new("echo");
+        cmd.arg(name);
+        Some(cmd)
+    };
+    let mut timer = Xidlehook::new(vec![
+        CmdTimer {
+            time: TEST_UNIT,
+            activation: command("first"),
+            limit: Some(Rc::new(CommandLimit::new(1, QueueFull::Queue))),
+            spawn_hook: Some(hook.clone()),
+            ..CmdTimer::default()
+        },
+        CmdTimer {
+            time: TEST_UNIT,
+            activation: command("second"),
+            abortion: command("cancel"),
+            spawn_hook: Some(hook),
+            ..CmdTimer::default()
+        },
+    ]);
+
+    // It's used through a command limit...
+    timer.poll(TEST_UNIT).unwrap();
+    assert_eq!(*spawned.borrow(), ["first"]);
+
+    // ... and without one, also for cancellers
+    timer.poll(TEST_UNIT * 2).unwrap();
+    timer.poll(Duration::default()).unwrap();
+    assert_eq!(*spawned.borrow(), ["first", "second", "cancel"]);
+}
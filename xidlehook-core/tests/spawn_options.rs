@@ -0,0 +1,66 @@
+use std::{env, fs, path::PathBuf};
+use xidlehook_core::timers::{SpawnOptions, TimerCommand};
+
+fn run(command: &TimerCommand, options: &SpawnOptions) -> String {
+    let output = command.to_command(options).output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn spawn_options() {
+    let dir = env::temp_dir().join(format!("xidlehook-spawn-options-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let dir = dir.canonicalize().unwrap();
+    let options = SpawnOptions {
+        current_dir: Some(dir.clone()),
+        env: vec![("XIDLEHOOK_TEST".into(), "a b $HOME".into())],
+        clear_env: false,
+    };
+
+    // A script goes through the shell, which expands variables
+    let shell = TimerCommand::from("echo \"$XIDLEHOOK_TEST\"; pwd");
+    assert_eq!(
+        run(&shell, &options),
+        format!("a b $HOME\n{}\n", dir.display())
+    );
+
+    // A list isn't, so arguments are passed as they are, until a timer
+    // expands them, see CmdTimer::expand_args
+    let argv = TimerCommand::Argv(vec!["echo".into(), "$XIDLEHOOK_TEST".into()]);
+    assert_eq!(run(&argv, &options), "$XIDLEHOOK_TEST\n");
+    let pwd = TimerCommand::Argv(vec!["pwd".into()]);
+    assert_eq!(PathBuf::from(run(&pwd, &options).trim_end()), dir);
+
+    // The environment is inherited unless it's cleared
+    env::set_var("XIDLEHOOK_INHERITED", "1");
+    let printenv = TimerCommand::Argv(vec!["env".into()]);
+    let inherited = run(&printenv, &options);
+    assert!(inherited
+        .lines()
+        .any(|line| line == "XIDLEHOOK_INHERITED=1"));
+    assert!(inherited
+        .lines()
+        .any(|line| line == "XIDLEHOOK_TEST=a b $HOME"));
+    let cleared = run(
+        &printenv,
+        &SpawnOptions {
+            clear_env: true,
+            ..options.clone()
+        },
+    );
+    assert_eq!(cleared, "XIDLEHOOK_TEST=a b $HOME\n");
+
+    // Without options, commands run where xidlehook does
+    assert_eq!(
+        PathBuf::from(run(&pwd, &SpawnOptions::default()).trim_end()),
+        env::current_dir().unwrap()
+    );
+
+    // An empty list has nothing to run
+    let empty = TimerCommand::Argv(Vec::new());
+    assert!(empty.is_empty());
+    assert!(empty.to_command(&options).spawn().is_err());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,48 @@
+#![cfg(feature = "state")]
+
+use std::{env, fs, process};
+use xidlehook_core::{
+    modules::Toggle,
+    state::{State, STATE_VERSION},
+    Progress,
+};
+
+#[test]
+fn state() {
+    let path = env::temp_dir().join(format!("xidlehook-state-{}.json", process::id()));
+    let _ = fs::remove_file(&path);
+
+    // Nothing to restore on the first start
+    assert_eq!(State::load(&path).unwrap(), None);
+
+    // Shutting down with presentation mode on, after a daily timer ran
+    let presentation = Toggle::new(Progress::Reset);
+    presentation.set(true);
+    let mut state = State {
+        presentation: presentation.is_on(),
+        ..State::default()
+    };
+    state
+        .once_per_day
+        .insert(String::from("backup"), 1_602_681_600);
+    state.save(&path).unwrap();
+    assert!(!path.with_extension("json.tmp").exists());
+
+    // ... and starting up again
+    let restored = State::load(&path).unwrap().unwrap();
+    let presentation = Toggle::new(Progress::Reset);
+    presentation.set(restored.presentation);
+    assert!(presentation.is_on());
+    assert!(!restored.disabled);
+    assert_eq!(restored.once_per_day.get("backup"), Some(&1_602_681_600));
+    assert_eq!(restored, state);
+
+    // Missing fields keep their defaults, newer versions are refused
+    let old = State::from_json(r#"{"version":1,"disabled":true}"#).unwrap();
+    assert!(old.disabled);
+    assert!(!old.presentation);
+    let newer = format!(r#"{{"version":{}}}"#, STATE_VERSION + 1);
+    assert!(State::from_json(&newer).is_err());
+
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,26 @@
+use std::time::Duration;
+use xidlehook_core::status::Status;
+
+#[test]
+fn status_rounding() {
+    let second = Duration::from_secs(1);
+    let ten_seconds = Duration::from_secs(10);
+
+    for &(raw, rounding, rounded) in &[
+        (1_499, second, 1_000),
+        (1_500, second, 2_000),
+        (59_999, second, 60_000),
+        (4_999, ten_seconds, 0),
+        (5_000, ten_seconds, 10_000),
+        (244_123, ten_seconds, 240_000),
+        (1_234, Duration::default(), 1_234),
+    ] {
+        let status = Status::new(Duration::from_millis(raw), rounding);
+        assert_eq!(
+            status.idle_ms, rounded,
+            "{} ms rounded to {:?}",
+            raw, rounding
+        );
+        assert_eq!(status.raw_idle_ms, raw);
+    }
+}
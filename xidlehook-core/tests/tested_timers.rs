@@ -0,0 +1,95 @@
+mod common;
+
+use common::RecordingTimer;
+use std::{cell::RefCell, fs, process::Command, thread, time::Duration};
+use xidlehook_core::{timers::CmdTimer, Action::*, Timer, Xidlehook};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+#[test]
+fn tested_timers() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let log = RefCell::new(Vec::new());
+
+    let recording = || RecordingTimer {
+        abortion: Some("abortion"),
+        ..RecordingTimer::new("activation", TEST_UNIT * 10, &log)
+    };
+    let mut timer = Xidlehook::new(vec![recording(), recording()]);
+
+    assert_eq!(timer.poll(TEST_UNIT * 5).unwrap(), Sleep(TEST_UNIT * 5));
+
+    // Both the activation and the canceller run, in order
+    timer.test_activation(1).unwrap();
+    timer.test_abortion(1).unwrap();
+    assert_eq!(*log.borrow(), ["activation", "abortion"]);
+
+    // ... without disturbing the idle chain
+    assert_eq!(timer.poll(TEST_UNIT * 6).unwrap(), Sleep(TEST_UNIT * 4));
+    log.borrow_mut().clear();
+    assert_eq!(timer.poll(TEST_UNIT * 10).unwrap(), Sleep(TEST_UNIT * 10));
+    assert_eq!(*log.borrow(), ["activation"]);
+
+    // The currently active timer can't be tested
+    assert!(timer.test_activation(0).is_err());
+    assert!(timer.test_abortion(0).is_err());
+}
+
+#[test]
+fn tested_commands() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let pid_file = std::env::temp_dir().join(format!("xidlehook-tested-{}", std::process::id()));
+    let mut activation = Command::new("sleep");
+    activation.arg("10");
+    let mut canceller = Command::new("sh");
+    canceller
+        .arg("-c")
+        .arg(format!("echo $XIDLEHOOK_PID > {}", pid_file.display()));
+
+    let mut xidlehook = Xidlehook::new(vec![CmdTimer {
+        time: TEST_UNIT * 10,
+        activation: Some(activation),
+        abortion: Some(canceller),
+        ..CmdTimer::default()
+    }]);
+
+    xidlehook.test_activation(0).unwrap();
+    let pid = xidlehook.timers()[0].test_pid.unwrap();
+
+    // The timer's own state is left alone, so it isn't disabled while the test runs
+    {
+        let timer = &mut xidlehook.timers_mut().unwrap()[0];
+        assert!(timer.activation_child.is_none());
+        assert!(!timer.disabled());
+    }
+
+    // The canceller gets the test activation's pid, through a copy
+    xidlehook.test_abortion(0).unwrap();
+    let timer = &xidlehook.timers()[0];
+    assert!(timer
+        .abortion
+        .as_ref()
+        .unwrap()
+        .get_envs()
+        .all(|(key, _)| key != "XIDLEHOOK_PID"));
+
+    let mut written = None;
+    for _ in 0..40 {
+        written = fs::read_to_string(&pid_file)
+            .ok()
+            .and_then(|pid| pid.trim().parse::<u32>().ok());
+        if written.is_some() {
+            break;
+        }
+        thread::sleep(TEST_UNIT);
+    }
+    assert_eq!(written, Some(pid));
+
+    for child in &mut xidlehook.timers_mut().unwrap()[0].children {
+        let _ = child.kill();
+        child.wait().unwrap();
+    }
+    fs::remove_file(&pid_file).unwrap();
+}
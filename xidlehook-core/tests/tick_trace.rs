@@ -0,0 +1,81 @@
+use log::{Log, Metadata, Record};
+use serde_json::{json, Value};
+use std::{
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+use xidlehook_core::{logging, modules::Toggle, timers::CallbackTimer, Progress, Xidlehook};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+/// Keeps the fields of every `tick` event
+struct Capture(Mutex<Vec<Value>>);
+impl Log for Capture {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+    fn log(&self, record: &Record) {
+        let line: Value = serde_json::from_str(&logging::json_line(record, SystemTime::now()))
+            .expect("invalid JSON");
+        if line["event"] == "tick" {
+            let fields = ["idle_ms", "eligible", "verdicts", "fired", "action"];
+            let fields = fields.iter().map(|&key| (key, line[key].clone()));
+            self.0.lock().unwrap().push(fields.collect());
+        }
+    }
+    fn flush(&self) {}
+}
+
+static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+
+fn ticks() -> Vec<Value> {
+    CAPTURE.0.lock().unwrap().drain(..).collect()
+}
+
+#[test]
+fn tick_trace() {
+    log::set_logger(&CAPTURE).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+
+    let disabled = Toggle::new(Progress::Abort);
+    let mut timer = Xidlehook::new(vec![
+        CallbackTimer::new(TEST_UNIT, || ()),
+        CallbackTimer::new(TEST_UNIT, || ()),
+    ])
+    .register(disabled.clone());
+
+    // Nothing is due yet
+    timer.poll(Duration::default()).unwrap();
+    // The first timer goes off
+    timer.poll(TEST_UNIT).unwrap();
+    // The second one is due, but the module keeps it from going off
+    disabled.set(true);
+    timer.poll(TEST_UNIT * 2).unwrap();
+
+    assert_eq!(
+        ticks(),
+        [
+            json!({
+                "idle_ms": 0,
+                "eligible": "",
+                "verdicts": "",
+                "fired": "",
+                "action": "sleep:50",
+            }),
+            json!({
+                "idle_ms": 50,
+                "eligible": "0",
+                "verdicts": "0:continue",
+                "fired": "0",
+                "action": "sleep:50",
+            }),
+            json!({
+                "idle_ms": 100,
+                "eligible": "1",
+                "verdicts": "1:abort",
+                "fired": "",
+                "action": "sleep:50",
+            }),
+        ]
+    );
+}
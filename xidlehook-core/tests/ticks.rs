@@ -0,0 +1,70 @@
+use std::{cell::RefCell, time::Duration};
+use xidlehook_core::{
+    modules::{StopAt, Toggle},
+    sources::ManualIdleSource,
+    timers::CallbackTimer,
+    Action, Progress, Xidlehook,
+};
+
+const TEST_UNIT: Duration = Duration::from_millis(10);
+
+#[test]
+fn ticks() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let fired = RefCell::new(Vec::new());
+    let source = ManualIdleSource::new(Duration::default());
+    let disabled = Toggle::new(Progress::Abort);
+    let mut xidlehook = Xidlehook::new(vec![
+        CallbackTimer::new(TEST_UNIT * 3, || fired.borrow_mut().push(0)),
+        CallbackTimer::new(TEST_UNIT * 2, || fired.borrow_mut().push(1)),
+    ])
+    .register(disabled.clone())
+    .register(StopAt::completion());
+
+    // One unit of idle time per tick: the first timer goes off after
+    // three, and the second two after that
+    let action = xidlehook
+        .run_ticks(&source, 4, |_| source.advance(TEST_UNIT))
+        .unwrap();
+    assert_eq!(*fired.borrow(), vec![0]);
+    assert!(matches!(action, Action::Sleep(_)));
+    xidlehook
+        .run_ticks(&source, 1, |_| source.advance(TEST_UNIT))
+        .unwrap();
+    assert_eq!(*fired.borrow(), vec![0, 1]);
+
+    // The chain completed, so the loop stops early
+    let mut ticks = 0;
+    let action = xidlehook
+        .run_ticks(&source, 10, |_| {
+            ticks += 1;
+            source.advance(TEST_UNIT)
+        })
+        .unwrap();
+    assert_eq!(action, Action::Quit);
+    assert!(ticks < 10);
+
+    // Modules still decide, and activity starts the chain over
+    fired.borrow_mut().clear();
+    disabled.set(true);
+    let mut xidlehook = Xidlehook::new(vec![CallbackTimer::new(TEST_UNIT * 3, || {
+        fired.borrow_mut().push(0)
+    })])
+    .register(disabled.clone());
+    source.set(Duration::default());
+    xidlehook
+        .run_ticks(&source, 5, |_| source.advance(TEST_UNIT))
+        .unwrap();
+    assert!(fired.borrow().is_empty());
+    disabled.set(false);
+    xidlehook
+        .run_ticks(&source, 5, |tick| {
+            if tick == 0 {
+                source.set(Duration::default());
+            }
+            source.advance(TEST_UNIT)
+        })
+        .unwrap();
+    assert_eq!(*fired.borrow(), vec![0]);
+}
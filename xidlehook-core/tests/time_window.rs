@@ -0,0 +1,84 @@
+use xidlehook_core::{
+    modules::{time_window::local_minute, TimeRange, TimeWindow},
+    Module, Progress, TimerInfo,
+};
+
+fn timer(index: usize) -> TimerInfo {
+    TimerInfo { index, length: 2 }
+}
+
+fn at(hours: u16, minutes: u16) -> u16 {
+    hours * 60 + minutes
+}
+
+#[test]
+fn time_ranges() {
+    let evening: TimeRange = "18:30-23:00".parse().unwrap();
+    assert_eq!(evening, TimeRange::new((18, 30), (23, 0)).unwrap());
+    assert_eq!(evening.to_string(), "18:30-23:00");
+    assert!(!evening.contains(at(18, 29)));
+    assert!(evening.contains(at(18, 30)));
+    assert!(evening.contains(at(22, 59)));
+    assert!(!evening.contains(at(23, 0)));
+
+    // Wrapping past midnight
+    let night: TimeRange = "22:00-6:00".parse().unwrap();
+    assert_eq!(night.to_string(), "22:00-06:00");
+    assert!(night.contains(at(23, 30)));
+    assert!(night.contains(at(0, 0)));
+    assert!(night.contains(at(5, 59)));
+    assert!(!night.contains(at(6, 0)));
+    assert!(!night.contains(at(12, 0)));
+    assert!(!night.contains(at(21, 59)));
+
+    let day: TimeRange = "00:00-00:00".parse().unwrap();
+    assert!(day.contains(at(0, 0)));
+    assert!(day.contains(at(23, 59)));
+
+    for invalid in &[
+        "22:00",
+        "22-06",
+        "24:00-06:00",
+        "22:60-06:00",
+        "ten:00-11:00",
+    ] {
+        assert!(invalid.parse::<TimeRange>().is_err(), "{}", invalid);
+    }
+}
+
+#[test]
+fn time_window() {
+    let window = TimeWindow::new(vec![
+        "22:00-06:00".parse().unwrap(),
+        "12:00-13:00".parse().unwrap(),
+    ]);
+    assert!(window.allows(timer(0), at(23, 0)));
+    assert!(window.allows(timer(1), at(12, 30)));
+    assert!(!window.allows(timer(0), at(9, 0)));
+    assert!(!window.allows(timer(1), at(20, 0)));
+
+    // Only the dim timer is kept from running
+    let window = window.with_timers(vec![0]);
+    assert!(!window.allows(timer(0), at(9, 0)));
+    assert!(window.allows(timer(1), at(9, 0)));
+
+    let description = window.describe();
+    assert_eq!(description.kind, "time-window");
+    assert_eq!(
+        description.settings["ranges"],
+        vec!["22:00-06:00".to_string(), "12:00-13:00".into()].into()
+    );
+    assert_eq!(description.settings["timers"], vec!["0".to_string()].into());
+}
+
+#[test]
+fn local_time() {
+    assert!(local_minute().unwrap() < 24 * 60);
+
+    let mut always = TimeWindow::new(vec!["00:00-00:00".parse().unwrap()]);
+    assert_eq!(always.pre_timer(timer(0)).unwrap(), Progress::Continue);
+    let mut never = TimeWindow::new(Vec::new());
+    assert_eq!(never.pre_timer(timer(0)).unwrap(), Progress::Abort);
+    let mut others = TimeWindow::new(Vec::new()).with_timers(vec![1]);
+    assert_eq!(others.pre_timer(timer(0)).unwrap(), Progress::Continue);
+}
@@ -0,0 +1,70 @@
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+use xidlehook_core::{sources::TimeoutIdleSource, IdleSource, Result};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+/// Returns 1s, 2s, 3s, ... and blocks for however long is requested
+struct BlockingSource {
+    calls: Mutex<u64>,
+    block: Arc<Mutex<Duration>>,
+}
+impl IdleSource for BlockingSource {
+    fn get_idle(&self) -> Result<Duration> {
+        let block = *self.block.lock().unwrap();
+        thread::sleep(block);
+        let mut calls = self.calls.lock().unwrap();
+        *calls += 1;
+        Ok(Duration::from_secs(*calls))
+    }
+}
+
+#[test]
+fn timeout_idle() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let block = Arc::new(Mutex::new(Duration::default()));
+    let source = TimeoutIdleSource::new(
+        BlockingSource {
+            calls: Mutex::new(0),
+            block: Arc::clone(&block),
+        },
+        TEST_UNIT * 2,
+    );
+
+    assert_eq!(source.get_idle().unwrap(), Duration::from_secs(1));
+
+    // The source hangs, so the cached value is returned after the timeout
+    *block.lock().unwrap() = TEST_UNIT * 6;
+    let start = Instant::now();
+    assert_eq!(source.get_idle().unwrap(), Duration::from_secs(1));
+    let elapsed = start.elapsed();
+    assert!(elapsed >= TEST_UNIT * 2 && elapsed < TEST_UNIT * 6);
+
+    // While it's still stuck, no more requests are queued up
+    assert_eq!(source.get_idle().unwrap(), Duration::from_secs(1));
+
+    // Once it responds, the late value is picked up
+    *block.lock().unwrap() = Duration::default();
+    thread::sleep(TEST_UNIT * 4);
+    assert_eq!(source.get_idle().unwrap(), Duration::from_secs(3));
+}
+
+#[test]
+fn timeout_idle_without_value() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let source = TimeoutIdleSource::new(
+        BlockingSource {
+            calls: Mutex::new(0),
+            block: Arc::new(Mutex::new(TEST_UNIT * 4)),
+        },
+        TEST_UNIT,
+    );
+
+    // There's nothing to fall back to yet
+    assert!(source.get_idle().is_err());
+}
@@ -0,0 +1,79 @@
+use std::{cell::RefCell, time::Duration};
+use xidlehook_core::{modules::Toggle, timers::CallbackTimer, Progress, Xidlehook};
+
+/// Poll once with the user idle for a minute, with the fullscreen
+/// module only on the lock timer and the inhibitor on all of them,
+/// returning which timers fired and whether the chain was aborted
+fn poll(fullscreen: bool, inhibited: bool) -> (Vec<&'static str>, bool) {
+    let fired = RefCell::new(Vec::new());
+    let fullscreen_module = Toggle::new(Progress::Abort);
+    fullscreen_module.set(fullscreen);
+    let inhibitor = Toggle::new(Progress::Abort);
+    inhibitor.set(inhibited);
+
+    let mut xidlehook = Xidlehook::new(vec![
+        CallbackTimer::new(Duration::from_secs(60), || fired.borrow_mut().push("away")),
+        CallbackTimer::new(Duration::default(), || fired.borrow_mut().push("lock")),
+    ])
+    .register(inhibitor)
+    .with_timer_module(1, "not-when-fullscreen", fullscreen_module);
+    assert!(xidlehook.timer_modules(0).is_none());
+    assert_eq!(
+        xidlehook.timer_modules(1).map(|list| list.names()),
+        Some(vec!["not-when-fullscreen"])
+    );
+
+    xidlehook.poll(Duration::from_secs(60)).unwrap();
+    let aborted = xidlehook.is_aborted();
+    drop(xidlehook);
+    (fired.into_inner(), aborted)
+}
+
+#[test]
+fn timer_modules() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    assert_eq!(poll(false, false), (vec!["away", "lock"], false));
+
+    // Fullscreen only keeps the lock timer from running, in the same
+    // poll the other one fires in
+    assert_eq!(poll(true, false), (vec!["away"], true));
+
+    // The modules of all timers still apply
+    assert_eq!(poll(false, true), (vec![], true));
+}
+
+#[test]
+fn moved_timer_modules() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let timer = || CallbackTimer::new(Duration::from_secs(60), || ());
+    let mut xidlehook = Xidlehook::new(vec![timer(), timer(), timer()])
+        .with_timer_module(1, "first", Toggle::new(Progress::Abort))
+        .with_timer_module(2, "second", Toggle::new(Progress::Abort));
+    let names = |xidlehook: &Xidlehook<_, _>, index| {
+        xidlehook
+            .timer_modules(index)
+            .map(|list| list.names().join(","))
+    };
+
+    // The modules move along with their timers
+    xidlehook.insert_timer(0, timer()).unwrap();
+    assert_eq!(xidlehook.timers().len(), 4);
+    assert_eq!(names(&xidlehook, 1), None);
+    assert_eq!(names(&xidlehook, 2).as_deref(), Some("first"));
+    assert_eq!(names(&xidlehook, 3).as_deref(), Some("second"));
+
+    // ... and go away with them
+    xidlehook.remove_timer(2).unwrap();
+    assert_eq!(xidlehook.timers().len(), 3);
+    assert_eq!(names(&xidlehook, 2).as_deref(), Some("second"));
+    xidlehook.remove_timer(0).unwrap();
+    assert_eq!(names(&xidlehook, 1).as_deref(), Some("second"));
+    assert_eq!(names(&xidlehook, 2), None);
+
+    // Past the timers with modules, nothing moves
+    xidlehook.insert_timer(2, timer()).unwrap();
+    assert_eq!(names(&xidlehook, 1).as_deref(), Some("second"));
+    assert_eq!(names(&xidlehook, 2), None);
+}
@@ -0,0 +1,114 @@
+use std::{cell::Cell, rc::Rc, time::Duration};
+use xidlehook_core::{modules::Toggle, Action, Progress, Result, Timer, Xidlehook};
+
+const TEST_UNIT: Duration = Duration::from_millis(10);
+
+#[derive(Default)]
+struct Counts {
+    warned: Cell<usize>,
+    cancelled: Cell<usize>,
+    activated: Cell<usize>,
+}
+impl Counts {
+    fn get(&self) -> (usize, usize, usize) {
+        (
+            self.warned.get(),
+            self.cancelled.get(),
+            self.activated.get(),
+        )
+    }
+}
+
+/// Goes off after 10 units, warning 3 units before
+struct Warned(Rc<Counts>);
+impl Timer for Warned {
+    fn time_left(&mut self, idle_time: Duration) -> Result<Option<Duration>> {
+        Ok((TEST_UNIT * 10)
+            .checked_sub(idle_time)
+            .filter(|&d| d > Duration::default()))
+    }
+    fn activate(&mut self) -> Result<()> {
+        self.0.activated.set(self.0.activated.get() + 1);
+        Ok(())
+    }
+    fn warning_lead(&self) -> Option<Duration> {
+        Some(TEST_UNIT * 3)
+    }
+    fn warn(&mut self) -> Result<()> {
+        self.0.warned.set(self.0.warned.get() + 1);
+        Ok(())
+    }
+    fn cancel_warning(&mut self) -> Result<()> {
+        self.0.cancelled.set(self.0.cancelled.get() + 1);
+        Ok(())
+    }
+}
+
+#[test]
+fn timer_warnings() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let counts = Rc::new(Counts::default());
+    let mut xidlehook = Xidlehook::new(vec![Warned(Rc::clone(&counts))]);
+
+    // It wakes up in time for the warning
+    assert_eq!(
+        xidlehook.poll(Duration::default()).unwrap(),
+        Action::Sleep(TEST_UNIT * 7)
+    );
+    assert_eq!(counts.get(), (0, 0, 0));
+
+    // Then the warning goes out, once
+    assert_eq!(
+        xidlehook.poll(TEST_UNIT * 7).unwrap(),
+        Action::Sleep(TEST_UNIT * 3)
+    );
+    xidlehook.poll(TEST_UNIT * 8).unwrap();
+    assert_eq!(counts.get(), (1, 0, 0));
+
+    // Then the timer itself
+    xidlehook.poll(TEST_UNIT * 10).unwrap();
+    assert_eq!(counts.get(), (1, 0, 1));
+
+    // Being active after it went off doesn't cancel the warning
+    xidlehook.poll(Duration::default()).unwrap();
+    assert_eq!(counts.get(), (1, 0, 1));
+}
+
+#[test]
+fn cancelled_warnings() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let counts = Rc::new(Counts::default());
+    let mut xidlehook = Xidlehook::new(vec![Warned(Rc::clone(&counts))]);
+
+    // Being active after the warning cancels it, and the timer
+    xidlehook.poll(TEST_UNIT * 8).unwrap();
+    assert_eq!(counts.get(), (1, 0, 0));
+    xidlehook.poll(TEST_UNIT).unwrap();
+    assert_eq!(counts.get(), (1, 1, 0));
+    xidlehook.poll(TEST_UNIT * 2).unwrap();
+    assert_eq!(counts.get(), (1, 1, 0));
+
+    // It warns again next time
+    xidlehook.poll(TEST_UNIT * 8).unwrap();
+    assert_eq!(counts.get(), (2, 1, 0));
+}
+
+#[test]
+fn inhibited_warnings() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let counts = Rc::new(Counts::default());
+    let toggle = Toggle::new(Progress::Abort);
+    toggle.set(true);
+    let mut xidlehook = Xidlehook::new(vec![Warned(Rc::clone(&counts))]).register(toggle.clone());
+
+    // No warning goes out while the module keeps the timer from running,
+    // so there's nothing to cancel either
+    xidlehook.poll(TEST_UNIT * 8).unwrap();
+    xidlehook.poll(TEST_UNIT * 10).unwrap();
+    assert_eq!(counts.get(), (0, 0, 0));
+    xidlehook.poll(Duration::default()).unwrap();
+    assert_eq!(counts.get(), (0, 0, 0));
+}
@@ -0,0 +1,82 @@
+use std::{cell::Cell, time::Duration};
+use xidlehook_core::{modules::Toggle, timers::CallbackTimer, Action::*, Progress, Xidlehook};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+#[test]
+fn toggled_timers() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let triggered = Cell::new(0);
+
+    let disabled = Toggle::new(Progress::Abort);
+    let presentation = Toggle::new(Progress::Reset);
+
+    let mut timer = Xidlehook::new(vec![CallbackTimer::new(TEST_UNIT * 10, || {
+        triggered.set(triggered.get() + 1)
+    })])
+    .register(disabled.clone())
+    .register(presentation.clone());
+
+    // Toggling flips the shared state
+    assert!(disabled.toggle());
+    assert!(disabled.is_on());
+
+    // While disabled, the timer doesn't run, and the chain is aborted
+    assert_eq!(timer.poll(TEST_UNIT * 10).unwrap(), Sleep(TEST_UNIT * 10));
+    assert_eq!(triggered.get(), 0);
+
+    assert!(!disabled.toggle());
+    timer.poll(Duration::default()).unwrap();
+    timer.poll(TEST_UNIT * 10).unwrap();
+    assert_eq!(triggered.get(), 1);
+
+    // In presentation mode the user is treated as active, so the full
+    // time counts again once it's switched off
+    timer.poll(Duration::default()).unwrap();
+    assert!(presentation.toggle());
+    assert_eq!(timer.poll(TEST_UNIT * 10).unwrap(), Sleep(TEST_UNIT * 10));
+    assert_eq!(triggered.get(), 1);
+
+    presentation.set(false);
+    assert_eq!(timer.poll(TEST_UNIT * 15).unwrap(), Sleep(TEST_UNIT * 5));
+    timer.poll(TEST_UNIT * 20).unwrap();
+    assert_eq!(triggered.get(), 2);
+}
+
+#[test]
+fn paused_timers() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let triggered = Cell::new(0);
+    let paused = Toggle::new(Progress::Abort);
+    let mut timer = Xidlehook::new(vec![
+        CallbackTimer::new(TEST_UNIT * 10, || triggered.set(triggered.get() + 1)),
+        CallbackTimer::new(TEST_UNIT * 10, || triggered.set(triggered.get() + 10)),
+    ])
+    .register(paused.clone());
+
+    timer.poll(TEST_UNIT * 10).unwrap();
+    assert_eq!(triggered.get(), 1);
+
+    // Pausing stops the chain, like the daemon does
+    paused.set(true);
+    timer.abort().unwrap();
+    assert!(timer.is_aborted());
+    for idle in [20, 50, 1000] {
+        timer.poll(TEST_UNIT * idle).unwrap();
+    }
+    // Even when the user comes back for a while
+    timer.poll(Duration::default()).unwrap();
+    for idle in [10, 20, 1000] {
+        timer.poll(TEST_UNIT * idle).unwrap();
+    }
+    assert_eq!(triggered.get(), 1);
+
+    // Once resumed, timers run again from the start
+    paused.set(false);
+    timer.poll(Duration::default()).unwrap();
+    timer.poll(TEST_UNIT * 10).unwrap();
+    timer.poll(TEST_UNIT * 20).unwrap();
+    assert_eq!(triggered.get(), 12);
+}
@@ -0,0 +1,31 @@
+use std::{
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use xidlehook_core::{status::Status, timers::CallbackTimer, Xidlehook};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+#[test]
+fn uptime() {
+    let before = SystemTime::now();
+    let timer = Xidlehook::new(vec![CallbackTimer::new(TEST_UNIT, || ())]);
+    let after = SystemTime::now();
+
+    assert!(before <= timer.started_at() && timer.started_at() <= after);
+
+    let first = timer.uptime();
+    thread::sleep(TEST_UNIT);
+    let timer = timer.register(());
+    assert!(timer.uptime() >= first + TEST_UNIT);
+    assert!(
+        timer.started_at() <= after,
+        "registering keeps the start time"
+    );
+
+    let status = Status::default().with_uptime(timer.started_at(), Duration::from_millis(61_999));
+    let since_epoch = timer.started_at().duration_since(UNIX_EPOCH).unwrap();
+    assert_eq!(status.started_at, since_epoch.as_secs());
+    assert!(status.started_at > 0);
+    assert_eq!(status.uptime_seconds, 61);
+}
@@ -0,0 +1,128 @@
+use std::{fs, process::Command, thread, time::Duration};
+use xidlehook_core::{timers::CallbackTimer, Action::*, Watchdog, Xidlehook};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+#[test]
+fn watchdog() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dir = std::env::temp_dir().join(format!("xidlehook-watchdog-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let heartbeat = dir.join("heartbeat");
+    let recovered = dir.join("recovered");
+
+    let mut recovery = Command::new("touch");
+    recovery.arg(&recovered);
+
+    let mut timer = Xidlehook::new(vec![
+        CallbackTimer::new(TEST_UNIT * 10, || ()),
+        // A timer which hangs the poll loop
+        CallbackTimer::new(TEST_UNIT * 10, || thread::sleep(TEST_UNIT * 3)),
+    ])
+    .with_watchdog(
+        Watchdog::new()
+            .with_heartbeat(&heartbeat, TEST_UNIT * 4)
+            .with_max_iteration(TEST_UNIT * 2, Some(recovery)),
+    );
+
+    // Sleeps are capped to the heartbeat interval
+    assert_eq!(
+        timer.poll(Duration::default()).unwrap(),
+        Sleep(TEST_UNIT * 4)
+    );
+    assert_eq!(fs::read_to_string(&heartbeat).unwrap(), "1\n");
+    assert_eq!(timer.poll(TEST_UNIT * 9).unwrap(), Sleep(TEST_UNIT * 1));
+    assert_eq!(fs::read_to_string(&heartbeat).unwrap(), "2\n");
+
+    // Quick polls don't run the recovery command
+    assert_eq!(timer.poll(TEST_UNIT * 10).unwrap(), Sleep(TEST_UNIT * 4));
+    assert!(timer.watchdog_mut().unwrap().recovery_child().is_none());
+
+    // ... but one which overruns the maximum does
+    assert_eq!(timer.poll(TEST_UNIT * 20).unwrap(), Sleep(TEST_UNIT * 4));
+    let watchdog = timer.watchdog_mut().unwrap();
+    assert_eq!(watchdog.beats(), 4);
+    assert!(watchdog.recovery_child().unwrap().wait().unwrap().success());
+    assert!(recovered.exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn stuck_idle() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dir = std::env::temp_dir().join(format!("xidlehook-stuck-idle-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let reported = dir.join("reported");
+
+    let mut command = Command::new("touch");
+    command.arg(&reported);
+
+    let mut timer = Xidlehook::new(vec![
+        CallbackTimer::new(TEST_UNIT * 2, || ()),
+        CallbackTimer::new(TEST_UNIT * 10, || ()),
+    ])
+    .with_watchdog(Watchdog::new().with_stuck_idle(3, Some(command)));
+
+    // An idle time that grows, or falls as the user is active, is fine
+    for &idle in &[0, 1, 0, 1, 0, 1, 0, 1] {
+        timer.poll(TEST_UNIT * idle).unwrap();
+        thread::sleep(TEST_UNIT);
+    }
+    assert!(timer.watchdog_mut().unwrap().stuck_idle_child().is_none());
+
+    // One that never moves, while time passes, is not
+    for _ in 0..8 {
+        timer.poll(Duration::from_millis(3)).unwrap();
+        thread::sleep(TEST_UNIT);
+    }
+    let watchdog = timer.watchdog_mut().unwrap();
+    assert!(watchdog
+        .stuck_idle_child()
+        .unwrap()
+        .wait()
+        .unwrap()
+        .success());
+    assert!(reported.exists());
+
+    // It's reported only once
+    fs::remove_file(&reported).unwrap();
+    timer.poll(Duration::from_millis(3)).unwrap();
+    assert!(!reported.exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn failing_watchdog() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dir = std::env::temp_dir().join(format!("xidlehook-failing-{}", std::process::id()));
+    let mut recovery = Command::new("sleep");
+    recovery.arg("10");
+
+    let mut timer = Xidlehook::new(vec![CallbackTimer::new(TEST_UNIT * 10, || {
+        thread::sleep(TEST_UNIT * 3)
+    })])
+    .with_watchdog(
+        Watchdog::new()
+            // The directory doesn't exist, so writing the heartbeat fails
+            .with_heartbeat(dir.join("heartbeat"), TEST_UNIT * 4)
+            .with_max_iteration(TEST_UNIT * 2, Some(recovery)),
+    );
+
+    // ... which is only a warning, and the overrun is still noticed
+    assert_eq!(timer.poll(TEST_UNIT * 10).unwrap(), Sleep(TEST_UNIT * 4));
+    let pid = timer.watchdog_mut().unwrap().recovery_child().unwrap().id();
+
+    // The recovery command isn't run again while it's still running
+    timer.poll(Duration::default()).unwrap();
+    assert_eq!(timer.poll(TEST_UNIT * 10).unwrap(), Sleep(TEST_UNIT * 4));
+    let child = timer.watchdog_mut().unwrap().recovery_child().unwrap();
+    assert_eq!(child.id(), pid);
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
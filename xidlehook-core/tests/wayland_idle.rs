@@ -0,0 +1,108 @@
+use std::{
+    env,
+    io::{Read, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    thread,
+    time::Duration,
+};
+use xidlehook_core::{sources::WaylandIdle, IdleSource};
+
+const TEST_UNIT: Duration = Duration::from_millis(50);
+
+fn message(object: u32, opcode: u16, args: &[u8]) -> Vec<u8> {
+    let size = (args.len() + 8) as u32;
+    let mut out = Vec::new();
+    out.extend_from_slice(&object.to_ne_bytes());
+    out.extend_from_slice(&(size << 16 | u32::from(opcode)).to_ne_bytes());
+    out.extend_from_slice(args);
+    out
+}
+
+fn global(name: u32, interface: &str) -> Vec<u8> {
+    let mut args = name.to_ne_bytes().to_vec();
+    args.extend_from_slice(&(interface.len() as u32 + 1).to_ne_bytes());
+    args.extend_from_slice(interface.as_bytes());
+    args.push(0);
+    while args.len() & 3 != 0 {
+        args.push(0);
+    }
+    args.extend_from_slice(&1u32.to_ne_bytes());
+    message(2, 0, &args)
+}
+
+/// Returns the object and opcode of the next request
+fn request(stream: &mut UnixStream) -> (u32, u16) {
+    let mut header = [0; 8];
+    stream.read_exact(&mut header).unwrap();
+    let object = u32::from_ne_bytes([header[0], header[1], header[2], header[3]]);
+    let word = u32::from_ne_bytes([header[4], header[5], header[6], header[7]]);
+    let mut args = vec![0; (word >> 16) as usize - 8];
+    stream.read_exact(&mut args).unwrap();
+    (object, (word & 0xFFFF) as u16)
+}
+
+/// A compositor advertising these interfaces, which answers the
+/// registry requests and then runs `then`
+fn compositor<F>(name: &str, interfaces: &'static [&'static str], then: F) -> String
+where
+    F: FnOnce(UnixStream) + Send + 'static,
+{
+    let path = env::temp_dir().join(format!("xidlehook-test-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path).unwrap();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        assert_eq!(request(&mut stream), (1, 1)); // get_registry
+        assert_eq!(request(&mut stream), (1, 0)); // sync
+        let mut reply = Vec::new();
+        for (name, interface) in interfaces.iter().enumerate() {
+            reply.extend(global(name as u32 + 1, interface));
+        }
+        reply.extend(message(3, 0, &0u32.to_ne_bytes()));
+        stream.write_all(&reply).unwrap();
+        then(stream);
+    });
+    path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn wayland_idle() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // Without the idle notifier, construction fails so the caller can
+    // fall back to X
+    let path = compositor("unsupported", &["wl_compositor", "wl_seat"], |_| ());
+    let err = WaylandIdle::connect(&path, TEST_UNIT).unwrap_err();
+    assert!(err.to_string().contains("ext_idle_notifier_v1"));
+
+    let path = compositor(
+        "supported",
+        &["wl_compositor", "wl_seat", "ext_idle_notifier_v1"],
+        |mut stream| {
+            assert_eq!(request(&mut stream), (2, 0)); // bind the seat
+            assert_eq!(request(&mut stream), (2, 0)); // bind the notifier
+            assert_eq!(request(&mut stream), (5, 1)); // get_idle_notification
+
+            thread::sleep(TEST_UNIT);
+            stream.write_all(&message(6, 0, &[])).unwrap(); // idled
+            thread::sleep(TEST_UNIT * 4);
+            stream.write_all(&message(6, 1, &[])).unwrap(); // resumed
+            thread::sleep(TEST_UNIT * 2);
+        },
+    );
+    let source = WaylandIdle::connect(&path, TEST_UNIT).unwrap();
+    assert_eq!(source.resolution(), TEST_UNIT);
+    assert_eq!(source.get_idle().unwrap(), Duration::default());
+
+    // Idle counts from the threshold before the notification
+    thread::sleep(TEST_UNIT * 3);
+    let idle = source.get_idle().unwrap();
+    assert!(idle >= TEST_UNIT * 2 && idle < TEST_UNIT * 4, "{:?}", idle);
+
+    thread::sleep(TEST_UNIT * 3);
+    assert_eq!(source.get_idle().unwrap(), Duration::default());
+
+    // Once the compositor is gone, that's an error
+    thread::sleep(TEST_UNIT * 4);
+    assert!(source.get_idle().is_err());
+}
@@ -0,0 +1,41 @@
+use std::collections::HashSet;
+use xidlehook_core::modules::xcb::WindowCache;
+
+#[test]
+fn window_cache() {
+    let mut cache = WindowCache::new(3);
+    for window in 1..=3 {
+        cache.insert(window, window * 10);
+    }
+    assert_eq!(cache.len(), 3);
+
+    // Using window 1 makes window 2 the least recently used, which
+    // is evicted to make room
+    assert_eq!(cache.get(1), Some(&10));
+    cache.insert(4, 40);
+    assert_eq!(cache.len(), 3);
+    assert_eq!(cache.get(2), None);
+    assert_eq!(cache.get(1), Some(&10));
+
+    // Replacing an entry doesn't evict anything
+    cache.insert(4, 41);
+    assert_eq!(cache.len(), 3);
+    assert_eq!(cache.get(3), Some(&30));
+
+    // Windows missing from the next tree are evicted
+    let present: HashSet<_> = vec![1, 4, 5].into_iter().collect();
+    cache.retain_present(&present);
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.get(3), None);
+    assert_eq!(cache.get(4), Some(&41));
+
+    // Shrinking the cache evicts the least recently used windows
+    cache.set_max_entries(1);
+    assert_eq!(cache.len(), 1);
+    assert_eq!(cache.get(4), Some(&41));
+
+    // A zero-sized cache holds nothing
+    cache.set_max_entries(0);
+    cache.insert(6, 60);
+    assert!(cache.is_empty());
+}
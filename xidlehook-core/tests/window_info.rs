@@ -0,0 +1,28 @@
+use xidlehook_core::modules::xcb::{WindowInfo, WindowProperties};
+
+#[test]
+fn window_info() {
+    let props = WindowProperties {
+        wm_class: vec![String::from("navigator"), String::from("Firefox")],
+        wm_name: String::from("Video - Mozilla Firefox"),
+        desktop: Some(1),
+        fullscreen: true,
+        wm_state: Some(1), // NormalState
+        ..WindowProperties::default()
+    };
+    assert_eq!(
+        WindowInfo::from(props),
+        WindowInfo {
+            wm_class: vec![String::from("navigator"), String::from("Firefox")],
+            wm_name: String::from("Video - Mozilla Firefox"),
+            desktop: Some(1),
+            fullscreen: true,
+        }
+    );
+
+    // Windows without the properties are empty
+    assert_eq!(
+        WindowInfo::from(WindowProperties::default()),
+        WindowInfo::default()
+    );
+}
@@ -0,0 +1,25 @@
+use std::{env, fs};
+use xidlehook_core::modules::Xcb;
+
+fn open_fds() -> usize {
+    fs::read_dir("/proc/self/fd").map_or(0, |fds| fds.count())
+}
+
+#[test]
+fn xcb_connections() {
+    // This needs an X server
+    if env::var_os("DISPLAY").is_none() {
+        return;
+    }
+
+    let before = open_fds();
+    for i in 0..2000 {
+        let xcb = Xcb::new().unwrap();
+        xcb.get_idle().unwrap();
+        if i % 2 == 0 {
+            xcb.close().unwrap();
+        }
+    }
+    // Other tests may open files meanwhile, but not thousands
+    assert!(open_fds() < before + 100);
+}
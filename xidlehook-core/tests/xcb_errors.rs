@@ -0,0 +1,36 @@
+use std::env;
+use xidlehook_core::modules::{xcb::XidlehookError, Xcb};
+
+#[test]
+fn xcb_errors() {
+    // There's no X server on a display this high
+    env::set_var("DISPLAY", ":4093");
+    let err = Xcb::new().unwrap_err();
+    match err.downcast_ref::<XidlehookError>() {
+        Some(XidlehookError::Connect { display, .. }) => assert_eq!(display, ":4093"),
+        other => panic!("expected a connection error, got {:?}", other),
+    }
+
+    // A display given explicitly is named in the error
+    for display in &["not a display", ":4093", "nul\0"] {
+        let err = Xcb::with_display(Some(display)).unwrap_err().to_string();
+        assert!(
+            err.contains(&format!("display {}:", display)),
+            "{:?} doesn't name the display",
+            err
+        );
+    }
+
+    // Not needing MIT-SCREEN-SAVER still needs a server
+    match Xcb::without_screensaver(Some(":4093")) {
+        Err(err) => assert!(err.downcast_ref::<XidlehookError>().is_some()),
+        Ok(_) => panic!("connected to :4093"),
+    }
+
+    let err = XidlehookError::MissingExtension("MIT-SCREEN-SAVER");
+    assert_eq!(
+        err.to_string(),
+        "xcb: the X server doesn't support MIT-SCREEN-SAVER"
+    );
+    assert!(!err.is_bad_window());
+}
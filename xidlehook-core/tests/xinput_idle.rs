@@ -0,0 +1,26 @@
+#![cfg(feature = "xinput")]
+
+use std::{env, thread, time::Duration};
+use xidlehook_core::{
+    sources::{InputClass, XInput2Idle},
+    IdleSource,
+};
+
+#[test]
+fn xinput_idle() {
+    // This needs an X server
+    if env::var_os("DISPLAY").is_none() {
+        return;
+    }
+
+    let source = XInput2Idle::with_classes(&[InputClass::Buttons, InputClass::Motion]).unwrap();
+    assert_eq!(source.classes(), &[InputClass::Buttons, InputClass::Motion]);
+
+    // Nobody touches the test server, so the idle time keeps growing
+    let before = source.get_idle().unwrap();
+    thread::sleep(Duration::from_millis(50));
+    assert!(source.get_idle().unwrap() >= before + Duration::from_millis(50));
+
+    // Dropping it stops the event thread, rather than hanging
+    drop(source);
+}
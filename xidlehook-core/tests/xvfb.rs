@@ -0,0 +1,616 @@
+//! Runs the fullscreen detection against a real, headless X server.
+//! These tests are skipped if `Xvfb` isn't installed or won't start.
+
+use nix::sys::socket::{shutdown, Shutdown};
+use std::{
+    cell::{Cell, RefCell},
+    io::{BufRead, BufReader},
+    os::unix::io::AsRawFd,
+    process::{Child, Command, Stdio},
+    rc::Rc,
+    time::Duration,
+};
+use xidlehook_core::{
+    modules::{
+        xcb::{
+            idle_from_ms_since_input, ConnectionEvent, FullscreenOptions, ScanMode,
+            DEFAULT_INHIBIT_PROPERTY, STICKY_DESKTOP,
+        },
+        Xcb,
+    },
+    timers::CallbackTimer,
+    IdleSource, Module, Progress, TimerInfo, Xidlehook,
+};
+
+/// A headless X server, killed when dropped
+struct Xvfb {
+    child: Child,
+    display: String,
+}
+impl Xvfb {
+    /// Start a server on a free display, or return `None` if that's
+    /// not possible here
+    fn start() -> Option<Self> {
+        let mut child = Command::new("Xvfb")
+            .args([
+                "-displayfd",
+                "1",
+                "-nolisten",
+                "tcp",
+                "-screen",
+                "0",
+                "1920x1080x24",
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        // The server writes the display number once it's ready
+        let mut line = String::new();
+        let read = BufReader::new(child.stdout.take()?).read_line(&mut line);
+        let number = read.ok().and_then(|_| line.trim().parse::<u32>().ok());
+        match number {
+            Some(number) => Some(Self {
+                child,
+                display: format!(":{}", number),
+            }),
+            None => {
+                let _ = child.kill();
+                let _ = child.wait();
+                None
+            },
+        }
+    }
+}
+impl Drop for Xvfb {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// A second client, acting as the applications and window manager
+struct Client {
+    conn: xcb::Connection,
+    root: xcb::Window,
+    visual: xcb::Visualid,
+}
+impl Client {
+    fn connect(display: &str) -> Self {
+        let (conn, screen) = xcb::Connection::connect(Some(display)).unwrap();
+        let (root, visual) = {
+            let screen = conn.get_setup().roots().nth(screen as usize).unwrap();
+            (screen.root(), screen.root_visual())
+        };
+        Self { conn, root, visual }
+    }
+    fn atom(&self, name: &str) -> xcb::Atom {
+        xcb::xproto::intern_atom(&self.conn, false, name)
+            .get_reply()
+            .unwrap()
+            .atom()
+    }
+    /// Open a managed-looking window with this `WM_CLASS`
+    fn window(&self, instance: &str, class: &str) -> xcb::Window {
+        let window = self.conn.generate_id();
+        xcb::xproto::create_window(
+            &self.conn,
+            xcb::COPY_FROM_PARENT as u8,
+            window,
+            self.root,
+            0,
+            0,
+            1920,
+            1080,
+            0,
+            xcb::xproto::WINDOW_CLASS_INPUT_OUTPUT as u16,
+            self.visual,
+            &[],
+        );
+        let wm_class = format!("{}\0{}\0", instance, class);
+        self.set(
+            window,
+            xcb::xproto::ATOM_WM_CLASS,
+            xcb::xproto::ATOM_STRING,
+            wm_class.as_bytes(),
+        );
+        // There's no window manager to say it's mapped
+        let wm_state = self.atom("WM_STATE");
+        self.set32(window, wm_state, wm_state, &[1, 0]);
+        xcb::xproto::map_window(&self.conn, window);
+        self.sync();
+        window
+    }
+    fn set(&self, window: xcb::Window, property: xcb::Atom, type_: xcb::Atom, value: &[u8]) {
+        xcb::xproto::change_property(
+            &self.conn,
+            xcb::xproto::PROP_MODE_REPLACE as u8,
+            window,
+            property,
+            type_,
+            8,
+            value,
+        );
+        self.sync();
+    }
+    fn set32(&self, window: xcb::Window, property: xcb::Atom, type_: xcb::Atom, value: &[u32]) {
+        xcb::xproto::change_property(
+            &self.conn,
+            xcb::xproto::PROP_MODE_REPLACE as u8,
+            window,
+            property,
+            type_,
+            32,
+            value,
+        );
+        self.sync();
+    }
+    fn set_fullscreen(&self, window: xcb::Window, fullscreen: bool) {
+        let state = if fullscreen {
+            vec![self.atom("_NET_WM_STATE_FULLSCREEN")]
+        } else {
+            Vec::new()
+        };
+        self.set32(
+            window,
+            self.atom("_NET_WM_STATE"),
+            xcb::xproto::ATOM_ATOM,
+            &state,
+        );
+    }
+    fn set_desktop(&self, window: xcb::Window, property: &str, desktop: u32) {
+        self.set32(
+            window,
+            self.atom(property),
+            xcb::xproto::ATOM_CARDINAL,
+            &[desktop],
+        );
+    }
+    /// Wait for the server to have handled every request so far
+    fn sync(&self) {
+        xcb::xproto::get_input_focus(&self.conn)
+            .get_reply()
+            .unwrap();
+    }
+}
+
+#[test]
+fn xvfb_fullscreen() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let server = match Xvfb::start() {
+        Some(server) => server,
+        None => return,
+    };
+    let client = Client::connect(&server.display);
+    let xcb = Xcb::with_display(Some(&server.display)).unwrap();
+    xcb.get_idle().unwrap();
+    // The raw counter and the duration agree, give or take the time
+    // between the two requests
+    let raw = xcb.get_idle_ms().unwrap();
+    let idle = xcb.get_idle().unwrap();
+    assert!(
+        idle >= idle_from_ms_since_input(raw),
+        "{:?} < {} ms",
+        idle,
+        raw
+    );
+    assert!(idle < idle_from_ms_since_input(raw) + Duration::from_secs(1));
+    // Every screen's root has an idle time of its own
+    let root = client.root;
+    let all = xcb.get_idle_all().unwrap();
+    assert_eq!(all.keys().collect::<Vec<_>>(), [&root]);
+    assert!(xcb.get_idle_for_root(root).unwrap() >= all[&root]);
+    assert!(xcb.get_idle_for_root(root + 1).is_err());
+    let full_tree = FullscreenOptions {
+        scan_mode: ScanMode::FullTree,
+        ..FullscreenOptions::default()
+    };
+    assert!(!xcb.get_fullscreen().unwrap());
+
+    let mpv = client.window("mpv", "mpv");
+    assert!(!xcb.get_fullscreen().unwrap());
+    client.set_fullscreen(mpv, true);
+    // Without _NET_ACTIVE_WINDOW, the whole tree is walked either way
+    assert!(xcb.get_fullscreen().unwrap());
+    assert_eq!(xcb.find_fullscreen_with(&full_tree).unwrap(), Some(mpv));
+    // Without a window manager, mpv is one level under the root
+    let top_level = FullscreenOptions {
+        scan_mode: ScanMode::TopLevel,
+        ..FullscreenOptions::default()
+    };
+    assert_eq!(xcb.find_fullscreen_with(&top_level).unwrap(), Some(mpv));
+    assert!(xcb.is_window_fullscreen(mpv).unwrap());
+
+    // Exceptions, including globs and names that aren't ASCII
+    let except = |options: FullscreenOptions| FullscreenOptions {
+        scan_mode: ScanMode::FullTree,
+        ..options
+    };
+    assert!(!xcb
+        .get_fullscreen_with(&except(FullscreenOptions {
+            exceptions_wm_class1: vec!["mpv".into()],
+            ..FullscreenOptions::default()
+        }))
+        .unwrap());
+    // ... which apply to a single window too
+    let mpv_class = FullscreenOptions {
+        exceptions_wm_class1: vec!["mpv".into()],
+        ..FullscreenOptions::default()
+    };
+    assert!(!xcb.is_window_fullscreen_with(mpv, &mpv_class).unwrap());
+    assert!(!xcb
+        .get_fullscreen_with(&except(FullscreenOptions {
+            exceptions_wm_class2: vec!["m?v*".into()],
+            ..FullscreenOptions::default()
+        }))
+        .unwrap());
+    let title = "Café ▶ Playing";
+    client.set(
+        mpv,
+        client.atom("_NET_WM_NAME"),
+        client.atom("UTF8_STRING"),
+        title.as_bytes(),
+    );
+    assert_eq!(xcb.window_info(mpv).unwrap().wm_name, title);
+    assert!(!xcb
+        .get_fullscreen_with(&except(FullscreenOptions {
+            exceptions_wm_name: vec![title.into()],
+            ..FullscreenOptions::default()
+        }))
+        .unwrap());
+    client.set(
+        mpv,
+        client.atom("WM_WINDOW_ROLE"),
+        xcb::xproto::ATOM_STRING,
+        b"pip",
+    );
+    let roles = |roles: &[&str]| {
+        except(FullscreenOptions {
+            exceptions_wm_role: roles.iter().map(|&role| role.into()).collect(),
+            ..FullscreenOptions::default()
+        })
+    };
+    assert!(!xcb.get_fullscreen_with(&roles(&["pip"])).unwrap());
+    assert!(xcb.get_fullscreen_with(&roles(&["browser"])).unwrap());
+
+    // Only windows on the current desktop count
+    client.set_desktop(mpv, "_NET_WM_DESKTOP", 0);
+    client.set_desktop(client.root, "_NET_CURRENT_DESKTOP", 1);
+    assert!(!xcb.get_fullscreen_with(&full_tree).unwrap());
+    client.set_desktop(client.root, "_NET_CURRENT_DESKTOP", 0);
+    assert!(xcb.get_fullscreen_with(&full_tree).unwrap());
+    client.set_desktop(client.root, "_NET_CURRENT_DESKTOP", 1);
+    client.set_desktop(mpv, "_NET_WM_DESKTOP", STICKY_DESKTOP);
+    assert!(xcb.get_fullscreen_with(&full_tree).unwrap());
+    assert!(!xcb
+        .get_fullscreen_with(&FullscreenOptions {
+            ignore_sticky_windows: true,
+            ..full_tree.clone()
+        })
+        .unwrap());
+
+    // With an active window, only it is checked by default
+    let editor = client.window("gvim", "Gvim");
+    client.set32(
+        client.root,
+        client.atom("_NET_ACTIVE_WINDOW"),
+        xcb::xproto::ATOM_WINDOW,
+        &[editor],
+    );
+    assert!(!xcb.get_fullscreen().unwrap());
+    assert_eq!(xcb.find_fullscreen_with(&full_tree).unwrap(), Some(mpv));
+    client.set_fullscreen(editor, true);
+    assert!(xcb.get_fullscreen().unwrap());
+
+    // Leaving fullscreen is noticed
+    client.set_fullscreen(editor, false);
+    client.set_fullscreen(mpv, false);
+    assert!(!xcb.get_fullscreen_with(&full_tree).unwrap());
+}
+
+#[test]
+fn xvfb_override_redirect() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let server = match Xvfb::start() {
+        Some(server) => server,
+        None => return,
+    };
+    let client = Client::connect(&server.display);
+    let xcb = Xcb::with_display(Some(&server.display)).unwrap();
+
+    // Like an SDL game, which isn't managed and doesn't say it's
+    // fullscreen
+    let game = client.conn.generate_id();
+    xcb::xproto::create_window(
+        &client.conn,
+        xcb::COPY_FROM_PARENT as u8,
+        game,
+        client.root,
+        0,
+        0,
+        1920,
+        1080,
+        0,
+        xcb::xproto::WINDOW_CLASS_INPUT_OUTPUT as u16,
+        client.visual,
+        &[(xcb::xproto::CW_OVERRIDE_REDIRECT, 1)],
+    );
+    xcb::xproto::map_window(&client.conn, game);
+    client.sync();
+
+    let options = FullscreenOptions {
+        override_redirect: true,
+        ..FullscreenOptions::default()
+    };
+    assert!(!xcb.get_fullscreen().unwrap());
+    assert_eq!(xcb.find_fullscreen_with(&options).unwrap(), Some(game));
+    let hits = xcb.fullscreen_windows(&options).unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].window, game);
+
+    xcb::xproto::configure_window(
+        &client.conn,
+        game,
+        &[(xcb::xproto::CONFIG_WINDOW_WIDTH as u16, 800)],
+    );
+    client.sync();
+    assert!(!xcb.get_fullscreen_with(&options).unwrap());
+}
+
+#[test]
+fn xvfb_fullscreen_reset() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let server = match Xvfb::start() {
+        Some(server) => server,
+        None => return,
+    };
+    let client = Client::connect(&server.display);
+    let xcb = Rc::new(Xcb::with_display(Some(&server.display)).unwrap());
+    let movie = client.window("mpv", "mpv");
+    client.set_fullscreen(movie, true);
+
+    let locked = Cell::new(0);
+    let second = Duration::from_secs(1);
+    let run = |progress| {
+        locked.set(0);
+        let module = Rc::clone(&xcb)
+            .not_when_fullscreen()
+            .with_progress(progress);
+        assert_eq!(module.progress(), progress);
+        let timers = vec![CallbackTimer::new(10 * second, || {
+            locked.set(locked.get() + 1)
+        })];
+        let mut xidlehook = Xidlehook::new(timers).with_module(module);
+        client.set_fullscreen(movie, true);
+        xidlehook.poll(10 * second).unwrap();
+        // The movie ends, with the user still away
+        client.set_fullscreen(movie, false);
+        xidlehook.poll(15 * second).unwrap();
+        xidlehook.poll(20 * second).unwrap();
+        xidlehook.poll(60 * second).unwrap();
+        locked.get()
+    };
+    // Aborting gives up until the user is back
+    assert_eq!(run(Progress::Abort), 0);
+    // Resetting counts from when the window was last fullscreen
+    assert_eq!(run(Progress::Reset), 1);
+}
+
+#[test]
+fn xvfb_fullscreen_cache() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let server = match Xvfb::start() {
+        Some(server) => server,
+        None => return,
+    };
+    let client = Client::connect(&server.display);
+    let xcb = Rc::new(Xcb::with_display(Some(&server.display)).unwrap());
+    let movie = client.window("mpv", "mpv");
+    client.set_fullscreen(movie, true);
+
+    let mut dim = Rc::clone(&xcb).not_when_fullscreen();
+    let mut lock = Rc::clone(&xcb).not_when_fullscreen();
+    let mut other = Rc::clone(&xcb).not_when_fullscreen_with(FullscreenOptions {
+        exceptions_wm_class1: vec!["mpv".into()],
+        ..FullscreenOptions::default()
+    });
+    let timer = |index| TimerInfo { index, length: 2 };
+    let scans = || xcb.fullscreen_scans();
+
+    // Before the first tick, nothing is cached
+    let before = scans();
+    dim.pre_timer(timer(0)).unwrap();
+    lock.pre_timer(timer(1)).unwrap();
+    assert_eq!(scans(), before + 2);
+
+    // Within a tick, the same options search once
+    for _ in 0..2 {
+        xcb.refresh();
+        let before = scans();
+        assert_eq!(dim.pre_timer(timer(0)).unwrap(), Progress::Abort);
+        assert_eq!(lock.pre_timer(timer(1)).unwrap(), Progress::Abort);
+        assert_eq!(scans(), before + 1);
+        // ... and others search for themselves
+        assert_eq!(other.pre_timer(timer(1)).unwrap(), Progress::Continue);
+        assert_eq!(scans(), before + 2);
+    }
+
+    // A new tick sees the window leave fullscreen
+    client.set_fullscreen(movie, false);
+    assert_eq!(lock.pre_timer(timer(1)).unwrap(), Progress::Abort);
+    xcb.refresh();
+    assert_eq!(lock.pre_timer(timer(1)).unwrap(), Progress::Continue);
+}
+
+#[test]
+fn xvfb_reloaded_exceptions() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let server = match Xvfb::start() {
+        Some(server) => server,
+        None => return,
+    };
+    let client = Client::connect(&server.display);
+    let xcb = Rc::new(Xcb::with_display(Some(&server.display)).unwrap());
+    let movie = client.window("mpv", "mpv");
+    client.set_fullscreen(movie, true);
+
+    let info = TimerInfo {
+        index: 0,
+        length: 1,
+    };
+    let mut module = Rc::clone(&xcb).not_when_fullscreen();
+    let options = module.shared_options();
+    assert_eq!(module.pre_timer(info).unwrap(), Progress::Abort);
+
+    // Like on SIGHUP, without a new module or connection
+    options.borrow_mut().set_exceptions(&FullscreenOptions {
+        exceptions_wm_class2: vec!["mpv".into()],
+        ..FullscreenOptions::default()
+    });
+    assert_eq!(module.pre_timer(info).unwrap(), Progress::Continue);
+    assert_eq!(
+        module.options().exceptions_wm_class2,
+        vec![String::from("mpv")]
+    );
+
+    options
+        .borrow_mut()
+        .set_exceptions(&FullscreenOptions::default());
+    assert_eq!(module.pre_timer(info).unwrap(), Progress::Abort);
+}
+
+#[test]
+fn xvfb_atoms() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let server = match Xvfb::start() {
+        Some(server) => server,
+        None => return,
+    };
+    let xcb = Xcb::with_display(Some(&server.display)).unwrap();
+
+    let atoms = xcb.atoms();
+    assert_eq!(atoms.len(), 11);
+    for (name, atom) in atoms {
+        assert_ne!(atom, 0, "{} wasn't interned", name);
+    }
+    assert_eq!(xcb.atom_flushes(), 1);
+}
+
+#[test]
+fn xvfb_iconic() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let server = match Xvfb::start() {
+        Some(server) => server,
+        None => return,
+    };
+    let client = Client::connect(&server.display);
+    let xcb = Xcb::with_display(Some(&server.display)).unwrap();
+    let options = FullscreenOptions {
+        scan_mode: ScanMode::FullTree,
+        ..FullscreenOptions::default()
+    };
+
+    // A minimized fullscreen window doesn't count
+    let movie = client.window("mpv", "mpv");
+    client.set_fullscreen(movie, true);
+    let wm_state = client.atom("WM_STATE");
+    client.set32(movie, wm_state, wm_state, &[3, 0]);
+    assert!(!xcb.get_fullscreen_with(&options).unwrap());
+    assert!(xcb
+        .get_fullscreen_with(&FullscreenOptions {
+            count_iconic: true,
+            ..options.clone()
+        })
+        .unwrap());
+
+    // Until it's shown again
+    client.set32(movie, wm_state, wm_state, &[1, 0]);
+    assert!(xcb.get_fullscreen_with(&options).unwrap());
+}
+
+#[test]
+fn xvfb_root_property() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let server = match Xvfb::start() {
+        Some(server) => server,
+        None => return,
+    };
+    let client = Client::connect(&server.display);
+    let xcb = Rc::new(Xcb::with_display(Some(&server.display)).unwrap());
+    let mut module = Rc::clone(&xcb)
+        .not_when_root_property()
+        .with_property("_CAFFEINE_HEARTBEAT");
+    let info = TimerInfo {
+        index: 0,
+        length: 1,
+    };
+
+    // The property doesn't exist yet
+    assert_eq!(module.pre_timer(info).unwrap(), Progress::Continue);
+
+    let inhibit = client.atom(DEFAULT_INHIBIT_PROPERTY);
+    client.set32(client.root, inhibit, xcb::xproto::ATOM_CARDINAL, &[1]);
+    assert_eq!(module.pre_timer(info).unwrap(), Progress::Abort);
+
+    // Zero doesn't inhibit, and neither does an absent property
+    client.set32(client.root, inhibit, xcb::xproto::ATOM_CARDINAL, &[0]);
+    assert_eq!(module.pre_timer(info).unwrap(), Progress::Continue);
+    client.set32(client.root, inhibit, xcb::xproto::ATOM_CARDINAL, &[1]);
+    xcb::xproto::delete_property(&client.conn, client.root, inhibit);
+    client.sync();
+    assert_eq!(module.pre_timer(info).unwrap(), Progress::Continue);
+
+    // Any of the properties inhibits, including as text
+    let heartbeat = client.atom("_CAFFEINE_HEARTBEAT");
+    client.set(client.root, heartbeat, xcb::xproto::ATOM_STRING, b"on");
+    assert_eq!(module.pre_timer(info).unwrap(), Progress::Abort);
+    client.set(client.root, heartbeat, xcb::xproto::ATOM_STRING, b"0");
+    assert_eq!(module.pre_timer(info).unwrap(), Progress::Continue);
+}
+
+#[test]
+fn xvfb_connection_events() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let server = match Xvfb::start() {
+        Some(server) => server,
+        None => return,
+    };
+    let xcb = Xcb::with_display(Some(&server.display)).unwrap();
+    let events = Rc::new(RefCell::new(Vec::new()));
+    xcb.set_connection_observer({
+        let events = Rc::clone(&events);
+        move |event| events.borrow_mut().push(event)
+    });
+    xcb.get_idle().unwrap();
+    assert!(events.borrow().is_empty());
+
+    // Breaking the connection is told once, and so is getting it back
+    let regained = ConnectionEvent::Regained { attempts: 1 };
+    for reconnects in 1..=2 {
+        shutdown(xcb.as_raw_fd(), Shutdown::Both).unwrap();
+        xcb.get_idle().unwrap();
+        assert_eq!(xcb.reconnects(), reconnects);
+        assert_eq!(events.borrow().len(), 2 * reconnects as usize);
+        assert_eq!(
+            events.borrow()[events.borrow().len() - 2..],
+            [ConnectionEvent::Lost, regained]
+        );
+    }
+
+    // A server that's gone is only told about once
+    events.borrow_mut().clear();
+    drop(server);
+    assert!(xcb.get_idle().is_err());
+    assert!(xcb.get_idle().is_err());
+    assert_eq!(*events.borrow(), [ConnectionEvent::Lost]);
+}
@@ -1,10 +1,12 @@
 use std::{
     io::{prelude::*, BufReader, LineWriter},
     os::unix::net::UnixStream,
+    process,
     time::Duration,
 };
 
 use structopt::{clap::arg_enum, StructOpt};
+use xidlehook_core::status::EXIT_NOT_RUNNING;
 
 #[allow(dead_code)]
 mod socket {
@@ -59,6 +61,10 @@ enum Subcommands {
         /// deactivation = next timer's activation basically)
         #[structopt(long, value_terminator = ";", allow_hyphen_values = true)]
         deactivation: Vec<String>,
+        /// Run the activation again every this many seconds while
+        /// the user stays idle after it
+        #[structopt(long)]
+        recurring: Option<u64>,
     },
     /// A control operation
     Control {
@@ -78,6 +84,11 @@ enum Subcommands {
     /// this behaviour implicitly to avoid potential glitches that could arise when you modify
     /// currently running timers.
     StopTimers,
+    /// Keep all timers from running until resumed, like SIGUSR1. The
+    /// canceller of the last timer that went off is run.
+    Pause,
+    /// Let timers run again after pausing them
+    Resume,
     /// Query the list of timers
     Query {
         /// The timers which this operation should apply to. Leave
@@ -85,6 +96,51 @@ enum Subcommands {
         #[structopt(long)]
         timer: Vec<socket::TimerId>,
     },
+    /// Run the activation of a timer right away, followed by its canceller after a delay. This
+    /// does not affect the idle state, and is meant to check that the commands work.
+    Test {
+        /// The timer to test
+        #[structopt(long)]
+        timer: socket::TimerId,
+        /// The number of seconds to wait before running the canceller
+        #[structopt(long, default_value = "3")]
+        delay: u64,
+    },
+    /// Query the idle time, rounded as configured with --status-rounding
+    Status,
+    /// List the windows which are fullscreen right now
+    Fullscreen {
+        /// Only say whether this window is fullscreen, taking the
+        /// exceptions of --not-when-fullscreen into account
+        #[structopt(long, parse(try_from_str = socket::parse_window))]
+        window: Option<u32>,
+    },
+    /// Log every time the user becomes active, with the idle time just
+    /// before, to see if small movements keep resetting the timers
+    LogResets {
+        /// Stop logging them instead
+        #[structopt(long)]
+        off: bool,
+    },
+    /// Show which timers would have fired by now if the user had been
+    /// idle this long, without running any of them
+    Simulate {
+        /// The idle time to simulate, in seconds
+        idle_seconds: u64,
+    },
+    /// Exit with 0 if timers are being kept from running right now,
+    /// printing by which modules, and with 1 if not. If the daemon
+    /// can't be reached, the exit code is 2.
+    IsInhibited,
+    /// List the modules with their settings, in the order they're
+    /// checked in
+    Modules,
+    /// Query the idle time, whether a window is fullscreen, and which
+    /// modules keep timers from running
+    Overview,
+    /// Print the metrics in the Prometheus text format, if xidlehook
+    /// was built with `--features metrics`
+    Metrics,
 }
 
 fn filter(filter: Vec<socket::TimerId>) -> socket::Filter {
@@ -104,12 +160,14 @@ fn main() -> xidlehook_core::Result<()> {
             activation,
             abortion,
             deactivation,
+            recurring,
         } => socket::Message::Add(socket::Add {
             time: Duration::from_secs(time),
             index,
             activation,
             abortion,
             deactivation,
+            recurring: recurring.map(Duration::from_secs),
         }),
         Subcommands::Control { timer, action } => socket::Message::Control(socket::Control {
             timer: filter(timer),
@@ -122,23 +180,66 @@ fn main() -> xidlehook_core::Result<()> {
         }),
         Subcommands::ResetIdle => socket::Message::ResetIdle,
         Subcommands::StopTimers => socket::Message::StopTimers,
+        Subcommands::Pause => socket::Message::Pause,
+        Subcommands::Resume => socket::Message::Resume,
         Subcommands::Query { timer } => socket::Message::Query(socket::Query {
             timer: filter(timer),
         }),
+        Subcommands::Test { timer, delay } => socket::Message::Test(socket::Test {
+            timer,
+            delay: Duration::from_secs(delay),
+        }),
+        Subcommands::Status => socket::Message::Status,
+        Subcommands::Fullscreen { window: None } => socket::Message::Fullscreen,
+        Subcommands::Fullscreen {
+            window: Some(window),
+        } => socket::Message::FullscreenWindow(socket::FullscreenWindow { window }),
+        Subcommands::LogResets { off } => {
+            socket::Message::LogResets(socket::LogResets { enabled: !off })
+        },
+        Subcommands::Simulate { idle_seconds } => {
+            socket::Message::Simulate(socket::Simulate { idle_seconds })
+        },
+        Subcommands::IsInhibited => socket::Message::Inhibition,
+        Subcommands::Modules => socket::Message::Modules,
+        Subcommands::Overview => socket::Message::Overview,
+        Subcommands::Metrics => socket::Message::Metrics,
     };
+    let is_inhibited = matches!(packet, socket::Message::Inhibition);
 
-    let stream = UnixStream::connect(opt.socket)?;
+    let stream = match UnixStream::connect(opt.socket) {
+        Ok(stream) => stream,
+        Err(err) if is_inhibited => {
+            eprintln!("error: can't reach xidlehook: {}", err);
+            process::exit(EXIT_NOT_RUNNING);
+        },
+        Err(err) => return Err(err.into()),
+    };
     let reader = BufReader::new(&stream);
     let mut writer = LineWriter::new(&stream);
 
     serde_json::to_writer(&mut writer, &packet)?;
-    writer.write_all(&[b'\n'])?;
+    writer.write_all(b"\n")?;
     writer.flush()?;
 
     if let Some(line) = reader.lines().next() {
         let reply: socket::Reply = serde_json::from_str(&line?)?;
+        if let socket::Reply::Inhibition(inhibition) = reply {
+            for inhibitor in &inhibition.inhibitors {
+                println!("{}: {}", inhibitor.module, inhibitor.verdict);
+            }
+            process::exit(inhibition.exit_code());
+        }
+        if let socket::Reply::Metrics(metrics) = reply {
+            print!("{}", metrics.metrics);
+            return Ok(());
+        }
         println!("{:#?}", reply);
     }
+    if is_inhibited {
+        // There was no answer to tell by
+        process::exit(EXIT_NOT_RUNNING);
+    }
 
     Ok(())
 }
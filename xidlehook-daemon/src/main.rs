@@ -13,23 +13,69 @@
     clippy::integer_division,
 )]
 
-use std::{fs, rc::Rc, time::Duration};
+use std::{
+    convert::TryFrom,
+    env, fs,
+    io::Write,
+    os::unix::io::AsRawFd,
+    process::Command,
+    rc::Rc,
+    time::{Duration, Instant, SystemTime},
+};
 
-use log::{trace, warn};
-use nix::sys::wait;
-use structopt::StructOpt;
+use log::{info, trace, warn};
+use nix::sys::signal::Signal;
+use structopt::{clap::arg_enum, StructOpt};
 use tokio::{
+    io::unix::AsyncFd,
     signal::unix::{signal, SignalKind},
     sync::mpsc,
+    time,
 };
 use xidlehook_core::{
-    modules::{StopAt, Xcb},
-    Module, Xidlehook,
+    daily::DailyTimer,
+    history::History,
+    logging,
+    modules::{
+        xcb::{
+            FullscreenOptions, FullscreenWatch, IdleAggregate, IdleTarget, ScanMode,
+            DEFAULT_MAX_DEPTH,
+        },
+        Backlight, LidSwitch, ModuleList, NotWhenProcess, OnResume, PowerSource, PowerState,
+        ResetOnRelease, StopAt, TimeRange, TimeWindow, Toggle, Xcb,
+    },
+    sources::{
+        init_idle_source, Backend, CachedIdleSource, FallbackIdleSource, IdleSource,
+        MissingIdleSource, WaylandIdle,
+    },
+    state::State,
+    timers::{CommandLimit, QueueFull, ShellWorker, SpawnHook},
+    Module, Progress, Timer, Watchdog, Xidlehook,
 };
 
 mod socket;
 mod timers;
 
+/// The names of the modules, for `--module-order`
+const MODULE_NAMES: &[&str] = &[
+    "disabled",
+    "presentation",
+    "once",
+    "on-resume",
+    "fullscreen-hook",
+    "not-when-fullscreen",
+    "not-when-screensaver-disabled",
+    "not-when-screen-off",
+    "not-when-root-property",
+    "not-when-audio",
+    "not-when-inhibited",
+    "not-when-media-playing",
+    "not-when-process",
+    "not-on-power",
+    "time-window",
+    "dim-backlight",
+];
+
 use self::timers::CmdTimer;
 
 struct Defer<F: FnMut()>(F);
@@ -39,6 +85,50 @@ impl<F: FnMut()> Drop for Defer<F> {
     }
 }
 
+arg_enum! {
+    #[derive(Debug)]
+    pub enum OptMissing {
+        Error,
+        Retry,
+        Exit,
+    }
+}
+
+arg_enum! {
+    #[derive(Debug)]
+    pub enum OptAggregate {
+        Min,
+        Max,
+    }
+}
+
+arg_enum! {
+    #[derive(Debug)]
+    pub enum OptPower {
+        Ac,
+        Battery,
+    }
+}
+
+arg_enum! {
+    #[derive(Debug)]
+    pub enum OptLogFormat {
+        Text,
+        Json,
+    }
+}
+
+#[cfg(feature = "xinput")]
+arg_enum! {
+    #[derive(Debug)]
+    pub enum OptInputClass {
+        Keys,
+        Buttons,
+        Motion,
+        Touch,
+    }
+}
+
 #[derive(StructOpt, Debug)]
 pub struct Opt {
     /// Print the idle time to standard output. This is similar to xprintidle.
@@ -53,6 +143,205 @@ pub struct Opt {
     /// videos.
     #[structopt(long, conflicts_with("print"))]
     pub not_when_fullscreen: bool,
+    /// Look for any fullscreen window, rather than only checking the
+    /// active window. Window managers which don't tell which window is
+    /// active get every window checked regardless.
+    #[structopt(long, requires("not-when-fullscreen"))]
+    pub full_tree: bool,
+    /// Only look at the windows directly below the root window, and
+    /// the client windows inside frames among them, for any fullscreen
+    /// window. This is cheaper than --full-tree, and suits window
+    /// managers which don't reparent, or put each window in a single
+    /// frame.
+    #[structopt(long, requires("not-when-fullscreen"), conflicts_with("full-tree"))]
+    pub top_level: bool,
+    /// Only check the active window, which is the default now
+    #[structopt(long, hidden = true, conflicts_with("full-tree"))]
+    pub active_window_only: bool,
+    /// Cache the WM_CLASS of at most this many windows while looking
+    /// for fullscreen windows
+    #[structopt(long, requires("not-when-fullscreen"))]
+    pub window_cache_size: Option<usize>,
+    /// Listen for fullscreen windows leaving fullscreen, and start
+    /// counting the idle time from then rather than waiting for the
+    /// user to be active
+    #[structopt(long, requires("not-when-fullscreen"))]
+    pub watch_fullscreen: bool,
+    /// Start the timers over whenever a window is found fullscreen,
+    /// rather than giving up on them until the user is active, so they
+    /// count from about when the window left fullscreen
+    #[structopt(long, requires("not-when-fullscreen"))]
+    pub fullscreen_reset: bool,
+    /// Start the timers over once no window is fullscreen anymore, so
+    /// they all wait their whole time again rather than going off right
+    /// away after a long video. Mostly useful with --fullscreen-reset,
+    /// as otherwise the timers wait for the user to be active.
+    #[structopt(long, requires("not-when-fullscreen"))]
+    pub reset_after_fullscreen: bool,
+    /// Only let --not-when-fullscreen keep the timer at this index from
+    /// running, such as the lock, while the others still go off
+    #[structopt(long, requires("not-when-fullscreen"))]
+    pub fullscreen_timer: Option<usize>,
+    /// Stop looking for fullscreen windows after examining this many
+    /// windows, to bound the cost of each check
+    #[structopt(long, requires("not-when-fullscreen"))]
+    pub max_windows: Option<usize>,
+    /// Don't look for fullscreen windows deeper than this in the
+    /// window tree, where 1 means only the direct children of the root
+    /// window. Defaults to 3.
+    #[structopt(long, requires("not-when-fullscreen"))]
+    pub max_depth: Option<usize>,
+    /// Only count fullscreen windows on this RandR output, like
+    /// HDMI-1. Can be given several times. Without RandR, windows on
+    /// any monitor count.
+    #[structopt(long, requires("not-when-fullscreen"), number_of_values = 1)]
+    pub fullscreen_monitor: Vec<String>,
+    /// Count fullscreen windows on any desktop, not only the current
+    /// one. Use it with --full-tree, as the active window is always on
+    /// the current desktop.
+    #[structopt(long, requires("not-when-fullscreen"))]
+    pub ignore_desktop_match: bool,
+    /// If the window manager doesn't say which desktop is current, only
+    /// count fullscreen windows that aren't on any desktop, or are on
+    /// all of them, rather than counting windows on any desktop
+    #[structopt(
+        long,
+        requires("not-when-fullscreen"),
+        conflicts_with("ignore-desktop-match")
+    )]
+    pub strict_desktop_match: bool,
+    /// Also count minimized fullscreen windows, which don't by default
+    #[structopt(long, requires("not-when-fullscreen"))]
+    pub count_iconic: bool,
+    /// Read the exceptions of --not-when-fullscreen from this TOML or
+    /// JSON file, laid out like the `[modules.not_when_fullscreen]`
+    /// table of a config file. SIGHUP reads it again.
+    #[cfg(feature = "config")]
+    #[structopt(long, requires("not-when-fullscreen"))]
+    pub fullscreen_exceptions: Option<String>,
+    /// Also count windows the window manager doesn't manage as
+    /// fullscreen if they cover the whole screen, like some games using
+    /// SDL. This is a guess, so it may catch other windows, such as
+    /// screen lockers.
+    #[structopt(long, requires("not-when-fullscreen"))]
+    pub fullscreen_override_redirect: bool,
+    /// Run this command when a window enters fullscreen, such as to
+    /// turn off notifications. It's passed through \"/bin/sh -c\".
+    /// Windows are checked with the same options as for
+    /// --not-when-fullscreen, whenever a timer is about to run.
+    #[structopt(long, conflicts_with("print"))]
+    pub on_fullscreen_enter: Option<String>,
+    /// Run this command when no window is fullscreen anymore, see
+    /// --on-fullscreen-enter
+    #[structopt(long, conflicts_with("print"))]
+    pub on_fullscreen_exit: Option<String>,
+    /// Only run --on-fullscreen-enter or --on-fullscreen-exit once a
+    /// change has lasted this many milliseconds, so that windows
+    /// flapping in and out of fullscreen don't run them over and over
+    #[structopt(long, default_value = "2000")]
+    pub fullscreen_debounce: u64,
+    /// Don't invoke the timer while the X screensaver is disabled, such
+    /// as by `xdg-screensaver suspend` or `xset s off`. Don't use this
+    /// if you keep the X screensaver disabled yourself.
+    #[structopt(long, conflicts_with("print"))]
+    pub not_when_screensaver_disabled: bool,
+    /// Don't invoke the timer while DPMS has put the monitors in
+    /// standby, suspended or turned them off, so that a dim or a lock
+    /// doesn't wake them up again
+    #[structopt(long, conflicts_with("print"))]
+    pub not_when_screen_off: bool,
+    /// Only let --not-when-screen-off keep the timer at this index from
+    /// running, such as just the lock. Can be given several times.
+    #[structopt(long, requires("not-when-screen-off"), number_of_values = 1)]
+    pub screen_off_timer: Vec<usize>,
+    /// Don't invoke the timer while the _XIDLEHOOK_INHIBIT property
+    /// of the root window is set to something other than zero, such as
+    /// by `xprop -root -f _XIDLEHOOK_INHIBIT 32c -set
+    /// _XIDLEHOOK_INHIBIT 1`
+    #[structopt(long, conflicts_with("print"))]
+    pub not_when_root_property: bool,
+    /// Watch this root window property for --not-when-root-property
+    /// instead of _XIDLEHOOK_INHIBIT. Can be given several times.
+    #[structopt(long, requires("not-when-root-property"), number_of_values = 1)]
+    pub inhibit_property: Vec<String>,
+    /// Measure the idle time of the screen with this index, rather than
+    /// the first one
+    #[structopt(long)]
+    pub idle_screen: Option<usize>,
+    /// With several X screens, count the user as active if there's
+    /// activity on any screen (min), or only if there's activity on
+    /// all of them (max)
+    #[structopt(
+        long,
+        default_value = "min",
+        possible_values = &OptAggregate::variants(),
+        case_insensitive = true,
+        conflicts_with("idle-screen")
+    )]
+    pub idle_aggregate: OptAggregate,
+    /// Get the idle time from the Wayland compositor, using its
+    /// ext-idle-notify-v1 protocol, instead of from X. X is used if the
+    /// compositor doesn't support it, or stops responding.
+    #[structopt(long)]
+    pub wayland_idle: bool,
+    /// Pick where the idle time comes from by the session: like
+    /// --wayland-idle if $WAYLAND_DISPLAY is set, and from X otherwise
+    #[structopt(long, conflicts_with("wayland-idle"))]
+    pub auto_idle: bool,
+    /// With --wayland-idle or --xinput-idle, go back to it this many
+    /// seconds after it failed and X took over, if it works again.
+    /// Without this, X is kept until it fails too.
+    #[structopt(long)]
+    pub idle_retry: Option<u64>,
+    /// Get the idle time from XInput 2 raw events instead of the X
+    /// screensaver, so that devices it misses, like gamepads, count
+    /// too. The X screensaver is used if XInput 2 isn't supported.
+    #[cfg(feature = "xinput")]
+    #[structopt(long, conflicts_with_all(&["wayland-idle", "auto-idle"]))]
+    pub xinput_idle: bool,
+    /// With --xinput-idle, only count these kinds of input, rather
+    /// than all of them
+    #[cfg(feature = "xinput")]
+    #[structopt(
+        long,
+        possible_values = &OptInputClass::variants(),
+        case_insensitive = true,
+        use_delimiter(true),
+        requires("xinput-idle")
+    )]
+    pub xinput_classes: Vec<OptInputClass>,
+    /// Connect to this X display, like :1, instead of the one in
+    /// $DISPLAY. The commands are run on it too.
+    #[structopt(long)]
+    pub display: Option<String>,
+    /// What to do if the X server can't be connected to: fail, retry
+    /// every --retry-interval seconds until it's up, or exit
+    /// successfully without doing anything
+    #[structopt(
+        long,
+        default_value = "error",
+        possible_values = &OptMissing::variants(),
+        case_insensitive = true
+    )]
+    pub missing_idle_source: OptMissing,
+    /// How many seconds to wait between attempts with
+    /// --missing-idle-source retry
+    #[structopt(long, default_value = "5")]
+    pub retry_interval: u64,
+    /// Log every time the user becomes active, with the idle time just
+    /// before. This can also be switched at runtime over the socket.
+    #[structopt(long, conflicts_with("print"))]
+    pub log_resets: bool,
+    /// How to write logs: as text, or as one JSON object per line
+    /// with the fields of events like timers firing, for log
+    /// aggregation. JSON also shows events without RUST_LOG.
+    #[structopt(
+        long,
+        default_value = "text",
+        possible_values = &OptLogFormat::variants(),
+        case_insensitive = true
+    )]
+    pub log_format: OptLogFormat,
     /// Detect when the system wakes up from a suspend and reset the idle timer
     #[structopt(long, conflicts_with("print"))]
     pub detect_sleep: bool,
@@ -61,39 +350,433 @@ pub struct Opt {
     /// should trigger this timer.
     ///
     /// The command is what is invoked when the idle duration is
-    /// reached. It's passed through \"/bin/sh -c\".
+    /// reached. It's passed through \"/bin/sh -c\", which expands
+    /// variables like $HOME, with $XIDLEHOOK_IDLE_MS set to how many
+    /// milliseconds the user has been idle.
     ///
     /// The canceller is what is invoked when the user becomes active
     /// after the timer has gone off, but before the next timer (if
     /// any). Pass an empty string to not have one.
     #[structopt(long, conflicts_with("print"), required_unless("print"), value_names = &["duration", "command", "canceller"])]
     pub timer: Vec<String>,
+    /// Run this command through \"/bin/sh -c\" at this time of day,
+    /// like 02:00, if the user is idle then, at most once a day. Can be
+    /// given several times.
+    #[structopt(long, conflicts_with("print"), value_names = &["time", "command"])]
+    pub daily: Vec<String>,
+    /// How many seconds the user has to have been idle for at the time
+    /// of day of a --daily command
+    #[structopt(long, conflicts_with("print"), default_value = "60")]
+    pub daily_min_idle: u64,
+
+    /// Let the idle time fall by up to this many milliseconds after a
+    /// timer fired without counting it as activity, and once it fell
+    /// further, only fire that timer again this much past its
+    /// duration. This keeps jittery idle times from firing and
+    /// cancelling timers over and over.
+    #[structopt(long, conflicts_with("print"), default_value = "0")]
+    pub dead_zone: u64,
+    /// Only count the idle time falling as activity once it stayed
+    /// fallen for this many polls in a row, so that a single low
+    /// sample of a jittery idle counter doesn't reset the timers
+    #[structopt(long, conflicts_with("print"), default_value = "1")]
+    pub activity_debounce: usize,
+    /// Run the command of the timer with this index (counting from 0)
+    /// again if it exits unsuccessfully before the user is active, so
+    /// that a screen locker which crashed or was killed locks the
+    /// screen again
+    #[structopt(long, conflicts_with("print"))]
+    pub relock_on_crash: Option<usize>,
+    /// Send SIGTERM to the command of the timer with this index
+    /// (counting from 0) if it's still running when the user is
+    /// active again, such as a fade-out. Can be given several times.
+    #[structopt(long, conflicts_with("print"), number_of_values = 1)]
+    pub kill_on_resume: Vec<usize>,
+    /// Start the commands of the timer with this index (counting from
+    /// 0) in a process group of their own, so that they keep running
+    /// if xidlehook is stopped, such as a long backup. Can be given
+    /// several times.
+    #[structopt(long, conflicts_with("print"), number_of_values = 1)]
+    pub detached: Vec<usize>,
+    /// Run the command of the timer with this index (counting from 0)
+    /// again every this many seconds while the user stays idle after
+    /// it, until the next timer goes off
+    #[structopt(long, conflicts_with("print"), value_names = &["index", "seconds"])]
+    pub recurring: Vec<u64>,
+    /// Only run the command of the timer with this index (counting
+    /// from 0) once until the user is active again, even if the chain
+    /// starts over without them, such as after --watch-fullscreen.
+    /// This can't be used along with --recurring. Can be given several
+    /// times.
+    #[structopt(long, conflicts_with("print"), number_of_values = 1)]
+    pub fire_once: Vec<usize>,
+    /// Only let the timer with this index (counting from 0) go off once
+    /// the user has been idle this many seconds in total
+    #[structopt(long, conflicts_with("print"), value_names = &["index", "seconds"])]
+    pub min_idle: Vec<u64>,
+    /// Skip the timer with this index (counting from 0) once the user
+    /// has been idle more than this many seconds in total. A recurring
+    /// timer stops recurring then, so it only reminds the user for a
+    /// while.
+    #[structopt(long, conflicts_with("print"), value_names = &["index", "seconds"])]
+    pub max_idle: Vec<u64>,
+    /// Run a command this many seconds before the timer with this
+    /// index (counting from 0) goes off, such as to warn that the
+    /// screen is about to be locked. It's killed if the user becomes
+    /// active before the timer goes off.
+    #[structopt(long, conflicts_with("print"), value_names = &["index", "seconds", "command"])]
+    pub warning: Vec<String>,
 
     /// Listen to a unix socket at this address for events.
     /// Each event is one line of JSON data.
     #[structopt(long, conflicts_with("print"))]
     pub socket: Option<String>,
+    /// Also take the socket's messages over HTTP at this address, like
+    /// 127.0.0.1:8080, replying with JSON. Anyone who can connect can
+    /// run commands, as there's no authentication, so keep it on
+    /// localhost.
+    #[cfg(feature = "http")]
+    #[structopt(long, conflicts_with("print"))]
+    pub http: Option<String>,
+    /// Round the idle time reported by the socket's status message to
+    /// the nearest multiple of this many milliseconds. The raw value
+    /// is reported alongside it.
+    #[structopt(long, requires("socket"), default_value = "0")]
+    pub status_rounding: u64,
+
+    /// Run this command once every time the user becomes active after
+    /// having been idle past the first timer, or after a detected
+    /// suspend, regardless of which timers went off. It's passed
+    /// through \"/bin/sh -c\", with $XIDLEHOOK_PRIOR_IDLE set to how
+    /// many seconds the user had been idle for.
+    #[structopt(long, conflicts_with("print"))]
+    pub on_resume: Option<String>,
+    /// Run this command when the laptop lid closes, such as to lock or
+    /// suspend, regardless of the idle time. It's passed through
+    /// \"/bin/sh -c\". Machines without a lid never run it.
+    #[structopt(long, conflicts_with("print"))]
+    pub on_lid_close: Option<String>,
+    /// Run this command when the laptop lid opens again, through
+    /// \"/bin/sh -c\"
+    #[structopt(long, conflicts_with("print"))]
+    pub on_lid_open: Option<String>,
+
+    /// When the user returns, run the cancellers of all timers that
+    /// went off, in reverse order and with this many milliseconds in
+    /// between, instead of only the canceller of the last one
+    #[structopt(long, conflicts_with("print"))]
+    pub canceller_gap: Option<u64>,
+    /// After the user returns from a timer going off, or after a
+    /// detected suspend, don't let timers fire for this many seconds,
+    /// so going idle right after doesn't fire them again soon
+    #[structopt(long, default_value = "0", conflicts_with("print"))]
+    pub resume_grace: u64,
+    /// Wait at least this many milliseconds after a timer fires before
+    /// letting another one fire, so their commands don't race
+    #[structopt(long, default_value = "0", conflicts_with("print"))]
+    pub cooldown: u64,
+
+    /// Never sleep less than this many milliseconds between polls,
+    /// unless a timer is due sooner. This saves power at the cost of
+    /// noticing activity later.
+    #[structopt(long, conflicts_with("print"))]
+    pub poll_interval_min: Option<u64>,
+    /// Never sleep more than this many milliseconds between polls, so
+    /// that activity is noticed sooner
+    #[structopt(long, conflicts_with("print"))]
+    pub poll_interval_max: Option<u64>,
+    /// Reuse the idle time for this many milliseconds, so that asking
+    /// for it several times in one poll, such as over the socket,
+    /// only asks the X server once. 0 disables this.
+    #[structopt(long, default_value = "50")]
+    pub idle_cache: u64,
+
+    /// Append a JSON line to this file every time a timer is activated
+    /// or aborted
+    #[structopt(long, conflicts_with("print"))]
+    pub history: Option<String>,
+    /// Rotate the history file once it would get larger than this
+    /// many bytes, keeping one old file with a .1 suffix
+    #[structopt(long, requires("history"))]
+    pub history_max_size: Option<u64>,
+    /// Keep whether timers are disabled and presentation mode is on in
+    /// this file, so they survive a restart
+    #[structopt(long, conflicts_with("print"))]
+    pub state_file: Option<String>,
+
+    /// Don't run more than this many commands at the same time. Further
+    /// commands are dropped, unless --queue-commands is passed.
+    #[structopt(long, conflicts_with("print"))]
+    pub max_concurrent_commands: Option<usize>,
+    /// Instead of dropping commands over --max-concurrent-commands, run
+    /// them once another command has exited
+    #[structopt(long, requires("max-concurrent-commands"))]
+    pub queue_commands: bool,
+    /// Launch every timer command through this command, which is split
+    /// on whitespace and gets the program and arguments appended. For
+    /// example, \"systemd-run --user --scope\" runs each command in a
+    /// scope of its own.
+    #[structopt(long, conflicts_with("print"))]
+    pub spawn_with: Option<String>,
+    /// Run the cancellers through one long-lived /bin/sh instead of
+    /// starting a new one each time. They run one after another in
+    /// the same shell, so variables and directory changes made by one
+    /// are seen by the next.
+    #[structopt(long, conflicts_with_all(&["print", "spawn-with"]))]
+    pub shell_worker: bool,
+
+    /// Write a counter to this file after every poll, so an external
+    /// watchdog can check that xidlehook is alive
+    #[structopt(long, conflicts_with("print"))]
+    pub heartbeat: Option<String>,
+    /// The longest time, in seconds, to go without rewriting the
+    /// heartbeat file
+    #[structopt(long, conflicts_with("print"), default_value = "60")]
+    pub heartbeat_interval: u64,
+    /// Consider xidlehook hung when a single poll takes longer than
+    /// this many seconds, and log an error
+    #[structopt(long, conflicts_with("print"))]
+    pub max_poll_time: Option<u64>,
+    /// The command to run when a poll took longer than
+    /// --max-poll-time. It's passed through \"/bin/sh -c\".
+    #[structopt(long, conflicts_with("print"), requires("max-poll-time"))]
+    pub recovery: Option<String>,
+    /// Warn that the idle source may be broken when the idle time stays
+    /// below the shortest timer for this many times its length, without
+    /// the user being active, such as when a device keeps it at zero
+    #[structopt(long, conflicts_with("print"))]
+    pub stuck_idle: Option<u32>,
+    /// The command to run when the idle time seems stuck, see
+    /// --stuck-idle. It's passed through \"/bin/sh -c\".
+    #[structopt(long, conflicts_with("print"), requires("stuck-idle"))]
+    pub on_stuck_idle: Option<String>,
 
     /// Don't invoke the timer when any audio is playing (PulseAudio specific)
     #[cfg(feature = "pulse")]
     #[structopt(long, conflicts_with("print"))]
     pub not_when_audio: bool,
+    /// Don't invoke the timer while a program, such as a video player,
+    /// asks for the screen to stay on through the
+    /// org.freedesktop.ScreenSaver D-Bus service, which xidlehook then
+    /// provides
+    #[cfg(feature = "dbus")]
+    #[structopt(long, conflicts_with("print"))]
+    pub not_when_inhibited: bool,
+    /// Don't invoke the timer while a media player is playing, as told
+    /// by its MPRIS D-Bus interface
+    #[cfg(feature = "dbus")]
+    #[structopt(long, conflicts_with("print"))]
+    pub not_when_media_playing: bool,
+    /// Only let --not-when-media-playing look at the MPRIS player of
+    /// this name, like spotify. Can be given several times.
+    #[cfg(feature = "dbus")]
+    #[structopt(long, requires("not-when-media-playing"), number_of_values = 1)]
+    pub media_player: Vec<String>,
+    /// Don't invoke the timer while a process of this name is running.
+    /// Can be given several times.
+    #[structopt(long, conflicts_with("print"), number_of_values = 1)]
+    pub not_when_process: Vec<String>,
+    /// Match processes whose name contains a --not-when-process name,
+    /// rather than exactly matches it
+    #[structopt(long, requires("not-when-process"))]
+    pub process_substring: bool,
+    /// Match the --not-when-process names against the whole command
+    /// line of processes, as well as their name
+    #[structopt(long, requires("not-when-process"))]
+    pub process_cmdline: bool,
+    /// Don't invoke the timer while running off AC power, or off the
+    /// battery, such as to only dim the screen on battery
+    #[structopt(
+        long,
+        conflicts_with("print"),
+        possible_values = &OptPower::variants(),
+        case_insensitive = true
+    )]
+    pub not_on_power: Option<OptPower>,
+    /// Only invoke the timer during this time of day, in local time,
+    /// like 22:00-06:00. Can be given several times.
+    #[structopt(long, conflicts_with("print"), number_of_values = 1)]
+    pub time_window: Vec<TimeRange>,
+    /// Only let --time-window keep the timer at this index from
+    /// running, such as just the one dimming the screen. Can be given
+    /// several times.
+    #[structopt(long, requires("time-window"), number_of_values = 1)]
+    pub time_window_timer: Vec<usize>,
+    /// Dim the screen when the timer at this index goes off, by
+    /// lowering the brightness in /sys/class/backlight, and bring it
+    /// back when the user is active again. This takes write access to
+    /// the brightness, see the README.
+    #[structopt(long, conflicts_with("print"))]
+    pub dim_backlight: Option<usize>,
+    /// The percentage of the maximum brightness --dim-backlight dims
+    /// to
+    #[structopt(long, requires("dim-backlight"), default_value = "10")]
+    pub backlight_level: u32,
+    /// The backlight --dim-backlight uses, like intel_backlight,
+    /// rather than picking one
+    #[structopt(long, requires("dim-backlight"))]
+    pub backlight_device: Option<String>,
+
+    /// Check these modules first, in this order, such as to put cheap
+    /// checks before expensive ones. The names are disabled,
+    /// presentation, once, on-resume, fullscreen-hook,
+    /// not-when-fullscreen, not-when-screensaver-disabled,
+    /// not-when-screen-off, not-when-root-property, not-when-audio,
+    /// not-when-inhibited, not-when-media-playing,
+    /// not-when-process, not-on-power, time-window and dim-backlight.
+    /// The others follow in that order.
+    #[structopt(long, conflicts_with("print"), use_delimiter(true))]
+    pub module_order: Vec<String>,
+    /// Check every module before each timer, instead of stopping at the
+    /// first that keeps the timer from running
+    #[structopt(long, conflicts_with("print"))]
+    pub check_all_modules: bool,
 }
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> xidlehook_core::Result<()> {
-    env_logger::init();
-
     let opt = Opt::from_args();
 
-    let xcb = Rc::new(Xcb::new()?);
+    // The resets are only logged when asked for, so always show them
+    let mut logger = env_logger::Builder::from_default_env();
+    logger.filter_module("xidlehook_core::resets", log::LevelFilter::Info);
+    if let OptLogFormat::Json = opt.log_format {
+        logger
+            .filter_module(logging::EVENTS_TARGET, log::LevelFilter::Info)
+            .format(|buf, record| {
+                writeln!(buf, "{}", logging::json_line(record, SystemTime::now()))
+            });
+    }
+    logger.init();
+
+    let policy = match opt.missing_idle_source {
+        OptMissing::Error => MissingIdleSource::Error,
+        OptMissing::Retry => MissingIdleSource::RetryForever {
+            interval: Duration::from_secs(opt.retry_interval),
+        },
+        OptMissing::Exit => MissingIdleSource::ExitZero,
+    };
+    if let Some(ref display) = opt.display {
+        env::set_var("DISPLAY", display);
+    }
+    let wayland_idle = opt.wayland_idle
+        || (opt.auto_idle
+            && Backend::candidates(|name| env::var_os(name)).first() == Some(&Backend::Wayland));
+    if opt.auto_idle {
+        info!(
+            "Detected a {} session",
+            if wayland_idle { "Wayland" } else { "X" }
+        );
+    }
+    // The X screensaver is only needed if nothing else gives the idle
+    // time
+    #[cfg(feature = "xinput")]
+    let other_idle = wayland_idle || opt.xinput_idle;
+    #[cfg(not(feature = "xinput"))]
+    let other_idle = wayland_idle;
+    let connect = || {
+        if other_idle {
+            Xcb::without_screensaver(opt.display.as_deref())
+        } else {
+            Xcb::with_display(opt.display.as_deref())
+        }
+    };
+    let mut xcb = match init_idle_source(policy, connect)? {
+        Some(xcb) => xcb,
+        None => return Ok(()),
+    };
+    xcb.set_idle_aggregate(match opt.idle_aggregate {
+        OptAggregate::Min => IdleAggregate::Min,
+        OptAggregate::Max => IdleAggregate::Max,
+    });
+    if let Some(screen) = opt.idle_screen {
+        xcb.set_idle_target(IdleTarget::Screen(screen))?;
+    }
+    let xcb = Rc::new(xcb);
+    let fallback = || match opt.idle_retry {
+        Some(secs) => FallbackIdleSource::new().with_retry_after(Duration::from_secs(secs)),
+        None => FallbackIdleSource::new(),
+    };
+    let idle_source: Box<dyn IdleSource> = if wayland_idle {
+        match WaylandIdle::new() {
+            Ok(wayland) => Box::new(
+                fallback()
+                    .with("wayland", wayland)
+                    .with("xcb", Rc::clone(&xcb)),
+            ),
+            Err(err) => {
+                warn!("Using X for the idle time instead: {}", err);
+                Box::new(Rc::clone(&xcb))
+            },
+        }
+    } else {
+        Box::new(Rc::clone(&xcb))
+    };
+    #[cfg(feature = "xinput")]
+    let idle_source: Box<dyn IdleSource> = if opt.xinput_idle {
+        let classes: Vec<xidlehook_core::sources::InputClass> = if opt.xinput_classes.is_empty() {
+            xidlehook_core::sources::InputClass::ALL.to_vec()
+        } else {
+            opt.xinput_classes
+                .iter()
+                .map(|class| match class {
+                    OptInputClass::Keys => xidlehook_core::sources::InputClass::Keys,
+                    OptInputClass::Buttons => xidlehook_core::sources::InputClass::Buttons,
+                    OptInputClass::Motion => xidlehook_core::sources::InputClass::Motion,
+                    OptInputClass::Touch => xidlehook_core::sources::InputClass::Touch,
+                })
+                .collect()
+        };
+        match xidlehook_core::sources::XInput2Idle::with_classes(&classes) {
+            Ok(xinput) => Box::new(
+                fallback()
+                    .with("xinput", xinput)
+                    .with("xcb", Rc::clone(&xcb)),
+            ),
+            Err(err) => {
+                warn!("Using the X screensaver for the idle time instead: {}", err);
+                idle_source
+            },
+        }
+    } else {
+        idle_source
+    };
+    let idle_source: Box<dyn IdleSource> = Box::new(CachedIdleSource::with_ttl(
+        idle_source,
+        Duration::from_millis(opt.idle_cache),
+    ));
 
     if opt.print {
-        let idle = xcb.get_idle()?;
+        let idle = idle_source.get_idle()?;
         println!("{}", idle.as_millis());
         return Ok(());
     }
 
+    let command_limit = opt.max_concurrent_commands.map(|max| {
+        let policy = if opt.queue_commands {
+            QueueFull::Queue
+        } else {
+            QueueFull::Drop
+        };
+        Rc::new(CommandLimit::new(max, policy))
+    });
+    let spawn_hook = match opt.spawn_with {
+        Some(ref wrapper) => {
+            let wrapper: Vec<String> = wrapper.split_whitespace().map(String::from).collect();
+            if wrapper.is_empty() {
+                eprintln!("error: --spawn-with needs a command");
+                return Ok(());
+            }
+            Some(timers::wrapper_hook(wrapper))
+        },
+        None => None,
+    };
+    let worker = if opt.shell_worker {
+        Some(Rc::new(ShellWorker::new()))
+    } else {
+        None
+    };
+
     let mut timers = Vec::new();
     let mut iter = opt.timer.iter().peekable();
     while iter.peek().is_some() {
@@ -105,56 +788,595 @@ async fn main() -> xidlehook_core::Result<()> {
                 return Ok(());
             },
         };
-        timers.push(CmdTimer::from_shell(
+        let mut timer = CmdTimer::from_shell(
             Duration::from_secs(duration),
             iter.next().unwrap().into(),
             iter.next().unwrap().into(),
             String::new(),
-        ));
+        );
+        timer.set_limit(command_limit.clone());
+        timer.set_spawn_hook(spawn_hook.clone());
+        timer.set_worker(worker.clone());
+        timer.set_dead_zone(Duration::from_millis(opt.dead_zone));
+        timers.push(timer);
     }
 
-    let mut modules: Vec<Box<dyn Module>> = Vec::new();
+    if let Some(index) = opt.relock_on_crash {
+        match timers.get_mut(index) {
+            Some(timer) => timer.set_relock_on_crash(true),
+            None => {
+                eprintln!("error: there's no timer with index {}", index);
+                return Ok(());
+            },
+        }
+    }
+    for &index in &opt.kill_on_resume {
+        match timers.get_mut(index) {
+            Some(timer) => timer.set_kill_on_resume(true),
+            None => {
+                eprintln!("error: there's no timer with index {}", index);
+                return Ok(());
+            },
+        }
+    }
+    for &index in &opt.detached {
+        match timers.get_mut(index) {
+            Some(timer) => timer.set_detached(true),
+            None => {
+                eprintln!("error: there's no timer with index {}", index);
+                return Ok(());
+            },
+        }
+    }
+    for pair in opt.recurring.chunks(2) {
+        // clap-rs will ensure there are always a multiple of 2 arguments
+        let timer = usize::try_from(pair[0])
+            .ok()
+            .and_then(|index| timers.get_mut(index));
+        match timer {
+            Some(timer) => {
+                if let Err(err) = timer.set_recurring(Some(Duration::from_secs(pair[1]))) {
+                    eprintln!("error: {}", err);
+                    return Ok(());
+                }
+            },
+            None => {
+                eprintln!("error: there's no timer with index {}", pair[0]);
+                return Ok(());
+            },
+        }
+    }
+    for pair in opt.min_idle.chunks(2) {
+        // clap-rs will ensure there are always a multiple of 2 arguments
+        let timer = usize::try_from(pair[0])
+            .ok()
+            .and_then(|index| timers.get_mut(index));
+        match timer {
+            Some(timer) => timer.set_min_idle(Some(Duration::from_secs(pair[1]))),
+            None => {
+                eprintln!("error: there's no timer with index {}", pair[0]);
+                return Ok(());
+            },
+        }
+    }
+    for pair in opt.max_idle.chunks(2) {
+        // clap-rs will ensure there are always a multiple of 2 arguments
+        let timer = usize::try_from(pair[0])
+            .ok()
+            .and_then(|index| timers.get_mut(index));
+        match timer {
+            Some(timer) => timer.set_max_idle(Some(Duration::from_secs(pair[1]))),
+            None => {
+                eprintln!("error: there's no timer with index {}", pair[0]);
+                return Ok(());
+            },
+        }
+    }
+    for &index in &opt.fire_once {
+        match timers.get_mut(index) {
+            Some(timer) if timer.recurring().is_some() => {
+                eprintln!("error: timer {} can't be both once and recurring", index);
+                return Ok(());
+            },
+            Some(timer) => timer.set_once(true),
+            None => {
+                eprintln!("error: there's no timer with index {}", index);
+                return Ok(());
+            },
+        }
+    }
+    for warning in opt.warning.chunks(3) {
+        // clap-rs will ensure there are always a multiple of 3 arguments
+        let (index, lead) = match (warning[0].parse::<usize>(), warning[1].parse::<u64>()) {
+            (Ok(index), Ok(lead)) => (index, Duration::from_secs(lead)),
+            _ => {
+                eprintln!("error: failed to parse warning index and seconds as numbers");
+                return Ok(());
+            },
+        };
+        match timers.get_mut(index) {
+            Some(timer) if lead > Duration::default() && lead < timer.get_time() => {
+                timer.set_warning(lead, warning[2].clone());
+            },
+            Some(timer) => {
+                eprintln!(
+                    "error: the warning of timer {} must be between 1 and {} seconds before it",
+                    index,
+                    timer.get_time().as_secs().saturating_sub(1)
+                );
+                return Ok(());
+            },
+            None => {
+                eprintln!("error: there's no timer with index {}", index);
+                return Ok(());
+            },
+        }
+    }
+
+    if let Some(index) = opt
+        .time_window_timer
+        .iter()
+        .chain(&opt.screen_off_timer)
+        .chain(&opt.dim_backlight)
+        .chain(&opt.fullscreen_timer)
+        .find(|&&index| index >= timers.len())
+    {
+        eprintln!("error: there's no timer with index {}", index);
+        return Ok(());
+    }
+
+    let disabled = Toggle::new(Progress::Abort);
+    let presentation = Toggle::new(Progress::Reset);
+
+    let state = match opt.state_file {
+        Some(ref path) => State::load(path)?.unwrap_or_default(),
+        None => State::default(),
+    };
+    disabled.set(state.disabled);
+    presentation.set(state.presentation);
+
+    let mut modules = ModuleList::new()
+        .with("disabled", disabled.clone())
+        .with("presentation", presentation.clone());
 
     if opt.once {
-        modules.push(Box::new(StopAt::completion()));
+        modules.push("once", StopAt::completion());
     }
+    if let Some(ref on_resume) = opt.on_resume {
+        modules.push("on-resume", OnResume::shell(on_resume));
+    }
+    let fullscreen_options = FullscreenOptions {
+        scan_mode: if opt.full_tree {
+            ScanMode::FullTree
+        } else if opt.top_level {
+            ScanMode::TopLevel
+        } else {
+            ScanMode::ActiveOnly
+        },
+        max_windows: opt.max_windows,
+        max_depth: opt.max_depth.or(Some(DEFAULT_MAX_DEPTH)),
+        monitors: if opt.fullscreen_monitor.is_empty() {
+            None
+        } else {
+            Some(opt.fullscreen_monitor.clone())
+        },
+        override_redirect: opt.fullscreen_override_redirect,
+        ignore_desktop_match: opt.ignore_desktop_match,
+        strict_desktop_match: opt.strict_desktop_match,
+        count_iconic: opt.count_iconic,
+        ..FullscreenOptions::default()
+    };
+    #[cfg(feature = "config")]
+    let fullscreen_options = {
+        let mut options = fullscreen_options;
+        if let Some(ref path) = opt.fullscreen_exceptions {
+            load_exceptions(path, &mut options)?;
+        }
+        options
+    };
+    if opt.on_fullscreen_enter.is_some() || opt.on_fullscreen_exit.is_some() {
+        let shell = |script: &str| {
+            let mut cmd = Command::new("/bin/sh");
+            cmd.arg("-c").arg(script);
+            cmd
+        };
+        let mut module = Rc::clone(&xcb)
+            .fullscreen_hook(fullscreen_options.clone())
+            .with_debounce(Duration::from_millis(opt.fullscreen_debounce));
+        if let Some(ref script) = opt.on_fullscreen_enter {
+            module = module.with_on_enter(shell(script));
+        }
+        if let Some(ref script) = opt.on_fullscreen_exit {
+            module = module.with_on_exit(shell(script));
+        }
+        modules.push("fullscreen-hook", module);
+    }
+    #[cfg(feature = "metrics")]
+    let metrics = xidlehook_core::metrics::Metrics::new();
+    let mut fullscreen_watch = None;
+    let mut shared_fullscreen_options = None;
+    let mut fullscreen_timer = None;
     if opt.not_when_fullscreen {
-        modules.push(Box::new(Rc::clone(&xcb).not_when_fullscreen()));
+        if let Some(size) = opt.window_cache_size {
+            xcb.set_window_cache_size(size);
+        }
+        let mut module = Rc::clone(&xcb).not_when_fullscreen_with(fullscreen_options);
+        if opt.watch_fullscreen {
+            let watch = xcb.fullscreen_watch();
+            module = module.with_watch(watch.clone());
+            fullscreen_watch = Some(watch);
+        }
+        if opt.fullscreen_reset {
+            module = module.with_progress(Progress::Reset);
+        }
+        shared_fullscreen_options = Some(module.shared_options());
+        #[cfg(feature = "metrics")]
+        let module = metrics.count_suppressions(module);
+        let module: Box<dyn Module> = if opt.reset_after_fullscreen {
+            Box::new(ResetOnRelease::new(module))
+        } else {
+            Box::new(module)
+        };
+        match opt.fullscreen_timer {
+            Some(index) => fullscreen_timer = Some((index, module)),
+            None => modules.push("not-when-fullscreen", module),
+        }
+    }
+    if opt.not_when_screensaver_disabled {
+        modules.push(
+            "not-when-screensaver-disabled",
+            Rc::clone(&xcb).not_when_screensaver_disabled(),
+        );
+    }
+    if opt.not_when_screen_off {
+        let mut module = Rc::clone(&xcb).not_when_screen_off();
+        if !opt.screen_off_timer.is_empty() {
+            module = module.with_timers(opt.screen_off_timer.clone());
+        }
+        modules.push("not-when-screen-off", module);
+    }
+    if opt.not_when_root_property {
+        let mut module = Rc::clone(&xcb).not_when_root_property();
+        if !opt.inhibit_property.is_empty() {
+            module = module.with_properties(opt.inhibit_property.clone());
+        }
+        modules.push("not-when-root-property", module);
     }
     #[cfg(feature = "pulse")]
     {
         if opt.not_when_audio {
-            modules.push(Box::new(xidlehook_core::modules::NotWhenAudio::new()?))
+            modules.push(
+                "not-when-audio",
+                xidlehook_core::modules::NotWhenAudio::lenient(),
+            )
+        }
+    }
+    #[cfg(feature = "dbus")]
+    {
+        if opt.not_when_inhibited {
+            modules.push(
+                "not-when-inhibited",
+                xidlehook_core::modules::NotWhenInhibited::new()?,
+            )
+        }
+        if opt.not_when_media_playing {
+            modules.push(
+                "not-when-media-playing",
+                xidlehook_core::modules::NotWhenMediaPlaying::new()?
+                    .with_players(opt.media_player.clone()),
+            )
+        }
+    }
+    if !opt.not_when_process.is_empty() {
+        modules.push(
+            "not-when-process",
+            NotWhenProcess::new(opt.not_when_process.clone())
+                .with_substring(opt.process_substring)
+                .with_cmdline(opt.process_cmdline),
+        );
+    }
+    if let Some(ref power) = opt.not_on_power {
+        let abort_on = match power {
+            OptPower::Ac => PowerSource::Ac,
+            OptPower::Battery => PowerSource::Battery,
+        };
+        modules.push("not-on-power", PowerState::new(abort_on));
+    }
+    if !opt.time_window.is_empty() {
+        let mut time_window = TimeWindow::new(opt.time_window.clone());
+        if !opt.time_window_timer.is_empty() {
+            time_window = time_window.with_timers(opt.time_window_timer.clone());
+        }
+        modules.push("time-window", time_window);
+    }
+    if let Some(index) = opt.dim_backlight {
+        let mut backlight = Backlight::new()
+            .with_timer(index)
+            .with_level(opt.backlight_level);
+        if let Some(ref device) = opt.backlight_device {
+            backlight = backlight.with_device(device.as_str());
         }
+        modules.push("dim-backlight", backlight);
+    }
+    if let Some(name) = opt
+        .module_order
+        .iter()
+        .find(|name| !MODULE_NAMES.contains(&name.as_str()))
+    {
+        eprintln!("error: there's no module named {}", name);
+        return Ok(());
     }
+    // Modules that weren't enabled can't be ordered, which is fine
+    let enabled: Vec<&String> = opt
+        .module_order
+        .iter()
+        .filter(|name| modules.names().contains(&name.as_str()))
+        .collect();
+    modules.reorder_by_name(&enabled)?;
+    modules.set_short_circuit(!opt.check_all_modules);
 
-    let xidlehook = Xidlehook::new(timers)
+    let mut xidlehook = Xidlehook::new(timers)
         .register(modules)
-        .with_detect_sleep(opt.detect_sleep);
-    App {
+        .with_detect_sleep(opt.detect_sleep)
+        .with_log_resets(opt.log_resets);
+    xidlehook.set_canceller_gap(opt.canceller_gap.map(Duration::from_millis));
+    xidlehook.set_resume_grace(Duration::from_secs(opt.resume_grace));
+    xidlehook.set_cooldown(Duration::from_millis(opt.cooldown));
+    xidlehook.set_activity_debounce(opt.activity_debounce);
+    if let Some((index, module)) = fullscreen_timer {
+        xidlehook.add_timer_module(index, "not-when-fullscreen", module);
+    }
+    for pair in opt.daily.chunks(2) {
+        // clap-rs will ensure there are always a multiple of 2 arguments
+        let mut timer = CmdTimer::from_shell(
+            Duration::default(),
+            pair[1].clone(),
+            String::new(),
+            String::new(),
+        );
+        timer.set_limit(command_limit.clone());
+        timer.set_spawn_hook(spawn_hook.clone());
+        timer.set_worker(worker.clone());
+        let daily = match DailyTimer::parse(&pair[0], timer) {
+            Ok(daily) => daily,
+            Err(err) => {
+                eprintln!("error: failed to parse time of day {}: {}", pair[0], err);
+                return Ok(());
+            },
+        };
+        xidlehook.add_daily(daily.with_min_idle(Duration::from_secs(opt.daily_min_idle)));
+    }
+    xidlehook.set_poll_interval_min(opt.poll_interval_min.map(Duration::from_millis));
+    xidlehook.set_poll_interval_max(opt.poll_interval_max.map(Duration::from_millis));
+    xidlehook.check_resolution(&*idle_source)?;
+    #[cfg(feature = "metrics")]
+    xidlehook.add_observer(metrics.clone());
+
+    if let Some(ref path) = opt.history {
+        let mut history = History::new(path);
+        if let Some(max_size) = opt.history_max_size {
+            history = history.with_max_size(max_size);
+        }
+        xidlehook.set_history(Some(history));
+    }
+
+    if opt.heartbeat.is_some() || opt.max_poll_time.is_some() || opt.stuck_idle.is_some() {
+        let mut watchdog = Watchdog::new();
+        if let Some(ref path) = opt.heartbeat {
+            watchdog = watchdog.with_heartbeat(path, Duration::from_secs(opt.heartbeat_interval));
+        }
+        if let Some(max) = opt.max_poll_time {
+            let recovery = opt.recovery.as_ref().map(|recovery| {
+                let mut cmd = Command::new("/bin/sh");
+                cmd.arg("-c").arg(recovery);
+                cmd
+            });
+            watchdog = watchdog.with_max_iteration(Duration::from_secs(max), recovery);
+        }
+        if let Some(multiple) = opt.stuck_idle {
+            let command = opt.on_stuck_idle.as_ref().map(|command| {
+                let mut cmd = Command::new("/bin/sh");
+                cmd.arg("-c").arg(command);
+                cmd
+            });
+            watchdog = watchdog.with_stuck_idle(multiple, command);
+        }
+        xidlehook.set_watchdog(Some(watchdog));
+    }
+    let lid = if opt.on_lid_close.is_some() || opt.on_lid_open.is_some() {
+        let shell = |script: &str| {
+            let mut cmd = Command::new("/bin/sh");
+            cmd.arg("-c").arg(script);
+            cmd
+        };
+        let mut lid = LidSwitch::new();
+        if let Some(ref script) = opt.on_lid_close {
+            lid = lid.with_on_close(shell(script));
+        }
+        if let Some(ref script) = opt.on_lid_open {
+            lid = lid.with_on_open(shell(script));
+        }
+        // Learn the state it starts in, so that isn't taken as a change
+        lid.check()?;
+        Some(lid)
+    } else {
+        None
+    };
+    let mut app = App {
         opt,
         xcb,
+        idle_source,
         xidlehook,
-    }
-    .main_loop()
-    .await
+        pending_tests: Vec::new(),
+        command_limit,
+        spawn_hook,
+        worker,
+        disabled,
+        presentation,
+        fullscreen_watch,
+        fullscreen_options: shared_fullscreen_options,
+        lid,
+        #[cfg(feature = "metrics")]
+        metrics,
+        state,
+    };
+    let result = app.main_loop().await;
+    let shutdown = app.xidlehook.shutdown();
+    app.save_state()?;
+    result.and(shutdown)
+}
+
+/// Replace the exceptions in these options with the ones in this file,
+/// see --fullscreen-exceptions
+#[cfg(feature = "config")]
+fn load_exceptions(path: &str, options: &mut FullscreenOptions) -> xidlehook_core::Result<()> {
+    let config = xidlehook_core::config::FullscreenConfig::load(path)?;
+    options.set_exceptions(&config.options());
+    Ok(())
 }
 
 struct App {
     opt: Opt,
     xcb: Rc<Xcb>,
-    xidlehook: Xidlehook<CmdTimer, ((), Vec<Box<dyn Module>>)>,
+    /// Where the idle time comes from, which is `xcb` unless
+    /// --wayland-idle is given
+    idle_source: Box<dyn IdleSource>,
+    xidlehook: Xidlehook<CmdTimer, ((), ModuleList)>,
+    /// Timers being tested, whose canceller should run at the specified instant
+    pending_tests: Vec<(Instant, usize)>,
+    /// The limit shared by all timers' commands, if any
+    command_limit: Option<Rc<CommandLimit>>,
+    /// Launches all timers' commands, if set
+    spawn_hook: Option<SpawnHook>,
+    /// Runs all timers' cancellers, if set
+    worker: Option<Rc<ShellWorker>>,
+    /// Keeps all timers from running, toggled by SIGUSR1
+    disabled: Toggle,
+    /// Treats the user as active, toggled by SIGUSR2
+    presentation: Toggle,
+    /// The fullscreen windows keeping timers from running, if watched
+    fullscreen_watch: Option<FullscreenWatch>,
+    /// The options of --not-when-fullscreen, whose exceptions SIGHUP
+    /// reloads
+    fullscreen_options: Option<Rc<std::cell::RefCell<FullscreenOptions>>>,
+    /// Runs --on-lid-close and --on-lid-open, if given
+    lid: Option<LidSwitch>,
+    /// The counters served by the METRICS socket command
+    #[cfg(feature = "metrics")]
+    metrics: xidlehook_core::metrics::Metrics,
+    /// The state read from `--state-file`, to write back on shutdown
+    state: State,
 }
 impl App {
+    /// Switch all timers off or on. Switching them off also stops the
+    /// chain, running the canceller of the last timer that went off.
+    fn set_paused(&mut self, paused: bool) -> xidlehook_core::Result<()> {
+        self.disabled.set(paused);
+        info!(
+            "Timers are now {}",
+            if paused { "disabled" } else { "enabled" }
+        );
+        if paused {
+            self.xidlehook.abort()?;
+        }
+        Ok(())
+    }
+
+    /// Toggle the state controlled by this signal
+    fn handle_signal(&mut self, signal: Signal) -> xidlehook_core::Result<()> {
+        match signal {
+            Signal::SIGUSR1 => {
+                trace!("SIGUSR1 received");
+                self.set_paused(!self.disabled.is_on())?;
+            },
+            Signal::SIGUSR2 => {
+                let on = self.presentation.toggle();
+                info!(
+                    "SIGUSR2: presentation mode is now {}",
+                    if on { "on" } else { "off" }
+                );
+            },
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Read the exceptions from --fullscreen-exceptions again, keeping
+    /// the old ones if that fails. Returns false if there's nothing to
+    /// reload.
+    fn reload(&mut self) -> bool {
+        #[cfg(feature = "config")]
+        {
+            if let (Some(path), Some(options)) = (
+                self.opt.fullscreen_exceptions.as_ref(),
+                self.fullscreen_options.as_ref(),
+            ) {
+                match load_exceptions(path, &mut options.borrow_mut()) {
+                    Ok(()) => info!("Reloaded the fullscreen exceptions from {}", path),
+                    Err(err) => warn!(
+                        "Can't reload the fullscreen exceptions from {}: {}",
+                        path, err
+                    ),
+                }
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Bring the metrics that aren't counted as they happen up to
+    /// date, except for the idle time, which is read when they're asked
+    /// for
+    #[cfg(feature = "metrics")]
+    fn update_metrics(&self) {
+        self.metrics.set_timers(self.xidlehook.timers().len());
+        self.metrics.set_x_reconnects(self.xcb.reconnects());
+    }
+
+    /// Write the state to `--state-file`, if set
+    fn save_state(&mut self) -> xidlehook_core::Result<()> {
+        let path = match self.opt.state_file {
+            Some(ref path) => path,
+            None => return Ok(()),
+        };
+        self.state.disabled = self.disabled.is_on();
+        self.state.presentation = self.presentation.is_on();
+        self.state.save(path)
+    }
+
+    /// Handle the X events of watched fullscreen windows. Once none of
+    /// them is fullscreen anymore, a chain they kept from running
+    /// starts over from the current idle time.
+    fn handle_x_events(&mut self) -> xidlehook_core::Result<()> {
+        let watch = match self.fullscreen_watch {
+            Some(ref watch) => watch,
+            None => return Ok(()),
+        };
+        if self.xcb.handle_fullscreen_events(watch)? && self.xidlehook.is_aborted() {
+            info!("No window is fullscreen anymore, restarting the timers");
+            self.xidlehook.reset(self.idle_source.get_idle()?)?;
+        }
+        Ok(())
+    }
+
     async fn main_loop(&mut self) -> xidlehook_core::Result<()> {
         let (socket_tx, socket_rx) = mpsc::channel(4);
+        #[cfg(feature = "http")]
+        {
+            if let Some(address) = self.opt.http.clone() {
+                let socket_tx = socket_tx.clone();
+                tokio::spawn(async move {
+                    let Err(err) = socket::http::main_loop(&address, socket_tx).await;
+                    warn!("HTTP handling errored: {}", err);
+                });
+            }
+        }
         let _scope = if let Some(address) = self.opt.socket.clone() {
             {
                 let address = address.clone();
                 tokio::spawn(async move {
-                    if let Err(err) = socket::main_loop(&address, socket_tx).await {
-                        warn!("Socket handling errored: {}", err);
-                    }
+                    let Err(err) = socket::main_loop(&address, socket_tx).await;
+                    warn!("Socket handling errored: {}", err);
                 });
             }
             Some(Defer(move || {
@@ -167,10 +1389,37 @@ impl App {
 
         let mut socket_rx = Some(socket_rx);
 
+        let mut x_events = None;
+        let mut x_reconnects = None;
+        let mut idle_reconnects = self.xcb.reconnects();
+
         let mut sigint = signal(SignalKind::interrupt())?;
-        let mut sigchld = signal(SignalKind::child())?;
+        let mut sigterm = signal(SignalKind::terminate())?;
+        let mut sigusr1 = signal(SignalKind::user_defined1())?;
+        let mut sigusr2 = signal(SignalKind::user_defined2())?;
+        let mut sighup = signal(SignalKind::hangup())?;
+
+        let mut lid_checked = Instant::now();
 
         loop {
+            // An idle time from before reconnecting is stale
+            if idle_reconnects != self.xcb.reconnects() {
+                self.idle_source.refresh();
+                idle_reconnects = self.xcb.reconnects();
+            }
+            #[cfg(feature = "metrics")]
+            self.update_metrics();
+
+            // The connection, and thus its file descriptor, changes when
+            // reconnecting to the X server
+            if self.fullscreen_watch.is_some()
+                && x_reconnects != Some(self.xcb.reconnects())
+                && self.xcb.is_connected()
+            {
+                x_events = Some(AsyncFd::new(self.xcb.as_raw_fd())?);
+                x_reconnects = Some(self.xcb.reconnects());
+            }
+
             let socket_msg = async {
                 if let Some(ref mut rx) = socket_rx {
                     rx.recv().await
@@ -180,6 +1429,31 @@ impl App {
                 }
             };
 
+            let next_test = self.pending_tests.iter().map(|&(at, _)| at).min();
+            let test_msg = async move {
+                if let Some(at) = next_test {
+                    time::sleep_until(at.into()).await;
+                } else {
+                    std::future::pending::<()>().await;
+                }
+            };
+
+            let next_lid_check = self.lid.as_ref().map(|_| lid_checked + LidSwitch::INTERVAL);
+            let lid_tick = async move {
+                if let Some(at) = next_lid_check {
+                    time::sleep_until(at.into()).await;
+                } else {
+                    std::future::pending::<()>().await;
+                }
+            };
+
+            let x_ready = async {
+                match x_events {
+                    Some(ref fd) => fd.readable().await,
+                    None => std::future::pending().await,
+                }
+            };
+
             tokio::select! {
                 data = socket_msg => {
                     if let Some((msg, reply)) = data {
@@ -193,7 +1467,39 @@ impl App {
                         socket_rx = None;
                     }
                 },
-                res = self.xidlehook.main_async(&self.xcb) => {
+                _ = test_msg => {
+                    let now = Instant::now();
+                    let (due, pending) = self.pending_tests.drain(..).partition(|&(at, _)| at <= now);
+                    self.pending_tests = pending;
+                    for (_, id) in due {
+                        if id >= self.xidlehook.timers().len() {
+                            continue;
+                        }
+                        if let Err(err) = self.xidlehook.test_abortion(id) {
+                            warn!("Testing canceller of timer {} failed: {}", id, err);
+                        }
+                    }
+                },
+                _ = lid_tick => {
+                    lid_checked = Instant::now();
+                    if let Some(ref mut lid) = self.lid {
+                        if let Err(err) = lid.check() {
+                            warn!("Running the lid command failed: {}", err);
+                        }
+                    }
+                },
+                guard = x_ready => {
+                    {
+                        let mut guard = guard?;
+                        self.handle_x_events()?;
+                        guard.clear_ready();
+                    }
+                    // A broken connection is always readable
+                    if !self.xcb.is_connected() {
+                        x_events = None;
+                    }
+                },
+                res = self.xidlehook.main_async(&*self.idle_source) => {
                     res?;
                     break;
                 },
@@ -201,9 +1507,18 @@ impl App {
                     trace!("SIGINT received");
                     break;
                 },
-                _ = sigchld.recv() => {
-                    trace!("Waiting for child process");
-                    let _ = wait::waitpid(None, Some(wait::WaitPidFlag::WNOHANG));
+                _ = sigterm.recv() => {
+                    trace!("SIGTERM received");
+                    break;
+                },
+                _ = sigusr1.recv() => self.handle_signal(Signal::SIGUSR1)?,
+                _ = sigusr2.recv() => self.handle_signal(Signal::SIGUSR2)?,
+                _ = sighup.recv() => {
+                    trace!("SIGHUP received");
+                    // Without anything to reload, it's a hangup as usual
+                    if !self.reload() {
+                        break;
+                    }
                 },
             }
         }
@@ -1,29 +1,63 @@
 use super::models::*;
 use crate::{timers::CmdTimer, App};
 
-use std::convert::TryInto;
+use std::{
+    convert::{TryFrom, TryInto},
+    time::{Duration, Instant},
+};
 
-use xidlehook_core::Progress;
+use xidlehook_core::{
+    modules::xcb::FullscreenOptions,
+    status::{Inhibition, Overview, Status, TimerStatus},
+    Progress, TimerInfo,
+};
 
 impl App {
     pub fn handle_socket(&mut self, msg: Message) -> xidlehook_core::Result<Option<Reply>> {
         match msg {
             Message::Add(add) => {
-                let timers = self.xidlehook.timers_mut()?;
-
-                let index = add.index.map_or_else(|| timers.len(), usize::from);
-                if index > timers.len() {
+                if add.time == Duration::default() {
+                    return Ok(Some(Reply::Error(String::from("time must not be zero"))));
+                }
+                if add.recurring == Some(Duration::default()) {
+                    return Ok(Some(Reply::Error(String::from(
+                        "recurring must not be zero",
+                    ))));
+                }
+                let len = self.xidlehook.timers().len();
+                let index = add.index.map_or(len, usize::from);
+                if index > len {
                     return Ok(Some(Reply::Error(String::from("index > length"))));
                 }
-                timers.insert(
-                    index,
-                    CmdTimer::from_parts(add.time, add.activation, add.abortion, add.deactivation),
-                );
+                let id = match TimerId::try_from(index) {
+                    Ok(id) => id,
+                    Err(_) => return Ok(Some(Reply::Error(String::from("too many timers")))),
+                };
 
-                Ok(Some(Reply::Empty))
+                let mut timer =
+                    CmdTimer::from_parts(add.time, add.activation, add.abortion, add.deactivation);
+                timer.set_limit(self.command_limit.clone());
+                timer.set_spawn_hook(self.spawn_hook.clone());
+                timer.set_worker(self.worker.clone());
+                timer.set_recurring(add.recurring)?;
+                self.xidlehook.insert_timer(index, timer)?;
+
+                // Keep pending tests pointing at the timers they were started for
+                for (_, test) in &mut self.pending_tests {
+                    if *test >= index {
+                        *test += 1;
+                    }
+                }
+
+                Ok(Some(Reply::Added(Added { index: id })))
             },
             Message::Control(control) => {
                 let len = self.xidlehook.timers().len();
+                if let Filter::One(id) = control.timer {
+                    if usize::from(id) >= len {
+                        return Ok(Some(Reply::Error(String::from("index >= length"))));
+                    }
+                }
 
                 // Relies on control.timer being sorted, which it is, because it's a btreeset
                 let mut removed = 0;
@@ -50,16 +84,24 @@ impl App {
                             timers[id].set_disabled(false);
                         },
                         Action::Trigger => {
-                            if self.xidlehook.trigger(id, self.xcb.get_idle()?, true)?
+                            if self
+                                .xidlehook
+                                .trigger(id, self.idle_source.get_idle()?, true)?
                                 == Progress::Stop
                             {
                                 return Ok(None);
                             }
                         },
                         Action::Delete => {
-                            let timers = self.xidlehook.timers_mut()?;
                             // TODO: Probably want to use `retain` to optimize this...
-                            timers.remove(id);
+                            self.xidlehook.remove_timer(id)?;
+
+                            self.pending_tests.retain(|&(_, test)| test != id);
+                            for (_, test) in &mut self.pending_tests {
+                                if *test > id {
+                                    *test -= 1;
+                                }
+                            }
 
                             // Working with this large indices pointing to an allocated object... I
                             // think we're fine
@@ -71,15 +113,24 @@ impl App {
                 Ok(Some(Reply::Empty))
             },
             Message::ResetIdle => {
-                self.xidlehook.reset(self.xcb.get_idle()?)?;
+                self.xidlehook.reset(self.idle_source.get_idle()?)?;
                 Ok(Some(Reply::Empty))
             },
             Message::StopTimers => {
                 self.xidlehook.abort()?;
                 Ok(Some(Reply::Empty))
             },
+            Message::Pause => {
+                self.set_paused(true)?;
+                Ok(Some(Reply::Empty))
+            },
+            Message::Resume => {
+                self.set_paused(false)?;
+                Ok(Some(Reply::Empty))
+            },
             Message::Query(query) => {
                 let timers = self.xidlehook.timers();
+                let next_index = self.xidlehook.next_index();
                 let mut output = Vec::new();
 
                 for id in query.timer.iter(
@@ -99,11 +150,117 @@ impl App {
                         abortion: timer.abortion().to_vec(),
                         deactivation: timer.deactivation().to_vec(),
                         disabled: timer.get_disabled(),
+                        fired: usize::from(id) < next_index && !timer.get_disabled(),
                     });
                 }
 
                 Ok(Some(Reply::QueryResult(output)))
             },
+            Message::Test(test) => {
+                let id = usize::from(test.timer);
+                if id >= self.xidlehook.timers().len() {
+                    return Ok(Some(Reply::Error(String::from("index >= length"))));
+                }
+                if let Err(err) = self.xidlehook.test_activation(id) {
+                    return Ok(Some(Reply::Error(err.to_string())));
+                }
+                self.pending_tests.push((Instant::now() + test.delay, id));
+
+                Ok(Some(Reply::Empty))
+            },
+            Message::Status => {
+                let rounding = Duration::from_millis(self.opt.status_rounding);
+                let status = Status::new(self.idle_source.get_idle()?, rounding)
+                    .with_uptime(self.xidlehook.started_at(), self.xidlehook.uptime());
+                let modules = &self.xidlehook.module().1;
+                Ok(Some(Reply::Status(Status {
+                    window_cache_entries: self.xcb.window_cache_len(),
+                    module_order: modules.names().into_iter().map(String::from).collect(),
+                    paused: self.disabled.is_on(),
+                    ..status
+                })))
+            },
+            Message::LogResets(log_resets) => {
+                self.xidlehook.set_log_resets(log_resets.enabled);
+                Ok(Some(Reply::Empty))
+            },
+            Message::Fullscreen => {
+                let hits = self.xcb.fullscreen_windows(&FullscreenOptions::default())?;
+                Ok(Some(Reply::Fullscreen(hits)))
+            },
+            Message::FullscreenWindow(FullscreenWindow { window }) => {
+                let fullscreen = match self.fullscreen_options {
+                    Some(ref options) => self
+                        .xcb
+                        .is_window_fullscreen_with(window, &options.borrow())?,
+                    None => self.xcb.is_window_fullscreen(window)?,
+                };
+                Ok(Some(Reply::WindowFullscreen(WindowFullscreen {
+                    window,
+                    fullscreen,
+                })))
+            },
+            Message::Simulate(simulate) => {
+                let idle = Duration::from_secs(simulate.idle_seconds);
+                let simulation = self.xidlehook.simulate(idle)?;
+                Ok(Some(Reply::Simulation(simulation)))
+            },
+            Message::Inhibition => Ok(Some(Reply::Inhibition(self.inhibition()?))),
+            Message::Modules => {
+                let modules = self.xidlehook.module().1.describe_all();
+                Ok(Some(Reply::Modules(modules)))
+            },
+            Message::Overview => {
+                let idle = self.idle_source.get_idle()?;
+                let fullscreen = !self
+                    .xcb
+                    .fullscreen_windows(&FullscreenOptions::default())?
+                    .is_empty();
+                let next_index = self.xidlehook.next_index();
+                let timers = self
+                    .xidlehook
+                    .timers()
+                    .iter()
+                    .enumerate()
+                    .map(|(index, timer)| TimerStatus {
+                        index,
+                        duration_ms: timer.get_time().as_millis().try_into().unwrap_or(u64::MAX),
+                        fired: index < next_index && !timer.get_disabled(),
+                        command: timer.command(),
+                    })
+                    .collect();
+                Ok(Some(Reply::Overview(Overview {
+                    idle_ms: idle.as_millis().try_into().unwrap_or(u64::MAX),
+                    fullscreen,
+                    inhibition: self.inhibition()?,
+                    timers,
+                })))
+            },
+            Message::Metrics => {
+                #[cfg(feature = "metrics")]
+                let reply = {
+                    self.update_metrics();
+                    self.metrics.set_idle(self.idle_source.get_idle()?);
+                    Reply::Metrics(Metrics {
+                        metrics: self.metrics.render(),
+                    })
+                };
+                #[cfg(not(feature = "metrics"))]
+                let reply = Reply::Error(String::from(
+                    "xidlehook was built without the metrics feature",
+                ));
+                Ok(Some(reply))
+            },
         }
     }
+
+    /// Which modules would keep the first timer from running right now
+    fn inhibition(&mut self) -> xidlehook_core::Result<Inhibition> {
+        let timer = TimerInfo {
+            index: 0,
+            length: self.xidlehook.timers().len(),
+        };
+        let verdicts = self.xidlehook.module_mut().1.verdicts(timer)?;
+        Ok(Inhibition::new(verdicts))
+    }
 }
@@ -0,0 +1,155 @@
+//! The socket's messages over a tiny HTTP server, for when a Unix
+//! socket is out of reach. There's no authentication whatsoever:
+//! anyone who can connect can add timers running any command, so bind
+//! it to localhost.
+//!
+//! The routes are `GET /overview`, `/status` and `/timers`, `POST
+//! /timers` with an `Add` as the body, `DELETE /timers/<index>`, and
+//! `POST /pause` and `/resume`. `POST /` takes any message, like the
+//! socket. Replies are JSON.
+
+use super::models::*;
+
+use std::convert::Infallible;
+
+use log::{trace, warn};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, oneshot},
+};
+
+/// Requests with a bigger body are refused
+const MAX_BODY: usize = 64 * 1024;
+
+/// Find the message for this route
+pub fn route(method: &str, path: &str, body: &[u8]) -> Result<Message, (u16, String)> {
+    let invalid = |err: serde_json::Error| (400, err.to_string());
+    let segments: Vec<&str> = path
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    let message = match (method, segments.as_slice()) {
+        ("POST", []) => serde_json::from_slice(body).map_err(invalid)?,
+        ("GET", ["overview"]) => Message::Overview,
+        ("GET", ["status"]) => Message::Status,
+        ("GET", ["timers"]) => Message::Query(Query { timer: Filter::All }),
+        ("POST", ["timers"]) => Message::Add(serde_json::from_slice(body).map_err(invalid)?),
+        ("DELETE", ["timers", index]) => Message::Control(Control {
+            timer: Filter::One(
+                index
+                    .parse()
+                    .map_err(|err| (400, format!("invalid index {:?}: {}", index, err)))?,
+            ),
+            action: Action::Delete,
+        }),
+        ("POST", ["pause"]) => Message::Pause,
+        ("POST", ["resume"]) => Message::Resume,
+        (_, [] | ["overview" | "status" | "timers" | "pause" | "resume"] | ["timers", _]) => {
+            return Err((405, format!("{} isn't allowed on {}", method, path)))
+        },
+        _ => return Err((404, format!("there's nothing at {}", path))),
+    };
+    Ok(message)
+}
+
+pub async fn main_loop(
+    address: &str,
+    socket_tx: mpsc::Sender<(Message, oneshot::Sender<Reply>)>,
+) -> xidlehook_core::Result<Infallible> {
+    let listener = TcpListener::bind(address).await?;
+    trace!("Bound HTTP listener on address {:?}", address);
+
+    loop {
+        let (stream, addr) = listener.accept().await?;
+        trace!("HTTP connection from {:?}", addr);
+
+        let socket_tx = socket_tx.clone();
+        tokio::spawn(async move {
+            if let Err(err) = serve(stream, socket_tx).await {
+                warn!("couldn't answer HTTP request: {}", err);
+            }
+        });
+    }
+}
+
+/// Answer a single request, and close the connection
+async fn serve(
+    mut stream: TcpStream,
+    socket_tx: mpsc::Sender<(Message, oneshot::Sender<Reply>)>,
+) -> xidlehook_core::Result<()> {
+    let (reader, mut writer) = stream.split();
+    let mut reader = BufReader::new(reader);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().map_err(|_| "invalid Content-Length")?;
+            }
+        }
+    }
+
+    let (status, reply) = if length > MAX_BODY {
+        (413, Reply::Error(String::from("the body is too big")))
+    } else {
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).await?;
+        match route(&method, &path, &body) {
+            Ok(msg) => {
+                let (reply_tx, reply_rx) = oneshot::channel();
+                socket_tx
+                    .send((msg, reply_tx))
+                    .await
+                    .expect("receiver closed too early");
+                match reply_rx.await {
+                    Ok(reply @ Reply::Error(_)) => (400, reply),
+                    Ok(reply) => (200, reply),
+                    Err(_) => (503, Reply::Error(String::from("xidlehook is exiting"))),
+                }
+            },
+            Err((status, err)) => {
+                warn!("couldn't interpret HTTP request: {}", err);
+                (status, Reply::Error(err))
+            },
+        }
+    };
+
+    let body = serde_json::to_vec(&reply)?;
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        reason(status),
+        body.len().saturating_add(1),
+    );
+    writer.write_all(head.as_bytes()).await?;
+    writer.write_all(&body).await?;
+    writer.write_all(b"\n").await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// The reason phrase of the status codes used here
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        _ => "Service Unavailable",
+    }
+}
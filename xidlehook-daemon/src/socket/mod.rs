@@ -8,6 +8,8 @@ use tokio::{
 };
 
 pub mod handler;
+#[cfg(feature = "http")]
+pub mod http;
 pub mod models;
 
 pub use self::models::*;
@@ -32,31 +34,43 @@ pub async fn main_loop(
             let mut writer = BufWriter::new(writer);
             let mut lines = reader.lines();
             while let Some(msg) = lines.next_line().await.ok().and_then(|inner| inner) {
-                let res = serde_json::from_str(&msg).map_err(|err| err.to_string());
+                // Lines are either JSON, or one of the plain text commands
+                let res = if msg.trim_start().starts_with('{') {
+                    serde_json::from_str(&msg)
+                        .map(|msg| (msg, Format::Json))
+                        .map_err(|err| err.to_string())
+                } else {
+                    Message::from_command(&msg)
+                };
+
+                let (reply, format) = match res {
+                    Ok((msg, format)) => {
+                        let (reply_tx, reply_rx) = oneshot::channel();
+                        socket_tx
+                            .send((msg, reply_tx))
+                            .await
+                            .expect("receiver closed too early");
 
-                let msg: Message = match res {
-                    Ok(json) => json,
+                        match reply_rx.await {
+                            Ok(reply) => (reply, format),
+                            Err(_) => break,
+                        }
+                    },
                     Err(err) => {
                         warn!("couldn't interpret message: {}", err);
-                        continue;
+                        (Reply::Error(err), Format::Json)
                     },
                 };
 
-                let (reply_tx, reply_rx) = oneshot::channel();
-                socket_tx
-                    .send((msg, reply_tx))
-                    .await
-                    .expect("receiver closed too early");
-
-                let reply = match reply_rx.await {
-                    Ok(reply) => reply,
-                    Err(_) => break,
-                };
-
                 let res = async {
-                    let msg = serde_json::to_vec(&reply)?;
-                    writer.write_all(&msg).await?;
-                    writer.write_all(&[b'\n']).await?;
+                    match reply.to_text().filter(|_| format == Format::Text) {
+                        Some(text) => writer.write_all(text.as_bytes()).await?,
+                        None => {
+                            let msg = serde_json::to_vec(&reply)?;
+                            writer.write_all(&msg).await?;
+                            writer.write_all(b"\n").await?;
+                        },
+                    }
                     writer.flush().await?;
                     Ok::<(), std::io::Error>(())
                 };
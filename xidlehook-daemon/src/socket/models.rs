@@ -1,21 +1,22 @@
 use std::{collections::BTreeSet, time::Duration};
 
 use serde::{Deserialize, Serialize};
+use xidlehook_core::{
+    modules::{xcb::FullscreenHit, ModuleDescription},
+    simulation::Simulation,
+    status::{Inhibition, Overview, Status},
+};
 
 pub type TimerId = u16;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum Filter {
+    #[default]
     All,
     Selected(BTreeSet<TimerId>),
     One(TimerId),
 }
-impl Default for Filter {
-    fn default() -> Self {
-        Self::All
-    }
-}
 impl Filter {
     pub fn iter(&'_ self, len: TimerId) -> Box<dyn Iterator<Item = TimerId> + '_> {
         match *self {
@@ -42,6 +43,9 @@ pub struct Add {
     pub activation: Vec<String>,
     pub abortion: Vec<String>,
     pub deactivation: Vec<String>,
+    /// Run the activation again this often while the user stays idle
+    #[serde(default)]
+    pub recurring: Option<Duration>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -57,6 +61,42 @@ pub struct Query {
     pub timer: Filter,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Test {
+    pub timer: TimerId,
+    /// How long to wait after the activation before running the canceller
+    pub delay: Duration,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LogResets {
+    pub enabled: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FullscreenWindow {
+    /// The id of the window, as shown by `xwininfo` or `xdotool`
+    pub window: u32,
+}
+
+/// Parse a window id, in hexadecimal with a leading "0x" like
+/// `xwininfo` shows them, or in decimal like `xdotool` does
+pub fn parse_window(window: &str) -> Result<u32, String> {
+    let parsed = match window
+        .strip_prefix("0x")
+        .or_else(|| window.strip_prefix("0X"))
+    {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => window.parse(),
+    };
+    parsed.map_err(|err| format!("invalid window id {:?}: {}", window, err))
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Simulate {
+    pub idle_seconds: u64,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(tag = "type", rename_all = "camelCase")]
 pub enum Message {
@@ -64,7 +104,117 @@ pub enum Message {
     Control(Control),
     ResetIdle,
     StopTimers,
+    Pause,
+    Resume,
     Query(Query),
+    Test(Test),
+    Status,
+    Fullscreen,
+    FullscreenWindow(FullscreenWindow),
+    LogResets(LogResets),
+    Simulate(Simulate),
+    Inhibition,
+    Modules,
+    Overview,
+    Metrics,
+}
+impl Message {
+    /// Parse one of the plain text commands, which are easier to type
+    /// into something like `socat` than JSON: `QUERY` for an
+    /// `Overview`, `TIMERS` to query all timers, and `STATUS`,
+    /// `FULLSCREEN`, `INHIBITION` and `MODULES` like their JSON
+    /// counterparts, `FULLSCREEN <window id>` to ask if one window is
+    /// fullscreen, `PAUSE` and `RESUME` to switch timers off and on,
+    /// and `METRICS` for the Prometheus text format. A trailing
+    /// `--json` asks for the reply as JSON even if it has a plain text
+    /// form.
+    ///
+    /// Timers can be changed with `ADD <seconds> <command>`, where the
+    /// rest of the line is passed through "/bin/sh -c", and `REMOVE`,
+    /// `ENABLE` and `DISABLE` followed by the index of a timer.
+    pub fn from_command(command: &str) -> Result<(Self, Format), String> {
+        let command = command.trim();
+        let (name, args) = command
+            .split_once(char::is_whitespace)
+            .unwrap_or((command, ""));
+        let name = name.to_ascii_uppercase();
+
+        if name == "ADD" {
+            let (seconds, shell) = args
+                .trim_start()
+                .split_once(char::is_whitespace)
+                .ok_or("usage: ADD <seconds> <command>")?;
+            let seconds: u64 = seconds
+                .parse()
+                .map_err(|err| format!("invalid duration {:?}: {}", seconds, err))?;
+            let add = Add {
+                index: None,
+                time: Duration::from_secs(seconds),
+                activation: vec!["/bin/sh".into(), "-c".into(), shell.trim().into()],
+                abortion: Vec::new(),
+                deactivation: Vec::new(),
+                recurring: None,
+            };
+            return Ok((Self::Add(add), Format::Text));
+        }
+
+        let mut words = args.split_whitespace();
+        let window = match words.clone().next() {
+            Some(window) if name == "FULLSCREEN" && window != "--json" => {
+                words.next();
+                Some(parse_window(window)?)
+            },
+            _ => None,
+        };
+        let mut control = |action| -> Result<Self, String> {
+            let index = words
+                .next()
+                .ok_or_else(|| format!("usage: {} <index>", name))?;
+            let index = index
+                .parse()
+                .map_err(|err| format!("invalid index {:?}: {}", index, err))?;
+            Ok(Self::Control(Control {
+                timer: Filter::One(index),
+                action,
+            }))
+        };
+        let message = match name.as_str() {
+            "QUERY" => Self::Overview,
+            "TIMERS" => Self::Query(Query { timer: Filter::All }),
+            "STATUS" => Self::Status,
+            "FULLSCREEN" => match window {
+                Some(window) => Self::FullscreenWindow(FullscreenWindow { window }),
+                None => Self::Fullscreen,
+            },
+            "INHIBITION" => Self::Inhibition,
+            "MODULES" => Self::Modules,
+            "METRICS" => Self::Metrics,
+            "PAUSE" => Self::Pause,
+            "RESUME" => Self::Resume,
+            "REMOVE" => control(Action::Delete)?,
+            "ENABLE" => control(Action::Enable)?,
+            "DISABLE" => control(Action::Disable)?,
+            _ => return Err(format!("unknown command {:?}", command)),
+        };
+        let format = match words.next() {
+            None => Format::Text,
+            Some("--json") => Format::Json,
+            Some(arg) => return Err(format!("unexpected argument {:?}", arg)),
+        };
+        if let Some(arg) = words.next() {
+            return Err(format!("unexpected argument {:?}", arg));
+        }
+        Ok((message, format))
+    }
+}
+
+/// How a reply is written to the socket
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// A line of JSON
+    Json,
+    /// Plain text for humans, for replies that have a plain text form
+    Text,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -75,6 +225,26 @@ pub struct QueryResult {
     pub abortion: Vec<String>,
     pub deactivation: Vec<String>,
     pub disabled: bool,
+    /// Whether the timer went off since the user was last active
+    pub fired: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Added {
+    /// Where the timer was inserted
+    pub index: TimerId,
+}
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WindowFullscreen {
+    pub window: u32,
+    /// Whether the window is fullscreen, by the options of
+    /// --not-when-fullscreen
+    pub fullscreen: bool,
+}
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Metrics {
+    /// The metrics in the Prometheus text format
+    pub metrics: String,
 }
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(untagged)]
@@ -82,4 +252,29 @@ pub enum Reply {
     Empty,
     Error(String),
     QueryResult(Vec<QueryResult>),
+    Status(Status),
+    Fullscreen(Vec<FullscreenHit>),
+    Simulation(Simulation),
+    Inhibition(Inhibition),
+    Modules(Vec<ModuleDescription>),
+    Overview(Overview),
+    Added(Added),
+    WindowFullscreen(WindowFullscreen),
+    Metrics(Metrics),
+}
+impl Reply {
+    /// The plain text form of the reply, if it has one
+    pub fn to_text(&self) -> Option<String> {
+        match self {
+            Self::Overview(overview) => Some(overview.to_string()),
+            Self::Added(added) => Some(format!("{}\n", added.index)),
+            Self::WindowFullscreen(reply) => Some(String::from(if reply.fullscreen {
+                "yes\n"
+            } else {
+                "no\n"
+            })),
+            Self::Metrics(metrics) => Some(metrics.metrics.clone()),
+            _ => None,
+        }
+    }
 }
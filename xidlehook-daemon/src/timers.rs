@@ -1,6 +1,31 @@
-use std::{process::Command, time::Duration};
+use std::{process::Command, rc::Rc, time::Duration};
 
-use xidlehook_core::{timers::CmdTimer as Inner, Result, Timer};
+use xidlehook_core::{
+    timers::{CmdTimer as Inner, CommandLimit, ShellWorker, SpawnHook},
+    Result, Timer,
+};
+
+/// Returns a spawn hook which launches commands through the wrapper,
+/// with the program and arguments of each command appended to it
+pub fn wrapper_hook(wrapper: Vec<String>) -> SpawnHook {
+    SpawnHook::new(move |cmd: &Command| {
+        let mut wrapped = Command::new(&wrapper[0]);
+        wrapped
+            .args(&wrapper[1..])
+            .arg(cmd.get_program())
+            .args(cmd.get_args());
+        for (key, value) in cmd.get_envs() {
+            match value {
+                Some(value) => wrapped.env(key, value),
+                None => wrapped.env_remove(key),
+            };
+        }
+        if let Some(dir) = cmd.get_current_dir() {
+            wrapped.current_dir(dir);
+        }
+        Ok(wrapped.spawn()?)
+    })
+}
 
 pub struct CmdTimer {
     inner: Inner,
@@ -61,6 +86,51 @@ impl CmdTimer {
         self.inner.disabled
     }
 
+    pub fn set_limit(&mut self, limit: Option<Rc<CommandLimit>>) {
+        self.inner.limit = limit;
+    }
+    pub fn set_spawn_hook(&mut self, hook: Option<SpawnHook>) {
+        self.inner.spawn_hook = hook;
+    }
+    pub fn set_dead_zone(&mut self, dead_zone: Duration) {
+        self.inner.dead_zone = dead_zone;
+    }
+    pub fn set_relock_on_crash(&mut self, val: bool) {
+        self.inner.relock_on_crash = val;
+    }
+    pub fn set_kill_on_resume(&mut self, val: bool) {
+        self.inner.kill_on_resume = val;
+    }
+    pub fn set_detached(&mut self, val: bool) {
+        self.inner.detached = val;
+    }
+    pub fn set_recurring(&mut self, interval: Option<Duration>) -> Result<()> {
+        if interval == Some(Duration::default()) {
+            return Err("a timer can't recur every 0 seconds".into());
+        }
+        self.inner.recurring = interval;
+        Ok(())
+    }
+    pub fn set_once(&mut self, val: bool) {
+        self.inner.once = val;
+    }
+    pub fn set_min_idle(&mut self, min: Option<Duration>) {
+        self.inner.min_idle = min;
+    }
+    pub fn set_max_idle(&mut self, max: Option<Duration>) {
+        self.inner.max_idle = max;
+    }
+    /// Run this shell command `lead` before the timer goes off
+    pub fn set_warning(&mut self, lead: Duration, warning: String) {
+        let mut cmd = Command::new("/bin/sh");
+        cmd.arg("-c").arg(warning);
+        self.inner.warning = Some(cmd);
+        self.inner.warning_lead = lead;
+    }
+    pub fn set_worker(&mut self, worker: Option<Rc<ShellWorker>>) {
+        self.inner.worker = worker;
+    }
+
     // There's no reason to not have `set_time` here as well, it just
     // isn't available in the API yet.
 
@@ -71,6 +141,15 @@ impl CmdTimer {
     pub fn activation(&self) -> &[String] {
         self.activation.as_ref().map_or(&[], |v| &**v)
     }
+    /// The activation command as it would be written: the shell
+    /// command for timers made with `from_shell`, otherwise the
+    /// arguments joined by spaces
+    pub fn command(&self) -> String {
+        match self.activation() {
+            [shell, flag, command] if shell == "/bin/sh" && flag == "-c" => command.clone(),
+            args => args.join(" "),
+        }
+    }
     pub fn abortion(&self) -> &[String] {
         self.abortion.as_ref().map_or(&[], |v| &**v)
     }
@@ -113,6 +192,12 @@ impl Timer for CmdTimer {
     fn abort_urgency(&self) -> Option<Duration> {
         self.inner.abort_urgency()
     }
+    fn poll_interval(&self) -> Option<Duration> {
+        self.inner.poll_interval()
+    }
+    fn reap(&mut self) -> Result<()> {
+        self.inner.reap()
+    }
     fn activate(&mut self) -> Result<()> {
         self.inner.activate()
     }
@@ -122,7 +207,46 @@ impl Timer for CmdTimer {
     fn deactivate(&mut self) -> Result<()> {
         self.inner.deactivate()
     }
+    fn reset(&mut self) -> Result<()> {
+        self.inner.reset()
+    }
     fn disabled(&mut self) -> bool {
         self.inner.disabled()
     }
+    fn dead_zone(&self) -> Duration {
+        self.inner.dead_zone()
+    }
+    fn recurring(&self) -> Option<Duration> {
+        self.inner.recurring()
+    }
+    fn once(&self) -> bool {
+        self.inner.once()
+    }
+    fn min_idle(&self) -> Option<Duration> {
+        self.inner.min_idle()
+    }
+    fn max_idle(&self) -> Option<Duration> {
+        self.inner.max_idle()
+    }
+    fn supervise(&mut self) -> Result<Option<Duration>> {
+        self.inner.supervise()
+    }
+    fn warning_lead(&self) -> Option<Duration> {
+        self.inner.warning_lead()
+    }
+    fn warn(&mut self) -> Result<()> {
+        self.inner.warn()
+    }
+    fn cancel_warning(&mut self) -> Result<()> {
+        self.inner.cancel_warning()
+    }
+    fn set_idle_time(&mut self, idle_time: Duration) {
+        self.inner.set_idle_time(idle_time)
+    }
+    fn test_activation(&mut self) -> Result<()> {
+        self.inner.test_activation()
+    }
+    fn test_abortion(&mut self) -> Result<()> {
+        self.inner.test_abortion()
+    }
 }